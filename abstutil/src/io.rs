@@ -88,13 +88,17 @@ pub fn read_json<T: DeserializeOwned>(path: String, timer: &mut Timer) -> T {
     }
 }
 
-fn maybe_write_binary<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
+pub fn maybe_write_binary<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
     if !path.ends_with(".bin") {
-        panic!("write_binary needs {} to end with .bin", path);
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("write_binary needs {} to end with .bin", path),
+        ));
     }
 
-    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())
-        .expect("Creating parent dir failed");
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
 
     let file = BufWriter::new(File::create(path)?);
     bincode::serialize_into(file, obj).map_err(|err| Error::new(ErrorKind::Other, err))
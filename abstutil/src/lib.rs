@@ -14,14 +14,14 @@ pub use crate::collections::{
 pub use crate::io::{
     basename, deserialize_btreemap, deserialize_multimap, deserialize_usize, file_exists,
     find_next_file, find_prev_file, list_all_objects, list_dir, load_all_objects,
-    maybe_read_binary, maybe_read_json, read_binary, read_json, serialize_btreemap,
-    serialize_multimap, serialize_usize, serialized_size_bytes, slurp_file, to_json, write_binary,
-    write_json, FileWithProgress,
+    maybe_read_binary, maybe_read_json, maybe_write_binary, read_binary, read_json,
+    serialize_btreemap, serialize_multimap, serialize_usize, serialized_size_bytes, slurp_file,
+    to_json, write_binary, write_json, FileWithProgress,
 };
 pub use crate::random::{fork_rng, WeightedUsizeChoice};
 pub use crate::time::{
-    elapsed_seconds, prettyprint_usize, start_profiler, stop_profiler, MeasureMemory, Profiler,
-    Timer, TimerSink,
+    elapsed_seconds, prettyprint_cents, prettyprint_usize, start_profiler, stop_profiler,
+    MeasureMemory, Profiler, Timer, TimerSink,
 };
 use std::collections::BTreeSet;
 use std::fmt::Write;
@@ -108,6 +108,12 @@ pub fn path_map(map_name: &str) -> String {
 pub fn path_all_maps() -> String {
     path("system/maps")
 }
+pub fn path_map_summary(map_name: &str) -> String {
+    path(format!("system/map_summaries/{}.bin", map_name))
+}
+pub fn path_map_provenance(map_name: &str) -> String {
+    path(format!("system/map_provenance/{}.json", map_name))
+}
 
 pub fn path_prebaked_results(map_name: &str, scenario_name: &str) -> String {
     path(format!(
@@ -116,6 +122,14 @@ pub fn path_prebaked_results(map_name: &str, scenario_name: &str) -> String {
     ))
 }
 
+// The routes agents took in the prebaked run
+pub fn path_prebaked_routes(map_name: &str, scenario_name: &str) -> String {
+    path(format!(
+        "system/prebaked_results/{}/{}_routes.bin",
+        map_name, scenario_name
+    ))
+}
+
 pub fn path_scenario(map_name: &str, scenario_name: &str) -> String {
     path(format!(
         "system/scenarios/{}/{}.bin",
@@ -126,6 +140,22 @@ pub fn path_all_scenarios(map_name: &str) -> String {
     path(format!("system/scenarios/{}", map_name))
 }
 
+pub fn path_population(map_name: &str) -> String {
+    path(format!("system/population/{}.bin", map_name))
+}
+
+pub fn path_car_ownership(map_name: &str) -> String {
+    path(format!("system/population/{}_car_ownership.json", map_name))
+}
+
+pub fn path_tolls(map_name: &str, name: &str) -> String {
+    path(format!("system/tolls/{}/{}.json", map_name, name))
+}
+
+pub fn path_incidents(map_name: &str, name: &str) -> String {
+    path(format!("system/incidents/{}/{}.json", map_name, name))
+}
+
 pub fn path_synthetic_map(map_name: &str) -> String {
     path(format!("system/synthetic_maps/{}.json", map_name))
 }
@@ -133,18 +163,54 @@ pub fn path_all_synthetic_maps() -> String {
     path("system/synthetic_maps")
 }
 
+pub fn path_neighborhood(map_name: &str, neighborhood: &str) -> String {
+    path(format!(
+        "system/neighborhoods/{}/{}.json",
+        map_name, neighborhood
+    ))
+}
+pub fn path_all_neighborhoods(map_name: &str) -> String {
+    path(format!("system/neighborhoods/{}", map_name))
+}
+
+pub fn path_tour(map_name: &str, name: &str) -> String {
+    path(format!("system/tours/{}/{}.json", map_name, name))
+}
+pub fn path_all_tours(map_name: &str) -> String {
+    path(format!("system/tours/{}", map_name))
+}
+
 // Player data (Players edit this)
 
 pub fn path_camera_state(map_name: &str) -> String {
     path(format!("player/camera_state/{}.json", map_name))
 }
 
+pub fn path_player_settings() -> String {
+    path("player/settings.json")
+}
+pub fn path_maps_last_opened() -> String {
+    path("player/maps_last_opened.json")
+}
+
 pub fn path_edits(map_name: &str, edits_name: &str) -> String {
     path(format!("player/edits/{}/{}.json", map_name, edits_name))
 }
 pub fn path_all_edits(map_name: &str) -> String {
     path(format!("player/edits/{}", map_name))
 }
+// Untitled edits, in case the game crashes before they're saved. Each session has its own file.
+pub fn path_edits_recovery(session: &str) -> String {
+    path(format!("player/edits_recovery/{}.json", session))
+}
+pub fn path_all_edits_recovery() -> String {
+    path("player/edits_recovery")
+}
+
+// Files the importer built, which the updater shouldn't touch
+pub fn path_local_builds() -> String {
+    path("player/local_builds.txt")
+}
 
 pub fn path_save(map_name: &str, edits_name: &str, run_name: &str, time: String) -> String {
     path(format!(
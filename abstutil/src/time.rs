@@ -550,6 +550,11 @@ pub fn prettyprint_usize(x: usize) -> String {
     result
 }
 
+// Like $1,234.50
+pub fn prettyprint_cents(cents: usize) -> String {
+    format!("${}.{:02}", prettyprint_usize(cents / 100), cents % 100)
+}
+
 pub fn prettyprint_time(seconds: f64) -> String {
     format!("{:.4}s", seconds)
 }
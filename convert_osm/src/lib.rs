@@ -1,13 +1,15 @@
 mod clip;
 mod osm_reader;
+mod pbf;
 mod split_ways;
 mod srtm;
 
 use abstutil::Timer;
 use geom::{Distance, FindClosest, PolyLine, Pt2D};
 use kml::ExtraShapes;
-use map_model::raw::{OriginalBuilding, OriginalRoad, RawMap};
-use map_model::{osm, MapConfig};
+use map_model::raw::{OriginalBuilding, OriginalIntersection, OriginalRoad, RawMap};
+use map_model::{osm, IntersectionType, MapConfig, MapProvenance};
+use std::collections::{BTreeMap, BTreeSet};
 
 // Just used for matching hints to different sides of a road.
 const DIRECTED_ROAD_THICKNESS: Distance = Distance::const_meters(2.5);
@@ -22,8 +24,10 @@ pub struct Options {
     pub map_config: MapConfig,
 
     pub onstreet_parking: OnstreetParking,
+    pub sidewalks: Sidewalks,
     pub public_offstreet_parking: PublicOffstreetParking,
     pub private_offstreet_parking: PrivateOffstreetParking,
+    pub traffic_signals: TrafficSignals,
     // If provided, pull elevation data from this SRTM file. The SRTM parser is incorrect, so the
     // results will be nonsense.
     pub elevation: Option<String>,
@@ -34,8 +38,9 @@ pub struct Options {
 pub enum OnstreetParking {
     // If not tagged, there won't be parking.
     JustOSM,
-    // If OSM data is missing, then try to match data from
-    // http://data-seattlecitygis.opendata.arcgis.com/datasets/blockface. This is Seattle specific.
+    // If OSM data is missing, then try to match ExtraShapes along the curb, like
+    // http://data-seattlecitygis.opendata.arcgis.com/datasets/blockface. Each shape either has
+    // Seattle's PARKING_CATEGORY, or parking=yes/no.
     Blockface(String),
     // If OSM data is missing, then infer parking lanes on some percentage of
     // "highway=residential" roads.
@@ -45,12 +50,23 @@ pub enum OnstreetParking {
     },
 }
 
+// What sidewalks do roads get when https://wiki.openstreetmap.org/wiki/Key:sidewalk isn't tagged?
+// Inferred sidewalks are marked with osm::INFERRED_SIDEWALKS either way.
+pub enum Sidewalks {
+    // Both sides of residential and arterial roads, the right side of other one-ways, and none on
+    // motorways, roundabouts, and service roads.
+    Infer,
+    // If not tagged, there won't be sidewalks.
+    JustOSM,
+}
+
 // How many spots are available in public parking garages?
 pub enum PublicOffstreetParking {
     None,
-    // Pull data from
-    // https://data-seattlecitygis.opendata.arcgis.com/datasets/public-garages-or-parking-lots, a
-    // Seattle-specific data source.
+    // Match ExtraShapes inside buildings, like
+    // https://data-seattlecitygis.opendata.arcgis.com/datasets/public-garages-or-parking-lots. The
+    // number of spots comes from DEA_STALLS, capacity, or spots, and the name from
+    // DEA_FACILITY_NAME or name.
     GIS(String),
 }
 
@@ -61,6 +77,16 @@ pub enum PrivateOffstreetParking {
     // TODO Based on the number of residents?
 }
 
+// Where are the traffic signals? Nodes tagged
+// https://wiki.openstreetmap.org/wiki/Tag:highway%3Dtraffic_signals are always used.
+pub enum TrafficSignals {
+    JustOSM,
+    // Also put signals wherever a city's inventory of signal locations says, in the form of
+    // ExtraShapes with one point each. Signals only OSM knows about are kept. Every disagreement
+    // between the two sources is reported, so somebody can fix OSM or the inventory.
+    Inventory(String),
+}
+
 pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
     let (mut map, amenities) = split_ways::split_up_roads(
         osm_reader::extract_osm(
@@ -68,12 +94,15 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
             &opts.clip,
             &opts.city_name,
             &opts.name,
+            &opts.sidewalks,
             timer,
         ),
         timer,
     );
     clip::clip_map(&mut map, timer);
     map.config = opts.map_config;
+    // Kept next to the map instead of inside it, so the map format doesn't change
+    provenance(&opts).save(&opts.name);
 
     // Need to do a first pass of removing cul-de-sacs here, or we wind up with loop PolyLines when
     // doing the parking hint matching.
@@ -114,6 +143,12 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
         }
     }
     apply_private_offstreet_parking(&mut map, opts.private_offstreet_parking);
+    match opts.traffic_signals {
+        TrafficSignals::JustOSM => {}
+        TrafficSignals::Inventory(ref path) => {
+            use_traffic_signal_inventory(&mut map, path.clone(), timer);
+        }
+    }
     if let Some(ref path) = opts.elevation {
         use_elevation(&mut map, path, timer);
     }
@@ -121,13 +156,65 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
     map
 }
 
+fn provenance(opts: &Options) -> MapProvenance {
+    let osm_date = std::fs::metadata(&opts.osm_input)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|dt| dt.as_secs());
+    let importer_version = std::process::Command::new("git")
+        .args(&["describe", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| format!("git {}", String::from_utf8_lossy(&out.stdout).trim()))
+        .unwrap_or_else(|| format!("version {}", env!("CARGO_PKG_VERSION")));
+
+    let mut sources = Vec::new();
+    if opts.clip.is_some() {
+        sources.push("clipping");
+    }
+    match opts.onstreet_parking {
+        OnstreetParking::JustOSM => {}
+        OnstreetParking::Blockface(_) => sources.push("blockface parking"),
+        OnstreetParking::SomeAdditionalWhereNoData { .. } => sources.push("inferred parking"),
+    }
+    if let Sidewalks::Infer = opts.sidewalks {
+        sources.push("inferred sidewalks");
+    }
+    if let PublicOffstreetParking::GIS(_) = opts.public_offstreet_parking {
+        sources.push("public garages");
+    }
+    if let TrafficSignals::Inventory(_) = opts.traffic_signals {
+        sources.push("signal inventory");
+    }
+    if opts.elevation.is_some() {
+        sources.push("elevation");
+    }
+    let config = if sources.is_empty() {
+        format!("{} settings", opts.city_name)
+    } else {
+        format!("{} settings ({})", opts.city_name, sources.join(", "))
+    };
+
+    MapProvenance {
+        osm_source: std::path::Path::new(&opts.osm_input)
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| opts.osm_input.clone()),
+        osm_date,
+        importer_version,
+        config,
+        content_hash: String::new(),
+    }
+}
+
 fn use_parking_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
     timer.start("apply parking hints");
     let shapes: ExtraShapes = abstutil::read_binary(path, timer);
 
     // Match shapes with the nearest road + direction (true for forwards)
-    let mut closest: FindClosest<(OriginalRoad, bool)> =
-        FindClosest::new(&map.gps_bounds.to_bounds());
+    let mut closest: FindClosest<(OriginalRoad, bool)> = FindClosest::new();
     for (id, r) in &map.roads {
         if r.is_light_rail() {
             continue;
@@ -172,9 +259,7 @@ fn use_parking_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
                 continue;
             }
 
-            let category = s.attributes.get("PARKING_CATEGORY");
-            let has_parking = category != Some(&"None".to_string())
-                && category != Some(&"No Parking Allowed".to_string());
+            let has_parking = blockface_has_parking(&s.attributes);
 
             let definitely_no_parking = match tags.get(osm::HIGHWAY) {
                 Some(hwy) => hwy == "motorway" || hwy == "motorway_link",
@@ -219,26 +304,50 @@ fn use_parking_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
     timer.stop("apply parking hints");
 }
 
+// Seattle's blockface data has a PARKING_CATEGORY. Other cities can just say parking=yes or no.
+fn blockface_has_parking(attributes: &BTreeMap<String, String>) -> bool {
+    if let Some(category) = attributes.get("PARKING_CATEGORY") {
+        return category != "None" && category != "No Parking Allowed";
+    }
+    match attributes.get("parking").map(|x| x.to_lowercase()) {
+        Some(x) => x != "no" && x != "false" && x != "0",
+        None => true,
+    }
+}
+
 fn use_offstreet_parking(map: &mut RawMap, path: String, timer: &mut Timer) {
     timer.start("match offstreet parking points");
     let shapes: ExtraShapes = abstutil::read_binary(path, timer);
 
-    let mut closest: FindClosest<OriginalBuilding> = FindClosest::new(&map.gps_bounds.to_bounds());
+    let mut closest: FindClosest<OriginalBuilding> = FindClosest::new();
     for (id, b) in &map.buildings {
         closest.add(*id, b.polygon.points());
     }
 
     // TODO Another function just to use ?. Try blocks would rock.
     let mut handle_shape: Box<dyn FnMut(kml::ExtraShape) -> Option<()>> = Box::new(|s| {
-        assert_eq!(s.points.len(), 1);
-        let pt = Pt2D::from_gps(s.points[0], &map.gps_bounds);
+        if s.points.is_empty() {
+            return None;
+        }
+        // Garages drawn as areas are matched by their middle.
+        let pt = Pt2D::center(&map.gps_bounds.convert(&s.points));
         let (id, _) = closest.closest_pt(pt, Distance::meters(50.0))?;
         // TODO Handle parking lots.
         if !map.buildings[&id].polygon.contains_pt(pt) {
             return None;
         }
-        let name = s.attributes.get("DEA_FACILITY_NAME")?.to_string();
-        let num_stalls = s.attributes.get("DEA_STALLS")?.parse::<usize>().ok()?;
+        // Seattle's attributes, or more generic ones from other cities
+        let name = s
+            .attributes
+            .get("DEA_FACILITY_NAME")
+            .or_else(|| s.attributes.get("name"))
+            .cloned()
+            .unwrap_or_else(|| "public parking".to_string());
+        let num_stalls = ["DEA_STALLS", "capacity", "spots"]
+            .iter()
+            .find_map(|key| s.attributes.get(*key))?
+            .parse::<usize>()
+            .ok()?;
         // Well that's silly. Why's it listed?
         if num_stalls == 0 {
             return None;
@@ -280,8 +389,85 @@ fn apply_private_offstreet_parking(map: &mut RawMap, policy: PrivateOffstreetPar
     }
 }
 
+fn use_traffic_signal_inventory(map: &mut RawMap, path: String, timer: &mut Timer) {
+    timer.start("match traffic signal inventory");
+    let shapes: ExtraShapes = abstutil::read_binary(path, timer);
+    // Inventories usually locate signals at the center of the intersection, but not precisely.
+    let max_dist = Distance::meters(30.0);
+
+    let mut closest: FindClosest<OriginalRoad> = FindClosest::new();
+    for (id, r) in &map.roads {
+        closest.add(*id, &r.center_points);
+    }
+
+    let mut matched: BTreeSet<OriginalIntersection> = BTreeSet::new();
+    let mut unmatched = 0;
+    for s in shapes.shapes {
+        if s.points.len() != 1 {
+            continue;
+        }
+        let pt = Pt2D::from_gps(s.points[0], &map.gps_bounds);
+        if !map.boundary_polygon.contains_pt(pt) {
+            continue;
+        }
+        // The signal belongs to whichever end of the closest road is nearer.
+        let i = closest.closest_pt(pt, max_dist).and_then(|(r, _)| {
+            vec![r.i1, r.i2]
+                .into_iter()
+                .map(|i| (i, map.intersections[&i].point.dist_to(pt)))
+                .filter(|(_, dist)| *dist <= max_dist)
+                .min_by_key(|(_, dist)| *dist)
+                .map(|(i, _)| i)
+        });
+        if let Some(i) = i {
+            matched.insert(i);
+        } else {
+            timer.warn(format!(
+                "Inventory has a signal at {}, but there's no intersection there",
+                s.points[0]
+            ));
+            unmatched += 1;
+        }
+    }
+
+    let mut agree = 0;
+    let mut added = 0;
+    let mut only_osm = 0;
+    for (id, i) in map.intersections.iter_mut() {
+        match i.intersection_type {
+            IntersectionType::StopSign if matched.contains(id) => {
+                timer.warn(format!(
+                    "Inventory has a signal at https://www.openstreetmap.org/node/{}, but OSM \
+                     doesn't; adding it",
+                    id.osm_node_id
+                ));
+                i.intersection_type = IntersectionType::TrafficSignal;
+                added += 1;
+            }
+            IntersectionType::TrafficSignal if matched.contains(id) => {
+                agree += 1;
+            }
+            IntersectionType::TrafficSignal => {
+                timer.warn(format!(
+                    "OSM has a signal at https://www.openstreetmap.org/node/{}, but the inventory \
+                     doesn't; keeping it",
+                    id.osm_node_id
+                ));
+                only_osm += 1;
+            }
+            _ => {}
+        }
+    }
+    timer.note(format!(
+        "Traffic signal inventory: {} intersections agree, {} signals added, {} only in OSM, {} \
+         didn't match any intersection",
+        agree, added, only_osm, unmatched
+    ));
+    timer.stop("match traffic signal inventory");
+}
+
 fn use_amenities(map: &mut RawMap, amenities: Vec<(Pt2D, String, String)>, timer: &mut Timer) {
-    let mut closest: FindClosest<OriginalBuilding> = FindClosest::new(&map.gps_bounds.to_bounds());
+    let mut closest: FindClosest<OriginalBuilding> = FindClosest::new();
     for (id, b) in &map.buildings {
         closest.add(*id, b.polygon.points());
     }
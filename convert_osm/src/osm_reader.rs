@@ -1,5 +1,6 @@
+use crate::Sidewalks;
 use abstutil::{retain_btreemap, FileWithProgress, Tags, Timer};
-use geom::{GPSBounds, HashablePt2D, LonLat, PolyLine, Polygon, Pt2D, Ring};
+use geom::{Distance, FindClosest, GPSBounds, HashablePt2D, LonLat, PolyLine, Polygon, Pt2D, Ring};
 use map_model::raw::{
     OriginalBuilding, OriginalIntersection, RawArea, RawBuilding, RawBusRoute, RawBusStop, RawMap,
     RawParkingLot, RawRoad, RestrictionType,
@@ -13,6 +14,7 @@ pub fn extract_osm(
     maybe_clip_path: &Option<String>,
     city_name: &str,
     map_name: &str,
+    sidewalks: &Sidewalks,
     timer: &mut Timer,
 ) -> (
     RawMap,
@@ -20,6 +22,8 @@ pub fn extract_osm(
     Vec<(i64, RawRoad)>,
     // Traffic signals
     HashSet<HashablePt2D>,
+    // Pedestrian crossings and their type
+    HashMap<HashablePt2D, String>,
     // OSM Node IDs
     HashMap<HashablePt2D, i64>,
     // Simple turn restrictions: (relation ID, restriction type, from way ID, via node ID, to way
@@ -30,25 +34,31 @@ pub fn extract_osm(
     // Amenities (location, name, amenity type)
     Vec<(Pt2D, String, String)>,
 ) {
-    let (reader, done) = FileWithProgress::new(osm_path).unwrap();
-    let doc = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
+    let clip = maybe_clip_path.as_ref().map(|path| {
+        let pts = LonLat::read_osmosis_polygon(path.to_string()).unwrap();
+        let gps_bounds = GPSBounds::from(pts.clone());
+        let boundary_polygon = Polygon::new(&gps_bounds.convert(&pts));
+        (gps_bounds, boundary_polygon)
+    });
+
+    let doc = if osm_path.ends_with(".pbf") {
+        crate::pbf::read(osm_path, clip.as_ref().map(|(b, p)| (b, p)), timer)
+    } else {
+        let (reader, done) = FileWithProgress::new(osm_path).unwrap();
+        let doc = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
+        done(timer);
+        doc
+    };
     println!(
         "OSM doc has {} nodes, {} ways, {} relations",
         doc.nodes.len(),
         doc.ways.len(),
         doc.relations.len()
     );
-    done(timer);
-
-    let mut map = if let Some(path) = maybe_clip_path {
-        let pts = LonLat::read_osmosis_polygon(path.to_string()).unwrap();
-        let mut gps_bounds = GPSBounds::new();
-        for pt in &pts {
-            gps_bounds.update(*pt);
-        }
 
+    let mut map = if let Some((gps_bounds, boundary_polygon)) = clip {
         let mut map = RawMap::blank(city_name, map_name);
-        map.boundary_polygon = Polygon::new(&gps_bounds.convert(&pts));
+        map.boundary_polygon = boundary_polygon;
         map.gps_bounds = gps_bounds;
         map
     } else {
@@ -63,8 +73,11 @@ pub fn extract_osm(
     let mut id_to_way: HashMap<i64, Vec<Pt2D>> = HashMap::new();
     let mut roads: Vec<(i64, RawRoad)> = Vec::new();
     let mut traffic_signals: HashSet<HashablePt2D> = HashSet::new();
+    let mut crossings: HashMap<HashablePt2D, String> = HashMap::new();
     let mut osm_node_ids = HashMap::new();
     let mut node_amenities = Vec::new();
+    // Standalone address points, often mapped at entrances instead of on the building itself
+    let mut node_addresses: Vec<(Pt2D, BTreeMap<String, String>)> = Vec::new();
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
     for node in doc.nodes.values() {
@@ -76,6 +89,17 @@ pub fn extract_osm(
         if tags.is(osm::HIGHWAY, "traffic_signals") {
             traffic_signals.insert(pt.to_hashable());
         }
+        if tags.is(osm::HIGHWAY, "crossing") {
+            // crossing=zebra is deprecated in favor of crossing_ref, but still common
+            let kind = if tags.is("crossing_ref", "zebra") {
+                "zebra".to_string()
+            } else {
+                tags.get("crossing")
+                    .cloned()
+                    .unwrap_or_else(|| "marked".to_string())
+            };
+            crossings.insert(pt.to_hashable(), kind);
+        }
         if let Some(amenity) = tags.get("amenity") {
             node_amenities.push((
                 pt,
@@ -94,6 +118,15 @@ pub fn extract_osm(
                 shop.clone(),
             ));
         }
+        if tags.contains_key("addr:housenumber") {
+            node_addresses.push((
+                pt,
+                tags.take()
+                    .into_iter()
+                    .filter(|(k, _)| k.starts_with("addr:"))
+                    .collect(),
+            ));
+        }
     }
 
     let mut coastline_groups: Vec<(i64, Vec<Pt2D>)> = Vec::new();
@@ -122,7 +155,7 @@ pub fn extract_osm(
         let mut tags = tags_to_map(&way.tags);
         tags.insert(osm::OSM_WAY_ID, way.id.to_string());
 
-        if is_road(&mut tags) {
+        if is_road(&mut tags, sidewalks) {
             // TODO Hardcoding these overrides. OSM is correct, these don't have
             // sidewalks; there's a crosswalk mapped. But until we can snap sidewalks properly, do
             // this to prevent the sidewalks from being disconnected.
@@ -202,8 +235,8 @@ pub fn extract_osm(
 
         if let Some(area_type) = get_area_type(&tags) {
             if tags.is("type", "multipolygon") {
-                if let Some(pts_per_way) = get_multipolygon_members(rel, &id_to_way) {
-                    for polygon in glue_multipolygon(rel.id, pts_per_way, &boundary) {
+                if let Some((outer, inner)) = get_multipolygon_members(rel, &id_to_way) {
+                    for polygon in glue_multipolygon(rel.id, outer, inner, &boundary) {
                         map.areas.push(RawArea {
                             area_type,
                             osm_id: rel.id,
@@ -335,7 +368,7 @@ pub fn extract_osm(
 
     // Special case the coastline.
     println!("{} ways of coastline", coastline_groups.len());
-    for polygon in glue_multipolygon(-1, coastline_groups, &boundary) {
+    for polygon in glue_multipolygon(-1, coastline_groups, Vec::new(), &boundary) {
         let mut osm_tags = BTreeMap::new();
         osm_tags.insert("water".to_string(), "ocean".to_string());
         // Put it at the beginning, so that it's naturally beneath island areas
@@ -380,8 +413,11 @@ pub fn extract_osm(
         }
     }
 
+    use_address_nodes(&mut map, node_addresses, timer);
+
     // Hack to fix z-ordering for Green Lake (and probably other places). Put water and islands
-    // last. I think the more proper fix is interpreting "inner" roles in relations.
+    // last. Islands that are inner members of the water's relation are already cut out of it, but
+    // plenty of islands are only mapped as separate ways.
     map.areas.sort_by_key(|a| match a.area_type {
         AreaType::Island => 2,
         AreaType::Water => 1,
@@ -392,6 +428,7 @@ pub fn extract_osm(
         map,
         roads,
         traffic_signals,
+        crossings,
         osm_node_ids,
         simple_turn_restrictions,
         complicated_turn_restrictions,
@@ -399,6 +436,32 @@ pub fn extract_osm(
     )
 }
 
+// Buildings without their own address take one from an address point inside them. If there are
+// several, like one per entrance, the first wins.
+fn use_address_nodes(
+    map: &mut RawMap,
+    addresses: Vec<(Pt2D, BTreeMap<String, String>)>,
+    timer: &mut Timer,
+) {
+    let mut closest: FindClosest<OriginalBuilding> = FindClosest::new();
+    for (id, b) in &map.buildings {
+        if !b.osm_tags.contains_key("addr:housenumber") {
+            closest.add(*id, b.polygon.points());
+        }
+    }
+
+    timer.start_iter("match address points to buildings", addresses.len());
+    for (pt, addr_tags) in addresses {
+        timer.next();
+        if let Some((id, _)) = closest.closest_pt(pt, Distance::meters(50.0)) {
+            let b = map.buildings.get_mut(&id).unwrap();
+            if b.polygon.contains_pt(pt) && !b.osm_tags.contains_key("addr:housenumber") {
+                b.osm_tags.extend(addr_tags);
+            }
+        }
+    }
+}
+
 fn tags_to_map(raw_tags: &[osm_xml::Tag]) -> Tags {
     Tags::new(
         raw_tags
@@ -415,7 +478,7 @@ fn tags_to_map(raw_tags: &[osm_xml::Tag]) -> Tags {
     )
 }
 
-fn is_road(tags: &mut Tags) -> bool {
+fn is_road(tags: &mut Tags, sidewalks: &Sidewalks) -> bool {
     if tags.is("railway", "light_rail") {
         return true;
     }
@@ -467,6 +530,12 @@ fn is_road(tags: &mut Tags) -> bool {
         }
     }
 
+    // OSM treats freeways and their ramps as oneway unless tagged otherwise.
+    if tags.is_any(osm::HIGHWAY, vec!["motorway", "motorway_link"]) && !tags.contains_key("oneway")
+    {
+        tags.insert("oneway", "yes");
+    }
+
     // If there's no parking data in OSM already, then assume no parking and mark that it's
     // inferred.
     if !tags.contains_key(osm::PARKING_LEFT)
@@ -484,17 +553,21 @@ fn is_road(tags: &mut Tags) -> bool {
     // it's inferred.
     if !tags.contains_key(osm::SIDEWALK) {
         tags.insert(osm::INFERRED_SIDEWALKS, "true");
-        if tags.is_any(osm::HIGHWAY, vec!["motorway", "motorway_link"])
+        if let Sidewalks::JustOSM = sidewalks {
+            tags.insert(osm::SIDEWALK, "none");
+        } else if tags.is_any(osm::HIGHWAY, vec!["motorway", "motorway_link"])
             || tags.is("junction", "roundabout")
             || tags.is("foot", "no")
             || tags.is(osm::HIGHWAY, "service")
         {
             tags.insert(osm::SIDEWALK, "none");
-        } else if tags.is("oneway", "yes") {
+        } else if tags.is("oneway", "yes")
+            && !tags.is_any(
+                osm::HIGHWAY,
+                vec!["residential", "primary", "secondary", "tertiary"],
+            )
+        {
             tags.insert(osm::SIDEWALK, "right");
-            if tags.is(osm::HIGHWAY, "residential") {
-                tags.insert(osm::SIDEWALK, "both");
-            }
         } else {
             tags.insert(osm::SIDEWALK, "both");
         }
@@ -561,19 +634,24 @@ fn get_area_type(tags: &Tags) -> Option<AreaType> {
     None
 }
 
+// Returns (outer, inner) ways
 fn get_multipolygon_members(
     rel: &osm_xml::Relation,
     id_to_way: &HashMap<i64, Vec<Pt2D>>,
-) -> Option<Vec<(i64, Vec<Pt2D>)>> {
+) -> Option<(Vec<(i64, Vec<Pt2D>)>, Vec<(i64, Vec<Pt2D>)>)> {
     let mut ok = true;
-    let mut pts_per_way: Vec<(i64, Vec<Pt2D>)> = Vec::new();
+    let mut outer: Vec<(i64, Vec<Pt2D>)> = Vec::new();
+    let mut inner: Vec<(i64, Vec<Pt2D>)> = Vec::new();
     for member in &rel.members {
         match member {
             osm_xml::Member::Way(osm_xml::UnresolvedReference::Way(id), ref role) => {
                 // If the way is clipped out, that's fine
                 if let Some(pts) = id_to_way.get(id) {
-                    if role == "outer" {
-                        pts_per_way.push((*id, pts.to_vec()));
+                    // A missing role is an old way of saying outer
+                    if role == "outer" || role == "" {
+                        outer.push((*id, pts.to_vec()));
+                    } else if role == "inner" {
+                        inner.push((*id, pts.to_vec()));
                     } else {
                         println!(
                             "{} has unhandled member role {}, ignoring it",
@@ -590,83 +668,110 @@ fn get_multipolygon_members(
         }
     }
     if ok {
-        Some(pts_per_way)
+        Some((outer, inner))
     } else {
         None
     }
 }
 
-// The result could be more than one disjoint polygon.
+// The result could be more than one disjoint polygon. Inner rings become holes in whichever outer
+// ring contains them.
 fn glue_multipolygon(
     rel_id: i64,
-    mut pts_per_way: Vec<(i64, Vec<Pt2D>)>,
+    outer: Vec<(i64, Vec<Pt2D>)>,
+    inner: Vec<(i64, Vec<Pt2D>)>,
     boundary: &Ring,
 ) -> Vec<Polygon> {
-    // First deal with all of the closed loops.
-    let mut polygons: Vec<Polygon> = Vec::new();
-    pts_per_way.retain(|(_, pts)| {
-        if pts[0] == *pts.last().unwrap() {
-            polygons.push(Polygon::new(pts));
-            false
+    let (mut outer_rings, open_chains) = glue_rings(outer);
+    for mut result in open_chains {
+        // Some ways of the multipolygon must be clipped out.
+        if let Some(poly) = glue_to_boundary(PolyLine::must_new(result.clone()), boundary) {
+            outer_rings.push(poly.into_points());
         } else {
-            true
+            // Give up and just connect the ends directly.
+            result.push(result[0]);
+            outer_rings.push(result);
         }
-    });
-    if pts_per_way.is_empty() {
-        return polygons;
     }
 
-    // The main polygon
-    let (_, mut result) = pts_per_way.pop().unwrap();
-    let mut reversed = false;
-    while !pts_per_way.is_empty() {
-        let glue_pt = *result.last().unwrap();
-        if let Some(idx) = pts_per_way
-            .iter()
-            .position(|(_, pts)| pts[0] == glue_pt || *pts.last().unwrap() == glue_pt)
-        {
-            let (_, mut append) = pts_per_way.remove(idx);
-            if append[0] != glue_pt {
-                append.reverse();
-            }
-            result.pop();
-            result.extend(append);
-        } else {
-            if reversed {
-                // TODO Investigate what's going on here. At the very least, take what we have so
-                // far and try to glue it up.
-                println!(
-                    "Throwing away {} chunks from relation {}: ways {:?}",
-                    pts_per_way.len(),
-                    rel_id,
-                    pts_per_way.iter().map(|(id, _)| *id).collect::<Vec<i64>>()
-                );
+    let (inner_rings, open_chains) = glue_rings(inner);
+    if !open_chains.is_empty() {
+        println!(
+            "Ignoring {} inner pieces of {} that don't close up",
+            open_chains.len(),
+            rel_url(rel_id)
+        );
+    }
+
+    outer_rings
+        .into_iter()
+        .filter(|pts| pts.len() >= 4)
+        .map(|pts| polygon_with_holes(pts, &inner_rings))
+        .collect()
+}
+
+// Glue ways end-to-end into as many closed rings as possible. Anything that doesn't close up
+// (usually because part of it was clipped out) is returned separately as an open chain.
+fn glue_rings(mut pts_per_way: Vec<(i64, Vec<Pt2D>)>) -> (Vec<Vec<Pt2D>>, Vec<Vec<Pt2D>>) {
+    let mut rings = Vec::new();
+    let mut open_chains = Vec::new();
+    while let Some((_, mut result)) = pts_per_way.pop() {
+        let mut reversed = false;
+        while result[0] != *result.last().unwrap() {
+            let glue_pt = *result.last().unwrap();
+            if let Some(idx) = pts_per_way
+                .iter()
+                .position(|(_, pts)| pts[0] == glue_pt || *pts.last().unwrap() == glue_pt)
+            {
+                let (_, mut append) = pts_per_way.remove(idx);
+                if append[0] != glue_pt {
+                    append.reverse();
+                }
+                result.pop();
+                result.extend(append);
+            } else if reversed {
                 break;
             } else {
+                // Try to grow from the other end
                 reversed = true;
                 result.reverse();
-                // Try again!
             }
         }
+        if result[0] == *result.last().unwrap() {
+            rings.push(result);
+        } else {
+            open_chains.push(result);
+        }
     }
+    (rings, open_chains)
+}
 
-    if result[0] == *result.last().unwrap() {
-        polygons.push(Polygon::new(&result));
-        return polygons;
+// Cut out every inner ring that's inside the outer one. Rings that aren't valid get skipped,
+// instead of breaking the whole polygon.
+fn polygon_with_holes(mut outer: Vec<Pt2D>, inner_rings: &Vec<Vec<Pt2D>>) -> Polygon {
+    outer.dedup();
+    let outer_poly = Polygon::new(&outer);
+    if Ring::new(outer.clone()).is_err() {
+        return outer_poly;
     }
-    if let Some(poly) = glue_to_boundary(PolyLine::must_new(result.clone()), boundary) {
-        polygons.push(poly);
+    let holes: Vec<Vec<Pt2D>> = inner_rings
+        .iter()
+        .map(|pts| {
+            let mut pts = pts.clone();
+            pts.dedup();
+            pts
+        })
+        .filter(|pts| Ring::new(pts.clone()).is_ok() && outer_poly.contains_pt(pts[0]))
+        .collect();
+    if holes.is_empty() {
+        outer_poly
     } else {
-        // Give up and just connect the ends directly.
-        result.push(result[0]);
-        polygons.push(Polygon::new(&result));
+        Polygon::with_holes(outer, holes)
     }
-
-    polygons
 }
 
 fn glue_to_boundary(result_pl: PolyLine, boundary: &Ring) -> Option<Polygon> {
-    // Some ways of the multipolygon must be clipped out. First try to trace along the boundary.
+    // Try to trace along the boundary.
     let hits = boundary.all_intersections(&result_pl);
     if hits.len() != 2 {
         return None;
@@ -725,29 +830,25 @@ fn extract_route(
     for (role, member) in get_members(rel, doc) {
         if role == "stop" {
             if let osm_xml::Reference::Node(node) = member {
+                let node_tags = tags_to_map(&node.tags);
                 stops.push(RawBusStop {
-                    name: tags_to_map(&node.tags)
+                    name: node_tags
                         .get("name")
                         .cloned()
                         .unwrap_or_else(|| format!("stop #{}", stops.len() + 1)),
                     vehicle_pos: Pt2D::from_gps(LonLat::new(node.lon, node.lat), gps_bounds),
                     ped_pos: None,
+                    shelter: node_tags.is("shelter", "yes"),
                 });
             }
         } else if role == "platform" {
-            let (platform_name, pt) = match member {
+            let (platform_tags, pt) = match member {
                 osm_xml::Reference::Node(node) => (
-                    tags_to_map(&node.tags)
-                        .get("name")
-                        .cloned()
-                        .unwrap_or_else(|| format!("stop #{}", platforms.len() + 1)),
+                    tags_to_map(&node.tags),
                     Pt2D::from_gps(LonLat::new(node.lon, node.lat), gps_bounds),
                 ),
                 osm_xml::Reference::Way(way) => (
-                    tags_to_map(&way.tags)
-                        .get("name")
-                        .cloned()
-                        .unwrap_or_else(|| format!("stop #{}", platforms.len() + 1)),
+                    tags_to_map(&way.tags),
                     if let Some(ref pts) = id_to_way.get(&way.id) {
                         Pt2D::center(pts)
                     } else {
@@ -756,7 +857,11 @@ fn extract_route(
                 ),
                 _ => continue,
             };
-            platforms.insert(platform_name, pt);
+            let platform_name = platform_tags
+                .get("name")
+                .cloned()
+                .unwrap_or_else(|| format!("stop #{}", platforms.len() + 1));
+            platforms.insert(platform_name, (pt, platform_tags.is("shelter", "yes")));
         } else if let osm_xml::Reference::Way(way) = member {
             // The order of nodes might be wrong, doesn't matter
             for node in &way.nodes {
@@ -767,8 +872,9 @@ fn extract_route(
         }
     }
     for stop in &mut stops {
-        if let Some(pt) = platforms.remove(&stop.name) {
+        if let Some((pt, shelter)) = platforms.remove(&stop.name) {
             stop.ped_pos = Some(pt);
+            stop.shelter |= shelter;
         }
     }
 
@@ -836,21 +942,15 @@ fn multipoly_geometry<'a>(
     doc: &'a osm_xml::OSM,
     id_to_way: &HashMap<i64, Vec<Pt2D>>,
 ) -> Result<Polygon, Box<dyn Error>> {
-    let mut outer: Vec<Vec<Pt2D>> = Vec::new();
-    let mut inner: Vec<Vec<Pt2D>> = Vec::new();
+    let mut outer: Vec<(i64, Vec<Pt2D>)> = Vec::new();
+    let mut inner: Vec<(i64, Vec<Pt2D>)> = Vec::new();
     for (role, member) in get_members(rel, &doc) {
-        if let osm_xml::Reference::Way(node) = member {
-            if let Some(pts) = id_to_way.get(&node.id) {
-                let mut deduped = pts.clone();
-                deduped.dedup();
-                if deduped.len() < 3 {
-                    continue;
-                }
-
-                if role == "outer" {
-                    outer.push(deduped);
+        if let osm_xml::Reference::Way(way) = member {
+            if let Some(pts) = id_to_way.get(&way.id) {
+                if role == "outer" || role == "" {
+                    outer.push((way.id, pts.clone()));
                 } else if role == "inner" {
-                    inner.push(deduped);
+                    inner.push((way.id, pts.clone()));
                 } else {
                     return Err(format!(
                         "What's role {} for multipolygon {}?",
@@ -862,24 +962,31 @@ fn multipoly_geometry<'a>(
             }
         }
     }
-    // TODO Handle multiple outers with holes
-    if outer.len() == 0 || outer.len() > 1 && !inner.is_empty() {
+
+    // Courtyards and such are often split into several ways, so glue them up first.
+    let (outer_rings, open_outer) = glue_rings(outer);
+    let (inner_rings, open_inner) = glue_rings(inner);
+    if outer_rings.is_empty() || !open_outer.is_empty() || !open_inner.is_empty() {
         return Err(format!(
-            "Multipolygon {} has {} outer, {} inner. Huh?",
+            "Multipolygon {} has {} outer and {} inner rings, with {} pieces that don't close. \
+             Huh?",
             rel_url(rel.id),
-            outer.len(),
-            inner.len()
+            outer_rings.len(),
+            inner_rings.len(),
+            open_outer.len() + open_inner.len()
         )
         .into());
     }
-    if inner.is_empty() {
-        if outer.len() > 1 {
-            Ok(Polygon::union_all(outer.iter().map(Polygon::new).collect()))
-        } else {
-            Ok(Polygon::new(&outer[0]))
-        }
-    } else {
-        Ok(Polygon::with_holes(outer.pop().unwrap(), inner))
+    let mut polygons: Vec<Polygon> = outer_rings
+        .into_iter()
+        .filter(|pts| pts.len() >= 4)
+        .map(|pts| polygon_with_holes(pts, &inner_rings))
+        .collect();
+    match polygons.len() {
+        0 => Err(format!("Multipolygon {} has no usable outer rings", rel_url(rel.id)).into()),
+        1 => Ok(polygons.pop().unwrap()),
+        // Keep the rings of every piece and hole around, so they all get outlines
+        _ => Ok(Polygon::union_all_rings(polygons)),
     }
 }
 
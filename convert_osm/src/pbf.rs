@@ -0,0 +1,148 @@
+use abstutil::{FileWithProgress, Timer};
+use geom::{GPSBounds, LonLat, Polygon, Pt2D};
+use osmpbfreader::{OsmId, OsmObj, OsmPbfReader, Tags};
+use std::collections::{HashMap, HashSet};
+
+// Reads an .osm.pbf file into the same structure the XML parser produces, so the rest of
+// convert_osm doesn't care about the format.
+//
+// PBF extracts usually cover a whole region, so when there's a boundary, only keep what's inside,
+// like `osmconvert --complete-ways`: nodes inside, every way with at least one of those nodes
+// (along with all of its nodes), and every relation referring to something kept.
+pub fn read(
+    path: &str,
+    boundary: Option<(&GPSBounds, &Polygon)>,
+    timer: &mut Timer,
+) -> osm_xml::OSM {
+    let mut doc = osm_xml::OSM {
+        bounds: None,
+        nodes: HashMap::new(),
+        ways: HashMap::new(),
+        relations: HashMap::new(),
+    };
+    // Node IDs referenced by kept ways, but not loaded yet
+    let mut missing_nodes: HashSet<i64> = HashSet::new();
+
+    timer.start(format!("read {}", path));
+    // PBF files list all nodes, then all ways, then all relations, so one pass decides
+    // everything. Only the nodes outside the boundary that kept ways need are left.
+    read_pbf(path, timer, |obj| match obj {
+        OsmObj::Node(n) => {
+            let inside = boundary
+                .map(|(gps_bounds, polygon)| {
+                    let pt = LonLat::new(n.lon(), n.lat());
+                    gps_bounds.contains(pt) && polygon.contains_pt(Pt2D::from_gps(pt, gps_bounds))
+                })
+                .unwrap_or(true);
+            if inside {
+                doc.nodes.insert(
+                    n.id.0,
+                    osm_xml::Node {
+                        id: n.id.0,
+                        lat: n.lat(),
+                        lon: n.lon(),
+                        tags: convert_tags(&n.tags),
+                    },
+                );
+            }
+        }
+        OsmObj::Way(w) => {
+            if !w.nodes.iter().any(|n| doc.nodes.contains_key(&n.0)) {
+                return;
+            }
+            for n in &w.nodes {
+                if !doc.nodes.contains_key(&n.0) {
+                    missing_nodes.insert(n.0);
+                }
+            }
+            doc.ways.insert(
+                w.id.0,
+                osm_xml::Way {
+                    id: w.id.0,
+                    tags: convert_tags(&w.tags),
+                    nodes: w
+                        .nodes
+                        .iter()
+                        .map(|n| osm_xml::UnresolvedReference::Node(n.0))
+                        .collect(),
+                },
+            );
+        }
+        OsmObj::Relation(r) => {
+            let keep = r.refs.iter().any(|member| match member.member {
+                OsmId::Node(id) => doc.nodes.contains_key(&id.0),
+                OsmId::Way(id) => doc.ways.contains_key(&id.0),
+                OsmId::Relation(id) => doc.relations.contains_key(&id.0),
+            });
+            if !keep {
+                return;
+            }
+            doc.relations.insert(
+                r.id.0,
+                osm_xml::Relation {
+                    id: r.id.0,
+                    tags: convert_tags(&r.tags),
+                    members: r
+                        .refs
+                        .iter()
+                        .map(|member| {
+                            let role = member.role.to_string();
+                            match member.member {
+                                OsmId::Node(id) => osm_xml::Member::Node(
+                                    osm_xml::UnresolvedReference::Node(id.0),
+                                    role,
+                                ),
+                                OsmId::Way(id) => osm_xml::Member::Way(
+                                    osm_xml::UnresolvedReference::Way(id.0),
+                                    role,
+                                ),
+                                OsmId::Relation(id) => osm_xml::Member::Relation(
+                                    osm_xml::UnresolvedReference::Relation(id.0),
+                                    role,
+                                ),
+                            }
+                        })
+                        .collect(),
+                },
+            );
+        }
+    });
+
+    if !missing_nodes.is_empty() {
+        read_pbf(path, timer, |obj| {
+            if let OsmObj::Node(n) = obj {
+                if missing_nodes.contains(&n.id.0) {
+                    doc.nodes.insert(
+                        n.id.0,
+                        osm_xml::Node {
+                            id: n.id.0,
+                            lat: n.lat(),
+                            lon: n.lon(),
+                            tags: convert_tags(&n.tags),
+                        },
+                    );
+                }
+            }
+        });
+    }
+    timer.stop(format!("read {}", path));
+
+    doc
+}
+
+fn read_pbf<F: FnMut(OsmObj)>(path: &str, timer: &mut Timer, mut handle: F) {
+    let (reader, done) = FileWithProgress::new(path).unwrap();
+    for obj in OsmPbfReader::new(reader).iter() {
+        handle(obj.unwrap_or_else(|err| panic!("Can't parse {}: {}", path, err)));
+    }
+    done(timer);
+}
+
+fn convert_tags(tags: &Tags) -> Vec<osm_xml::Tag> {
+    tags.iter()
+        .map(|(k, v)| osm_xml::Tag {
+            key: k.to_string(),
+            val: v.to_string(),
+        })
+        .collect()
+}
@@ -4,13 +4,14 @@ use map_model::raw::{
     OriginalIntersection, OriginalRoad, RawIntersection, RawMap, RawRoad, RestrictionType,
 };
 use map_model::{osm, IntersectionType};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub fn split_up_roads(
     (
         mut map,
         roads,
         traffic_signals,
+        crossings,
         osm_node_ids,
         simple_turn_restrictions,
         complicated_turn_restrictions,
@@ -19,6 +20,7 @@ pub fn split_up_roads(
         RawMap,
         Vec<(i64, RawRoad)>,
         HashSet<HashablePt2D>,
+        HashMap<HashablePt2D, String>,
         HashMap<HashablePt2D, i64>,
         Vec<(i64, RestrictionType, i64, i64, i64)>,
         Vec<(i64, i64, i64, i64)>,
@@ -63,47 +65,11 @@ pub fn split_up_roads(
         );
     }
 
-    // Now actually split up the roads based on the intersections
-    timer.start_iter("split roads", roads.len());
-    for (osm_way_id, orig_road) in &roads {
-        timer.next();
-        let mut r = orig_road.clone();
-        let mut pts = Vec::new();
-        let endpt1 = pt_to_intersection[&orig_road.center_points[0].to_hashable()];
-        let endpt2 = pt_to_intersection[&orig_road.center_points.last().unwrap().to_hashable()];
-        let mut i1 = endpt1;
-
-        for pt in &orig_road.center_points {
-            pts.push(*pt);
-            if pts.len() == 1 {
-                continue;
-            }
-            if let Some(i2) = pt_to_intersection.get(&pt.to_hashable()) {
-                if i1 == endpt1 {
-                    r.osm_tags
-                        .insert(osm::ENDPT_BACK.to_string(), "true".to_string());
-                }
-                if *i2 == endpt2 {
-                    r.osm_tags
-                        .insert(osm::ENDPT_FWD.to_string(), "true".to_string());
-                }
-                r.center_points = dedupe_angles(std::mem::replace(&mut pts, Vec::new()));
-                // Start a new road
-                map.roads.insert(
-                    OriginalRoad {
-                        osm_way_id: *osm_way_id,
-                        i1,
-                        i2: *i2,
-                    },
-                    r.clone(),
-                );
-                r.osm_tags.remove(osm::ENDPT_FWD);
-                r.osm_tags.remove(osm::ENDPT_BACK);
-                i1 = *i2;
-                pts.push(*pt);
-            }
-        }
-        assert!(pts.len() == 1);
+    // Now actually split up the roads based on the intersections. Each way is independent.
+    for pieces in timer.parallelize("split roads", roads.iter().collect(), |(osm_way_id, r)| {
+        split_road(*osm_way_id, r, &pt_to_intersection, &crossings)
+    }) {
+        map.roads.extend(pieces);
     }
 
     // Resolve simple turn restrictions (via a node)
@@ -189,6 +155,94 @@ pub fn split_up_roads(
     (map, amenities)
 }
 
+fn split_road(
+    osm_way_id: i64,
+    orig_road: &RawRoad,
+    pt_to_intersection: &HashMap<HashablePt2D, OriginalIntersection>,
+    crossings: &HashMap<HashablePt2D, String>,
+) -> Vec<(OriginalRoad, RawRoad)> {
+    let mut pieces = Vec::new();
+    let mut r = orig_road.clone();
+    let mut pts = Vec::new();
+    let endpt1 = pt_to_intersection[&orig_road.center_points[0].to_hashable()];
+    let endpt2 = pt_to_intersection[&orig_road.center_points.last().unwrap().to_hashable()];
+    let mut i1 = endpt1;
+
+    for pt in &orig_road.center_points {
+        pts.push(*pt);
+        if pts.len() == 1 {
+            continue;
+        }
+        if let Some(i2) = pt_to_intersection.get(&pt.to_hashable()) {
+            if i1 == endpt1 {
+                r.osm_tags
+                    .insert(osm::ENDPT_BACK.to_string(), "true".to_string());
+            }
+            if *i2 == endpt2 {
+                r.osm_tags
+                    .insert(osm::ENDPT_FWD.to_string(), "true".to_string());
+            }
+            // Before dedupe_angles, since crossing nodes are usually right in line
+            tag_crossings(&pts, &mut r.osm_tags, crossings);
+            r.center_points = dedupe_angles(std::mem::replace(&mut pts, Vec::new()));
+            // Start a new road
+            pieces.push((
+                OriginalRoad {
+                    osm_way_id,
+                    i1,
+                    i2: *i2,
+                },
+                r.clone(),
+            ));
+            r.osm_tags.remove(osm::ENDPT_FWD);
+            r.osm_tags.remove(osm::ENDPT_BACK);
+            r.osm_tags.remove(osm::CROSSING_FWD);
+            r.osm_tags.remove(osm::CROSSING_BACK);
+            i1 = *i2;
+            pts.push(*pt);
+        }
+    }
+    assert!(pts.len() == 1);
+    pieces
+}
+
+// Crossing nodes close to either end of the road are probably the crosswalk at that intersection.
+// Anything further in is a mid-block crossing, which isn't modeled yet.
+fn tag_crossings(
+    pts: &Vec<Pt2D>,
+    osm_tags: &mut BTreeMap<String, String>,
+    crossings: &HashMap<HashablePt2D, String>,
+) {
+    let max_dist = Distance::meters(20.0);
+    if pts.len() < 3 {
+        return;
+    }
+
+    let mut dist = Distance::ZERO;
+    for pair in pts[..pts.len() - 1].windows(2) {
+        dist += pair[0].dist_to(pair[1]);
+        if dist > max_dist {
+            break;
+        }
+        if let Some(kind) = crossings.get(&pair[1].to_hashable()) {
+            osm_tags.insert(osm::CROSSING_BACK.to_string(), kind.clone());
+            break;
+        }
+    }
+
+    let mut dist = Distance::ZERO;
+    for pair in pts[1..].windows(2).rev() {
+        dist += pair[0].dist_to(pair[1]);
+        if dist > max_dist {
+            break;
+        }
+        if let Some(kind) = crossings.get(&pair[0].to_hashable()) {
+            osm_tags.insert(osm::CROSSING_FWD.to_string(), kind.clone());
+            break;
+        }
+    }
+}
+
 // TODO Consider doing this in PolyLine::new always. extend() there does this too.
 fn dedupe_angles(pts: Vec<Pt2D>) -> Vec<Pt2D> {
     let mut result = Vec::new();
@@ -1,4 +1,4 @@
-use crate::text::Font;
+use crate::text::{Font, Glyph};
 use crate::{text, GeomBatch};
 use geom::Bounds;
 use lru::LruCache;
@@ -12,6 +12,11 @@ pub struct Assets {
     pub default_font_size: RefCell<usize>,
     pub scale_factor: RefCell<f64>,
     text_cache: RefCell<LruCache<String, GeomBatch>>,
+    // Every glyph is tessellated once per font, size, and quality, then laid out by every line that
+    // uses it. The tolerance is mangled into a hashable form. None means the font lacks the glyph.
+    glyph_cache: RefCell<HashMap<(Font, usize, char, usize), Option<Glyph>>>,
+    // Adjustment between a pair of characters, already scaled
+    kerning_cache: RefCell<HashMap<(Font, usize, char, char), f64>>,
     line_height_cache: RefCell<HashMap<(Font, usize), f64>>,
     // Keyed by filename, then scale factor mangled into a hashable form. Tuple doesn't work
     // because of borrowing.
@@ -27,6 +32,8 @@ impl Assets {
             default_font_size: RefCell::new(default_font_size),
             scale_factor: RefCell::new(scale_factor),
             text_cache: RefCell::new(LruCache::new(500)),
+            glyph_cache: RefCell::new(HashMap::new()),
+            kerning_cache: RefCell::new(HashMap::new()),
             line_height_cache: RefCell::new(HashMap::new()),
             svg_cache: RefCell::new(HashMap::new()),
             font_to_id: HashMap::new(),
@@ -107,6 +114,43 @@ impl Assets {
         self.text_cache.borrow_mut().put(key, geom);
     }
 
+    pub fn glyph(&self, font: Font, font_size: usize, c: char, tolerance: f32) -> Option<Glyph> {
+        let key = (font, font_size, c, (tolerance * 100.0) as usize);
+        if let Some(glyph) = self.glyph_cache.borrow().get(&key) {
+            return glyph.clone();
+        }
+
+        let scale = (font_size as f64) * *self.scale_factor.borrow();
+        let glyph = self
+            .text_opts
+            .fontdb
+            .with_face_data(self.font_to_id[&font], |data, face_index| {
+                text::make_glyph(data, face_index, c, scale, tolerance)
+            })
+            .flatten();
+        self.glyph_cache.borrow_mut().insert(key, glyph.clone());
+        glyph
+    }
+
+    pub fn kerning(&self, font: Font, font_size: usize, left: char, right: char) -> f64 {
+        let key = (font, font_size, left, right);
+        if let Some(kerning) = self.kerning_cache.borrow().get(&key) {
+            return *kerning;
+        }
+
+        let scale = (font_size as f64) * *self.scale_factor.borrow();
+        let kerning = self
+            .text_opts
+            .fontdb
+            .with_face_data(self.font_to_id[&font], |data, face_index| {
+                text::kerning(data, face_index, left, right, scale)
+            })
+            .flatten()
+            .unwrap_or(0.0);
+        self.kerning_cache.borrow_mut().insert(key, kerning);
+        kerning
+    }
+
     pub fn get_cached_svg(&self, key: &str, scale_factor: f64) -> Option<(GeomBatch, Bounds)> {
         self.svg_cache
             .borrow()
@@ -124,6 +168,8 @@ impl Assets {
     pub fn set_scale_factor(&self, scale_factor: f64) {
         *self.scale_factor.borrow_mut() = scale_factor;
         self.text_cache.borrow_mut().clear();
+        self.glyph_cache.borrow_mut().clear();
+        self.kerning_cache.borrow_mut().clear();
         self.line_height_cache.borrow_mut().clear();
         *self.default_line_height.borrow_mut() =
             self.line_height(text::DEFAULT_FONT, *self.default_font_size.borrow());
@@ -0,0 +1,51 @@
+use crate::drawing::{Instance, Uniforms};
+use crate::{Canvas, Color, FancyColor, ScreenRectangle};
+use geom::Polygon;
+
+#[cfg(feature = "glium-backend")]
+pub use crate::backend_glium::*;
+
+#[cfg(feature = "glow-backend")]
+pub use crate::backend_glow::*;
+
+#[cfg(feature = "wasm-backend")]
+pub use crate::backend_wasm::*;
+
+#[cfg(feature = "wgpu-backend")]
+pub use crate::backend_wgpu::*;
+
+// Exactly one backend is compiled in, picked by a feature. Each one has a setup() function and
+// implements these traits for its own PrerenderInnards and GfxCtxInnards, so the rest of ezgui
+// can't start depending on something only one backend happens to have.
+
+pub trait PrerenderImpl {
+    fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable;
+    fn actually_upload_instanced(
+        &self,
+        template: Vec<(FancyColor, &Polygon)>,
+        instances: Vec<Instance>,
+    ) -> Drawable;
+    fn request_redraw(&self);
+    fn set_cursor_icon(&self, icon: winit::window::CursorIcon);
+    fn draw_new_frame(&self) -> GfxCtxInnards<'_>;
+    fn window_resized(&self, width: f64, height: f64);
+    fn get_inner_size(&self) -> (f64, f64);
+    fn set_window_icon(&self, icon: winit::window::Icon);
+    fn monitor_scale_factor(&self) -> f64;
+    // Reads back the last frame drawn. Backends that can't do this return None, and screenshots
+    // fall back to an external tool.
+    fn screencap(&self) -> Option<image::RgbaImage>;
+}
+
+pub trait GfxCtxImpl {
+    // Whatever the backend needs to remember about the current clipping rectangle
+    type Clip;
+
+    fn clear(&mut self, color: Color);
+    fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards);
+    fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas);
+    fn disable_clipping(&mut self, canvas: &Canvas);
+    fn take_clip(&mut self) -> Option<Self::Clip>;
+    fn restore_clip(&mut self, clip: Option<Self::Clip>);
+    fn finish(self);
+}
@@ -1,4 +1,5 @@
-use crate::drawing::Uniforms;
+use crate::backend::{GfxCtxImpl, PrerenderImpl};
+use crate::drawing::{Instance, Uniforms};
 use crate::{Canvas, Color, FancyColor, ScreenDims, ScreenRectangle};
 use geom::Polygon;
 use glium::uniforms::UniformValue;
@@ -89,22 +90,12 @@ pub fn setup(
         (&vert, &frag)
     };*/
 
-    let program = glium::Program::new(
+    let program = make_program(&display, vertex_shader, fragment_shader);
+    let instanced_program = make_program(
         &display,
-        glium::program::ProgramCreationInput::SourceCode {
-            vertex_shader,
-            tessellation_control_shader: None,
-            tessellation_evaluation_shader: None,
-            geometry_shader: None,
-            fragment_shader,
-            transform_feedback_varyings: None,
-            // Without this, SRGB gets enabled and post-processes the color from the fragment
-            // shader.
-            outputs_srgb: true,
-            uses_point_size: false,
-        },
-    )
-    .unwrap();
+        include_str!("shaders/vertex_instanced_140.glsl"),
+        fragment_shader,
+    );
 
     let inner_window = display.gl_window().window().inner_size();
     let monitor = event_loop.primary_monitor().size();
@@ -123,6 +114,7 @@ pub fn setup(
         PrerenderInnards {
             display,
             program,
+            instanced_program,
             total_bytes_uploaded: Cell::new(0),
         },
         event_loop,
@@ -130,6 +122,29 @@ pub fn setup(
     )
 }
 
+fn make_program(
+    display: &glium::Display,
+    vertex_shader: &str,
+    fragment_shader: &str,
+) -> glium::Program {
+    glium::Program::new(
+        display,
+        glium::program::ProgramCreationInput::SourceCode {
+            vertex_shader,
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            geometry_shader: None,
+            fragment_shader,
+            transform_feedback_varyings: None,
+            // Without this, SRGB gets enabled and post-processes the color from the fragment
+            // shader.
+            outputs_srgb: true,
+            uses_point_size: false,
+        },
+    )
+    .unwrap()
+}
+
 struct InnerUniforms<'a> {
     values: &'a Uniforms,
 }
@@ -147,15 +162,29 @@ pub struct GfxCtxInnards<'a> {
     params: glium::DrawParameters<'a>,
 }
 
-impl<'a> GfxCtxInnards<'a> {
-    pub fn clear(&mut self, c: Color) {
+impl<'a> GfxCtxImpl for GfxCtxInnards<'a> {
+    type Clip = glium::Rect;
+
+    fn clear(&mut self, c: Color) {
         // Without this, SRGB gets enabled and post-processes the color from the fragment
         // shader.
         self.target
             .clear_color_srgb_and_depth((c.r, c.g, c.b, c.a), 1.0);
     }
 
-    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards) {
+    fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards) {
+        if let Some(ref instances) = obj.instances {
+            self.target
+                .draw(
+                    (&obj.vertex_buffer, instances.per_instance().unwrap()),
+                    &obj.index_buffer,
+                    &prerender.instanced_program,
+                    &InnerUniforms { values: uniforms },
+                    &self.params,
+                )
+                .unwrap();
+            return;
+        }
         self.target
             .draw(
                 &obj.vertex_buffer,
@@ -167,7 +196,7 @@ impl<'a> GfxCtxInnards<'a> {
             .unwrap();
     }
 
-    pub fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
+    fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
         assert!(self.params.scissor.is_none());
         // The scissor rectangle has to be in device coordinates, so you would think some transform
         // by scale factor (previously called HiDPI factor) has to happen here. But actually,
@@ -182,19 +211,19 @@ impl<'a> GfxCtxInnards<'a> {
         });
     }
 
-    pub fn disable_clipping(&mut self, _: &Canvas) {
+    fn disable_clipping(&mut self, _: &Canvas) {
         assert!(self.params.scissor.is_some());
         self.params.scissor = None;
     }
 
-    pub fn take_clip(&mut self) -> Option<glium::Rect> {
+    fn take_clip(&mut self) -> Option<glium::Rect> {
         self.params.scissor.take()
     }
-    pub fn restore_clip(&mut self, clip: Option<glium::Rect>) {
+    fn restore_clip(&mut self, clip: Option<glium::Rect>) {
         self.params.scissor = clip;
     }
 
-    pub fn finish(self) {
+    fn finish(self) {
         self.target.finish().unwrap();
     }
 }
@@ -203,6 +232,8 @@ impl<'a> GfxCtxInnards<'a> {
 pub struct Drawable {
     vertex_buffer: glium::VertexBuffer<Vertex>,
     index_buffer: glium::IndexBuffer<u32>,
+    // If this is present, the vertices are a template drawn once per instance.
+    instances: Option<glium::VertexBuffer<InstanceData>>,
 }
 
 #[derive(Copy, Clone)]
@@ -215,17 +246,27 @@ struct Vertex {
 
 glium::implement_vertex!(Vertex, position, style);
 
+#[derive(Copy, Clone)]
+struct InstanceData {
+    // (x, y, rotation in radians, scale)
+    instance: [f32; 4],
+    instance_color: [f32; 4],
+}
+
+glium::implement_vertex!(InstanceData, instance, instance_color);
+
 pub struct PrerenderInnards {
     display: glium::Display,
     program: glium::Program,
+    instanced_program: glium::Program,
 
     // TODO Prerender doesn't know what things are temporary and permanent. Could make the API more
     // detailed.
     pub total_bytes_uploaded: Cell<usize>,
 }
 
-impl PrerenderInnards {
-    pub fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
+impl PrerenderImpl for PrerenderInnards {
+    fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
         let mut vertices: Vec<Vertex> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
 
@@ -275,18 +316,41 @@ impl PrerenderInnards {
         Drawable {
             vertex_buffer,
             index_buffer,
+            instances: None,
         }
     }
 
-    pub fn request_redraw(&self) {
+    fn actually_upload_instanced(
+        &self,
+        template: Vec<(FancyColor, &Polygon)>,
+        instances: Vec<Instance>,
+    ) -> Drawable {
+        let mut drawable = self.actually_upload(false, template);
+        let data: Vec<InstanceData> = instances
+            .into_iter()
+            .map(|i| InstanceData {
+                instance: [
+                    i.pos.x() as f32,
+                    i.pos.y() as f32,
+                    i.angle.normalized_radians() as f32,
+                    i.scale as f32,
+                ],
+                instance_color: [i.color.r, i.color.g, i.color.b, i.color.a],
+            })
+            .collect();
+        drawable.instances = Some(glium::VertexBuffer::new(&self.display, &data).unwrap());
+        drawable
+    }
+
+    fn request_redraw(&self) {
         self.display.gl_window().window().request_redraw();
     }
 
-    pub fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
+    fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
         self.display.gl_window().window().set_cursor_icon(icon);
     }
 
-    pub fn draw_new_frame<'a>(&self) -> GfxCtxInnards<'a> {
+    fn draw_new_frame(&self) -> GfxCtxInnards<'_> {
         GfxCtxInnards {
             target: self.display.draw(),
             params: glium::DrawParameters {
@@ -301,21 +365,29 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn window_resized(&self, _: f64, _: f64) {}
+    fn window_resized(&self, _: f64, _: f64) {}
 
-    pub fn get_inner_size(&self) -> (f64, f64) {
+    fn get_inner_size(&self) -> (f64, f64) {
         let size = self.display.gl_window().window().inner_size();
         (size.width.into(), size.height.into())
     }
 
-    pub fn set_window_icon(&self, icon: winit::window::Icon) {
+    fn set_window_icon(&self, icon: winit::window::Icon) {
         self.display
             .gl_window()
             .window()
             .set_window_icon(Some(icon));
     }
 
-    pub fn monitor_scale_factor(&self) -> f64 {
+    fn monitor_scale_factor(&self) -> f64 {
         self.display.gl_window().window().scale_factor()
     }
+
+    // The last frame drawn
+    fn screencap(&self) -> Option<image::RgbaImage> {
+        let raw: glium::texture::RawImage2d<u8> = self.display.read_front_buffer().ok()?;
+        let img = image::RgbaImage::from_raw(raw.width, raw.height, raw.data.into_owned())?;
+        // OpenGL starts from the bottom row
+        Some(image::imageops::flip_vertical(&img))
+    }
 }
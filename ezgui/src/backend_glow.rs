@@ -1,4 +1,5 @@
-use crate::drawing::Uniforms;
+use crate::backend::{GfxCtxImpl, PrerenderImpl};
+use crate::drawing::{expand_instances, Instance, Uniforms};
 use crate::{Canvas, Color, FancyColor, ScreenDims, ScreenRectangle};
 use geom::Polygon;
 use glow::HasContext;
@@ -107,8 +108,10 @@ pub struct GfxCtxInnards<'a> {
     current_clip: Option<[i32; 4]>,
 }
 
-impl<'a> GfxCtxInnards<'a> {
-    pub fn clear(&mut self, color: Color) {
+impl<'a> GfxCtxImpl for GfxCtxInnards<'a> {
+    type Clip = [i32; 4];
+
+    fn clear(&mut self, color: Color) {
         unsafe {
             self.gl.clear_color(color.r, color.g, color.b, color.a);
             self.gl.clear(glow::COLOR_BUFFER_BIT);
@@ -118,7 +121,7 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, _: &PrerenderInnards) {
+    fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, _: &PrerenderInnards) {
         unsafe {
             let transform_loc = self
                 .gl
@@ -140,7 +143,7 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
+    fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
         assert!(self.current_clip.is_none());
         // The scissor rectangle has to be in device coordinates, so you would think some transform
         // by scale factor (previously called HiDPI factor) has to happen here. But actually,
@@ -157,7 +160,7 @@ impl<'a> GfxCtxInnards<'a> {
         self.current_clip = Some([left, bottom, width, height]);
     }
 
-    pub fn disable_clipping(&mut self, canvas: &Canvas) {
+    fn disable_clipping(&mut self, canvas: &Canvas) {
         assert!(self.current_clip.is_some());
         self.current_clip = None;
         unsafe {
@@ -170,10 +173,10 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn take_clip(&mut self) -> Option<[i32; 4]> {
+    fn take_clip(&mut self) -> Option<[i32; 4]> {
         self.current_clip.take()
     }
-    pub fn restore_clip(&mut self, clip: Option<[i32; 4]>) {
+    fn restore_clip(&mut self, clip: Option<[i32; 4]>) {
         self.current_clip = clip;
         if let Some(c) = clip {
             unsafe {
@@ -182,7 +185,7 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn finish(self) {
+    fn finish(self) {
         self.windowed_context.swap_buffers().unwrap();
     }
 }
@@ -206,8 +209,8 @@ pub struct PrerenderInnards {
     pub total_bytes_uploaded: Cell<usize>,
 }
 
-impl PrerenderInnards {
-    pub fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
+impl PrerenderImpl for PrerenderInnards {
+    fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
         let mut vertices: Vec<[f32; 6]> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
 
@@ -296,15 +299,25 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn request_redraw(&self) {
+    fn actually_upload_instanced(
+        &self,
+        template: Vec<(FancyColor, &Polygon)>,
+        instances: Vec<Instance>,
+    ) -> Drawable {
+        // TODO Instance on the GPU, like the glium backend
+        let list = expand_instances(template, instances);
+        self.actually_upload(false, list.iter().map(|(c, p)| (c.clone(), p)).collect())
+    }
+
+    fn request_redraw(&self) {
         self.windowed_context.window().request_redraw();
     }
 
-    pub fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
+    fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
         self.windowed_context.window().set_cursor_icon(icon);
     }
 
-    pub fn draw_new_frame(&self) -> GfxCtxInnards {
+    fn draw_new_frame(&self) -> GfxCtxInnards<'_> {
         GfxCtxInnards {
             gl: &self.gl,
             windowed_context: &self.windowed_context,
@@ -313,7 +326,7 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn window_resized(&self, width: f64, height: f64) {
+    fn window_resized(&self, width: f64, height: f64) {
         self.windowed_context
             .resize(winit::dpi::PhysicalSize::new(width as u32, height as u32));
         unsafe {
@@ -323,16 +336,21 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn get_inner_size(&self) -> (f64, f64) {
+    fn get_inner_size(&self) -> (f64, f64) {
         let size = self.windowed_context.window().inner_size();
         (size.width.into(), size.height.into())
     }
 
-    pub fn set_window_icon(&self, icon: winit::window::Icon) {
+    fn set_window_icon(&self, icon: winit::window::Icon) {
         self.windowed_context.window().set_window_icon(Some(icon));
     }
 
-    pub fn monitor_scale_factor(&self) -> f64 {
+    fn monitor_scale_factor(&self) -> f64 {
         self.windowed_context.window().scale_factor()
     }
+
+    // TODO Not implemented; screenshots fall back to an external tool.
+    fn screencap(&self) -> Option<image::RgbaImage> {
+        None
+    }
 }
@@ -0,0 +1,569 @@
+use crate::backend::{GfxCtxImpl, PrerenderImpl};
+use crate::drawing::{expand_instances, Instance, Uniforms};
+use crate::{Canvas, Color, FancyColor, ScreenDims, ScreenRectangle};
+use geom::Polygon;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// Goes through Metal, Vulkan, or DX12 instead of OpenGL, for drivers where OpenGL misbehaves.
+
+const SWAP_CHAIN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8Unorm;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+// Antialias like the OpenGL backends
+const SAMPLE_COUNT: u32 = 4;
+
+pub fn setup(
+    window_title: &str,
+) -> (
+    PrerenderInnards,
+    winit::event_loop::EventLoop<()>,
+    ScreenDims,
+) {
+    let event_loop = winit::event_loop::EventLoop::new();
+    let window = winit::window::WindowBuilder::new()
+        .with_title(window_title)
+        .with_maximized(true)
+        .build(&event_loop)
+        .unwrap();
+    let surface = wgpu::Surface::create(&window);
+    let (device, queue) = futures::executor::block_on(async {
+        let adapter = match wgpu::Adapter::request(
+            &wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::Default,
+                compatible_surface: Some(&surface),
+            },
+            wgpu::BackendBit::PRIMARY,
+        )
+        .await
+        {
+            Some(a) => a,
+            None => panic!(
+                "Can't find a graphics adapter for wgpu. Please file an issue at \
+                 https://github.com/dabreegster/abstreet/issues/ and include output.txt, or try \
+                 the default OpenGL backend."
+            ),
+        };
+        adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                extensions: wgpu::Extensions {
+                    anisotropic_filtering: false,
+                },
+                limits: wgpu::Limits::default(),
+            })
+            .await
+    });
+
+    let vs_module = device.create_shader_module(&compile_shader(
+        include_str!("shaders/vertex_450.glsl"),
+        glsl_to_spirv::ShaderType::Vertex,
+    ));
+    let fs_module = device.create_shader_module(&compile_shader(
+        include_str!("shaders/fragment_450.glsl"),
+        glsl_to_spirv::ShaderType::Fragment,
+    ));
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        bindings: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStage::VERTEX,
+            ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+        }],
+        label: None,
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        bind_group_layouts: &[&bind_group_layout],
+    });
+    let alpha_blending = wgpu::BlendDescriptor {
+        src_factor: wgpu::BlendFactor::SrcAlpha,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation: wgpu::BlendOperation::Add,
+    };
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        layout: &pipeline_layout,
+        vertex_stage: wgpu::ProgrammableStageDescriptor {
+            module: &vs_module,
+            entry_point: "main",
+        },
+        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+            module: &fs_module,
+            entry_point: "main",
+        }),
+        rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }),
+        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+        color_states: &[wgpu::ColorStateDescriptor {
+            format: SWAP_CHAIN_FORMAT,
+            color_blend: alpha_blending.clone(),
+            alpha_blend: alpha_blending,
+            write_mask: wgpu::ColorWrite::ALL,
+        }],
+        depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_read_mask: 0,
+            stencil_write_mask: 0,
+        }),
+        vertex_state: wgpu::VertexStateDescriptor {
+            index_format: wgpu::IndexFormat::Uint32,
+            vertex_buffers: &[wgpu::VertexBufferDescriptor {
+                stride: 6 * std::mem::size_of::<f32>() as wgpu::BufferAddress,
+                step_mode: wgpu::InputStepMode::Vertex,
+                // position is vec2, style is vec4
+                attributes: &wgpu::vertex_attr_array![0 => Float2, 1 => Float4],
+            }],
+        },
+        sample_count: SAMPLE_COUNT,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false,
+    });
+
+    let inner_window = window.inner_size();
+    let monitor = event_loop.primary_monitor().size();
+    let initial_size = if cfg!(target_os = "linux") {
+        monitor
+    } else {
+        inner_window
+    };
+    let size = (inner_window.width, inner_window.height);
+    let targets = RenderTargets::new(&device, &surface, size);
+    (
+        PrerenderInnards {
+            window,
+            surface,
+            device,
+            queue,
+            pipeline,
+            bind_group_layout,
+            targets: RefCell::new(targets),
+            last_frame: RefCell::new(None),
+            total_bytes_uploaded: Cell::new(0),
+        },
+        event_loop,
+        ScreenDims::new(initial_size.width.into(), initial_size.height.into()),
+    )
+}
+
+fn compile_shader(src: &str, ty: glsl_to_spirv::ShaderType) -> Vec<u32> {
+    let spirv = match glsl_to_spirv::compile(src, ty) {
+        Ok(f) => f,
+        Err(err) => panic!("Couldn't compile shader: {}", err),
+    };
+    wgpu::read_spirv(spirv).unwrap()
+}
+
+// Everything that has to be recreated when the window is resized
+struct RenderTargets {
+    swap_chain: wgpu::SwapChain,
+    // Everything's drawn here first, then resolved into the swap chain's texture
+    msaa_view: wgpu::TextureView,
+    depth_view: wgpu::TextureView,
+    size: (u32, u32),
+}
+
+impl RenderTargets {
+    fn new(device: &wgpu::Device, surface: &wgpu::Surface, size: (u32, u32)) -> RenderTargets {
+        let swap_chain = device.create_swap_chain(
+            surface,
+            &wgpu::SwapChainDescriptor {
+                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                format: SWAP_CHAIN_FORMAT,
+                width: size.0,
+                height: size.1,
+                present_mode: wgpu::PresentMode::Fifo,
+            },
+        );
+        let multisampled = |format| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    size: extent(size),
+                    array_layer_count: 1,
+                    mip_level_count: 1,
+                    sample_count: SAMPLE_COUNT,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                    label: None,
+                })
+                .create_default_view()
+        };
+        RenderTargets {
+            swap_chain,
+            msaa_view: multisampled(SWAP_CHAIN_FORMAT),
+            depth_view: multisampled(DEPTH_FORMAT),
+            size,
+        }
+    }
+}
+
+fn extent(size: (u32, u32)) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: size.0,
+        height: size.1,
+        depth: 1,
+    }
+}
+
+// The draws from the last finished frame, kept around so screencap() can draw it again
+struct RecordedFrame {
+    clear_color: Color,
+    draws: Vec<DrawCall>,
+}
+
+impl RecordedFrame {
+    fn encode(
+        &self,
+        prerender: &PrerenderInnards,
+        targets: &RenderTargets,
+        output: &wgpu::TextureView,
+    ) -> wgpu::CommandBuffer {
+        let (width, height) = targets.size;
+        let mut encoder = prerender
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let c = self.clear_color;
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &targets.msaa_view,
+                    resolve_target: Some(output),
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: wgpu::Color {
+                        r: c.r as f64,
+                        g: c.g as f64,
+                        b: c.b as f64,
+                        a: c.a as f64,
+                    },
+                }],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &targets.depth_view,
+                    depth_load_op: wgpu::LoadOp::Clear,
+                    depth_store_op: wgpu::StoreOp::Store,
+                    clear_depth: 1.0,
+                    stencil_load_op: wgpu::LoadOp::Clear,
+                    stencil_store_op: wgpu::StoreOp::Store,
+                    clear_stencil: 0,
+                }),
+            });
+            pass.set_pipeline(&prerender.pipeline);
+            for draw in &self.draws {
+                // wgpu rejects scissor rectangles that leave the screen
+                let [x, y, w, h] = draw.clip.unwrap_or([0, 0, width as i32, height as i32]);
+                let x1 = x.max(0).min(width as i32) as u32;
+                let y1 = y.max(0).min(height as i32) as u32;
+                let x2 = (x + w).max(0).min(width as i32) as u32;
+                let y2 = (y + h).max(0).min(height as i32) as u32;
+                if x2 <= x1 || y2 <= y1 {
+                    continue;
+                }
+                pass.set_scissor_rect(x1, y1, x2 - x1, y2 - y1);
+
+                pass.set_bind_group(0, &draw.bind_group, &[]);
+                pass.set_vertex_buffer(0, &draw.vertex_buffer, 0, 0);
+                pass.set_index_buffer(&draw.index_buffer, 0, 0);
+                pass.draw_indexed(0..draw.num_indices, 0, 0..1);
+            }
+        }
+        encoder.finish()
+    }
+}
+
+// Represents one frame that's gonna be drawn. wgpu wants the whole frame described up-front, so
+// the draws are just recorded here and submitted in finish().
+pub struct GfxCtxInnards<'a> {
+    prerender: &'a PrerenderInnards,
+    clear_color: Option<Color>,
+    draws: Vec<DrawCall>,
+
+    // (left, top, width, height)
+    current_clip: Option<[i32; 4]>,
+}
+
+struct DrawCall {
+    vertex_buffer: Rc<wgpu::Buffer>,
+    index_buffer: Rc<wgpu::Buffer>,
+    num_indices: u32,
+    _uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    clip: Option<[i32; 4]>,
+}
+
+impl<'a> GfxCtxImpl for GfxCtxInnards<'a> {
+    type Clip = [i32; 4];
+
+    fn clear(&mut self, color: Color) {
+        // Clearing covers everything drawn so far
+        self.draws.clear();
+        self.clear_color = Some(color);
+    }
+
+    fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards) {
+        // Each vec3 is padded to 16 bytes in the uniform block
+        let values: [f32; 8] = [
+            uniforms.transform[0],
+            uniforms.transform[1],
+            uniforms.transform[2],
+            0.0,
+            uniforms.window[0],
+            uniforms.window[1],
+            uniforms.window[2],
+            0.0,
+        ];
+        let uniform_buffer = prerender.device.create_buffer_with_data(
+            unsafe { values.align_to::<u8>().1 },
+            wgpu::BufferUsage::UNIFORM,
+        );
+        let bind_group = prerender
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &prerender.bind_group_layout,
+                bindings: &[wgpu::Binding {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &uniform_buffer,
+                        range: 0..(8 * std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+                    },
+                }],
+                label: None,
+            });
+        self.draws.push(DrawCall {
+            vertex_buffer: obj.vertex_buffer.clone(),
+            index_buffer: obj.index_buffer.clone(),
+            num_indices: obj.num_indices,
+            _uniform_buffer: uniform_buffer,
+            bind_group,
+            clip: self.current_clip,
+        });
+    }
+
+    fn enable_clipping(&mut self, rect: ScreenRectangle, _: &Canvas) {
+        assert!(self.current_clip.is_none());
+        // Like the OpenGL backends, the rectangle is already in device coordinates. Unlike them,
+        // there's no Y-inversion.
+        self.current_clip = Some([
+            rect.x1 as i32,
+            rect.y1 as i32,
+            (rect.x2 - rect.x1) as i32,
+            (rect.y2 - rect.y1) as i32,
+        ]);
+    }
+
+    fn disable_clipping(&mut self, _: &Canvas) {
+        assert!(self.current_clip.is_some());
+        self.current_clip = None;
+    }
+
+    fn take_clip(&mut self) -> Option<[i32; 4]> {
+        self.current_clip.take()
+    }
+    fn restore_clip(&mut self, clip: Option<[i32; 4]>) {
+        self.current_clip = clip;
+    }
+
+    fn finish(self) {
+        let frame = RecordedFrame {
+            clear_color: self.clear_color.unwrap_or(Color::BLACK),
+            draws: self.draws,
+        };
+        let mut targets = self.prerender.targets.borrow_mut();
+        // If wgpu times out, just skip this frame; the next one will probably make it.
+        if let Ok(output) = targets.swap_chain.get_next_texture() {
+            let commands = frame.encode(self.prerender, &targets, &output.view);
+            self.prerender.queue.submit(&[commands]);
+            // The frame is presented when it's dropped
+        }
+        *self.prerender.last_frame.borrow_mut() = Some(frame);
+    }
+}
+
+// Something that's been sent to the GPU already.
+pub struct Drawable {
+    // Shared with any frame that's still being recorded
+    vertex_buffer: Rc<wgpu::Buffer>,
+    index_buffer: Rc<wgpu::Buffer>,
+    num_indices: u32,
+}
+
+pub struct PrerenderInnards {
+    window: winit::window::Window,
+    surface: wgpu::Surface,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    targets: RefCell<RenderTargets>,
+    last_frame: RefCell<Option<RecordedFrame>>,
+
+    // TODO Prerender doesn't know what things are temporary and permanent. Could make the API more
+    // detailed.
+    pub total_bytes_uploaded: Cell<usize>,
+}
+
+impl PrerenderImpl for PrerenderInnards {
+    fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
+        let mut vertices: Vec<[f32; 6]> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        for (color, poly) in list {
+            let idx_offset = vertices.len();
+            let (pts, raw_indices) = poly.raw_for_rendering();
+            for pt in pts {
+                let style = color.style(*pt);
+                vertices.push([
+                    pt.x() as f32,
+                    pt.y() as f32,
+                    style[0],
+                    style[1],
+                    style[2],
+                    style[3],
+                ]);
+            }
+            for idx in raw_indices {
+                indices.push((idx_offset + *idx) as u32);
+            }
+        }
+
+        let vertex_bytes = unsafe { vertices.align_to::<u8>().1 };
+        let index_bytes = unsafe { indices.align_to::<u8>().1 };
+        let vertex_buffer = self
+            .device
+            .create_buffer_with_data(vertex_bytes, wgpu::BufferUsage::VERTEX);
+        let index_buffer = self
+            .device
+            .create_buffer_with_data(index_bytes, wgpu::BufferUsage::INDEX);
+
+        if permanent {
+            self.total_bytes_uploaded
+                .set(self.total_bytes_uploaded.get() + vertex_bytes.len() + index_bytes.len());
+        }
+
+        Drawable {
+            vertex_buffer: Rc::new(vertex_buffer),
+            index_buffer: Rc::new(index_buffer),
+            num_indices: indices.len() as u32,
+        }
+    }
+
+    fn actually_upload_instanced(
+        &self,
+        template: Vec<(FancyColor, &Polygon)>,
+        instances: Vec<Instance>,
+    ) -> Drawable {
+        // TODO Instance on the GPU, like the glium backend
+        let list = expand_instances(template, instances);
+        self.actually_upload(false, list.iter().map(|(c, p)| (c.clone(), p)).collect())
+    }
+
+    fn request_redraw(&self) {
+        self.window.request_redraw();
+    }
+
+    fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
+        self.window.set_cursor_icon(icon);
+    }
+
+    fn draw_new_frame(&self) -> GfxCtxInnards<'_> {
+        GfxCtxInnards {
+            prerender: self,
+            clear_color: None,
+            draws: Vec::new(),
+            current_clip: None,
+        }
+    }
+
+    fn window_resized(&self, width: f64, height: f64) {
+        let size = (width as u32, height as u32);
+        if size.0 == 0 || size.1 == 0 {
+            // Minimized. Keep the old swap chain; wgpu can't make an empty one.
+            return;
+        }
+        *self.targets.borrow_mut() = RenderTargets::new(&self.device, &self.surface, size);
+    }
+
+    fn get_inner_size(&self) -> (f64, f64) {
+        let size = self.window.inner_size();
+        (size.width.into(), size.height.into())
+    }
+
+    fn set_window_icon(&self, icon: winit::window::Icon) {
+        self.window.set_window_icon(Some(icon));
+    }
+
+    fn monitor_scale_factor(&self) -> f64 {
+        self.window.scale_factor()
+    }
+
+    // The swap chain's textures can only be drawn to, so draw the last frame again somewhere that
+    // can be copied back.
+    fn screencap(&self) -> Option<image::RgbaImage> {
+        let last_frame = self.last_frame.borrow();
+        let frame = last_frame.as_ref()?;
+        let targets = self.targets.borrow();
+        let (width, height) = targets.size;
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: extent(targets.size),
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SWAP_CHAIN_FORMAT,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+            label: None,
+        });
+        // Each row copied out has to be padded to a multiple of 256 bytes
+        let bytes_per_row = (4 * width + 255) / 256 * 256;
+        let buffer_size = (bytes_per_row * height) as wgpu::BufferAddress;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: buffer_size,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let draw = frame.encode(self, &targets, &texture.create_default_view());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                offset: 0,
+                bytes_per_row,
+                rows_per_image: height,
+            },
+            extent(targets.size),
+        );
+        self.queue.submit(&[draw, encoder.finish()]);
+
+        let mapping = buffer.map_read(0, buffer_size);
+        self.device.poll(wgpu::Maintain::Wait);
+        let mapping = futures::executor::block_on(mapping).ok()?;
+        let mut img = image::RgbaImage::new(width, height);
+        for (y, row) in mapping
+            .as_slice()
+            .chunks(bytes_per_row as usize)
+            .enumerate()
+        {
+            for x in 0..width {
+                let px = &row[(4 * x) as usize..];
+                // The swap chain format is BGRA
+                img.put_pixel(x, y as u32, image::Rgba([px[2], px[1], px[0], px[3]]));
+            }
+        }
+        Some(img)
+    }
+}
@@ -1,9 +1,11 @@
 use crate::assets::Assets;
 use crate::{hotkey, Key, ScreenDims, ScreenPt, ScreenRectangle, UpdateType, UserInput};
-use abstutil::Timer;
-use geom::{Bounds, Pt2D};
+use abstutil::{elapsed_seconds, Timer};
+use geom::{Bounds, Distance, Pt2D};
+use instant::Instant;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 // Click and release counts as a normal click, not a drag, if the distance between click and
 // release is less than this.
@@ -13,6 +15,16 @@ const PAN_SPEED: f64 = 15.0;
 
 const PANNING_THRESHOLD: f64 = 25.0;
 
+const MAX_ZOOM: f64 = 150.0;
+
+// After a drag, the map keeps sliding, losing half of its speed every this many seconds, no matter
+// the frame rate.
+const INERTIA_HALF_LIFE: f64 = 0.07;
+// In screen pixels per second
+const MIN_INERTIA_SPEED: f64 = 30.0;
+// If the mouse is held still for this many seconds before releasing a drag, the map doesn't slide.
+const STILL_RELEASE: f64 = 0.1;
+
 pub struct Canvas {
     // All of these f64's are in screen-space, so do NOT use Pt2D.
     // Public for saving/loading... should probably do better
@@ -28,6 +40,11 @@ pub struct Canvas {
     // Only for drags starting on the map. Only used to pan the map. (Last event, original)
     pub(crate) drag_canvas_from: Option<(ScreenPt, ScreenPt)>,
     pub(crate) drag_just_ended: bool,
+    // In screen pixels per second, and when the map last moved because of it (or the mouse moved
+    // while dragging)
+    pan_velocity: Option<((f64, f64), Instant)>,
+    // When glide_toward_map_pt was last called
+    last_glide: Option<Instant>,
 
     pub window_width: f64,
     pub window_height: f64,
@@ -38,7 +55,10 @@ pub struct Canvas {
     pub touchpad_to_move: bool,
     pub edge_auto_panning: bool,
     pub keys_to_pan: bool,
+    pub inertial_panning: bool,
     pub gui_scroll_speed: usize,
+    // Pressing the first key acts like pressing the second, everywhere
+    pub key_remap: BTreeMap<Key, Key>,
 
     // TODO Bit weird and hacky to mutate inside of draw() calls.
     pub(crate) covered_areas: RefCell<Vec<ScreenRectangle>>,
@@ -46,6 +66,8 @@ pub struct Canvas {
     // Kind of just ezgui state awkwardly stuck here...
     pub(crate) lctrl_held: bool,
     pub(crate) lshift_held: bool,
+    // Set by a text box with focus, so keys typed into it aren't remapped
+    pub(crate) typing: bool,
 }
 
 impl Canvas {
@@ -61,6 +83,8 @@ impl Canvas {
 
             drag_canvas_from: None,
             drag_just_ended: false,
+            pan_velocity: None,
+            last_glide: None,
 
             window_width: initial_width,
             window_height: initial_height,
@@ -70,12 +94,15 @@ impl Canvas {
             touchpad_to_move: false,
             edge_auto_panning: false,
             keys_to_pan: false,
+            inertial_panning: false,
             gui_scroll_speed: 5,
+            key_remap: BTreeMap::new(),
 
             covered_areas: RefCell::new(Vec::new()),
 
             lctrl_held: false,
             lshift_held: false,
+            typing: false,
         }
     }
 
@@ -85,7 +112,17 @@ impl Canvas {
             .min(percent_window * self.window_height / self.map_dims.1)
     }
 
+    pub fn max_zoom(&self) -> f64 {
+        MAX_ZOOM
+    }
+
     pub(crate) fn handle_event(&mut self, input: &mut UserInput) -> Option<UpdateType> {
+        let update = self.handle_movement(input);
+        self.constrain_to_map();
+        update
+    }
+
+    fn handle_movement(&mut self, input: &mut UserInput) -> Option<UpdateType> {
         // Can't start dragging or zooming on top of covered area
         if self.get_cursor_in_map_space().is_some() {
             if self.touchpad_to_move {
@@ -101,6 +138,7 @@ impl Canvas {
             } else {
                 if input.left_mouse_button_pressed() {
                     self.drag_canvas_from = Some((self.get_cursor(), self.get_cursor()));
+                    self.pan_velocity = None;
                 }
 
                 if let Some((_, scroll)) = input.get_mouse_scroll() {
@@ -133,9 +171,20 @@ impl Canvas {
         // If we start the drag on the map and move the mouse off the map, keep dragging.
         if let Some((click, orig)) = self.drag_canvas_from {
             let pt = self.get_cursor();
-            self.cam_x += click.x - pt.x;
-            self.cam_y += click.y - pt.y;
+            let (dx, dy) = (click.x - pt.x, click.y - pt.y);
+            self.cam_x += dx;
+            self.cam_y += dy;
             self.drag_canvas_from = Some((pt, orig));
+            if dx != 0.0 || dy != 0.0 {
+                let now = Instant::now();
+                // Events can arrive in bursts; don't divide by almost nothing.
+                let dt = self
+                    .pan_velocity
+                    .map(|(_, t)| elapsed_seconds(t))
+                    .unwrap_or(0.0)
+                    .max(1.0 / 60.0);
+                self.pan_velocity = Some(((dx / dt, dy / dt), now));
+            }
 
             if input.left_mouse_button_released() {
                 let (_, orig) = self.drag_canvas_from.take().unwrap();
@@ -143,9 +192,39 @@ impl Canvas {
                 if dist > DRAG_THRESHOLD {
                     self.drag_just_ended = true;
                 }
+                // Holding the mouse still and then letting go shouldn't fling the map.
+                if self
+                    .pan_velocity
+                    .map(|(_, t)| elapsed_seconds(t) > STILL_RELEASE)
+                    .unwrap_or(false)
+                {
+                    self.pan_velocity = None;
+                }
+                if self.inertial_panning && self.pan_velocity.is_some() {
+                    // Keep getting events while the map slides to a stop
+                    return Some(UpdateType::Game);
+                }
+                self.pan_velocity = None;
             }
         } else if self.drag_just_ended {
             self.drag_just_ended = false;
+            if self.pan_velocity.is_some() {
+                return Some(UpdateType::Game);
+            }
+        } else if let Some(((vx, vy), last)) = self.pan_velocity {
+            if input.nonblocking_is_update_event().is_some() {
+                let dt = elapsed_seconds(last);
+                self.cam_x += vx * dt;
+                self.cam_y += vy * dt;
+                let decay = 0.5_f64.powf(dt / INERTIA_HALF_LIFE);
+                let (vx, vy) = (vx * decay, vy * decay);
+                if vx.hypot(vy) < MIN_INERTIA_SPEED {
+                    self.pan_velocity = None;
+                    return None;
+                }
+                self.pan_velocity = Some(((vx, vy), Instant::now()));
+            }
+            return Some(UpdateType::Game);
         } else {
             let cursor_screen_pt = self.get_cursor().to_pt();
             let cursor_map_pt = self.screen_to_map(self.get_cursor());
@@ -178,7 +257,7 @@ impl Canvas {
         self.cam_zoom = 1.1_f64
             .powf(old_zoom.log(1.1) + delta)
             .max(self.min_zoom())
-            .min(150.0);
+            .min(self.max_zoom());
 
         // Make screen_to_map of the focus point still point to the same thing after
         // zooming.
@@ -186,6 +265,21 @@ impl Canvas {
         self.cam_y = ((self.cam_zoom / old_zoom) * (focus.1 + self.cam_y)) - focus.1;
     }
 
+    // Don't let the camera wander off into the void; the center of the screen has to stay on the
+    // map.
+    fn constrain_to_map(&mut self) {
+        if self.map_dims.0 == 0.0 || self.map_dims.1 == 0.0 {
+            return;
+        }
+        let center = self.center_to_map_pt();
+        let x = center.x().max(0.0).min(self.map_dims.0);
+        let y = center.y().max(0.0).min(self.map_dims.1);
+        if x != center.x() || y != center.y() {
+            self.center_on_map_pt(Pt2D::new(x, y));
+            self.pan_velocity = None;
+        }
+    }
+
     pub(crate) fn start_drawing(&self) {
         self.covered_areas.borrow_mut().clear();
     }
@@ -244,6 +338,23 @@ impl Canvas {
         self.cam_y = (pt.y() * self.cam_zoom) - (self.window_height / 2.0);
     }
 
+    // Only move part of the way to centering on this point. Doing this every frame follows
+    // something moving smoothly, instead of jumping. Half of the remaining distance is covered
+    // every half_life seconds, however often this is called.
+    pub fn glide_toward_map_pt(&mut self, pt: Pt2D, half_life: f64) {
+        // The first time, just jump there
+        let pct = self
+            .last_glide
+            .map(|t| 1.0 - 0.5_f64.powf(elapsed_seconds(t) / half_life))
+            .unwrap_or(1.0);
+        self.last_glide = Some(Instant::now());
+        let current = self.center_to_map_pt();
+        self.center_on_map_pt(Pt2D::new(
+            current.x() + pct * (pt.x() - current.x()),
+            current.y() + pct * (pt.y() - current.y()),
+        ));
+    }
+
     pub fn map_to_screen(&self, pt: Pt2D) -> ScreenPt {
         ScreenPt::new(
             (pt.x() * self.cam_zoom) - self.cam_x,
@@ -251,6 +362,16 @@ impl Canvas {
         )
     }
 
+    // How much of the map a distance on the screen covers at the current zoom
+    pub fn screen_to_map_dist(&self, pixels: f64) -> Distance {
+        Distance::meters(pixels / self.cam_zoom)
+    }
+
+    // How many screen pixels a distance on the map covers at the current zoom
+    pub fn map_to_screen_dist(&self, dist: Distance) -> f64 {
+        dist.inner_meters() * self.cam_zoom
+    }
+
     //the inner bound tells us whether auto-panning should or should not take place
     fn get_inner_bounds(&self) -> Bounds {
         let mut b = Bounds::new();
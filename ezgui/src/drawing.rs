@@ -1,10 +1,10 @@
 use crate::assets::Assets;
-use crate::backend::{GfxCtxInnards, PrerenderInnards};
+use crate::backend::{GfxCtxImpl, GfxCtxInnards, PrerenderImpl, PrerenderInnards};
 use crate::{
     Canvas, Color, Drawable, FancyColor, GeomBatch, ScreenDims, ScreenPt, ScreenRectangle, Style,
     Text,
 };
-use geom::{ArrowCap, Bounds, Circle, Distance, Line, Polygon, Pt2D};
+use geom::{Angle, ArrowCap, Bounds, Circle, Distance, Line, Polygon, Pt2D};
 use std::cell::Cell;
 
 // Lower is more on top
@@ -36,6 +36,17 @@ impl Uniforms {
     }
 }
 
+/// One copy of a template drawn with `upload_instanced`. The template is scaled, then rotated
+/// around its origin, then moved to `pos`. Its colors are multiplied by `color`, so a white
+/// template takes on the color of each instance.
+#[derive(Clone, Copy)]
+pub struct Instance {
+    pub pos: Pt2D,
+    pub angle: Angle,
+    pub scale: f64,
+    pub color: Color,
+}
+
 pub struct GfxCtx<'a> {
     pub(crate) inner: GfxCtxInnards<'a>,
     uniforms: Uniforms,
@@ -249,6 +260,10 @@ impl<'a> GfxCtx<'a> {
         self.prerender.upload(batch)
     }
 
+    pub fn upload_instanced(&mut self, template: GeomBatch, instances: Vec<Instance>) -> Drawable {
+        self.prerender.upload_instanced(template, instances)
+    }
+
     // Delegation to assets
     pub fn default_line_height(&self) -> f64 {
         *self.prerender.assets.default_line_height.borrow()
@@ -273,6 +288,14 @@ impl Prerender {
         self.actually_upload(true, borrows)
     }
 
+    /// Draws many copies of the same template, like agents, in one draw call. Backends that
+    /// can't instance on the GPU just expand the copies here.
+    pub fn upload_instanced(&self, template: GeomBatch, instances: Vec<Instance>) -> Drawable {
+        self.num_uploads.set(self.num_uploads.get() + 1);
+        let borrows = template.list.iter().map(|(c, p)| (c.clone(), p)).collect();
+        self.inner.actually_upload_instanced(borrows, instances)
+    }
+
     pub fn get_total_bytes_uploaded(&self) -> usize {
         self.inner.total_bytes_uploaded.get()
     }
@@ -291,3 +314,32 @@ impl Prerender {
         self.inner.request_redraw()
     }
 }
+
+// For backends without GPU instancing. Gradients aren't multiplied by the instance color.
+pub(crate) fn expand_instances(
+    template: Vec<(FancyColor, &Polygon)>,
+    instances: Vec<Instance>,
+) -> Vec<(FancyColor, Polygon)> {
+    let origin = Pt2D::new(0.0, 0.0);
+    let mut list = Vec::new();
+    for instance in instances {
+        for (color, poly) in &template {
+            let color = match color {
+                FancyColor::RGBA(c) => FancyColor::RGBA(Color::rgba_f(
+                    c.r * instance.color.r,
+                    c.g * instance.color.g,
+                    c.b * instance.color.b,
+                    c.a * instance.color.a,
+                )),
+                FancyColor::LinearGradient(_) => color.clone(),
+            };
+            list.push((
+                color,
+                poly.scale(instance.scale)
+                    .rotate_around(instance.angle, origin)
+                    .translate(instance.pos.x(), instance.pos.y()),
+            ));
+        }
+    }
+    list
+}
@@ -1,5 +1,6 @@
 use crate::ScreenPt;
 use geom::Duration;
+use serde::{Deserialize, Serialize};
 use winit::event::{
     ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
 };
@@ -24,6 +25,8 @@ pub enum Event {
     WindowGainedCursor,
     MouseWheelScroll(f64, f64),
     WindowResized(f64, f64),
+    // The path isn't here to keep Event Copy; get it from UserInput::dropped_file.
+    FileDropped,
 }
 
 impl Event {
@@ -95,7 +98,7 @@ fn scroll_wheel_multiplier() -> f64 {
     1.0
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub enum Key {
     // Case is unspecified.
     // TODO Would be cool to represent A and UpperA, but then release semantics get weird... hold
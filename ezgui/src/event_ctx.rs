@@ -1,3 +1,4 @@
+use crate::backend::{GfxCtxImpl, PrerenderImpl};
 use crate::{
     svg, text, Canvas, Color, Drawable, Event, GeomBatch, GfxCtx, Line, Prerender, ScreenPt, Style,
     Text, UserInput,
@@ -12,12 +13,19 @@ pub enum UpdateType {
     InputOnly,
     Game,
     Pan,
+    // Like InputOnly, but also wake up once after this long, for checking on something happening
+    // in the background without redrawing every frame
+    Poll(std::time::Duration),
     ScreenCaptureEverything {
         dir: String,
         zoom: f64,
         max_x: f64,
         max_y: f64,
     },
+    // Just the current view, without moving the camera
+    ScreenCaptureCurrentShot {
+        filename: String,
+    },
 }
 
 pub struct EventCtx<'a> {
@@ -54,6 +54,12 @@ impl GeomBatch {
         self.list
     }
 
+    /// Draws the batch into an image on the CPU, without needing a window. The bounds are stretched
+    /// to cover the whole image.
+    pub fn rasterize(&self, bounds: &Bounds, img: &mut image::RgbaImage) {
+        crate::tools::rasterize::rasterize(self, bounds, img);
+    }
+
     /// Draws the batch, consuming it. Only use this for drawing things once.
     pub fn draw(self, g: &mut GfxCtx) {
         let refs = self
@@ -96,10 +102,19 @@ impl GeomBatch {
     }
 
     /// True when the batch is empty.
-    pub(crate) fn is_empty(&self) -> bool {
+    pub fn is_empty(&self) -> bool {
         self.list.is_empty()
     }
 
+    /// Returns the bounds of all geometry in the batch, without autocropping.
+    pub fn get_bounds(&self) -> Bounds {
+        let mut bounds = Bounds::new();
+        for (_, poly) in &self.list {
+            bounds.union(poly.get_bounds());
+        }
+        bounds
+    }
+
     /// Returns the width and height of all geometry contained in the batch.
     pub fn get_dims(&self) -> ScreenDims {
         // TODO Maybe warn about this happening and avoid in the first place? Sometimes we wind up
@@ -12,6 +12,7 @@ pub struct UserInput {
     reserved_keys: HashMap<Key, String>,
 
     lctrl_held: bool,
+    pub(crate) dropped_file: Option<String>,
 }
 
 impl UserInput {
@@ -22,6 +23,7 @@ impl UserInput {
             important_actions: Vec::new(),
             reserved_keys: HashMap::new(),
             lctrl_held: canvas.lctrl_held,
+            dropped_file: None,
         }
     }
 
@@ -114,6 +116,15 @@ impl UserInput {
         self.event == Event::WindowLostCursor
     }
 
+    // The path of a file dragged onto the window
+    pub fn dropped_file(&mut self) -> Option<String> {
+        if self.event_consumed || self.event != Event::FileDropped {
+            return None;
+        }
+        self.consume_event();
+        self.dropped_file.clone()
+    }
+
     pub fn get_moved_mouse(&self) -> Option<ScreenPt> {
         if let Event::MouseMovedTo(pt) = self.event {
             return Some(pt);
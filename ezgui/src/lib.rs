@@ -22,12 +22,15 @@
 //! * [`TexBox`] - single line text entry
 
 mod assets;
+mod backend;
 #[cfg(feature = "glium-backend")]
 mod backend_glium;
 #[cfg(feature = "glow-backend")]
 mod backend_glow;
 #[cfg(feature = "wasm-backend")]
 mod backend_wasm;
+#[cfg(feature = "wgpu-backend")]
+mod backend_wgpu;
 mod canvas;
 mod color;
 mod drawing;
@@ -47,16 +50,18 @@ mod widgets;
 pub use crate::backend::Drawable;
 pub use crate::canvas::{Canvas, HorizontalAlignment, VerticalAlignment};
 pub use crate::color::{Color, FancyColor, LinearGradient};
-pub use crate::drawing::{GfxCtx, Prerender};
+pub use crate::drawing::{GfxCtx, Instance, Prerender};
 pub use crate::event::{hotkey, hotkeys, lctrl, Event, Key, MultiKey};
 pub use crate::event_ctx::{EventCtx, UpdateType};
 pub use crate::geom::{GeomBatch, RewriteColor};
 pub use crate::input::UserInput;
 pub use crate::managed::{Composite, Widget};
-pub use crate::runner::{run, Settings, GUI};
+pub use crate::runner::{run, Settings, GUI, UPDATE_FREQUENCY};
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
 pub use crate::style::Style;
 pub use crate::text::{Line, Text, TextExt, TextSpan};
+pub use crate::tools::clipboard::{get_clipboard, set_clipboard};
+pub use crate::tools::notifications::Notifications;
 pub use crate::tools::warper::Warper;
 pub use crate::tools::wizard::{Choice, Wizard, WrappedWizard};
 pub use crate::widgets::autocomplete::Autocomplete;
@@ -82,14 +87,3 @@ pub(crate) enum InputResult<T: Clone> {
     StillActive,
     Done(String, T),
 }
-
-mod backend {
-    #[cfg(feature = "glium-backend")]
-    pub use crate::backend_glium::*;
-
-    #[cfg(feature = "glow-backend")]
-    pub use crate::backend_glow::*;
-
-    #[cfg(feature = "wasm-backend")]
-    pub use crate::backend_wasm::*;
-}
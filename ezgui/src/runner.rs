@@ -1,5 +1,6 @@
 use crate::assets::Assets;
-use crate::tools::screenshot::screenshot_everything;
+use crate::backend::{GfxCtxImpl, PrerenderImpl};
+use crate::tools::screenshot::{screenshot_current, screenshot_everything};
 use crate::{text, Canvas, Event, EventCtx, GfxCtx, Key, Prerender, Style, UpdateType, UserInput};
 use geom::Duration;
 use image::{GenericImageView, Pixel};
@@ -8,7 +9,9 @@ use std::cell::Cell;
 use std::panic;
 use winit::window::Icon;
 
-const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
+// How often Event::Update fires while something is animating. Anything doing work on every update,
+// like stepping a simulation, should finish within this to keep drawing smooth.
+pub const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
 
 pub trait GUI {
     fn event(&mut self, ctx: &mut EventCtx);
@@ -23,11 +26,31 @@ pub(crate) struct State<G: GUI> {
     pub(crate) gui: G,
     pub(crate) canvas: Canvas,
     style: Style,
+    // Set right before sending Event::FileDropped
+    dropped_file: Option<String>,
 }
 
 impl<G: GUI> State<G> {
     // The bool indicates if the input was actually used.
     fn event(&mut self, mut ev: Event, prerender: &Prerender) -> (Vec<UpdateType>, bool) {
+        match ev {
+            Event::KeyPress(key) if !self.canvas.typing => {
+                if let Some(to) = self.canvas.key_remap.get(&key) {
+                    ev = Event::KeyPress(*to);
+                }
+            }
+            Event::KeyRelease(key) if !self.canvas.typing => {
+                if let Some(to) = self.canvas.key_remap.get(&key) {
+                    ev = Event::KeyRelease(*to);
+                }
+            }
+            _ => {}
+        }
+        // A text box with focus sets this again while handling the event. Update events don't
+        // always reach every widget, so they leave it alone.
+        if !matches!(ev, Event::Update(_)) {
+            self.canvas.typing = false;
+        }
         if let Event::MouseWheelScroll(dx, dy) = ev {
             if self.canvas.invert_scroll {
                 ev = Event::MouseWheelScroll(-dx, -dy);
@@ -55,7 +78,10 @@ impl<G: GUI> State<G> {
 
         // It's impossible / very unlikely we'll grab the cursor in map space before the very first
         // start_drawing call.
-        let input = UserInput::new(ev, &self.canvas);
+        let mut input = UserInput::new(ev, &self.canvas);
+        if ev == Event::FileDropped {
+            input.dropped_file = self.dropped_file.take();
+        }
 
         // Update some ezgui state that's stashed in Canvas for sad reasons.
         {
@@ -240,7 +266,12 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
         updates_requested: vec![],
     });
 
-    let mut state = State { canvas, gui, style };
+    let mut state = State {
+        canvas,
+        gui,
+        style,
+        dropped_file: None,
+    };
 
     if settings.profiling_enabled {
         abstutil::start_profiler();
@@ -251,6 +282,8 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
 
     let mut running = true;
     let mut last_update = Instant::now();
+    // When to send an update while idle, if anything asked for one
+    let mut poll_at: Option<Instant> = None;
     event_loop.run(move |event, _, control_flow| {
         if dump_raw_events {
             println!("Event: {:?}", event);
@@ -269,6 +302,13 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
                 state.gui.before_quit(&state.canvas);
                 std::process::exit(0);
             }
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::DroppedFile(path),
+                ..
+            } => {
+                state.dropped_file = Some(path.display().to_string());
+                Event::FileDropped
+            }
             winit::event::Event::WindowEvent { event, .. } => {
                 if let Some(ev) = Event::from_winit_event(event) {
                     ev
@@ -286,6 +326,9 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
                 // We might've switched to InputOnly after the WaitUntil was requested.
                 if running {
                     Event::Update(Duration::realtime_elapsed(last_update))
+                } else if poll_at.map(|t| Instant::now() >= t).unwrap_or(false) {
+                    poll_at = None;
+                    Event::Update(Duration::realtime_elapsed(last_update))
                 } else {
                     return;
                 }
@@ -312,11 +355,16 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
         if updates.is_empty() {
             updates.push(UpdateType::InputOnly);
         }
+        let wants_game = updates.contains(&UpdateType::Game);
         for update in updates {
             match update {
                 UpdateType::InputOnly => {
                     running = false;
-                    *control_flow = winit::event_loop::ControlFlow::Wait;
+                    // Don't forget about a pending poll
+                    *control_flow = match poll_at {
+                        Some(at) => winit::event_loop::ControlFlow::WaitUntil(at),
+                        None => winit::event_loop::ControlFlow::Wait,
+                    };
                 }
                 UpdateType::Game => {
                     // If we just unpaused, then don't act as if lots of time has passed.
@@ -329,6 +377,14 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
 
                     running = true;
                 }
+                UpdateType::Poll(dt) => {
+                    if !wants_game {
+                        running = false;
+                        let at = Instant::now() + dt;
+                        poll_at = Some(at);
+                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(at);
+                    }
+                }
                 UpdateType::Pan => {}
                 UpdateType::ScreenCaptureEverything {
                     dir,
@@ -338,6 +394,9 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
                 } => {
                     screenshot_everything(&mut state, &dir, &prerender, zoom, max_x, max_y);
                 }
+                UpdateType::ScreenCaptureCurrentShot { filename } => {
+                    screenshot_current(&mut state, &filename, &prerender);
+                }
             }
         }
     });
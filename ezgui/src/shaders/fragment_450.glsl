@@ -0,0 +1,8 @@
+#version 450
+
+layout(location = 0) in vec4 pass_style;
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = pass_style;
+}
@@ -0,0 +1,32 @@
+#version 140
+
+// (x offset, y offset, zoom)
+uniform vec3 transform;
+// (window width, window height, z value)
+uniform vec3 window;
+
+// The template
+in vec2 position;
+in vec4 style;
+// Per instance: (x, y, rotation in radians, scale)
+in vec4 instance;
+in vec4 instance_color;
+out vec4 pass_style;
+
+void main() {
+    pass_style = style * instance_color;
+
+    // Scale, then rotate around the origin, then move into place
+    float c = cos(instance[2]);
+    float s = sin(instance[2]);
+    float map_x = instance[3] * (position[0] * c - position[1] * s) + instance[0];
+    float map_y = instance[3] * (position[1] * c + position[0] * s) + instance[1];
+
+    // The rest is the same as vertex_140.glsl
+    float screen_x = (map_x * transform[2]) - transform[0];
+    float screen_y = (map_y * transform[2]) - transform[1];
+    float x = (screen_x / window[0] * 2.0) - 1.0;
+    float y = (screen_y / window[1] * 2.0) - 1.0;
+
+    gl_Position = vec4(x, -y, window[2], 1.0);
+}
@@ -2,9 +2,12 @@ use crate::assets::Assets;
 use crate::{
     svg, Color, EventCtx, GeomBatch, GfxCtx, JustDraw, MultiKey, Prerender, ScreenDims, Widget,
 };
-use geom::Polygon;
+use geom::{Polygon, Pt2D};
+use lyon::math::Point;
+use lyon::path::PathEvent;
+use lyon::tessellation;
+use lyon::tessellation::geometry_builder::{simple_builder, VertexBuffers};
 use std::collections::hash_map::DefaultHasher;
-use std::fmt::Write;
 use std::hash::Hasher;
 
 // Same as body()
@@ -391,58 +394,188 @@ impl Text {
     }
 }
 
-fn render_line(spans: Vec<TextSpan>, tolerance: f32, assets: &Assets) -> GeomBatch {
-    // TODO This assumes size and font don't change mid-line. We might be able to support that now,
-    // actually.
-    // https://www.oreilly.com/library/view/svg-text-layout/9781491933817/ch04.html
-
-    // Just set a sufficiently large view box
-    let mut svg = r##"<svg width="9999" height="9999" viewBox="0 0 9999 9999" xmlns="http://www.w3.org/2000/svg">"##.to_string();
-
-    write!(
-        &mut svg,
-        r##"<text x="0" y="0" font-size="{}" font-family="{}" {}>"##,
-        spans[0].size,
-        spans[0].font.family(),
-        match spans[0].font {
-            Font::OverpassBold => "font-weight=\"bold\"",
-            Font::OverpassSemiBold => "font-weight=\"600\"",
-            _ => "",
-        }
-    )
-    .unwrap();
+// Drawn in place of characters the font doesn't have. Not every font has the replacement
+// character, so fall back to something every font has.
+const MISSING_GLYPHS: [char; 2] = ['\u{FFFD}', '?'];
 
-    let mut contents = String::new();
+// Lays out glyphs from the cache, one after another along the baseline at y = 0, applying kerning
+// between pairs of characters in the same span.
+// TODO No shaping, so this is only right for simple scripts.
+fn render_line(spans: Vec<TextSpan>, tolerance: f32, assets: &Assets) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let mut x = 0.0;
     for span in spans {
-        write!(
-            &mut contents,
-            r##"<tspan fill="{}" {}>{}</tspan>"##,
-            // TODO Doesn't support alpha
-            span.fg_color.to_hex(),
-            if span.underlined {
-                "text-decoration=\"underline\""
-            } else {
-                ""
-            },
-            htmlescape::encode_minimal(&span.text)
-        )
-        .unwrap();
+        let start = x;
+        let mut prev: Option<char> = None;
+        for c in span.text.chars() {
+            let found = std::iter::once(c)
+                .chain(MISSING_GLYPHS.iter().cloned())
+                .filter_map(|c| {
+                    assets
+                        .glyph(span.font, span.size, c, tolerance)
+                        .map(|glyph| (c, glyph))
+                })
+                .next();
+            if let Some((c, glyph)) = found {
+                if let Some(left) = prev {
+                    x += assets.kerning(span.font, span.size, left, c);
+                }
+                if let Some(ref polygon) = glyph.polygon {
+                    batch.push(span.fg_color, polygon.translate(x, 0.0));
+                }
+                x += glyph.advance;
+                prev = Some(c);
+            }
+        }
+        if span.underlined && x > start {
+            let thickness = (span.size as f64) * *assets.scale_factor.borrow() / 15.0;
+            batch.push(
+                span.fg_color,
+                Polygon::rectangle(x - start, thickness).translate(start, thickness),
+            );
+        }
     }
-    write!(&mut svg, "{}</text></svg>", contents).unwrap();
+    batch
+}
+
+// One glyph, tessellated at a certain size
+#[derive(Clone)]
+pub(crate) struct Glyph {
+    // Relative to where the glyph starts on the baseline. None for whitespace.
+    pub polygon: Option<Polygon>,
+    // How far to move along the baseline for the next glyph
+    pub advance: f64,
+}
 
-    let svg_tree = match usvg::Tree::from_str(&svg, &assets.text_opts) {
-        Ok(t) => t,
-        Err(err) => panic!("render_line({}): {}", contents, err),
+// The scale is the font size, after the scale factor is applied. Returns None if the font doesn't
+// have this glyph.
+pub(crate) fn make_glyph(
+    data: &[u8],
+    face_index: u32,
+    c: char,
+    scale: f64,
+    tolerance: f32,
+) -> Option<Glyph> {
+    let font = ttf_parser::Font::from_data(data, face_index)?;
+    let id = font.glyph_index(c)?;
+    let scale = scale / (font.units_per_em()? as f64);
+    let mut outline = Outline {
+        events: Vec::new(),
+        scale: scale as f32,
+        first: Point::new(0.0, 0.0),
+        prev: Point::new(0.0, 0.0),
+        open: false,
     };
-    let mut batch = GeomBatch::new();
-    match crate::svg::add_svg_inner(
-        &mut batch,
-        svg_tree,
-        tolerance,
-        *assets.scale_factor.borrow(),
-    ) {
-        Ok(_) => batch,
-        Err(err) => panic!("render_line({}): {}", contents, err),
+    let polygon = if font.outline_glyph(id, &mut outline).is_some() {
+        outline.end(false);
+        let mut mesh: VertexBuffers<_, u16> = VertexBuffers::new();
+        tessellation::FillTessellator::new()
+            .tessellate(
+                outline.events.into_iter(),
+                &tessellation::FillOptions::tolerance(tolerance),
+                &mut simple_builder(&mut mesh),
+            )
+            .ok()
+            .map(|_| {
+                Polygon::precomputed(
+                    mesh.vertices
+                        .into_iter()
+                        .map(|v| Pt2D::new(f64::from(v.x), f64::from(v.y)))
+                        .collect(),
+                    mesh.indices.into_iter().map(|idx| idx as usize).collect(),
+                )
+            })
+    } else {
+        None
+    };
+    Some(Glyph {
+        polygon,
+        advance: scale * (font.glyph_hor_advance(id).unwrap_or(0) as f64),
+    })
+}
+
+// How much closer together (if negative) to draw a pair of characters. None if the font doesn't
+// have either of them or doesn't kern them.
+pub(crate) fn kerning(
+    data: &[u8],
+    face_index: u32,
+    left: char,
+    right: char,
+    scale: f64,
+) -> Option<f64> {
+    let font = ttf_parser::Font::from_data(data, face_index)?;
+    let kerning = font.glyphs_kerning(font.glyph_index(left)?, font.glyph_index(right)?)?;
+    Some(scale * (kerning as f64) / (font.units_per_em()? as f64))
+}
+
+// Turns a glyph's outline into something lyon can tessellate, already scaled from font units.
+struct Outline {
+    events: Vec<PathEvent>,
+    scale: f32,
+    first: Point,
+    prev: Point,
+    open: bool,
+}
+
+impl Outline {
+    // Fonts have Y pointing up
+    fn pt(&self, x: f32, y: f32) -> Point {
+        Point::new(self.scale * x, -self.scale * y)
+    }
+
+    fn end(&mut self, close: bool) {
+        if self.open {
+            self.open = false;
+            self.events.push(PathEvent::End {
+                last: self.prev,
+                first: self.first,
+                close,
+            });
+        }
+    }
+}
+
+impl ttf_parser::OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.end(false);
+        self.first = self.pt(x, y);
+        self.prev = self.first;
+        self.open = true;
+        self.events.push(PathEvent::Begin { at: self.first });
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.pt(x, y);
+        self.events.push(PathEvent::Line {
+            from: self.prev,
+            to,
+        });
+        self.prev = to;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let to = self.pt(x, y);
+        self.events.push(PathEvent::Quadratic {
+            from: self.prev,
+            ctrl: self.pt(x1, y1),
+            to,
+        });
+        self.prev = to;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let to = self.pt(x, y);
+        self.events.push(PathEvent::Cubic {
+            from: self.prev,
+            ctrl1: self.pt(x1, y1),
+            ctrl2: self.pt(x2, y2),
+            to,
+        });
+        self.prev = to;
+    }
+
+    fn close(&mut self) {
+        self.end(true);
     }
 }
 
@@ -0,0 +1,36 @@
+// The system clipboard. Without the clipboard feature (like on the web), copying does nothing and
+// there's never anything to paste.
+
+pub fn set_clipboard(x: String) -> bool {
+    #[cfg(feature = "clipboard")]
+    {
+        use clipboard::{ClipboardContext, ClipboardProvider};
+
+        let result: Result<(), Box<dyn std::error::Error>> =
+            ClipboardProvider::new().and_then(|mut cb: ClipboardContext| cb.set_contents(x));
+        if let Err(err) = result {
+            println!("Couldn't copy to the clipboard: {}", err);
+            return false;
+        }
+        true
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        let _ = x;
+        false
+    }
+}
+
+pub fn get_clipboard() -> Option<String> {
+    #[cfg(feature = "clipboard")]
+    {
+        use clipboard::{ClipboardContext, ClipboardProvider};
+
+        let mut cb: ClipboardContext = ClipboardProvider::new().ok()?;
+        cb.get_contents().ok()
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        None
+    }
+}
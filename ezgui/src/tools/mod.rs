@@ -1,3 +1,6 @@
+pub mod clipboard;
+pub mod notifications;
+pub mod rasterize;
 pub mod screenshot;
 pub mod warper;
 pub mod wizard;
@@ -0,0 +1,117 @@
+use crate::{
+    Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Outcome, Text, UpdateType,
+    VerticalAlignment, Widget,
+};
+use instant::Instant;
+
+// How long a toast stays on screen
+const TOAST_SECONDS: f64 = 5.0;
+
+// Messages drawn on top of everything else that don't stop the caller from handling events.
+// Toasts disappear by themselves. Questions stay until they're answered; each carries some data T
+// that's handed back with the answer, so the caller knows what was being asked. Only the oldest
+// question is shown at a time.
+pub struct Notifications<T> {
+    toasts: Vec<(Instant, Text)>,
+    questions: Vec<(Text, T)>,
+    composite: Option<Composite>,
+}
+
+impl<T> Notifications<T> {
+    pub fn new() -> Notifications<T> {
+        Notifications {
+            toasts: Vec::new(),
+            questions: Vec::new(),
+            composite: None,
+        }
+    }
+
+    // If the same message is already showing, it just stays up longer.
+    pub fn toast(&mut self, ctx: &mut EventCtx, txt: Text) {
+        let key = format!("{:?}", txt);
+        self.toasts.retain(|(_, t)| format!("{:?}", t) != key);
+        self.toasts.push((Instant::now(), txt));
+        self.rebuild(ctx);
+    }
+
+    pub fn confirm(&mut self, ctx: &mut EventCtx, question: Text, data: T) {
+        self.questions.push((question, data));
+        self.rebuild(ctx);
+    }
+
+    // Returns the data for a question once it's answered, along with true for yes. This doesn't
+    // consume the update event, so callers should keep handling events normally afterwards.
+    pub fn event(&mut self, ctx: &mut EventCtx) -> Option<(T, bool)> {
+        if self.composite.is_none() {
+            return None;
+        }
+
+        let before = self.toasts.len();
+        self.toasts
+            .retain(|(t, _)| abstutil::elapsed_seconds(*t) < TOAST_SECONDS);
+        if self.toasts.len() != before {
+            self.rebuild(ctx);
+        }
+        if !self.toasts.is_empty() {
+            // Keep waking up to notice when the toasts expire
+            ctx.request_update(UpdateType::Game);
+        }
+
+        let answer = match self.composite.as_mut().and_then(|c| c.event(ctx)) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "yes" => true,
+                "no" => false,
+                _ => unreachable!(),
+            },
+            None => {
+                return None;
+            }
+        };
+        let (_, data) = self.questions.remove(0);
+        self.rebuild(ctx);
+        Some((data, answer))
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if let Some(ref c) = self.composite {
+            c.draw(g);
+        }
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx) {
+        let mut col = Vec::new();
+        if let Some((question, _)) = self.questions.get(0) {
+            col.push(
+                Widget::col(vec![
+                    question.clone().draw(ctx),
+                    Widget::row(vec![
+                        Btn::text_bg2("yes").build_def(ctx, None),
+                        Btn::text_bg2("no").build_def(ctx, None),
+                    ]),
+                ])
+                .padding(16)
+                .bg(ctx.style.panel_bg)
+                .margin_below(8),
+            );
+        }
+        for (_, txt) in &self.toasts {
+            col.push(
+                txt.clone()
+                    .draw(ctx)
+                    .padding(16)
+                    .bg(ctx.style.panel_bg)
+                    .margin_below(8),
+            );
+        }
+
+        self.composite = if col.is_empty() {
+            None
+        } else {
+            Some(
+                Composite::new(Widget::col(col))
+                    .aligned(HorizontalAlignment::Center, VerticalAlignment::Percent(0.1))
+                    .build_custom(ctx),
+            )
+        };
+    }
+}
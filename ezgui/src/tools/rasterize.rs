@@ -0,0 +1,130 @@
+use crate::GeomBatch;
+use geom::{Bounds, Pt2D};
+
+// Fills triangles on the CPU, so tools can draw to images without a window or GPU. There's no
+// anti-aliasing; a pixel is covered when its center is inside a triangle.
+pub(crate) fn rasterize(batch: &GeomBatch, bounds: &Bounds, img: &mut image::RgbaImage) {
+    if bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+        return;
+    }
+    let scale_x = (img.width() as f64) / bounds.width();
+    let scale_y = (img.height() as f64) / bounds.height();
+
+    for (color, polygon) in &batch.list {
+        let (pts, indices) = polygon.raw_for_rendering();
+        let pixels: Vec<(f64, f64)> = pts
+            .iter()
+            .map(|pt| {
+                (
+                    (pt.x() - bounds.min_x) * scale_x,
+                    (pt.y() - bounds.min_y) * scale_y,
+                )
+            })
+            .collect();
+        for tri in indices.chunks(3) {
+            if tri.len() != 3 {
+                continue;
+            }
+            fill_triangle(
+                img,
+                [pixels[tri[0]], pixels[tri[1]], pixels[tri[2]]],
+                |x, y| {
+                    color.style(Pt2D::new(
+                        bounds.min_x + x / scale_x,
+                        bounds.min_y + y / scale_y,
+                    ))
+                },
+            );
+        }
+    }
+}
+
+fn fill_triangle<F: Fn(f64, f64) -> [f32; 4]>(
+    img: &mut image::RgbaImage,
+    [a, b, c]: [(f64, f64); 3],
+    color: F,
+) {
+    if edge(a, b, c) == 0.0 {
+        return;
+    }
+    let (width, height) = (img.width() as f64, img.height() as f64);
+    let min_x = a.0.min(b.0).min(c.0).floor().max(0.0).min(width) as u32;
+    let max_x = a.0.max(b.0).max(c.0).ceil().max(0.0).min(width) as u32;
+    let min_y = a.1.min(b.1).min(c.1).floor().max(0.0).min(height) as u32;
+    let max_y = a.1.max(b.1).max(c.1).ceil().max(0.0).min(height) as u32;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let pt = ((x as f64) + 0.5, (y as f64) + 0.5);
+            let w1 = edge(b, c, pt);
+            let w2 = edge(c, a, pt);
+            let w3 = edge(a, b, pt);
+            // Triangles can be wound either way
+            if (w1 >= 0.0 && w2 >= 0.0 && w3 >= 0.0) || (w1 <= 0.0 && w2 <= 0.0 && w3 <= 0.0) {
+                blend(img.get_pixel_mut(x, y), color(pt.0, pt.1));
+            }
+        }
+    }
+}
+
+// Which side of the line from a to b the point is on
+fn edge(a: (f64, f64), b: (f64, f64), pt: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (pt.1 - a.1) - (b.1 - a.1) * (pt.0 - a.0)
+}
+
+// Draws the color over whatever's already there, like the GPU backends do
+fn blend(dst: &mut image::Rgba<u8>, [r, g, b, a]: [f32; 4]) {
+    let a = a.max(0.0).min(1.0);
+    let dst_a = (dst[3] as f32) / 255.0;
+    let out_a = a + dst_a * (1.0 - a);
+    if out_a == 0.0 {
+        return;
+    }
+    for (i, src) in [r, g, b].iter().enumerate() {
+        let dst_c = (dst[i] as f32) / 255.0;
+        let out = (src * a + dst_c * dst_a * (1.0 - a)) / out_a;
+        dst[i] = (out.max(0.0).min(1.0) * 255.0).round() as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+    use geom::Polygon;
+
+    #[test]
+    fn fills_covered_pixels() {
+        let mut batch = GeomBatch::new();
+        batch.push(Color::RED, Polygon::rectangle(5.0, 10.0));
+        batch.push(
+            Color::BLUE.alpha(0.5),
+            Polygon::rectangle(10.0, 5.0).translate(0.0, 5.0),
+        );
+        let mut bounds = Bounds::new();
+        bounds.update(Pt2D::new(0.0, 0.0));
+        bounds.update(Pt2D::new(10.0, 10.0));
+        let mut img = image::RgbaImage::new(20, 20);
+        rasterize(&batch, &bounds, &mut img);
+
+        // Only red
+        assert_eq!(img.get_pixel(2, 2).0, [255, 0, 0, 255]);
+        // Nothing
+        assert_eq!(img.get_pixel(18, 2).0, [0, 0, 0, 0]);
+        // Half blue over red
+        assert_eq!(img.get_pixel(2, 18).0, [128, 0, 128, 255]);
+        // Half blue over nothing
+        assert_eq!(img.get_pixel(18, 18).0, [0, 0, 255, 128]);
+
+        // Stays within the image when the geometry doesn't
+        let mut batch = GeomBatch::new();
+        batch.push(
+            Color::GREEN,
+            Polygon::rectangle(100.0, 100.0).translate(-50.0, -50.0),
+        );
+        let mut img = image::RgbaImage::new(20, 20);
+        rasterize(&batch, &bounds, &mut img);
+        assert!(img.pixels().all(|p| p.0 == [0, 255, 0, 255]));
+    }
+}
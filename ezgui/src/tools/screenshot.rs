@@ -1,3 +1,4 @@
+use crate::backend::PrerenderImpl;
 use crate::runner::{State, GUI};
 use crate::Prerender;
 use abstutil::Timer;
@@ -21,6 +22,10 @@ pub(crate) fn screenshot_everything<G: GUI>(
 
     timer.start_iter("capturing images", num_tiles_x * num_tiles_y);
     let mut filenames: Vec<String> = Vec::new();
+    // Only used if the backend can read back what it drew. Otherwise, fall back to scrot and leave
+    // a script to stitch the tiles together.
+    let mut full: Option<image::RgbaImage> = None;
+    let mut in_process = true;
     state.canvas.cam_zoom = zoom;
     fs::create_dir_all(dir_path).unwrap();
 
@@ -31,6 +36,28 @@ pub(crate) fn screenshot_everything<G: GUI>(
             state.canvas.cam_y = (tile_y as f64) * state.canvas.window_height;
 
             let suffix = state.draw(prerender, true).unwrap_or_else(String::new);
+
+            if in_process {
+                if let Some(tile) = prerender.inner.screencap() {
+                    let filename = format!("{:02}x{:02}{}.png", tile_x + 1, tile_y + 1, suffix);
+                    tile.save(format!("{}/{}", dir_path, filename)).unwrap();
+                    if tile_x == 0 && tile_y == 0 {
+                        full = new_full_image(&tile, num_tiles_x, num_tiles_y);
+                    }
+                    if let Some(ref mut img) = full {
+                        image::imageops::replace(
+                            img,
+                            &tile,
+                            (tile_x as u32) * tile.width(),
+                            (tile_y as u32) * tile.height(),
+                        );
+                    }
+                    filenames.push(filename);
+                    continue;
+                }
+                in_process = false;
+            }
+
             let filename = format!("{:02}x{:02}{}.gif", tile_x + 1, tile_y + 1, suffix);
 
             // TODO Is vsync or something else causing the above redraw to not actually show up in
@@ -49,7 +76,65 @@ pub(crate) fn screenshot_everything<G: GUI>(
     state.canvas.cam_zoom = orig_zoom;
     state.canvas.cam_x = orig_x;
     state.canvas.cam_y = orig_y;
-    finish(dir_path, filenames, num_tiles_x, num_tiles_y);
+
+    if !in_process {
+        finish(dir_path, filenames, num_tiles_x, num_tiles_y);
+        return;
+    }
+    if let Some(img) = full {
+        // The last row and column of tiles usually go past the edge of the map. Tiles are in
+        // physical pixels, which may not match the canvas size.
+        let scale = (img.width() as f64) / (num_tiles_x as f64) / state.canvas.window_width;
+        let width = ((max_x * zoom * scale).ceil() as u32).min(img.width());
+        let height = ((max_y * zoom * scale).ceil() as u32).min(img.height());
+        let cropped = image::imageops::crop_imm(&img, 0, 0, width, height).to_image();
+        timer.start(format!("saving {}/full.png", dir_path));
+        cropped.save(format!("{}/full.png", dir_path)).unwrap();
+        timer.stop(format!("saving {}/full.png", dir_path));
+    } else {
+        timer.note(format!(
+            "The map is too big to stitch into one image; the {} tiles in {} are all there is",
+            filenames.len(),
+            dir_path
+        ));
+    }
+}
+
+// Stitching together a huge map at a high zoom could exhaust memory, so cap the size of the
+// combined image.
+const MAX_FULL_PIXELS: u64 = 400_000_000;
+
+fn new_full_image(
+    first_tile: &image::RgbaImage,
+    num_tiles_x: usize,
+    num_tiles_y: usize,
+) -> Option<image::RgbaImage> {
+    let width = (first_tile.width() as u64) * (num_tiles_x as u64);
+    let height = (first_tile.height() as u64) * (num_tiles_y as u64);
+    if width * height > MAX_FULL_PIXELS {
+        return None;
+    }
+    Some(image::RgbaImage::new(width as u32, height as u32))
+}
+
+pub(crate) fn screenshot_current<G: GUI>(
+    state: &mut State<G>,
+    filename: &str,
+    prerender: &Prerender,
+) {
+    if let Some(dir) = std::path::Path::new(filename).parent() {
+        fs::create_dir_all(dir).unwrap();
+    }
+    state.draw(prerender, true);
+    if let Some(img) = prerender.inner.screencap() {
+        // The format comes from the extension
+        if let Err(err) = img.save(filename) {
+            println!("Couldn't save {}: {}", filename, err);
+        }
+        return;
+    }
+    thread::sleep(time::Duration::from_millis(100));
+    screencap(filename);
 }
 
 fn screencap(filename: &str) -> bool {
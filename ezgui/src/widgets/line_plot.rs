@@ -3,7 +3,7 @@ use crate::{
     ScreenRectangle, Text, TextExt, Widget, WidgetImpl, WidgetOutput,
 };
 use abstutil::prettyprint_usize;
-use geom::{Angle, Bounds, Circle, Distance, Duration, FindClosest, PolyLine, Polygon, Pt2D, Time};
+use geom::{Angle, Circle, Distance, Duration, FindClosest, PolyLine, Polygon, Pt2D, Time};
 use std::collections::HashSet;
 
 // The X is always time
@@ -127,10 +127,7 @@ impl<T: Yvalue<T>> LinePlot<T> {
             }
         }
 
-        let mut closest = FindClosest::new(&Bounds::from(&vec![
-            Pt2D::new(0.0, 0.0),
-            Pt2D::new(width, height),
-        ]));
+        let mut closest = FindClosest::new();
         for s in series {
             if max_x == Time::START_OF_DAY {
                 continue;
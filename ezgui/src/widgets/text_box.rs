@@ -1,6 +1,6 @@
 use crate::{
-    text, EventCtx, GeomBatch, GfxCtx, Key, Line, ScreenDims, ScreenPt, ScreenRectangle, Text,
-    WidgetImpl, WidgetOutput,
+    get_clipboard, text, EventCtx, GeomBatch, GfxCtx, Key, Line, ScreenDims, ScreenPt,
+    ScreenRectangle, Text, WidgetImpl, WidgetOutput,
 };
 use geom::Polygon;
 
@@ -8,6 +8,7 @@ use geom::Polygon;
 
 pub struct TextBox {
     line: String,
+    // A byte offset into line, always at the start of a char, since pasted text might not be ASCII
     cursor_x: usize,
     has_focus: bool,
     hovering: bool,
@@ -38,10 +39,11 @@ impl TextBox {
         let mut txt = Text::from(Line(&self.line[0..self.cursor_x]));
         if self.cursor_x < self.line.len() {
             // TODO This "cursor" looks awful!
+            let next = self.cursor_x + self.char_len_after();
             txt.append_all(vec![
                 Line("|").fg(text::SELECTED_COLOR),
-                Line(&self.line[self.cursor_x..=self.cursor_x]),
-                Line(&self.line[self.cursor_x + 1..]),
+                Line(&self.line[self.cursor_x..next]),
+                Line(&self.line[next..]),
             ]);
         } else {
             txt.append(Line("|").fg(text::SELECTED_COLOR));
@@ -52,6 +54,24 @@ impl TextBox {
     pub fn get_line(&self) -> String {
         self.line.clone()
     }
+
+    // How many bytes the char before the cursor takes up, or 0 at the start
+    fn char_len_before(&self) -> usize {
+        self.line[..self.cursor_x]
+            .chars()
+            .next_back()
+            .map(|c| c.len_utf8())
+            .unwrap_or(0)
+    }
+
+    // How many bytes the char after the cursor takes up, or 0 at the end
+    fn char_len_after(&self) -> usize {
+        self.line[self.cursor_x..]
+            .chars()
+            .next()
+            .map(|c| c.len_utf8())
+            .unwrap_or(0)
+    }
 }
 
 impl WidgetImpl for TextBox {
@@ -82,26 +102,35 @@ impl WidgetImpl for TextBox {
         if !self.has_focus && !self.autofocus {
             return;
         }
+        ctx.canvas.typing = true;
         if let Some(key) = ctx.input.any_key_pressed() {
+            if key == Key::V && ctx.canvas.lctrl_held {
+                // Only the first line, since this is a single-line box
+                if let Some(pasted) = get_clipboard()
+                    .and_then(|x| x.lines().next().map(|line| line.trim().to_string()))
+                {
+                    self.line.insert_str(self.cursor_x, &pasted);
+                    self.cursor_x += pasted.len();
+                }
+                return;
+            }
             match key {
                 Key::LeftArrow => {
-                    if self.cursor_x > 0 {
-                        self.cursor_x -= 1;
-                    }
+                    self.cursor_x -= self.char_len_before();
                 }
                 Key::RightArrow => {
-                    self.cursor_x = (self.cursor_x + 1).min(self.line.len());
+                    self.cursor_x += self.char_len_after();
                 }
                 Key::Backspace => {
                     if self.cursor_x > 0 {
-                        self.line.remove(self.cursor_x - 1);
-                        self.cursor_x -= 1;
+                        self.cursor_x -= self.char_len_before();
+                        self.line.remove(self.cursor_x);
                     }
                 }
                 _ => {
                     if let Some(c) = key.to_char(ctx.canvas.lshift_held) {
                         self.line.insert(self.cursor_x, c);
-                        self.cursor_x += 1;
+                        self.cursor_x += c.len_utf8();
                     } else {
                         ctx.input.unconsume_event();
                     }
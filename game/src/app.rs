@@ -1,23 +1,35 @@
 use crate::challenges::HighScore;
 use crate::colors::ColorScheme;
+use crate::common::record_opened;
+use crate::game::Transition;
 use crate::helpers::ID;
 use crate::layer::Layer;
 use crate::options::Options;
-use crate::render::{AgentCache, DrawMap, DrawOptions, Renderable, UnzoomedAgents};
+use crate::render::{
+    draw_trip_markers, AgentCache, BackgroundTiles, DrawMap, DrawOptions, MapGeometry, Renderable,
+    UnzoomedAgents, MIN_ZOOM_FOR_EXTRUSION, MIN_ZOOM_FOR_SMALL_DETAILS,
+};
 use crate::sandbox::{GameplayMode, TutorialState};
 use abstutil::{MeasureMemory, Timer};
-use ezgui::{EventCtx, GfxCtx, Prerender};
+use ezgui::{EventCtx, GfxCtx, Notifications, Prerender};
 use geom::{Bounds, Circle, Distance, Duration, Pt2D, Time};
-use map_model::{IntersectionID, Map, Traversable};
+use instant::Instant;
+use map_model::{IntersectionID, Map, PermanentMapEdits, Traversable};
 use maplit::btreemap;
 use rand::seq::SliceRandom;
 use sim::{Analytics, GetDrawAgents, Sim, SimCallback, SimFlags};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+// Runs when a question asked through App::notifications is answered yes. The transition applies to
+// whatever state is active by then.
+pub type OnConfirm = Box<dyn FnOnce(&mut EventCtx, &mut App) -> Transition>;
 
 pub struct App {
-    // Naming is from older days when there was an A/B test, "side-by-side" mode. Keeping this
-    // naming, because that mode will return someday.
+    // What's shown and interacted with. Everything else only cares about this one.
     pub primary: PerMap,
+    // Only exists during an A/B test, running the same scenario on a different map.
+    pub secondary: Option<PerMap>,
     // Only exists in some gameplay modes. Must be carefully reset otherwise. Has the map and
     // scenario name too. TODO Embed that in Analytics directly instead.
     prebaked: Option<(String, String, Analytics)>,
@@ -29,6 +41,8 @@ pub struct App {
 
     pub per_obj: PerObjectActions,
     pub layer: Option<Box<dyn Layer>>,
+    // Toasts and questions that don't block whatever state is active
+    pub notifications: Notifications<OnConfirm>,
 
     // Static data that lasts the entire session. Use sparingly.
     pub session: SessionState,
@@ -45,7 +59,16 @@ impl App {
         let primary = ctx.loading_screen("load map", |ctx, mut timer| {
             PerMap::new(flags, &cs, ctx, &mut timer)
         });
+        App::with_map(primary, cs, opts, ctx, splash)
+    }
 
+    fn with_map(
+        primary: PerMap,
+        cs: ColorScheme,
+        opts: Options,
+        ctx: &mut EventCtx,
+        splash: bool,
+    ) -> App {
         let mut rng = primary.current_flags.sim_flags.make_rng();
         let rand_focus_pt = primary
             .map
@@ -60,6 +83,8 @@ impl App {
                     .and_then(|l| ID::Lane(l.id).canonical_point(&primary))
             })
             .expect("Can't get canonical_point of a random building or lane");
+        record_opened(&primary.map);
+
         let bounds = primary.map.get_bounds();
         ctx.canvas.map_dims = (bounds.width(), bounds.height());
 
@@ -74,12 +99,14 @@ impl App {
 
         App {
             primary,
+            secondary: None,
             prebaked: None,
             unzoomed_agents: UnzoomedAgents::new(&cs),
             cs,
             opts,
             per_obj: PerObjectActions::new(),
             layer: None,
+            notifications: Notifications::new(),
             session: SessionState::empty(),
             suspended_sim: None,
         }
@@ -154,6 +181,18 @@ impl App {
         self.session = session;
     }
 
+    // Like switch_map, for a map that's already been loaded in the background
+    pub fn switch_to_loaded(&mut self, ctx: &mut EventCtx, loaded: LoadedMap) {
+        ctx.canvas.save_camera_state(self.primary.map.get_name());
+        let cs = ColorScheme::new(self.opts.color_scheme);
+        let primary = ctx.loading_screen("upload map", |ctx, mut timer| {
+            PerMap::from_loaded(loaded, &cs, ctx, &mut timer)
+        });
+        let session = std::mem::replace(&mut self.session, SessionState::empty());
+        *self = App::with_map(primary, cs, self.opts.clone(), ctx, false);
+        self.session = session;
+    }
+
     pub fn draw(
         &self,
         g: &mut GfxCtx,
@@ -176,17 +215,24 @@ impl App {
                 g.redraw(&self.primary.draw_map.draw_all_unzoomed_parking_lots);
             }
             if layers.show_intersections || layers.show_lanes {
-                g.redraw(
-                    &self
-                        .primary
-                        .draw_map
-                        .draw_all_unzoomed_roads_and_intersections,
-                );
+                if g.canvas.cam_zoom < self.opts.min_zoom_for_density {
+                    g.redraw(&self.primary.draw_map.draw_all_road_centerlines);
+                } else {
+                    g.redraw(
+                        &self
+                            .primary
+                            .draw_map
+                            .draw_all_unzoomed_roads_and_intersections,
+                    );
+                }
             }
             if layers.show_buildings {
                 g.redraw(&self.primary.draw_map.draw_all_buildings);
                 // Not the building paths
             }
+            if let Some(ref heatmap) = self.primary.draw_map.heatmap {
+                heatmap.draw(g, self, true);
+            }
 
             // Still show some shape selection when zoomed out.
             // TODO Refactor! Ideally use get_obj
@@ -216,19 +262,29 @@ impl App {
             }
 
             let mut cache = self.primary.draw_map.agents.borrow_mut();
-            cache.draw_unzoomed_agents(
-                source,
-                &self.primary.map,
-                &self.unzoomed_agents,
-                g,
-                if self.opts.large_unzoomed_agents {
-                    Some(Distance::meters(10.0) / g.canvas.cam_zoom)
-                } else {
-                    None
-                },
-                self.opts.debug_all_agents,
-                &self.cs,
-            );
+            if g.canvas.cam_zoom < self.opts.min_zoom_for_density {
+                cache.draw_agent_density(
+                    source,
+                    &self.primary.map,
+                    &self.unzoomed_agents,
+                    g,
+                    &self.cs,
+                );
+            } else {
+                cache.draw_unzoomed_agents(
+                    source,
+                    &self.primary.map,
+                    &self.unzoomed_agents,
+                    g,
+                    if self.opts.large_unzoomed_agents {
+                        Some(g.canvas.screen_to_map_dist(10.0))
+                    } else {
+                        None
+                    },
+                    self.opts.debug_all_agents,
+                    &self.cs,
+                );
+            }
         } else {
             let mut cache = self.primary.draw_map.agents.borrow_mut();
             let objects = self.get_renderables_back_to_front(
@@ -241,22 +297,41 @@ impl App {
 
             let mut drawn_all_buildings = false;
             let mut drawn_all_areas = false;
+            let use_background = show_objs.everything_visible();
+            let mut drawn_background_zorders = HashSet::new();
 
             for obj in objects {
-                obj.draw(g, self, &opts);
+                if use_background && BackgroundTiles::handles(&obj.get_id()) {
+                    let zorder = obj.get_zorder();
+                    if drawn_background_zorders.insert(zorder) {
+                        self.primary.draw_map.background.draw(g, self, zorder);
+                    }
+                    obj.draw_dynamic(g, self, &opts);
+                } else {
+                    obj.draw(g, self, &opts);
+                }
 
                 match obj.get_id() {
                     ID::Building(_) => {
-                        if !drawn_all_buildings {
+                        if !drawn_all_buildings && !use_background {
                             g.redraw(&self.primary.draw_map.draw_all_building_paths);
-                            g.redraw(&self.primary.draw_map.draw_all_buildings);
-                            g.redraw(&self.primary.draw_map.draw_all_building_outlines);
+                            if g.canvas.cam_zoom >= MIN_ZOOM_FOR_EXTRUSION {
+                                g.redraw(&self.primary.draw_map.draw_all_extruded_buildings);
+                            } else {
+                                g.redraw(&self.primary.draw_map.draw_all_buildings);
+                                g.redraw(&self.primary.draw_map.draw_all_building_outlines);
+                            }
                             drawn_all_buildings = true;
                         }
                     }
                     ID::Area(_) => {
                         if !drawn_all_areas {
-                            g.redraw(&self.primary.draw_map.draw_all_areas);
+                            // The grass and water textures aren't static, so they're never part
+                            // of the background tiles
+                            if !use_background {
+                                g.redraw(&self.primary.draw_map.draw_all_areas);
+                            }
+                            self.primary.draw_map.draw_area_textures(g);
                             drawn_all_areas = true;
                         }
                     }
@@ -273,6 +348,12 @@ impl App {
                     }
                 }
             }
+
+            if let Some(ref heatmap) = self.primary.draw_map.heatmap {
+                heatmap.draw(g, self, false);
+            }
+
+            draw_trip_markers(g, self, source.time());
         }
 
         if let Some(i) = sample_intersection {
@@ -349,6 +430,12 @@ impl App {
                         continue;
                     }
                 }
+                ID::BusStop(_) => {
+                    // Not drawn yet
+                    if ctx.canvas.cam_zoom < MIN_ZOOM_FOR_SMALL_DETAILS {
+                        continue;
+                    }
+                }
                 _ => {
                     if ctx.canvas.cam_zoom < self.opts.min_zoom_for_detail {
                         continue;
@@ -382,9 +469,10 @@ impl App {
         let mut intersections: Vec<&dyn Renderable> = Vec::new();
         let mut buildings: Vec<&dyn Renderable> = Vec::new();
         let mut bus_stops: Vec<&dyn Renderable> = Vec::new();
+        let mut bus_routes: Vec<&dyn Renderable> = Vec::new();
         let mut agents_on: Vec<Traversable> = Vec::new();
 
-        for id in draw_map.get_matching_objects(bounds) {
+        for id in draw_map.get_matching_objects(bounds.clone()) {
             if !show_objs.show(&id) {
                 continue;
             }
@@ -415,6 +503,13 @@ impl App {
                     agents_on.push(Traversable::Lane(map.get_pl(id).driving_pos.lane()));
                 }
 
+                // Only in the quadtree once they've been rendered
+                ID::BusRoute(id) => {
+                    if show_objs.layers().show_bus_routes {
+                        bus_routes.push(&draw_map.bus_routes[&id]);
+                    }
+                }
+
                 ID::BusStop(_) | ID::Car(_) | ID::Pedestrian(_) | ID::PedCrowd(_) => {
                     panic!("{:?} shouldn't be in the quadtree", id)
                 }
@@ -428,15 +523,20 @@ impl App {
         borrows.extend(lanes);
         borrows.extend(roads);
         borrows.extend(intersections);
+        borrows.extend(bus_routes);
         borrows.extend(buildings);
         borrows.extend(bus_stops);
 
         // Expand all of the Traversables into agents, populating the cache if needed.
-        {
-            for on in &agents_on {
-                agents.populate_if_needed(*on, map, source, &self.cs, prerender);
-            }
-        }
+        agents.populate_in(
+            &bounds,
+            &agents_on,
+            map,
+            source,
+            &self.cs,
+            prerender,
+            self.opts.agent_trails,
+        );
 
         for on in agents_on {
             for obj in agents.get(on) {
@@ -460,6 +560,7 @@ pub struct ShowLayers {
     pub show_lanes: bool,
     pub show_areas: bool,
     pub show_labels: bool,
+    pub show_bus_routes: bool,
 }
 
 impl ShowLayers {
@@ -471,6 +572,7 @@ impl ShowLayers {
             show_lanes: true,
             show_areas: true,
             show_labels: false,
+            show_bus_routes: false,
         }
     }
 }
@@ -478,6 +580,10 @@ impl ShowLayers {
 pub trait ShowObject {
     fn show(&self, obj: &ID) -> bool;
     fn layers(&self) -> &ShowLayers;
+    // If nothing is ever hidden, static map geometry can be drawn from BackgroundTiles.
+    fn everything_visible(&self) -> bool {
+        false
+    }
 }
 
 pub struct ShowEverything {
@@ -500,6 +606,10 @@ impl ShowObject for ShowEverything {
     fn layers(&self) -> &ShowLayers {
         &self.layers
     }
+
+    fn everything_visible(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone)]
@@ -510,6 +620,14 @@ pub struct Flags {
     pub num_agents: Option<usize>,
 }
 
+// Everything needed for a PerMap that doesn't touch the GPU, so it can be loaded on another thread
+pub struct LoadedMap {
+    pub flags: Flags,
+    pub map: Map,
+    pub sim: Sim,
+    pub geometry: MapGeometry,
+}
+
 // All of the state that's bound to a specific map+edit has to live here.
 pub struct PerMap {
     pub map: Map,
@@ -526,6 +644,7 @@ pub struct PerMap {
     pub dirty_from_edits: bool,
     // Any ScenarioModifiers in effect?
     pub has_modified_trips: bool,
+    pub checkpoints: Checkpoints,
 }
 
 impl PerMap {
@@ -533,9 +652,35 @@ impl PerMap {
         let mut mem = MeasureMemory::new();
         let (map, sim, _) = flags.sim_flags.load(timer);
         mem.reset("Map and Sim", timer);
+        let geometry = DrawMap::calculate_geometry(&map, cs, timer);
+        PerMap::from_loaded(
+            LoadedMap {
+                flags,
+                map,
+                sim,
+                geometry,
+            },
+            cs,
+            ctx,
+            timer,
+        )
+    }
 
+    pub fn from_loaded(
+        loaded: LoadedMap,
+        cs: &ColorScheme,
+        ctx: &mut EventCtx,
+        timer: &mut Timer,
+    ) -> PerMap {
+        let LoadedMap {
+            flags,
+            map,
+            sim,
+            geometry,
+        } = loaded;
+        let mut mem = MeasureMemory::new();
         timer.start("draw_map");
-        let draw_map = DrawMap::new(&map, cs, ctx, timer);
+        let draw_map = DrawMap::from_geometry(&map, cs, geometry, ctx, timer);
         timer.stop("draw_map");
         mem.reset("DrawMap", timer);
 
@@ -558,12 +703,15 @@ impl PerMap {
             show_zorder: high_z,
             dirty_from_edits: false,
             has_modified_trips: false,
+            checkpoints: Checkpoints::new(),
         }
     }
 
     // Returns whatever was there
     pub fn clear_sim(&mut self) -> Sim {
         self.dirty_from_edits = false;
+        // They were made with the old map edits
+        self.checkpoints.clear();
         std::mem::replace(
             &mut self.sim,
             Sim::new(
@@ -573,13 +721,127 @@ impl PerMap {
             ),
         )
     }
+
+    // Cheap to call before every step; only occasionally copies the sim.
+    pub fn maybe_checkpoint(&mut self) {
+        self.checkpoints.maybe_add(&self.sim);
+    }
+
+    // Restores the latest checkpoint at or before the target, discarding any later ones. The
+    // caller still has to step forwards to the target. Returns false if there's no such
+    // checkpoint.
+    pub fn rewind_to_checkpoint(&mut self, target: Time) -> bool {
+        match self.checkpoints.rewind(target) {
+            Some(sim) => {
+                self.sim = sim;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Copies of the sim from earlier in the day. Rewinding restores one and replays forwards from
+// there, which is deterministic. A few are spread over the whole day, and a ring buffer of recent
+// ones covers the last few minutes, so something like a gridlock forming can be watched again.
+pub struct Checkpoints {
+    // Both oldest first. When both are due at once, they share one copy.
+    sparse: Vec<Rc<Sim>>,
+    recent: VecDeque<Rc<Sim>>,
+    // Copying the sim isn't free. No matter how fast the sim runs, only do it so often.
+    last_copy: Option<Instant>,
+}
+
+impl Checkpoints {
+    pub fn new() -> Checkpoints {
+        Checkpoints {
+            sparse: Vec::new(),
+            recent: VecDeque::new(),
+            last_copy: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Checkpoints::new();
+    }
+
+    // How far back the recent checkpoints go
+    pub fn earliest_recent(&self) -> Option<Time> {
+        self.recent.front().map(|sim| sim.time())
+    }
+
+    fn maybe_add(&mut self, sim: &Sim) {
+        if let Some(last) = self.last_copy {
+            if last.elapsed() < MIN_REAL_TIME_BETWEEN_COPIES {
+                return;
+            }
+        }
+        let sparse_due = match self.sparse.last() {
+            Some(prev) => sim.time() - prev.time() >= CHECKPOINT_INTERVAL,
+            None => true,
+        };
+        let recent_due = match self.recent.back() {
+            Some(prev) => sim.time() - prev.time() >= RECENT_INTERVAL,
+            None => true,
+        };
+        if !sparse_due && !recent_due {
+            return;
+        }
+        let copy = Rc::new(sim.clone());
+        self.last_copy = Some(Instant::now());
+
+        if sparse_due {
+            if self.sparse.len() == MAX_CHECKPOINTS {
+                self.sparse.remove(0);
+            }
+            self.sparse.push(copy.clone());
+        }
+        if recent_due {
+            if self.recent.len() == MAX_RECENT {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(copy);
+        }
+    }
+
+    // Later checkpoints get discarded; they'll be made again while replaying.
+    fn rewind(&mut self, target: Time) -> Option<Sim> {
+        self.sparse.retain(|s| s.time() <= target);
+        self.recent.retain(|s| s.time() <= target);
+        let latest = match (self.sparse.last(), self.recent.back()) {
+            (Some(a), Some(b)) => {
+                if a.time() > b.time() {
+                    a
+                } else {
+                    b
+                }
+            }
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => {
+                return None;
+            }
+        };
+        Some(Sim::clone(latest))
+    }
 }
 
+const CHECKPOINT_INTERVAL: Duration = Duration::const_seconds(15.0 * 60.0);
+// Each one is a full copy of the sim, so don't keep too many around
+const MAX_CHECKPOINTS: usize = 16;
+// Covers the last 5 minutes, or more when the sim runs faster than 30x
+const RECENT_INTERVAL: Duration = Duration::const_seconds(30.0);
+const MAX_RECENT: usize = 10;
+const MIN_REAL_TIME_BETWEEN_COPIES: std::time::Duration = std::time::Duration::from_secs(1);
+
 // TODO Serialize these, but in a very careful, future-compatible way
 pub struct SessionState {
     pub tutorial: Option<TutorialState>,
     pub high_scores: BTreeMap<GameplayMode, Vec<HighScore>>,
     pub info_panel_tab: BTreeMap<&'static str, &'static str>,
+    // The saved edits that the map edits layer compares against, read once when they're picked.
+    // None means the original map.
+    pub edits_diff_against: Option<PermanentMapEdits>,
 }
 
 impl SessionState {
@@ -594,6 +856,7 @@ impl SessionState {
                 "person" => "trips",
                 "bus" => "status",
             },
+            edits_diff_against: None,
         }
     }
 }
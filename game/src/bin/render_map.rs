@@ -0,0 +1,3 @@
+fn main() {
+    game::render_map_main();
+}
@@ -0,0 +1,3 @@
+fn main() {
+    game::viewer_main();
+}
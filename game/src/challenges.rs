@@ -365,6 +365,7 @@ fn prebake(map: &Map, scenario: Scenario, time_limit: Option<Duration>, timer: &
     let mut sim = Sim::new(&map, opts, timer);
     // Bit of an abuse of this, but just need to fix the rng seed.
     let mut rng = SimFlags::for_test("prebaked").make_rng();
+    sim.capture_routes();
     scenario.instantiate(&mut sim, &map, &mut rng, timer);
     if let Some(dt) = time_limit {
         sim.timed_step(&map, dt, &mut None, timer);
@@ -381,6 +382,10 @@ fn prebake(map: &Map, scenario: Scenario, time_limit: Option<Duration>, timer: &
         abstutil::path_prebaked_results(&scenario.map_name, &scenario.scenario_name),
         sim.get_analytics(),
     );
+    abstutil::write_binary(
+        abstutil::path_prebaked_routes(&scenario.map_name, &scenario.scenario_name),
+        sim.get_captured_routes().unwrap(),
+    );
     timer.stop(format!(
         "prebake for {} / {}",
         scenario.map_name, scenario.scenario_name
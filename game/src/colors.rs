@@ -1,5 +1,6 @@
 use crate::common::ColorScale;
 use ezgui::{Choice, Color, Style};
+use serde::{Deserialize, Serialize};
 
 // I've gone back and forth how to organize color scheme code. I was previously against having one
 // centralized place with all definitions, because careful naming or comments are needed to explain
@@ -12,7 +13,7 @@ use ezgui::{Choice, Color, Style};
 //
 // TODO There are plenty of colors left that aren't captured here. :(
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum ColorSchemeChoice {
     Standard,
     NightMode,
@@ -55,6 +56,7 @@ pub struct ColorScheme {
 
     // Roads
     pub driving_lane: Color,
+    pub highway_lane: Color,
     pub bus_lane: Color,
     pub parking_lane: Color,
     pub bike_lane: Color,
@@ -64,6 +66,8 @@ pub struct ColorScheme {
     pub road_center_line: Color,
     pub light_rail_track: Color,
     pub private_road: Color,
+    pub bus_shelter: Color,
+    pub bus_layover: Color,
     pub unzoomed_highway: Color,
     pub unzoomed_arterial: Color,
     pub unzoomed_residential: Color,
@@ -78,6 +82,12 @@ pub struct ColorScheme {
     pub signal_box: Color,
     pub signal_spinner: Color,
     pub signal_turn_block_bg: Color,
+    pub turn_icon_straight: Color,
+    pub turn_icon_left: Color,
+    pub turn_icon_right: Color,
+    pub turn_icon_u_turn: Color,
+    pub turn_icon_crosswalk: Color,
+    pub turn_icon_banned_strike: Color,
 
     // Other static elements
     pub void_background: Color,
@@ -110,12 +120,15 @@ pub struct ColorScheme {
     pub ped_crowd: Color,
     pub bike_frame: Color,
     pub parked_car: Color,
+    pub trip_started: Color,
+    pub trip_ended: Color,
 
     // Layers
     pub good_to_bad_red: ColorScale,
     pub good_to_bad_green: ColorScale,
     pub bus_layer: Color,
     pub edits_layer: Color,
+    pub annotations_layer: Color,
 
     // Misc
     pub parking_trip: Color,
@@ -156,6 +169,7 @@ impl ColorScheme {
 
             // Roads
             driving_lane: Color::BLACK,
+            highway_lane: Color::rgb(60, 30, 40),
             bus_lane: Color::rgb(190, 74, 76),
             parking_lane: Color::grey(0.2),
             bike_lane: Color::rgb(15, 125, 75),
@@ -165,6 +179,8 @@ impl ColorScheme {
             road_center_line: Color::YELLOW,
             light_rail_track: Color::hex("#844204"),
             private_road: Color::hex("#F0B0C0"),
+            bus_shelter: hex("#7E9BA8"),
+            bus_layover: hex("#4CA7E9").alpha(0.4),
             unzoomed_highway: Color::rgb(232, 146, 162),
             unzoomed_arterial: Color::rgb(255, 199, 62),
             unzoomed_residential: Color::WHITE,
@@ -179,6 +195,12 @@ impl ColorScheme {
             signal_box: Color::grey(0.5),
             signal_spinner: hex("#F2994A"),
             signal_turn_block_bg: Color::grey(0.6),
+            turn_icon_straight: Color::WHITE,
+            turn_icon_left: hex("#F2994A"),
+            turn_icon_right: hex("#9B51E0"),
+            turn_icon_u_turn: hex("#EB5757"),
+            turn_icon_crosswalk: Color::grey(0.9),
+            turn_icon_banned_strike: Color::RED,
 
             // Other static elements
             void_background: Color::BLACK,
@@ -217,12 +239,15 @@ impl ColorScheme {
             ped_crowd: Color::rgb_f(0.2, 0.7, 0.7),
             bike_frame: Color::hex("#AAA9AD"),
             parked_car: Color::hex("#938E85"),
+            trip_started: hex("#00A27B"),
+            trip_ended: hex("#5C45A0"),
 
             // Layers
             good_to_bad_red: ColorScale(vec![hex("#F19A93"), hex("#A32015")]),
             good_to_bad_green: ColorScale(vec![hex("#BEDB92"), hex("#397A4C")]),
             bus_layer: hex("#4CA7E9"),
             edits_layer: hex("#12409D"),
+            annotations_layer: hex("#EB5757"),
 
             // Misc
             parking_trip: hex("#4E30A6"),
@@ -281,6 +306,7 @@ impl ColorScheme {
         cs.map_background = hex("#CFE2C4");
         cs.water = hex("#B4D3E5");
         cs.driving_lane = hex("#C6CDD5");
+        cs.highway_lane = hex("#D5C0C6");
         cs.residential_building = hex("#CCD4BD");
         cs.sidewalk = hex("#98A1AA");
         cs
@@ -291,6 +317,7 @@ impl ColorScheme {
         cs.map_background = hex("#FEE4D7");
         cs.grass = hex("#F6C6AF");
         cs.driving_lane = hex("#BECBD3");
+        cs.highway_lane = hex("#D3BEC4");
         cs.residential_building = hex("#DEAA95");
         cs.sidewalk = hex("#8B9EA8");
         cs
@@ -312,6 +339,7 @@ impl ColorScheme {
         cs.residential_building = hex("#367335"); // #194C18
         cs.normal_intersection = hex("#4B5485");
         cs.driving_lane = hex("#384173");
+        cs.highway_lane = hex("#733438");
         cs.parking_lane = hex("#4B5485");
         cs.sidewalk = hex("#89ABD9");
         cs.sidewalk_lines = hex("#4B5485");
@@ -336,6 +364,7 @@ impl ColorScheme {
         cs.residential_building = hex("#8099A8"); // #5E7486
         cs.map_background = hex("#737373");
         cs.driving_lane = hex("#2A2A2A"); // TODO for arterial
+        cs.highway_lane = hex("#3D2A30");
         cs.road_center_line = hex("#DB952E");
         cs.general_road_marking = hex("#D6D6D6");
         cs.sidewalk = cs.general_road_marking;
@@ -1,4 +1,5 @@
 use crate::app::App;
+use crate::common::{MapChooser, MapLoader};
 use crate::game::{DrawBaselayer, State, Transition};
 use crate::helpers::nice_map_name;
 use crate::render::DrawArea;
@@ -98,12 +99,19 @@ impl CityPicker {
                         Widget::draw_batch(ctx, batch).named("picker"),
                         Widget::col(this_city).centered_vert(),
                     ]),
+                    Btn::text_fg("details and saved edits").build_def(ctx, None),
                 ])
                 .outline(2.0, Color::WHITE),
             )
             .build(ctx),
         })
     }
+
+    fn load(&mut self, ctx: &mut EventCtx, app: &App, name: String) -> Transition {
+        // Nothing calls this once the loader takes over
+        let on_load = std::mem::replace(&mut self.on_load, Box::new(|_, _| Transition::Keep));
+        Transition::Replace(MapLoader::new(ctx, app, name, on_load))
+    }
 }
 
 impl State for CityPicker {
@@ -113,11 +121,14 @@ impl State for CityPicker {
                 "close" => {
                     return Transition::Pop;
                 }
+                "details and saved edits" => {
+                    // Nothing calls this once the chooser takes over
+                    let on_load =
+                        std::mem::replace(&mut self.on_load, Box::new(|_, _| Transition::Keep));
+                    return Transition::Replace(MapChooser::new(ctx, app, on_load));
+                }
                 name => {
-                    return ctx.loading_screen("switch map", |ctx, _| {
-                        app.switch_map(ctx, abstutil::path_map(name));
-                        (self.on_load)(ctx, app)
-                    });
+                    return self.load(ctx, app, name.to_string());
                 }
             },
             None => {}
@@ -151,10 +162,8 @@ impl State for CityPicker {
                 .per_obj
                 .left_click(ctx, format!("switch to {}", nice_map_name(name)))
             {
-                return ctx.loading_screen("switch map", |ctx, _| {
-                    app.switch_map(ctx, abstutil::path_map(name));
-                    (self.on_load)(ctx, app)
-                });
+                let name = name.clone();
+                return self.load(ctx, app, name);
             }
         }
 
@@ -248,6 +248,13 @@ impl<'a> ColorNetwork<'a> {
         );
     }
 
+    pub fn add_l(&mut self, l: LaneID, color: Color) {
+        let lane = self.map.get_l(l);
+        let polygon = lane.lane_center_pts.make_polygons(lane.width);
+        self.unzoomed.push(color, polygon.clone());
+        self.zoomed.push(color.alpha(0.4), polygon);
+    }
+
     pub fn add_i(&mut self, i: IntersectionID, color: Color) {
         self.unzoomed.push(color, self.map.get_i(i).polygon.clone());
         self.zoomed
@@ -0,0 +1,230 @@
+use crate::app::App;
+use crate::devtools::kml::ViewKML;
+use crate::edit::EditMode;
+use crate::game::{State, Transition};
+use abstutil::Timer;
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+use map_model::PermanentMapEdits;
+use std::path::Path;
+
+// Instead of native open/save dialogs, browse one directory at a time. Subdirectories are always
+// listed; files only if they have one of the extensions.
+pub struct FileBrowser {
+    composite: Composite,
+    dir: String,
+    extensions: Vec<String>,
+    // True when choosing where to save something
+    saving: bool,
+    on_choose: Option<Box<dyn FnOnce(&mut EventCtx, &mut App, String) -> Transition>>,
+}
+
+impl FileBrowser {
+    pub fn open(
+        ctx: &mut EventCtx,
+        dir: String,
+        extensions: Vec<&str>,
+        on_choose: Box<dyn FnOnce(&mut EventCtx, &mut App, String) -> Transition>,
+    ) -> Box<dyn State> {
+        let extensions = extensions.into_iter().map(|x| x.to_string()).collect();
+        FileBrowser::new(ctx, dir, extensions, false, String::new(), on_choose)
+    }
+
+    // Starts with the given filename, but the player can change it.
+    pub fn save(
+        ctx: &mut EventCtx,
+        dir: String,
+        filename: String,
+        on_choose: Box<dyn FnOnce(&mut EventCtx, &mut App, String) -> Transition>,
+    ) -> Box<dyn State> {
+        let extensions = Path::new(&filename)
+            .extension()
+            .map(|ext| vec![ext.to_string_lossy().to_string()])
+            .unwrap_or_else(Vec::new);
+        FileBrowser::new(ctx, dir, extensions, true, filename, on_choose)
+    }
+
+    fn new(
+        ctx: &mut EventCtx,
+        dir: String,
+        extensions: Vec<String>,
+        saving: bool,
+        filename: String,
+        on_choose: Box<dyn FnOnce(&mut EventCtx, &mut App, String) -> Transition>,
+    ) -> Box<dyn State> {
+        let mut browser = FileBrowser {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            dir: absolute(dir),
+            extensions: extensions.into_iter().map(|x| format!(".{}", x)).collect(),
+            saving,
+            on_choose: Some(on_choose),
+        };
+        browser.rebuild(ctx, filename);
+        Box::new(browser)
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, filename: String) {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        for path in abstutil::list_dir(Path::new(&self.dir)) {
+            let name = Path::new(&path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            if Path::new(&path).is_dir() {
+                dirs.push(Btn::text_fg(format!("{}/", name)).build_def(ctx, None));
+            } else if self.extensions.is_empty()
+                || self.extensions.iter().any(|ext| name.ends_with(ext))
+            {
+                if self.saving {
+                    // Just a reminder of what's already there
+                    files.push(Line(name).secondary().draw(ctx));
+                } else {
+                    files.push(Btn::text_fg(name).build_def(ctx, None));
+                }
+            }
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line(if self.saving {
+                    "Save a file"
+                } else {
+                    "Open a file"
+                })
+                .small_heading()
+                .draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(Line(&self.dir)).wrap_to_pct(ctx, 40).draw(ctx),
+        ];
+        if self.saving {
+            col.push(Widget::row(vec![
+                "Filename:".draw_text(ctx).margin_right(10),
+                Widget::text_entry(ctx, filename, true).named("filename"),
+                Btn::text_bg2("save").build_def(ctx, hotkey(Key::Enter)),
+            ]));
+        }
+        if Path::new(&self.dir).parent().is_some() {
+            col.push(Btn::text_fg("../").build_def(ctx, None));
+        }
+        if dirs.is_empty() && files.is_empty() {
+            col.push("Nothing here".draw_text(ctx));
+        }
+        col.extend(dirs);
+        col.extend(files);
+
+        self.composite = Composite::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+            .max_size_percent(50, 70)
+            .build(ctx);
+    }
+}
+
+impl State for FileBrowser {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => {
+                let filename = if self.saving {
+                    self.composite.text_box("filename")
+                } else {
+                    String::new()
+                };
+                match x.as_ref() {
+                    "close" => Transition::Pop,
+                    "../" => {
+                        self.dir = Path::new(&self.dir).parent().unwrap().display().to_string();
+                        self.rebuild(ctx, filename);
+                        Transition::Keep
+                    }
+                    "save" => {
+                        // Whatever writes the file probably insists on the extension (like
+                        // abstutil::write_json), so put it back if the player removed it
+                        let mut filename = filename.trim().to_string();
+                        if let Some(ext) = self.extensions.get(0) {
+                            if !filename.ends_with(ext) {
+                                filename = format!("{}{}", filename, ext);
+                            }
+                        }
+                        let path = Path::new(&self.dir).join(filename);
+                        (self.on_choose.take().unwrap())(ctx, app, path.display().to_string())
+                    }
+                    x if x.ends_with('/') => {
+                        self.dir = Path::new(&self.dir)
+                            .join(x.trim_end_matches('/'))
+                            .display()
+                            .to_string();
+                        self.rebuild(ctx, filename);
+                        Transition::Keep
+                    }
+                    x => {
+                        let path = Path::new(&self.dir).join(x);
+                        (self.on_choose.take().unwrap())(ctx, app, path.display().to_string())
+                    }
+                }
+            }
+            None => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+fn absolute(dir: String) -> String {
+    match std::fs::canonicalize(&dir) {
+        Ok(path) => path.display().to_string(),
+        Err(_) => dir,
+    }
+}
+
+// For files dragged onto the window. Shapes (.kml, .geojson, .csv, .bin) open in the KML viewer.
+// Edits (.json) are loaded, but only from the map editor. Problems just wind up in a toast.
+pub fn open_file(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    top: &mut Box<dyn State>,
+    path: String,
+) -> Transition {
+    if path.ends_with(".json") {
+        if let Ok(perma) =
+            abstutil::maybe_read_json::<PermanentMapEdits>(path.clone(), &mut Timer::throwaway())
+        {
+            let result = match top.downcast_mut::<EditMode>() {
+                Some(editor) => editor.load_edits(ctx, app, perma),
+                None => Err("Open the map editor first".to_string()),
+            };
+            return match result {
+                Ok(transition) => transition,
+                Err(err) => {
+                    app.notifications.toast(
+                        ctx,
+                        Text::from(Line(format!("Can't load edits from {}: {}", path, err))),
+                    );
+                    Transition::Keep
+                }
+            };
+        }
+    }
+
+    match ViewKML::new(ctx, app, path.clone()) {
+        Ok(state) => Transition::Push(state),
+        Err(err) => {
+            app.notifications.toast(
+                ctx,
+                Text::from(Line(format!("Can't load {}: {}", path, err))),
+            );
+            Transition::Keep
+        }
+    }
+}
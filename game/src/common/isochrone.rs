@@ -1,22 +1,29 @@
 use crate::app::App;
 use crate::common::heatmap::Grid;
+use crate::common::ColorLegend;
 use crate::game::{State, Transition};
 use ezgui::{
-    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
-    Line, Outcome, VerticalAlignment, Widget,
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Duration, Polygon, Pt2D};
-use map_model::{connectivity, IntersectionID};
+use map_model::{connectivity, BuildingID, PathConstraints};
 
 // TODO Move cursor live
 pub struct IsochroneViewer {
     composite: Composite,
+    start: BuildingID,
     draw: Drawable,
 }
 
 impl IsochroneViewer {
-    pub fn new(ctx: &mut EventCtx, app: &App, start: IntersectionID) -> Box<dyn State> {
-        let draw = make_isochrone(ctx, app, start);
+    pub fn new(ctx: &mut EventCtx, app: &App, start: BuildingID) -> Box<dyn State> {
+        let constraints = PathConstraints::Pedestrian;
+        let draw = make_isochrone(ctx, app, start, constraints);
+        let mut legend = vec!["Reachable within:".draw_text(ctx)];
+        for (color, threshold) in thresholds().into_iter().rev() {
+            legend.push(ColorLegend::row(ctx, color, threshold.to_string()));
+        }
         Box::new(IsochroneViewer {
             composite: Composite::new(Widget::col(vec![
                 Widget::row(vec![
@@ -25,19 +32,34 @@ impl IsochroneViewer {
                         .build(ctx, "close", hotkey(Key::Escape))
                         .align_right(),
                 ]),
-                // TODO legend, mode picker
+                Widget::row(vec![
+                    "Starting from the building by".draw_text(ctx),
+                    Widget::dropdown(
+                        ctx,
+                        "mode",
+                        constraints,
+                        vec![
+                            Choice::new("walking", PathConstraints::Pedestrian),
+                            Choice::new("biking", PathConstraints::Bike),
+                            Choice::new("driving", PathConstraints::Car),
+                        ],
+                    ),
+                ]),
+                Widget::col(legend),
             ]))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
             .build(ctx),
+            start,
             draw,
         })
     }
 }
 
 impl State for IsochroneViewer {
-    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         ctx.canvas_movement();
 
+        let old_constraints: PathConstraints = self.composite.dropdown_value("mode");
         match self.composite.event(ctx) {
             Some(Outcome::Clicked(x)) => match x.as_ref() {
                 "close" => {
@@ -47,34 +69,56 @@ impl State for IsochroneViewer {
             },
             None => {}
         }
+        let constraints: PathConstraints = self.composite.dropdown_value("mode");
+        if constraints != old_constraints {
+            self.draw = make_isochrone(ctx, app, self.start, constraints);
+        }
 
         Transition::Keep
     }
 
-    fn draw(&self, g: &mut GfxCtx, _: &App) {
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
         g.redraw(&self.draw);
+        g.draw_polygon(Color::BLUE, &app.primary.map.get_b(self.start).polygon);
         self.composite.draw(g);
     }
 }
 
-// TODO Probably to start, just color lanes/intersections
-fn make_isochrone(ctx: &mut EventCtx, app: &App, start: IntersectionID) -> Drawable {
-    let bounds = app.primary.map.get_bounds();
+// Biggest first, so the smaller areas are drawn on top
+fn thresholds() -> Vec<(Color, Duration)> {
+    vec![
+        (Color::RED.alpha(0.3), Duration::minutes(15)),
+        (Color::ORANGE.alpha(0.3), Duration::minutes(10)),
+        (Color::GREEN.alpha(0.3), Duration::minutes(5)),
+    ]
+}
+
+fn make_isochrone(
+    ctx: &mut EventCtx,
+    app: &App,
+    start: BuildingID,
+    constraints: PathConstraints,
+) -> Drawable {
+    let map = &app.primary.map;
+    let (_, time_limit) = thresholds()[0];
+    // Anything not reached counts as a bit past the limit, so contours close around it.
+    let unreachable = time_limit + Duration::minutes(1);
+
+    let bounds = map.get_bounds();
     let resolution_m = 100.0;
     let mut grid: Grid<Duration> = Grid::new(
         (bounds.width() / resolution_m).ceil() as usize,
         (bounds.height() / resolution_m).ceil() as usize,
-        Duration::ZERO,
+        unreachable,
     );
-
-    for (i, cost) in connectivity::all_costs_from(&app.primary.map, start) {
-        let pt = app.primary.map.get_i(i).polygon.center();
+    for (b, cost) in connectivity::all_costs_from(map, start, time_limit, constraints) {
+        let pt = map.get_b(b).polygon.center();
         let idx = grid.idx(
             ((pt.x() - bounds.min_x) / resolution_m) as usize,
             ((pt.y() - bounds.min_y) / resolution_m) as usize,
         );
-        // Don't add! If two intersections map to the same cell, should pick a finer resolution.
-        grid.data[idx] = cost;
+        // Several buildings can land in the same cell; the closest one wins.
+        grid.data[idx] = grid.data[idx].min(cost);
     }
 
     // Turn into contours
@@ -89,24 +133,23 @@ fn make_isochrone(ctx: &mut EventCtx, app: &App, start: IntersectionID) -> Drawa
         values: &rows,
     };
     let mut batch = GeomBatch::new();
-    for (color, threshold) in vec![
-        (Color::RED.alpha(0.5), Duration::seconds(30.0)),
-        (Color::BLUE.alpha(0.5), Duration::minutes(2)),
-    ] {
+    for (color, threshold) in thresholds() {
+        let mut areas = Vec::new();
         for line in field.get_contours(threshold.inner_seconds() as i16) {
             if line.points.len() >= 3 {
-                batch.push(
-                    color,
-                    Polygon::new(
-                        &line
-                            .points
-                            .into_iter()
-                            .map(|pt| Pt2D::new(pt.x.into(), pt.y.into()))
-                            .collect(),
-                    ),
-                );
+                areas.push(Polygon::new(
+                    &line
+                        .points
+                        .into_iter()
+                        .map(|pt| Pt2D::new(pt.x.into(), pt.y.into()))
+                        .collect(),
+                ));
             }
         }
+        // One area per threshold, even if it's made of disjoint pieces
+        if !areas.is_empty() {
+            batch.push(color, Polygon::union_all(areas));
+        }
     }
 
     batch.upload(ctx)
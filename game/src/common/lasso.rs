@@ -0,0 +1,69 @@
+use ezgui::{Color, EventCtx, GfxCtx};
+use geom::{PolyLine, Pt2D, Ring};
+
+// Draw a freehand shape by dragging the mouse. It closes when the line crosses itself or the
+// button is released.
+// TODO This should totally be an ezgui tool
+// TODO Simplify points
+pub struct Lasso {
+    pl: PolyLine,
+}
+
+impl Lasso {
+    pub fn new(pt: Pt2D) -> Lasso {
+        Lasso {
+            pl: PolyLine::must_new(vec![pt, pt.offset(0.1, 0.0)]),
+        }
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx) -> Option<Ring> {
+        if ctx.input.left_mouse_button_released() {
+            return Some(simplify(self.pl.points().clone()));
+        }
+        if ctx.redo_mouseover() {
+            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                if let Ok(pl) = PolyLine::new(vec![self.pl.last_pt(), pt]) {
+                    // Did we make a crossing?
+                    if let Some((hit, _)) = self.pl.intersection(&pl) {
+                        if let Some(slice) = self.pl.get_slice_starting_at(hit) {
+                            return Some(simplify(slice.into_points()));
+                        }
+                    }
+
+                    let mut pts = self.pl.points().clone();
+                    pts.push(pt);
+                    if let Ok(new) = PolyLine::new(pts) {
+                        self.pl = new;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        g.draw_polygon(
+            Color::RED.alpha(0.8),
+            &self.pl.make_polygons(g.canvas.screen_to_map_dist(5.0)),
+        );
+    }
+}
+
+fn simplify(mut raw: Vec<Pt2D>) -> Ring {
+    // TODO This is eating some of the shapes entirely. Wasn't meant for this.
+    if false {
+        let pts = raw
+            .into_iter()
+            .map(|pt| lttb::DataPoint::new(pt.x(), pt.y()))
+            .collect();
+        let mut downsampled = Vec::new();
+        for pt in lttb::lttb(pts, 50) {
+            downsampled.push(Pt2D::new(pt.x, pt.y));
+        }
+        downsampled.push(downsampled[0]);
+        Ring::must_new(downsampled)
+    } else {
+        raw.push(raw[0]);
+        Ring::must_new(raw)
+    }
+}
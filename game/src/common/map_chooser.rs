@@ -0,0 +1,375 @@
+use crate::app::App;
+use crate::common::MapLoader;
+use crate::game::{DrawBaselayer, State, Transition, WizardState};
+use crate::helpers::nice_map_name;
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Distance, Polygon};
+use map_model::{Map, MapSummary, PermanentMapEdits};
+use std::collections::BTreeMap;
+
+// Remembers when each map was last opened, for the chooser. If the map came from an older
+// importer that didn't write a summary, or the summary is from an older version, make one now.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn record_opened(map: &Map) {
+    let mut last_opened = load_last_opened();
+    last_opened.insert(map.get_name().to_string(), seconds_since_epoch());
+    abstutil::write_json(abstutil::path_maps_last_opened(), &last_opened);
+
+    if MapSummary::load(map.get_name()).is_none() {
+        MapSummary::new(map).save(map.get_name());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn record_opened(_: &Map) {}
+
+// Map name to seconds since the Unix epoch
+fn load_last_opened() -> BTreeMap<String, u64> {
+    abstutil::maybe_read_json(
+        abstutil::path_maps_last_opened(),
+        &mut abstutil::Timer::throwaway(),
+    )
+    .unwrap_or_else(|_| BTreeMap::new())
+}
+
+// Every map installed, with details about whichever one is selected, and the saved edits for it.
+// Maps besides the one open now can be deleted, to free up space.
+pub struct MapChooser {
+    composite: Composite,
+    selected: String,
+    on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+}
+
+impl MapChooser {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+    ) -> Box<dyn State> {
+        let mut chooser = MapChooser {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            selected: app.primary.map.get_name().to_string(),
+            on_load,
+        };
+        chooser.rebuild(ctx, app);
+        Box::new(chooser)
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, app: &App) {
+        let current = app.primary.map.get_name();
+        let mut maps = Vec::new();
+        for name in abstutil::list_all_objects(abstutil::path_all_maps()) {
+            let label = if &name == current {
+                format!("{} (open now)", nice_map_name(&name))
+            } else {
+                nice_map_name(&name).to_string()
+            };
+            let btn = Btn::txt(format!("select {}", name), Text::from(Line(label)));
+            maps.push(if name == self.selected {
+                btn.inactive(ctx)
+            } else {
+                btn.build_def(ctx, None)
+            });
+        }
+
+        self.composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("Choose a map").small_heading().draw(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Widget::row(vec![
+                Widget::col(maps).margin_right(20),
+                self.details(ctx, app),
+            ]),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+        .max_size_percent(80, 80)
+        .build(ctx);
+    }
+
+    fn details(&self, ctx: &mut EventCtx, app: &App) -> Widget {
+        let name = &self.selected;
+        let mut col = vec![Line(nice_map_name(name)).small_heading().draw(ctx)];
+
+        let mut txt = Text::new();
+        if let Ok(metadata) = std::fs::metadata(abstutil::path_map(name)) {
+            txt.add(Line(format!(
+                "{:.1} MB on disk",
+                (metadata.len() as f64) / 1024.0 / 1024.0
+            )));
+        }
+        match MapSummary::load(name) {
+            Some(summary) => {
+                let zoom = MapSummary::THUMBNAIL_SIZE
+                    / summary
+                        .width
+                        .inner_meters()
+                        .max(summary.height.inner_meters());
+                let mut batch = GeomBatch::new();
+                batch.push(
+                    app.cs.map_background,
+                    Polygon::rectangle(
+                        zoom * summary.width.inner_meters(),
+                        zoom * summary.height.inner_meters(),
+                    ),
+                );
+                for (pt1, pt2) in &summary.thumbnail {
+                    if let Some(line) = geom::Line::new(*pt1, *pt2) {
+                        batch.push(
+                            app.cs.unzoomed_residential,
+                            line.make_polygons(Distance::meters(1.0)),
+                        );
+                    }
+                }
+                col.push(Widget::draw_batch(ctx, batch).margin_below(10));
+
+                txt.add(Line(format!(
+                    "{} by {}",
+                    summary.width.describe_rounded(&app.opts.units),
+                    summary.height.describe_rounded(&app.opts.units)
+                )));
+                txt.add(Line(format!(
+                    "{} roads, {} intersections, {} buildings",
+                    abstutil::prettyprint_usize(summary.num_roads),
+                    abstutil::prettyprint_usize(summary.num_intersections),
+                    abstutil::prettyprint_usize(summary.num_buildings)
+                )));
+                for line in summary.provenance.describe() {
+                    txt.add(Line(line).secondary());
+                }
+                if let Some(then) = summary.provenance.osm_date {
+                    txt.add(
+                        Line(format!("OSM file last modified {}", describe_ago(then))).secondary(),
+                    );
+                }
+            }
+            None => {
+                txt.add(Line("Not opened yet, so there's no preview").secondary());
+            }
+        }
+        if let Some(then) = load_last_opened().get(name) {
+            txt.add(Line(format!("Last opened {}", describe_ago(*then))));
+        }
+
+        let scenarios = abstutil::list_all_objects(abstutil::path_all_scenarios(name));
+        if scenarios.is_empty() {
+            txt.add(Line("No scenarios"));
+        } else {
+            txt.add(Line(format!("Scenarios: {}", scenarios.join(", "))));
+        }
+        col.push(txt.draw(ctx).margin_below(10));
+
+        col.push("Saved edits:".draw_text(ctx));
+        let all_edits = abstutil::list_all_objects(abstutil::path_all_edits(name));
+        if all_edits.is_empty() {
+            col.push(Line("None").secondary().draw(ctx));
+        }
+        for edits in all_edits {
+            // The edits in use can't be pulled out from under the map
+            if name == app.primary.map.get_name() && edits == app.primary.map.get_edits().edits_name
+            {
+                col.push(format!("{} (in use)", edits).draw_text(ctx));
+                continue;
+            }
+            col.push(Widget::row(vec![
+                edits.clone().draw_text(ctx),
+                Btn::text_fg("rename")
+                    .build(ctx, format!("rename {}", edits), None)
+                    .align_right(),
+                Btn::text_fg("delete").build(ctx, format!("delete {}", edits), None),
+            ]));
+        }
+
+        if name != app.primary.map.get_name() {
+            col.push(
+                Widget::row(vec![
+                    Btn::text_bg2("Load this map").build_def(ctx, hotkey(Key::Enter)),
+                    Btn::text_bg2("Delete this map").build_def(ctx, None),
+                ])
+                .margin_above(10),
+            );
+        }
+
+        Widget::col(col)
+    }
+}
+
+impl State for MapChooser {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => {
+                if x == "close" {
+                    return Transition::Pop;
+                } else if x == "Load this map" {
+                    // Nothing calls this once the loader takes over
+                    let on_load =
+                        std::mem::replace(&mut self.on_load, Box::new(|_, _| Transition::Keep));
+                    return Transition::Replace(MapLoader::new(
+                        ctx,
+                        app,
+                        self.selected.clone(),
+                        on_load,
+                    ));
+                } else if x == "Delete this map" {
+                    let name = self.selected.clone();
+                    app.notifications.confirm(
+                        ctx,
+                        Text::from_multiline(vec![
+                            Line(format!("Really delete {}?", nice_map_name(&name))),
+                            Line("Its scenarios and saved edits are kept.").secondary(),
+                        ]),
+                        Box::new(move |ctx, app| {
+                            delete_map(ctx, app, &name);
+                            refresh()
+                        }),
+                    );
+                } else if let Some(name) = x.strip_prefix("select ") {
+                    self.selected = name.to_string();
+                    self.rebuild(ctx, app);
+                } else if let Some(edits) = x.strip_prefix("rename ") {
+                    return Transition::Push(rename_edits(&self.selected, edits));
+                } else if let Some(edits) = x.strip_prefix("delete ") {
+                    let path = abstutil::path_edits(&self.selected, edits);
+                    app.notifications.confirm(
+                        ctx,
+                        Text::from(Line(format!("Really delete the edits {}?", edits))),
+                        Box::new(move |ctx, app| {
+                            if let Err(err) = std::fs::remove_file(&path) {
+                                app.notifications.toast(
+                                    ctx,
+                                    Text::from(Line(format!("Couldn't delete {}: {}", path, err))),
+                                );
+                            }
+                            refresh()
+                        }),
+                    );
+                } else {
+                    unreachable!()
+                }
+            }
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+fn rename_edits(map_name: &str, old_name: &str) -> Box<dyn State> {
+    let map_name = map_name.to_string();
+    let old_name = old_name.to_string();
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let new_name = wiz
+            .wrap(ctx)
+            .input_string(&format!("Rename {} to what?", old_name))?;
+        let new_name = new_name.trim();
+        let new_path = abstutil::path_edits(&map_name, new_name);
+        let problem = if new_name.is_empty() || new_name.contains('/') {
+            Some(format!("\"{}\" isn't a valid name", new_name))
+        } else if abstutil::file_exists(new_path.clone()) {
+            Some(format!("There are already edits called {}", new_name))
+        } else {
+            let old_path = abstutil::path_edits(&map_name, &old_name);
+            match abstutil::maybe_read_json::<PermanentMapEdits>(
+                old_path.clone(),
+                &mut abstutil::Timer::throwaway(),
+            ) {
+                Ok(mut perma) => {
+                    perma.edits_name = new_name.to_string();
+                    abstutil::write_json(new_path, &perma);
+                    std::fs::remove_file(&old_path)
+                        .err()
+                        .map(|err| format!("Couldn't delete {}: {}", old_path, err))
+                }
+                Err(err) => Some(format!("Couldn't load {}: {}", old_path, err)),
+            }
+        };
+        if let Some(msg) = problem {
+            app.notifications.toast(ctx, Text::from(Line(msg)));
+        }
+        Some(Transition::PopWithData(Box::new(|state, ctx, app| {
+            state
+                .downcast_mut::<MapChooser>()
+                .unwrap()
+                .rebuild(ctx, app);
+        })))
+    }))
+}
+
+// Just the map and what's derived from it
+fn delete_map(ctx: &mut EventCtx, app: &mut App, name: &str) {
+    if let Err(err) = std::fs::remove_file(abstutil::path_map(name)) {
+        app.notifications.toast(
+            ctx,
+            Text::from(Line(format!(
+                "Couldn't delete {}: {}",
+                nice_map_name(name),
+                err
+            ))),
+        );
+        return;
+    }
+    // Only meaningful next to the map
+    let _ = std::fs::remove_file(abstutil::path_map_provenance(name));
+    MapSummary::delete(name);
+    let mut last_opened = load_last_opened();
+    if last_opened.remove(name).is_some() {
+        abstutil::write_json(abstutil::path_maps_last_opened(), &last_opened);
+    }
+    app.notifications.toast(
+        ctx,
+        Text::from(Line(format!("Deleted {}", nice_map_name(name)))),
+    );
+}
+
+// For after a question is answered; the chooser might not be open anymore by then.
+fn refresh() -> Transition {
+    Transition::KeepWithData(Box::new(|state, ctx, app| {
+        if let Some(chooser) = state.downcast_mut::<MapChooser>() {
+            // The selected map might've just been deleted
+            if !abstutil::file_exists(abstutil::path_map(&chooser.selected)) {
+                chooser.selected = app.primary.map.get_name().to_string();
+            }
+            chooser.rebuild(ctx, app);
+        }
+    }))
+}
+
+fn seconds_since_epoch() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|dt| dt.as_secs())
+        .unwrap_or(0)
+}
+
+fn describe_ago(then: u64) -> String {
+    let secs = seconds_since_epoch().saturating_sub(then);
+    if secs < 60 * 60 {
+        "in the last hour".to_string()
+    } else if secs < 24 * 60 * 60 {
+        plural(secs / 60 / 60, "hour")
+    } else {
+        plural(secs / 60 / 60 / 24, "day")
+    }
+}
+
+fn plural(n: u64, unit: &str) -> String {
+    if n == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", n, unit)
+    }
+}
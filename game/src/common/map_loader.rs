@@ -0,0 +1,168 @@
+use crate::app::{App, LoadedMap};
+use crate::colors::ColorScheme;
+use crate::game::{msg, State, Transition};
+use crate::helpers::nice_map_name;
+use crate::render::DrawMap;
+use abstutil::{Timer, TimerSink};
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Text,
+    UpdateType, VerticalAlignment, Widget,
+};
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+
+// How many lines of the loading Timer to show
+const MAX_LINES: usize = 20;
+
+// Loads a map and does the slow part of rendering it on a background thread, showing the progress
+// as it goes. The window stays responsive, and the old map can still be panned around. Only
+// uploading to the GPU happens on the UI thread, once everything else is done.
+pub struct MapLoader {
+    composite: Composite,
+    name: String,
+    progress: Arc<Mutex<Progress>>,
+    result: Receiver<LoadedMap>,
+    on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+}
+
+impl MapLoader {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        name: String,
+        on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+    ) -> Box<dyn State> {
+        let mut flags = app.primary.current_flags.clone();
+        flags.sim_flags.load = abstutil::path_map(&name);
+        let color_scheme = app.opts.color_scheme;
+        let progress = Arc::new(Mutex::new(Progress {
+            lines: VecDeque::new(),
+            changed: true,
+        }));
+        let (tx, rx) = channel();
+
+        let sink = ProgressSink(progress.clone());
+        let timer_name = format!("load {}", name);
+        let work = move || {
+            let mut timer = Timer::new_with_sink(&timer_name, Box::new(sink));
+            let cs = ColorScheme::new(color_scheme);
+            let (map, sim, _) = flags.sim_flags.load(&mut timer);
+            let geometry = DrawMap::calculate_geometry(&map, &cs, &mut timer);
+            // If the player cancelled, nobody's listening anymore
+            let _ = tx.send(LoadedMap {
+                flags,
+                map,
+                sim,
+                geometry,
+            });
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(work);
+        // No threads in the browser, so just block
+        #[cfg(target_arch = "wasm32")]
+        work();
+
+        let mut loader = MapLoader {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            name,
+            progress,
+            result: rx,
+            on_load,
+        };
+        loader.refresh(ctx);
+        Box::new(loader)
+    }
+
+    fn refresh(&mut self, ctx: &mut EventCtx) {
+        let mut progress = self.progress.lock().unwrap();
+        if !progress.changed {
+            return;
+        }
+        progress.changed = false;
+
+        let mut txt = Text::new();
+        for line in &progress.lines {
+            txt.add(Line(line));
+        }
+        self.composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Line(format!("Loading {}", nice_map_name(&self.name)))
+                    .small_heading()
+                    .draw(ctx),
+                Btn::text_fg("Cancel")
+                    .build_def(ctx, hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            txt.draw(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+        .build(ctx);
+    }
+}
+
+impl State for MapLoader {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.result.try_recv() {
+            Ok(loaded) => {
+                app.switch_to_loaded(ctx, loaded);
+                return (self.on_load)(ctx, app);
+            }
+            Err(TryRecvError::Disconnected) => {
+                // The thread panicked; the details are in the console
+                return Transition::Replace(msg(
+                    "Error",
+                    vec![format!("Couldn't load {}", nice_map_name(&self.name))],
+                ));
+            }
+            Err(TryRecvError::Empty) => {}
+        }
+
+        ctx.canvas_movement();
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                // The thread keeps going, but its result is dropped
+                "Cancel" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        // Keep checking for more progress
+        ctx.request_update(UpdateType::Game);
+        self.refresh(ctx);
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+struct Progress {
+    lines: VecDeque<String>,
+    changed: bool,
+}
+
+struct ProgressSink(Arc<Mutex<Progress>>);
+
+impl TimerSink for ProgressSink {
+    fn println(&mut self, line: String) {
+        let mut progress = self.0.lock().unwrap();
+        if progress.lines.len() == MAX_LINES {
+            progress.lines.pop_front();
+        }
+        progress.lines.push_back(line);
+        progress.changed = true;
+    }
+
+    fn reprintln(&mut self, line: String) {
+        let mut progress = self.0.lock().unwrap();
+        progress.lines.pop_back();
+        progress.lines.push_back(line);
+        progress.changed = true;
+    }
+}
@@ -1,22 +1,33 @@
 mod city_picker;
 mod colors;
+mod file_browser;
 mod heatmap;
 mod isochrone;
+mod lasso;
+mod map_chooser;
+mod map_loader;
 mod minimap;
 mod navigate;
 mod panels;
+pub mod permalink;
 mod warp;
 
 pub use self::city_picker::CityPicker;
 pub use self::colors::{ColorDiscrete, ColorLegend, ColorNetwork, ColorScale, DivergingScale};
+pub use self::file_browser::{open_file, FileBrowser};
 pub use self::heatmap::{make_heatmap, HeatmapOptions};
 pub use self::isochrone::IsochroneViewer;
+pub use self::lasso::Lasso;
+pub use self::map_chooser::{record_opened, MapChooser};
+pub use self::map_loader::MapLoader;
 pub use self::minimap::Minimap;
+pub use self::navigate::SearchByName;
 pub use self::panels::tool_panel;
+pub use self::permalink::Permalink;
 pub use self::warp::Warping;
 use crate::app::App;
 use crate::game::Transition;
-use crate::helpers::{list_names, ID};
+use crate::helpers::{copy_to_clipboard, list_names, ID};
 use crate::info::InfoPanel;
 pub use crate::info::{ContextualActions, Tab};
 use ezgui::{
@@ -54,6 +65,21 @@ impl CommonState {
         if app.opts.dev && ctx.input.new_was_pressed(&lctrl(Key::J).unwrap()) {
             return Some(Transition::Push(warp::EnteringWarp::new()));
         }
+        if ctx.input.new_was_pressed(&lctrl(Key::K).unwrap()) {
+            let uri = Permalink::current(ctx, app).to_uri();
+            copy_to_clipboard(ctx, app, uri);
+        }
+        if ctx.input.new_was_pressed(&lctrl(Key::C).unwrap()) {
+            // In the same form the warp box takes
+            if let Some(code) = app
+                .primary
+                .current_selection
+                .as_ref()
+                .and_then(warp::warp_code)
+            {
+                copy_to_clipboard(ctx, app, code);
+            }
+        }
 
         if let Some(id) = app.primary.current_selection.clone() {
             // TODO Also have a hotkey binding for this?
@@ -204,6 +230,13 @@ impl CommonState {
                     .collect();
                 list_names(&mut osd, |l| l.fg(name_color), routes);
             }
+            ID::BusRoute(r) => {
+                if app.opts.dev {
+                    osd.append(Line(r.to_string()).fg(id_color));
+                    osd.append(Line(" is "));
+                }
+                osd.append(Line(&map.get_br(r).full_name).fg(name_color));
+            }
             ID::Area(a) => {
                 // Only selectable in dev mode anyway
                 osd.append(Line(a.to_string()).fg(id_color));
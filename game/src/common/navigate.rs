@@ -3,11 +3,15 @@ use crate::common::Warping;
 use crate::game::{State, Transition};
 use crate::helpers::ID;
 use ezgui::{
-    hotkey, Autocomplete, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, Key, Line,
-    Outcome, Text, Widget,
+    hotkey, Autocomplete, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
+use geom::Pt2D;
 use map_model::RoadID;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+
+// Don't list every building on "Main St"
+const MAX_RESULTS: usize = 30;
 
 // TODO Canonicalize names, handling abbreviations like east/e and street/st
 pub struct Navigator {
@@ -187,3 +191,158 @@ impl State for CrossStreet {
         self.composite.draw(g);
     }
 }
+
+// Finds roads and buildings by name. Picking one warps there and selects it. The selection is drawn
+// like any other, so whatever the map layers hide stays hidden.
+pub struct SearchByName {
+    composite: Composite,
+    query: String,
+    // None until something's been searched for. The index of each is the "result N" button.
+    matches: Option<Vec<(String, ID, Pt2D)>>,
+    chosen: Option<ID>,
+}
+
+impl SearchByName {
+    pub fn new(ctx: &mut EventCtx) -> Box<dyn State> {
+        let mut search = SearchByName {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            query: String::new(),
+            matches: None,
+            chosen: None,
+        };
+        search.rebuild(ctx);
+        Box::new(search)
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx) {
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Search roads and buildings").small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Widget::row(vec![
+                Widget::text_entry(ctx, self.query.clone(), true).named("query"),
+                Btn::text_bg2("search").build_def(ctx, hotkey(Key::Enter)),
+            ]),
+        ];
+
+        if let Some(ref matches) = self.matches {
+            if matches.is_empty() {
+                col.push("Nothing found".draw_text(ctx));
+            }
+            for (idx, (label, id, _)) in matches.iter().take(MAX_RESULTS).enumerate() {
+                let btn = Btn::txt(format!("result {}", idx), Text::from(Line(label)));
+                col.push(if self.chosen.as_ref() == Some(id) {
+                    btn.inactive(ctx)
+                } else {
+                    btn.build_def(ctx, None)
+                });
+            }
+            if matches.len() > MAX_RESULTS {
+                col.push(
+                    Line(format!(
+                        "{} more; try a longer search",
+                        matches.len() - MAX_RESULTS
+                    ))
+                    .secondary()
+                    .draw(ctx),
+                );
+            }
+        }
+
+        self.composite = Composite::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .max_size_percent(30, 70)
+            .build(ctx);
+    }
+}
+
+fn search(app: &App, query: &str) -> Vec<(String, ID, Pt2D)> {
+    let map = &app.primary.map;
+    let (roads, bldgs) = map.search_by_name(query);
+
+    // A street is usually split into many roads, so only list each name once, warping to the
+    // middle piece.
+    let mut by_name: BTreeMap<String, Vec<RoadID>> = BTreeMap::new();
+    for r in roads {
+        by_name
+            .entry(map.get_r(r).get_name())
+            .or_insert_with(Vec::new)
+            .push(r);
+    }
+    let mut matches = Vec::new();
+    for (name, roads) in by_name {
+        let r = map.get_r(roads[roads.len() / 2]);
+        matches.push((
+            format!("{} (street)", name),
+            ID::Road(r.id),
+            r.center_pts.middle(),
+        ));
+    }
+    for b in bldgs {
+        let b = map.get_b(b);
+        let name = b.name.as_ref().or_else(|| {
+            b.amenities
+                .iter()
+                .map(|(name, _)| name)
+                .find(|name| *name != "unnamed")
+        });
+        matches.push((
+            match name {
+                Some(name) => format!("{} ({})", name, b.address),
+                None => b.address.clone(),
+            },
+            ID::Building(b.id),
+            b.label_center,
+        ));
+    }
+    matches
+}
+
+impl State for SearchByName {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        // Whatever's underneath would normally reset the selection when the mouse moves
+        app.primary.current_selection = self.chosen.clone();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    app.primary.current_selection = None;
+                    return Transition::Pop;
+                }
+                "search" => {
+                    self.query = self.composite.text_box("query").trim().to_string();
+                    if !self.query.is_empty() {
+                        self.matches = Some(search(app, &self.query));
+                        self.chosen = None;
+                        self.rebuild(ctx);
+                    }
+                }
+                x => {
+                    let idx = x["result ".len()..].parse::<usize>().unwrap();
+                    let (_, id, pt) = self.matches.as_ref().unwrap()[idx].clone();
+                    self.chosen = Some(id.clone());
+                    app.primary.current_selection = Some(id);
+                    self.rebuild(ctx);
+                    return Transition::Push(Warping::new(
+                        ctx,
+                        pt,
+                        Some(app.opts.min_zoom_for_detail),
+                        None,
+                        &mut app.primary,
+                    ));
+                }
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::app::App;
+use crate::common::Warping;
+use crate::game::State;
+use crate::helpers::ID;
+use ezgui::EventCtx;
+use geom::{LonLat, Pt2D};
+use map_model::{AreaID, BuildingID, IntersectionID, LaneID, ParkingLotID, RoadID};
+use std::sync::mpsc::Receiver;
+
+const SCHEME: &str = "abst://map/";
+// An already running game listens here for permalinks opened from somewhere else.
+const PORT: u16 = 7346;
+const DEFAULT_ZOOM: f64 = 10.0;
+
+// Something like abst://map/seattle/lane/423?zoom=5 or abst://map/seattle?lon=-122.3&lat=47.6.
+// Object IDs are only stable for one version of a map, so these are meant for bug reports and
+// discussions, not long-term bookmarks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Permalink {
+    pub map: String,
+    // Only objects that're part of the map; agents don't exist across runs.
+    pub id: Option<ID>,
+    // If there's no ID, just point the camera here.
+    pub pt: Option<LonLat>,
+    pub zoom: Option<f64>,
+}
+
+impl Permalink {
+    // The selected object if it's part of the map, otherwise the current camera position.
+    pub fn current(ctx: &EventCtx, app: &App) -> Permalink {
+        let id = match app.primary.current_selection {
+            Some(ID::Road(_))
+            | Some(ID::Lane(_))
+            | Some(ID::Intersection(_))
+            | Some(ID::Building(_))
+            | Some(ID::ParkingLot(_))
+            | Some(ID::Area(_)) => app.primary.current_selection.clone(),
+            _ => None,
+        };
+        let pt = if id.is_some() {
+            None
+        } else {
+            Some(
+                ctx.canvas
+                    .center_to_map_pt()
+                    .to_gps(app.primary.map.get_gps_bounds()),
+            )
+        };
+        Permalink {
+            map: app.primary.map.get_name().to_string(),
+            id,
+            pt,
+            zoom: Some(ctx.canvas.cam_zoom),
+        }
+    }
+
+    pub fn parse(uri: &str) -> Result<Permalink, String> {
+        if !uri.starts_with(SCHEME) {
+            return Err(format!("{} doesn't start with {}", uri, SCHEME));
+        }
+        let mut parts = uri[SCHEME.len()..].splitn(2, '?');
+        let path: Vec<&str> = parts.next().unwrap().split('/').collect();
+        let query = parts.next().unwrap_or("");
+
+        let mut lon = None;
+        let mut lat = None;
+        let mut zoom = None;
+        for pair in query.split('&').filter(|x| !x.is_empty()) {
+            let mut kv = pair.splitn(2, '=');
+            let key = kv.next().unwrap();
+            let value = kv
+                .next()
+                .ok_or(format!("{} is missing a value", key))?
+                .parse::<f64>()
+                .map_err(|_| format!("{} isn't a number", pair))?;
+            match key {
+                "lon" => {
+                    lon = Some(value);
+                }
+                "lat" => {
+                    lat = Some(value);
+                }
+                "zoom" => {
+                    zoom = Some(value);
+                }
+                _ => {
+                    return Err(format!("Unknown parameter {}", key));
+                }
+            }
+        }
+
+        let id = match path.len() {
+            1 => None,
+            3 => {
+                let idx = path[2]
+                    .parse::<usize>()
+                    .map_err(|_| format!("{} isn't a valid ID", path[2]))?;
+                Some(match path[1] {
+                    "road" => ID::Road(RoadID(idx)),
+                    "lane" => ID::Lane(LaneID(idx)),
+                    "intersection" => ID::Intersection(IntersectionID(idx)),
+                    "building" => ID::Building(BuildingID(idx)),
+                    "parking_lot" => ID::ParkingLot(ParkingLotID(idx)),
+                    "area" => ID::Area(AreaID(idx)),
+                    x => {
+                        return Err(format!("Unknown object type {}", x));
+                    }
+                })
+            }
+            _ => {
+                return Err(format!("{} should be map/name or map/name/type/id", uri));
+            }
+        };
+        if path[0].is_empty() {
+            return Err(format!("{} is missing the map name", uri));
+        }
+        let pt = match (lon, lat) {
+            (Some(lon), Some(lat)) => Some(LonLat::new(lon, lat)),
+            (None, None) => None,
+            _ => {
+                return Err(format!("{} needs both lon and lat", uri));
+            }
+        };
+
+        Ok(Permalink {
+            map: path[0].to_string(),
+            id,
+            pt,
+            zoom,
+        })
+    }
+
+    pub fn to_uri(&self) -> String {
+        let mut uri = format!("{}{}", SCHEME, self.map);
+        match self.id {
+            Some(ID::Road(r)) => uri.push_str(&format!("/road/{}", r.0)),
+            Some(ID::Lane(l)) => uri.push_str(&format!("/lane/{}", l.0)),
+            Some(ID::Intersection(i)) => uri.push_str(&format!("/intersection/{}", i.0)),
+            Some(ID::Building(b)) => uri.push_str(&format!("/building/{}", b.0)),
+            Some(ID::ParkingLot(pl)) => uri.push_str(&format!("/parking_lot/{}", pl.0)),
+            Some(ID::Area(a)) => uri.push_str(&format!("/area/{}", a.0)),
+            _ => {}
+        }
+        let mut query = Vec::new();
+        if let Some(pt) = self.pt {
+            query.push(format!("lon={}", pt.x()));
+            query.push(format!("lat={}", pt.y()));
+        }
+        if let Some(zoom) = self.zoom {
+            query.push(format!("zoom={:.2}", zoom));
+        }
+        if !query.is_empty() {
+            uri.push('?');
+            uri.push_str(&query.join("&"));
+        }
+        uri
+    }
+
+    // Fails if the permalink is for a different map or refers to something that doesn't exist.
+    pub fn warp(&self, ctx: &EventCtx, app: &mut App) -> Result<Box<dyn State>, String> {
+        if self.map != app.primary.map.get_name() {
+            return Err(format!(
+                "{} is for {}, but {} is loaded",
+                self.to_uri(),
+                self.map,
+                app.primary.map.get_name()
+            ));
+        }
+        let zoom = Some(self.zoom.unwrap_or(DEFAULT_ZOOM));
+        if let Some(ref id) = self.id {
+            // There's no info panel for entire roads
+            let id = match id {
+                ID::Road(r) => ID::Lane(
+                    app.primary
+                        .map
+                        .maybe_get_r(*r)
+                        .ok_or(format!("{} doesn't exist", r))?
+                        .all_lanes()[0],
+                ),
+                _ => id.clone(),
+            };
+            let pt = id
+                .canonical_point(&app.primary)
+                .ok_or(format!("{:?} doesn't exist", id))?;
+            return Ok(Warping::new(ctx, pt, zoom, Some(id), &mut app.primary));
+        }
+        let pt = match self.pt {
+            Some(gps) => Pt2D::from_gps(gps, app.primary.map.get_gps_bounds()),
+            None => app.primary.map.get_bounds().center(),
+        };
+        Ok(Warping::new(ctx, pt, zoom, None, &mut app.primary))
+    }
+}
+
+// Returns false if no game is running to receive it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn send_to_running_game(uri: &str) -> bool {
+    use std::io::Write;
+
+    match std::net::TcpStream::connect(("127.0.0.1", PORT)) {
+        Ok(mut stream) => stream.write_all(uri.as_bytes()).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn send_to_running_game(_: &str) -> bool {
+    false
+}
+
+// Only one game gets to listen; if another is already listening, this returns None. Permalinks
+// arrive in the background and get handled the next time the game has an event to process.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn listen() -> Option<Receiver<String>> {
+    use std::io::Read;
+
+    let listener = match std::net::TcpListener::bind(("127.0.0.1", PORT)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            println!("Can't listen for links on port {}: {}", PORT, err);
+            return None;
+        }
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut uri = String::new();
+            if let Ok(mut stream) = stream {
+                if stream.read_to_string(&mut uri).is_ok() && tx.send(uri).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Some(rx)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn listen() -> Option<Receiver<String>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_round_trip() {
+        use super::Permalink;
+        use crate::helpers::ID;
+        use geom::LonLat;
+        use map_model::LaneID;
+
+        for link in vec![
+            Permalink {
+                map: "montlake".to_string(),
+                id: Some(ID::Lane(LaneID(423))),
+                pt: None,
+                zoom: Some(5.0),
+            },
+            Permalink {
+                map: "montlake".to_string(),
+                id: None,
+                pt: Some(LonLat::new(-122.3, 47.6)),
+                zoom: None,
+            },
+            Permalink {
+                map: "23rd".to_string(),
+                id: None,
+                pt: None,
+                zoom: None,
+            },
+        ] {
+            assert_eq!(Ok(link.clone()), Permalink::parse(&link.to_uri()));
+        }
+
+        assert!(Permalink::parse("abst://map/montlake/lane").is_err());
+        assert!(Permalink::parse("abst://map/montlake?lon=-122.3").is_err());
+        assert!(Permalink::parse("http://map/montlake").is_err());
+    }
+}
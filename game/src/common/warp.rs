@@ -5,7 +5,7 @@ use crate::helpers::ID;
 use crate::info::OpenTrip;
 use crate::sandbox::SandboxMode;
 use ezgui::{EventCtx, GfxCtx, Warper, Wizard};
-use geom::Pt2D;
+use geom::{LonLat, Pt2D};
 use map_model::{AreaID, BuildingID, IntersectionID, LaneID, RoadID};
 use sim::{PedestrianID, PersonID, TripID};
 use std::collections::BTreeMap;
@@ -97,6 +97,15 @@ fn inner_warp(ctx: &mut EventCtx, app: &mut App, line: &str) -> Option<Transitio
         }
         return None;
     }
+    if let Some(pt) = parse_coordinates(app, line) {
+        return Some(Transition::Replace(Warping::new(
+            ctx,
+            pt,
+            Some(WARP_TO_CAM_ZOOM),
+            None,
+            &mut app.primary,
+        )));
+    }
 
     let id = match usize::from_str_radix(&line[1..line.len()], 10) {
         Ok(idx) => match line.chars().next().unwrap() {
@@ -167,3 +176,38 @@ fn inner_warp(ctx: &mut EventCtx, app: &mut App, line: &str) -> Option<Transitio
         None
     }
 }
+
+// The inverse of inner_warp, for the objects that have a short code
+pub fn warp_code(id: &ID) -> Option<String> {
+    match id {
+        ID::Road(r) => Some(format!("r{}", r.0)),
+        ID::Lane(l) => Some(format!("l{}", l.0)),
+        ID::Intersection(i) => Some(format!("i{}", i.0)),
+        ID::Building(b) => Some(format!("b{}", b.0)),
+        ID::Area(a) => Some(format!("a{}", a.0)),
+        ID::Pedestrian(p) => Some(format!("p{}", p.0)),
+        ID::Car(c) => Some(format!("c{}", c.0)),
+        _ => None,
+    }
+}
+
+// Something like "47.6535, -122.3059" pasted from another map. Most places list latitude first,
+// but longitude-first works too, as long as the point is somewhere in this map.
+fn parse_coordinates(app: &App, line: &str) -> Option<Pt2D> {
+    let nums = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    if nums.len() != 2 {
+        return None;
+    }
+    let gps_bounds = app.primary.map.get_gps_bounds();
+    for gps in vec![LonLat::new(nums[1], nums[0]), LonLat::new(nums[0], nums[1])] {
+        if gps_bounds.contains(gps) {
+            return Some(Pt2D::from_gps(gps, gps_bounds));
+        }
+    }
+    None
+}
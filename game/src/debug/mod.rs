@@ -1,14 +1,15 @@
 mod floodfill;
 mod objects;
 mod polygons;
+mod quadtree;
 pub mod shared_row;
 
 use crate::app::{App, ShowLayers, ShowObject};
-use crate::common::{tool_panel, CommonState, ContextualActions};
+use crate::common::{tool_panel, CommonState, ContextualActions, FileBrowser};
 use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
 use crate::helpers::ID;
 use crate::managed::{WrappedComposite, WrappedOutcome};
-use crate::render::{calculate_corners, DrawOptions};
+use crate::render::{all_heatmap_sources, calculate_corners, DrawOptions, HeatmapOverlay};
 use abstutil::Timer;
 use ezgui::{
     hotkey, lctrl, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
@@ -28,6 +29,7 @@ pub struct DebugMode {
     layers: ShowLayers,
     search_results: Option<SearchResults>,
     all_routes: Option<(usize, Drawable)>,
+    quadtree: Option<quadtree::QuadtreeViz>,
 
     highlighted_agents: Option<(ID, Drawable)>,
 }
@@ -48,7 +50,9 @@ impl DebugMode {
                 Checkbox::text(ctx, "show lanes", hotkey(Key::Num3), true),
                 Checkbox::text(ctx, "show areas", hotkey(Key::Num4), true),
                 Checkbox::text(ctx, "show labels", hotkey(Key::Num5), false),
+                Checkbox::text(ctx, "show bus routes", hotkey(Key::Num6), false),
                 Checkbox::text(ctx, "show route for all agents", hotkey(Key::R), false),
+                Checkbox::text(ctx, "show quadtree", None, false),
                 Widget::col(
                     vec![
                         (lctrl(Key::H), "unhide everything"),
@@ -59,8 +63,12 @@ impl DebugMode {
                         (hotkey(Key::Y), "load previous sim state"),
                         (hotkey(Key::U), "load next sim state"),
                         (None, "pick a savestate to load"),
+                        (lctrl(Key::O), "save checkpoint"),
+                        (lctrl(Key::Y), "restore checkpoint"),
                         (None, "find bad traffic signals"),
                         (None, "find degenerate roads"),
+                        (None, "pick a heatmap overlay"),
+                        (None, "export geometry to GeoJSON"),
                     ]
                     .into_iter()
                     .map(|(key, action)| Btn::text_fg(action).build_def(ctx, key))
@@ -76,6 +84,7 @@ impl DebugMode {
             layers: ShowLayers::new(),
             search_results: None,
             all_routes: None,
+            quadtree: None,
             highlighted_agents: None,
         }
     }
@@ -97,6 +106,12 @@ impl DebugMode {
                 abstutil::prettyprint_usize(n)
             )));
         }
+        if let Some(ref viz) = self.quadtree {
+            txt.add(Line(format!(
+                "Viewport touches {} quadtree cells",
+                abstutil::prettyprint_usize(viz.num_touched)
+            )));
+        }
         self.composite
             .replace(ctx, "current info", txt.draw(ctx).named("current info"));
     }
@@ -135,6 +150,7 @@ impl State for DebugMode {
                             }) {
                                 Some(new_sim) => {
                                     app.primary.sim = new_sim;
+                                    app.primary.checkpoints.clear();
                                     app.recalculate_current_selection(ctx);
                                     None
                                 }
@@ -160,6 +176,7 @@ impl State for DebugMode {
                         }) {
                             Some(new_sim) => {
                                 app.primary.sim = new_sim;
+                                app.primary.checkpoints.clear();
                                 app.recalculate_current_selection(ctx);
                                 None
                             }
@@ -175,6 +192,78 @@ impl State for DebugMode {
                 "pick a savestate to load" => {
                     return Transition::Push(WizardState::new(Box::new(load_savestate)));
                 }
+                "save checkpoint" => {
+                    return Transition::Push(FileBrowser::save(
+                        ctx,
+                        abstutil::path("player/checkpoints/"),
+                        format!(
+                            "{}_{}.bin",
+                            app.primary.map.get_name(),
+                            app.primary.sim.time().as_filename()
+                        ),
+                        Box::new(|ctx, app, path| {
+                            let result = ctx.loading_screen("save checkpoint", |_, _| {
+                                app.primary.sim.save_checkpoint(path)
+                            });
+                            match result {
+                                Ok(()) => Transition::Pop,
+                                Err(err) => {
+                                    Transition::Replace(msg("Can't save checkpoint", vec![err]))
+                                }
+                            }
+                        }),
+                    ));
+                }
+                "restore checkpoint" => {
+                    return Transition::Push(FileBrowser::open(
+                        ctx,
+                        abstutil::path("player/checkpoints/"),
+                        vec!["bin"],
+                        Box::new(|ctx, app, path| {
+                            let result = ctx.loading_screen("restore checkpoint", |_, timer| {
+                                Sim::load_checkpoint(path, &app.primary.map, timer)
+                            });
+                            match result {
+                                Ok(sim) => {
+                                    app.primary.sim = sim;
+                                    app.primary.checkpoints.clear();
+                                    app.recalculate_current_selection(ctx);
+                                    Transition::Pop
+                                }
+                                Err(err) => {
+                                    Transition::Replace(msg("Can't restore checkpoint", vec![err]))
+                                }
+                            }
+                        }),
+                    ));
+                }
+                "export geometry to GeoJSON" => {
+                    return Transition::Push(FileBrowser::save(
+                        ctx,
+                        ".".to_string(),
+                        format!("{}_geometry.geojson", app.primary.map.get_name()),
+                        Box::new(|ctx, app, path| {
+                            match app
+                                .primary
+                                .draw_map
+                                .export_geojson(&app.primary.map, path.clone())
+                            {
+                                Ok(()) => {
+                                    app.notifications
+                                        .toast(ctx, Text::from(Line(format!("Wrote {}", path))));
+                                    Transition::Pop
+                                }
+                                Err(err) => Transition::Replace(msg(
+                                    "Can't export geometry",
+                                    vec![err.to_string()],
+                                )),
+                            }
+                        }),
+                    ));
+                }
+                "pick a heatmap overlay" => {
+                    return Transition::Push(WizardState::new(Box::new(pick_heatmap_overlay)));
+                }
                 "unhide everything" => {
                     self.hidden.clear();
                     app.primary.current_selection = app.calculate_current_selection(
@@ -221,6 +310,13 @@ impl State for DebugMode {
         self.layers.show_lanes = self.composite.is_checked("show lanes");
         self.layers.show_areas = self.composite.is_checked("show areas");
         self.layers.show_labels = self.composite.is_checked("show labels");
+        let show_bus_routes = self.composite.is_checked("show bus routes");
+        if show_bus_routes && !self.layers.show_bus_routes {
+            app.primary
+                .draw_map
+                .render_bus_routes(ctx, &app.primary.map, &app.cs);
+        }
+        self.layers.show_bus_routes = show_bus_routes;
         if self.composite.is_checked("show route for all agents") {
             if self.all_routes.is_none() {
                 self.all_routes = Some(calc_all_routes(ctx, app));
@@ -233,6 +329,20 @@ impl State for DebugMode {
             }
         }
 
+        if self.composite.is_checked("show quadtree") {
+            if self.quadtree.is_none() {
+                self.quadtree = Some(quadtree::QuadtreeViz::new(ctx, app));
+            }
+            if self.quadtree.as_mut().unwrap().event(ctx) {
+                self.reset_info(ctx);
+            }
+        } else {
+            if self.quadtree.is_some() {
+                self.quadtree = None;
+                self.reset_info(ctx);
+            }
+        }
+
         match app.primary.current_selection {
             Some(ID::Intersection(_)) | Some(ID::Car(_)) => {
                 let id = app.primary.current_selection.clone().unwrap();
@@ -306,6 +416,9 @@ impl State for DebugMode {
         if let Some((_, ref draw)) = self.all_routes {
             g.redraw(draw);
         }
+        if let Some(ref viz) = self.quadtree {
+            viz.draw(g);
+        }
 
         if !g.is_screencap() {
             self.composite.draw(g);
@@ -391,11 +504,28 @@ fn load_savestate(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option
     ctx.loading_screen("load savestate", |ctx, mut timer| {
         app.primary.sim = Sim::load_savestate(ss_path, &app.primary.map, &mut timer)
             .expect("Can't load savestate");
+        app.primary.checkpoints.clear();
         app.recalculate_current_selection(ctx);
     });
     Some(Transition::Pop)
 }
 
+// Stays on in every mode until it's turned off here
+fn pick_heatmap_overlay(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+    let name = wiz
+        .wrap(ctx)
+        .choose_string("Color lanes and intersections by what?", || {
+            let mut list = vec!["none"];
+            list.extend(all_heatmap_sources().into_iter().map(|s| s.name()));
+            list
+        })?;
+    app.primary.draw_map.heatmap = all_heatmap_sources()
+        .into_iter()
+        .find(|s| s.name() == name)
+        .map(HeatmapOverlay::new);
+    Some(Transition::Pop)
+}
+
 fn calc_all_routes(ctx: &EventCtx, app: &mut App) -> (usize, Drawable) {
     let agents = app.primary.sim.active_agents();
     let mut batch = GeomBatch::new();
@@ -449,7 +579,7 @@ impl ContextualActions for Actions {
             ID::ParkingLot(_) => {
                 actions.push((Key::H, "hide this".to_string()));
             }
-            ID::BusStop(_) => {
+            ID::BusStop(_) | ID::BusRoute(_) => {
                 actions.push((Key::H, "hide this".to_string()));
             }
             _ => {}
@@ -112,6 +112,9 @@ impl ObjectDebugger {
             ID::BusStop(id) => {
                 println!("{}", abstutil::to_json(map.get_bs(id)));
             }
+            ID::BusRoute(id) => {
+                println!("{}", abstutil::to_json(map.get_br(id)));
+            }
             ID::Area(id) => {
                 println!("{}", abstutil::to_json(map.get_a(id)));
             }
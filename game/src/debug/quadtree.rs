@@ -0,0 +1,106 @@
+use crate::app::App;
+use ezgui::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Text};
+use geom::{Bounds, Distance};
+
+// Shows how the quadtree in DrawMap carves up the map, to help debug slow queries or objects
+// inserted with the wrong bounds. Cells touched by the current viewport are highlighted.
+pub struct QuadtreeViz {
+    draw_cells: Drawable,
+    // The cells without any children
+    leaves: Vec<Bounds>,
+
+    viewport: Option<Bounds>,
+    draw_touched: Drawable,
+    pub num_touched: usize,
+}
+
+impl QuadtreeViz {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> QuadtreeViz {
+        let cells = app.primary.draw_map.quadtree_cells();
+        // inspect doesn't say which nodes are leaves, so look for children
+        let leaves: Vec<(Bounds, usize)> = cells
+            .iter()
+            .filter(|(b1, depth1, _)| {
+                !cells
+                    .iter()
+                    .any(|(b2, depth2, _)| *depth2 == depth1 + 1 && b1.contains(b2.center()))
+            })
+            .map(|(b, _, count)| (b.clone(), *count))
+            .collect();
+        let max_count = leaves.iter().map(|(_, cnt)| *cnt).max().unwrap_or(0).max(1);
+
+        let mut batch = GeomBatch::new();
+        for (bounds, count) in &leaves {
+            if *count == 0 {
+                continue;
+            }
+            batch.push(
+                app.cs
+                    .good_to_bad_red
+                    .eval((*count as f64) / (max_count as f64))
+                    .alpha(0.3),
+                bounds.get_rectangle(),
+            );
+        }
+        for (bounds, depth, _) in &cells {
+            // Outline the top levels more heavily
+            let thickness = Distance::meters((3.0 / (*depth as f64 + 1.0)).max(0.3));
+            if let Ok(outline) = bounds.get_rectangle().to_outline(thickness) {
+                batch.push(Color::BLACK, outline);
+            }
+        }
+        for (bounds, count) in &leaves {
+            if *count == 0 {
+                continue;
+            }
+            let width = bounds.width().min(bounds.height());
+            batch.append(
+                Text::from(Line(count.to_string()))
+                    .render_to_batch(ctx.prerender)
+                    .scale((width / 100.0).max(0.05).min(2.0))
+                    .centered_on(bounds.center()),
+            );
+        }
+
+        QuadtreeViz {
+            draw_cells: ctx.upload(batch),
+            leaves: leaves.into_iter().map(|(b, _)| b).collect(),
+            viewport: None,
+            draw_touched: ctx.upload(GeomBatch::new()),
+            num_touched: 0,
+        }
+    }
+
+    // Returns true if the viewport changed
+    pub fn event(&mut self, ctx: &mut EventCtx) -> bool {
+        let screen = ctx.canvas.get_screen_bounds();
+        if let Some(ref old) = self.viewport {
+            if old.min_x == screen.min_x
+                && old.min_y == screen.min_y
+                && old.max_x == screen.max_x
+                && old.max_y == screen.max_y
+            {
+                return false;
+            }
+        }
+
+        let mut batch = GeomBatch::new();
+        self.num_touched = 0;
+        for bounds in &self.leaves {
+            if bounds.overlaps(&screen) {
+                self.num_touched += 1;
+                if let Ok(outline) = bounds.get_rectangle().to_outline(Distance::meters(1.0)) {
+                    batch.push(Color::CYAN, outline);
+                }
+            }
+        }
+        self.draw_touched = ctx.upload(batch);
+        self.viewport = Some(screen);
+        true
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw_cells);
+        g.redraw(&self.draw_touched);
+    }
+}
@@ -1,13 +1,13 @@
 use crate::app::{App, ShowEverything};
 use crate::common::{make_heatmap, HeatmapOptions};
 use crate::game::{State, Transition};
-use crate::helpers::{amenity_type, ID};
+use crate::helpers::ID;
 use abstutil::Counter;
 use ezgui::{
     hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, VerticalAlignment, Widget,
 };
-use map_model::BuildingID;
+use map_model::{AmenityType, BuildingID};
 use sim::{DontDrawAgents, Scenario, TripEndpoint};
 
 pub struct PopularDestinations {
@@ -66,8 +66,8 @@ impl PopularDestinations {
         for (b, cnt) in per_bldg.borrow() {
             let mut other = true;
             for (_, amenity) in &map.get_b(*b).amenities {
-                if let Some(t) = amenity_type(amenity) {
-                    by_type.add(t, *cnt);
+                if let Some(t) = AmenityType::categorize(amenity) {
+                    by_type.add(t.describe(), *cnt);
                     other = false;
                 }
             }
@@ -2,14 +2,15 @@ use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::game::{State, Transition};
 use aabb_quadtree::QuadTree;
-use abstutil::prettyprint_usize;
+use abstutil::{prettyprint_usize, Timer};
 use ezgui::{
     hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Circle, Distance, PolyLine, Polygon, Pt2D, Ring};
 use kml::ExtraShapes;
-use map_model::BuildingID;
+use map_model::{BuildingID, Map};
+use serde::Deserialize;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub struct ViewKML {
@@ -24,8 +25,13 @@ pub struct ViewKML {
 }
 
 struct Object {
+    // Used for picking and queries
     polygon: Polygon,
+    // What's actually drawn. Polygons mostly hidden under buildings get clipped or skipped.
+    draw_polygons: Vec<Polygon>,
     color: Color,
+    // Centered on the object
+    icon: Option<(String, Pt2D)>,
     attribs: BTreeMap<String, String>,
 
     osm_bldg: Option<BuildingID>,
@@ -33,15 +39,112 @@ struct Object {
 
 const RADIUS: Distance = Distance::const_meters(5.0);
 const THICKNESS: Distance = Distance::const_meters(2.0);
+// Don't bother drawing polygons with more than this fraction of their area under buildings
+const MAX_COVERAGE: f64 = 0.9;
+
+// Optionally loaded from a file next to the dataset, like collisions.style.json for
+// collisions.kml or collisions.bin. Every rule matching an object's attributes applies in order,
+// so later rules override earlier ones, and different rules can control different things:
+//
+// {"rules": [
+//   {"key": "severity", "value": "fatal", "size_meters": 15.0},
+//   {"key": "mode", "value": "Bike", "color": "#0F7D4B"},
+//   {"key": "mode", "value": "Pedestrian", "icon": "system/assets/meters/pedestrian.svg"}
+// ]}
+#[derive(Deserialize, Default)]
+struct StyleRules {
+    rules: Vec<StyleRule>,
+}
+
+#[derive(Deserialize)]
+struct StyleRule {
+    key: String,
+    // If missing, any value matches, as long as the attribute is present
+    value: Option<String>,
+    // Like "#FF0000"
+    color: Option<String>,
+    // The radius of points or the thickness of lines
+    size_meters: Option<f64>,
+    icon: Option<String>,
+}
+
+#[derive(Default)]
+struct Style {
+    color: Option<Color>,
+    size: Option<Distance>,
+    icon: Option<String>,
+}
+
+impl StyleRules {
+    // No rules if the file doesn't exist, but an error if it's malformed.
+    fn load(dataset_path: &str, timer: &mut Timer) -> Result<StyleRules, String> {
+        let path = format!(
+            "{}.style.json",
+            dataset_path
+                .trim_end_matches(".kml")
+                .trim_end_matches(".bin")
+        );
+        if !abstutil::file_exists(path.clone()) {
+            return Ok(StyleRules::default());
+        }
+        let rules = abstutil::maybe_read_json::<StyleRules>(path.clone(), timer)
+            .map_err(|err| format!("Bad style rules in {}: {}", path, err))?;
+        for rule in &rules.rules {
+            if let Some(ref c) = rule.color {
+                // Color::hex panics on anything else
+                if c.len() != 7
+                    || !c.starts_with('#')
+                    || !c[1..].chars().all(|x| x.is_ascii_hexdigit())
+                {
+                    return Err(format!(
+                        "Bad style rules in {}: the color for {} should look like #FF0000, not {}",
+                        path, rule.key, c
+                    ));
+                }
+            }
+            if let Some(ref icon) = rule.icon {
+                // Loading the SVG panics later if it's missing
+                if !abstutil::file_exists(abstutil::path(icon)) {
+                    return Err(format!(
+                        "Bad style rules in {}: the icon for {} is {}, which doesn't exist",
+                        path, rule.key, icon
+                    ));
+                }
+            }
+        }
+        Ok(rules)
+    }
+
+    fn style_for(&self, attribs: &BTreeMap<String, String>) -> Style {
+        let mut style = Style::default();
+        for rule in &self.rules {
+            let matches = match (attribs.get(&rule.key), &rule.value) {
+                (Some(_), None) => true,
+                (Some(x), Some(y)) => x == y,
+                (None, _) => false,
+            };
+            if !matches {
+                continue;
+            }
+            if let Some(ref c) = rule.color {
+                style.color = Some(Color::hex(c));
+            }
+            if let Some(size) = rule.size_meters {
+                style.size = Some(Distance::meters(size));
+            }
+            if let Some(ref icon) = rule.icon {
+                style.icon = Some(icon.clone());
+            }
+        }
+        style
+    }
+}
 
 impl ViewKML {
-    pub fn new(ctx: &mut EventCtx, app: &App, path: String) -> Box<dyn State> {
+    // See load_shapes for the formats handled.
+    pub fn new(ctx: &mut EventCtx, app: &App, path: String) -> Result<Box<dyn State>, String> {
         ctx.loading_screen("load kml", |ctx, mut timer| {
-            let raw_shapes = if path.ends_with(".kml") {
-                kml::load(&path, &app.primary.map.get_gps_bounds(), true, &mut timer).unwrap()
-            } else {
-                abstutil::read_binary::<ExtraShapes>(path.clone(), &mut timer)
-            };
+            let raw_shapes = load_shapes(&path, app, &mut timer)?;
             let bounds = app.primary.map.get_gps_bounds();
             let boundary = app.primary.map.get_boundary_polygon();
             let dataset_name = abstutil::basename(&path);
@@ -52,13 +155,21 @@ impl ViewKML {
                 .iter()
                 .map(|b| (b.osm_way_id.to_string(), b.id))
                 .collect();
+            let mut bldg_quadtree = QuadTree::default(app.primary.map.get_bounds().as_bbox());
+            for b in app.primary.map.all_buildings() {
+                bldg_quadtree.insert_with_box(b.id, b.polygon.get_bounds().as_bbox());
+            }
+            let map = &app.primary.map;
             let cs = &app.cs;
+            let style_rules = StyleRules::load(&path, &mut timer)?;
             let objects: Vec<Object> = timer
                 .parallelize(
                     "convert shapes",
                     raw_shapes.shapes.into_iter().enumerate().collect(),
                     |(idx, shape)| {
-                        if boundary.contains_pt(Pt2D::from_gps(shape.points[0], bounds)) {
+                        if !shape.points.is_empty()
+                            && boundary.contains_pt(Pt2D::from_gps(shape.points[0], bounds))
+                        {
                             let pts: Vec<Pt2D> = shape
                                 .points
                                 .into_iter()
@@ -66,7 +177,10 @@ impl ViewKML {
                                 .collect();
                             Some(make_object(
                                 cs,
+                                map,
                                 &bldg_lookup,
+                                &bldg_quadtree,
+                                &style_rules,
                                 shape.attributes,
                                 pts,
                                 &dataset_name,
@@ -83,11 +197,25 @@ impl ViewKML {
 
             let mut batch = GeomBatch::new();
             let mut quadtree = QuadTree::default(app.primary.map.get_bounds().as_bbox());
+            let mut num_culled = 0;
             timer.start_iter("render shapes", objects.len());
             for (idx, obj) in objects.iter().enumerate() {
                 timer.next();
                 quadtree.insert_with_box(idx, obj.polygon.get_bounds().as_bbox());
-                batch.push(obj.color, obj.polygon.clone());
+                if obj.draw_polygons.is_empty() {
+                    num_culled += 1;
+                }
+                for p in &obj.draw_polygons {
+                    batch.push(obj.color, p.clone());
+                }
+                if let Some((ref icon, pt)) = obj.icon {
+                    batch.append(
+                        GeomBatch::mapspace_svg(ctx.prerender, icon)
+                            .autocrop()
+                            .scale(0.05)
+                            .centered_on(pt),
+                    );
+                }
             }
 
             let mut choices = vec![Choice::string("None")];
@@ -101,7 +229,7 @@ impl ViewKML {
                 choices.push(Choice::string("parcels with parking"));
             }
 
-            Box::new(ViewKML {
+            Ok(Box::new(ViewKML {
                 draw: ctx.upload(batch),
                 composite: Composite::new(Widget::col(vec![
                     Widget::row(vec![
@@ -111,9 +239,10 @@ impl ViewKML {
                             .align_right(),
                     ]),
                     format!(
-                        "{}: {} objects",
+                        "{}: {} objects ({} hidden under buildings)",
                         dataset_name,
-                        prettyprint_usize(objects.len())
+                        prettyprint_usize(objects.len()),
+                        prettyprint_usize(num_culled)
                     )
                     .draw_text(ctx),
                     Widget::row(vec![
@@ -129,7 +258,7 @@ impl ViewKML {
                 selected: None,
                 query: "None".to_string(),
                 draw_query: ctx.upload(GeomBatch::new()),
-            })
+            }))
         })
     }
 }
@@ -202,37 +331,67 @@ impl State for ViewKML {
     }
 }
 
+// Handles .kml, .geojson (or GeoJSON in a .json file), a .csv of points, and ExtraShapes in a .bin.
+pub fn load_shapes(path: &str, app: &App, timer: &mut Timer) -> Result<ExtraShapes, String> {
+    if path.ends_with(".kml") {
+        kml::load(path, app.primary.map.get_gps_bounds(), true, timer)
+            .map_err(|err| err.to_string())
+    } else if path.ends_with(".geojson") || path.ends_with(".json") {
+        kml::load_geojson(path).map_err(|err| err.to_string())
+    } else if path.ends_with(".csv") {
+        kml::load_csv(path).map_err(|err| err.to_string())
+    } else if path.ends_with(".bin") {
+        abstutil::maybe_read_binary::<ExtraShapes>(path.to_string(), timer)
+            .map_err(|err| err.to_string())
+    } else {
+        Err(format!("Don't know how to load {}", path))
+    }
+}
+
 fn make_object(
     cs: &ColorScheme,
+    map: &Map,
     bldg_lookup: &HashMap<String, BuildingID>,
+    bldg_quadtree: &QuadTree<BuildingID>,
+    style_rules: &StyleRules,
     attribs: BTreeMap<String, String>,
     pts: Vec<Pt2D>,
     dataset_name: &str,
     obj_idx: usize,
 ) -> Object {
+    let style = style_rules.style_for(&attribs);
     let mut color = Color::RED.alpha(0.8);
+    let mut draw_polygons = None;
     let polygon = if pts.len() == 1 {
-        Circle::new(pts[0], RADIUS).to_polygon()
+        Circle::new(pts[0], style.size.unwrap_or(RADIUS)).to_polygon()
     } else if pts[0] == *pts.last().unwrap() {
         if attribs.get("spatial_type") == Some(&"Polygon".to_string()) {
             color = cs.rotating_color_plot(obj_idx).alpha(0.8);
-            Polygon::new(&pts)
+            let polygon = Polygon::new(&pts);
+            if dataset_name == "parcels" {
+                draw_polygons = Some(clip_under_buildings(&polygon, map, bldg_quadtree));
+            }
+            polygon
         } else {
-            Ring::must_new(pts).make_polygons(THICKNESS)
+            Ring::must_new(pts).make_polygons(style.size.unwrap_or(THICKNESS))
         }
     } else {
         let backup = pts[0];
         match PolyLine::new(pts) {
-            Ok(pl) => pl.make_polygons(THICKNESS),
+            Ok(pl) => pl.make_polygons(style.size.unwrap_or(THICKNESS)),
             Err(err) => {
                 println!(
                     "Object with attribs {:?} has messed up geometry: {}",
                     attribs, err
                 );
-                Circle::new(backup, RADIUS).to_polygon()
+                Circle::new(backup, style.size.unwrap_or(RADIUS)).to_polygon()
             }
         }
     };
+    if let Some(c) = style.color {
+        color = c;
+    }
+    let icon = style.icon.map(|icon| (icon, polygon.center()));
 
     let mut osm_bldg = None;
     if dataset_name == "parcels" {
@@ -244,13 +403,53 @@ fn make_object(
     }
 
     Object {
+        draw_polygons: draw_polygons.unwrap_or_else(|| vec![polygon.clone()]),
         polygon,
         color,
+        icon,
         attribs,
         osm_bldg,
     }
 }
 
+// Buildings are drawn underneath, so the parts of a parcel covering them just waste fill rate and
+// obscure the buildings. Returns nothing if the polygon is almost entirely
+// covered.
+fn clip_under_buildings(
+    polygon: &Polygon,
+    map: &Map,
+    bldg_quadtree: &QuadTree<BuildingID>,
+) -> Vec<Polygon> {
+    let bldgs: Vec<&Polygon> = bldg_quadtree
+        .query(polygon.get_bounds().as_bbox())
+        .into_iter()
+        .map(|(b, _, _)| &map.get_b(*b).polygon)
+        .collect();
+    if bldgs.is_empty() {
+        return vec![polygon.clone()];
+    }
+
+    // Buildings don't overlap each other, so just sum up the pieces
+    let total = polygon.area();
+    let covered: f64 = bldgs
+        .iter()
+        .flat_map(|b| polygon.intersection(b))
+        .map(|p| p.area())
+        .sum();
+    if total == 0.0 || covered / total > MAX_COVERAGE {
+        return Vec::new();
+    }
+    if covered == 0.0 {
+        return vec![polygon.clone()];
+    }
+
+    let mut pieces = vec![polygon.clone()];
+    for b in bldgs {
+        pieces = pieces.into_iter().flat_map(|p| p.difference(b)).collect();
+    }
+    pieces
+}
+
 fn make_query(app: &App, objects: &Vec<Object>, query: &str) -> (GeomBatch, usize) {
     let mut batch = GeomBatch::new();
     let mut cnt = 0;
@@ -7,7 +7,7 @@ use ezgui::{
     hotkey, Btn, Checkbox, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::{Distance, FindClosest, PolyLine, Polygon};
+use geom::{Distance, PolyLine, Polygon, Pt2D};
 use map_model::{osm, RoadID};
 use sim::DontDrawAgents;
 use std::collections::{BTreeMap, HashSet};
@@ -561,21 +561,20 @@ fn generate_osmc(
 
 fn find_divided_highways(app: &App) -> HashSet<RoadID> {
     let map = &app.primary.map;
-    let mut closest: FindClosest<RoadID> = FindClosest::new(map.get_bounds());
-    let mut oneways = Vec::new();
-    for r in map.all_roads() {
-        if r.osm_tags.contains_key("oneway") {
-            closest.add(r.id, r.center_pts.points());
-            oneways.push(r.id);
-        }
-    }
+    let oneways: Vec<RoadID> = map
+        .all_roads()
+        .iter()
+        .filter(|r| r.osm_tags.contains_key("oneway"))
+        .map(|r| r.id)
+        .collect();
 
     let mut found = HashSet::new();
     for r1 in oneways {
         let r1 = map.get_r(r1);
         let (middle, angle) = r1.center_pts.must_dist_along(r1.center_pts.length() / 2.0);
-        for (r2, _, _) in closest.all_close_pts(middle, Distance::meters(250.0)) {
+        for (r2, _, _) in map.all_close_roads(middle, Distance::meters(250.0)) {
             if r1.id != r2
+                && map.get_r(r2).osm_tags.contains_key("oneway")
                 && PolyLine::must_new(vec![
                     middle.project_away(Distance::meters(100.0), angle.rotate_degs(90.0)),
                     middle.project_away(Distance::meters(100.0), angle.rotate_degs(-90.0)),
@@ -595,20 +594,21 @@ fn find_divided_highways(app: &App) -> HashSet<RoadID> {
 // TODO Lots of false positives here... why?
 fn find_overlapping_stuff(app: &App, timer: &mut Timer) -> Vec<Polygon> {
     let map = &app.primary.map;
-    let mut closest: FindClosest<RoadID> = FindClosest::new(map.get_bounds());
-    for r in map.all_roads() {
-        if r.osm_tags.contains_key("tunnel") {
-            continue;
-        }
-        closest.add(r.id, r.center_pts.points());
-    }
+    // Skip tunnels
+    let close_roads = |pt: Pt2D| -> Vec<RoadID> {
+        map.all_close_roads(pt, Distance::meters(500.0))
+            .into_iter()
+            .map(|(r, _, _)| r)
+            .filter(|r| !map.get_r(*r).osm_tags.contains_key("tunnel"))
+            .collect()
+    };
 
     let mut polygons = Vec::new();
 
     timer.start_iter("check buildings", map.all_buildings().len());
     for b in map.all_buildings() {
         timer.next();
-        for (r, _, _) in closest.all_close_pts(b.label_center, Distance::meters(500.0)) {
+        for r in close_roads(b.label_center) {
             if !b
                 .polygon
                 .intersection(&map.get_r(r).get_thick_polygon(map))
@@ -622,7 +622,7 @@ fn find_overlapping_stuff(app: &App, timer: &mut Timer) -> Vec<Polygon> {
     timer.start_iter("check parking lots", map.all_parking_lots().len());
     for pl in map.all_parking_lots() {
         timer.next();
-        for (r, _, _) in closest.all_close_pts(pl.polygon.center(), Distance::meters(500.0)) {
+        for r in close_roads(pl.polygon.center()) {
             if !pl
                 .polygon
                 .intersection(&map.get_r(r).get_thick_polygon(map))
@@ -1,13 +1,17 @@
 mod destinations;
-mod kml;
+pub mod kml;
 pub mod mapping;
+mod neighborhoods;
 mod polygon;
+pub mod render_map;
+mod route_planner;
 mod scenario;
+mod scenario_builder;
 mod story;
 
 use crate::app::App;
-use crate::common::CityPicker;
-use crate::game::{DrawBaselayer, State, Transition, WizardState};
+use crate::common::{CityPicker, FileBrowser};
+use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
 use crate::helpers::nice_map_name;
 use abstutil::Timer;
 use ezgui::{
@@ -44,6 +48,9 @@ impl DevToolsMode {
                     Btn::text_fg("load scenario").build_def(ctx, hotkey(Key::W)),
                     Btn::text_fg("view KML").build_def(ctx, hotkey(Key::K)),
                     Btn::text_fg("story maps").build_def(ctx, hotkey(Key::S)),
+                    Btn::text_fg("neighborhoods").build_def(ctx, hotkey(Key::N)),
+                    Btn::text_fg("plan a route").build_def(ctx, hotkey(Key::R)),
+                    Btn::text_fg("build a scenario").build_def(ctx, hotkey(Key::B)),
                 ])
                 .flex_wrap(ctx, 60),
             ]))
@@ -74,11 +81,28 @@ impl State for DevToolsMode {
                     return Transition::Push(WizardState::new(Box::new(load_scenario)));
                 }
                 "view KML" => {
-                    return Transition::Push(WizardState::new(Box::new(choose_kml)));
+                    return Transition::Push(FileBrowser::open(
+                        ctx,
+                        abstutil::path(format!("input/{}/", app.primary.map.get_city_name())),
+                        vec!["kml", "geojson", "json", "csv", "bin"],
+                        Box::new(|ctx, app, path| match kml::ViewKML::new(ctx, app, path) {
+                            Ok(state) => Transition::Replace(state),
+                            Err(err) => Transition::Replace(msg("Can't load shapes", vec![err])),
+                        }),
+                    ));
                 }
                 "story maps" => {
                     return Transition::Push(story::StoryMapEditor::new(ctx));
                 }
+                "neighborhoods" => {
+                    return Transition::Push(neighborhoods::NeighborhoodManager::new(ctx, app));
+                }
+                "plan a route" => {
+                    return Transition::Push(route_planner::RoutePlanner::new(ctx, app));
+                }
+                "build a scenario" => {
+                    return Transition::Push(scenario_builder::ScenarioBuilder::new(ctx, app));
+                }
                 "change map" => {
                     return Transition::Push(CityPicker::new(
                         ctx,
@@ -138,16 +162,3 @@ fn choose_polygon(wiz: &mut Wizard, ctx: &mut EventCtx, _: &mut App) -> Option<T
         }
     }
 }
-
-fn choose_kml(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
-    let path = wiz.wrap(ctx).choose_string("View what KML dataset?", || {
-        abstutil::list_dir(std::path::Path::new(&abstutil::path(format!(
-            "input/{}/",
-            app.primary.map.get_city_name()
-        ))))
-        .into_iter()
-        .filter(|x| x.ends_with(".bin") && !x.ends_with("popdat.bin"))
-        .collect()
-    })?;
-    Some(Transition::Replace(kml::ViewKML::new(ctx, app, path)))
-}
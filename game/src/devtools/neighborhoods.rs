@@ -0,0 +1,122 @@
+use crate::app::App;
+use crate::devtools::polygon::PolygonEditor;
+use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
+use ezgui::{
+    hotkey, Btn, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Distance;
+use map_model::Neighborhood;
+
+pub struct NeighborhoodManager {
+    composite: Composite,
+    neighborhoods: Vec<Neighborhood>,
+    draw: Drawable,
+}
+
+impl NeighborhoodManager {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let neighborhoods = Neighborhood::load_all(app.primary.map.get_name());
+
+        let mut batch = GeomBatch::new();
+        let mut rows = Vec::new();
+        for (idx, n) in neighborhoods.iter().enumerate() {
+            let color = app.cs.rotating_color_plot(idx);
+            if let Some(poly) = n.polygon(&app.primary.map) {
+                batch.push(color.alpha(0.3), poly.clone());
+                if let Ok(o) = poly.to_outline(Distance::meters(5.0)) {
+                    batch.push(color, o);
+                }
+                batch.append(
+                    Text::from(Line(&n.name))
+                        .with_bg()
+                        .render_to_batch(ctx.prerender)
+                        .scale(2.0)
+                        .centered_on(poly.polylabel()),
+                );
+            }
+            rows.push(Widget::row(vec![
+                Line(&n.name).fg(color).draw(ctx),
+                Btn::text_fg("edit").build(ctx, format!("edit {}", n.name), None),
+                Btn::text_fg("delete").build(ctx, format!("delete {}", n.name), None),
+            ]));
+        }
+        if rows.is_empty() {
+            rows.push("No neighborhoods defined for this map yet".draw_text(ctx));
+        }
+
+        Box::new(NeighborhoodManager {
+            composite: Composite::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Neighborhoods").small_heading().draw(ctx),
+                    Btn::text_fg("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Widget::col(rows),
+                Btn::text_fg("draw a new neighborhood").build_def(ctx, hotkey(Key::N)),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            neighborhoods,
+            draw: ctx.upload(batch),
+        })
+    }
+}
+
+impl State for NeighborhoodManager {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "draw a new neighborhood" => {
+                    return Transition::Push(WizardState::new(Box::new(|wiz, ctx, _| {
+                        let name = wiz.wrap(ctx).input_string("Name the neighborhood")?;
+                        Some(Transition::PopThenReplace(PolygonEditor::new(
+                            ctx,
+                            name,
+                            Vec::new(),
+                        )))
+                    })));
+                }
+                x => {
+                    if let Some(name) = x.strip_prefix("edit ") {
+                        let n = self.neighborhoods.iter().find(|n| n.name == name).unwrap();
+                        // PolygonEditor expects the Osmosis convention of repeating the first
+                        // point.
+                        let mut pts = n.points.clone();
+                        pts.push(pts[0]);
+                        return Transition::Replace(PolygonEditor::new(ctx, n.name.clone(), pts));
+                    }
+                    if let Some(name) = x.strip_prefix("delete ") {
+                        let n = self.neighborhoods.iter().find(|n| n.name == name).unwrap();
+                        if let Err(err) = n.delete() {
+                            return Transition::Push(msg(
+                                "Error",
+                                vec![format!("Couldn't delete {}: {}", name, err)],
+                            ));
+                        }
+                        return Transition::Replace(NeighborhoodManager::new(ctx, app));
+                    }
+                    unreachable!()
+                }
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::DefaultMap
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+    }
+}
@@ -5,11 +5,13 @@ use ezgui::{
     hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Text,
     VerticalAlignment, Widget,
 };
-use geom::{Circle, Distance, LonLat, Polygon, Pt2D};
+use geom::{Circle, LonLat, Polygon, Pt2D};
+use map_model::Neighborhood;
 use std::fs::File;
 use std::io::{Error, Write};
 
-const POINT_RADIUS: Distance = Distance::const_meters(10.0);
+// In screen pixels, so points stay the same size at any zoom
+const POINT_RADIUS: f64 = 10.0;
 // Localized and internal, so don't put in ColorScheme.
 const POINT_COLOR: Color = Color::RED;
 const POLYGON_COLOR: Color = Color::BLUE.alpha(0.6);
@@ -36,6 +38,7 @@ impl PolygonEditor {
                         .align_right(),
                 ]),
                 Btn::text_fg("export as an Osmosis polygon filter").build_def(ctx, hotkey(Key::X)),
+                Btn::text_fg("save as a neighborhood").build_def(ctx, hotkey(Key::N)),
             ]))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
             .build(ctx),
@@ -74,6 +77,17 @@ impl State for PolygonEditor {
                         save_as_osmosis(&self.name, &self.points).unwrap();
                     }
                 }
+                "save as a neighborhood" => {
+                    if self.points.len() >= 3 {
+                        let n = Neighborhood::new(
+                            self.name.clone(),
+                            app.primary.map.get_name().to_string(),
+                            self.points.clone(),
+                        );
+                        n.save();
+                        println!("Saved neighborhood {}", n.name);
+                    }
+                }
                 _ => unreachable!(),
             },
             None => {}
@@ -83,7 +97,7 @@ impl State for PolygonEditor {
             self.mouseover_pt = self.points.iter().position(|pt| {
                 Circle::new(
                     Pt2D::from_gps(*pt, gps_bounds),
-                    POINT_RADIUS / ctx.canvas.cam_zoom,
+                    ctx.canvas.screen_to_map_dist(POINT_RADIUS),
                 )
                 .contains_pt(cursor)
             });
@@ -113,7 +127,7 @@ impl State for PolygonEditor {
         if pts.len() == 2 {
             g.draw_line(
                 POINT_COLOR,
-                POINT_RADIUS / 2.0,
+                g.canvas.screen_to_map_dist(POINT_RADIUS / 2.0),
                 &geom::Line::must_new(pts[0], pts[1]),
             );
         }
@@ -128,7 +142,10 @@ impl State for PolygonEditor {
             } else {
                 POINT_COLOR
             };
-            g.draw_circle(color, &Circle::new(*pt, POINT_RADIUS / g.canvas.cam_zoom));
+            g.draw_circle(
+                color,
+                &Circle::new(*pt, g.canvas.screen_to_map_dist(POINT_RADIUS)),
+            );
         }
 
         self.composite.draw(g);
@@ -0,0 +1,125 @@
+use crate::colors::{ColorScheme, ColorSchemeChoice};
+use crate::render::HeadlessMap;
+use abstutil::Timer;
+use geom::{Bounds, LonLat, Pt2D};
+use sim::SimFlags;
+use std::f64::consts::PI;
+
+// Web map tiles are always this size
+const TILE_PIXELS: u32 = 256;
+// Drawing a huge map at a high zoom into one image could exhaust memory
+const MAX_FULL_PIXELS: u64 = 400_000_000;
+
+// Draws the whole map (and agents, if a savestate is loaded) without a window. full.png is the
+// entire map at some zoom, in pixels per meter. Web map tiles for every zoom level in the range are
+// saved as z/x/y.png, skipping tiles that don't touch the map.
+pub fn render_map(
+    sim_flags: SimFlags,
+    output: Option<String>,
+    zoom: f64,
+    min_tile_zoom: u32,
+    max_tile_zoom: u32,
+) {
+    let mut timer = Timer::new("render map");
+    let (map, sim, _) = sim_flags.load(&mut timer);
+    let dir = output.unwrap_or_else(|| format!("screenshots_{}", map.get_name()));
+    let cs = ColorScheme::new(ColorSchemeChoice::Standard);
+    let headless = HeadlessMap::new(&map, &sim, &cs, &mut timer);
+
+    let bounds = map.get_bounds();
+    let width = (bounds.max_x * zoom).ceil() as u32;
+    let height = (bounds.max_y * zoom).ceil() as u32;
+    if (width as u64) * (height as u64) > MAX_FULL_PIXELS {
+        timer.note(format!(
+            "The map is too big to draw into one image at zoom {}; only saving tiles",
+            zoom
+        ));
+    } else {
+        timer.start(format!("draw {}/full.png", dir));
+        let mut img = image::RgbaImage::new(width, height);
+        let mut full_bounds = Bounds::new();
+        full_bounds.update(Pt2D::new(0.0, 0.0));
+        full_bounds.update(Pt2D::new((width as f64) / zoom, (height as f64) / zoom));
+        headless.draw(&full_bounds, &mut img);
+        std::fs::create_dir_all(&dir).unwrap();
+        img.save(format!("{}/full.png", dir)).unwrap();
+        timer.stop(format!("draw {}/full.png", dir));
+    }
+
+    let gps_bounds = map.get_gps_bounds();
+    let corners = gps_bounds.convert_back(&vec![
+        Pt2D::new(0.0, 0.0),
+        Pt2D::new(bounds.max_x, bounds.max_y),
+    ]);
+    let mut tiles = Vec::new();
+    for z in min_tile_zoom..=max_tile_zoom {
+        let (min_x, min_y) = tile_containing(corners[0], z);
+        let (max_x, max_y) = tile_containing(corners[1], z);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                tiles.push((z, x, y));
+            }
+        }
+    }
+
+    timer.start_iter("draw tiles", tiles.len());
+    let mut saved = 0;
+    for (z, x, y) in tiles {
+        timer.next();
+        let tile_bounds = Bounds::from(&vec![
+            Pt2D::from_gps(tile_corner(x, y, z), gps_bounds),
+            Pt2D::from_gps(tile_corner(x + 1, y + 1, z), gps_bounds),
+        ]);
+        let mut img = image::RgbaImage::new(TILE_PIXELS, TILE_PIXELS);
+        if !headless.draw(&tile_bounds, &mut img) {
+            continue;
+        }
+        std::fs::create_dir_all(format!("{}/{}/{}", dir, z, x)).unwrap();
+        img.save(format!("{}/{}/{}/{}.png", dir, z, x, y)).unwrap();
+        saved += 1;
+    }
+    timer.note(format!("Saved {} tiles in {}", saved, dir));
+}
+
+// Web map tiles use the Web Mercator projection; see
+// https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames.
+fn tile_containing(pt: LonLat, zoom: u32) -> (u32, u32) {
+    let n = 2.0_f64.powi(zoom as i32);
+    let lat = pt.y().to_radians();
+    let x = (pt.x() + 180.0) / 360.0 * n;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+    (
+        x.floor().max(0.0).min(n - 1.0) as u32,
+        y.floor().max(0.0).min(n - 1.0) as u32,
+    )
+}
+
+// The northwest corner of a tile
+fn tile_corner(x: u32, y: u32, zoom: u32) -> LonLat {
+    let n = 2.0_f64.powi(zoom as i32);
+    let lon = (x as f64) / n * 360.0 - 180.0;
+    let lat = (PI * (1.0 - 2.0 * (y as f64) / n))
+        .sinh()
+        .atan()
+        .to_degrees();
+    LonLat::new(lon, lat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slippy_tiles() {
+        assert_eq!(tile_containing(LonLat::new(-122.3, 47.6), 0), (0, 0));
+        // Seattle
+        assert_eq!(tile_containing(LonLat::new(-122.3, 47.6), 12), (656, 1430));
+
+        for (x, y, z) in vec![(656, 1430, 12), (167_936, 366_080, 20)] {
+            // Just inside the corner is the same tile
+            let corner = tile_corner(x, y, z);
+            let inside = LonLat::new(corner.x() + 1e-7, corner.y() - 1e-7);
+            assert_eq!(tile_containing(inside, z), (x, y));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::app::{App, ShowEverything};
+use crate::common::CommonState;
+use crate::game::{State, Transition};
+use crate::helpers::ID;
+use crate::render::DrawRoute;
+use crate::sandbox::path_request;
+use ezgui::{
+    hotkey, Btn, Choice, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use map_model::{Map, Path, PathConstraints, PathRequest};
+use sim::{DontDrawAgents, TripEndpoint, TripMode};
+
+// Pick two places and a way to travel between them, then see the route and roughly how long it
+// takes. Nobody is actually spawned.
+pub struct RoutePlanner {
+    composite: Composite,
+    start: Option<TripEndpoint>,
+    end: Option<TripEndpoint>,
+    route: Option<DrawRoute>,
+}
+
+impl RoutePlanner {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let mut planner = RoutePlanner {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            start: None,
+            end: None,
+            route: None,
+        };
+        planner.update(ctx, app, TripMode::Drive);
+        Box::new(planner)
+    }
+
+    fn update(&mut self, ctx: &mut EventCtx, app: &App, mode: TripMode) {
+        let map = &app.primary.map;
+        let mut txt = Text::new();
+        self.route = None;
+        match (self.start.clone(), self.end.clone()) {
+            (None, _) => {
+                txt.add(Line("Click a building or border to specify start"));
+            }
+            (Some(_), None) => {
+                txt.add(Line("Click a building or border to specify end"));
+            }
+            (Some(from), Some(to)) => {
+                let legs = legs(from, to, mode, map);
+                if mode == TripMode::Transit
+                    && legs
+                        .iter()
+                        .all(|(req, _)| req.constraints == PathConstraints::Pedestrian)
+                {
+                    txt.add(Line("No bus helps here, so this is walking").secondary());
+                }
+                self.route = DrawRoute::new(ctx, legs, app.primary.sim.time(), map, &app.cs);
+                if let Some(ref route) = self.route {
+                    txt.add(Line(format!(
+                        "Distance: {}",
+                        route.distance.describe_rounded(&app.opts.units)
+                    )));
+                    txt.add(Line(format!("Estimated time: {}", route.estimated_time)));
+                } else {
+                    txt.add(Line(format!("Can't {} between these places", mode.verb())));
+                }
+            }
+        }
+
+        self.composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("Plan a route").small_heading().draw(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Widget::row(vec![
+                "Type of trip:".draw_text(ctx),
+                Widget::dropdown(
+                    ctx,
+                    "mode",
+                    mode,
+                    TripMode::all()
+                        .into_iter()
+                        .map(|m| Choice::new(m.ongoing_verb(), m))
+                        .collect(),
+                ),
+            ]),
+            txt.draw(ctx),
+            if self.start.is_some() {
+                Btn::text_fg("start over").build_def(ctx, hotkey(Key::Backspace))
+            } else {
+                Btn::text_fg("start over").inactive(ctx)
+            },
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
+
+impl State for RoutePlanner {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let old_mode: TripMode = self.composite.dropdown_value("mode");
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    app.primary.current_selection = None;
+                    return Transition::Pop;
+                }
+                "start over" => {
+                    self.start = None;
+                    self.end = None;
+                    self.update(ctx, app, old_mode);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+        let mode: TripMode = self.composite.dropdown_value("mode");
+        if mode != old_mode {
+            self.update(ctx, app, mode);
+        }
+
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            app.primary.current_selection = app.calculate_current_selection(
+                ctx,
+                &DontDrawAgents {},
+                &ShowEverything::new(),
+                false,
+                true,
+                true,
+            );
+            match app.primary.current_selection {
+                Some(ID::Intersection(i)) if app.primary.map.get_i(i).is_border() => {}
+                Some(ID::Building(_)) => {}
+                _ => {
+                    app.primary.current_selection = None;
+                }
+            }
+        }
+        let hovering = match app.primary.current_selection {
+            Some(ID::Intersection(i)) => Some(TripEndpoint::Border(i, None)),
+            Some(ID::Building(b)) => Some(TripEndpoint::Bldg(b)),
+            _ => None,
+        };
+        if let Some(endpt) = hovering {
+            if self.start.is_none() && app.per_obj.left_click(ctx, "start here") {
+                self.start = Some(endpt);
+                self.update(ctx, app, mode);
+            } else if self.start.is_some()
+                && self.end.is_none()
+                && self.start != Some(endpt.clone())
+                && app.per_obj.left_click(ctx, "end here")
+            {
+                self.end = Some(endpt);
+                self.update(ctx, app, mode);
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some(ref route) = self.route {
+            route.draw(g);
+        }
+        for (endpt, color) in vec![(&self.start, Color::BLUE), (&self.end, Color::GREEN)] {
+            if let Some(endpt) = endpt {
+                g.draw_polygon(
+                    color.alpha(0.8),
+                    match endpt {
+                        TripEndpoint::Border(i, _) => &app.primary.map.get_i(*i).polygon,
+                        TripEndpoint::Bldg(b) => &app.primary.map.get_b(*b).polygon,
+                    },
+                );
+            }
+        }
+
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+// Every leg of the trip, in order. Transit trips walk to a stop, ride the bus through all the
+// stops in between, then walk the rest of the way. Legs that can't be pathfound are left out.
+fn legs(
+    from: TripEndpoint,
+    to: TripEndpoint,
+    mode: TripMode,
+    map: &Map,
+) -> Vec<(PathRequest, Path)> {
+    let req = match path_request(from, to, mode, map) {
+        Some(req) => req,
+        None => {
+            return Vec::new();
+        }
+    };
+    let mut requests = Vec::new();
+    let transit = if mode == TripMode::Transit {
+        map.should_use_transit(req.start, req.end)
+    } else {
+        None
+    };
+    if let Some((stop1, stop2, route)) = transit {
+        requests.push(PathRequest {
+            start: req.start,
+            end: map.get_bs(stop1).sidewalk_pos,
+            constraints: PathConstraints::Pedestrian,
+        });
+        // Routes without borders loop around, so the ride might wrap past the last stop.
+        let route = map.get_br(route);
+        if let Some(mut idx) = route.stops.iter().position(|s| *s == stop1) {
+            for _ in 0..route.stops.len() {
+                if route.stops[idx] == stop2 {
+                    break;
+                }
+                let next = (idx + 1) % route.stops.len();
+                requests.push(PathRequest {
+                    start: map.get_bs(route.stops[idx]).driving_pos,
+                    end: map.get_bs(route.stops[next]).driving_pos,
+                    constraints: route.route_type,
+                });
+                idx = next;
+            }
+        }
+        requests.push(PathRequest {
+            start: map.get_bs(stop2).sidewalk_pos,
+            end: req.end,
+            constraints: PathConstraints::Pedestrian,
+        });
+    } else {
+        requests.push(req);
+    }
+
+    requests
+        .into_iter()
+        .filter(|req| req.start != req.end)
+        .filter_map(|req| map.pathfind(req.clone()).map(|path| (req, path)))
+        .collect()
+}
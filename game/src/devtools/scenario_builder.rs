@@ -0,0 +1,318 @@
+use crate::app::App;
+use crate::common::CommonState;
+use crate::game::{msg, State, Transition};
+use crate::render::DrawNeighborhood;
+use ezgui::{
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Spinner, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{ArrowCap, Distance, Duration, PolyLine, Time};
+use sim::{DepartureTimes, NeighborhoodSpawnOverTime, ScenarioGenerator, TripMode};
+
+// Describe traffic demand as trips between neighborhoods, then generate a scenario from it. The
+// neighborhoods have to be drawn first.
+pub struct ScenarioBuilder {
+    composite: Composite,
+    neighborhoods: Vec<DrawNeighborhood>,
+    demand: Vec<NeighborhoodSpawnOverTime>,
+    // Which neighborhood each entry in demand goes from and to
+    endpoints: Vec<(usize, usize)>,
+    draw: Drawable,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Spread {
+    Evenly,
+    Peak,
+}
+
+impl ScenarioBuilder {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let mut builder = ScenarioBuilder {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            neighborhoods: DrawNeighborhood::load_all(app),
+            demand: Vec::new(),
+            endpoints: Vec::new(),
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        builder.update(ctx, (0, 0), TripMode::Drive, Spread::Peak);
+        Box::new(builder)
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut EventCtx,
+        (from, to): (usize, usize),
+        mode: TripMode,
+        spread: Spread,
+    ) {
+        let mut batch = GeomBatch::new();
+        for n in &self.neighborhoods {
+            batch.append(n.render(ctx));
+        }
+        for (s, (src, dst)) in self.demand.iter().zip(self.endpoints.iter()) {
+            if src == dst {
+                continue;
+            }
+            let pl = PolyLine::must_new(vec![
+                self.neighborhoods[*src].polygon.polylabel(),
+                self.neighborhoods[*dst].polygon.polylabel(),
+            ]);
+            // Thicker arrows for more trips
+            let thickness = Distance::meters(5.0 + (s.num_agents as f64).sqrt());
+            batch.push(
+                Color::BLACK.alpha(0.7),
+                pl.make_arrow(thickness, ArrowCap::Triangle),
+            );
+        }
+        self.draw = ctx.upload(batch);
+
+        let mut col = vec![Widget::row(vec![
+            Line("Build a scenario").small_heading().draw(ctx),
+            Btn::text_fg("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])];
+        if self.neighborhoods.is_empty() {
+            col.push(
+                Text::from(Line("Draw some neighborhoods on this map first").secondary())
+                    .wrap_to_pct(ctx, 20)
+                    .draw(ctx),
+            );
+            self.composite = Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx);
+            return;
+        }
+
+        // Keep whatever's already been typed into the form
+        let old = &self.composite;
+        let spinner = |name: &str, default: isize| {
+            if old.has_widget(name) {
+                old.spinner(name)
+            } else {
+                default
+            }
+        };
+        let scenario_name = if old.has_widget("name") {
+            old.text_box("name")
+        } else {
+            "neighborhood trips".to_string()
+        };
+
+        let choices = || -> Vec<Choice<usize>> {
+            self.neighborhoods
+                .iter()
+                .enumerate()
+                .map(|(idx, n)| Choice::new(n.neighborhood.name.clone(), idx))
+                .collect()
+        };
+        col.push(Widget::row(vec![
+            "From".draw_text(ctx).centered_vert(),
+            Widget::dropdown(ctx, "from", from, choices()),
+            "to".draw_text(ctx).centered_vert(),
+            Widget::dropdown(ctx, "to", to, choices()),
+        ]));
+        col.push(Widget::row(vec![
+            Spinner::new(ctx, (1, 10_000), spinner("count", 100)).named("count"),
+            "people".draw_text(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "mode",
+                mode,
+                TripMode::all()
+                    .into_iter()
+                    .map(|m| Choice::new(m.ongoing_verb(), m))
+                    .collect(),
+            ),
+        ]));
+        col.push(Widget::row(vec![
+            "leaving".draw_text(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "spread",
+                spread,
+                vec![
+                    Choice::new("evenly", Spread::Evenly),
+                    Choice::new("around a peak", Spread::Peak),
+                ],
+            ),
+        ]));
+        col.push(match spread {
+            Spread::Evenly => Widget::row(vec![
+                "between hour".draw_text(ctx).centered_vert(),
+                Spinner::new(ctx, (0, 23), spinner("hour", 7)).named("hour"),
+                "and hour".draw_text(ctx).centered_vert(),
+                Spinner::new(ctx, (1, 24), spinner("end hour", 9)).named("end hour"),
+            ]),
+            Spread::Peak => Widget::row(vec![
+                "at hour".draw_text(ctx).centered_vert(),
+                Spinner::new(ctx, (0, 23), spinner("hour", 8)).named("hour"),
+                "give or take".draw_text(ctx).centered_vert(),
+                Spinner::new(ctx, (5, 240), spinner("minutes", 60)).named("minutes"),
+                "minutes".draw_text(ctx).centered_vert(),
+            ]),
+        });
+        col.push(Btn::text_bg2("add these trips").build_def(ctx, hotkey(Key::Enter)));
+
+        if !self.demand.is_empty() {
+            col.push(Line("Trips so far").small_heading().draw(ctx));
+        }
+        for (idx, s) in self.demand.iter().enumerate() {
+            col.push(Widget::row(vec![
+                Text::from(Line(describe(s))).wrap_to_pct(ctx, 20).draw(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, format!("delete trips {}", idx), None)
+                    .align_right(),
+            ]));
+        }
+
+        col.push(Widget::row(vec![
+            "Scenario name:".draw_text(ctx).centered_vert(),
+            Widget::text_entry(ctx, scenario_name, false).named("name"),
+        ]));
+        col.push(if self.demand.is_empty() {
+            Btn::text_bg2("save scenario").inactive(ctx)
+        } else {
+            Btn::text_bg2("save scenario").build_def(ctx, None)
+        });
+
+        self.composite = Composite::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx);
+    }
+
+    fn form(&self) -> ((usize, usize), TripMode, Spread) {
+        (
+            (
+                self.composite.dropdown_value("from"),
+                self.composite.dropdown_value("to"),
+            ),
+            self.composite.dropdown_value("mode"),
+            self.composite.dropdown_value("spread"),
+        )
+    }
+}
+
+impl State for ScenarioBuilder {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "add these trips" => {
+                    let ((from, to), mode, spread) = self.form();
+                    let hour = Time::START_OF_DAY
+                        + Duration::hours(self.composite.spinner("hour") as usize);
+                    let departures = match spread {
+                        Spread::Evenly => {
+                            let end = Time::START_OF_DAY
+                                + Duration::hours(self.composite.spinner("end hour") as usize);
+                            if end <= hour {
+                                return Transition::Push(msg(
+                                    "Error",
+                                    vec!["The trips have to end after they start"],
+                                ));
+                            }
+                            DepartureTimes::Uniform(hour, end)
+                        }
+                        Spread::Peak => DepartureTimes::Peak(
+                            hour,
+                            Duration::minutes(self.composite.spinner("minutes") as usize),
+                        ),
+                    };
+                    self.demand.push(NeighborhoodSpawnOverTime {
+                        from: self.neighborhoods[from].neighborhood.clone(),
+                        to: self.neighborhoods[to].neighborhood.clone(),
+                        mode,
+                        num_agents: self.composite.spinner("count") as usize,
+                        departures,
+                    });
+                    self.endpoints.push((from, to));
+                    self.update(ctx, (from, to), mode, spread);
+                }
+                "save scenario" => {
+                    let name = self.composite.text_box("name").trim().to_string();
+                    if name.is_empty() {
+                        return Transition::Push(msg("Error", vec!["Name the scenario first"]));
+                    }
+                    let mut generator = ScenarioGenerator::empty(&name);
+                    generator.only_seed_buses = None;
+                    generator.neighborhood_spawn_over_time = self.demand.clone();
+                    let scenario = ctx.loading_screen("generate scenario", |_, timer| {
+                        generator.generate(
+                            &app.primary.map,
+                            &mut app.primary.current_flags.sim_flags.make_rng(),
+                            timer,
+                        )
+                    });
+                    scenario.save();
+                    return Transition::Push(msg(
+                        "Saved",
+                        vec![format!(
+                            "Scenario \"{}\" has {} people",
+                            name,
+                            scenario.people.len()
+                        )],
+                    ));
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("delete trips ") {
+                        let idx = idx.parse::<usize>().unwrap();
+                        self.demand.remove(idx);
+                        self.endpoints.remove(idx);
+                        let (endpts, mode, spread) = self.form();
+                        self.update(ctx, endpts, mode, spread);
+                    } else {
+                        unreachable!()
+                    }
+                }
+            },
+            None => {
+                if !self.neighborhoods.is_empty() {
+                    let (endpts, mode, spread) = self.form();
+                    let old_spread = if self.composite.has_widget("end hour") {
+                        Spread::Evenly
+                    } else {
+                        Spread::Peak
+                    };
+                    if spread != old_spread {
+                        self.update(ctx, endpts, mode, spread);
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn describe(s: &NeighborhoodSpawnOverTime) -> String {
+    let when = match s.departures {
+        DepartureTimes::Uniform(start, end) => format!(
+            "between {} and {}",
+            start.ampm_tostring(),
+            end.ampm_tostring()
+        ),
+        DepartureTimes::Peak(peak, spread) => {
+            format!("around {}, give or take {}", peak.ampm_tostring(), spread)
+        }
+    };
+    format!(
+        "{} people {} from {} to {}, leaving {}",
+        s.num_agents,
+        s.mode.ongoing_verb(),
+        s.from.name,
+        s.to.name,
+        when
+    )
+}
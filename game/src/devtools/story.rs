@@ -1,12 +1,12 @@
 use crate::app::{App, ShowEverything};
-use crate::common::CommonState;
+use crate::common::{CommonState, Lasso};
 use crate::game::{DrawBaselayer, State, Transition, WizardState};
 use crate::render::DrawOptions;
 use ezgui::{
     hotkey, lctrl, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, RewriteColor, Text, VerticalAlignment, Widget,
 };
-use geom::{Distance, LonLat, PolyLine, Polygon, Pt2D, Ring};
+use geom::{Distance, LonLat, Polygon, Pt2D};
 use serde::{Deserialize, Serialize};
 use sim::DontDrawAgents;
 
@@ -530,70 +530,3 @@ impl Marker {
         .build(ctx)
     }
 }
-
-// TODO This should totally be an ezgui tool
-// TODO Simplify points
-struct Lasso {
-    pl: PolyLine,
-}
-
-impl Lasso {
-    fn new(pt: Pt2D) -> Lasso {
-        Lasso {
-            pl: PolyLine::must_new(vec![pt, pt.offset(0.1, 0.0)]),
-        }
-    }
-
-    fn event(&mut self, ctx: &mut EventCtx) -> Option<Ring> {
-        if ctx.input.left_mouse_button_released() {
-            return Some(simplify(self.pl.points().clone()));
-        }
-        if ctx.redo_mouseover() {
-            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
-                if let Ok(pl) = PolyLine::new(vec![self.pl.last_pt(), pt]) {
-                    // Did we make a crossing?
-                    if let Some((hit, _)) = self.pl.intersection(&pl) {
-                        if let Some(slice) = self.pl.get_slice_starting_at(hit) {
-                            return Some(simplify(slice.into_points()));
-                        }
-                    }
-
-                    let mut pts = self.pl.points().clone();
-                    pts.push(pt);
-                    if let Ok(new) = PolyLine::new(pts) {
-                        self.pl = new;
-                    }
-                }
-            }
-        }
-        None
-    }
-
-    fn draw(&self, g: &mut GfxCtx) {
-        g.draw_polygon(
-            Color::RED.alpha(0.8),
-            &self
-                .pl
-                .make_polygons(Distance::meters(5.0) / g.canvas.cam_zoom),
-        );
-    }
-}
-
-fn simplify(mut raw: Vec<Pt2D>) -> Ring {
-    // TODO This is eating some of the shapes entirely. Wasn't meant for this.
-    if false {
-        let pts = raw
-            .into_iter()
-            .map(|pt| lttb::DataPoint::new(pt.x(), pt.y()))
-            .collect();
-        let mut downsampled = Vec::new();
-        for pt in lttb::lttb(pts, 50) {
-            downsampled.push(Pt2D::new(pt.x, pt.y));
-        }
-        downsampled.push(downsampled[0]);
-        Ring::must_new(downsampled)
-    } else {
-        raw.push(raw[0]);
-        Ring::must_new(raw)
-    }
-}
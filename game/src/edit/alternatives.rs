@@ -0,0 +1,197 @@
+use crate::app::App;
+use crate::edit::merge::shape;
+use crate::edit::{apply_map_edits, load_all_edits, LoadEdits, SaveEdits};
+use crate::game::{State, Transition};
+use crate::sandbox::GameplayMode;
+use ezgui::{
+    hotkey, Btn, Checkbox, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment,
+    Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use map_model::MapEdits;
+
+// Pick some saved edits to compare against the current ones
+pub struct PickAlternatives {
+    composite: Composite,
+    candidates: Vec<MapEdits>,
+}
+
+impl PickAlternatives {
+    pub fn new(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Box<dyn State> {
+        let current = app.primary.map.get_edits().edits_name.clone();
+        let candidates: Vec<MapEdits> = load_all_edits(app, mode, &current)
+            .into_iter()
+            .map(|(_, edits)| edits)
+            .collect();
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Compare design alternatives")
+                    .small_heading()
+                    .draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            format!("Compare {} with:", current).draw_text(ctx),
+        ];
+        for edits in &candidates {
+            col.push(Checkbox::text(ctx, &edits.edits_name, None, false));
+        }
+        col.push(Btn::text_bg2("compare").build_def(ctx, hotkey(Key::Enter)));
+
+        Box::new(PickAlternatives {
+            composite: Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+                .build(ctx),
+            candidates,
+        })
+    }
+}
+
+impl State for PickAlternatives {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "compare" => {
+                    let mut alternatives = vec![app.primary.map.get_edits().clone()];
+                    for edits in self.candidates.drain(..) {
+                        if self.composite.is_checked(&edits.edits_name) {
+                            alternatives.push(edits);
+                        }
+                    }
+                    Transition::Replace(CompareAlternatives::new(ctx, app, alternatives))
+                }
+                _ => unreachable!(),
+            },
+            None => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+// For public meetings. Everything touched by each alternative is drawn in its own color, and the
+// map shows one of them at a time.
+struct CompareAlternatives {
+    composite: Composite,
+    // The first is what we started with
+    alternatives: Vec<MapEdits>,
+    active: usize,
+    draw_touched: Drawable,
+}
+
+impl CompareAlternatives {
+    fn new(ctx: &mut EventCtx, app: &mut App, alternatives: Vec<MapEdits>) -> Box<dyn State> {
+        let mut state = CompareAlternatives {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            alternatives,
+            active: 0,
+            draw_touched: ctx.upload(GeomBatch::new()),
+        };
+        state.activate(ctx, app, 0);
+        Box::new(state)
+    }
+
+    fn activate(&mut self, ctx: &mut EventCtx, app: &mut App, idx: usize) {
+        self.active = idx;
+        apply_map_edits(ctx, app, self.alternatives[idx].clone());
+
+        // The active alternative goes on top
+        let mut batch = GeomBatch::new();
+        let mut order: Vec<usize> = (0..self.alternatives.len()).filter(|i| *i != idx).collect();
+        order.push(idx);
+        for i in order {
+            let color = app.cs.rotating_color_plot(i);
+            let alpha = if i == idx { 0.8 } else { 0.3 };
+            for cmd in &self.alternatives[i].commands {
+                batch.push(color.alpha(alpha), shape(app, cmd.edited_object()));
+            }
+        }
+        self.draw_touched = ctx.upload(batch);
+
+        let mut txt = Text::new();
+        for (i, edits) in self.alternatives.iter().enumerate() {
+            let line = Line(if i == idx {
+                format!("{} (showing)", edits.edits_name)
+            } else {
+                edits.edits_name.clone()
+            })
+            .fg(app.cs.rotating_color_plot(i));
+            txt.add(line);
+        }
+        self.composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("Design alternatives").small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            txt.draw(ctx),
+            Widget::row(vec![
+                Btn::text_fg("previous alternative").build_def(ctx, hotkey(Key::LeftArrow)),
+                Btn::text_fg("next alternative").build_def(ctx, hotkey(Key::RightArrow)),
+            ]),
+            Btn::text_bg2("keep editing this one").build_def(ctx, None),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
+
+impl State for CompareAlternatives {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    apply_map_edits(ctx, app, self.alternatives[0].clone());
+                    return Transition::Pop;
+                }
+                "keep editing this one" => {
+                    let orig = &self.alternatives[0];
+                    if self.active != 0
+                        && orig.edits_name == "untitled edits"
+                        && !orig.commands.is_empty()
+                    {
+                        // The edits we started with were never saved, so offer to save them first
+                        let chosen = self.alternatives[self.active].clone();
+                        apply_map_edits(ctx, app, self.alternatives[0].clone());
+                        return Transition::ReplaceThenPush(
+                            Box::new(LoadEdits {
+                                edits: Some(chosen),
+                            }),
+                            SaveEdits::new(
+                                ctx,
+                                app,
+                                "Do you want to save your edits first?",
+                                true,
+                                Some(Transition::PopTwice),
+                            ),
+                        );
+                    }
+                    return Transition::Pop;
+                }
+                "previous alternative" => {
+                    let n = self.alternatives.len();
+                    self.activate(ctx, app, (self.active + n - 1) % n);
+                }
+                "next alternative" => {
+                    self.activate(ctx, app, (self.active + 1) % self.alternatives.len());
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_touched);
+        self.composite.draw(g);
+    }
+}
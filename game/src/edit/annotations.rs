@@ -0,0 +1,260 @@
+use crate::app::App;
+use crate::game::{State, Transition, WizardState};
+use ezgui::{
+    hotkey, Btn, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{ArrowCap, Circle, Distance, PolyLine, Polygon, Pt2D};
+use map_model::Annotation;
+
+const ARROW_THICKNESS: Distance = Distance::const_meters(3.0);
+
+// Draw arrows, text callouts, and highlighted regions that get saved with the current edits.
+pub struct AnnotationEditor {
+    composite: Composite,
+    tool: Tool,
+    draw: Drawable,
+}
+
+#[derive(Clone, PartialEq)]
+enum Tool {
+    Idle,
+    // Once the first point is chosen, it's here
+    Arrow(Option<Pt2D>),
+    Callout,
+    Region(Vec<Pt2D>),
+}
+
+impl AnnotationEditor {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        Box::new(AnnotationEditor::with_tool(ctx, app, Tool::Idle))
+    }
+
+    fn with_tool(ctx: &mut EventCtx, app: &App, tool: Tool) -> AnnotationEditor {
+        let annotations = &app.primary.map.get_edits().annotations;
+
+        let mut col = vec![Widget::row(vec![
+            Line("Annotate proposal").small_heading().draw(ctx),
+            Btn::text_fg("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])];
+        match tool {
+            Tool::Idle => {
+                col.push(Widget::row(vec![
+                    Btn::text_fg("draw arrow").build_def(ctx, hotkey(Key::A)),
+                    Btn::text_fg("add callout").build_def(ctx, hotkey(Key::C)),
+                    Btn::text_fg("highlight region").build_def(ctx, hotkey(Key::R)),
+                ]));
+            }
+            Tool::Arrow(None) => {
+                col.push("Click where the arrow starts".draw_text(ctx));
+            }
+            Tool::Arrow(Some(_)) => {
+                col.push("Click where the arrow points".draw_text(ctx));
+            }
+            Tool::Callout => {
+                col.push("Click where the callout goes".draw_text(ctx));
+            }
+            Tool::Region(ref pts) => {
+                col.push("Click to add points around the region".draw_text(ctx));
+                if pts.len() >= 3 {
+                    col.push(Btn::text_fg("finish region").build_def(ctx, hotkey(Key::Enter)));
+                } else {
+                    col.push(Btn::text_fg("finish region").inactive(ctx));
+                }
+            }
+        }
+        if tool != Tool::Idle {
+            col.push(Btn::text_fg("cancel").build_def(ctx, hotkey(Key::Backspace)));
+        }
+
+        if annotations.is_empty() {
+            col.push("No annotations yet".draw_text(ctx));
+        }
+        for (idx, a) in annotations.iter().enumerate() {
+            let label = match a {
+                Annotation::Arrow { .. } => "arrow".to_string(),
+                Annotation::Callout { text, .. } => format!("\"{}\"", text),
+                Annotation::Region { .. } => "region".to_string(),
+            };
+            col.push(Widget::row(vec![
+                format!("{}) {}", idx + 1, label).draw_text(ctx),
+                Btn::text_fg("delete")
+                    .build(ctx, format!("delete #{}", idx + 1), None)
+                    .align_right(),
+            ]));
+        }
+
+        AnnotationEditor {
+            composite: Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+                .build(ctx),
+            tool,
+            draw: ctx.upload(draw_annotations(ctx, app, annotations)),
+        }
+    }
+
+    fn add(&mut self, ctx: &mut EventCtx, app: &mut App, annotation: Annotation) {
+        let mut annotations = app.primary.map.get_edits().annotations.clone();
+        annotations.push(annotation);
+        save(app, annotations);
+        *self = AnnotationEditor::with_tool(ctx, app, Tool::Idle);
+    }
+}
+
+impl State for AnnotationEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "cancel" => {
+                    *self = AnnotationEditor::with_tool(ctx, app, Tool::Idle);
+                }
+                "draw arrow" => {
+                    *self = AnnotationEditor::with_tool(ctx, app, Tool::Arrow(None));
+                }
+                "add callout" => {
+                    *self = AnnotationEditor::with_tool(ctx, app, Tool::Callout);
+                }
+                "highlight region" => {
+                    *self = AnnotationEditor::with_tool(ctx, app, Tool::Region(Vec::new()));
+                }
+                "finish region" => {
+                    if let Tool::Region(ref pts) = self.tool {
+                        let gps = app.primary.map.get_gps_bounds();
+                        let points = pts.iter().map(|pt| pt.to_gps(gps)).collect();
+                        self.add(ctx, app, Annotation::Region { points });
+                    }
+                }
+                x => {
+                    let idx = x["delete #".len()..].parse::<usize>().unwrap() - 1;
+                    let mut annotations = app.primary.map.get_edits().annotations.clone();
+                    annotations.remove(idx);
+                    save(app, annotations);
+                    *self = AnnotationEditor::with_tool(ctx, app, Tool::Idle);
+                }
+            },
+            None => {}
+        }
+
+        if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+            if self.tool != Tool::Idle && ctx.normal_left_click() {
+                let gps = app.primary.map.get_gps_bounds();
+                match self.tool.clone() {
+                    Tool::Idle => unreachable!(),
+                    Tool::Arrow(None) => {
+                        *self = AnnotationEditor::with_tool(ctx, app, Tool::Arrow(Some(pt)));
+                    }
+                    Tool::Arrow(Some(from)) => {
+                        if from.dist_to(pt) > ARROW_THICKNESS * 2.0 {
+                            let (from, to) = (from.to_gps(gps), pt.to_gps(gps));
+                            self.add(ctx, app, Annotation::Arrow { from, to });
+                        }
+                    }
+                    Tool::Callout => {
+                        let pt = pt.to_gps(gps);
+                        return Transition::Push(WizardState::new(Box::new(move |wiz, ctx, _| {
+                            let text = wiz.wrap(ctx).input_string("What should it say?")?;
+                            Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+                                let editor = state.downcast_mut::<AnnotationEditor>().unwrap();
+                                editor.add(ctx, app, Annotation::Callout { pt, text });
+                            })))
+                        })));
+                    }
+                    Tool::Region(mut pts) => {
+                        pts.push(pt);
+                        *self = AnnotationEditor::with_tool(ctx, app, Tool::Region(pts));
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+
+        let color = app.cs.annotations_layer;
+        match self.tool {
+            Tool::Arrow(Some(from)) => {
+                g.draw_circle(color, &Circle::new(from, ARROW_THICKNESS));
+                if let Some(pt) = g.get_cursor_in_map_space() {
+                    if from.dist_to(pt) > ARROW_THICKNESS * 2.0 {
+                        g.draw_polygon(
+                            color.alpha(0.5),
+                            &PolyLine::must_new(vec![from, pt])
+                                .make_arrow(ARROW_THICKNESS, ArrowCap::Triangle),
+                        );
+                    }
+                }
+            }
+            Tool::Region(ref pts) => {
+                for pt in pts {
+                    g.draw_circle(color, &Circle::new(*pt, ARROW_THICKNESS));
+                }
+                if pts.len() >= 3 {
+                    g.draw_polygon(color.alpha(0.3), &Polygon::new(pts));
+                }
+            }
+            _ => {}
+        }
+
+        self.composite.draw(g);
+    }
+}
+
+pub fn draw_annotations(ctx: &EventCtx, app: &App, annotations: &Vec<Annotation>) -> GeomBatch {
+    let gps = app.primary.map.get_gps_bounds();
+    let color = app.cs.annotations_layer;
+    let mut batch = GeomBatch::new();
+    for a in annotations {
+        match a {
+            Annotation::Arrow { from, to } => {
+                let (from, to) = (Pt2D::from_gps(*from, gps), Pt2D::from_gps(*to, gps));
+                if from.dist_to(to) > ARROW_THICKNESS * 2.0 {
+                    batch.push(
+                        color,
+                        PolyLine::must_new(vec![from, to])
+                            .make_arrow(ARROW_THICKNESS, ArrowCap::Triangle),
+                    );
+                }
+            }
+            Annotation::Callout { pt, text } => {
+                let pt = Pt2D::from_gps(*pt, gps);
+                batch.push(color, Circle::new(pt, ARROW_THICKNESS).to_polygon());
+                batch.append(
+                    Text::from(Line(text))
+                        .with_bg()
+                        .render_to_batch(ctx.prerender)
+                        .scale(0.5)
+                        .centered_on(pt.offset(0.0, -4.0 * ARROW_THICKNESS.inner_meters())),
+                );
+            }
+            Annotation::Region { points } => {
+                let pts: Vec<Pt2D> = points.iter().map(|pt| Pt2D::from_gps(*pt, gps)).collect();
+                if pts.len() < 3 {
+                    continue;
+                }
+                let poly = Polygon::new(&pts);
+                batch.push(color.alpha(0.3), poly.clone());
+                if let Ok(o) = poly.to_outline(Distance::meters(2.0)) {
+                    batch.push(color, o);
+                }
+            }
+        }
+    }
+    batch
+}
+
+fn save(app: &mut App, annotations: Vec<Annotation>) {
+    app.primary.map.set_edit_annotations(annotations);
+    if app.primary.map.get_edits().edits_name != "untitled edits" {
+        app.primary.map.save_edits();
+    }
+}
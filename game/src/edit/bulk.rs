@@ -1,13 +1,14 @@
 use crate::app::App;
+use crate::common::Lasso;
 use crate::edit::select::RoadSelector;
 use crate::edit::{apply_map_edits, change_speed_limit, try_change_lt};
 use crate::game::{msg, State, Transition};
 use ezgui::{
-    hotkey, Btn, Choice, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
-    Outcome, TextExt, VerticalAlignment, Widget,
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, TextExt, VerticalAlignment, Widget,
 };
-use geom::Speed;
-use map_model::{EditCmd, LaneType, RoadID};
+use geom::{Polygon, Pt2D, Speed};
+use map_model::{EditCmd, LaneID, LaneType, RoadID};
 use std::collections::BTreeSet;
 
 pub struct BulkSelect {
@@ -67,6 +68,7 @@ impl State for BulkSelect {
                 "edit roads" => {
                     return Transition::Replace(crate::edit::bulk::BulkEdit::new(
                         ctx,
+                        app,
                         self.selector.roads.iter().cloned().collect(),
                         self.selector.preview.take().unwrap(),
                     ));
@@ -106,45 +108,25 @@ struct BulkEdit {
 }
 
 impl BulkEdit {
-    fn new(ctx: &mut EventCtx, roads: Vec<RoadID>, preview: Drawable) -> Box<dyn State> {
+    fn new(ctx: &mut EventCtx, app: &App, roads: Vec<RoadID>, preview: Drawable) -> Box<dyn State> {
+        let default_speed = if app.opts.units.metric {
+            Speed::km_per_hour(40.0)
+        } else {
+            Speed::miles_per_hour(25.0)
+        };
         Box::new(BulkEdit {
             composite: Composite::new(Widget::col(vec![
                 Line(format!("Editing {} roads", roads.len()))
                     .small_heading()
                     .draw(ctx),
                 Widget::custom_row(vec![
-                    change_speed_limit(ctx, Speed::miles_per_hour(25.0)),
+                    change_speed_limit(ctx, &app.opts.units, default_speed),
                     Btn::text_fg("Confirm")
                         .build(ctx, "confirm speed limit", None)
                         .align_right(),
                 ]),
                 Widget::row(vec![
-                    "Change all".draw_text(ctx).centered_vert(),
-                    Widget::dropdown(
-                        ctx,
-                        "from lt",
-                        LaneType::Driving,
-                        vec![
-                            Choice::new("driving", LaneType::Driving),
-                            Choice::new("parking", LaneType::Parking),
-                            Choice::new("bike", LaneType::Biking),
-                            Choice::new("bus", LaneType::Bus),
-                            Choice::new("construction", LaneType::Construction),
-                        ],
-                    ),
-                    "lanes to".draw_text(ctx).centered_vert(),
-                    Widget::dropdown(
-                        ctx,
-                        "to lt",
-                        LaneType::Bus,
-                        vec![
-                            Choice::new("driving", LaneType::Driving),
-                            Choice::new("parking", LaneType::Parking),
-                            Choice::new("bike", LaneType::Biking),
-                            Choice::new("bus", LaneType::Bus),
-                            Choice::new("construction", LaneType::Construction),
-                        ],
-                    ),
+                    change_lane_types_widgets(ctx, LaneType::Driving, LaneType::Bus),
                     Btn::text_fg("Confirm")
                         .build(ctx, "confirm lanes", None)
                         .align_right(),
@@ -250,3 +232,276 @@ fn change_lane_types(
         )],
     )
 }
+
+fn change_lane_types_widgets(ctx: &mut EventCtx, from: LaneType, to: LaneType) -> Widget {
+    let choices = || {
+        vec![
+            Choice::new("driving", LaneType::Driving),
+            Choice::new("parking", LaneType::Parking),
+            Choice::new("bike", LaneType::Biking),
+            Choice::new("bus", LaneType::Bus),
+            Choice::new("construction", LaneType::Construction),
+        ]
+    };
+    Widget::row(vec![
+        "Change all".draw_text(ctx).centered_vert(),
+        Widget::dropdown(ctx, "from lt", from, choices()),
+        "lanes to".draw_text(ctx).centered_vert(),
+        Widget::dropdown(ctx, "to lt", to, choices()),
+    ])
+}
+
+// Pick individual lanes by dragging a rectangle or a freehand shape around them, then change all of
+// them at once.
+pub struct LaneSelect {
+    composite: Composite,
+    lanes: BTreeSet<LaneID>,
+    mode: SelectMode,
+    preview: Drawable,
+}
+
+enum SelectMode {
+    Pan,
+    // The first corner, once dragging starts
+    Rectangle(Option<Pt2D>),
+    Freehand(Option<Lasso>),
+}
+
+impl LaneSelect {
+    pub fn new(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State> {
+        app.primary.current_selection = None;
+        let mut state = LaneSelect {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            lanes: BTreeSet::new(),
+            mode: SelectMode::Rectangle(None),
+            preview: ctx.upload(GeomBatch::new()),
+        };
+        state.lanes_changed(ctx, app);
+        Box::new(state)
+    }
+
+    fn lanes_changed(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let mut batch = GeomBatch::new();
+        for l in &self.lanes {
+            let lane = map.get_l(*l);
+            batch.push(
+                Color::BLUE.alpha(0.5),
+                lane.lane_center_pts.make_polygons(lane.width),
+            );
+        }
+        self.preview = ctx.upload(batch);
+        self.make_composite(ctx);
+    }
+
+    fn make_composite(&mut self, ctx: &mut EventCtx) {
+        let mode_btn = |ctx: &mut EventCtx, label: &str, key: Key, active: bool| {
+            if active {
+                Btn::text_bg2(label).inactive(ctx)
+            } else {
+                Btn::text_bg2(label).build_def(ctx, hotkey(key))
+            }
+        };
+        // Rebuilding resets the dropdowns
+        let (from, to) = if self.composite.has_widget("from lt") {
+            (
+                self.composite.dropdown_value("from lt"),
+                self.composite.dropdown_value("to lt"),
+            )
+        } else {
+            (LaneType::Parking, LaneType::Biking)
+        };
+        let mut new = Composite::new(Widget::col(vec![
+            Line("Select lanes").small_heading().draw(ctx),
+            Widget::row(vec![
+                mode_btn(
+                    ctx,
+                    "rectangle",
+                    Key::R,
+                    matches!(self.mode, SelectMode::Rectangle(_)),
+                ),
+                mode_btn(
+                    ctx,
+                    "freehand",
+                    Key::F,
+                    matches!(self.mode, SelectMode::Freehand(_)),
+                ),
+                mode_btn(ctx, "pan", Key::P, matches!(self.mode, SelectMode::Pan)),
+            ]),
+            Widget::row(vec![
+                format!("{} lanes selected", self.lanes.len())
+                    .draw_text(ctx)
+                    .centered_vert(),
+                if self.lanes.is_empty() {
+                    Btn::text_fg("clear").inactive(ctx)
+                } else {
+                    Btn::text_fg("clear").build_def(ctx, None)
+                },
+            ]),
+            Widget::row(vec![
+                change_lane_types_widgets(ctx, from, to),
+                if self.lanes.is_empty() {
+                    Btn::text_fg("Confirm").inactive(ctx)
+                } else {
+                    Btn::text_fg("Confirm").build(ctx, "confirm lanes", hotkey(Key::Enter))
+                }
+                .align_right(),
+            ]),
+            Btn::text_fg("Quit").build_def(ctx, hotkey(Key::Escape)),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+        new.restore(ctx, &self.composite);
+        self.composite = new;
+    }
+}
+
+impl State for LaneSelect {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let mut area: Option<Polygon> = None;
+        match self.mode {
+            SelectMode::Pan => {
+                ctx.canvas_movement();
+            }
+            SelectMode::Rectangle(ref mut corner) => {
+                if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                    if corner.is_none() && ctx.input.left_mouse_button_pressed() {
+                        *corner = Some(pt);
+                    } else if corner.is_some() && ctx.input.left_mouse_button_released() {
+                        area = Polygon::rectangle_two_corners(corner.take().unwrap(), pt);
+                    }
+                }
+            }
+            SelectMode::Freehand(ref mut lasso) => {
+                if let Some(l) = lasso {
+                    if let Some(ring) = l.event(ctx) {
+                        area = Some(Polygon::new(&ring.into_points()));
+                        *lasso = None;
+                    }
+                } else if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                    if ctx.input.left_mouse_button_pressed() {
+                        *lasso = Some(Lasso::new(pt));
+                    }
+                }
+            }
+        }
+        if let Some(area) = area {
+            let before = self.lanes.len();
+            self.lanes.extend(
+                app.primary
+                    .draw_map
+                    .get_matching_lanes(&app.primary.map, &area),
+            );
+            if self.lanes.len() != before {
+                self.lanes_changed(ctx, app);
+            }
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "Quit" => {
+                    return Transition::Pop;
+                }
+                "rectangle" => {
+                    self.mode = SelectMode::Rectangle(None);
+                    self.make_composite(ctx);
+                }
+                "freehand" => {
+                    self.mode = SelectMode::Freehand(None);
+                    self.make_composite(ctx);
+                }
+                "pan" => {
+                    self.mode = SelectMode::Pan;
+                    self.make_composite(ctx);
+                }
+                "clear" => {
+                    self.lanes.clear();
+                    self.lanes_changed(ctx, app);
+                }
+                "confirm lanes" => {
+                    return Transition::Push(change_selected_lane_types(
+                        ctx,
+                        app,
+                        &self.lanes,
+                        self.composite.dropdown_value("from lt"),
+                        self.composite.dropdown_value("to lt"),
+                    ));
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.preview);
+        match self.mode {
+            SelectMode::Pan => {}
+            SelectMode::Rectangle(corner) => {
+                if let (Some(pt1), Some(pt2)) = (corner, g.canvas.get_cursor_in_map_space()) {
+                    if let Some(rect) = Polygon::rectangle_two_corners(pt1, pt2) {
+                        g.draw_polygon(Color::RED.alpha(0.5), &rect);
+                    }
+                }
+            }
+            SelectMode::Freehand(ref lasso) => {
+                if let Some(l) = lasso {
+                    l.draw(g);
+                }
+            }
+        }
+        self.composite.draw(g);
+    }
+}
+
+// All of the changes undo together.
+fn change_selected_lane_types(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    lanes: &BTreeSet<LaneID>,
+    from: LaneType,
+    to: LaneType,
+) -> Box<dyn State> {
+    let orig_edits = app.primary.map.get_edits().clone();
+    let mut cmds = Vec::new();
+    let mut errors = 0;
+    ctx.loading_screen("change lane types", |ctx, timer| {
+        timer.start_iter("validate lanes", lanes.len());
+        for l in lanes {
+            timer.next();
+            if app.primary.map.get_l(*l).lane_type != from {
+                continue;
+            }
+            match try_change_lt(&mut app.primary.map, *l, to) {
+                Ok(cmd) => {
+                    // Change the map (but not the rendering yet), so the next lane sees the true
+                    // state of the world.
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(cmd.clone());
+                    app.primary.map.must_apply_edits(edits, timer);
+                    cmds.push(cmd);
+                }
+                Err(_) => {
+                    errors += 1;
+                }
+            }
+        }
+
+        let mut edits = orig_edits;
+        edits.push_group(cmds.clone());
+        apply_map_edits(ctx, app, edits);
+    });
+
+    msg(
+        "Changed lane types",
+        vec![format!(
+            "Changed {} {:?} lanes to {:?} lanes. {} couldn't be changed.",
+            cmds.len(),
+            from,
+            to,
+            errors
+        )],
+    )
+}
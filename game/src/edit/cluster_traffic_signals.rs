@@ -85,6 +85,7 @@ impl State for ClusterTrafficSignalEditor {
                 );
             } else {
                 batch.push(app.cs.signal_turn_block_bg, g.block.clone());
+                batch.push(g.kind.color(&app.cs), g.outline.clone());
             }
             let arrow_color = app.cs.signal_protected_turn;
             batch.push(arrow_color, g.arrow.clone());
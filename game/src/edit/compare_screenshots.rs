@@ -0,0 +1,254 @@
+use crate::app::{App, ShowEverything};
+use crate::common::ColorNetwork;
+use crate::edit::apply_map_edits;
+use crate::game::{DrawBaselayer, State, Transition};
+use crate::render::DrawOptions;
+use abstutil::Counter;
+use ezgui::{
+    hotkey, Btn, Checkbox, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, UpdateType, VerticalAlignment, Widget,
+};
+use geom::{Duration, Time};
+use map_model::{MapEdits, RoadID};
+use sim::{Analytics, DontDrawAgents};
+use std::process;
+
+// Captures the current view twice, once on the original map and once with the edits, and tiles
+// the two into one labeled image for a proposal document. Needs scrot and imagemagick, like
+// "screenshot everything" in debug mode.
+pub struct CompareScreenshots {
+    composite: Composite,
+    edits: MapEdits,
+    dir: String,
+    step: Step,
+    // The baseline run and the run with these edits, if both exist. Throughput during the busiest
+    // hour of the baseline, both colored on the same scale.
+    peak_hour: Option<(usize, Overlay, Overlay)>,
+    show_overlay: bool,
+}
+
+struct Overlay {
+    unzoomed: Drawable,
+    zoomed: Drawable,
+}
+
+#[derive(PartialEq)]
+enum Step {
+    Setup,
+    Before,
+    After,
+}
+
+impl CompareScreenshots {
+    // after_run should be the analytics from simulating with the current edits.
+    pub fn new(ctx: &mut EventCtx, app: &App, after_run: Option<&Analytics>) -> Box<dyn State> {
+        let peak_hour = match (app.has_prebaked(), after_run) {
+            (Some(_), Some(after)) => peak_hour_overlays(ctx, app, app.prebaked(), after),
+            _ => None,
+        };
+
+        Box::new(CompareScreenshots {
+            composite: Composite::new(Widget::col(vec![
+                Line("Compare screenshots").small_heading().draw(ctx),
+                "Capture this view before and after the edits"
+                    .draw_text(ctx)
+                    .margin_below(10),
+                if let Some((hour, _, _)) = &peak_hour {
+                    Checkbox::text(
+                        ctx,
+                        format!("show throughput from {} in both runs", hour_label(*hour)),
+                        None,
+                        false,
+                    )
+                } else {
+                    Widget::nothing()
+                },
+                Widget::row(vec![
+                    Btn::text_bg2("capture").build_def(ctx, hotkey(Key::Enter)),
+                    Btn::text_bg2("cancel").build_def(ctx, hotkey(Key::Escape)),
+                ])
+                .margin_above(10),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+            .build(ctx),
+            edits: app.primary.map.get_edits().clone(),
+            dir: format!("screenshots_{}_comparison", app.primary.map.get_name()),
+            step: Step::Setup,
+            peak_hour,
+            show_overlay: false,
+        })
+    }
+
+    fn label(&self, ctx: &mut EventCtx) -> Composite {
+        let mut txt = Text::from(
+            Line(match self.step {
+                Step::Before => "Before".to_string(),
+                _ => format!("After: {}", self.edits.edits_name),
+            })
+            .small_heading(),
+        );
+        if self.show_overlay {
+            let (hour, _, _) = self.peak_hour.as_ref().unwrap();
+            txt.add(Line(format!("Throughput from {}", hour_label(*hour))).secondary());
+        }
+        Composite::new(txt.draw(ctx))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx)
+    }
+
+    // Switches the map to the original or edited version, then captures it.
+    fn capture(&mut self, ctx: &mut EventCtx, app: &mut App, step: Step) {
+        let (edits, name) = if step == Step::Before {
+            (MapEdits::new(), "before")
+        } else {
+            (self.edits.clone(), "after")
+        };
+        apply_map_edits(ctx, app, edits);
+        self.step = step;
+        self.composite = self.label(ctx);
+        ctx.request_update(UpdateType::ScreenCaptureCurrentShot {
+            filename: format!("{}/{}.png", self.dir, name),
+        });
+        // Make sure we get another event after the capture
+        ctx.request_update(UpdateType::Game);
+    }
+}
+
+impl State for CompareScreenshots {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.step {
+            Step::Setup => {
+                match self.composite.event(ctx) {
+                    Some(Outcome::Clicked(x)) => match x.as_ref() {
+                        "capture" => {
+                            self.show_overlay = self.peak_hour.is_some()
+                                && self.composite.is_checked(&format!(
+                                    "show throughput from {} in both runs",
+                                    hour_label(self.peak_hour.as_ref().unwrap().0)
+                                ));
+                            self.capture(ctx, app, Step::Before);
+                        }
+                        "cancel" => {
+                            return Transition::Pop;
+                        }
+                        _ => unreachable!(),
+                    },
+                    None => {}
+                }
+                Transition::Keep
+            }
+            Step::Before => {
+                self.capture(ctx, app, Step::After);
+                Transition::Keep
+            }
+            Step::After => {
+                let output = format!("{}/comparison.png", self.dir);
+                if process::Command::new("montage")
+                    .arg(format!("{}/before.png", self.dir))
+                    .arg(format!("{}/after.png", self.dir))
+                    .args(&["-tile", "2x1", "-geometry", "+4+0"])
+                    .arg(&output)
+                    .status()
+                    .map(|s| s.success())
+                    .unwrap_or(false)
+                {
+                    println!("Wrote {}", output);
+                } else {
+                    println!(
+                        "Couldn't combine the screenshots in {}; you probably don't have montage \
+                         (https://imagemagick.org) installed",
+                        self.dir
+                    );
+                }
+                Transition::Pop
+            }
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        app.draw(
+            g,
+            DrawOptions::new(),
+            &DontDrawAgents {},
+            &ShowEverything::new(),
+        );
+        if self.show_overlay {
+            let (_, before, after) = self.peak_hour.as_ref().unwrap();
+            let overlay = if self.step == Step::Before {
+                before
+            } else {
+                after
+            };
+            if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+                g.redraw(&overlay.unzoomed);
+            } else {
+                g.redraw(&overlay.zoomed);
+            }
+        }
+        self.composite.draw(g);
+    }
+}
+
+fn peak_hour_overlays(
+    ctx: &mut EventCtx,
+    app: &App,
+    before: &Analytics,
+    after: &Analytics,
+) -> Option<(usize, Overlay, Overlay)> {
+    let mut per_hour = Counter::new();
+    for ((_, _, hr), count) in &before.road_thruput.counts {
+        per_hour.add(*hr, *count);
+    }
+    let hour = per_hour
+        .borrow()
+        .iter()
+        .max_by_key(|(_, count)| **count)
+        .map(|(hr, _)| *hr)?;
+
+    let before_counts = road_counts_during(before, hour);
+    let after_counts = road_counts_during(after, hour);
+    let max = before_counts
+        .borrow()
+        .values()
+        .chain(after_counts.borrow().values())
+        .max()
+        .cloned()
+        .unwrap_or(0)
+        .max(1) as f64;
+
+    let mut overlays = Vec::new();
+    for counts in vec![before_counts, after_counts] {
+        let mut colorer = ColorNetwork::new(app);
+        for (r, count) in counts.consume() {
+            colorer.add_r(r, app.cs.good_to_bad_red.eval((count as f64) / max));
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+        overlays.push(Overlay { unzoomed, zoomed });
+    }
+    let after_overlay = overlays.pop().unwrap();
+    let before_overlay = overlays.pop().unwrap();
+    Some((hour, before_overlay, after_overlay))
+}
+
+fn road_counts_during(analytics: &Analytics, hour: usize) -> Counter<RoadID> {
+    let mut cnt = Counter::new();
+    for ((r, _, hr), count) in &analytics.road_thruput.counts {
+        if *hr == hour {
+            cnt.add(*r, *count);
+        }
+    }
+    cnt
+}
+
+fn hour_label(hour: usize) -> String {
+    let start = Time::START_OF_DAY + Duration::hours(hour);
+    format!(
+        "{} to {}",
+        start.ampm_tostring(),
+        (start + Duration::hours(1)).ampm_tostring()
+    )
+}
@@ -0,0 +1,304 @@
+use crate::app::App;
+use crate::common::{ColorDiscrete, CommonState};
+use crate::edit::apply_map_edits;
+use crate::game::{State, Transition};
+use crate::helpers::ID;
+use enumset::EnumSet;
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Distance;
+use map_model::{
+    osm, EditCmd, IntersectionID, LaneID, LaneType, Map, Neighborhood, PathConstraints,
+    PathRequest, PathStep, Position, RoadID,
+};
+use std::collections::{BTreeSet, HashMap};
+
+// Turn a neighborhood into a low-traffic neighborhood by placing modal filters (bollards) on its
+// residential streets. Filtered streets become restricted access zones: people walking and biking
+// can pass through, but cars can only enter to reach somewhere inside. Zones are flooded from
+// connected streets with the same restrictions, so adjacent filtered streets join into one zone,
+// and cars can drive along all of them to reach somewhere on one.
+pub struct FilterEditor {
+    composite: Composite,
+    name: String,
+    // Residential streets inside the neighborhood
+    interior: BTreeSet<RoadID>,
+    filters: BTreeSet<RoadID>,
+    // Every other road cars can't cut through, like private zones
+    other_zones: BTreeSet<RoadID>,
+    // Finding rat runs is slow, so remember the result for every set of filters tried
+    rat_runs: HashMap<BTreeSet<RoadID>, Vec<BTreeSet<RoadID>>>,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+}
+
+impl FilterEditor {
+    pub fn new(ctx: &mut EventCtx, app: &mut App, n: Neighborhood) -> Box<dyn State> {
+        let map = &app.primary.map;
+        let interior: BTreeSet<RoadID> = if let Some(poly) = n.polygon(map) {
+            map.all_roads()
+                .iter()
+                // Footways, service roads, and so on have lower ranks, but aren't residential
+                .filter(|r| {
+                    r.osm_tags.get(osm::HIGHWAY) == Some(&"residential".to_string())
+                        && poly.contains_pt(r.center_pts.middle())
+                })
+                .map(|r| r.id)
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+        let filters = interior
+            .iter()
+            .filter(|r| map.get_r(**r).allow_through_traffic == filtered())
+            .cloned()
+            .collect();
+        let other_zones = map
+            .all_roads()
+            .iter()
+            .filter(|r| {
+                !r.allow_through_traffic.contains(PathConstraints::Car)
+                    && !(interior.contains(&r.id) && r.allow_through_traffic == filtered())
+            })
+            .map(|r| r.id)
+            .collect();
+
+        app.primary.current_selection = None;
+        let mut editor = FilterEditor {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            name: n.name,
+            interior,
+            filters,
+            other_zones,
+            rat_runs: HashMap::new(),
+            unzoomed: ctx.upload(GeomBatch::new()),
+            zoomed: ctx.upload(GeomBatch::new()),
+        };
+        editor.recalculate(ctx, app);
+        Box::new(editor)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        if !self.rat_runs.contains_key(&self.filters) {
+            let mut avoid = self.filters.clone();
+            avoid.extend(self.other_zones.iter().cloned());
+            let runs = find_rat_runs(&app.primary.map, &self.interior, &avoid);
+            self.rat_runs.insert(self.filters.clone(), runs);
+        }
+        let rat_runs = &self.rat_runs[&self.filters];
+        let mut rat_run_roads = BTreeSet::new();
+        for run in rat_runs {
+            rat_run_roads.extend(run.iter().cloned());
+        }
+
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("residential street", Color::CYAN.alpha(0.5)),
+                ("modal filter", Color::GREEN),
+                ("rat run", Color::RED),
+            ],
+        );
+        for r in &self.interior {
+            colorer.add_r(
+                *r,
+                if self.filters.contains(r) {
+                    "modal filter"
+                } else if rat_run_roads.contains(r) {
+                    "rat run"
+                } else {
+                    "residential street"
+                },
+            );
+        }
+        let (unzoomed, zoomed, legend) = colorer.build(ctx);
+        self.unzoomed = unzoomed;
+        self.zoomed = zoomed;
+
+        self.composite = Composite::new(Widget::col(vec![
+            Line(format!("Modal filters in {}", self.name))
+                .small_heading()
+                .draw(ctx),
+            Text::from(
+                Line(
+                    "Click residential streets to add or remove filters. Drivers can still reach \
+                     every street, but can't cut through.",
+                )
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 30)
+            .draw(ctx),
+            legend,
+            format!(
+                "{} filters, {} residential streets",
+                self.filters.len(),
+                self.interior.len()
+            )
+            .draw_text(ctx),
+            if rat_runs.is_empty() {
+                "No rat runs left".draw_text(ctx)
+            } else {
+                format!(
+                    "{} rat runs left, through {} streets",
+                    rat_runs.len(),
+                    rat_run_roads.len()
+                )
+                .draw_text(ctx)
+            },
+            Widget::custom_row(vec![
+                Btn::text_fg("Apply").build_def(ctx, hotkey(Key::Enter)),
+                Btn::text_fg("Cancel").build_def(ctx, hotkey(Key::Escape)),
+            ])
+            .evenly_spaced(),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
+
+impl State for FilterEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+        }
+        if let Some(ID::Lane(l)) = app.primary.current_selection {
+            let r = app.primary.map.get_l(l).parent;
+            if self.interior.contains(&r) {
+                if self.filters.contains(&r) {
+                    if app.per_obj.left_click(ctx, "remove the modal filter") {
+                        self.filters.remove(&r);
+                        self.recalculate(ctx, app);
+                    }
+                } else if app.per_obj.left_click(ctx, "add a modal filter") {
+                    self.filters.insert(r);
+                    self.recalculate(ctx, app);
+                }
+            }
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "Apply" => {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    for r in &self.interior {
+                        let old = app.primary.map.get_r(*r).allow_through_traffic;
+                        let new = if self.filters.contains(r) {
+                            filtered()
+                        } else if old == filtered() {
+                            EnumSet::all()
+                        } else {
+                            // Leave other zones alone
+                            continue;
+                        };
+                        if old != new {
+                            edits.commands.push(EditCmd::ChangeAccessRestrictions {
+                                id: *r,
+                                old_allow_through_traffic: old,
+                                new_allow_through_traffic: new,
+                            });
+                        }
+                    }
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Pop;
+                }
+                "Cancel" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+// Who can pass through a modal filter
+fn filtered() -> EnumSet<PathConstraints> {
+    PathConstraints::Pedestrian | PathConstraints::Bike
+}
+
+// Try driving between every pair of places where a main road meets the neighborhood, never passing
+// through the roads to avoid. If the fastest way uses a residential street, that's a rat run.
+// Returns the residential streets used by each one.
+fn find_rat_runs(
+    map: &Map,
+    interior: &BTreeSet<RoadID>,
+    avoid: &BTreeSet<RoadID>,
+) -> Vec<BTreeSet<RoadID>> {
+    let mut entrances: Vec<(IntersectionID, LaneID, LaneID)> = Vec::new();
+    let mut seen = BTreeSet::new();
+    for r in interior {
+        let r = map.get_r(*r);
+        for i in vec![r.src_i, r.dst_i] {
+            if !seen.insert(i) {
+                continue;
+            }
+            let i = map.get_i(i);
+            let main_road_lane = |lanes: &Vec<LaneID>| {
+                lanes
+                    .iter()
+                    .find(|l| {
+                        let lane = map.get_l(**l);
+                        lane.lane_type == LaneType::Driving && !interior.contains(&lane.parent)
+                    })
+                    .cloned()
+            };
+            if let (Some(into), Some(out_of)) = (
+                main_road_lane(&i.incoming_lanes),
+                main_road_lane(&i.outgoing_lanes),
+            ) {
+                entrances.push((i.id, into, out_of));
+            }
+        }
+    }
+
+    let mut reqs = Vec::new();
+    for (i1, into, _) in &entrances {
+        for (i2, _, out_of) in &entrances {
+            if i1 == i2 {
+                continue;
+            }
+            reqs.push(PathRequest {
+                start: Position::new(*into, Distance::ZERO),
+                end: Position::new(*out_of, map.get_l(*out_of).length()),
+                constraints: PathConstraints::Car,
+            });
+        }
+    }
+
+    let mut rat_runs = Vec::new();
+    // One search from each entrance
+    for path in map
+        .pathfind_many_avoiding(reqs, avoid)
+        .into_iter()
+        .flatten()
+    {
+        let through: BTreeSet<RoadID> = path
+            .get_steps()
+            .iter()
+            .filter_map(|step| match step {
+                PathStep::Lane(l) => Some(map.get_l(*l).parent),
+                _ => None,
+            })
+            .filter(|r| interior.contains(r))
+            .collect();
+        if !through.is_empty() {
+            rat_runs.push(through);
+        }
+    }
+    rat_runs
+}
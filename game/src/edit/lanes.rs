@@ -2,18 +2,19 @@ use crate::app::App;
 use crate::common::CommonState;
 use crate::edit::zones::ZoneEditor;
 use crate::edit::{
-    apply_map_edits, can_edit_lane, change_speed_limit, maybe_edit_intersection, try_change_lt,
-    try_reverse,
+    apply_map_edits, can_edit_lane, change_parking_policy, change_speed_limit,
+    maybe_edit_intersection, try_change_lt, try_reverse,
 };
-use crate::game::{State, Transition};
+use crate::game::{State, Transition, WizardState};
 use crate::helpers::ID;
 use crate::render::Renderable;
 use crate::sandbox::GameplayMode;
 use ezgui::{
-    hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome,
-    RewriteColor, TextExt, VerticalAlignment, Widget,
+    hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    RewriteColor, Text, TextExt, VerticalAlignment, Widget,
 };
-use map_model::{EditCmd, LaneID, LaneType};
+use geom::{Duration, Speed, Time};
+use map_model::{EditCmd, LaneID, LaneType, ParkingPolicy, SpeedLimitPeriod};
 
 pub struct LaneEditor {
     l: LaneID,
@@ -74,13 +75,61 @@ impl LaneEditor {
         }
 
         let parent = app.primary.map.get_parent(l);
-        let col = vec![
+        let mut col = vec![
             format!("Convert this lane of {} to what type?", parent.get_name())
                 .draw_text(ctx)
                 .centered_horiz(),
             Widget::custom_row(row).centered(),
-            change_speed_limit(ctx, parent.speed_limit),
-            Btn::text_fg("Change access restrictions").build_def(ctx, hotkey(Key::A)),
+            change_speed_limit(ctx, &app.opts.units, parent.speed_limit),
+            Widget::row(vec![
+                if parent.speed_limit_schedule.is_empty() {
+                    "No time-based limits".draw_text(ctx)
+                } else {
+                    let mut lines: Vec<_> = parent
+                        .speed_limit_schedule
+                        .iter()
+                        .map(|p| Line(p.describe(&app.opts.units)))
+                        .collect();
+                    lines.push(
+                        Line("Vehicles obey these, but still pick routes using the usual limit")
+                            .secondary(),
+                    );
+                    Text::from_multiline(lines).draw(ctx)
+                },
+                Btn::text_fg("add time-based limit").build_def(ctx, None),
+                if parent.speed_limit_schedule.is_empty() {
+                    Btn::text_fg("clear time-based limits").inactive(ctx)
+                } else {
+                    Btn::text_fg("clear time-based limits").build_def(ctx, None)
+                },
+            ]),
+        ];
+        if lt == LaneType::Parking {
+            col.push(change_parking_policy(
+                ctx,
+                app.primary.map.get_l(l).parking_policy,
+            ));
+        }
+        col.extend(vec![
+            Widget::row(vec![
+                Btn::text_fg("Change access restrictions").build_def(ctx, hotkey(Key::A)),
+                if parent.allow_trucks {
+                    Btn::text_fg("ban trucks").build_def(ctx, None)
+                } else {
+                    Btn::text_fg("allow trucks").build_def(ctx, None)
+                },
+                if parent.bus_lane_camera {
+                    Btn::text_fg("remove bus lane camera").build_def(ctx, None)
+                } else if parent
+                    .all_lanes()
+                    .into_iter()
+                    .any(|l| app.primary.map.get_l(l).is_bus())
+                {
+                    Btn::text_fg("add bus lane camera").build_def(ctx, None)
+                } else {
+                    Widget::nothing()
+                },
+            ]),
             Widget::custom_row(vec![
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
                 // TODO Handle reverting speed limit too...
@@ -93,7 +142,7 @@ impl LaneEditor {
                 },
             ])
             .centered(),
-        ];
+        ]);
 
         let composite = Composite::new(Widget::col(col))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
@@ -148,6 +197,57 @@ impl State for LaneEditor {
                         app.primary.map.get_l(self.l).parent,
                     ));
                 }
+                "ban trucks" | "allow trucks" => {
+                    let parent = app.primary.map.get_parent(self.l);
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(EditCmd::ChangeTruckAccess {
+                        id: parent.id,
+                        new: !parent.allow_trucks,
+                        old: parent.allow_trucks,
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(LaneEditor::new(
+                        ctx,
+                        app,
+                        self.l,
+                        self.mode.clone(),
+                    )));
+                }
+                "add bus lane camera" | "remove bus lane camera" => {
+                    let parent = app.primary.map.get_parent(self.l);
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(EditCmd::ChangeBusLaneCamera {
+                        id: parent.id,
+                        new: !parent.bus_lane_camera,
+                        old: parent.bus_lane_camera,
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(LaneEditor::new(
+                        ctx,
+                        app,
+                        self.l,
+                        self.mode.clone(),
+                    )));
+                }
+                "add time-based limit" => {
+                    return Transition::Push(add_speed_limit_period(self.l, self.mode.clone()));
+                }
+                "clear time-based limits" => {
+                    let parent = app.primary.map.get_parent(self.l);
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(EditCmd::ChangeSpeedLimitSchedule {
+                        id: parent.id,
+                        new: Vec::new(),
+                        old: parent.speed_limit_schedule.clone(),
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(LaneEditor::new(
+                        ctx,
+                        app,
+                        self.l,
+                        self.mode.clone(),
+                    )));
+                }
                 "Finish" => {
                     return Transition::Pop;
                 }
@@ -216,6 +316,30 @@ impl State for LaneEditor {
                         self.mode.clone(),
                     )));
                 }
+
+                let lane = app.primary.map.get_l(self.l);
+                if lane.is_parking() {
+                    let new = ParkingPolicy {
+                        hourly_price: self.composite.dropdown_value("parking price"),
+                        max_stay: self.composite.dropdown_value("parking time limit"),
+                    };
+                    let old = lane.parking_policy;
+                    if new != old {
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.commands.push(EditCmd::ChangeParkingPolicy {
+                            id: self.l,
+                            new,
+                            old,
+                        });
+                        apply_map_edits(ctx, app, edits);
+                        return Transition::Replace(Box::new(LaneEditor::new(
+                            ctx,
+                            app,
+                            self.l,
+                            self.mode.clone(),
+                        )));
+                    }
+                }
             }
         }
 
@@ -234,3 +358,62 @@ impl State for LaneEditor {
         CommonState::draw_osd(g, app);
     }
 }
+
+// Like a school zone. Whole hours are precise enough.
+fn add_speed_limit_period(l: LaneID, mode: GameplayMode) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let mut wizard = wiz.wrap(ctx);
+        let metric = app.opts.units.metric;
+        // A limit of 0 would stop everybody forever
+        let limit = wizard.input_something(
+            if metric {
+                "What speed limit, in km/h?"
+            } else {
+                "What speed limit, in mph?"
+            },
+            None,
+            Box::new(|line| line.parse::<usize>().ok().filter(|x| *x >= 1)),
+        )?;
+        let start = wizard.input_something(
+            "Starting at what hour? (0 to 23)",
+            None,
+            Box::new(parse_hour),
+        )?;
+        let end = wizard.input_something(
+            "Until what hour? (0 to 23, not the start; earlier to go past midnight)",
+            None,
+            // SpeedLimitPeriod::validate rejects empty periods
+            Box::new(move |line| parse_hour(line).filter(|h| *h != start)),
+        )?;
+
+        let parent = app.primary.map.get_parent(l);
+        let old = parent.speed_limit_schedule.clone();
+        let mut new = old.clone();
+        new.push(SpeedLimitPeriod {
+            start: Time::START_OF_DAY + Duration::hours(start),
+            end: Time::START_OF_DAY + Duration::hours(end),
+            limit: if metric {
+                Speed::km_per_hour(limit as f64)
+            } else {
+                Speed::miles_per_hour(limit as f64)
+            },
+        });
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeSpeedLimitSchedule {
+            id: parent.id,
+            new,
+            old,
+        });
+        apply_map_edits(ctx, app, edits);
+        Some(Transition::PopThenReplace(Box::new(LaneEditor::new(
+            ctx,
+            app,
+            l,
+            mode.clone(),
+        ))))
+    }))
+}
+
+fn parse_hour(line: String) -> Option<usize> {
+    line.parse::<usize>().ok().filter(|h| *h < 24)
+}
@@ -0,0 +1,340 @@
+use crate::app::App;
+use crate::edit::{apply_map_edits, load_all_edits};
+use crate::game::{State, Transition, WizardState};
+use crate::sandbox::GameplayMode;
+use ezgui::{
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, MultiKey, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{ArrowCap, Distance, Polygon};
+use map_model::{
+    EditCmd, EditIntersection, EditedObject, LaneType, MapEdits, MergedEdits, Resolution,
+};
+
+const PREVIEW_SIZE: f64 = 200.0;
+
+// Merge another set of edits into the current ones. Everything that doesn't conflict is kept
+// automatically.
+pub fn make_merge_edits(app: &App, mode: GameplayMode) -> Box<dyn State> {
+    let current_edits_name = app.primary.map.get_edits().edits_name.clone();
+
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let mut wizard = wiz.wrap(ctx);
+        let (_, theirs) = wizard.choose("Merge which edits into yours?", || {
+            Choice::from(load_all_edits(app, &mode, &current_edits_name))
+        })?;
+        let (_, base) = wizard.choose("What did both edits start from?", || {
+            let mut list = vec![Choice::new("the original map", MapEdits::new())];
+            list.extend(Choice::from(
+                load_all_edits(app, &mode, &current_edits_name)
+                    .into_iter()
+                    .filter(|(_, edits)| edits.edits_name != theirs.edits_name)
+                    .collect(),
+            ));
+            list
+        })?;
+
+        let ours = app.primary.map.get_edits();
+        let merged = MergedEdits::new(&base, ours, &theirs);
+        let name = format!("{} merged with {}", ours.edits_name, theirs.edits_name);
+        if merged.conflicts.is_empty() {
+            let edits = merged.resolve(name, &Vec::new());
+            apply_map_edits(ctx, app, edits);
+            return Some(Transition::Pop);
+        }
+        Some(Transition::Replace(ResolveConflicts::new(
+            ctx,
+            app,
+            merged,
+            theirs.edits_name,
+            name,
+            Vec::new(),
+        )))
+    }))
+}
+
+struct ResolveConflicts {
+    composite: Composite,
+    merged: MergedEdits,
+    theirs_name: String,
+    result_name: String,
+    resolutions: Vec<Resolution>,
+    highlight: Drawable,
+}
+
+impl ResolveConflicts {
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        merged: MergedEdits,
+        theirs_name: String,
+        result_name: String,
+        resolutions: Vec<Resolution>,
+    ) -> Box<dyn State> {
+        let idx = resolutions.len();
+        let conflict = &merged.conflicts[idx];
+        let map = &app.primary.map;
+
+        let (name, center) = match conflict.obj {
+            EditedObject::Lane(l) => (
+                format!("Lane #{} of {}", l.0, map.get_parent(l).get_name()),
+                map.get_l(l).lane_center_pts.middle(),
+            ),
+            EditedObject::Road(r) => (map.get_r(r).get_name(), map.get_r(r).center_pts.middle()),
+            EditedObject::Intersection(i) => (
+                format!("Intersection #{}", i.0),
+                map.get_i(i).polygon.center(),
+            ),
+        };
+        ctx.canvas.center_on_map_pt(center);
+
+        let highlight = GeomBatch::from(vec![(app.cs.selected, shape(app, conflict.obj))]);
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("Resolve conflicting edits").small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            format!(
+                "Conflict {} of {}: {}",
+                idx + 1,
+                merged.conflicts.len(),
+                name
+            )
+            .draw_text(ctx),
+            Widget::row(vec![
+                side(
+                    ctx,
+                    app,
+                    conflict.obj,
+                    &conflict.ours,
+                    format!("Yours ({})", map.get_edits().edits_name),
+                    "keep yours",
+                    hotkey(Key::Num1),
+                )
+                .margin_right(16),
+                side(
+                    ctx,
+                    app,
+                    conflict.obj,
+                    &conflict.theirs,
+                    format!("Theirs ({})", theirs_name),
+                    "keep theirs",
+                    hotkey(Key::Num2),
+                ),
+            ]),
+            if idx == 0 {
+                Btn::text_fg("previous conflict").inactive(ctx)
+            } else {
+                Btn::text_fg("previous conflict").build_def(ctx, hotkey(Key::LeftArrow))
+            },
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+
+        Box::new(ResolveConflicts {
+            composite,
+            merged,
+            theirs_name,
+            result_name,
+            resolutions,
+            highlight: ctx.upload(highlight),
+        })
+    }
+}
+
+impl State for ResolveConflicts {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => {
+                let mut resolutions = self.resolutions.clone();
+                match x.as_ref() {
+                    "close" => {
+                        return Transition::Pop;
+                    }
+                    "previous conflict" => {
+                        resolutions.pop();
+                    }
+                    "keep yours" => {
+                        resolutions.push(Resolution::KeepOurs);
+                    }
+                    "keep theirs" => {
+                        resolutions.push(Resolution::KeepTheirs);
+                    }
+                    _ => unreachable!(),
+                }
+
+                if resolutions.len() == self.merged.conflicts.len() {
+                    let edits = self.merged.resolve(self.result_name.clone(), &resolutions);
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Pop;
+                }
+                // Rebuilding is the simplest way to reset the panel.
+                return Transition::Replace(ResolveConflicts::new(
+                    ctx,
+                    app,
+                    std::mem::replace(
+                        &mut self.merged,
+                        MergedEdits {
+                            clean: Vec::new(),
+                            conflicts: Vec::new(),
+                            annotations: Vec::new(),
+                        },
+                    ),
+                    self.theirs_name.clone(),
+                    self.result_name.clone(),
+                    resolutions,
+                ));
+            }
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.highlight);
+        self.composite.draw(g);
+    }
+}
+
+fn side(
+    ctx: &mut EventCtx,
+    app: &App,
+    obj: EditedObject,
+    cmds: &Vec<EditCmd>,
+    title: String,
+    action: &str,
+    key: Option<MultiKey>,
+) -> Widget {
+    let mut txt = Text::new();
+    if cmds.is_empty() {
+        txt.add(Line("no change from the original map").secondary());
+    }
+    for cmd in cmds {
+        txt.add(Line(cmd.short_name()));
+    }
+
+    // Shrink the map-space geometry to a fixed size
+    let batch = render(app, obj, cmds).autocrop();
+    let dims = batch.get_dims();
+    let batch = batch.scale(PREVIEW_SIZE / dims.width.max(dims.height).max(1.0));
+
+    Widget::col(vec![
+        Line(title).draw(ctx),
+        Widget::draw_batch(ctx, batch),
+        txt.draw(ctx),
+        Btn::text_bg2(action).build_def(ctx, key),
+    ])
+    .bg(app.cs.section_bg)
+    .padding(10)
+}
+
+pub fn shape(app: &App, obj: EditedObject) -> Polygon {
+    let map = &app.primary.map;
+    match obj {
+        EditedObject::Lane(l) => {
+            let lane = map.get_l(l);
+            lane.lane_center_pts.make_polygons(lane.width)
+        }
+        EditedObject::Road(r) => map.get_r(r).get_thick_polygon(map),
+        EditedObject::Intersection(i) => map.get_i(i).polygon.clone(),
+    }
+}
+
+// Draw what the object looks like after some commands, in map-space.
+fn render(app: &App, obj: EditedObject, cmds: &Vec<EditCmd>) -> GeomBatch {
+    let map = &app.primary.map;
+    let edits = map.get_edits();
+    let mut batch = GeomBatch::new();
+    match obj {
+        EditedObject::Lane(l) => {
+            let lane = map.get_l(l);
+            // For context, draw the rest of the road the way it is now.
+            for l2 in map.get_parent(l).all_lanes() {
+                if l2 != l {
+                    let lane2 = map.get_l(l2);
+                    batch.push(
+                        lane_color(app, lane2.lane_type).alpha(0.5),
+                        lane2.lane_center_pts.make_polygons(lane2.width),
+                    );
+                }
+            }
+
+            let mut lt = edits
+                .original_lts
+                .get(&l)
+                .cloned()
+                .unwrap_or(lane.lane_type);
+            let mut dst_i = if edits.reversed_lanes.contains(&l) {
+                lane.src_i
+            } else {
+                lane.dst_i
+            };
+            for cmd in cmds {
+                match cmd {
+                    EditCmd::ChangeLaneType { lt: new_lt, .. } => {
+                        lt = *new_lt;
+                    }
+                    EditCmd::ReverseLane { dst_i: new_dst, .. } => {
+                        dst_i = *new_dst;
+                    }
+                    _ => {}
+                }
+            }
+            batch.push(lane_color(app, lt), shape(app, obj));
+            let pl = if dst_i == lane.dst_i {
+                lane.lane_center_pts.clone()
+            } else {
+                lane.lane_center_pts.reversed()
+            };
+            batch.push(
+                Color::WHITE,
+                pl.make_arrow(Distance::meters(0.5), ArrowCap::Triangle),
+            );
+        }
+        EditedObject::Road(_) => {
+            batch.push(app.cs.driving_lane, shape(app, obj));
+        }
+        EditedObject::Intersection(i) => {
+            for r in &map.get_i(i).roads {
+                batch.push(
+                    app.cs.driving_lane.alpha(0.5),
+                    map.get_r(*r).get_thick_polygon(map),
+                );
+            }
+            let mut control = edits
+                .original_intersections
+                .get(&i)
+                .cloned()
+                .unwrap_or_else(|| map.get_i_edit(i));
+            for cmd in cmds {
+                if let EditCmd::ChangeIntersection { new, .. } = cmd {
+                    control = new.clone();
+                }
+            }
+            let color = match control {
+                EditIntersection::StopSign(_) => Color::RED,
+                EditIntersection::TrafficSignal(_) => Color::YELLOW,
+                EditIntersection::Closed => app.cs.parking_lane,
+            };
+            batch.push(color, shape(app, obj));
+        }
+    }
+    batch
+}
+
+fn lane_color(app: &App, lt: LaneType) -> Color {
+    match lt {
+        LaneType::Driving | LaneType::SharedLeftTurn => app.cs.driving_lane,
+        LaneType::Bus => app.cs.bus_lane,
+        LaneType::Parking | LaneType::Construction => app.cs.parking_lane,
+        LaneType::Sidewalk => app.cs.sidewalk,
+        LaneType::Biking => app.cs.bike_lane,
+        LaneType::LightRail => app.cs.light_rail_track,
+    }
+}
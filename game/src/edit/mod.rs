@@ -1,34 +1,45 @@
+mod alternatives;
+mod annotations;
 mod bulk;
 mod cluster_traffic_signals;
+mod compare_screenshots;
+mod filters;
 mod lanes;
+mod merge;
+mod recovery;
 mod select;
 mod stop_signs;
 mod traffic_signals;
 mod validate;
 mod zones;
 
+pub use self::annotations::draw_annotations;
 pub use self::cluster_traffic_signals::ClusterTrafficSignalEditor;
 pub use self::lanes::LaneEditor;
+pub use self::recovery::EditsRecovery;
 pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
 pub use self::validate::{
     check_parking_blackholes, check_sidewalk_connectivity, try_change_lt, try_reverse,
 };
-use crate::app::{App, ShowEverything};
-use crate::common::{tool_panel, CommonState, Warping};
+use crate::app::{App, Checkpoints, ShowEverything};
+use crate::common::{tool_panel, CommonState, SearchByName, Warping};
 use crate::debug::DebugMode;
-use crate::game::{State, Transition, WizardState};
+use crate::game::{msg, State, Transition, WizardState};
 use crate::helpers::ID;
 use crate::managed::{WrappedComposite, WrappedOutcome};
 use crate::render::{DrawIntersection, DrawMap, DrawRoad};
-use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
+use crate::sandbox::{ABTestMode, GameplayMode, SandboxMode, TimeWarpScreen};
 use abstutil::Timer;
 use ezgui::{
     hotkey, lctrl, Btn, Choice, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment,
     Key, Line, Outcome, PersistentSplit, RewriteColor, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::Speed;
-use map_model::{EditCmd, IntersectionID, LaneID, LaneType, MapEdits, PermanentMapEdits};
+use geom::{Duration, Speed, UnitFmt};
+use map_model::{
+    EditCmd, IntersectionID, LaneID, LaneType, MapEdits, Neighborhood, ParkingPolicy,
+    PermanentMapEdits,
+};
 use sim::DontDrawAgents;
 use std::collections::BTreeSet;
 
@@ -38,6 +49,8 @@ pub struct EditMode {
     changelist: Composite,
     orig_edits: MapEdits,
     orig_dirty: bool,
+    // Still valid if nothing changes
+    orig_checkpoints: Checkpoints,
 
     // Retained state from the SandboxMode that spawned us
     mode: GameplayMode,
@@ -53,15 +66,17 @@ impl EditMode {
     pub fn new(ctx: &mut EventCtx, app: &mut App, mode: GameplayMode) -> EditMode {
         let orig_dirty = app.primary.dirty_from_edits;
         assert!(app.suspended_sim.is_none());
+        let orig_checkpoints = std::mem::replace(&mut app.primary.checkpoints, Checkpoints::new());
         app.suspended_sim = Some(app.primary.clear_sim());
         let edits = app.primary.map.get_edits();
-        let layer = crate::layer::map::Static::edits(ctx, app);
+        let layer = crate::layer::map::Edits::new(ctx, app);
         EditMode {
             tool_panel: tool_panel(ctx),
             top_center: make_topcenter(ctx, app, &mode),
             changelist: make_changelist(ctx, app),
             orig_edits: edits.clone(),
             orig_dirty,
+            orig_checkpoints,
             mode,
             changelist_key: (edits.edits_name.clone(), edits.commands.len()),
             unzoomed: layer.unzoomed,
@@ -69,13 +84,45 @@ impl EditMode {
         }
     }
 
-    fn quit(&self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+    // Like "load edits", the player first gets a chance to save any unsaved edits.
+    pub fn load_edits(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        perma: PermanentMapEdits,
+    ) -> Result<Transition, String> {
+        if &perma.map_name != app.primary.map.get_name() {
+            return Err(format!("they're for {}", perma.map_name));
+        }
+        let edits = PermanentMapEdits::from_permanent(perma, &app.primary.map)?;
+        if !self.mode.allows(&edits) {
+            return Err("they can't be used in this gameplay mode".to_string());
+        }
+        if app.primary.map.unsaved_edits() {
+            return Ok(Transition::PushTwice(
+                Box::new(LoadEdits { edits: Some(edits) }),
+                SaveEdits::new(
+                    ctx,
+                    app,
+                    "Do you want to save your edits first?",
+                    true,
+                    Some(Transition::PopTwice),
+                ),
+            ));
+        }
+        finish_loading(ctx, app, edits);
+        Ok(Transition::Keep)
+    }
+
+    fn quit(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         let old_sim = app.suspended_sim.take().unwrap();
 
         // If nothing changed, short-circuit
         if app.primary.map.get_edits() == &self.orig_edits {
             app.primary.sim = old_sim;
             app.primary.dirty_from_edits = self.orig_dirty;
+            app.primary.checkpoints =
+                std::mem::replace(&mut self.orig_checkpoints, Checkpoints::new());
             // Could happen if we load some edits, then load whatever we entered edit mode with.
             ctx.loading_screen("apply edits", |_, mut timer| {
                 app.primary
@@ -120,7 +167,7 @@ impl State for EditMode {
             if self.changelist_key != changelist_key {
                 self.changelist_key = changelist_key;
                 self.changelist = make_changelist(ctx, app);
-                let layer = crate::layer::map::Static::edits(ctx, app);
+                let layer = crate::layer::map::Edits::new(ctx, app);
                 self.unzoomed = layer.unzoomed;
                 self.zoomed = layer.zoomed;
             }
@@ -162,6 +209,15 @@ impl State for EditMode {
                 "bulk edit" => {
                     return Transition::Push(bulk::BulkSelect::new(ctx, app));
                 }
+                "select lanes" => {
+                    return Transition::Push(bulk::LaneSelect::new(ctx, app));
+                }
+                "modal filters" => {
+                    return Transition::Push(choose_neighborhood_for_filters(app));
+                }
+                "search" => {
+                    return Transition::Push(SearchByName::new(ctx));
+                }
                 "finish editing" => {
                     return self.quit(ctx, app);
                 }
@@ -187,6 +243,42 @@ impl State for EditMode {
                         return Transition::Push(make_load_edits(app, self.mode.clone()));
                     }
                 }
+                "annotate proposal" => {
+                    return Transition::Push(annotations::AnnotationEditor::new(ctx, app));
+                }
+                "compare design alternatives" => {
+                    return Transition::Push(alternatives::PickAlternatives::new(
+                        ctx, app, &self.mode,
+                    ));
+                }
+                "compare screenshots" => {
+                    // The suspended sim only shows the effects of these edits if they haven't
+                    // changed since it ran.
+                    let after_run =
+                        if app.primary.map.get_edits() == &self.orig_edits && !self.orig_dirty {
+                            app.suspended_sim.as_ref().map(|s| s.get_analytics())
+                        } else {
+                            None
+                        };
+                    return Transition::Push(compare_screenshots::CompareScreenshots::new(
+                        ctx, app, after_run,
+                    ));
+                }
+                "merge with other edits" => {
+                    return Transition::Push(merge::make_merge_edits(app, self.mode.clone()));
+                }
+                "A/B test against the original map" => {
+                    return match ABTestMode::new(ctx, app, &self.mode) {
+                        Some(state) => Transition::Push(state),
+                        None => {
+                            app.notifications.toast(
+                                ctx,
+                                Text::from(Line("There's no scenario to run in this mode")),
+                            );
+                            Transition::Keep
+                        }
+                    };
+                }
                 "save edits as" | "save edits" => {
                     return Transition::Push(SaveEdits::new(
                         ctx,
@@ -198,7 +290,7 @@ impl State for EditMode {
                 }
                 "undo" => {
                     let mut edits = app.primary.map.get_edits().clone();
-                    let id = cmd_to_id(&edits.commands.pop().unwrap());
+                    let id = cmd_to_id(&edits.undo_last()[0]);
                     apply_map_edits(ctx, app, edits);
                     return Transition::Push(Warping::new(
                         ctx,
@@ -386,31 +478,34 @@ impl State for SaveEdits {
     }
 }
 
+// Waits underneath SaveEdits, then loads the edits once the player's saved or discarded the old
+// ones.
+struct LoadEdits {
+    edits: Option<MapEdits>,
+}
+
+impl State for LoadEdits {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        finish_loading(ctx, app, self.edits.take().unwrap());
+        Transition::Pop
+    }
+
+    fn draw(&self, _: &mut GfxCtx, _: &App) {}
+}
+
+fn finish_loading(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
+    let name = edits.edits_name.clone();
+    apply_map_edits(ctx, app, edits);
+    app.notifications
+        .toast(ctx, Text::from(Line(format!("Loaded edits {}", name))));
+}
+
 fn make_load_edits(app: &App, mode: GameplayMode) -> Box<dyn State> {
     let current_edits_name = app.primary.map.get_edits().edits_name.clone();
 
     WizardState::new(Box::new(move |wiz, ctx, app| {
         let (_, new_edits) = wiz.wrap(ctx).choose("Load which edits?", || {
-            let mut list = Choice::from(
-                abstutil::load_all_objects(abstutil::path_all_edits(app.primary.map.get_name()))
-                    .into_iter()
-                    .chain(abstutil::load_all_objects::<PermanentMapEdits>(
-                        abstutil::path("system/proposals"),
-                    ))
-                    .filter_map(|(path, perma)| {
-                        match PermanentMapEdits::from_permanent(perma, &app.primary.map) {
-                            Ok(edits) => Some((path, edits)),
-                            Err(err) => {
-                                println!("{} is corrupted: {}", path, err);
-                                None
-                            }
-                        }
-                    })
-                    .filter(|(_, edits)| {
-                        mode.allows(edits) && edits.edits_name != current_edits_name
-                    })
-                    .collect(),
-            );
+            let mut list = Choice::from(load_all_edits(app, &mode, &current_edits_name));
             list.push(Choice::new("start over with blank edits", MapEdits::new()));
             list
         })?;
@@ -419,6 +514,46 @@ fn make_load_edits(app: &App, mode: GameplayMode) -> Box<dyn State> {
     }))
 }
 
+fn choose_neighborhood_for_filters(app: &App) -> Box<dyn State> {
+    let map_name = app.primary.map.get_name().to_string();
+    let names = abstutil::list_all_objects(abstutil::path_all_neighborhoods(&map_name));
+    if names.is_empty() {
+        return msg(
+            "No neighborhoods",
+            vec!["Draw a neighborhood first, from the dev tools"],
+        );
+    }
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let name = wiz
+            .wrap(ctx)
+            .choose_string("Place modal filters in which neighborhood?", || {
+                names.clone()
+            })?;
+        let n = Neighborhood::load(&map_name, &name).unwrap();
+        Some(Transition::Replace(filters::FilterEditor::new(ctx, app, n)))
+    }))
+}
+
+// Everything saved for this map and all proposals, besides the edits named `except`.
+fn load_all_edits(app: &App, mode: &GameplayMode, except: &str) -> Vec<(String, MapEdits)> {
+    abstutil::load_all_objects(abstutil::path_all_edits(app.primary.map.get_name()))
+        .into_iter()
+        .chain(abstutil::load_all_objects::<PermanentMapEdits>(
+            abstutil::path("system/proposals"),
+        ))
+        .filter_map(|(path, perma)| {
+            match PermanentMapEdits::from_permanent(perma, &app.primary.map) {
+                Ok(edits) => Some((path, edits)),
+                Err(err) => {
+                    println!("{} is corrupted: {}", path, err);
+                    None
+                }
+            }
+        })
+        .filter(|(_, edits)| mode.allows(edits) && edits.edits_name != except)
+        .collect()
+}
+
 fn make_topcenter(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Composite {
     Composite::new(Widget::col(vec![
         Line("Editing map")
@@ -431,6 +566,17 @@ fn make_topcenter(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Composi
             } else {
                 Btn::text_fg("bulk edit").inactive(ctx)
             },
+            if mode.can_edit_lanes() {
+                Btn::text_fg("select lanes").build_def(ctx, hotkey(Key::M))
+            } else {
+                Btn::text_fg("select lanes").inactive(ctx)
+            },
+            if mode.can_edit_lanes() {
+                Btn::text_fg("modal filters").build_def(ctx, hotkey(Key::F))
+            } else {
+                Btn::text_fg("modal filters").inactive(ctx)
+            },
+            Btn::text_fg("search").build_def(ctx, hotkey(Key::K)),
             PersistentSplit::new(
                 ctx,
                 "finish editing",
@@ -465,6 +611,8 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
             .draw_map
             .draw_all_unzoomed_roads_and_intersections =
             DrawMap::regenerate_unzoomed_layer(&app.primary.map, &app.cs, ctx, &mut timer);
+        app.primary.draw_map.draw_all_road_centerlines =
+            DrawMap::regenerate_road_centerlines(&app.primary.map, &app.cs, ctx, &mut timer);
     }
 
     for r in roads_changed {
@@ -476,7 +624,13 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
         // of one-way markings, driving lines, etc.
         for l in road.all_lanes() {
             app.primary.draw_map.lanes[l.0].clear_rendering();
+            app.primary
+                .draw_map
+                .update_quadtree(ID::Lane(l), &app.primary.map);
         }
+        app.primary
+            .draw_map
+            .update_quadtree(ID::Road(r), &app.primary.map);
     }
 
     let mut lanes_of_modified_turns: BTreeSet<LaneID> = BTreeSet::new();
@@ -496,15 +650,27 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
             &app.cs,
             ctx.prerender,
         );
+        app.primary
+            .draw_map
+            .update_quadtree(ID::Intersection(i), &app.primary.map);
     }
 
+    app.primary
+        .draw_map
+        .rerender_bus_routes(ctx, &app.primary.map, &app.cs);
+
     if app.layer.as_ref().and_then(|l| l.name()) == Some("map edits") {
-        app.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
+        app.layer = Some(Box::new(crate::layer::map::Edits::new(ctx, app)));
     }
 
     // Autosave
     if app.primary.map.get_edits().edits_name != "untitled edits" {
         app.primary.map.save_edits();
+        let txt = Text::from(Line(format!(
+            "Saved {}",
+            app.primary.map.get_edits().edits_name
+        )));
+        app.notifications.toast(ctx, txt);
     }
 }
 
@@ -515,33 +681,94 @@ pub fn can_edit_lane(mode: &GameplayMode, l: LaneID, app: &App) -> bool {
         && !app.primary.map.get_l(l).is_light_rail()
 }
 
-pub fn change_speed_limit(ctx: &mut EventCtx, default: Speed) -> Widget {
+pub fn change_speed_limit(ctx: &mut EventCtx, units: &UnitFmt, default: Speed) -> Widget {
+    let mut speeds: Vec<Speed> = if units.metric {
+        (1..=11)
+            .map(|x| Speed::km_per_hour(10.0 * x as f64))
+            .collect()
+    } else {
+        // Don't need anything higher. Though now I kind of miss 3am drives on TX-71...
+        (2..=14)
+            .map(|x| Speed::miles_per_hour(5.0 * x as f64))
+            .collect()
+    };
+    // The current limit might've been imported in the other units
+    if !speeds.contains(&default) {
+        speeds.push(default);
+        speeds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    }
     Widget::row(vec![
         "Change speed limit:".draw_text(ctx).centered_vert(),
         Widget::dropdown(
             ctx,
             "speed limit",
             default,
-            vec![
-                Choice::new("10 mph", Speed::miles_per_hour(10.0)),
-                Choice::new("15 mph", Speed::miles_per_hour(15.0)),
-                Choice::new("20 mph", Speed::miles_per_hour(20.0)),
-                Choice::new("25 mph", Speed::miles_per_hour(25.0)),
-                Choice::new("30 mph", Speed::miles_per_hour(30.0)),
-                Choice::new("35 mph", Speed::miles_per_hour(35.0)),
-                Choice::new("40 mph", Speed::miles_per_hour(40.0)),
-                Choice::new("45 mph", Speed::miles_per_hour(45.0)),
-                Choice::new("50 mph", Speed::miles_per_hour(50.0)),
-                Choice::new("55 mph", Speed::miles_per_hour(55.0)),
-                Choice::new("60 mph", Speed::miles_per_hour(60.0)),
-                Choice::new("65 mph", Speed::miles_per_hour(65.0)),
-                Choice::new("70 mph", Speed::miles_per_hour(70.0)),
-                // Don't need anything higher. Though now I kind of miss 3am drives on TX-71...
-            ],
+            speeds
+                .into_iter()
+                .map(|x| Choice::new(x.describe(units), x))
+                .collect(),
         ),
     ])
 }
 
+pub fn change_parking_policy(ctx: &mut EventCtx, policy: ParkingPolicy) -> Widget {
+    let mut prices = vec![0, 50, 100, 200, 300, 500, 800];
+    if !prices.contains(&policy.hourly_price) {
+        prices.push(policy.hourly_price);
+        prices.sort();
+    }
+    let mut limits = vec![
+        None,
+        Some(Duration::minutes(30)),
+        Some(Duration::hours(1)),
+        Some(Duration::hours(2)),
+        Some(Duration::hours(4)),
+    ];
+    if !limits.contains(&policy.max_stay) {
+        limits.push(policy.max_stay);
+    }
+
+    Widget::col(vec![
+        Widget::row(vec![
+            "Parking price:".draw_text(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "parking price",
+                policy.hourly_price,
+                prices
+                    .into_iter()
+                    .map(|cents| {
+                        let label = if cents == 0 {
+                            "free".to_string()
+                        } else {
+                            format!("{}/hour", abstutil::prettyprint_cents(cents))
+                        };
+                        Choice::new(label, cents)
+                    })
+                    .collect(),
+            ),
+        ]),
+        Widget::row(vec![
+            "Time limit:".draw_text(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "parking time limit",
+                policy.max_stay,
+                limits
+                    .into_iter()
+                    .map(|dt| {
+                        let label = match dt {
+                            Some(dt) => dt.to_string(),
+                            None => "none".to_string(),
+                        };
+                        Choice::new(label, dt)
+                    })
+                    .collect(),
+            ),
+        ]),
+    ])
+}
+
 pub fn maybe_edit_intersection(
     ctx: &mut EventCtx,
     app: &mut App,
@@ -624,6 +851,11 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         } else {
             Btn::text_fg("Autosaved!").inactive(ctx)
         },
+        Btn::text_fg("merge with other edits").build_def(ctx, None),
+        Btn::text_fg("annotate proposal").build_def(ctx, None),
+        Btn::text_fg("compare screenshots").build_def(ctx, None),
+        Btn::text_fg("compare design alternatives").build_def(ctx, None),
+        Btn::text_fg("A/B test against the original map").build_def(ctx, None),
         Text::from_multiline(vec![
             Line(format!("{} lane types changed", edits.original_lts.len())),
             Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
@@ -631,10 +863,34 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
                 "{} speed limits changed",
                 edits.changed_speed_limits.len()
             )),
+            Line(format!(
+                "{} time-based speed limits changed",
+                edits.changed_speed_limit_schedules.len()
+            )),
+            Line(format!(
+                "{} roads with truck access changed",
+                edits.changed_truck_access.len()
+            )),
+            Line(format!(
+                "{} bus lane cameras changed",
+                edits.changed_bus_lane_cameras.len()
+            )),
             Line(format!(
                 "{} intersections changed",
                 edits.original_intersections.len()
             )),
+            Line(format!(
+                "{} pedestrian head starts changed",
+                edits.changed_leading_pedestrian_intervals.len()
+            )),
+            Line(format!(
+                "{} turn on red policies changed",
+                edits.changed_right_turn_on_red.len()
+            )),
+            Line(format!(
+                "{} signals with transit priority",
+                edits.changed_transit_signal_priority.len()
+            )),
         ])
         .draw(ctx),
     ];
@@ -657,12 +913,19 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         .build(ctx)
 }
 
-fn cmd_to_id(cmd: &EditCmd) -> ID {
+pub fn cmd_to_id(cmd: &EditCmd) -> ID {
     match cmd {
         EditCmd::ChangeLaneType { id, .. } => ID::Lane(*id),
         EditCmd::ReverseLane { l, .. } => ID::Lane(*l),
         EditCmd::ChangeSpeedLimit { id, .. } => ID::Road(*id),
+        EditCmd::ChangeSpeedLimitSchedule { id, .. } => ID::Road(*id),
         EditCmd::ChangeIntersection { i, .. } => ID::Intersection(*i),
         EditCmd::ChangeAccessRestrictions { id, .. } => ID::Road(*id),
+        EditCmd::ChangeParkingPolicy { id, .. } => ID::Lane(*id),
+        EditCmd::ChangeTruckAccess { id, .. } => ID::Road(*id),
+        EditCmd::ChangeBusLaneCamera { id, .. } => ID::Road(*id),
+        EditCmd::ChangeLeadingPedestrianInterval { i, .. } => ID::Intersection(*i),
+        EditCmd::ChangeRightTurnOnRed { i, .. } => ID::Intersection(*i),
+        EditCmd::ChangeTransitSignalPriority { i, .. } => ID::Intersection(*i),
     }
 }
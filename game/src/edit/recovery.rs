@@ -0,0 +1,206 @@
+use crate::app::App;
+use crate::game::Transition;
+use ezgui::{EventCtx, Line, Text};
+use instant::Instant;
+use map_model::PermanentMapEdits;
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Named edits are saved after every change, but untitled edits only live in memory. Every so
+// often (the autosave interval in the settings), write them somewhere, so a crash (even one that
+// takes down the whole process without panicking, like a GPU driver problem) doesn't lose them.
+// The file is removed once the edits are saved with a name or thrown away, or the game quits
+// cleanly.
+//
+// Each session writes its own file, so two copies of the game running at once don't clobber each
+// other. The file is named after the process, so a file whose process isn't running anymore was
+// left behind by a crash. Only the full game does this; the viewer and other tools never touch
+// these files.
+pub struct EditsRecovery {
+    path: String,
+    last_check: Instant,
+    written: bool,
+    // Files left behind by crashes that the player's already been asked about
+    offered: BTreeSet<String>,
+}
+
+impl EditsRecovery {
+    pub fn new(ctx: &mut EventCtx, app: &mut App) -> EditsRecovery {
+        let session = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|dt| dt.as_millis())
+            .unwrap_or(0);
+        let mut recovery = EditsRecovery {
+            path: abstutil::path_edits_recovery(&format!("{}_{}", process_id(), session)),
+            last_check: Instant::now(),
+            written: false,
+            offered: BTreeSet::new(),
+        };
+        recovery.offer_abandoned(ctx, app);
+        recovery
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        if abstutil::elapsed_seconds(self.last_check) < app.opts.autosave_interval.inner_seconds() {
+            return;
+        }
+        self.last_check = Instant::now();
+        // Another copy of the game might've crashed since this one started
+        self.offer_abandoned(ctx, app);
+
+        let map = &app.primary.map;
+        if map.unsaved_edits() {
+            abstutil::write_json(
+                self.path.clone(),
+                &PermanentMapEdits::to_permanent(map.get_edits(), map),
+            );
+            self.written = true;
+        } else if self.written {
+            self.written = false;
+            self.delete();
+        }
+    }
+
+    // After a clean exit, there's nothing to recover.
+    pub fn delete(&self) {
+        delete_file(&self.path);
+    }
+
+    // If other sessions didn't clean up after themselves, offer to keep their untitled edits.
+    // They're saved with a new name, so they can be loaded from the map editor like any other
+    // edits. If the player says no, they're deleted; if the question's ignored, it's asked again
+    // next time.
+    fn offer_abandoned(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        for path in abstutil::list_dir(std::path::Path::new(&abstutil::path_all_edits_recovery())) {
+            if path == self.path || self.offered.contains(&path) || !abandoned(&path) {
+                continue;
+            }
+            self.offered.insert(path.clone());
+
+            let perma = match abstutil::maybe_read_json::<PermanentMapEdits>(
+                path.clone(),
+                &mut abstutil::Timer::throwaway(),
+            ) {
+                Ok(perma) => perma,
+                Err(err) => {
+                    app.notifications.toast(
+                        ctx,
+                        Text::from(Line(format!("Ignoring corrupt {}: {}", path, err))),
+                    );
+                    delete_file(&path);
+                    continue;
+                }
+            };
+
+            let question = Text::from_multiline(vec![
+                Line("The game didn't quit cleanly before."),
+                Line(format!("Recover your unsaved edits to {}?", perma.map_name)),
+            ]);
+            // Answering either way drops the callback, and with it, this
+            let answer = Answer { path };
+            app.notifications.confirm(
+                ctx,
+                question,
+                Box::new(move |ctx, app| {
+                    let mut perma = perma;
+                    let mut name = "recovered edits".to_string();
+                    let mut n = 2;
+                    while abstutil::file_exists(abstutil::path_edits(&perma.map_name, &name)) {
+                        name = format!("recovered edits {}", n);
+                        n += 1;
+                    }
+                    perma.edits_name = name.clone();
+                    abstutil::write_json(abstutil::path_edits(&perma.map_name, &name), &perma);
+                    drop(answer);
+                    app.notifications.toast(
+                        ctx,
+                        Text::from(Line(format!(
+                            "Saved as \"{}\". Load them from the map editor.",
+                            name
+                        ))),
+                    );
+                    Transition::Keep
+                }),
+            );
+        }
+    }
+}
+
+// Deletes the recovery file once the player answers the question about it, either way.
+struct Answer {
+    path: String,
+}
+
+impl Drop for Answer {
+    fn drop(&mut self) {
+        // If the player said yes, the edits were just saved under a name
+        delete_file(&self.path);
+    }
+}
+
+// Files are named "<process ID>_<start time>", so the file is abandoned if that process is gone.
+fn abandoned(path: &str) -> bool {
+    match abstutil::basename(path)
+        .split('_')
+        .next()
+        .and_then(|pid| pid.parse::<u32>().ok())
+    {
+        Some(pid) => !process_running(pid),
+        // Nothing's named like this, but if it's there, nobody's going to clean it up
+        None => true,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn process_id() -> u32 {
+    std::process::id()
+}
+
+// No process IDs in the browser
+#[cfg(target_arch = "wasm32")]
+fn process_id() -> u32 {
+    0
+}
+
+#[cfg(target_os = "linux")]
+fn process_running(pid: u32) -> bool {
+    std::path::Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_running(pid: u32) -> bool {
+    // Signal 0 just checks if the process exists. If kill itself fails, assume it's alive.
+    std::process::Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+#[cfg(windows)]
+fn process_running(pid: u32) -> bool {
+    // tasklist says "No tasks are running" instead of failing when nothing matches
+    std::process::Command::new("tasklist")
+        .arg("/FI")
+        .arg(format!("PID eq {}", pid))
+        .arg("/NH")
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).contains(&pid.to_string()))
+        .unwrap_or(true)
+}
+
+// No other processes in the browser
+#[cfg(not(any(unix, windows)))]
+fn process_running(_: u32) -> bool {
+    true
+}
+
+fn delete_file(path: &str) {
+    if abstutil::file_exists(path.to_string()) {
+        if let Err(err) = std::fs::remove_file(path) {
+            println!("Couldn't delete {}: {}", path, err);
+        }
+    }
+}
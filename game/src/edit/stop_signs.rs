@@ -56,6 +56,18 @@ impl StopSignEditor {
             } else {
                 Btn::text_fg("reset to default").inactive(ctx)
             },
+            if app
+                .primary
+                .map
+                .get_stop_sign(id)
+                .roads
+                .values()
+                .all(|ss| ss.must_stop)
+            {
+                Btn::text_fg("make all-way stop").inactive(ctx)
+            } else {
+                Btn::text_fg("make all-way stop").build_def(ctx, hotkey(Key::A))
+            },
             Btn::text_fg("close intersection for construction").build_def(ctx, hotkey(Key::C)),
             Btn::text_fg("convert to traffic signal").build_def(ctx, None),
             Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
@@ -71,6 +83,22 @@ impl StopSignEditor {
             selected_sign: None,
         }
     }
+
+    fn change_sign(&self, ctx: &mut EventCtx, app: &mut App, sign: ControlStopSign) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeIntersection {
+            i: self.id,
+            old: app.primary.map.get_i_edit(self.id),
+            new: EditIntersection::StopSign(sign),
+        });
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(Box::new(StopSignEditor::new(
+            ctx,
+            app,
+            self.id,
+            self.mode.clone(),
+        )))
+    }
 }
 
 impl State for StopSignEditor {
@@ -98,20 +126,7 @@ impl State for StopSignEditor {
             };
             if app.per_obj.left_click(ctx, label) {
                 sign.flip_sign(r);
-
-                let mut edits = app.primary.map.get_edits().clone();
-                edits.commands.push(EditCmd::ChangeIntersection {
-                    i: self.id,
-                    old: app.primary.map.get_i_edit(self.id),
-                    new: EditIntersection::StopSign(sign),
-                });
-                apply_map_edits(ctx, app, edits);
-                return Transition::Replace(Box::new(StopSignEditor::new(
-                    ctx,
-                    app,
-                    self.id,
-                    self.mode.clone(),
-                )));
+                return self.change_sign(ctx, app, sign);
             }
         }
 
@@ -121,22 +136,15 @@ impl State for StopSignEditor {
                     return Transition::Pop;
                 }
                 "reset to default" => {
-                    let mut edits = app.primary.map.get_edits().clone();
-                    edits.commands.push(EditCmd::ChangeIntersection {
-                        i: self.id,
-                        old: app.primary.map.get_i_edit(self.id),
-                        new: EditIntersection::StopSign(ControlStopSign::new(
-                            &app.primary.map,
-                            self.id,
-                        )),
-                    });
-                    apply_map_edits(ctx, app, edits);
-                    return Transition::Replace(Box::new(StopSignEditor::new(
-                        ctx,
-                        app,
-                        self.id,
-                        self.mode.clone(),
-                    )));
+                    let sign = ControlStopSign::new(&app.primary.map, self.id);
+                    return self.change_sign(ctx, app, sign);
+                }
+                "make all-way stop" => {
+                    let mut sign = app.primary.map.get_stop_sign(self.id).clone();
+                    for ss in sign.roads.values_mut() {
+                        ss.must_stop = true;
+                    }
+                    return self.change_sign(ctx, app, sign);
                 }
                 "close intersection for construction" => {
                     let cmd = EditCmd::ChangeIntersection {
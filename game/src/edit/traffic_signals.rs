@@ -1,14 +1,15 @@
 use crate::app::{App, ShowEverything};
-use crate::common::CommonState;
+use crate::common::{ColorLegend, CommonState};
 use crate::edit::{apply_map_edits, check_sidewalk_connectivity, StopSignEditor};
 use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
 use crate::render::{
-    draw_signal_phase, make_signal_diagram, DrawOptions, DrawTurnGroup, BIG_ARROW_THICKNESS,
+    banned_by_edits, draw_signal_phase, make_signal_diagram, DrawOptions, DrawTurnGroup,
+    BIG_ARROW_THICKNESS,
 };
 use crate::sandbox::{spawn_agents_around, GameplayMode, SpeedControls, TimePanel};
 use abstutil::Timer;
 use ezgui::{
-    hotkey, lctrl, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    hotkey, lctrl, Btn, Checkbox, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, RewriteColor, Text, TextExt, UpdateType,
     VerticalAlignment, Widget,
 };
@@ -36,6 +37,9 @@ pub struct TrafficSignalEditor {
     pub redo_stack: Vec<ControlTrafficSignal>,
 
     fade_irrelevant: Drawable,
+    // Draws every phase at once, each in its own color. Turns can't be toggled meanwhile.
+    overview: Composite,
+    show_all_phases: bool,
 }
 
 impl TrafficSignalEditor {
@@ -69,6 +73,8 @@ impl TrafficSignalEditor {
             command_stack: Vec::new(),
             redo_stack: Vec::new(),
             fade_irrelevant: GeomBatch::from(vec![(app.cs.fade_map_dark, fade_area)]).upload(ctx),
+            overview: make_overview(ctx, app, id, false),
+            show_all_phases: false,
         }
     }
 
@@ -84,6 +90,8 @@ impl TrafficSignalEditor {
             self.composite
                 .scroll_to_member(ctx, format!("phase {}", idx + 1));
         }
+        // The number of phases might've changed
+        self.overview = make_overview(ctx, app, self.i, self.show_all_phases);
     }
 }
 
@@ -181,7 +189,14 @@ impl State for TrafficSignalEditor {
             None => {}
         }
 
-        if ctx.redo_mouseover() {
+        self.overview.event(ctx);
+        if self.overview.is_checked("show all phases at once") != self.show_all_phases {
+            self.show_all_phases = !self.show_all_phases;
+            self.overview = make_overview(ctx, app, self.i, self.show_all_phases);
+            self.group_selected = None;
+        }
+
+        if ctx.redo_mouseover() && !self.show_all_phases {
             self.group_selected = None;
             if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
                 for g in &self.groups {
@@ -300,6 +315,39 @@ impl State for TrafficSignalEditor {
         g.redraw(&self.fade_irrelevant);
 
         let signal = app.primary.map.get_traffic_signal(self.i);
+        if self.show_all_phases {
+            let mut batch = GeomBatch::new();
+            for (idx, phase) in signal.phases.iter().enumerate() {
+                let color = app.cs.rotating_color_agents(idx);
+                for id in &phase.protected_groups {
+                    batch.push(
+                        color.alpha(0.8),
+                        signal.turn_groups[id]
+                            .geom
+                            .make_arrow(BIG_ARROW_THICKNESS, ArrowCap::Triangle),
+                    );
+                }
+                for id in &phase.yield_groups {
+                    batch.extend(
+                        color.alpha(0.8),
+                        signal.turn_groups[id].geom.dashed_arrow(
+                            BIG_ARROW_THICKNESS / 2.0,
+                            Distance::meters(1.0),
+                            Distance::meters(0.5),
+                            ArrowCap::Triangle,
+                        ),
+                    );
+                }
+            }
+            batch.draw(g);
+
+            self.composite.draw(g);
+            self.top_panel.draw(g);
+            self.overview.draw(g);
+            CommonState::draw_osd(g, app);
+            return;
+        }
+
         let phase = match self.group_selected {
             Some((id, _)) => {
                 let mut p = signal.phases[self.current_phase].clone();
@@ -319,6 +367,12 @@ impl State for TrafficSignalEditor {
             app.opts.traffic_signal_style.clone(),
         );
 
+        // The signal as it was before this editor opened, if there aren't any saved edits yet
+        let banned = banned_by_edits(
+            &app.primary.map,
+            signal,
+            self.command_stack.first().unwrap_or(signal),
+        );
         for g in &self.groups {
             if self
                 .group_selected
@@ -397,18 +451,24 @@ impl State for TrafficSignalEditor {
                 batch.push(Color::WHITE, g.arrow.clone());
             } else {
                 batch.push(app.cs.signal_turn_block_bg, g.block.clone());
-                let arrow_color = match phase.get_priority_of_group(g.id) {
+                batch.push(g.kind.color(&app.cs), g.outline.clone());
+                let priority = phase.get_priority_of_group(g.id);
+                let arrow_color = match priority {
                     TurnPriority::Protected => app.cs.signal_protected_turn,
                     TurnPriority::Yield => app.cs.signal_permitted_turn,
                     TurnPriority::Banned => app.cs.signal_banned_turn,
                 };
                 batch.push(arrow_color, g.arrow.clone());
+                if banned.contains(&g.id) {
+                    batch.push(app.cs.turn_icon_banned_strike, g.strike.clone());
+                }
             }
         }
         batch.draw(g);
 
         self.composite.draw(g);
         self.top_panel.draw(g);
+        self.overview.draw(g);
         if let Some((id, _)) = self.group_selected {
             let osd = if id.crosswalk {
                 Text::from(Line(format!(
@@ -478,6 +538,30 @@ pub fn make_top_panel(ctx: &mut EventCtx, app: &App, can_undo: bool, can_redo: b
         .build(ctx)
 }
 
+// When all phases are shown, this is also the legend.
+fn make_overview(ctx: &mut EventCtx, app: &App, i: IntersectionID, show_all: bool) -> Composite {
+    let mut col = vec![Checkbox::text(
+        ctx,
+        "show all phases at once",
+        hotkey(Key::A),
+        show_all,
+    )];
+    if show_all {
+        let signal = app.primary.map.get_traffic_signal(i);
+        for idx in 0..signal.phases.len() {
+            col.push(ColorLegend::row(
+                ctx,
+                app.cs.rotating_color_agents(idx),
+                format!("Phase {}", idx + 1),
+            ));
+        }
+        col.push(Line("Dashed turns only yield").secondary().draw(ctx));
+    }
+    Composite::new(Widget::col(col))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Bottom)
+        .build(ctx)
+}
+
 fn edit_entire_signal(
     app: &App,
     i: IntersectionID,
@@ -490,6 +574,17 @@ fn edit_entire_signal(
         .get_turns_in_intersection(i)
         .any(|t| t.between_sidewalks());
     let current_offset = app.primary.map.get_traffic_signal(i).offset;
+    let current_lpi = app
+        .primary
+        .map
+        .get_traffic_signal(i)
+        .leading_pedestrian_interval;
+    let turn_on_red = app.primary.map.get_traffic_signal(i).right_turn_on_red;
+    let transit_priority = app
+        .primary
+        .map
+        .get_traffic_signal(i)
+        .transit_signal_priority;
 
     WizardState::new(Box::new(move |wiz, ctx, app| {
         let use_template = "use template";
@@ -497,6 +592,17 @@ fn edit_entire_signal(
         let stop_sign = "convert to stop signs";
         let close = "close intersection for construction";
         let offset = "edit signal offset";
+        let lpi = "edit pedestrian head start";
+        let toggle_turn_on_red = if turn_on_red {
+            "ban turns on red"
+        } else {
+            "allow turns on red"
+        };
+        let toggle_transit_priority = if transit_priority {
+            "remove transit signal priority"
+        } else {
+            "add transit signal priority"
+        };
         let reset = "reset to default";
 
         let mut choices = vec![use_template];
@@ -509,6 +615,11 @@ fn edit_entire_signal(
             choices.push(close);
         }
         choices.push(offset);
+        if has_sidewalks {
+            choices.push(lpi);
+        }
+        choices.push(toggle_turn_on_red);
+        choices.push(toggle_transit_priority);
         choices.push(reset);
 
         let mut wizard = wiz.wrap(ctx);
@@ -608,6 +719,46 @@ fn edit_entire_signal(
                     editor.change_phase(editor.current_phase, ctx, app);
                 })))
             }
+            x if x == lpi => {
+                let new_lpi = wizard.input_usize_prefilled(
+                    "How many seconds should crosswalks start before the turns yielding to them?",
+                    format!("{}", current_lpi.inner_seconds() as usize),
+                )?;
+                Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+                    let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
+                    let mut signal = app.primary.map.get_traffic_signal(editor.i).clone();
+                    editor.command_stack.push(signal.clone());
+                    editor.redo_stack.clear();
+                    editor.top_panel = make_top_panel(ctx, app, true, false);
+                    signal.leading_pedestrian_interval = Duration::seconds(new_lpi as f64);
+                    app.primary.map.incremental_edit_traffic_signal(signal);
+                    editor.change_phase(editor.current_phase, ctx, app);
+                })))
+            }
+            x if x == toggle_turn_on_red => {
+                Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+                    let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
+                    let mut signal = app.primary.map.get_traffic_signal(editor.i).clone();
+                    editor.command_stack.push(signal.clone());
+                    editor.redo_stack.clear();
+                    editor.top_panel = make_top_panel(ctx, app, true, false);
+                    signal.right_turn_on_red = !turn_on_red;
+                    app.primary.map.incremental_edit_traffic_signal(signal);
+                    editor.change_phase(editor.current_phase, ctx, app);
+                })))
+            }
+            x if x == toggle_transit_priority => {
+                Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+                    let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
+                    let mut signal = app.primary.map.get_traffic_signal(editor.i).clone();
+                    editor.command_stack.push(signal.clone());
+                    editor.redo_stack.clear();
+                    editor.top_panel = make_top_panel(ctx, app, true, false);
+                    signal.transit_signal_priority = !transit_priority;
+                    app.primary.map.incremental_edit_traffic_signal(signal);
+                    editor.change_phase(editor.current_phase, ctx, app);
+                })))
+            }
             x if x == reset => {
                 Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
                     let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
@@ -707,11 +858,39 @@ fn check_for_missing_groups(
                     .incremental_edit_traffic_signal(orig_signal.clone());
 
                 let mut edits = app.primary.map.get_edits().clone();
-                edits.commands.push(EditCmd::ChangeIntersection {
-                    i: new_signal.id,
-                    old: app.primary.map.get_i_edit(new_signal.id),
-                    new: EditIntersection::TrafficSignal(new_signal.export(&app.primary.map)),
-                });
+                let old = app.primary.map.get_i_edit(new_signal.id);
+                let new = EditIntersection::TrafficSignal(new_signal.export(&app.primary.map));
+                if old != new {
+                    edits.commands.push(EditCmd::ChangeIntersection {
+                        i: new_signal.id,
+                        old,
+                        new,
+                    });
+                }
+                if new_signal.leading_pedestrian_interval != orig_signal.leading_pedestrian_interval
+                {
+                    edits
+                        .commands
+                        .push(EditCmd::ChangeLeadingPedestrianInterval {
+                            i: new_signal.id,
+                            new: new_signal.leading_pedestrian_interval,
+                            old: orig_signal.leading_pedestrian_interval,
+                        });
+                }
+                if new_signal.right_turn_on_red != orig_signal.right_turn_on_red {
+                    edits.commands.push(EditCmd::ChangeRightTurnOnRed {
+                        i: new_signal.id,
+                        new: new_signal.right_turn_on_red,
+                        old: orig_signal.right_turn_on_red,
+                    });
+                }
+                if new_signal.transit_signal_priority != orig_signal.transit_signal_priority {
+                    edits.commands.push(EditCmd::ChangeTransitSignalPriority {
+                        i: new_signal.id,
+                        new: new_signal.transit_signal_priority,
+                        old: orig_signal.transit_signal_priority,
+                    });
+                }
                 apply_map_edits(ctx, app, edits);
             }
             Err(err) => {
@@ -1,11 +1,15 @@
 use crate::app::{App, Flags, ShowEverything};
+use crate::common::{open_file, Permalink};
+use crate::edit::EditsRecovery;
 use crate::options::Options;
 use crate::pregame::TitleScreen;
 use crate::render::DrawOptions;
 use crate::sandbox::{GameplayMode, SandboxMode};
+use crate::viewer::Viewer;
 use ezgui::{Canvas, Drawable, EventCtx, GfxCtx, Wizard, GUI};
 use geom::Polygon;
 use map_model::PermanentMapEdits;
+use std::sync::mpsc::Receiver;
 
 // This is the top-level of the GUI logic. This module should just manage interactions between the
 // top-level game states.
@@ -13,6 +17,10 @@ pub struct Game {
     // A stack of states
     states: Vec<Box<dyn State>>,
     app: App,
+    // Permalinks opened while the game is already running
+    permalinks: Option<Receiver<String>>,
+    // Only the full game keeps untitled edits safe from crashes
+    recovery: Option<EditsRecovery>,
 }
 
 impl Game {
@@ -21,6 +29,8 @@ impl Game {
         opts: Options,
         start_with_edits: Option<String>,
         maybe_mode: Option<GameplayMode>,
+        start_at: Option<Permalink>,
+        permalinks: Option<Receiver<String>>,
         ctx: &mut EventCtx,
     ) -> Game {
         let title = !opts.dev
@@ -57,7 +67,7 @@ impl Game {
             app.primary.clear_sim();
         }
 
-        let states: Vec<Box<dyn State>> = if title {
+        let mut states: Vec<Box<dyn State>> = if title {
             vec![Box::new(TitleScreen::new(ctx, &mut app))]
         } else {
             // TODO We're assuming we never wind up starting freeform mode with a synthetic map
@@ -71,15 +81,70 @@ impl Game {
             // PlayScenario without clobbering.
             app.primary.sim = ss;
         }
-        Game { states, app }
+        if let Some(link) = start_at {
+            match link.warp(ctx, &mut app) {
+                Ok(state) => states.push(state),
+                Err(err) => println!("Can't open permalink: {}", err),
+            }
+        }
+        let recovery = EditsRecovery::new(ctx, &mut app);
+        Game {
+            states,
+            app,
+            permalinks,
+            recovery: Some(recovery),
+        }
+    }
+
+    // The read-only viewer skips the title screen and sandbox entirely. Savestates keep their
+    // simulation, since that's the whole point of sharing one.
+    pub fn new_viewer(
+        flags: Flags,
+        opts: Options,
+        telemetry: Option<String>,
+        ctx: &mut EventCtx,
+    ) -> Game {
+        let mut app = App::new(flags, opts, ctx, false);
+        let states: Vec<Box<dyn State>> = vec![Box::new(Viewer::new(ctx, &mut app, telemetry))];
+        Game {
+            states,
+            app,
+            permalinks: None,
+            recovery: None,
+        }
     }
 }
 
 impl GUI for Game {
     fn event(&mut self, ctx: &mut EventCtx) {
         self.app.per_obj.reset();
+        if let Some(ref mut recovery) = self.recovery {
+            recovery.event(ctx, &mut self.app);
+        }
+
+        if let Some(uri) = self.permalinks.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            match Permalink::parse(uri.trim()).and_then(|link| link.warp(ctx, &mut self.app)) {
+                Ok(state) => {
+                    self.states.push(state);
+                    return;
+                }
+                Err(err) => println!("Can't open permalink: {}", err),
+            }
+        }
 
-        let transition = self.states.last_mut().unwrap().event(ctx, &mut self.app);
+        let transition = if let Some(path) = ctx.input.dropped_file() {
+            open_file(ctx, &mut self.app, self.states.last_mut().unwrap(), path)
+        } else {
+            match self.app.notifications.event(ctx) {
+                Some((cb, true)) => cb(ctx, &mut self.app),
+                Some((_, false)) => Transition::Keep,
+                None => self.states.last_mut().unwrap().event(ctx, &mut self.app),
+            }
+        };
+        self.app
+            .primary
+            .draw_map
+            .animate_water(ctx, self.app.opts.min_zoom_for_detail);
         // If we fall through, there's a new state that we need to wakeup.
         match transition {
             Transition::Keep => {
@@ -184,6 +249,7 @@ impl GUI for Game {
             }
         }
         state.draw(g, &self.app);
+        self.app.notifications.draw(g);
     }
 
     fn dump_before_abort(&self, canvas: &Canvas) {
@@ -234,6 +300,9 @@ impl GUI for Game {
 
     fn before_quit(&self, canvas: &Canvas) {
         canvas.save_camera_state(self.app.primary.map.get_name());
+        if let Some(ref recovery) = self.recovery {
+            recovery.delete();
+        }
     }
 }
 
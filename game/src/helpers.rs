@@ -1,7 +1,12 @@
 use crate::app::{App, PerMap};
-use ezgui::{hotkey, Btn, Checkbox, Color, EventCtx, Key, Line, Text, TextExt, TextSpan, Widget};
+use ezgui::{
+    hotkey, set_clipboard, Btn, Checkbox, Color, EventCtx, Key, Line, Text, TextExt, TextSpan,
+    Widget,
+};
 use geom::{Duration, Pt2D};
-use map_model::{AreaID, BuildingID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, RoadID};
+use map_model::{
+    AreaID, BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, RoadID,
+};
 use sim::{AgentID, AgentType, CarID, PedestrianID, TripMode, TripPhaseType};
 use std::collections::BTreeSet;
 
@@ -17,6 +22,7 @@ pub enum ID {
     Pedestrian(PedestrianID),
     PedCrowd(Vec<PedestrianID>),
     BusStop(BusStopID),
+    BusRoute(BusRouteID),
     Area(AreaID),
 }
 
@@ -60,6 +66,13 @@ impl ID {
                 .map
                 .maybe_get_bs(id)
                 .map(|bs| bs.sidewalk_pos.pt(&primary.map)),
+            ID::BusRoute(id) => primary.map.maybe_get_br(id).map(|br| {
+                primary
+                    .map
+                    .get_bs(br.stops[0])
+                    .sidewalk_pos
+                    .pt(&primary.map)
+            }),
             ID::Area(id) => primary.map.maybe_get_a(id).map(|a| a.polygon.center()),
         }
     }
@@ -153,54 +166,6 @@ pub fn color_for_trip_phase(app: &App, tpt: TripPhaseType) -> Color {
     }
 }
 
-pub fn amenity_type(a: &str) -> Option<&'static str> {
-    if a == "supermarket" || a == "convenience" {
-        Some("groceries")
-    } else if a == "restaurant"
-        || a == "cafe"
-        || a == "fast_food"
-        || a == "food_court"
-        || a == "ice_cream"
-        || a == "pastry"
-        || a == "deli"
-    {
-        Some("food")
-    } else if a == "pub" || a == "bar" || a == "nightclub" || a == "lounge" {
-        Some("bar")
-    } else if a == "doctors"
-        || a == "dentist"
-        || a == "clinic"
-        || a == "pharmacy"
-        || a == "chiropractor"
-    {
-        Some("medical")
-    } else if a == "place_of_worship" {
-        Some("church / temple")
-    } else if a == "college" || a == "school" || a == "kindergarten" || a == "university" {
-        Some("education")
-    } else if a == "bank" || a == "post_office" || a == "atm" {
-        Some("bank / post office")
-    } else if a == "theatre"
-        || a == "arts_centre"
-        || a == "library"
-        || a == "cinema"
-        || a == "art_gallery"
-    {
-        Some("media")
-    } else if a == "childcare" {
-        Some("childcare")
-    } else if a == "second_hand"
-        || a == "clothes"
-        || a == "furniture"
-        || a == "shoes"
-        || a == "department_store"
-    {
-        Some("shopping")
-    } else {
-        None
-    }
-}
-
 // TODO Well, there goes the nice consolidation of stuff in BtnBuilder. :\
 pub fn hotkey_btn<I: Into<String>>(ctx: &EventCtx, app: &App, label: I, key: Key) -> Widget {
     let label = label.into();
@@ -247,12 +212,16 @@ pub fn checkbox_per_mode(
     Widget::custom_row(filters)
 }
 
-pub fn copy_to_clipboard(x: String) {
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        use clipboard::{ClipboardContext, ClipboardProvider};
-
-        let mut cb: ClipboardContext = ClipboardProvider::new().unwrap();
-        cb.set_contents(x).unwrap();
+// Lets the player know what happened, since there's no other feedback
+pub fn copy_to_clipboard(ctx: &mut EventCtx, app: &mut App, x: String) {
+    // Long things like whole tables don't fit in a toast
+    let preview = if x.contains('\n') {
+        format!("{} lines", x.lines().count())
+    } else {
+        x.clone()
+    };
+    if set_clipboard(x) {
+        app.notifications
+            .toast(ctx, Text::from(Line(format!("Copied {}", preview))));
     }
 }
@@ -3,7 +3,7 @@ use crate::info::{header_btns, make_table, make_tabs, Details, Tab};
 use crate::render::DrawPedestrian;
 use ezgui::{Btn, Color, EventCtx, Line, Text, TextExt, Widget};
 use geom::{Angle, Circle, Distance, Speed, Time};
-use map_model::{BuildingID, LaneID, Traversable, SIDEWALK_THICKNESS};
+use map_model::{AmenityType, BuildingID, LaneID, Traversable, SIDEWALK_THICKNESS};
 use sim::{DrawPedestrianInput, PedestrianID, PersonID, TripMode, TripResult};
 use std::collections::BTreeMap;
 
@@ -51,7 +51,10 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID
             txt.add(Line(format!("{} amenities:", b.amenities.len())));
         }
         for (name, amenity) in &b.amenities {
-            txt.add(Line(format!("- {} ({})", name, amenity)));
+            txt.add(Line(match AmenityType::categorize(amenity) {
+                Some(category) => format!("- {} ({}, {})", name, amenity, category.describe()),
+                None => format!("- {} ({})", name, amenity),
+            }));
         }
     }
 
@@ -231,6 +234,7 @@ pub fn draw_occupants(details: &mut Details, app: &App, id: BuildingID, focus: O
                     // Both hands and feet!
                     waiting_for_bus: true,
                     on: Traversable::Lane(LaneID(0)),
+                    age: None,
                 },
                 0,
             );
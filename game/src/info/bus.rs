@@ -1,6 +1,6 @@
 use crate::app::App;
 use crate::helpers::ID;
-use crate::info::{header_btns, make_table, make_tabs, Details, Tab};
+use crate::info::{center_on, follow_btn, header_btns, make_table, make_tabs, Details, Tab};
 use ezgui::{
     Btn, Color, EventCtx, GeomBatch, Line, LinePlot, PlotOptions, RewriteColor, Series, Text,
     TextExt, Widget,
@@ -21,6 +21,12 @@ pub fn stop(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusStopID)
         header_btns(ctx),
     ]));
     rows.push(Line(&bs.name).draw(ctx));
+    if bs.has_shelter {
+        rows.push(Line("Has a shelter").secondary().draw(ctx));
+    }
+    if bs.is_terminal {
+        rows.push(Line("Terminal; buses lay over here").secondary().draw(ctx));
+    }
 
     let all_arrivals = &sim.get_analytics().bus_arrivals;
     for r in app.primary.map.get_routes_serving_stop(id) {
@@ -67,10 +73,57 @@ pub fn stop(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusStopID)
     rows
 }
 
+pub fn route(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteID) -> Vec<Widget> {
+    let map = &app.primary.map;
+    let route = map.get_br(id);
+    let mut rows = vec![];
+
+    rows.push(Widget::row(vec![
+        Line("Bus route").small_heading().draw(ctx),
+        header_btns(ctx),
+    ]));
+    rows.push(Line(&route.full_name).draw(ctx));
+
+    let buses = app.primary.sim.status_of_buses(id);
+    if buses.is_empty() {
+        rows.push("No buses running".draw_text(ctx));
+    } else {
+        rows.push(Btn::text_fg(format!("{} buses running", buses.len())).build_def(ctx, None));
+        details.hyperlinks.insert(
+            format!("{} buses running", buses.len()),
+            Tab::BusStatus(buses[0].0),
+        );
+    }
+
+    rows.push(Line(format!("{} stops", route.stops.len())).draw(ctx));
+    for (idx, bs) in route.stops.iter().enumerate() {
+        let label = format!("Stop {}: {}", idx + 1, map.get_bs(*bs).name);
+        rows.push(Btn::text_fg(&label).build_def(ctx, None));
+        details.hyperlinks.insert(label, Tab::BusStop(*bs));
+
+        details.unzoomed.push(
+            app.cs.bus_body,
+            Circle::new(map.get_bs(*bs).driving_pos.pt(map), Distance::meters(10.0)).to_polygon(),
+        );
+        details.zoomed.push(
+            app.cs.bus_body.alpha(0.5),
+            Circle::new(map.get_bs(*bs).driving_pos.pt(map), Distance::meters(2.5)).to_polygon(),
+        );
+    }
+
+    rows
+}
+
 // TODO For now, this conflates a single bus with the whole route, but that's fine, since the sim
 // only spawns one per route anyway.
-pub fn bus_status(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarID) -> Vec<Widget> {
-    let mut rows = bus_header(ctx, app, details, id, Tab::BusStatus(id));
+pub fn bus_status(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    id: CarID,
+    is_paused: bool,
+) -> Vec<Widget> {
+    let mut rows = bus_header(ctx, app, details, id, Tab::BusStatus(id), is_paused);
 
     let kv = app.primary.sim.bus_properties(id, &app.primary.map);
     rows.extend(make_table(ctx, kv.into_iter()));
@@ -81,8 +134,14 @@ pub fn bus_status(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarI
     rows
 }
 
-pub fn bus_delays(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarID) -> Vec<Widget> {
-    let mut rows = bus_header(ctx, app, details, id, Tab::BusDelays(id));
+pub fn bus_delays(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    id: CarID,
+    is_paused: bool,
+) -> Vec<Widget> {
+    let mut rows = bus_header(ctx, app, details, id, Tab::BusDelays(id), is_paused);
     let route = app.primary.sim.bus_route_id(id).unwrap();
     rows.push(delays_over_time(ctx, app, route));
     rows
@@ -94,6 +153,7 @@ fn bus_header(
     details: &mut Details,
     id: CarID,
     tab: Tab,
+    is_paused: bool,
 ) -> Vec<Widget> {
     let route = app.primary.sim.bus_route_id(id).unwrap();
 
@@ -102,7 +162,7 @@ fn bus_header(
         .sim
         .canonical_pt_for_agent(AgentID::Car(id), &app.primary.map)
     {
-        ctx.canvas.center_on_map_pt(pt);
+        center_on(ctx, pt, is_paused);
     }
 
     let mut rows = vec![];
@@ -114,7 +174,7 @@ fn bus_header(
         ))
         .small_heading()
         .draw(ctx),
-        header_btns(ctx),
+        Widget::row(vec![follow_btn(ctx, is_paused), header_btns(ctx)]).align_right(),
     ]));
     rows.push(make_tabs(
         ctx,
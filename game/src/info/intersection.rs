@@ -5,10 +5,12 @@ use abstutil::prettyprint_usize;
 use ezgui::{
     Btn, Color, EventCtx, GeomBatch, Line, PlotOptions, ScatterPlot, Series, Text, Widget,
 };
-use geom::{ArrowCap, Distance, Duration, PolyLine, Time};
-use map_model::{IntersectionID, IntersectionType};
-use sim::AgentType;
+use geom::{Angle, ArrowCap, Distance, Duration, PolyLine, Time};
+use map_model::{IntersectionID, IntersectionType, RoadID};
+use sim::{AgentType, TurnCounts, TurnMovement, TURN_COUNT_INTERVAL};
 use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
 
 pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: IntersectionID) -> Vec<Widget> {
     let mut rows = header(ctx, app, details, id, Tab::IntersectionInfo(id));
@@ -188,6 +190,208 @@ pub fn current_demand(
     rows
 }
 
+pub fn movements(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    id: IntersectionID,
+) -> Vec<Widget> {
+    let mut rows = header(ctx, app, details, id, Tab::IntersectionMovements(id));
+
+    let counts = match app.primary.sim.get_analytics().turn_counts.get(&id) {
+        Some(c) => c,
+        None => {
+            rows.push(
+                Text::from(
+                    Line("Turning movements aren't being counted here yet. Start now?").secondary(),
+                )
+                .wrap_to_pct(ctx, 20)
+                .draw(ctx),
+            );
+            rows.push(Btn::text_bg2("count turning movements").build_def(ctx, None));
+            return rows;
+        }
+    };
+
+    let mut txt = Text::from(Line(format!(
+        "Counting vehicles since {}",
+        counts.started.ampm_tostring()
+    )));
+    let mut per_interval: BTreeMap<usize, usize> = BTreeMap::new();
+    for ((interval, _, _), cnt) in &counts.counts {
+        *per_interval.entry(*interval).or_insert(0) += cnt;
+    }
+    if let Some((interval, cnt)) = per_interval.into_iter().max_by_key(|(_, cnt)| *cnt) {
+        let start = TurnCounts::interval_start(interval);
+        txt.add(
+            Line(format!(
+                "Busiest 15 minutes: {} - {}, {} vehicles",
+                start.ampm_tostring(),
+                (start + TURN_COUNT_INTERVAL).ampm_tostring(),
+                prettyprint_usize(cnt)
+            ))
+            .secondary(),
+        );
+    }
+    rows.push(txt.draw(ctx));
+
+    rows.push(
+        Widget::col(vec![
+            Line("Total turning movements").small_heading().draw(ctx),
+            Widget::draw_batch(ctx, movement_diagram(ctx, app, id, counts)),
+        ])
+        .padding(10)
+        .bg(app.cs.inner_panel)
+        .outline(2.0, Color::WHITE),
+    );
+    rows.push(Btn::text_bg2("export turning movements").build_def(ctx, None));
+
+    rows
+}
+
+// The usual turning movement diagram: each approach gets an arrow pointing into the intersection,
+// labeled with how many vehicles went left, through, and right from there.
+fn movement_diagram(
+    ctx: &EventCtx,
+    app: &App,
+    id: IntersectionID,
+    counts: &TurnCounts,
+) -> GeomBatch {
+    let map = &app.primary.map;
+    let totals = counts.totals();
+
+    let mut batch = GeomBatch::new();
+    batch.push(app.cs.normal_intersection, map.get_i(id).polygon.clone());
+    let mut txt_batch = GeomBatch::new();
+    for r in approaches(app, id) {
+        let pl = approach_pl(app, id, r);
+        let len = pl.length();
+        let arrow_len = Distance::meters(20.0).min(len);
+        if let Ok((arrow, _)) = pl.slice(len - arrow_len, len) {
+            batch.push(
+                Color::hex("#A3A3A3"),
+                arrow.make_arrow(Distance::meters(2.0), ArrowCap::Triangle),
+            );
+        }
+
+        let mut txt = Text::from(Line(format!(
+            "{} on {}",
+            approach_direction(pl.last_line().angle()),
+            map.get_r(r).get_name()
+        )));
+        for m in TurnMovement::all() {
+            let cnt = totals.get(&(r, m)).cloned().unwrap_or(0);
+            if m == TurnMovement::UTurn && cnt == 0 {
+                continue;
+            }
+            txt.add(
+                Line(format!("{}: {}", movement_name(m), prettyprint_usize(cnt))).fg(Color::RED),
+            );
+        }
+        let label_pt = pl
+            .dist_along(len - (arrow_len + Distance::meters(5.0)).min(len))
+            .map(|(pt, _)| pt)
+            .unwrap_or_else(|_| pl.first_pt());
+        txt_batch.append(
+            txt.with_bg()
+                .render_ctx(ctx)
+                .scale(0.1 / ctx.get_scale_factor())
+                .centered_on(label_pt),
+        );
+    }
+    batch.append(txt_batch);
+
+    let batch = batch.autocrop();
+    // Pick a zoom so that we fit a fixed width in pixels
+    let zoom = 300.0 / batch.get_dims().width;
+    batch.scale(zoom)
+}
+
+// Incoming roads with a vehicle lane, ordered around the intersection
+fn approaches(app: &App, id: IntersectionID) -> Vec<RoadID> {
+    let map = &app.primary.map;
+    let mut roads = BTreeSet::new();
+    for t in map.get_turns_in_intersection(id) {
+        if !t.between_sidewalks() {
+            roads.insert(map.get_l(t.id.src).parent);
+        }
+    }
+    let i = map.get_i(id);
+    i.roads
+        .iter()
+        .filter(|r| roads.contains(r))
+        .cloned()
+        .collect()
+}
+
+// The road's center line, ending at the intersection
+fn approach_pl(app: &App, id: IntersectionID, r: RoadID) -> PolyLine {
+    let road = app.primary.map.get_r(r);
+    if road.dst_i == id {
+        road.center_pts.clone()
+    } else {
+        road.center_pts.reversed()
+    }
+}
+
+// Which way vehicles on the approach are heading. Map coordinates have y pointing down.
+fn approach_direction(angle: Angle) -> &'static str {
+    let degrees = angle.normalized_degrees();
+    if degrees < 45.0 || degrees >= 315.0 {
+        "Eastbound"
+    } else if degrees < 135.0 {
+        "Southbound"
+    } else if degrees < 225.0 {
+        "Westbound"
+    } else {
+        "Northbound"
+    }
+}
+
+fn movement_name(m: TurnMovement) -> &'static str {
+    match m {
+        TurnMovement::Left => "Left",
+        TurnMovement::Through => "Through",
+        TurnMovement::Right => "Right",
+        TurnMovement::UTurn => "U-turn",
+    }
+}
+
+// One row per interval and approach, with a column per movement. Returns the path written.
+pub fn export_movements(app: &App, id: IntersectionID) -> Result<String, std::io::Error> {
+    let counts = &app.primary.sim.get_analytics().turn_counts[&id];
+    let path = format!(
+        "{}_turning_movements_{}.csv",
+        app.primary.map.get_name(),
+        id.0
+    );
+    let mut f = File::create(&path)?;
+    writeln!(
+        f,
+        "interval_start,interval_end,approach,road,left,through,right,u_turn"
+    )?;
+    let intervals: BTreeSet<usize> = counts.counts.keys().map(|(i, _, _)| *i).collect();
+    for interval in intervals {
+        let start = TurnCounts::interval_start(interval);
+        for r in approaches(app, id) {
+            let cnt = |m| counts.counts.get(&(interval, r, m)).cloned().unwrap_or(0);
+            writeln!(
+                f,
+                "{},{},{},\"{}\",{},{},{},{}",
+                start.ampm_tostring(),
+                (start + TURN_COUNT_INTERVAL).ampm_tostring(),
+                approach_direction(approach_pl(app, id, r).last_line().angle()),
+                app.primary.map.get_r(r).get_name(),
+                cnt(TurnMovement::Left),
+                cnt(TurnMovement::Through),
+                cnt(TurnMovement::Right),
+                cnt(TurnMovement::UTurn)
+            )?;
+        }
+    }
+    Ok(path)
+}
+
 pub fn arrivals(
     ctx: &mut EventCtx,
     app: &App,
@@ -295,6 +499,9 @@ fn header(
             tabs.push(("Delay", Tab::IntersectionDelay(id, DataOptions::new())));
             tabs.push(("Current demand", Tab::IntersectionDemand(id)));
         }
+        if !i.is_border() {
+            tabs.push(("Movements", Tab::IntersectionMovements(id)));
+        }
         if i.is_incoming_border() {
             tabs.push((
                 "Arrivals",
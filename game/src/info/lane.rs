@@ -30,11 +30,27 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
                 l.number_parking_spots()
             ),
         ));
+        kv.push(("Price", l.parking_policy.describe()));
     } else {
-        kv.push(("Speed limit", r.speed_limit.to_string()));
+        let now = r.speed_limit_at(app.primary.sim.time());
+        if now == r.speed_limit {
+            kv.push(("Speed limit", r.speed_limit.describe(&app.opts.units)));
+        } else {
+            kv.push((
+                "Speed limit",
+                format!(
+                    "{} right now, usually {}",
+                    now.describe(&app.opts.units),
+                    r.speed_limit.describe(&app.opts.units)
+                ),
+            ));
+        }
+        for p in &r.speed_limit_schedule {
+            kv.push(("Time-based limit", p.describe(&app.opts.units)));
+        }
     }
 
-    kv.push(("Length", l.length().describe_rounded()));
+    kv.push(("Length", l.length().describe_rounded(&app.opts.units)));
 
     rows.extend(make_table(ctx, kv.into_iter()));
 
@@ -111,8 +127,12 @@ pub fn debug(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Ve
         "Elevation change".to_string(),
         format!(
             "{} to {}",
-            map.get_i(l.src_i).elevation,
-            map.get_i(l.dst_i).elevation
+            map.get_i(l.src_i)
+                .elevation
+                .describe_rounded(&app.opts.units),
+            map.get_i(l.dst_i)
+                .elevation
+                .describe_rounded(&app.opts.units)
         ),
     ));
     kv.push((
@@ -123,8 +143,9 @@ pub fn debug(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Ve
         "Elevation details".to_string(),
         format!(
             "{} over {}",
-            map.get_i(l.dst_i).elevation - map.get_i(l.src_i).elevation,
-            l.length()
+            (map.get_i(l.dst_i).elevation - map.get_i(l.src_i).elevation)
+                .describe_rounded(&app.opts.units),
+            l.length().describe_rounded(&app.opts.units)
         ),
     ));
 
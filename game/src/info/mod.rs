@@ -9,7 +9,7 @@ mod trip;
 
 use crate::app::App;
 use crate::common::Warping;
-use crate::game::Transition;
+use crate::game::{msg, Transition};
 use crate::helpers::{color_for_agent_type, copy_to_clipboard, hotkey_btn, ID};
 use crate::sandbox::{SandboxMode, TimeWarpScreen};
 use ezgui::{
@@ -17,9 +17,9 @@ use ezgui::{
     HorizontalAlignment, Key, Line, LinePlot, Outcome, PlotOptions, Series, TextExt,
     VerticalAlignment, Widget,
 };
-use geom::{Circle, Distance, Time};
+use geom::{Circle, Distance, Pt2D, Time};
 use map_model::{
-    AreaID, BuildingID, BusStopID, IntersectionID, LaneID, OriginalLane, ParkingLotID,
+    AreaID, BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, OriginalLane, ParkingLotID,
 };
 use sim::{
     AgentID, AgentType, Analytics, CarID, ParkingSpot, PedestrianID, PersonID, PersonState, TripID,
@@ -56,6 +56,7 @@ pub enum Tab {
     BusStatus(CarID),
     BusDelays(CarID),
     BusStop(BusStopID),
+    BusRoute(BusRouteID),
 
     ParkedCar(CarID),
 
@@ -73,6 +74,7 @@ pub enum Tab {
     IntersectionDelay(IntersectionID, DataOptions),
     IntersectionDemand(IntersectionID),
     IntersectionArrivals(IntersectionID, DataOptions),
+    IntersectionMovements(IntersectionID),
 
     LaneInfo(LaneID),
     LaneDebug(LaneID),
@@ -113,6 +115,13 @@ impl Tab {
                         Tab::IntersectionInfo(i)
                     }
                 }
+                "movements" => {
+                    if app.primary.map.get_i(i).is_border() {
+                        Tab::IntersectionInfo(i)
+                    } else {
+                        Tab::IntersectionMovements(i)
+                    }
+                }
                 _ => unreachable!(),
             },
             ID::Building(b) => match app.session.info_panel_tab["bldg"] {
@@ -167,6 +176,7 @@ impl Tab {
             }
             ID::PedCrowd(members) => Tab::Crowd(members),
             ID::BusStop(bs) => Tab::BusStop(bs),
+            ID::BusRoute(r) => Tab::BusRoute(r),
             ID::Area(a) => Tab::Area(a),
         }
     }
@@ -187,6 +197,7 @@ impl Tab {
             }
             Tab::BusStatus(c) | Tab::BusDelays(c) => Some(ID::Car(*c)),
             Tab::BusStop(bs) => Some(ID::BusStop(*bs)),
+            Tab::BusRoute(r) => Some(ID::BusRoute(*r)),
             // TODO If a parked car becomes in use while the panel is open, should update the
             // panel better.
             Tab::ParkedCar(c) => match app.primary.sim.lookup_parked_car(*c)?.spot {
@@ -202,7 +213,8 @@ impl Tab {
             | Tab::IntersectionTraffic(i, _)
             | Tab::IntersectionDelay(i, _)
             | Tab::IntersectionDemand(i)
-            | Tab::IntersectionArrivals(i, _) => Some(ID::Intersection(*i)),
+            | Tab::IntersectionArrivals(i, _)
+            | Tab::IntersectionMovements(i) => Some(ID::Intersection(*i)),
             Tab::LaneInfo(l) | Tab::LaneDebug(l) | Tab::LaneTraffic(l, _) => Some(ID::Lane(*l)),
         }
     }
@@ -244,6 +256,7 @@ impl Tab {
             Tab::BusStatus(_) => ("bus", "status"),
             Tab::BusDelays(_) => ("bus", "delays"),
             Tab::BusStop(_) => ("bus stop", "info"),
+            Tab::BusRoute(_) => ("bus route", "info"),
             Tab::ParkedCar(_) => ("parked car", "info"),
             Tab::BldgInfo(_) => ("bldg", "info"),
             Tab::BldgPeople(_) => ("bldg", "people"),
@@ -255,6 +268,7 @@ impl Tab {
             Tab::IntersectionDelay(_, _) => ("intersection", "delay"),
             Tab::IntersectionDemand(_) => ("intersection", "demand"),
             Tab::IntersectionArrivals(_, _) => ("intersection", "arrivals"),
+            Tab::IntersectionMovements(_) => ("intersection", "movements"),
             Tab::LaneInfo(_) => ("lane", "info"),
             Tab::LaneDebug(_) => ("lane", "debug"),
             Tab::LaneTraffic(_, _) => ("lane", "traffic"),
@@ -302,9 +316,16 @@ impl InfoPanel {
                 person::schedule(ctx, app, &mut details, p, ctx_actions.is_paused()),
                 false,
             ),
-            Tab::BusStatus(c) => (bus::bus_status(ctx, app, &mut details, c), true),
-            Tab::BusDelays(c) => (bus::bus_delays(ctx, app, &mut details, c), true),
+            Tab::BusStatus(c) => (
+                bus::bus_status(ctx, app, &mut details, c, ctx_actions.is_paused()),
+                true,
+            ),
+            Tab::BusDelays(c) => (
+                bus::bus_delays(ctx, app, &mut details, c, ctx_actions.is_paused()),
+                true,
+            ),
             Tab::BusStop(bs) => (bus::stop(ctx, app, &mut details, bs), true),
+            Tab::BusRoute(r) => (bus::route(ctx, app, &mut details, r), true),
             Tab::ParkedCar(c) => (
                 person::parked_car(ctx, app, &mut details, c, ctx_actions.is_paused()),
                 true,
@@ -330,6 +351,9 @@ impl InfoPanel {
                 intersection::arrivals(ctx, app, &mut details, i, opts),
                 false,
             ),
+            Tab::IntersectionMovements(i) => {
+                (intersection::movements(ctx, app, &mut details, i), false)
+            }
             Tab::LaneInfo(l) => (lane::info(ctx, app, &mut details, l), true),
             Tab::LaneDebug(l) => (lane::debug(ctx, app, &mut details, l), false),
             Tab::LaneTraffic(l, ref opts) => {
@@ -528,18 +552,33 @@ impl InfoPanel {
                 } else if action == "copy OriginalLane" {
                     // TODO Not happy about this :(
                     if let Some(ID::Lane(l)) = maybe_id {
-                        copy_to_clipboard(format!(
-                            "{:?}",
-                            OriginalLane::to_permanent(l, &app.primary.map)
-                        ));
+                        let x = format!("{:?}", OriginalLane::to_permanent(l, &app.primary.map));
+                        copy_to_clipboard(ctx, app, x);
                     }
                     return (false, None);
                 } else if action == "copy OSM node ID" {
                     if let Some(ID::Intersection(i)) = maybe_id {
-                        copy_to_clipboard(format!(
-                            "{}",
-                            app.primary.map.get_i(i).orig_id.osm_node_id
-                        ));
+                        let x = app.primary.map.get_i(i).orig_id.osm_node_id.to_string();
+                        copy_to_clipboard(ctx, app, x);
+                    }
+                    return (false, None);
+                } else if action == "count turning movements" {
+                    if let Some(ID::Intersection(i)) = maybe_id {
+                        app.primary.sim.count_turning_movements(i);
+                        *self = InfoPanel::new(ctx, app, self.tab.clone(), ctx_actions);
+                    }
+                    return (false, None);
+                } else if action == "export turning movements" {
+                    if let Some(ID::Intersection(i)) = maybe_id {
+                        return (
+                            false,
+                            Some(Transition::Push(
+                                match intersection::export_movements(app, i) {
+                                    Ok(path) => msg("Exported", vec![format!("Saved to {}", path)]),
+                                    Err(err) => msg("Export failed", vec![err.to_string()]),
+                                },
+                            )),
+                        );
                     }
                     return (false, None);
                 } else {
@@ -652,6 +691,27 @@ fn make_tabs(
     Widget::custom_row(row).bg(Color::WHITE).margin_vert(16)
 }
 
+// Little indirect, but the handler of these actions is actually the ContextualActions for
+// SandboxMode. Labelled, so it isn't confused with the icon to jump to the object.
+fn follow_btn(ctx: &EventCtx, is_paused: bool) -> Widget {
+    if is_paused {
+        Btn::text_fg("follow").build(ctx, "follow (run the simulation)", hotkey(Key::F))
+    } else {
+        // TODO Blink
+        Btn::text_bg2("unfollow").build(ctx, "unfollow (pause the simulation)", hotkey(Key::F))
+    }
+}
+
+// While the sim runs, the panel is rebuilt every frame. Glide toward the agent then, instead of
+// jumping.
+fn center_on(ctx: &mut EventCtx, pt: Pt2D, is_paused: bool) {
+    if is_paused {
+        ctx.canvas.center_on_map_pt(pt);
+    } else {
+        ctx.canvas.glide_toward_map_pt(pt, 0.05);
+    }
+}
+
 fn header_btns(ctx: &EventCtx) -> Widget {
     Widget::row(vec![
         Btn::svg_def("system/assets/tools/location.svg").build(
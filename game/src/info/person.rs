@@ -1,5 +1,8 @@
 use crate::app::App;
-use crate::info::{building, header_btns, make_table, make_tabs, trip, Details, OpenTrip, Tab};
+use crate::info::{
+    building, center_on, follow_btn, header_btns, make_table, make_tabs, trip, Details, OpenTrip,
+    Tab,
+};
 use ezgui::{
     hotkey, Btn, Color, EventCtx, GeomBatch, Key, Line, RewriteColor, Text, TextExt, TextSpan,
     Widget,
@@ -441,20 +444,7 @@ pub fn parked_car(
             .small_heading()
             .draw(ctx),
         Widget::row(vec![
-            // Little indirect, but the handler of this action is actually the ContextualActions
-            // for SandboxMode.
-            if is_paused {
-                Btn::svg_def("system/assets/tools/location.svg").build(
-                    ctx,
-                    "follow (run the simulation)",
-                    hotkey(Key::F),
-                )
-            } else {
-                // TODO Blink
-                Btn::svg_def("system/assets/tools/location.svg")
-                    .normal_color(RewriteColor::ChangeAll(Color::hex("#7FFA4D")))
-                    .build(ctx, "unfollow (pause the simulation)", hotkey(Key::F))
-            },
+            follow_btn(ctx, is_paused),
             Btn::plaintext("X").build(ctx, "close info", hotkey(Key::Escape)),
         ])
         .align_right(),
@@ -506,8 +496,11 @@ fn header(
 
     let (current_trip, (descr, maybe_icon)) = match app.primary.sim.get_person(id).state {
         PersonState::Inside(b) => {
-            ctx.canvas
-                .center_on_map_pt(app.primary.map.get_b(b).label_center);
+            // If we were following them, stop once they're inside.
+            if is_paused {
+                ctx.canvas
+                    .center_on_map_pt(app.primary.map.get_b(b).label_center);
+            }
             building::draw_occupants(details, app, b, Some(id));
             (None, ("indoors", Some("system/assets/tools/home.svg")))
         }
@@ -515,7 +508,7 @@ fn header(
             Some(t),
             if let Some(a) = app.primary.sim.trip_to_agent(t).ok() {
                 if let Some(pt) = app.primary.sim.canonical_pt_for_agent(a, &app.primary.map) {
-                    ctx.canvas.center_on_map_pt(pt);
+                    center_on(ctx, pt, is_paused);
                 }
                 match a {
                     AgentID::Pedestrian(_) => {
@@ -524,6 +517,9 @@ fn header(
                     AgentID::Car(c) => match c.1 {
                         VehicleType::Car => ("driving", Some("system/assets/meters/car.svg")),
                         VehicleType::Bike => ("biking", Some("system/assets/meters/bike.svg")),
+                        VehicleType::Truck => {
+                            ("driving a truck", Some("system/assets/meters/car.svg"))
+                        }
                         VehicleType::Bus | VehicleType::Train => unreachable!(),
                     },
                     AgentID::BusPassenger(_, _) => {
@@ -552,20 +548,7 @@ fn header(
             .draw(ctx)
             .margin_horiz(10),
         Widget::row(vec![
-            // Little indirect, but the handler of this action is actually the ContextualActions
-            // for SandboxMode.
-            if is_paused {
-                Btn::svg_def("system/assets/tools/location.svg").build(
-                    ctx,
-                    "follow (run the simulation)",
-                    hotkey(Key::F),
-                )
-            } else {
-                // TODO Blink
-                Btn::svg_def("system/assets/tools/location.svg")
-                    .normal_color(RewriteColor::ChangeAll(Color::hex("#7FFA4D")))
-                    .build(ctx, "unfollow (pause the simulation)", hotkey(Key::F))
-            },
+            follow_btn(ctx, is_paused),
             Btn::plaintext("X").build(ctx, "close info", hotkey(Key::Escape)),
         ])
         .align_right(),
@@ -5,8 +5,8 @@ use ezgui::{
     Btn, Color, EventCtx, GeomBatch, Line, LinePlot, PlotOptions, RewriteColor, Series, Text,
     TextExt, Widget,
 };
-use geom::{ArrowCap, Distance, Duration, PolyLine, Polygon, Pt2D, Time};
-use map_model::{Map, Path, PathStep};
+use geom::{ArrowCap, Distance, Duration, PolyLine, Polygon, Pt2D, Speed, Time};
+use map_model::{Map, Path, PathStep, Traversable};
 use maplit::btreemap;
 use sim::{AgentID, PersonID, TripEndpoint, TripID, TripPhase, TripPhaseType};
 use std::collections::BTreeMap;
@@ -76,8 +76,12 @@ pub fn ongoing(
                 .force_width_pct(ctx, col_width),
             Widget::col(vec![
                 Text::from_all(vec![
-                    Line(props.dist_crossed.describe_rounded()),
-                    Line(format!("/{}", props.total_dist.describe_rounded())).secondary(),
+                    Line(props.dist_crossed.describe_rounded(&app.opts.units)),
+                    Line(format!(
+                        "/{}",
+                        props.total_dist.describe_rounded(&app.opts.units)
+                    ))
+                    .secondary(),
                 ])
                 .draw(ctx),
                 Text::from_all(vec![
@@ -113,6 +117,35 @@ pub fn ongoing(
         ]));
     }
 
+    if let Some(on) = props.on {
+        let map = &app.primary.map;
+        let stopped = props.speed == Speed::ZERO;
+        // The label already says when they're stopped
+        let mut txt = Text::new();
+        if !stopped {
+            txt.add(Line(format!("{} ", props.speed.describe(&app.opts.units))));
+        }
+        txt.append(
+            Line(match on {
+                Traversable::Lane(l) => format!("on {}", map.get_parent(l).get_name()),
+                Traversable::Turn(t) => format!("through {}", map.get_i(t.parent).name(map)),
+            })
+            .secondary(),
+        );
+        if let Some((ahead, total)) = props.queue_position {
+            txt.add(Line(format!("#{} in line", ahead + 1)));
+            txt.append(Line(format!(" of {} vehicles here", total)).secondary());
+        }
+        col.push(Widget::custom_row(vec![
+            Line(if stopped { "Stopped" } else { "Moving" })
+                .secondary()
+                .draw(ctx)
+                .container()
+                .force_width_pct(ctx, col_width),
+            txt.draw(ctx),
+        ]));
+    }
+
     col.push(make_timeline(
         ctx,
         app,
@@ -0,0 +1,150 @@
+use crate::app::App;
+use crate::game::{msg, Transition};
+use crate::helpers::copy_to_clipboard;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    LinePlot, Outcome, PlotOptions, Series, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Histogram, Time};
+use std::fs::File;
+use std::io::{Error, Write};
+
+// Live end-to-end travel times along a corridor the user picked. The sim does the measuring; this
+// just plots it.
+pub struct CorridorTravelTimes {
+    // Into the sim's list of corridors
+    idx: usize,
+    time: Time,
+    draw: Drawable,
+    composite: Composite,
+}
+
+impl Layer for CorridorTravelTimes {
+    fn name(&self) -> Option<&'static str> {
+        Some("corridor travel times")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = CorridorTravelTimes::new(ctx, app, self.idx);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                "export to CSV" => {
+                    let path = format!("{}_corridor{}.csv", app.primary.map.get_name(), self.idx);
+                    return Some(LayerOutcome::Transition(Transition::Push(
+                        match export(app, self.idx, &path) {
+                            Ok(()) => msg("Exported", vec![format!("Saved to {}", path)]),
+                            Err(err) => msg("Export failed", vec![err.to_string()]),
+                        },
+                    )));
+                }
+                "copy to clipboard" => {
+                    let summary = summarize(app, self.idx);
+                    copy_to_clipboard(ctx, app, summary);
+                    None
+                }
+                _ => unreachable!(),
+            },
+            None => None,
+        }
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl CorridorTravelTimes {
+    pub fn new(ctx: &mut EventCtx, app: &App, idx: usize) -> CorridorTravelTimes {
+        let corridor = &app.primary.sim.get_analytics().corridors[idx];
+        let map = &app.primary.map;
+
+        let mut batch = GeomBatch::new();
+        for r in &corridor.roads {
+            batch.push(
+                app.cs.after_changes.alpha(0.5),
+                map.get_r(*r).get_thick_polygon(map),
+            );
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Corridor travel times".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(
+                Line(format!(
+                    "{} roads, from {} to {}",
+                    corridor.roads.len(),
+                    map.get_r(corridor.roads[0]).get_name(),
+                    map.get_r(*corridor.roads.last().unwrap()).get_name()
+                ))
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+        ];
+        if corridor.travel_times.is_empty() {
+            col.push("No vehicles have made it through yet".draw_text(ctx));
+        } else {
+            col.push(summarize(app, idx).draw_text(ctx));
+            col.push(LinePlot::new(
+                ctx,
+                vec![Series {
+                    label: "travel time".to_string(),
+                    color: app.cs.after_changes,
+                    pts: corridor.travel_times.clone(),
+                }],
+                PlotOptions::fixed(),
+            ));
+            col.push(Widget::row(vec![
+                Btn::text_fg("copy to clipboard").build_def(ctx, None),
+                Btn::text_fg("export to CSV").build_def(ctx, None),
+            ]));
+        }
+
+        CorridorTravelTimes {
+            idx,
+            time: app.primary.sim.time(),
+            draw: ctx.upload(batch),
+            composite: Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .build(ctx),
+        }
+    }
+}
+
+fn summarize(app: &App, idx: usize) -> String {
+    let travel_times = &app.primary.sim.get_analytics().corridors[idx].travel_times;
+    let mut hgram = Histogram::new();
+    for (_, dt) in travel_times {
+        hgram.add(*dt);
+    }
+    format!("{} vehicles: {}", travel_times.len(), hgram.describe())
+}
+
+fn export(app: &App, idx: usize, path: &str) -> Result<(), Error> {
+    let mut f = File::create(path)?;
+    writeln!(f, "time_left_corridor,travel_time_seconds")?;
+    for (t, dt) in &app.primary.sim.get_analytics().corridors[idx].travel_times {
+        writeln!(f, "{},{}", t.inner_seconds(), dt.inner_seconds())?;
+    }
+    Ok(())
+}
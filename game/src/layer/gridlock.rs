@@ -0,0 +1,117 @@
+use crate::app::App;
+use crate::helpers::ID;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Time};
+use map_model::IntersectionID;
+
+// The agents responsible for gridlock at one intersection, in red. Keeps up with the sim until the
+// gridlock clears.
+pub struct GridlockAgents {
+    time: Time,
+    intersection: IntersectionID,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+impl Layer for GridlockAgents {
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = GridlockAgents::new(ctx, app, self.intersection);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+        self.composite.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl GridlockAgents {
+    pub fn new(ctx: &mut EventCtx, app: &App, intersection: IntersectionID) -> GridlockAgents {
+        let map = &app.primary.map;
+        let sim = &app.primary.sim;
+        let mut col = vec![Widget::row(vec![
+            Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+            "Gridlock".draw_text(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])];
+
+        let mut unzoomed = GeomBatch::new();
+        let mut zoomed = GeomBatch::new();
+        let outline = map.get_i(intersection).polygon.clone();
+        unzoomed.push(Color::RED.alpha(0.5), outline.clone());
+        zoomed.push(Color::RED.alpha(0.3), outline);
+
+        match sim
+            .current_gridlock()
+            .into_iter()
+            .find(|g| g.intersection == intersection)
+        {
+            Some(gridlock) => {
+                col.push(
+                    Text::from(Line(gridlock.describe()))
+                        .wrap_to_pct(ctx, 15)
+                        .draw(ctx),
+                );
+                let mut agents = app.primary.draw_map.agents.borrow_mut();
+                for a in gridlock.blocking {
+                    // They might've finished since the last check
+                    if let Some(pt) = sim.canonical_pt_for_agent(a, map) {
+                        unzoomed.push(
+                            Color::RED,
+                            Circle::new(pt, Distance::meters(10.0)).to_polygon(),
+                        );
+                    }
+                    if let Some(obj) = app.primary.draw_map.get_obj(
+                        ID::from_agent(a),
+                        app,
+                        &mut agents,
+                        ctx.prerender,
+                    ) {
+                        zoomed.push(Color::RED, obj.get_outline(map));
+                    }
+                }
+            }
+            None => {
+                col.push(
+                    Text::from(Line(format!("{} isn't gridlocked anymore", intersection)))
+                        .wrap_to_pct(ctx, 15)
+                        .draw(ctx),
+                );
+            }
+        }
+
+        GridlockAgents {
+            time: sim.time(),
+            intersection,
+            unzoomed: ctx.upload(unzoomed),
+            zoomed: ctx.upload(zoomed),
+            composite: Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .build(ctx),
+        }
+    }
+}
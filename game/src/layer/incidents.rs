@@ -0,0 +1,100 @@
+use crate::app::App;
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::prettyprint_usize;
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Time;
+
+// Roads closed by incidents right now, the message signs warning about them, and how many drivers
+// the signs have sent on a detour.
+pub struct Incidents {
+    time: Time,
+    draw: Drawable,
+    composite: Composite,
+}
+
+impl Layer for Incidents {
+    fn name(&self) -> Option<&'static str> {
+        Some("incidents")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Incidents::new(ctx, app);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => Some(LayerOutcome::Close),
+                _ => unreachable!(),
+            },
+            None => None,
+        }
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Incidents {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Incidents {
+        let map = &app.primary.map;
+        let now = app.primary.sim.time();
+        let incidents = app.primary.sim.get_incidents().unwrap();
+        let closed = incidents.closed_roads(now);
+
+        let mut batch = GeomBatch::new();
+        for r in &closed {
+            batch.push(Color::RED, map.get_r(*r).get_thick_polygon(map));
+        }
+        let sign_color = if closed.is_empty() {
+            Color::grey(0.5)
+        } else {
+            Color::YELLOW
+        };
+        for r in incidents.signs.keys() {
+            batch.push(sign_color.alpha(0.8), map.get_r(*r).get_thick_polygon(map));
+        }
+
+        let mut txt = Text::from(Line(format!("Using the {} plan", incidents.name)).secondary());
+        txt.add(Line(format!(
+            "{} roads closed, {} message signs {}",
+            closed.len(),
+            incidents.signs.len(),
+            if closed.is_empty() { "off" } else { "on" }
+        )));
+        txt.add(Line(format!(
+            "Signs advised drivers {} times, and {} took a detour",
+            prettyprint_usize(incidents.advised),
+            prettyprint_usize(incidents.detours.len())
+        )));
+
+        Incidents {
+            time: now,
+            draw: ctx.upload(batch),
+            composite: Composite::new(Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                    "Incidents".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                txt.draw(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx),
+        }
+    }
+}
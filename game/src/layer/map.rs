@@ -1,16 +1,133 @@
 use crate::app::App;
+use crate::common::Warping;
 use crate::common::{ColorDiscrete, ColorLegend, ColorNetwork};
-use crate::helpers::amenity_type;
+use crate::edit::{cmd_to_id, draw_annotations};
+use crate::game::Transition;
+use crate::helpers::ID;
 use crate::layer::{Layer, LayerOutcome};
-use abstutil::Counter;
+use crate::render::DrawNeighborhood;
+use abstutil::{Counter, Timer};
 use ezgui::{
-    hotkey, Btn, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
-    Text, TextExt, VerticalAlignment, Widget,
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, Time};
-use map_model::LaneType;
+use map_model::{osm, AmenityType, EditIntersection, EditedObject, LaneType, PermanentMapEdits};
 use sim::AgentType;
 
+pub struct TruckNetwork {
+    composite: Composite,
+    time: Time,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+}
+
+impl Layer for TruckNetwork {
+    fn name(&self) -> Option<&'static str> {
+        Some("truck network")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = TruckNetwork::new(ctx, app);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl TruckNetwork {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> TruckNetwork {
+        let map = &app.primary.map;
+        let violations = &app.primary.sim.get_analytics().truck_violations;
+        let mut violations_per_road = Counter::new();
+        for (_, _, r) in violations {
+            violations_per_road.inc(*r);
+        }
+
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("trucks allowed", Color::GREEN),
+                ("no trucks", Color::RED),
+                ("trucks drove here anyway", Color::PURPLE),
+            ],
+        );
+        let mut allowed_dist = Distance::ZERO;
+        let mut num_banned = 0;
+        for r in map.all_roads() {
+            if r.is_light_rail() || !r.all_lanes().iter().any(|l| map.get_l(*l).is_driving()) {
+                continue;
+            }
+            if violations_per_road.get(r.id) > 0 {
+                colorer.add_r(r.id, "trucks drove here anyway");
+            } else if r.allow_trucks {
+                colorer.add_r(r.id, "trucks allowed");
+            } else {
+                colorer.add_r(r.id, "no trucks");
+            }
+            if r.allow_trucks {
+                allowed_dist += r.center_pts.length();
+            } else {
+                num_banned += 1;
+            }
+        }
+        let (unzoomed, zoomed, legend) = colorer.build(ctx);
+
+        let mut txt = Text::from_multiline(vec![
+            Line(format!(
+                "{} of roads allow trucks",
+                allowed_dist.describe_rounded(&app.opts.units)
+            )),
+            Line(format!("{} roads don't", num_banned)),
+        ]);
+        if violations.is_empty() {
+            txt.add(Line("No trucks have broken the restrictions").secondary());
+        } else {
+            txt.add(Line(format!(
+                "{} times, trucks drove on {} restricted roads",
+                violations.len(),
+                violations_per_road.borrow().len()
+            )));
+        }
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Truck network".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            txt.draw(ctx),
+            legend,
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        TruckNetwork {
+            composite,
+            time: app.primary.sim.time(),
+            unzoomed,
+            zoomed,
+        }
+    }
+}
+
 pub struct BikeNetwork {
     composite: Composite,
     time: Time,
@@ -110,7 +227,7 @@ impl BikeNetwork {
                 Line(format!("{} lanes", num_lanes)),
                 Line(format!(
                     "total distance of {}",
-                    total_dist.describe_rounded()
+                    total_dist.describe_rounded(&app.opts.units)
                 )),
             ])
             .draw(ctx),
@@ -146,6 +263,273 @@ impl BikeNetwork {
     }
 }
 
+// Everything touched by the current edits, colored by the kind of change, with a list to jump
+// through them one by one.
+pub struct Edits {
+    composite: Composite,
+    pub unzoomed: Drawable,
+    pub zoomed: Drawable,
+    // The saved edits being compared against, if any
+    against: Option<String>,
+    // When comparing against other saved edits, what each listed difference is
+    differences: Vec<ID>,
+}
+
+impl Layer for Edits {
+    fn name(&self) -> Option<&'static str> {
+        Some("map edits")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                x => {
+                    let id = if x.starts_with("difference #") {
+                        let idx = x["difference #".len()..].parse::<usize>().unwrap() - 1;
+                        self.differences[idx].clone()
+                    } else {
+                        let idx = x["change #".len()..].parse::<usize>().unwrap() - 1;
+                        cmd_to_id(&app.primary.map.get_edits().commands[idx])
+                    };
+                    // There's no info panel for entire roads
+                    let id = match id {
+                        ID::Road(r) => ID::Lane(app.primary.map.get_r(r).all_lanes()[0]),
+                        id => id,
+                    };
+                    return Some(LayerOutcome::Transition(Transition::Push(Warping::new(
+                        ctx,
+                        id.canonical_point(&app.primary).unwrap(),
+                        Some(10.0),
+                        Some(id),
+                        &mut app.primary,
+                    ))));
+                }
+            },
+            None => {}
+        }
+
+        let against: Option<String> = self.composite.dropdown_value("diff against");
+        if against != self.against {
+            // Only read the file now, not every time the layer's rebuilt after an edit
+            app.session.edits_diff_against = against.and_then(|name| {
+                abstutil::maybe_read_json::<PermanentMapEdits>(
+                    abstutil::path_edits(app.primary.map.get_name(), &name),
+                    &mut Timer::throwaway(),
+                )
+                .ok()
+            });
+            *self = Edits::new(ctx, app);
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl Edits {
+    // Highlights everything changed from the original map, or if the player picked some saved
+    // edits to compare against, everything that differs from those.
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Edits {
+        let map = &app.primary.map;
+        let edits = map.get_edits();
+
+        let mut choices = vec![Choice::new("the original map", None)];
+        for name in abstutil::list_all_objects(abstutil::path_all_edits(map.get_name())) {
+            if name != edits.edits_name {
+                choices.push(Choice::new(name.clone(), Some(name)));
+            }
+        }
+        // The saved edits might be for another map, or they might be the ones loaded now
+        let other = app
+            .session
+            .edits_diff_against
+            .as_ref()
+            .filter(|perma| {
+                &perma.map_name == map.get_name() && perma.edits_name != edits.edits_name
+            })
+            .and_then(|perma| PermanentMapEdits::from_permanent(perma.clone(), map).ok());
+        let against = other.as_ref().map(|e| e.edits_name.clone());
+        // They could've been deleted since being picked
+        if let Some(ref name) = against {
+            if !choices.iter().any(|c| c.data.as_ref() == Some(name)) {
+                choices.push(Choice::new(name.clone(), Some(name.clone())));
+            }
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                format!("Map edits ({})", edits.edits_name).draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Widget::row(vec![
+                "Compare with:".draw_text(ctx).margin_right(10),
+                Widget::dropdown(ctx, "diff against", against.clone(), choices),
+            ]),
+            match against {
+                Some(ref name) => format!("Differences from {}", name),
+                None => "Changes from the original map".to_string(),
+            }
+            .draw_text(ctx),
+        ];
+
+        let (unzoomed, zoomed, differences) = if let Some(other) = other {
+            let mut colorer = ColorDiscrete::new(
+                app,
+                vec![
+                    ("only changed here", app.cs.edits_layer),
+                    ("only changed there", Color::PURPLE),
+                    ("changed differently", Color::YELLOW),
+                ],
+            );
+            let mut differences = Vec::new();
+            for diff in edits.diff(&other) {
+                let category = if diff.theirs.is_empty() {
+                    "only changed here"
+                } else if diff.ours.is_empty() {
+                    "only changed there"
+                } else {
+                    "changed differently"
+                };
+                let (id, label) = match diff.obj {
+                    EditedObject::Lane(l) => {
+                        colorer.add_l(l, category);
+                        (ID::Lane(l), l.to_string())
+                    }
+                    EditedObject::Road(r) => {
+                        colorer.add_r(r, category);
+                        (ID::Road(r), r.to_string())
+                    }
+                    EditedObject::Intersection(i) => {
+                        colorer.add_i(i, category);
+                        (ID::Intersection(i), i.to_string())
+                    }
+                };
+                let n = differences.len() + 1;
+                col.push(
+                    Btn::plaintext(format!("{}) {}: {}", n, label, category)).build(
+                        ctx,
+                        format!("difference #{}", n),
+                        None,
+                    ),
+                );
+                differences.push(id);
+            }
+            let (unzoomed, zoomed, legend) = colorer.build(ctx);
+            col.insert(3, legend);
+            if differences.is_empty() {
+                col.push(format!("No differences from {}", other.edits_name).draw_text(ctx));
+            }
+            (unzoomed, zoomed, differences)
+        } else {
+            let (unzoomed, zoomed, legend) = Edits::from_original_map(ctx, app);
+            col.push(legend);
+            if edits.commands.is_empty() {
+                col.push("No changes yet".draw_text(ctx));
+            }
+            for (idx, cmd) in edits.commands.iter().enumerate() {
+                col.push(
+                    Btn::plaintext(format!("{}) {}", idx + 1, cmd.short_name())).build(
+                        ctx,
+                        format!("change #{}", idx + 1),
+                        None,
+                    ),
+                );
+            }
+            (unzoomed, zoomed, Vec::new())
+        };
+
+        Edits {
+            composite: Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .max_size_percent(30, 70)
+                .build(ctx),
+            unzoomed,
+            zoomed,
+            against,
+            differences,
+        }
+    }
+
+    fn from_original_map(ctx: &mut EventCtx, app: &App) -> (Drawable, Drawable, Widget) {
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("lane type changed", app.cs.edits_layer),
+                ("lane reversed", Color::PURPLE),
+                ("speed limit changed", Color::CYAN),
+                ("access restricted", Color::ORANGE),
+                ("stop sign changed", Color::RED),
+                ("traffic signal changed", Color::YELLOW),
+                ("closed", Color::BLACK),
+            ],
+        );
+
+        let map = &app.primary.map;
+        let edits = map.get_edits();
+        for l in edits.original_lts.keys() {
+            if map.get_l(*l).lane_type == LaneType::Construction {
+                colorer.add_l(*l, "closed");
+            } else {
+                colorer.add_l(*l, "lane type changed");
+            }
+        }
+        for l in &edits.reversed_lanes {
+            colorer.add_l(*l, "lane reversed");
+        }
+        for r in &edits.changed_speed_limits {
+            colorer.add_r(*r, "speed limit changed");
+        }
+        for r in &edits.changed_speed_limit_schedules {
+            colorer.add_r(*r, "speed limit changed");
+        }
+        for r in &edits.changed_access_restrictions {
+            colorer.add_r(*r, "access restricted");
+        }
+        for r in &edits.changed_truck_access {
+            colorer.add_r(*r, "access restricted");
+        }
+        for r in &edits.changed_bus_lane_cameras {
+            colorer.add_r(*r, "access restricted");
+        }
+        for i in edits
+            .changed_leading_pedestrian_intervals
+            .iter()
+            .chain(edits.changed_right_turn_on_red.iter())
+            .chain(edits.changed_transit_signal_priority.iter())
+        {
+            colorer.add_i(*i, "traffic signal changed");
+        }
+        for i in edits.original_intersections.keys() {
+            match map.get_i_edit(*i) {
+                EditIntersection::StopSign(_) => colorer.add_i(*i, "stop sign changed"),
+                EditIntersection::TrafficSignal(_) => colorer.add_i(*i, "traffic signal changed"),
+                EditIntersection::Closed => colorer.add_i(*i, "closed"),
+            }
+        }
+        colorer.build(ctx)
+    }
+}
+
 pub struct Static {
     composite: Composite,
     pub unzoomed: Drawable,
@@ -209,44 +593,6 @@ impl Static {
         }
     }
 
-    pub fn edits(ctx: &mut EventCtx, app: &App) -> Static {
-        let mut colorer = ColorDiscrete::new(
-            app,
-            vec![("modified lane/intersection", app.cs.edits_layer)],
-        );
-
-        let edits = app.primary.map.get_edits();
-        for l in edits.original_lts.keys().chain(&edits.reversed_lanes) {
-            colorer.add_l(*l, "modified lane/intersection");
-        }
-        for i in edits.original_intersections.keys() {
-            colorer.add_i(*i, "modified lane/intersection");
-        }
-        for r in &edits.changed_speed_limits {
-            colorer.add_r(*r, "modified lane/intersection");
-        }
-
-        Static::new(
-            ctx,
-            colorer,
-            "map edits",
-            format!("Map edits ({})", edits.edits_name),
-            Text::from_multiline(vec![
-                Line(format!("{} lane types changed", edits.original_lts.len())),
-                Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
-                Line(format!(
-                    "{} speed limits changed",
-                    edits.changed_speed_limits.len()
-                )),
-                Line(format!(
-                    "{} intersections changed",
-                    edits.original_intersections.len()
-                )),
-            ])
-            .draw(ctx),
-        )
-    }
-
     pub fn amenities(ctx: &mut EventCtx, app: &App) -> Static {
         let mut colorer = ColorDiscrete::new(
             app,
@@ -268,8 +614,8 @@ impl Static {
         for b in app.primary.map.all_buildings() {
             let mut other = false;
             for (_, a) in &b.amenities {
-                if let Some(t) = amenity_type(a) {
-                    colorer.add_b(b.id, t);
+                if let Some(t) = AmenityType::categorize(a) {
+                    colorer.add_b(b.id, t.describe());
                 } else {
                     other = true;
                 }
@@ -287,4 +633,171 @@ impl Static {
             Widget::nothing(),
         )
     }
+
+    pub fn sidewalks(ctx: &mut EventCtx, app: &App) -> Static {
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("tagged in OSM", Color::GREEN),
+                ("inferred", Color::ORANGE),
+                ("no sidewalks", Color::RED),
+            ],
+        );
+
+        let map = &app.primary.map;
+        let mut inferred = 0;
+        for r in map.all_roads() {
+            let sidewalks: Vec<_> = r
+                .all_lanes()
+                .into_iter()
+                .filter(|l| map.get_l(*l).is_sidewalk())
+                .collect();
+            if sidewalks.is_empty() {
+                colorer.add_r(r.id, "no sidewalks");
+            } else if r.osm_tags.contains_key(osm::INFERRED_SIDEWALKS) {
+                inferred += 1;
+                for l in sidewalks {
+                    colorer.add_l(l, "inferred");
+                }
+            } else {
+                for l in sidewalks {
+                    colorer.add_l(l, "tagged in OSM");
+                }
+            }
+        }
+
+        Static::new(
+            ctx,
+            colorer,
+            "sidewalks",
+            "Sidewalks".to_string(),
+            Text::from(
+                Line(format!(
+                    "{} roads have sidewalks guessed because OSM doesn't say",
+                    abstutil::prettyprint_usize(inferred)
+                ))
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+        )
+    }
+}
+
+pub struct Neighborhoods {
+    composite: Composite,
+    draw: Drawable,
+}
+
+impl Layer for Neighborhoods {
+    fn name(&self) -> Option<&'static str> {
+        Some("neighborhoods")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        _: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Neighborhoods {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Neighborhoods {
+        let mut batch = GeomBatch::new();
+        let mut names = Vec::new();
+        for n in DrawNeighborhood::load_all(app) {
+            batch.append(n.render(ctx));
+            names.push(Line(n.neighborhood.name).fg(n.color));
+        }
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Neighborhoods".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            if names.is_empty() {
+                "No neighborhoods defined for this map".draw_text(ctx)
+            } else {
+                Text::from_multiline(names).draw(ctx)
+            },
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Neighborhoods {
+            composite,
+            draw: ctx.upload(batch),
+        }
+    }
+}
+
+// Markup saved with the current edits, for anybody looking at a proposal.
+pub struct Annotations {
+    composite: Composite,
+    draw: Drawable,
+}
+
+impl Layer for Annotations {
+    fn name(&self) -> Option<&'static str> {
+        Some("annotations")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        _: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Annotations {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Annotations {
+        let edits = app.primary.map.get_edits();
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Annotations".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            if edits.annotations.is_empty() {
+                "These edits aren't annotated".draw_text(ctx)
+            } else {
+                format!(
+                    "{} annotations on \"{}\"",
+                    edits.annotations.len(),
+                    edits.edits_name
+                )
+                .draw_text(ctx)
+            },
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Annotations {
+            composite,
+            draw: ctx.upload(draw_annotations(ctx, app, &edits.annotations)),
+        }
+    }
 }
@@ -0,0 +1,157 @@
+use crate::app::App;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Choice, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, LinePlot,
+    Outcome, PlotOptions, Series, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Time;
+use sim::{Analytics, MetricsSample};
+
+// Doesn't draw anything on the map, just plots how the whole simulation changes over time.
+pub struct SimMetrics {
+    time: Time,
+    metric: Metric,
+    composite: Composite,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    ActiveAgents,
+    FinishedTrips,
+    AverageDelay,
+}
+
+impl Layer for SimMetrics {
+    fn name(&self) -> Option<&'static str> {
+        Some("sim metrics")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = SimMetrics::new(ctx, app, self.metric);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let metric: Metric = self.composite.dropdown_value("metric");
+                if metric != self.metric {
+                    *self = SimMetrics::new(ctx, app, metric);
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+    }
+    fn draw_minimap(&self, _: &mut GfxCtx) {}
+}
+
+impl SimMetrics {
+    pub fn new(ctx: &mut EventCtx, app: &App, metric: Metric) -> SimMetrics {
+        let now = app.primary.sim.time();
+        let mut series = vec![(
+            format!("After \"{}\"", app.primary.map.get_edits().edits_name),
+            app.cs.after_changes,
+            app.primary.sim.get_analytics(),
+        )];
+        if app.has_prebaked().is_some() {
+            series.push((
+                format!("Before \"{}\"", app.primary.map.get_edits().edits_name),
+                app.cs.before_changes.alpha(0.5),
+                app.prebaked(),
+            ));
+        }
+
+        let plot = match metric {
+            Metric::ActiveAgents | Metric::FinishedTrips => LinePlot::new(
+                ctx,
+                series
+                    .into_iter()
+                    .map(|(label, color, analytics)| Series {
+                        label,
+                        color,
+                        pts: samples(analytics, now, |s| {
+                            if metric == Metric::ActiveAgents {
+                                s.active_agents
+                            } else {
+                                s.finished_trips
+                            }
+                        }),
+                    })
+                    .collect(),
+                PlotOptions::fixed(),
+            ),
+            Metric::AverageDelay => LinePlot::new(
+                ctx,
+                series
+                    .into_iter()
+                    .map(|(label, color, analytics)| Series {
+                        label,
+                        color,
+                        pts: samples(analytics, now, |s| s.avg_delay),
+                    })
+                    .collect(),
+                PlotOptions::fixed(),
+            ),
+        };
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Sim metrics".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Widget::dropdown(
+                ctx,
+                "metric",
+                metric,
+                vec![
+                    Choice::new("active agents", Metric::ActiveAgents),
+                    Choice::new("finished trips", Metric::FinishedTrips),
+                    Choice::new("average delay at intersections", Metric::AverageDelay),
+                ],
+            ),
+            Text::from(Line("Sampled every minute while the simulation runs").secondary())
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+            plot,
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        SimMetrics {
+            time: now,
+            metric,
+            composite,
+        }
+    }
+}
+
+// Prebaked results cover the whole day, so cut them off at the current time.
+fn samples<T, F: Fn(&MetricsSample) -> T>(
+    analytics: &Analytics,
+    now: Time,
+    f: F,
+) -> Vec<(Time, T)> {
+    analytics
+        .metrics
+        .iter()
+        .take_while(|s| s.time <= now)
+        .map(|s| (s.time, f(s)))
+        .collect()
+}
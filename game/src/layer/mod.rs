@@ -1,10 +1,19 @@
+pub mod corridor;
 mod elevation;
+pub mod gridlock;
+mod incidents;
 pub mod map;
+mod metrics;
 mod pandemic;
 mod parking;
+mod playback;
 mod population;
+mod safety;
+mod tolls;
 pub mod traffic;
 pub mod transit;
+mod trip_durations;
+pub mod trip_query;
 
 use crate::app::App;
 use crate::common::HeatmapOptions;
@@ -49,6 +58,7 @@ impl dyn Layer {
 // TODO Just return a bool for closed? Less readable...
 pub enum LayerOutcome {
     Close,
+    Transition(Transition),
 }
 
 // TODO Maybe overkill, but could embed a minimap and preview the layer on hover
@@ -69,6 +79,10 @@ impl PickLayer {
                 app.layer = None;
                 return None;
             }
+            Some(LayerOutcome::Transition(t)) => {
+                app.layer = Some(layer);
+                return Some(t);
+            }
             None => {}
         }
         app.layer = Some(layer);
@@ -105,14 +119,21 @@ impl PickLayer {
                     btn("delay", Key::D),
                     btn("throughput", Key::T),
                     btn("traffic jams", Key::J),
+                    btn("congestion", Key::C),
+                    btn("trip durations", Key::R),
+                    btn("safety", Key::F),
                 ]),
                 Widget::col(vec![
                     "Map".draw_text(ctx),
                     btn("map edits", Key::E),
                     btn("parking occupancy", Key::P),
                     btn("bike network", Key::B),
+                    btn("truck network", Key::K),
                     btn("transit network", Key::U),
+                    btn("sidewalks", Key::W),
                     btn("population map", Key::X),
+                    btn("neighborhoods", Key::H),
+                    btn("annotations", Key::O),
                 ]),
             ])
             .evenly_spaced(),
@@ -123,10 +144,18 @@ impl PickLayer {
             btn("amenities", Key::A),
             btn("backpressure", Key::Z),
             btn("elevation", Key::S),
+            btn("sim metrics", Key::M),
+            btn("recorded data", Key::G),
         ]);
         if app.primary.sim.get_pandemic_model().is_some() {
             col.push(btn("pandemic model", Key::Y));
         }
+        if app.primary.sim.get_tolls().is_some() {
+            col.push(btn("tolls", Key::L));
+        }
+        if app.primary.sim.get_incidents().is_some() {
+            col.push(btn("incidents", Key::I));
+        }
 
         Box::new(PickLayer {
             composite: Composite::new(Widget::col(col))
@@ -144,60 +173,9 @@ impl State for PickLayer {
                 "None" => {
                     app.layer = None;
                 }
-                "parking occupancy" => {
-                    app.layer = Some(Box::new(parking::Occupancy::new(
-                        ctx, app, true, true, true, false,
-                    )));
-                }
-                "delay" => {
-                    app.layer = Some(Box::new(traffic::Delay::new(ctx, app, false)));
-                }
-                "traffic jams" => {
-                    app.layer = Some(Box::new(traffic::TrafficJams::new(ctx, app)));
-                }
-                "throughput" => {
-                    app.layer = Some(Box::new(traffic::Throughput::new(ctx, app, false)));
-                }
-                "backpressure" => {
-                    app.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
-                }
-                "bike network" => {
-                    app.layer = Some(Box::new(map::BikeNetwork::new(ctx, app)));
-                }
-                "transit network" => {
-                    app.layer = Some(Box::new(transit::TransitNetwork::new(
-                        ctx, app, false, true, true,
-                    )));
-                }
-                "elevation" => {
-                    app.layer = Some(Box::new(elevation::Elevation::new(ctx, app)));
-                }
-                "map edits" => {
-                    app.layer = Some(Box::new(map::Static::edits(ctx, app)));
+                x => {
+                    app.layer = Some(layer_by_name(ctx, app, x).unwrap());
                 }
-                "amenities" => {
-                    app.layer = Some(Box::new(map::Static::amenities(ctx, app)));
-                }
-                "population map" => {
-                    app.layer = Some(Box::new(population::PopulationMap::new(
-                        ctx,
-                        app,
-                        population::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                        },
-                    )));
-                }
-                "pandemic model" => {
-                    app.layer = Some(Box::new(pandemic::Pandemic::new(
-                        ctx,
-                        app,
-                        pandemic::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                            state: pandemic::SEIR::Infected,
-                        },
-                    )));
-                }
-                _ => unreachable!(),
             },
             None => {
                 if self.composite.clicked_outside(ctx) {
@@ -218,3 +196,61 @@ impl State for PickLayer {
         self.composite.draw(g);
     }
 }
+
+// Everything in the layer picker besides "None". Also used to switch layers during tours.
+pub fn layer_by_name(ctx: &mut EventCtx, app: &mut App, name: &str) -> Option<Box<dyn Layer>> {
+    let layer: Box<dyn Layer> = match name {
+        "parking occupancy" => Box::new(parking::Occupancy::new(ctx, app, true, true, true, false)),
+        "delay" => Box::new(traffic::Delay::new(ctx, app, false)),
+        "traffic jams" => Box::new(traffic::TrafficJams::new(ctx, app)),
+        "congestion" => Box::new(traffic::Congestion::new(ctx, app, false)),
+        "throughput" => Box::new(traffic::Throughput::new(ctx, app, false)),
+        "trip durations" => Box::new(trip_durations::TripDurations::new(ctx, app, false)),
+        "safety" => Box::new(safety::Safety::new(
+            ctx,
+            app,
+            safety::Options {
+                conflicts: true,
+                hard_braking: true,
+                heatmap: Some(HeatmapOptions::new()),
+            },
+        )),
+        "backpressure" => Box::new(traffic::Backpressure::new(ctx, app)),
+        "bike network" => Box::new(map::BikeNetwork::new(ctx, app)),
+        "truck network" => Box::new(map::TruckNetwork::new(ctx, app)),
+        "transit network" => Box::new(transit::TransitNetwork::new(ctx, app, false, true, true)),
+        "elevation" => Box::new(elevation::Elevation::new(ctx, app)),
+        "sim metrics" => Box::new(metrics::SimMetrics::new(
+            ctx,
+            app,
+            metrics::Metric::ActiveAgents,
+        )),
+        "recorded data" => Box::new(playback::Playback::new(ctx, app, None)),
+        "map edits" => Box::new(map::Edits::new(ctx, app)),
+        "neighborhoods" => Box::new(map::Neighborhoods::new(ctx, app)),
+        "annotations" => Box::new(map::Annotations::new(ctx, app)),
+        "amenities" => Box::new(map::Static::amenities(ctx, app)),
+        "sidewalks" => Box::new(map::Static::sidewalks(ctx, app)),
+        "population map" => Box::new(population::PopulationMap::new(
+            ctx,
+            app,
+            population::Options {
+                heatmap: Some(HeatmapOptions::new()),
+            },
+        )),
+        "pandemic model" => Box::new(pandemic::Pandemic::new(
+            ctx,
+            app,
+            pandemic::Options {
+                heatmap: Some(HeatmapOptions::new()),
+                state: pandemic::SEIR::Infected,
+            },
+        )),
+        "tolls" => Box::new(tolls::Tolls::new(ctx, app)),
+        "incidents" => Box::new(incidents::Incidents::new(ctx, app)),
+        _ => {
+            return None;
+        }
+    };
+    Some(layer)
+}
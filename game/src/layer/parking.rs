@@ -1,7 +1,7 @@
 use crate::app::App;
 use crate::common::{ColorLegend, ColorNetwork};
 use crate::layer::{Layer, LayerOutcome};
-use abstutil::{prettyprint_usize, Counter};
+use abstutil::{prettyprint_cents, prettyprint_usize, Counter};
 use ezgui::{
     hotkey, Btn, Checkbox, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
     Outcome, Text, TextExt, VerticalAlignment, Widget,
@@ -155,6 +155,20 @@ impl Occupancy {
             }
         }
 
+        let mut revenue = 0;
+        let mut overstays = 0;
+        for (_, spot, dt, cents) in &app.primary.sim.get_analytics().parking_payments {
+            revenue += cents;
+            if spot
+                .policy(&app.primary.map)
+                .max_stay
+                .map(|max| *dt > max)
+                .unwrap_or(false)
+            {
+                overstays += 1;
+            }
+        }
+
         let composite = Composite::new(Widget::col(vec![
             Widget::row(vec![
                 Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
@@ -182,6 +196,14 @@ impl Occupancy {
                     prettyprint_usize(filled_private_spots),
                     prettyprint_usize(filled_private_spots + avail_private_spots)
                 )),
+                Line(format!(
+                    "{} collected from parking so far",
+                    prettyprint_cents(revenue)
+                )),
+                Line(format!(
+                    "{} cars stayed past the time limit",
+                    prettyprint_usize(overstays)
+                )),
             ])
             .draw(ctx),
             Widget::row(vec![
@@ -0,0 +1,329 @@
+use crate::app::App;
+use crate::common::{ColorLegend, FileBrowser};
+use crate::devtools::kml::load_shapes;
+use crate::game::{msg, Transition};
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::{prettyprint_usize, Timer};
+use ezgui::{
+    hotkey, Btn, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Duration, Line as GeomLine, PolyLine, Pt2D, Speed, Time};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+// How much of a trace to draw behind its current position
+const TRAIL: Duration = Duration::const_seconds(5.0 * 60.0);
+
+// Plays back recorded positions, like bus GPS traces or probe vehicles, at the current time in the
+// simulation. Comparing them with the simulated agents shows where the simulation is off.
+pub struct Playback {
+    time: Time,
+    recording: Option<Recording>,
+    // The traces with a position at the current time
+    visible: BTreeSet<String>,
+    draw: Drawable,
+    composite: Composite,
+}
+
+// Every point needs an id and a time attribute. Points with the same id form one trace. If any
+// point has a speed_mph attribute, traces are colored by it; otherwise the speed between points is
+// used.
+pub struct Recording {
+    path: String,
+    // Sorted by time
+    traces: BTreeMap<String, Vec<(Time, Pt2D, Option<Speed>)>>,
+    max_speed: Speed,
+}
+
+impl Layer for Playback {
+    fn name(&self) -> Option<&'static str> {
+        Some("recorded data")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        let now = app.primary.sim.time();
+        if now != self.time {
+            self.time = now;
+            if let Some(ref rec) = self.recording {
+                let visible = rec.visible(now);
+                // Nothing moves if nothing's there
+                if !visible.is_empty() || !self.visible.is_empty() {
+                    self.draw = ctx.upload(rec.render(app, now));
+                }
+                if visible != self.visible {
+                    self.visible = visible;
+                    self.composite = make_panel(ctx, app, Some(rec), self.visible.len());
+                }
+            }
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => Some(LayerOutcome::Close),
+                "load recorded data" => Some(LayerOutcome::Transition(Transition::Push(
+                    FileBrowser::open(
+                        ctx,
+                        abstutil::path(format!("input/{}/", app.primary.map.get_city_name())),
+                        vec!["csv", "kml", "geojson", "json", "bin"],
+                        Box::new(|ctx, app, path| {
+                            let result = ctx.loading_screen("load recorded data", |_, timer| {
+                                Recording::load(app, path, timer)
+                            });
+                            match result {
+                                Ok(recording) => {
+                                    app.layer =
+                                        Some(Box::new(Playback::new(ctx, app, Some(recording))));
+                                    Transition::Pop
+                                }
+                                Err(err) => {
+                                    Transition::Replace(msg("Can't load recorded data", vec![err]))
+                                }
+                            }
+                        }),
+                    ),
+                ))),
+                _ => unreachable!(),
+            },
+            None => None,
+        }
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Playback {
+    pub fn new(ctx: &mut EventCtx, app: &App, recording: Option<Recording>) -> Playback {
+        let now = app.primary.sim.time();
+        let (visible, batch) = match recording {
+            Some(ref rec) => (rec.visible(now), rec.render(app, now)),
+            None => (BTreeSet::new(), GeomBatch::new()),
+        };
+        let composite = make_panel(ctx, app, recording.as_ref(), visible.len());
+        Playback {
+            time: now,
+            recording,
+            visible,
+            draw: ctx.upload(batch),
+            composite,
+        }
+    }
+}
+
+fn make_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    recording: Option<&Recording>,
+    visible: usize,
+) -> Composite {
+    let mut col = vec![Widget::row(vec![
+        Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+        "Recorded data".draw_text(ctx),
+        Btn::plaintext("X")
+            .build(ctx, "close", hotkey(Key::Escape))
+            .align_right(),
+    ])];
+
+    if let Some(rec) = recording {
+        let mut txt = Text::from(Line(abstutil::basename(&rec.path)).secondary());
+        txt.add(Line(format!(
+            "{} of {} traces right now",
+            prettyprint_usize(visible),
+            prettyprint_usize(rec.traces.len()),
+        )));
+        txt.add(
+            Line("Uncheck agents in the minimap to hide the simulation and only see the data")
+                .secondary(),
+        );
+        col.push(txt.wrap_to_pct(ctx, 15).draw(ctx));
+        col.push(ColorLegend::gradient(
+            ctx,
+            &app.cs.good_to_bad_red,
+            vec![
+                rec.max_speed.describe(&app.opts.units),
+                "stopped".to_string(),
+            ],
+        ));
+    } else {
+        col.push(
+            Text::from(
+                Line(
+                    "Play back timestamped positions, like bus GPS traces, alongside the \
+                     simulation. Every point needs an id and a time attribute, and optionally \
+                     speed_mph. A CSV also needs longitude and latitude columns.",
+                )
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+        );
+    }
+    col.push(Btn::text_bg2("load recorded data").build_def(ctx, None));
+
+    Composite::new(Widget::col(col))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx)
+}
+
+impl Recording {
+    fn load(app: &App, path: String, timer: &mut Timer) -> Result<Recording, String> {
+        let shapes = load_shapes(&path, app, timer)?;
+        let gps_bounds = app.primary.map.get_gps_bounds();
+
+        let mut traces: BTreeMap<String, Vec<(Time, Pt2D, Option<Speed>)>> = BTreeMap::new();
+        let mut skipped = 0;
+        for shape in shapes.shapes {
+            let id = shape.attributes.get("id");
+            let time = shape
+                .attributes
+                .get("time")
+                .and_then(|t| Time::parse(t).ok());
+            match (id, time, shape.points.get(0)) {
+                (Some(id), Some(time), Some(gps)) if gps_bounds.contains(*gps) => {
+                    let speed = shape
+                        .attributes
+                        .get("speed_mph")
+                        .and_then(|x| x.parse::<f64>().ok())
+                        .map(Speed::miles_per_hour);
+                    traces.entry(id.clone()).or_insert_with(Vec::new).push((
+                        time,
+                        Pt2D::from_gps(*gps, gps_bounds),
+                        speed,
+                    ));
+                }
+                _ => {
+                    skipped += 1;
+                }
+            }
+        }
+        if traces.is_empty() {
+            return Err(format!(
+                "None of the {} points in {} have an id and time, and are on this map",
+                prettyprint_usize(skipped),
+                path
+            ));
+        }
+        if skipped > 0 {
+            timer.warn(format!(
+                "Skipped {} points without an id and time, or off the map",
+                prettyprint_usize(skipped)
+            ));
+        }
+
+        let mut max_speed = Speed::ZERO;
+        for trace in traces.values_mut() {
+            trace.sort_by_key(|(t, _, _)| *t);
+            for pair in trace.windows(2) {
+                let (t1, pt1, speed1) = pair[0];
+                let (t2, pt2, _) = pair[1];
+                if let Some(speed) = speed1 {
+                    max_speed = max_speed.max(speed);
+                } else if t2 > t1 {
+                    max_speed = max_speed.max(Speed::from_dist_time(pt1.dist_to(pt2), t2 - t1));
+                }
+            }
+            if let Some(speed) = trace.last().and_then(|(_, _, speed)| *speed) {
+                max_speed = max_speed.max(speed);
+            }
+        }
+        Ok(Recording {
+            path,
+            traces,
+            // Avoid dividing by zero if nothing moves
+            max_speed: max_speed.max(Speed::miles_per_hour(1.0)),
+        })
+    }
+
+    fn visible(&self, now: Time) -> BTreeSet<String> {
+        self.traces
+            .iter()
+            .filter(|(_, trace)| trace[0].0 <= now && now <= trace.last().unwrap().0)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    fn render(&self, app: &App, now: Time) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        let circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(10.0)).to_polygon();
+        // Times can't be negative
+        let trail_start = if now - Time::START_OF_DAY > TRAIL {
+            Some(now - TRAIL)
+        } else {
+            None
+        };
+        for trace in self.traces.values() {
+            let (pt, speed) = match position(trace, now) {
+                Some(x) => x,
+                None => continue,
+            };
+            let color = app
+                .cs
+                .good_to_bad_red
+                .eval(
+                    1.0 - speed.inner_meters_per_second()
+                        / self.max_speed.inner_meters_per_second(),
+                )
+                .alpha(0.9);
+
+            let first = trail_start.map(|t| after(trace, t)).unwrap_or(0);
+            let mut trail: Vec<Pt2D> = trace[first..after(trace, now)]
+                .iter()
+                .filter(|(t, _, _)| *t < now)
+                .map(|(_, pt, _)| *pt)
+                .collect();
+            trail.push(pt);
+            if let Ok(pl) = PolyLine::deduping_new(trail) {
+                batch.push(color.alpha(0.5), pl.make_polygons(Distance::meters(3.0)));
+            }
+            batch.push(color, circle.translate(pt.x(), pt.y()));
+        }
+        batch
+    }
+}
+
+// The index of the first sample after the time, or the length if there's none
+fn after(trace: &Vec<(Time, Pt2D, Option<Speed>)>, time: Time) -> usize {
+    match trace.binary_search_by(|(t, _, _)| {
+        if *t <= time {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }) {
+        Ok(idx) | Err(idx) => idx,
+    }
+}
+
+// Where a trace is at some time, and how fast it's going. None before it starts or after it ends.
+fn position(trace: &Vec<(Time, Pt2D, Option<Speed>)>, now: Time) -> Option<(Pt2D, Speed)> {
+    let (t1, pt1, speed1) = *trace.first()?;
+    if now < t1 || now > trace.last()?.0 {
+        return None;
+    }
+    if trace.len() == 1 {
+        return Some((pt1, speed1.unwrap_or(Speed::ZERO)));
+    }
+    // The pair of samples around now
+    let idx = after(trace, now).min(trace.len() - 1).max(1);
+    let (t1, pt1, speed1) = trace[idx - 1];
+    let (t2, pt2, _) = trace[idx];
+    if t1 == t2 {
+        return Some((pt2, speed1.unwrap_or(Speed::ZERO)));
+    }
+    let pct = (now - t1) / (t2 - t1);
+    let pt = GeomLine::new(pt1, pt2)
+        .and_then(|l| l.percent_along(pct))
+        .unwrap_or(pt1);
+    let speed = speed1.unwrap_or_else(|| Speed::from_dist_time(pt1.dist_to(pt2), t2 - t1));
+    Some((pt, speed))
+}
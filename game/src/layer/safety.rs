@@ -0,0 +1,226 @@
+use crate::app::App;
+use crate::common::{make_heatmap, HeatmapOptions};
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::prettyprint_usize;
+use ezgui::{
+    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Pt2D, Time};
+use sim::{Analytics, CONFLICT_THRESHOLD};
+
+// Surrogate safety measures: near-miss conflicts at intersections and hard braking. Edits can make
+// delay better and these worse, so it's worth looking at both.
+pub struct Safety {
+    time: Time,
+    opts: Options,
+    draw: Drawable,
+    composite: Composite,
+}
+
+impl Layer for Safety {
+    fn name(&self) -> Option<&'static str> {
+        Some("safety")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            let mut new = Safety::new(ctx, app, self.opts.clone());
+            new.composite.restore(ctx, &self.composite);
+            *self = new;
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let new_opts = self.options();
+                if self.opts != new_opts {
+                    *self = Safety::new(ctx, app, new_opts);
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.draw);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Safety {
+    pub fn new(ctx: &mut EventCtx, app: &App, opts: Options) -> Safety {
+        let now = app.primary.sim.time();
+        let (conflicts, hard_braking) = locations(app, app.primary.sim.get_analytics(), now);
+
+        let mut batch = GeomBatch::new();
+        let legend = if let Some(ref o) = opts.heatmap {
+            let mut pts = Vec::new();
+            if opts.conflicts {
+                pts.extend(conflicts.iter().cloned());
+            }
+            if opts.hard_braking {
+                pts.extend(hard_braking.iter().cloned());
+            }
+            Some(make_heatmap(
+                ctx,
+                &mut batch,
+                app.primary.map.get_bounds(),
+                pts,
+                o,
+            ))
+        } else {
+            let circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(10.0)).to_polygon();
+            if opts.conflicts {
+                for pt in &conflicts {
+                    batch.push(Color::RED.alpha(0.8), circle.translate(pt.x(), pt.y()));
+                }
+            }
+            if opts.hard_braking {
+                for pt in &hard_braking {
+                    batch.push(Color::ORANGE.alpha(0.8), circle.translate(pt.x(), pt.y()));
+                }
+            }
+            None
+        };
+
+        let baseline = if app.has_prebaked().is_some() {
+            let (c, h) = locations(app, app.prebaked(), now);
+            Some((c.len(), h.len()))
+        } else {
+            None
+        };
+        let composite = make_controls(
+            ctx,
+            &opts,
+            (conflicts.len(), hard_braking.len()),
+            baseline,
+            legend,
+        );
+        Safety {
+            time: now,
+            opts,
+            draw: ctx.upload(batch),
+            composite,
+        }
+    }
+
+    fn options(&self) -> Options {
+        let heatmap = if self.composite.is_checked("Show heatmap") {
+            Some(HeatmapOptions::from_controls(&self.composite))
+        } else {
+            None
+        };
+        Options {
+            conflicts: self.composite.is_checked("conflicts"),
+            hard_braking: self.composite.is_checked("hard braking"),
+            heatmap,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Options {
+    pub conflicts: bool,
+    pub hard_braking: bool,
+    // If None, just a dot map
+    pub heatmap: Option<HeatmapOptions>,
+}
+
+// Where conflicts and hard braking happened, up to some time
+fn locations(app: &App, analytics: &Analytics, now: Time) -> (Vec<Pt2D>, Vec<Pt2D>) {
+    let map = &app.primary.map;
+    let conflicts = analytics
+        .conflicts
+        .iter()
+        .take_while(|(t, _, _)| *t <= now)
+        .map(|(_, i, _)| map.get_i(*i).polygon.center())
+        .collect();
+    let hard_braking = analytics
+        .hard_braking
+        .iter()
+        .take_while(|(t, _, _)| *t <= now)
+        .filter_map(|(_, on, dist)| on.dist_along(*dist, map).ok().map(|(pt, _)| pt))
+        .collect();
+    (conflicts, hard_braking)
+}
+
+fn make_controls(
+    ctx: &mut EventCtx,
+    opts: &Options,
+    counts: (usize, usize),
+    baseline: Option<(usize, usize)>,
+    legend: Option<Widget>,
+) -> Composite {
+    let describe = |cnt: usize, before: Option<usize>| {
+        if let Some(before) = before {
+            format!(
+                "{} (baseline: {})",
+                prettyprint_usize(cnt),
+                prettyprint_usize(before)
+            )
+        } else {
+            prettyprint_usize(cnt)
+        }
+    };
+
+    let mut col = vec![
+        Widget::row(vec![
+            Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+            "Safety".draw_text(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ]),
+        Text::from(
+            Line(format!(
+                "Conflicts are turns started within {} of somebody clearing a conflicting turn",
+                CONFLICT_THRESHOLD
+            ))
+            .secondary(),
+        )
+        .wrap_to_pct(ctx, 15)
+        .draw(ctx),
+        Widget::row(vec![
+            Checkbox::colored(ctx, "conflicts", Color::RED, opts.conflicts),
+            describe(counts.0, baseline.map(|b| b.0))
+                .draw_text(ctx)
+                .align_right(),
+        ]),
+        Widget::row(vec![
+            Checkbox::colored(ctx, "hard braking", Color::ORANGE, opts.hard_braking),
+            describe(counts.1, baseline.map(|b| b.1))
+                .draw_text(ctx)
+                .align_right(),
+        ]),
+    ];
+
+    col.push(Checkbox::text(
+        ctx,
+        "Show heatmap",
+        None,
+        opts.heatmap.is_some(),
+    ));
+    if let Some(ref o) = opts.heatmap {
+        col.extend(o.to_controls(ctx, legend.unwrap()));
+    }
+
+    Composite::new(Widget::col(col))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx)
+}
@@ -0,0 +1,128 @@
+use crate::app::App;
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::{prettyprint_cents, prettyprint_usize};
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Time;
+use map_model::RoadID;
+use sim::{AgentType, Analytics};
+use std::collections::BTreeSet;
+
+// Where drivers are being charged right now, how much has been collected, and how car traffic in
+// each cordon compares to the baseline without tolls.
+pub struct Tolls {
+    time: Time,
+    draw: Drawable,
+    composite: Composite,
+}
+
+impl Layer for Tolls {
+    fn name(&self) -> Option<&'static str> {
+        Some("tolls")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Tolls::new(ctx, app);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => Some(LayerOutcome::Close),
+                _ => unreachable!(),
+            },
+            None => None,
+        }
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Tolls {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Tolls {
+        let map = &app.primary.map;
+        let now = app.primary.sim.time();
+        let tolls = app.primary.sim.get_tolls().unwrap();
+
+        let mut batch = GeomBatch::new();
+        for c in &tolls.cordons {
+            batch.push(Color::RED.alpha(0.2), c.polygon.clone());
+        }
+        for r in map.all_roads() {
+            if tolls.is_tolled(r.id, now) {
+                batch.push(Color::RED.alpha(0.8), r.get_thick_polygon(map));
+            }
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Tolls".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(Line(format!("Using the {} schedule", tolls.name)).secondary()).draw(ctx),
+            format!(
+                "{} collected from {} payments",
+                prettyprint_cents(tolls.total_revenue()),
+                prettyprint_usize(tolls.payments.len())
+            )
+            .draw_text(ctx),
+        ];
+        for c in &tolls.cordons {
+            let cars = car_traffic(app.primary.sim.get_analytics(), &c.roads, now);
+            let mut txt = Text::from(Line(&c.name));
+            txt.add(Line(format!("{} cars so far", prettyprint_usize(cars))).secondary());
+            if app.has_prebaked().is_some() {
+                let before = car_traffic(app.prebaked(), &c.roads, now);
+                txt.add(
+                    Line(if before == 0 {
+                        "No cars in the baseline".to_string()
+                    } else {
+                        format!(
+                            "{}% change from the baseline",
+                            ((cars as f64 / before as f64 - 1.0) * 100.0).round()
+                        )
+                    })
+                    .secondary(),
+                );
+            }
+            col.push(txt.draw(ctx));
+        }
+
+        Tolls {
+            time: now,
+            draw: ctx.upload(batch),
+            composite: Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .build(ctx),
+        }
+    }
+}
+
+// Cars crossing any of these roads, up through the current hour
+fn car_traffic(analytics: &Analytics, roads: &BTreeSet<RoadID>, now: Time) -> usize {
+    let hour = now.get_hours();
+    analytics
+        .road_thruput
+        .counts
+        .iter()
+        .filter(|((r, agent_type, h), _)| {
+            *agent_type == AgentType::Car && *h <= hour && roads.contains(r)
+        })
+        .map(|(_, cnt)| *cnt)
+        .sum()
+}
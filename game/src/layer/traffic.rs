@@ -3,7 +3,7 @@ use crate::common::{ColorLegend, ColorNetwork, ColorScale, DivergingScale};
 use crate::layer::{Layer, LayerOutcome};
 use abstutil::Counter;
 use ezgui::{
-    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    hotkey, Btn, Checkbox, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, Duration, Polygon, Time};
@@ -451,6 +451,119 @@ impl Delay {
     }
 }
 
+pub struct Congestion {
+    time: Time,
+    by_speed: bool,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+impl Layer for Congestion {
+    fn name(&self) -> Option<&'static str> {
+        Some("congestion")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Congestion::new(ctx, app, self.by_speed);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let by_speed: bool = self.composite.dropdown_value("measure");
+                if by_speed != self.by_speed {
+                    *self = Congestion::new(ctx, app, by_speed);
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl Congestion {
+    pub fn new(ctx: &mut EventCtx, app: &App, by_speed: bool) -> Congestion {
+        let map = &app.primary.map;
+        let mut colorer = ColorNetwork::new(app);
+        for (l, pct_full, speed) in app.primary.sim.lane_congestion() {
+            let pct = if by_speed {
+                // Slower relative to the speed limit is worse
+                let limit = map.get_parent(l).speed_limit;
+                1.0 - (speed.inner_meters_per_second() / limit.inner_meters_per_second())
+                    .max(0.0)
+                    .min(1.0)
+            } else {
+                pct_full
+            };
+            colorer.add_l(l, app.cs.good_to_bad_red.eval(pct));
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Congestion".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(Line("Only lanes with vehicles on them right now are shown").secondary())
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+            Widget::row(vec![
+                "Color lanes by:".draw_text(ctx),
+                Widget::dropdown(
+                    ctx,
+                    "measure",
+                    by_speed,
+                    vec![
+                        Choice::new("how full they are", false),
+                        Choice::new("how slow traffic is", true),
+                    ],
+                ),
+            ]),
+            if by_speed {
+                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["speed limit", "stopped"])
+            } else {
+                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["empty", "full"])
+            },
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Congestion {
+            time: app.primary.sim.time(),
+            by_speed,
+            unzoomed,
+            zoomed,
+            composite,
+        }
+    }
+}
+
 pub struct TrafficJams {
     time: Time,
     unzoomed: Drawable,
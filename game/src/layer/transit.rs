@@ -5,7 +5,7 @@ use ezgui::{
     hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::{Circle, Distance, Pt2D, Time};
+use geom::{Circle, Pt2D, Time};
 use map_model::{BusRouteID, PathConstraints, PathRequest, PathStep};
 
 pub struct TransitNetwork {
@@ -206,7 +206,7 @@ impl Layer for ShowTransitRoute {
         g.unfork();
 
         let mut batch = GeomBatch::new();
-        let radius = Distance::meters(20.0) / g.canvas.cam_zoom;
+        let radius = g.canvas.screen_to_map_dist(20.0);
         for pt in &self.bus_locations {
             batch.push(Color::BLUE, Circle::new(*pt, radius).to_polygon());
         }
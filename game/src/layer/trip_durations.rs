@@ -0,0 +1,229 @@
+use crate::app::App;
+use crate::common::ColorLegend;
+use crate::helpers::color_for_mode;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Checkbox, Color, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Distance, Duration, PolyLine, Pt2D, Time};
+use sim::{Analytics, TripMode};
+use std::collections::BTreeMap;
+
+const NUM_BUCKETS: usize = 20;
+const WIDTH: f64 = 400.0;
+const HEIGHT: f64 = 200.0;
+
+// The distribution of how long finished trips took, per mode. Unlike most layers, there's nothing
+// drawn on the map; it's just a chart that keeps up with the sim.
+pub struct TripDurations {
+    time: Time,
+    compare: bool,
+    composite: Composite,
+}
+
+impl Layer for TripDurations {
+    fn name(&self) -> Option<&'static str> {
+        Some("trip durations")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = TripDurations::new(ctx, app, self.compare);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let new_compare = self
+                    .composite
+                    .maybe_is_checked("Compare with baseline")
+                    .unwrap_or(false);
+                if new_compare != self.compare {
+                    *self = TripDurations::new(ctx, app, new_compare);
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+    }
+    fn draw_minimap(&self, _: &mut GfxCtx) {}
+}
+
+impl TripDurations {
+    pub fn new(ctx: &mut EventCtx, app: &App, compare: bool) -> TripDurations {
+        let now = app.primary.sim.time();
+        let current = durations_per_mode(app.primary.sim.get_analytics(), now);
+        // Only look at the baseline up to the current time, so the two are comparable
+        let baseline = if compare && app.has_prebaked().is_some() {
+            Some(durations_per_mode(app.prebaked(), now))
+        } else {
+            None
+        };
+
+        let max_duration = current
+            .values()
+            .chain(baseline.iter().flat_map(|b| b.values()))
+            .flatten()
+            .max()
+            .cloned()
+            .unwrap_or(Duration::ZERO);
+
+        let mut col = vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Trip durations".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(Line("How long finished trips took, by mode").secondary())
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+        ];
+        if app.has_prebaked().is_some() {
+            col.push(Checkbox::text(ctx, "Compare with baseline", None, compare));
+        }
+        if max_duration == Duration::ZERO {
+            col.push("No trips have finished yet".draw_text(ctx));
+        } else {
+            col.push(make_chart(
+                ctx,
+                app,
+                &current,
+                baseline.as_ref(),
+                max_duration,
+            ));
+            let mut legend = Vec::new();
+            for mode in TripMode::all() {
+                let cnt = current.get(&mode).map(|x| x.len()).unwrap_or(0);
+                legend.push(ColorLegend::row(
+                    ctx,
+                    color_for_mode(app, mode),
+                    format!("{} ({} trips)", mode.ongoing_verb(), cnt),
+                ));
+            }
+            col.push(Widget::col(legend));
+            if baseline.is_some() {
+                col.push(Text::from(Line("Dashed lines are the baseline").secondary()).draw(ctx));
+            }
+        }
+
+        TripDurations {
+            time: now,
+            compare,
+            composite: Composite::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .build(ctx),
+        }
+    }
+}
+
+fn durations_per_mode(analytics: &Analytics, now: Time) -> BTreeMap<TripMode, Vec<Duration>> {
+    let mut per_mode = BTreeMap::new();
+    for (t, _, maybe_mode, dt) in &analytics.finished_trips {
+        if *t > now {
+            break;
+        }
+        // Aborted trips don't have a mode
+        if let Some(mode) = maybe_mode {
+            per_mode.entry(*mode).or_insert_with(Vec::new).push(*dt);
+        }
+    }
+    per_mode
+}
+
+fn buckets(durations: &Vec<Duration>, max_duration: Duration) -> Vec<usize> {
+    let mut counts = vec![0; NUM_BUCKETS];
+    for dt in durations {
+        let idx = ((*dt / max_duration) * (NUM_BUCKETS as f64)) as usize;
+        counts[idx.min(NUM_BUCKETS - 1)] += 1;
+    }
+    counts
+}
+
+// Each mode is a stepped outline, so they can overlap without hiding each other.
+fn outline(counts: &Vec<usize>, max_count: usize) -> PolyLine {
+    let bucket_width = WIDTH / (NUM_BUCKETS as f64);
+    let y = |cnt: usize| HEIGHT * (1.0 - (cnt as f64) / (max_count as f64));
+    let mut pts = vec![Pt2D::new(0.0, HEIGHT)];
+    for (idx, cnt) in counts.iter().enumerate() {
+        pts.push(Pt2D::new((idx as f64) * bucket_width, y(*cnt)));
+        pts.push(Pt2D::new(((idx + 1) as f64) * bucket_width, y(*cnt)));
+    }
+    pts.push(Pt2D::new(WIDTH, HEIGHT));
+    pts.dedup();
+    PolyLine::unchecked_new(pts)
+}
+
+pub fn make_chart(
+    ctx: &EventCtx,
+    app: &App,
+    current: &BTreeMap<TripMode, Vec<Duration>>,
+    baseline: Option<&BTreeMap<TripMode, Vec<Duration>>>,
+    max_duration: Duration,
+) -> Widget {
+    let mut current_counts = BTreeMap::new();
+    let mut baseline_counts = BTreeMap::new();
+    for (mode, durations) in current {
+        current_counts.insert(*mode, buckets(durations, max_duration));
+    }
+    if let Some(b) = baseline {
+        for (mode, durations) in b {
+            baseline_counts.insert(*mode, buckets(durations, max_duration));
+        }
+    }
+    let max_count = current_counts
+        .values()
+        .chain(baseline_counts.values())
+        .flatten()
+        .max()
+        .cloned()
+        .unwrap_or(0)
+        .max(1);
+
+    let mut batch = GeomBatch::new();
+    batch.push(
+        Color::hex("#7C7C7C"),
+        PolyLine::must_new(vec![Pt2D::new(0.0, HEIGHT), Pt2D::new(WIDTH, HEIGHT)])
+            .make_polygons(Distance::meters(1.0)),
+    );
+    for (mode, counts) in &baseline_counts {
+        batch.extend(
+            color_for_mode(app, *mode).alpha(0.7),
+            outline(counts, max_count).dashed_lines(
+                Distance::meters(2.0),
+                Distance::meters(6.0),
+                Distance::meters(4.0),
+            ),
+        );
+    }
+    for (mode, counts) in &current_counts {
+        batch.push(
+            color_for_mode(app, *mode),
+            outline(counts, max_count).make_polygons(Distance::meters(2.0)),
+        );
+    }
+
+    Widget::col(vec![
+        format!("{} trips", max_count).draw_text(ctx),
+        Widget::draw_batch(ctx, batch),
+        Widget::row(vec![
+            "0s".draw_text(ctx),
+            max_duration.to_string().draw_text(ctx).align_right(),
+        ]),
+    ])
+}
@@ -0,0 +1,179 @@
+use crate::app::App;
+use crate::common::ColorLegend;
+use crate::helpers::color_for_mode;
+use crate::layer::trip_durations::make_chart;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Duration, Polygon};
+use sim::{TripID, TripMode, TripQuery};
+use std::collections::BTreeMap;
+
+// The trips matching a query from the trip table: where they start and end, and how long they
+// took. Keeps up with the sim as more trips finish.
+pub struct TripQueryResults {
+    query: String,
+    // The parsed query and the neighborhoods it needs, or why it's bad
+    prepared: Result<(TripQuery, BTreeMap<String, Polygon>), String>,
+    // How many finished trips have been checked against the query
+    checked: usize,
+    trips: Vec<TripID>,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+impl Layer for TripQueryResults {
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        let finished = app.primary.sim.get_analytics().finished_trips.len();
+        if finished != self.checked {
+            // The sim was reset
+            if finished < self.checked {
+                self.checked = 0;
+                self.trips.clear();
+            }
+            let new_trips = match self.prepared {
+                Ok((ref query, ref neighborhoods)) => query.find_since(
+                    &app.primary.sim,
+                    &app.primary.map,
+                    neighborhoods,
+                    self.checked,
+                ),
+                Err(_) => Vec::new(),
+            };
+            let changed = self.checked == 0 || !new_trips.is_empty();
+            self.trips.extend(new_trips);
+            self.checked = finished;
+            if changed {
+                self.rebuild(ctx, app);
+            }
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+        self.composite.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl TripQueryResults {
+    pub fn new(ctx: &mut EventCtx, app: &App, query: String) -> TripQueryResults {
+        let map = &app.primary.map;
+        let prepared = TripQuery::parse(&query).and_then(|q| {
+            let neighborhoods = q.load_neighborhoods(map)?;
+            Ok((q, neighborhoods))
+        });
+        let (trips, checked) = match prepared {
+            Ok((ref q, ref neighborhoods)) => (
+                q.find_since(&app.primary.sim, map, neighborhoods, 0),
+                app.primary.sim.get_analytics().finished_trips.len(),
+            ),
+            Err(_) => (Vec::new(), 0),
+        };
+        let mut layer = TripQueryResults {
+            query,
+            prepared,
+            checked,
+            trips,
+            unzoomed: ctx.upload(GeomBatch::new()),
+            zoomed: ctx.upload(GeomBatch::new()),
+            composite: Composite::new(Widget::nothing()).build(ctx),
+        };
+        layer.rebuild(ctx, app);
+        layer
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let sim = &app.primary.sim;
+        let mut col = vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Matching trips".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(Line(&self.query).secondary())
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+        ];
+
+        let mut unzoomed = GeomBatch::new();
+        let mut zoomed = GeomBatch::new();
+        if let Err(ref err) = self.prepared {
+            col.push(Text::from(Line(err)).wrap_to_pct(ctx, 15).draw(ctx));
+        } else {
+            let mut per_mode: BTreeMap<TripMode, Vec<Duration>> = BTreeMap::new();
+            for id in &self.trips {
+                let trip = sim.trip_info(*id);
+                let (duration, _) = sim.finished_trip_time(*id).unwrap();
+                per_mode
+                    .entry(trip.mode)
+                    .or_insert_with(Vec::new)
+                    .push(duration);
+                for (pt, color) in vec![
+                    (trip.start.pt(map), Color::RED),
+                    (trip.end.pt(map), Color::GREEN),
+                ] {
+                    unzoomed.push(
+                        color.alpha(0.5),
+                        Circle::new(pt, Distance::meters(30.0)).to_polygon(),
+                    );
+                    zoomed.push(
+                        color.alpha(0.5),
+                        Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+                    );
+                }
+            }
+
+            col.push(
+                format!("{} trips", abstutil::prettyprint_usize(self.trips.len())).draw_text(ctx),
+            );
+            col.push(ColorLegend::row(ctx, Color::RED, "origins"));
+            col.push(ColorLegend::row(ctx, Color::GREEN, "destinations"));
+            let max_duration = per_mode
+                .values()
+                .flatten()
+                .max()
+                .cloned()
+                .unwrap_or(Duration::ZERO);
+            if max_duration > Duration::ZERO {
+                col.push(make_chart(ctx, app, &per_mode, None, max_duration));
+                let mut legend = Vec::new();
+                for (mode, durations) in &per_mode {
+                    legend.push(ColorLegend::row(
+                        ctx,
+                        color_for_mode(app, *mode),
+                        format!("{} ({} trips)", mode.ongoing_verb(), durations.len()),
+                    ));
+                }
+                col.push(Widget::col(legend));
+            }
+        }
+
+        self.unzoomed = ctx.upload(unzoomed);
+        self.zoomed = ctx.upload(zoomed);
+        self.composite = Composite::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx);
+    }
+}
@@ -0,0 +1,211 @@
+mod app;
+mod challenges;
+mod colors;
+mod common;
+mod cutscene;
+mod debug;
+mod devtools;
+mod edit;
+mod game;
+mod helpers;
+mod info;
+mod layer;
+mod managed;
+mod options;
+mod pregame;
+mod render;
+mod sandbox;
+mod viewer;
+
+use crate::app::Flags;
+use abstutil::CmdArgs;
+use sim::SimFlags;
+
+pub fn main() {
+    let mut args = CmdArgs::new();
+
+    if args.enabled("--prebake") {
+        challenges::prebake_all();
+        return;
+    }
+
+    let mut flags = Flags {
+        sim_flags: SimFlags::from_args(&mut args),
+        num_agents: args.optional_parse("--num_agents", |s| s.parse()),
+    };
+    let mut opts = options::Options::default();
+    let saved = options::SavedSettings::load();
+    saved.apply(&mut opts);
+    opts.dev = args.enabled("--dev");
+    if args.enabled("--lowzoom") {
+        opts.min_zoom_for_detail = 1.0;
+    }
+
+    if let Some(x) = args.optional("--color_scheme") {
+        let mut ok = false;
+        let mut options = Vec::new();
+        for c in colors::ColorSchemeChoice::choices() {
+            options.push(c.label.clone());
+            if c.label == x {
+                opts.color_scheme = c.data;
+                ok = true;
+                break;
+            }
+        }
+        if !ok {
+            panic!(
+                "Invalid --color_scheme={}. Choices: {}",
+                x,
+                options.join(", ")
+            );
+        }
+    }
+    let mut settings = ezgui::Settings::new("A/B Street");
+    settings.window_icon(abstutil::path("system/assets/pregame/icon.png"));
+    if args.enabled("--enable_profiler") {
+        settings.enable_profiling();
+    }
+    if args.enabled("--dump_raw_events") {
+        settings.dump_raw_events();
+    }
+    if let Some(n) = args.optional_parse("--font_size", |s| s.parse::<usize>()) {
+        settings.default_font_size(n);
+    }
+    if let Some(s) = args
+        .optional_parse("--scale_factor", |s| s.parse::<f64>())
+        .or(saved.ui_scale)
+    {
+        settings.scale_factor(s);
+    }
+
+    let mut mode = None;
+    if let Some(x) = args.optional("--challenge") {
+        let mut aliases = Vec::new();
+        'OUTER: for (_, stages) in challenges::Challenge::all() {
+            for challenge in stages {
+                if challenge.alias == x {
+                    flags.sim_flags.load = challenge.gameplay.map_path();
+                    mode = Some(challenge.gameplay);
+                    break 'OUTER;
+                } else {
+                    aliases.push(challenge.alias);
+                }
+            }
+        }
+        if mode.is_none() {
+            panic!("Invalid --challenge={}. Choices: {}", x, aliases.join(", "));
+        }
+    }
+    if let Some(n) = args.optional_parse("--tutorial", |s| s.parse::<usize>()) {
+        mode = Some(sandbox::GameplayMode::Tutorial(
+            sandbox::TutorialPointer::new(n - 1, 0),
+        ));
+    }
+    if mode.is_none() && flags.sim_flags.load.contains("scenarios/") {
+        // TODO regex
+        let parts = flags.sim_flags.load.split("/").collect::<Vec<_>>();
+        let map_path = abstutil::path_map(parts[parts.len() - 2]);
+        let scenario = abstutil::basename(parts[parts.len() - 1]);
+        flags.sim_flags.load = map_path.clone();
+        mode = Some(sandbox::GameplayMode::PlayScenario(
+            map_path,
+            scenario,
+            Vec::new(),
+        ));
+    }
+    let start_with_edits = args.optional("--edits");
+    let mut start_at = None;
+    if let Some(uri) = args.optional("--open") {
+        match common::Permalink::parse(&uri) {
+            Ok(link) => {
+                if common::permalink::send_to_running_game(&uri) {
+                    println!("Opened {} in the game that's already running", uri);
+                    return;
+                }
+                if mode.is_none() {
+                    let map_path = abstutil::path_map(&link.map);
+                    flags.sim_flags.load = map_path.clone();
+                    mode = Some(sandbox::GameplayMode::Freeform(map_path));
+                }
+                start_at = Some(link);
+            }
+            // Just start normally
+            Err(err) => println!("Ignoring --open: {}", err),
+        }
+    }
+    // Let links opened later with --open go to this game, instead of starting another one
+    let permalinks = if args.enabled("--listen_for_links") {
+        common::permalink::listen()
+    } else {
+        None
+    };
+
+    args.done();
+
+    ezgui::run(settings, |ctx| {
+        saved.remap_keys(ctx);
+        game::Game::new(
+            flags,
+            opts,
+            start_with_edits,
+            mode,
+            start_at,
+            permalinks,
+            ctx,
+        )
+    });
+}
+
+// A read-only viewer for sharing results. It loads a map or a savestate and only allows looking
+// around; there's no editing and the simulation never advances. With --telemetry=0.0.0.0:9100, it
+// also plots the progress of headless runs streaming to that address. That listens on every
+// network interface without any authentication, so only do it on a trusted network.
+pub fn viewer_main() {
+    let mut args = CmdArgs::new();
+    let flags = Flags {
+        sim_flags: SimFlags::from_args(&mut args),
+        num_agents: None,
+    };
+    let telemetry = args.optional("--telemetry");
+    let mut opts = options::Options::default();
+    let saved = options::SavedSettings::load();
+    saved.apply(&mut opts);
+
+    let mut settings = ezgui::Settings::new("A/B Street viewer");
+    settings.window_icon(abstutil::path("system/assets/pregame/icon.png"));
+    if let Some(n) = args.optional_parse("--font_size", |s| s.parse::<usize>()) {
+        settings.default_font_size(n);
+    }
+    if let Some(s) = args
+        .optional_parse("--scale_factor", |s| s.parse::<f64>())
+        .or(saved.ui_scale)
+    {
+        settings.scale_factor(s);
+    }
+    args.done();
+
+    ezgui::run(settings, |ctx| {
+        saved.remap_keys(ctx);
+        game::Game::new_viewer(flags, opts, telemetry, ctx)
+    });
+}
+
+// Renders the entire map (and agents, if a savestate is loaded) to one big image and web map
+// tiles, then exits. Useful for posters and slippy maps. Doesn't need a window.
+pub fn render_map_main() {
+    let mut args = CmdArgs::new();
+    let sim_flags = SimFlags::from_args(&mut args);
+    let zoom = args
+        .optional_parse("--zoom", |s| s.parse::<f64>())
+        .unwrap_or(3.0);
+    let min_tile_zoom = args
+        .optional_parse("--min_tile_zoom", |s| s.parse::<u32>())
+        .unwrap_or(15);
+    let max_tile_zoom = args
+        .optional_parse("--max_tile_zoom", |s| s.parse::<u32>())
+        .unwrap_or(18);
+    let output = args.optional("--output");
+    args.done();
+
+    devtools::render_map::render_map(sim_flags, output, zoom, min_tile_zoom, max_tile_zoom);
+}
@@ -1,11 +1,15 @@
 use crate::app::App;
 use crate::colors::ColorSchemeChoice;
 use crate::game::{State, Transition};
+use crate::sandbox::SpeedSetting;
+use abstutil::Timer;
 use ezgui::{
     hotkey, Btn, Checkbox, Choice, Composite, EventCtx, GfxCtx, Key, Line, Outcome, Spinner,
     TextExt, Widget,
 };
-use geom::Duration;
+use geom::{Duration, UnitFmt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // TODO SimOptions stuff too
 #[derive(Clone)]
@@ -17,11 +21,21 @@ pub struct Options {
     pub traffic_signal_style: TrafficSignalStyle,
     pub color_scheme: ColorSchemeChoice,
     pub min_zoom_for_detail: f64,
+    // Below this, agents are drawn as density blobs instead of individually, and roads as
+    // simplified lines
+    pub min_zoom_for_density: f64,
     pub large_unzoomed_agents: bool,
+    pub agent_trails: bool,
 
     pub time_increment: Duration,
     pub resume_after_edit: bool,
     pub dont_draw_time_warp: bool,
+
+    pub units: UnitFmt,
+    // How often untitled edits are written somewhere safe
+    pub autosave_interval: Duration,
+    // The speed the simulation starts running at
+    pub default_speed: SpeedSetting,
 }
 
 impl Options {
@@ -34,15 +48,78 @@ impl Options {
             traffic_signal_style: TrafficSignalStyle::BAP,
             color_scheme: ColorSchemeChoice::Standard,
             min_zoom_for_detail: 4.0,
+            min_zoom_for_density: 0.5,
             large_unzoomed_agents: false,
+            agent_trails: false,
 
             time_increment: Duration::minutes(10),
             resume_after_edit: true,
             dont_draw_time_warp: false,
+
+            units: UnitFmt { metric: false },
+            autosave_interval: Duration::seconds(30.0),
+            default_speed: SpeedSetting::Realtime,
+        }
+    }
+}
+
+// The settings remembered between runs of the game. Everything else in Options resets. Older
+// files might be missing some of these, so everything has a default.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSettings {
+    // Scales panels, text, and widgets, but not the map. If this is missing, the monitor's scale
+    // is used.
+    pub ui_scale: Option<f64>,
+    pub units: UnitFmt,
+    pub color_scheme: ColorSchemeChoice,
+    pub autosave_interval: Duration,
+    pub default_speed: SpeedSetting,
+    // Pressing the first key acts like pressing the second
+    pub key_remap: Vec<(Key, Key)>,
+}
+
+impl Default for SavedSettings {
+    fn default() -> SavedSettings {
+        let opts = Options::default();
+        SavedSettings {
+            ui_scale: None,
+            units: opts.units,
+            color_scheme: opts.color_scheme,
+            autosave_interval: opts.autosave_interval,
+            default_speed: opts.default_speed,
+            key_remap: Vec::new(),
         }
     }
 }
 
+impl SavedSettings {
+    pub fn load() -> SavedSettings {
+        abstutil::maybe_read_json::<SavedSettings>(
+            abstutil::path_player_settings(),
+            &mut Timer::throwaway(),
+        )
+        .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        abstutil::write_json(abstutil::path_player_settings(), self);
+    }
+
+    // The UI scale has to be set up before the window exists, and the keys after, so both are up
+    // to the caller.
+    pub fn apply(&self, opts: &mut Options) {
+        opts.units = self.units;
+        opts.color_scheme = self.color_scheme;
+        opts.autosave_interval = self.autosave_interval;
+        opts.default_speed = self.default_speed;
+    }
+
+    pub fn remap_keys(&self, ctx: &mut EventCtx) {
+        ctx.canvas.key_remap = self.key_remap.iter().cloned().collect();
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum TrafficSignalStyle {
     BAP,
@@ -93,6 +170,13 @@ impl OptionsPanel {
                         None,
                         ctx.canvas.keys_to_pan,
                     ),
+                    Checkbox::text(
+                        ctx,
+                        "Keep the map sliding after dragging it",
+                        None,
+                        ctx.canvas.inertial_panning,
+                    )
+                    .named("inertia"),
                     Widget::row(vec![
                         "Scroll speed for menus".draw_text(ctx).centered_vert(),
                         Spinner::new(ctx, (1, 50), ctx.canvas.gui_scroll_speed as isize)
@@ -145,22 +229,37 @@ impl OptionsPanel {
                             ColorSchemeChoice::choices(),
                         ),
                     ]),
+                    Widget::row(vec![
+                        "Units:".draw_text(ctx),
+                        Widget::dropdown(
+                            ctx,
+                            "Units",
+                            app.opts.units.metric,
+                            vec![
+                                Choice::new("feet, miles, and mph", false),
+                                Choice::new("meters, kilometers, and km/h", true),
+                            ],
+                        ),
+                    ]),
                     Widget::row(vec![
                         format!(
-                            "Scale factor for text / UI elements (your monitor is {}):",
-                            ctx.monitor_scale_factor()
+                            "Scale for text / UI elements, not the map (your monitor is {}%):",
+                            (100.0 * ctx.monitor_scale_factor()).round()
                         )
                         .draw_text(ctx),
                         Widget::dropdown(ctx, "Scale factor", ctx.get_scale_factor(), {
-                            let mut choices = vec![
-                                Choice::new("0.5", 0.5),
-                                Choice::new("1.0", 1.0),
-                                Choice::new("1.5", 1.5),
-                                Choice::new("2.0", 2.0),
-                            ];
-                            let native = ctx.monitor_scale_factor();
-                            if !choices.iter().any(|c| c.data == native) {
-                                choices.push(Choice::new(native.to_string(), native));
+                            let mut choices: Vec<Choice<f64>> =
+                                vec![0.75, 1.0, 1.25, 1.5, 1.75, 2.0]
+                                    .into_iter()
+                                    .map(|x| Choice::new(format!("{}%", 100.0 * x), x))
+                                    .collect();
+                            // The monitor's native scale or --scale_factor might not be a
+                            // round number
+                            for x in vec![ctx.monitor_scale_factor(), ctx.get_scale_factor()] {
+                                if !choices.iter().any(|c| c.data == x) {
+                                    choices
+                                        .push(Choice::new(format!("{}%", (100.0 * x).round()), x));
+                                }
                             }
                             choices
                         }),
@@ -181,12 +280,72 @@ impl OptionsPanel {
                             ],
                         ),
                     ]),
+                    Widget::row(vec![
+                        "Camera zoom to group agents into blobs and simplify roads".draw_text(ctx),
+                        Widget::dropdown(
+                            ctx,
+                            "density zoom",
+                            app.opts.min_zoom_for_density,
+                            vec![
+                                Choice::new("never", 0.0),
+                                Choice::new("0.25", 0.25),
+                                Choice::new("0.5", 0.5),
+                                Choice::new("1.0", 1.0),
+                            ],
+                        ),
+                    ]),
                     Checkbox::text(
                         ctx,
                         "Draw enlarged unzoomed agents",
                         None,
                         app.opts.large_unzoomed_agents,
                     ),
+                    Checkbox::text(
+                        ctx,
+                        "Draw trails behind agents",
+                        None,
+                        app.opts.agent_trails,
+                    ),
+                ])
+                .bg(app.cs.section_bg)
+                .padding(8),
+                "Simulation and editing".draw_text(ctx),
+                Widget::col(vec![
+                    Widget::row(vec![
+                        "Start the simulation at".draw_text(ctx),
+                        Widget::dropdown(
+                            ctx,
+                            "default speed",
+                            app.opts.default_speed,
+                            vec![
+                                Choice::new("real-time speed", SpeedSetting::Realtime),
+                                Choice::new("10x speed", SpeedSetting::Fast),
+                                Choice::new("60x speed", SpeedSetting::Faster),
+                                Choice::new("3600x speed", SpeedSetting::Fastest),
+                            ],
+                        ),
+                    ]),
+                    Widget::row(vec![
+                        "Save untitled edits in case of a crash every".draw_text(ctx),
+                        Widget::dropdown(
+                            ctx,
+                            "autosave interval",
+                            app.opts.autosave_interval,
+                            vec![
+                                Choice::new("10 seconds", Duration::seconds(10.0)),
+                                Choice::new("30 seconds", Duration::seconds(30.0)),
+                                Choice::new("1 minute", Duration::minutes(1)),
+                                Choice::new("5 minutes", Duration::minutes(5)),
+                            ],
+                        ),
+                    ]),
+                ])
+                .bg(app.cs.section_bg)
+                .padding(8),
+                "Keys".draw_text(ctx),
+                Widget::row(vec![
+                    format!("{} keys remapped", ctx.canvas.key_remap.len()).draw_text(ctx),
+                    Btn::text_fg("remap keys").build_def(ctx, None),
                 ])
                 .bg(app.cs.section_bg)
                 .padding(8),
@@ -218,6 +377,9 @@ impl State for OptionsPanel {
                 "close" => {
                     return Transition::Pop;
                 }
+                "remap keys" => {
+                    return Transition::Push(RemapKeys::new(ctx));
+                }
                 "Apply" => {
                     app.opts.dev = self.composite.is_checked("Enable developer mode");
                     app.opts.debug_all_agents = self
@@ -234,6 +396,7 @@ impl State for OptionsPanel {
                         .composite
                         .is_checked("Use arrow keys to pan and Q/W to zoom");
                     ctx.canvas.edge_auto_panning = self.composite.is_checked("autopan");
+                    ctx.canvas.inertial_panning = self.composite.is_checked("inertia");
                     ctx.canvas.gui_scroll_speed =
                         self.composite.spinner("gui_scroll_speed") as usize;
 
@@ -253,14 +416,30 @@ impl State for OptionsPanel {
                         app.switch_map(ctx, app.primary.current_flags.sim_flags.load.clone());
                     }
 
+                    let mut saved = SavedSettings::load();
                     let factor = self.composite.dropdown_value("Scale factor");
                     if ctx.get_scale_factor() != factor {
                         ctx.set_scale_factor(factor);
+                        saved.ui_scale = Some(factor);
                     }
 
                     app.opts.min_zoom_for_detail = self.composite.dropdown_value("min zoom");
+                    app.opts.min_zoom_for_density = self.composite.dropdown_value("density zoom");
                     app.opts.large_unzoomed_agents =
                         self.composite.is_checked("Draw enlarged unzoomed agents");
+                    app.opts.agent_trails = self.composite.is_checked("Draw trails behind agents");
+
+                    // Info panels and layers already open keep the old units until they're
+                    // refreshed.
+                    app.opts.units.metric = self.composite.dropdown_value("Units");
+                    app.opts.default_speed = self.composite.dropdown_value("default speed");
+                    app.opts.autosave_interval = self.composite.dropdown_value("autosave interval");
+
+                    saved.units = app.opts.units;
+                    saved.color_scheme = app.opts.color_scheme;
+                    saved.default_speed = app.opts.default_speed;
+                    saved.autosave_interval = app.opts.autosave_interval;
+                    saved.save();
 
                     return Transition::Pop;
                 }
@@ -277,3 +456,121 @@ impl State for OptionsPanel {
         self.composite.draw(g);
     }
 }
+
+// Each key can be made to act like some other key.
+struct RemapKeys {
+    composite: Composite,
+    // While the player is pressing keys to set up a new remapping, the existing ones are turned
+    // off, so they get the physical key. The first key pressed is the one to change.
+    capturing: Option<(BTreeMap<Key, Key>, Option<Key>)>,
+}
+
+impl RemapKeys {
+    fn new(ctx: &mut EventCtx) -> Box<dyn State> {
+        let mut state = RemapKeys {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            capturing: None,
+        };
+        state.rebuild(ctx);
+        Box::new(state)
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx) {
+        let remap = match self.capturing {
+            Some((ref remap, _)) => remap,
+            None => &ctx.canvas.key_remap,
+        };
+        let mut col = vec![Widget::row(vec![
+            Line("Remap keys").small_heading().draw(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])];
+        if remap.is_empty() {
+            col.push("Every key does what it says".draw_text(ctx));
+        }
+        let rows: Vec<(Key, Key)> = remap.iter().map(|(a, b)| (*a, *b)).collect();
+        for (idx, (from, to)) in rows.into_iter().enumerate() {
+            col.push(Widget::row(vec![
+                format!("{} acts like {}", from.describe(), to.describe()).draw_text(ctx),
+                Btn::text_fg("remove")
+                    .build(ctx, format!("remove #{}", idx + 1), None)
+                    .align_right(),
+            ]));
+        }
+        col.push(match self.capturing {
+            Some((_, None)) => "Press the key to change (Escape to cancel)".draw_text(ctx),
+            Some((_, Some(from))) => format!(
+                "Now press the key that {} should act like (Escape to cancel)",
+                from.describe()
+            )
+            .draw_text(ctx),
+            None => Btn::text_bg2("add").build_def(ctx, None),
+        });
+
+        self.composite = Composite::new(Widget::col(col)).build(ctx);
+    }
+
+    fn save(ctx: &EventCtx) {
+        let mut saved = SavedSettings::load();
+        saved.key_remap = ctx.canvas.key_remap.iter().map(|(a, b)| (*a, *b)).collect();
+        saved.save();
+    }
+}
+
+impl State for RemapKeys {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        if let Some((remap, from)) = self.capturing.take() {
+            match (ctx.input.any_key_pressed(), from) {
+                (None, _) => {
+                    self.capturing = Some((remap, from));
+                }
+                (Some(Key::Escape), _) => {
+                    ctx.canvas.key_remap = remap;
+                    self.rebuild(ctx);
+                }
+                (Some(key), None) => {
+                    self.capturing = Some((remap, Some(key)));
+                    self.rebuild(ctx);
+                }
+                (Some(to), Some(from)) => {
+                    ctx.canvas.key_remap = remap;
+                    if from != to {
+                        ctx.canvas.key_remap.insert(from, to);
+                    }
+                    RemapKeys::save(ctx);
+                    self.rebuild(ctx);
+                }
+            }
+            return Transition::Keep;
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "add" => {
+                    let remap = std::mem::replace(&mut ctx.canvas.key_remap, BTreeMap::new());
+                    self.capturing = Some((remap, None));
+                    self.rebuild(ctx);
+                }
+                x => {
+                    let idx = x["remove #".len()..].parse::<usize>().unwrap() - 1;
+                    let from = *ctx.canvas.key_remap.keys().nth(idx).unwrap();
+                    ctx.canvas.key_remap.remove(&from);
+                    RemapKeys::save(ctx);
+                    self.rebuild(ctx);
+                }
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
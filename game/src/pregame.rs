@@ -436,7 +436,7 @@ impl State for Proposals {
                     if let Some(err) = maybe_err {
                         return Transition::Push(msg("Can't load proposal", vec![err]));
                     } else {
-                        app.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
+                        app.layer = Some(Box::new(crate::layer::map::Edits::new(ctx, app)));
                         return Transition::Push(Box::new(SandboxMode::new(
                             ctx,
                             app,
@@ -3,8 +3,17 @@ use crate::colors::ColorScheme;
 use crate::helpers::ID;
 use crate::render::{DrawOptions, Renderable};
 use ezgui::{Color, GeomBatch, GfxCtx};
-use geom::Polygon;
+use geom::{Angle, Distance, PolyLine, Polygon, Pt2D};
 use map_model::{Area, AreaID, AreaType, Map};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+// How many frames the water animation cycles through, and how long each one lasts
+pub const RIPPLE_PHASES: usize = 4;
+pub const RIPPLE_PHASE_DURATION: std::time::Duration = std::time::Duration::from_millis(500);
+const GRASS_SPACING: f64 = 8.0;
+const RIPPLE_SPACING: f64 = 20.0;
+const RIPPLE_LENGTH: Distance = Distance::const_meters(6.0);
 
 pub struct DrawArea {
     pub id: AreaID,
@@ -16,6 +25,58 @@ impl DrawArea {
         DrawArea { id: area.id }
     }
 
+    // A static pattern of grass tufts over parks. Only worth drawing when zoomed in.
+    pub fn grass_texture(area: &Area, cs: &ColorScheme, batch: &mut GeomBatch) {
+        if area.area_type != AreaType::Park {
+            return;
+        }
+        let color = cs.grass.lerp(Color::BLACK, 0.15);
+        let mut rng = XorShiftRng::seed_from_u64(area.id.0 as u64);
+        for pt in jittered_grid(&area.polygon, GRASS_SPACING, &mut rng) {
+            // Three blades fanning out from the bottom
+            for angle in vec![-110.0, -90.0, -70.0] {
+                let len = Distance::meters(rng.gen_range(0.6, 1.2));
+                if let Ok(pl) =
+                    PolyLine::new(vec![pt, pt.project_away(len, Angle::new_degs(angle))])
+                {
+                    batch.push(color, pl.make_polygons(Distance::meters(0.15)));
+                }
+            }
+        }
+    }
+
+    // Wavy crests over water, for one frame of the animation. Every phase uses the same crests,
+    // just drifted and bent a bit differently.
+    pub fn water_ripples(area: &Area, cs: &ColorScheme, phase: usize, batch: &mut GeomBatch) {
+        if area.area_type != AreaType::Water {
+            return;
+        }
+        let color = cs.water.lerp(Color::WHITE, 0.4);
+        let pct = (phase as f64) / (RIPPLE_PHASES as f64);
+        let mut rng = XorShiftRng::seed_from_u64(area.id.0 as u64);
+        for pt in jittered_grid(&area.polygon, RIPPLE_SPACING, &mut rng) {
+            let offset = rng.gen_range(0.0, 1.0);
+            let t = 2.0 * std::f64::consts::PI * (pct + offset);
+            let start = pt.offset(1.5 * t.cos(), 0.0);
+            let pts: Vec<Pt2D> = (0..=6)
+                .map(|i| {
+                    let x = RIPPLE_LENGTH.inner_meters() * (i as f64) / 6.0;
+                    let y = 0.4 * (t + x).sin();
+                    start.offset(x, y)
+                })
+                .collect();
+            if !pts.iter().all(|pt| area.polygon.contains_pt(*pt)) {
+                continue;
+            }
+            if let Ok(pl) = PolyLine::new(pts) {
+                batch.push(
+                    color.alpha((0.5 + 0.3 * t.sin()) as f32),
+                    pl.make_polygons(Distance::meters(0.3)),
+                );
+            }
+        }
+    }
+
     pub fn color(area_type: AreaType, cs: &ColorScheme) -> Color {
         match area_type {
             AreaType::Park => cs.grass,
@@ -38,3 +99,25 @@ impl Renderable for DrawArea {
         map.get_a(self.id).polygon.clone()
     }
 }
+
+// Points roughly evenly spaced inside a polygon
+fn jittered_grid(polygon: &Polygon, spacing: f64, rng: &mut XorShiftRng) -> Vec<Pt2D> {
+    let bounds = polygon.get_bounds();
+    let mut pts = Vec::new();
+    let mut y = bounds.min_y;
+    while y < bounds.max_y {
+        let mut x = bounds.min_x;
+        while x < bounds.max_x {
+            let pt = Pt2D::new(
+                x + rng.gen_range(0.0, spacing),
+                y + rng.gen_range(0.0, spacing),
+            );
+            if polygon.contains_pt(pt) {
+                pts.push(pt);
+            }
+            x += spacing;
+        }
+        y += spacing;
+    }
+    pts
+}
@@ -0,0 +1,266 @@
+use crate::app::App;
+use crate::helpers::ID;
+use crate::render::area::DrawArea;
+use crate::render::building::{DrawBuilding, MIN_ZOOM_FOR_EXTRUSION};
+use crate::render::intersection::DrawIntersection;
+use crate::render::parking_lot::DrawParkingLot;
+use crate::render::road::DrawRoad;
+use crate::render::Renderable;
+use ezgui::{Drawable, GeomBatch, GfxCtx};
+use geom::Bounds;
+use map_model::Building;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+// (minimum zoom, tile size). Further out, tiles are bigger, so there are fewer of them on screen.
+// Buildings are only extruded in the last band.
+const ZOOM_BANDS: [(f64, f64); 2] = [(0.0, 600.0), (MIN_ZOOM_FOR_EXTRUSION, 300.0)];
+// Building paths and the buildings themselves are separate ranks.
+const BUILDING_RANK: usize = 5;
+const NUM_RANKS: usize = BUILDING_RANK + 2;
+
+// When unzoomed, the whole map is just a few big Drawables. When zoomed in, every area, lane, road,
+// intersection, parking lot, and building used to be drawn separately. Instead, chop the map into
+// tiles and merge everything static in a tile at the same z-order into one Drawable. Each zoom band
+// has its own tiles. Tiles are rendered the first time they're on screen and stick around until
+// something in them is edited, so panning at the same zoom just redraws a handful of Drawables,
+// plus agents and anything dynamic.
+pub struct BackgroundTiles {
+    bands: Vec<Band>,
+}
+
+struct Band {
+    tile_size: f64,
+    // Every object belongs to exactly one tile, based on the top-left corner of its bounds. The
+    // bounds of the tile cover all of its objects, so they can extend past the grid cell.
+    members: HashMap<(isize, isize), (Bounds, Vec<(ID, Bounds)>)>,
+    // Keyed by (zorder, rank), so everything of a lower rank can be drawn first across all tiles
+    rendered: RefCell<HashMap<(isize, isize), BTreeMap<(isize, usize), Drawable>>>,
+}
+
+impl BackgroundTiles {
+    pub fn new(objects: Vec<(ID, Bounds)>) -> BackgroundTiles {
+        BackgroundTiles {
+            bands: ZOOM_BANDS
+                .iter()
+                .map(|(_, tile_size)| {
+                    let mut band = Band {
+                        tile_size: *tile_size,
+                        members: HashMap::new(),
+                        rendered: RefCell::new(HashMap::new()),
+                    };
+                    for (id, bounds) in &objects {
+                        band.add(id.clone(), bounds.clone());
+                    }
+                    band
+                })
+                .collect(),
+        }
+    }
+
+    // Only the objects that BackgroundTiles covers
+    pub fn handles(id: &ID) -> bool {
+        match id {
+            ID::Area(_)
+            | ID::Lane(_)
+            | ID::Road(_)
+            | ID::Intersection(_)
+            | ID::ParkingLot(_)
+            | ID::Building(_) => true,
+            _ => false,
+        }
+    }
+
+    // Within one z-order, match the order that objects are normally drawn in. Roads are just the
+    // center lines, which have to go on top of the lanes. Buildings are split into their front
+    // path and the building itself.
+    fn rank(id: &ID) -> usize {
+        match id {
+            ID::Area(_) => 0,
+            ID::ParkingLot(_) => 1,
+            ID::Lane(_) => 2,
+            ID::Road(_) => 3,
+            ID::Intersection(_) => 4,
+            ID::Building(_) => BUILDING_RANK,
+            _ => unreachable!(),
+        }
+    }
+
+    // Call after the map is edited with everything that changed, along with their new bounds. Any
+    // tile containing them (before or after) is re-rendered the next time it's on screen.
+    pub fn invalidate(&mut self, changed: Vec<(ID, Bounds)>) {
+        for band in &mut self.bands {
+            for (id, bounds) in &changed {
+                band.remove(id);
+                band.add(id.clone(), bounds.clone());
+            }
+        }
+    }
+
+    // Draws everything on screen at one z-order, rendering tiles first if needed.
+    pub fn draw(&self, g: &mut GfxCtx, app: &App, zorder: isize) {
+        let idx = ZOOM_BANDS
+            .iter()
+            .rposition(|(min_zoom, _)| g.canvas.cam_zoom >= *min_zoom)
+            .unwrap_or(0);
+        let extrude = ZOOM_BANDS[idx].0 >= MIN_ZOOM_FOR_EXTRUSION;
+        let band = &self.bands[idx];
+
+        let screen = g.get_screen_bounds();
+        let mut rendered = band.rendered.borrow_mut();
+        let mut visible = Vec::new();
+        for (key, (bounds, ids)) in &band.members {
+            if bounds.overlaps(&screen) {
+                if !rendered.contains_key(key) {
+                    let layers = render_tile(g, app, ids, extrude);
+                    rendered.insert(*key, layers);
+                }
+                visible.push(*key);
+            }
+        }
+        // Extruded buildings have to be drawn from north to south, so do the same with tiles.
+        visible.sort_by_key(|(x, y)| (*y, *x));
+
+        // A lane in one tile can overlap a road's center line in another tile, so draw each rank
+        // everywhere before moving on.
+        for rank in 0..NUM_RANKS {
+            for key in &visible {
+                if let Some(draw) = rendered[key].get(&(zorder, rank)) {
+                    g.redraw(draw);
+                }
+            }
+        }
+    }
+}
+
+impl Band {
+    fn add(&mut self, id: ID, bounds: Bounds) {
+        let key = (
+            (bounds.min_x / self.tile_size).floor() as isize,
+            (bounds.min_y / self.tile_size).floor() as isize,
+        );
+        let tile = self
+            .members
+            .entry(key)
+            .or_insert_with(|| (Bounds::new(), Vec::new()));
+        tile.0.union(bounds.clone());
+        tile.1.push((id, bounds));
+        self.rendered.borrow_mut().remove(&key);
+    }
+
+    fn remove(&mut self, id: &ID) {
+        let mut found = None;
+        for (key, (_, ids)) in &self.members {
+            if ids.iter().any(|(x, _)| x == id) {
+                found = Some(*key);
+                break;
+            }
+        }
+        if let Some(key) = found {
+            let tile = self.members.get_mut(&key).unwrap();
+            tile.1.retain(|(x, _)| x != id);
+            // The tile might be smaller now
+            tile.0 = Bounds::new();
+            for (_, b) in &tile.1 {
+                tile.0.union(b.clone());
+            }
+            self.rendered.borrow_mut().remove(&key);
+        }
+    }
+}
+
+fn render_tile(
+    g: &mut GfxCtx,
+    app: &App,
+    ids: &Vec<(ID, Bounds)>,
+    extrude: bool,
+) -> BTreeMap<(isize, usize), Drawable> {
+    let map = &app.primary.map;
+    let draw_map = &app.primary.draw_map;
+
+    let mut batches: BTreeMap<(isize, usize), GeomBatch> = BTreeMap::new();
+    let mut buildings: Vec<(isize, &Building)> = Vec::new();
+    for (id, _) in ids {
+        let (zorder, batch) = match id {
+            ID::Area(a) => {
+                let area = map.get_a(*a);
+                (
+                    draw_map.get_a(*a).get_zorder(),
+                    GeomBatch::from(vec![(
+                        DrawArea::color(area.area_type, &app.cs),
+                        area.polygon.clone(),
+                    )]),
+                )
+            }
+            ID::ParkingLot(pl) => (
+                draw_map.get_pl(*pl).get_zorder(),
+                DrawParkingLot::render(map.get_pl(*pl), &app.cs),
+            ),
+            ID::Lane(l) => {
+                let obj = draw_map.get_l(*l);
+                (obj.get_zorder(), obj.render(g.prerender, app))
+            }
+            ID::Road(r) => (
+                draw_map.get_r(*r).get_zorder(),
+                DrawRoad::render(map.get_r(*r), map, &app.cs),
+            ),
+            ID::Intersection(i) => (
+                draw_map.get_i(*i).get_zorder(),
+                DrawIntersection::render(map.get_i(*i), map, &app.cs, g.prerender),
+            ),
+            ID::Building(b) => {
+                buildings.push((draw_map.get_b(*b).get_zorder(), map.get_b(*b)));
+                continue;
+            }
+            _ => unreachable!(),
+        };
+        batches
+            .entry((zorder, BackgroundTiles::rank(id)))
+            .or_insert_with(GeomBatch::new)
+            .append(batch);
+    }
+
+    // Match how DrawMap draws all buildings at once: paths, then buildings with their outlines on
+    // top, or just the extruded buildings.
+    if extrude {
+        buildings.sort_by(|(_, b1), (_, b2)| {
+            b1.polygon
+                .get_bounds()
+                .max_y
+                .partial_cmp(&b2.polygon.get_bounds().max_y)
+                .unwrap()
+        });
+    }
+    for (zorder, b) in buildings {
+        let mut paths = GeomBatch::new();
+        let mut bldg = GeomBatch::new();
+        let mut outlines = GeomBatch::new();
+        DrawBuilding::render(
+            b,
+            &app.cs,
+            &mut bldg,
+            &mut paths,
+            &mut outlines,
+            g.prerender,
+        );
+        if extrude {
+            bldg = GeomBatch::new();
+            DrawBuilding::extrude(b, &app.cs, &mut bldg);
+        } else {
+            bldg.append(outlines);
+        }
+        batches
+            .entry((zorder, BUILDING_RANK))
+            .or_insert_with(GeomBatch::new)
+            .append(paths);
+        batches
+            .entry((zorder, BUILDING_RANK + 1))
+            .or_insert_with(GeomBatch::new)
+            .append(bldg);
+    }
+
+    batches
+        .into_iter()
+        .map(|(key, batch)| (key, g.upload(batch)))
+        .collect()
+}
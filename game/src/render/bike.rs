@@ -1,9 +1,10 @@
 use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
-use crate::render::{DrawOptions, Renderable};
+use crate::render::tween::spawn_in;
+use crate::render::{draw_trail, DrawOptions, Renderable};
 use ezgui::{Drawable, GeomBatch, GfxCtx, Prerender};
-use geom::{ArrowCap, Circle, Distance, Line, PolyLine, Polygon};
+use geom::{ArrowCap, Circle, Distance, Line, PolyLine, Polygon, Pt2D};
 use map_model::{Map, SIDEWALK_THICKNESS};
 use sim::{CarID, DrawCarInput};
 
@@ -19,6 +20,7 @@ pub struct DrawBike {
 impl DrawBike {
     pub fn new(
         input: DrawCarInput,
+        trail: &[Pt2D],
         map: &Map,
         prerender: &Prerender,
         cs: &ColorScheme,
@@ -101,11 +103,13 @@ impl DrawBike {
             .map(|on| on.get_zorder(map))
             .max()
             .unwrap();
+        let mut batch = draw_trail(trail, body_color, body_radius);
+        batch.append(spawn_in(draw_default, body_pos, input.age));
         DrawBike {
             id: input.id,
             body_circle,
             zorder,
-            draw_default: prerender.upload(draw_default),
+            draw_default: prerender.upload(batch),
         }
     }
 }
@@ -7,6 +7,13 @@ use geom::{Distance, Polygon, Pt2D};
 use map_model::{Building, BuildingID, Map, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
 use std::cell::RefCell;
 
+// Past this zoom, buildings are drawn with some fake height.
+pub const MIN_ZOOM_FOR_EXTRUSION: f64 = 8.0;
+// Not to scale; the roof is offset straight up (north) by this much per level, so tall buildings
+// don't cover entire blocks.
+const OFFSET_PER_LEVEL: Distance = Distance::const_meters(1.0);
+const MAX_LEVELS: f64 = 30.0;
+
 pub struct DrawBuilding {
     pub id: BuildingID,
     label: RefCell<Option<Drawable>>,
@@ -21,6 +28,46 @@ impl DrawBuilding {
         outlines_batch: &mut GeomBatch,
         prerender: &Prerender,
     ) -> DrawBuilding {
+        DrawBuilding::render(bldg, cs, bldg_batch, paths_batch, outlines_batch, prerender);
+        DrawBuilding {
+            id: bldg.id,
+            label: RefCell::new(None),
+        }
+    }
+
+    // The flat building, its front path, and its outline
+    pub fn render(
+        bldg: &Building,
+        cs: &ColorScheme,
+        bldg_batch: &mut GeomBatch,
+        paths_batch: &mut GeomBatch,
+        outlines_batch: &mut GeomBatch,
+        prerender: &Prerender,
+    ) {
+        DrawBuilding::render_geometry(bldg, cs, bldg_batch, paths_batch, outlines_batch);
+        if bldg
+            .parking
+            .as_ref()
+            .map(|p| p.public_garage_name.is_some())
+            .unwrap_or(false)
+        {
+            // Might need to scale down more for some buildings, but so far, this works everywhere.
+            bldg_batch.append(
+                GeomBatch::mapspace_svg(prerender, "system/assets/map/parking.svg")
+                    .scale(0.1)
+                    .centered_on(bldg.label_center),
+            );
+        }
+    }
+
+    // Everything from render() except for the parking icon, so this works without a window
+    pub fn render_geometry(
+        bldg: &Building,
+        cs: &ColorScheme,
+        bldg_batch: &mut GeomBatch,
+        paths_batch: &mut GeomBatch,
+        outlines_batch: &mut GeomBatch,
+    ) {
         // Trim the front path line away from the sidewalk's center line, so that it doesn't
         // overlap. For now, this cleanup is visual; it doesn't belong in the map_model layer.
         let orig_line = &bldg.front_path.line;
@@ -43,24 +90,41 @@ impl DrawBuilding {
         if let Ok(p) = bldg.polygon.to_outline(Distance::meters(0.1)) {
             outlines_batch.push(cs.building_outline, p);
         }
+    }
 
-        if bldg
-            .parking
-            .as_ref()
-            .map(|p| p.public_garage_name.is_some())
-            .unwrap_or(false)
-        {
-            // Might need to scale down more for some buildings, but so far, this works everywhere.
-            bldg_batch.append(
-                GeomBatch::mapspace_svg(prerender, "system/assets/map/parking.svg")
-                    .scale(0.1)
-                    .centered_on(bldg.label_center),
+    // Shaded walls, then the roof on top. Callers should draw buildings from north to south, so
+    // the ones closer to the viewer cover up the ones behind them.
+    pub fn extrude(bldg: &Building, cs: &ColorScheme, batch: &mut GeomBatch) {
+        let color = if bldg.amenities.is_empty() {
+            cs.residential_building
+        } else {
+            cs.commerical_building
+        };
+        let dy = -OFFSET_PER_LEVEL.inner_meters() * bldg.levels.min(MAX_LEVELS);
+
+        let mut pts = bldg.polygon.points().clone();
+        if pts[0] != *pts.last().unwrap() {
+            pts.push(pts[0]);
+        }
+        for pair in pts.windows(2) {
+            let (pt1, pt2) = (pair[0], pair[1]);
+            let len = pt1.dist_to(pt2).inner_meters();
+            if len == 0.0 {
+                continue;
+            }
+            // Walls facing north or south are lit; walls facing east or west are in shadow.
+            let shade = 0.2 + 0.3 * (1.0 - (pt2.x() - pt1.x()).abs() / len);
+            batch.push(
+                color.lerp(Color::BLACK, shade),
+                Polygon::new(&vec![pt1, pt2, pt2.offset(0.0, dy), pt1.offset(0.0, dy)]),
             );
         }
 
-        DrawBuilding {
-            id: bldg.id,
-            label: RefCell::new(None),
+        let roof = bldg.polygon.translate(0.0, dy);
+        let outline = roof.to_outline(Distance::meters(0.1));
+        batch.push(color, roof);
+        if let Ok(p) = outline {
+            batch.push(cs.building_outline, p);
         }
     }
 }
@@ -70,7 +134,13 @@ impl Renderable for DrawBuilding {
         ID::Building(self.id)
     }
 
+    // The buildings themselves are all drawn at once by DrawMap or BackgroundTiles; only labels are
+    // drawn individually.
     fn draw(&self, g: &mut GfxCtx, app: &App, opts: &DrawOptions) {
+        self.draw_dynamic(g, app, opts);
+    }
+
+    fn draw_dynamic(&self, g: &mut GfxCtx, app: &App, opts: &DrawOptions) {
         if opts.label_buildings {
             // Labels are expensive to compute up-front, so do it lazily, since we don't really
             // zoom in on all buildings in a single session anyway
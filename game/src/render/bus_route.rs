@@ -0,0 +1,72 @@
+use crate::app::App;
+use crate::colors::ColorScheme;
+use crate::helpers::ID;
+use crate::render::{DrawOptions, Renderable};
+use ezgui::{Drawable, GeomBatch, GfxCtx, Prerender};
+use geom::{Bounds, Distance, Polygon};
+use map_model::{BusRoute, BusRouteID, Map};
+
+const WIDTH: Distance = Distance::const_meters(2.0);
+
+// The entire path a route takes, start border to end border. Only shown when debugging.
+pub struct DrawBusRoute {
+    pub id: BusRouteID,
+    polygon: Polygon,
+    pub bounds: Bounds,
+
+    draw_default: Drawable,
+}
+
+impl DrawBusRoute {
+    // Pathfinding every route is slow, so this happens on worker threads. None if no part of the
+    // route could be traced.
+    pub fn render(route: &BusRoute, map: &Map) -> Option<Polygon> {
+        let mut pieces = Vec::new();
+        for req in route.all_steps(map) {
+            let start = req.start.dist_along();
+            if let Some(pl) = map
+                .pathfind(req)
+                .and_then(|path| path.trace(map, start, None))
+            {
+                pieces.push(pl.make_polygons(WIDTH));
+            }
+        }
+        if pieces.is_empty() {
+            None
+        } else {
+            Some(Polygon::union_all(pieces))
+        }
+    }
+
+    pub fn new(
+        id: BusRouteID,
+        polygon: Polygon,
+        cs: &ColorScheme,
+        prerender: &Prerender,
+    ) -> DrawBusRoute {
+        let draw_default = prerender.upload(GeomBatch::from(vec![(
+            cs.rotating_color_plot(id.0).alpha(0.7),
+            polygon.clone(),
+        )]));
+        DrawBusRoute {
+            id,
+            bounds: polygon.get_bounds(),
+            polygon,
+            draw_default,
+        }
+    }
+}
+
+impl Renderable for DrawBusRoute {
+    fn get_id(&self) -> ID {
+        ID::BusRoute(self.id)
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App, _: &DrawOptions) {
+        g.redraw(&self.draw_default);
+    }
+
+    fn get_outline(&self, _: &Map) -> Polygon {
+        self.polygon.clone()
+    }
+}
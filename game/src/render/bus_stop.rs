@@ -1,12 +1,17 @@
 use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
-use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
-use ezgui::{Drawable, GeomBatch, GfxCtx, Prerender};
-use geom::{Angle, Circle, Distance, Line, Polygon, Pt2D};
+use crate::render::{DrawOptions, Renderable, MIN_ZOOM_FOR_SMALL_DETAILS, OUTLINE_THICKNESS};
+use ezgui::{Drawable, GeomBatch, GfxCtx, Line, Prerender, Text};
+use geom::{Angle, Circle, Distance, PolyLine, Polygon, Pt2D};
 use map_model::{BusStop, BusStopID, Map};
+use std::collections::BTreeSet;
 
 const RADIUS: Distance = Distance::const_meters(1.0);
+const SHELTER_LENGTH: Distance = Distance::const_meters(4.0);
+const SHELTER_WIDTH: Distance = Distance::const_meters(1.2);
+// Room for about two buses waiting
+const LAYOVER_LENGTH: Distance = Distance::const_meters(25.0);
 
 pub struct DrawBusStop {
     pub id: BusStopID,
@@ -38,21 +43,67 @@ impl DrawBusStop {
             .centered_on(center),
         );
         let mut batch = GeomBatch::new();
+
+        // Terminals get the end of the vehicle lane marked off, so it's clear where buses wait
+        if stop.is_terminal {
+            let lane = map.get_l(stop.driving_pos.lane());
+            let end = stop.driving_pos.dist_along();
+            if let Ok((pl, _)) = lane
+                .lane_center_pts
+                .slice((end - LAYOVER_LENGTH).max(Distance::ZERO), end)
+            {
+                batch.push(cs.bus_layover, pl.make_polygons(lane.width));
+            }
+        }
+
+        // The shelter goes across the sidewalk from the stop marker
+        if stop.has_shelter {
+            let shelter_center = pt.project_away(
+                map.get_l(stop.sidewalk_pos.lane()).width / 4.0,
+                angle.rotate_degs(-90.0),
+            );
+            let footprint = PolyLine::must_new(vec![
+                shelter_center.project_away(SHELTER_LENGTH / 2.0, angle.opposite()),
+                shelter_center.project_away(SHELTER_LENGTH / 2.0, angle),
+            ])
+            .make_polygons(SHELTER_WIDTH);
+            batch.push(cs.bus_shelter.alpha(0.8), footprint.clone());
+            if let Ok(outline) = footprint.to_outline(Distance::meters(0.2)) {
+                batch.push(cs.bus_shelter, outline);
+            }
+        }
+
         batch.push(
             cs.bus_layer.alpha(0.8),
             Circle::new(center, RADIUS).to_polygon(),
         );
         batch.append(icon.autocrop().centered_on(center));
+        let pole_top = center.project_away(1.5 * RADIUS, Angle::new_degs(90.0));
         batch.push(
             cs.stop_sign_pole,
-            Line::new(
+            PolyLine::must_new(vec![
                 center.project_away(RADIUS, Angle::new_degs(90.0)),
-                center.project_away(1.5 * RADIUS, Angle::new_degs(90.0)),
-            )
-            .unwrap()
+                pole_top,
+            ])
             .make_polygons(Distance::meters(0.3)),
         );
 
+        // Like the sign hanging off a real stop pole, list every route serving it
+        let routes: BTreeSet<&String> = map
+            .get_routes_serving_stop(stop.id)
+            .into_iter()
+            .map(|r| &r.short_name)
+            .collect();
+        if !routes.is_empty() {
+            let mut txt = Text::new().with_bg();
+            for r in routes {
+                txt.add(Line(r).fg(cs.bus_label));
+            }
+            let label = txt.render_to_batch(prerender).scale(0.03);
+            let dims = label.get_dims();
+            batch.append(label.centered_on(pole_top.offset(0.0, dims.height / 2.0)));
+        }
+
         DrawBusStop {
             id: stop.id,
             center,
@@ -68,6 +119,9 @@ impl Renderable for DrawBusStop {
     }
 
     fn draw(&self, g: &mut GfxCtx, _: &App, _: &DrawOptions) {
+        if g.canvas.cam_zoom < MIN_ZOOM_FOR_SMALL_DETAILS {
+            return;
+        }
         g.redraw(&self.draw_default);
     }
 
@@ -1,13 +1,14 @@
 use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
-use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
+use crate::render::tween::spawn_in;
+use crate::render::{draw_trail, DrawOptions, Renderable, OUTLINE_THICKNESS};
 use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Line, Prerender, Text};
 use geom::{Angle, ArrowCap, Distance, PolyLine, Polygon, Pt2D};
 use map_model::{Map, TurnType};
 use sim::{CarID, CarStatus, DrawCarInput, VehicleType};
 
-const CAR_WIDTH: Distance = Distance::const_meters(1.75);
+pub(crate) const CAR_WIDTH: Distance = Distance::const_meters(1.75);
 
 pub struct DrawCar {
     pub id: CarID,
@@ -19,7 +20,13 @@ pub struct DrawCar {
 }
 
 impl DrawCar {
-    pub fn new(input: DrawCarInput, map: &Map, prerender: &Prerender, cs: &ColorScheme) -> DrawCar {
+    pub fn new(
+        input: DrawCarInput,
+        trail: &[Pt2D],
+        map: &Map,
+        prerender: &Prerender,
+        cs: &ColorScheme,
+    ) -> DrawCar {
         let mut draw_default = GeomBatch::new();
 
         // Wheels
@@ -161,12 +168,14 @@ impl DrawCar {
             .map(|on| on.get_zorder(map))
             .max()
             .unwrap();
+        let mut batch = draw_trail(trail, cs.rotating_color_agents(input.id.0), CAR_WIDTH / 2.0);
+        batch.append(spawn_in(draw_default, input.body.middle(), input.age));
         DrawCar {
             id: input.id,
             body: input.body,
             body_polygon,
             zorder,
-            draw_default: prerender.upload(draw_default),
+            draw_default: prerender.upload(batch),
         }
     }
 }
@@ -206,7 +215,7 @@ fn thick_line_from_angle(
     PolyLine::must_new(vec![pt, pt2]).make_polygons(thickness)
 }
 
-fn zoomed_color_car(input: &DrawCarInput, cs: &ColorScheme) -> Color {
+pub(crate) fn zoomed_color_car(input: &DrawCarInput, cs: &ColorScheme) -> Color {
     if input.id.1 == VehicleType::Bus {
         cs.bus_body
     } else if input.id.1 == VehicleType::Train {
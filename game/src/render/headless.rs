@@ -0,0 +1,146 @@
+use crate::colors::ColorScheme;
+use crate::render::area::DrawArea;
+use crate::render::building::DrawBuilding;
+use crate::render::car::{zoomed_color_car, CAR_WIDTH};
+use crate::render::intersection::DrawIntersection;
+use crate::render::lane::DrawLane;
+use crate::render::parking_lot::DrawParkingLot;
+use crate::render::pedestrian::DrawPedestrian;
+use crate::render::road::DrawRoad;
+use aabb_quadtree::QuadTree;
+use abstutil::Timer;
+use ezgui::GeomBatch;
+use geom::{Bounds, Distance};
+use map_model::Map;
+use sim::{GetDrawAgents, VehicleType};
+use std::collections::BTreeMap;
+
+// The same ranks as BackgroundTiles, with agents on top of everything
+const BUILDING_RANK: usize = 5;
+const AGENT_RANK: usize = BUILDING_RANK + 2;
+
+// Everything static in the map, plus agents from a savestate, drawn on the CPU instead of through
+// a window. SVG icons and labels need a Prerender, so they're left out.
+pub struct HeadlessMap {
+    // In the order to draw them
+    objects: Vec<(Bounds, GeomBatch)>,
+    quadtree: QuadTree<usize>,
+}
+
+impl HeadlessMap {
+    pub fn new(
+        map: &Map,
+        source: &dyn GetDrawAgents,
+        cs: &ColorScheme,
+        timer: &mut Timer,
+    ) -> HeadlessMap {
+        // Keyed by (zorder, rank), like BackgroundTiles
+        let mut layers: BTreeMap<(isize, usize), Vec<GeomBatch>> = BTreeMap::new();
+        let mut add = |zorder: isize, rank: usize, batch: GeomBatch| {
+            layers
+                .entry((zorder, rank))
+                .or_insert_with(Vec::new)
+                .push(batch);
+        };
+
+        timer.start("render map geometry");
+        add(
+            std::isize::MIN,
+            0,
+            GeomBatch::from(vec![(
+                cs.map_background,
+                map.get_boundary_polygon().clone(),
+            )]),
+        );
+        for a in map.all_areas() {
+            add(
+                0,
+                0,
+                GeomBatch::from(vec![(DrawArea::color(a.area_type, cs), a.polygon.clone())]),
+            );
+        }
+        for pl in map.all_parking_lots() {
+            add(0, 1, DrawParkingLot::render(pl, cs));
+        }
+        for l in map.all_lanes() {
+            add(
+                map.get_r(l.parent).zorder,
+                2,
+                DrawLane::render_geometry(l, map, cs),
+            );
+        }
+        for r in map.all_roads() {
+            add(r.zorder, 3, DrawRoad::render(r, map, cs));
+        }
+        for i in map.all_intersections() {
+            add(
+                i.get_zorder(map),
+                4,
+                DrawIntersection::render_geometry(i, map, cs),
+            );
+        }
+        for b in map.all_buildings() {
+            let mut bldg = GeomBatch::new();
+            let mut paths = GeomBatch::new();
+            let mut outlines = GeomBatch::new();
+            DrawBuilding::render_geometry(b, cs, &mut bldg, &mut paths, &mut outlines);
+            bldg.append(outlines);
+            add(0, BUILDING_RANK, paths);
+            add(0, BUILDING_RANK + 1, bldg);
+        }
+        timer.stop("render map geometry");
+
+        for input in source.get_all_draw_cars(map) {
+            let width = if input.id.1 == VehicleType::Bike {
+                Distance::meters(0.4)
+            } else {
+                CAR_WIDTH
+            };
+            add(
+                std::isize::MAX,
+                AGENT_RANK,
+                GeomBatch::from(vec![(
+                    zoomed_color_car(&input, cs),
+                    input.body.make_polygons(width),
+                )]),
+            );
+        }
+        let step_count = source.step_count();
+        for input in source.get_all_draw_peds(map) {
+            let mut batch = GeomBatch::new();
+            DrawPedestrian::geometry(&mut batch, cs, &input, step_count);
+            add(std::isize::MAX, AGENT_RANK, batch);
+        }
+
+        let mut objects = Vec::new();
+        let mut quadtree = QuadTree::default(map.get_bounds().as_bbox());
+        for (_, batches) in layers {
+            for batch in batches {
+                if batch.is_empty() {
+                    continue;
+                }
+                let bounds = batch.get_bounds();
+                quadtree.insert_with_box(objects.len(), bounds.as_bbox());
+                objects.push((bounds, batch));
+            }
+        }
+        HeadlessMap { objects, quadtree }
+    }
+
+    // Draws everything overlapping the bounds into the image, stretching the bounds over the whole
+    // image. Returns false if there was nothing to draw.
+    pub fn draw(&self, bounds: &Bounds, img: &mut image::RgbaImage) -> bool {
+        let mut indices: Vec<usize> = self
+            .quadtree
+            .query(bounds.as_bbox())
+            .into_iter()
+            .map(|(idx, _, _)| *idx)
+            .collect();
+        // The quadtree doesn't preserve drawing order
+        indices.sort();
+        for idx in &indices {
+            self.objects[*idx].1.rasterize(bounds, img);
+        }
+        !indices.is_empty()
+    }
+}
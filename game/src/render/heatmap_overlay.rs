@@ -0,0 +1,167 @@
+use crate::app::App;
+use crate::helpers::ID;
+use abstutil::Counter;
+use ezgui::{Drawable, GeomBatch, GfxCtx};
+use geom::{Duration, Time};
+use map_model::{RoadID, Traversable};
+use sim::{GetDrawAgents, VehicleType};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+// Some number per lane or intersection, like the current delay
+pub trait HeatmapSource {
+    fn name(&self) -> &'static str;
+    // Only lanes and intersections. Anything missing isn't colored.
+    fn values(&self, app: &App) -> Vec<(ID, f64)>;
+}
+
+// Colors lanes and intersections on a gradient from zero to the largest value, on top of the map.
+// Like AgentCache, the values are only recalculated when the sim time changes and something needs
+// to be drawn.
+pub struct HeatmapOverlay {
+    source: Box<dyn HeatmapSource>,
+    // Time, unzoomed, zoomed. Unzoomed colors whole roads by their worst lane.
+    cache: RefCell<Option<(Time, Drawable, Drawable)>>,
+}
+
+impl HeatmapOverlay {
+    pub fn new(source: Box<dyn HeatmapSource>) -> HeatmapOverlay {
+        HeatmapOverlay {
+            source,
+            cache: RefCell::new(None),
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, app: &App, unzoomed: bool) {
+        let now = app.primary.sim.time();
+        let mut cache = self.cache.borrow_mut();
+        if cache.as_ref().map(|(t, _, _)| *t != now).unwrap_or(true) {
+            let (batch_unzoomed, batch_zoomed) = self.recalculate(app);
+            *cache = Some((now, g.upload(batch_unzoomed), g.upload(batch_zoomed)));
+        }
+        let (_, draw_unzoomed, draw_zoomed) = cache.as_ref().unwrap();
+        if unzoomed {
+            g.redraw(draw_unzoomed);
+        } else {
+            g.redraw(draw_zoomed);
+        }
+    }
+
+    fn recalculate(&self, app: &App) -> (GeomBatch, GeomBatch) {
+        let map = &app.primary.map;
+        let values = self.source.values(app);
+        let max = values.iter().map(|(_, x)| *x).fold(0.0, f64::max);
+
+        let mut unzoomed = GeomBatch::new();
+        let mut zoomed = GeomBatch::new();
+        let mut per_road: BTreeMap<RoadID, f64> = BTreeMap::new();
+        for (id, value) in values {
+            if max == 0.0 || value <= 0.0 {
+                continue;
+            }
+            let color = app.cs.good_to_bad_red.eval((value / max).min(1.0));
+            match id {
+                ID::Lane(l) => {
+                    let lane = map.get_l(l);
+                    zoomed.push(
+                        color.alpha(0.6),
+                        lane.lane_center_pts.make_polygons(lane.width),
+                    );
+                    let worst = per_road.entry(lane.parent).or_insert(0.0);
+                    *worst = worst.max(value);
+                }
+                ID::Intersection(i) => {
+                    let polygon = map.get_i(i).polygon.clone();
+                    unzoomed.push(color, polygon.clone());
+                    zoomed.push(color.alpha(0.6), polygon);
+                }
+                _ => unreachable!(),
+            }
+        }
+        for (r, value) in per_road {
+            unzoomed.push(
+                app.cs.good_to_bad_red.eval((value / max).min(1.0)),
+                map.get_r(r).get_thick_polygon(map),
+            );
+        }
+        (unzoomed, zoomed)
+    }
+}
+
+pub fn all_heatmap_sources() -> Vec<Box<dyn HeatmapSource>> {
+    vec![
+        Box::new(CurrentDelay),
+        Box::new(Throughput),
+        Box::new(PollutionProxy),
+    ]
+}
+
+// How long the worst-off agent has been stuck, in minutes
+struct CurrentDelay;
+
+impl HeatmapSource for CurrentDelay {
+    fn name(&self) -> &'static str {
+        "current delay"
+    }
+    fn values(&self, app: &App) -> Vec<(ID, f64)> {
+        let map = &app.primary.map;
+        let (per_road, per_intersection) = app.primary.sim.worst_delay(map);
+        let mut values = Vec::new();
+        for (r, d) in per_road {
+            for l in map.get_r(r).all_lanes() {
+                values.push((ID::Lane(l), d / Duration::minutes(1)));
+            }
+        }
+        for (i, d) in per_intersection {
+            values.push((ID::Intersection(i), d / Duration::minutes(1)));
+        }
+        values
+    }
+}
+
+// Everybody crossing since midnight. Lanes share the count of their road.
+struct Throughput;
+
+impl HeatmapSource for Throughput {
+    fn name(&self) -> &'static str {
+        "throughput"
+    }
+    fn values(&self, app: &App) -> Vec<(ID, f64)> {
+        let map = &app.primary.map;
+        let stats = app.primary.sim.get_analytics();
+        let mut values = Vec::new();
+        for (r, cnt) in stats.road_thruput.all_total_counts().consume() {
+            for l in map.get_r(r).all_lanes() {
+                values.push((ID::Lane(l), cnt as f64));
+            }
+        }
+        for (i, cnt) in stats.intersection_thruput.all_total_counts().consume() {
+            values.push((ID::Intersection(i), cnt as f64));
+        }
+        values
+    }
+}
+
+// Motor vehicles on each lane right now, moving or idling. Bikes don't count.
+struct PollutionProxy;
+
+impl HeatmapSource for PollutionProxy {
+    fn name(&self) -> &'static str {
+        "pollution proxy"
+    }
+    fn values(&self, app: &App) -> Vec<(ID, f64)> {
+        let mut cnt = Counter::new();
+        for car in app.primary.sim.get_all_draw_cars(&app.primary.map) {
+            if car.id.1 == VehicleType::Bike {
+                continue;
+            }
+            if let Traversable::Lane(l) = car.on {
+                cnt.inc(l);
+            }
+        }
+        cnt.consume()
+            .into_iter()
+            .map(|(l, n)| (ID::Lane(l), n as f64))
+            .collect()
+    }
+}
@@ -3,12 +3,14 @@ use crate::colors::ColorScheme;
 use crate::helpers::ID;
 use crate::options::TrafficSignalStyle;
 use crate::render::{
-    draw_signal_phase, DrawOptions, Renderable, CROSSWALK_LINE_THICKNESS, OUTLINE_THICKNESS,
+    draw_signal_phase, DrawOptions, Renderable, CROSSWALK_LINE_THICKNESS,
+    MIN_ZOOM_FOR_SMALL_DETAILS, OUTLINE_THICKNESS,
 };
 use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Line, Prerender, RewriteColor, Text};
 use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D, Time, EPSILON_DIST};
 use map_model::{
-    Intersection, IntersectionID, IntersectionType, Map, Road, RoadWithStopSign, Turn, TurnType,
+    Intersection, IntersectionID, IntersectionType, Lane, Map, Road, RoadWithStopSign, Turn,
+    TurnType,
 };
 use std::cell::RefCell;
 
@@ -18,7 +20,8 @@ pub struct DrawIntersection {
     zorder: isize,
 
     draw_default: Drawable,
-    pub draw_traffic_signal: RefCell<Option<(Time, Drawable)>>,
+    // The bool is whether the phase number was drawn
+    pub draw_traffic_signal: RefCell<Option<(Time, bool, Drawable)>>,
 }
 
 impl DrawIntersection {
@@ -28,6 +31,60 @@ impl DrawIntersection {
         cs: &ColorScheme,
         prerender: &Prerender,
     ) -> DrawIntersection {
+        DrawIntersection::from_batch(
+            i,
+            map,
+            DrawIntersection::render_geometry(i, map, cs),
+            prerender,
+        )
+    }
+
+    // When render_geometry() already happened elsewhere, like on a worker thread
+    pub fn from_batch(
+        i: &Intersection,
+        map: &Map,
+        mut batch: GeomBatch,
+        prerender: &Prerender,
+    ) -> DrawIntersection {
+        DrawIntersection::add_icons(i, map, &mut batch, prerender);
+        DrawIntersection {
+            id: i.id,
+            intersection_type: i.intersection_type,
+            zorder: i.get_zorder(map),
+            draw_default: prerender.upload(batch),
+            draw_traffic_signal: RefCell::new(None),
+        }
+    }
+
+    // Everything except for the traffic signal, which changes over time
+    pub fn render(
+        i: &Intersection,
+        map: &Map,
+        cs: &ColorScheme,
+        prerender: &Prerender,
+    ) -> GeomBatch {
+        let mut batch = DrawIntersection::render_geometry(i, map, cs);
+        DrawIntersection::add_icons(i, map, &mut batch, prerender);
+        batch
+    }
+
+    // SVGs need the Prerender, so they can't be drawn from worker threads
+    fn add_icons(i: &Intersection, map: &Map, batch: &mut GeomBatch, prerender: &Prerender) {
+        if i.intersection_type == IntersectionType::Construction {
+            // TODO Centering seems weird
+            let mut icon =
+                GeomBatch::mapspace_svg(prerender, "system/assets/map/under_construction.svg")
+                    .scale(0.08)
+                    .centered_on(i.polygon.center());
+            if i.get_zorder(map) < 0 {
+                icon = icon.color(RewriteColor::ChangeAlpha(0.5));
+            }
+            batch.append(icon);
+        }
+    }
+
+    // Everything from render() except for icons
+    pub fn render_geometry(i: &Intersection, map: &Map, cs: &ColorScheme) -> GeomBatch {
         // Order matters... main polygon first, then sidewalk corners.
         let mut default_geom = GeomBatch::new();
         default_geom.push(cs.normal_intersection, i.polygon.clone());
@@ -38,7 +95,20 @@ impl DrawIntersection {
             if turn.turn_type == TurnType::Crosswalk
                 && !turn.other_crosswalk_ids.iter().any(|id| *id < turn.id)
             {
-                make_crosswalk(&mut default_geom, turn, map, cs);
+                // Most crosswalks aren't tagged at all; assume those are marked.
+                match map
+                    .get_parent(turn.id.src)
+                    .crossing_type(i.id)
+                    .map(|x| x.as_str())
+                {
+                    Some("unmarked") | Some("no") => {}
+                    Some("zebra") => {
+                        make_zebra_crosswalk(&mut default_geom, turn, map, cs);
+                    }
+                    _ => {
+                        make_crosswalk(&mut default_geom, turn, map, cs);
+                    }
+                }
             }
         }
 
@@ -52,7 +122,8 @@ impl DrawIntersection {
                 default_geom.extend(cs.road_center_line, calculate_border_arrows(i, r, map));
             }
             IntersectionType::StopSign => {
-                for ss in map.get_stop_sign(i.id).roads.values() {
+                let stop_sign = map.get_stop_sign(i.id);
+                for ss in stop_sign.roads.values() {
                     if ss.must_stop {
                         if let Some((octagon, pole)) = DrawIntersection::stop_sign_geom(ss, map) {
                             default_geom.push(cs.stop_sign, octagon);
@@ -60,30 +131,48 @@ impl DrawIntersection {
                         }
                     }
                 }
+
+                // Approaches without a stop sign normally have priority and aren't marked. But if
+                // nobody has to stop, everybody yields.
+                let uncontrolled =
+                    i.roads.len() > 2 && stop_sign.roads.values().all(|ss| !ss.must_stop);
+                for l in &i.incoming_lanes {
+                    let lane = map.get_l(*l);
+                    if !has_stop_line(lane) {
+                        continue;
+                    }
+                    if stop_sign
+                        .roads
+                        .get(&lane.parent)
+                        .map(|ss| ss.must_stop)
+                        .unwrap_or(false)
+                    {
+                        if let Some(line) = make_stop_line(lane) {
+                            default_geom.push(cs.general_road_marking, line);
+                        }
+                    } else if uncontrolled {
+                        default_geom.extend(cs.general_road_marking, make_yield_teeth(lane));
+                    }
+                }
             }
-            IntersectionType::Construction => {
-                // TODO Centering seems weird
-                default_geom.append(
-                    GeomBatch::mapspace_svg(prerender, "system/assets/map/under_construction.svg")
-                        .scale(0.08)
-                        .centered_on(i.polygon.center()),
-                );
+            // The icon is added separately
+            IntersectionType::Construction => {}
+            IntersectionType::TrafficSignal => {
+                for l in &i.incoming_lanes {
+                    let lane = map.get_l(*l);
+                    if has_stop_line(lane) {
+                        if let Some(line) = make_stop_line(lane) {
+                            default_geom.push(cs.general_road_marking, line);
+                        }
+                    }
+                }
             }
-            IntersectionType::TrafficSignal => {}
         }
 
-        let zorder = i.get_zorder(map);
-        if zorder < 0 {
+        if i.get_zorder(map) < 0 {
             default_geom = default_geom.color(RewriteColor::ChangeAlpha(0.5));
         }
-
-        DrawIntersection {
-            id: i.id,
-            intersection_type: i.intersection_type,
-            zorder,
-            draw_default: prerender.upload(default_geom),
-            draw_traffic_signal: RefCell::new(None),
-        }
+        default_geom
     }
 
     // Returns the (octagon, pole) if there's room to draw it.
@@ -125,15 +214,20 @@ impl Renderable for DrawIntersection {
 
     fn draw(&self, g: &mut GfxCtx, app: &App, opts: &DrawOptions) {
         g.redraw(&self.draw_default);
+        self.draw_dynamic(g, app, opts);
+    }
 
+    fn draw_dynamic(&self, g: &mut GfxCtx, app: &App, opts: &DrawOptions) {
         if self.intersection_type == IntersectionType::TrafficSignal
             && !opts.suppress_traffic_signal_details.contains(&self.id)
         {
             let signal = app.primary.map.get_traffic_signal(self.id);
+            let show_number = g.canvas.cam_zoom >= MIN_ZOOM_FOR_SMALL_DETAILS
+                && app.opts.traffic_signal_style != TrafficSignalStyle::BAP;
             let mut maybe_redraw = self.draw_traffic_signal.borrow_mut();
             let recalc = maybe_redraw
                 .as_ref()
-                .map(|(t, _)| *t != app.primary.sim.time())
+                .map(|(t, n, _)| *t != app.primary.sim.time() || *n != show_number)
                 .unwrap_or(true);
             if recalc {
                 let (idx, remaining) = app.primary.sim.current_phase_and_remaining_time(self.id);
@@ -147,7 +241,7 @@ impl Renderable for DrawIntersection {
                     app,
                     app.opts.traffic_signal_style.clone(),
                 );
-                if app.opts.traffic_signal_style != TrafficSignalStyle::BAP {
+                if show_number {
                     batch.append(
                         Text::from(Line(format!("{}", idx + 1)))
                             .render_to_batch(g.prerender)
@@ -155,9 +249,13 @@ impl Renderable for DrawIntersection {
                             .centered_on(app.primary.map.get_i(self.id).polygon.center()),
                     );
                 }
-                *maybe_redraw = Some((app.primary.sim.time(), g.prerender.upload(batch)));
+                *maybe_redraw = Some((
+                    app.primary.sim.time(),
+                    show_number,
+                    g.prerender.upload(batch),
+                ));
             }
-            let (_, batch) = maybe_redraw.as_ref().unwrap();
+            let (_, _, batch) = maybe_redraw.as_ref().unwrap();
             g.redraw(batch);
         }
     }
@@ -347,6 +445,87 @@ pub fn make_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorS
     }
 }
 
+// Wide stripes, without the double lines
+fn make_zebra_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorScheme) {
+    let width = map.get_l(turn.id.src).width;
+    let stripe = Distance::meters(0.5);
+    let pts = turn.geom.points();
+    if pts.len() < 3 {
+        return;
+    }
+    let line = match Line::new(pts[1], pts[2]) {
+        Some(l) => l,
+        None => {
+            return;
+        }
+    };
+
+    // Same boundaries as make_crosswalk
+    let available_length = line.length() - (width * 2.0);
+    if available_length <= stripe {
+        return;
+    }
+    let num_stripes = ((available_length + stripe) / (stripe * 2.0)).floor() as usize;
+    let mut dist_along = width
+        + (available_length - stripe * (2.0 * (num_stripes as f64) - 1.0)) / 2.0
+        + stripe / 2.0;
+    for _ in 0..num_stripes {
+        if let Some(pt1) = line.dist_along(dist_along) {
+            let pt2 = pt1.project_away(Distance::meters(1.0), turn.angle());
+            batch.push(
+                cs.general_road_marking,
+                perp_line(Line::must_new(pt1, pt2), width).make_polygons(stripe),
+            );
+        }
+        dist_along += stripe * 2.0;
+    }
+}
+
+// Sidewalks, parking, and light rail don't get stop lines
+fn has_stop_line(lane: &Lane) -> bool {
+    lane.is_driving() || lane.is_bus() || lane.is_biking()
+}
+
+// Just before the intersection, across the entire lane
+fn make_stop_line(lane: &Lane) -> Option<Polygon> {
+    let back = Distance::meters(1.0);
+    if lane.length() <= back * 2.0 {
+        return None;
+    }
+    let (pt, angle) = lane.lane_center_pts.must_dist_along(lane.length() - back);
+    let line = Line::new(
+        pt.project_away(lane.width / 2.0, angle.rotate_degs(90.0)),
+        pt.project_away(lane.width / 2.0, angle.rotate_degs(-90.0)),
+    )?;
+    Some(line.make_polygons(Distance::meters(0.4)))
+}
+
+// A row of triangles just before the intersection, pointing at oncoming traffic
+fn make_yield_teeth(lane: &Lane) -> Vec<Polygon> {
+    let back = Distance::meters(1.0);
+    let tooth_width = Distance::meters(0.75);
+    let tooth_length = Distance::meters(0.9);
+    if lane.length() <= back + tooth_length * 2.0 {
+        return Vec::new();
+    }
+    let (pt, angle) = lane.lane_center_pts.must_dist_along(lane.length() - back);
+    let num_teeth = (lane.width / tooth_width).floor() as usize;
+    let start = pt.project_away(
+        tooth_width * (num_teeth as f64) / 2.0,
+        angle.rotate_degs(-90.0),
+    );
+    (0..num_teeth)
+        .map(|idx| {
+            let base1 = start.project_away(tooth_width * (idx as f64), angle.rotate_degs(90.0));
+            let base2 = base1.project_away(tooth_width * 0.8, angle.rotate_degs(90.0));
+            let tip = base1
+                .project_away(tooth_width * 0.4, angle.rotate_degs(90.0))
+                .project_away(tooth_length, angle.opposite());
+            Polygon::new(&vec![base1, base2, tip])
+        })
+        .collect()
+}
+
 fn make_rainbow_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map) -> bool {
     // TODO The crosswalks aren't tagged in OSM yet. Manually hardcoding some now.
     let node = map.get_i(turn.id.parent).orig_id.osm_node_id;
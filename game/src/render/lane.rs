@@ -1,7 +1,8 @@
 use crate::app::App;
+use crate::colors::ColorScheme;
 use crate::helpers::ID;
 use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
-use ezgui::{Drawable, GeomBatch, GfxCtx, RewriteColor};
+use ezgui::{Drawable, GeomBatch, GfxCtx, Prerender, RewriteColor};
 use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D};
 use map_model::{Lane, LaneID, LaneType, Map, Road, TurnType, PARKING_SPOT_LENGTH};
 use std::cell::RefCell;
@@ -28,61 +29,69 @@ impl DrawLane {
         *self.draw_default.borrow_mut() = None;
     }
 
-    fn render(&self, g: &mut GfxCtx, app: &App) -> Drawable {
+    pub fn render(&self, prerender: &Prerender, app: &App) -> GeomBatch {
         let map = &app.primary.map;
         let lane = map.get_l(self.id);
+        let mut draw = DrawLane::render_geometry(lane, map, &app.cs);
+        let mut icons = DrawLane::render_icons(lane, prerender);
+        if self.zorder < 0 {
+            icons = icons.color(RewriteColor::ChangeAlpha(0.5));
+        }
+        draw.append(icons);
+        draw
+    }
+
+    // Everything from render() except for icons. SVGs need the Prerender, so this can also be used
+    // without a window.
+    pub fn render_geometry(lane: &Lane, map: &Map, cs: &ColorScheme) -> GeomBatch {
         let road = map.get_r(lane.parent);
+        let polygon = lane.lane_center_pts.make_polygons(lane.width);
 
         let mut draw = GeomBatch::new();
         if !lane.is_light_rail() {
             draw.push(
                 match lane.lane_type {
-                    LaneType::Driving => app.cs.driving_lane,
-                    LaneType::Bus => app.cs.bus_lane,
-                    LaneType::Parking => app.cs.parking_lane,
-                    LaneType::Sidewalk => app.cs.sidewalk,
-                    LaneType::Biking => app.cs.bike_lane,
-                    LaneType::SharedLeftTurn => app.cs.driving_lane,
-                    LaneType::Construction => app.cs.parking_lane,
+                    LaneType::Driving if road.is_highway() => cs.highway_lane,
+                    LaneType::Driving => cs.driving_lane,
+                    LaneType::Bus => cs.bus_lane,
+                    LaneType::Parking => cs.parking_lane,
+                    LaneType::Sidewalk => cs.sidewalk,
+                    LaneType::Biking => cs.bike_lane,
+                    LaneType::SharedLeftTurn => cs.driving_lane,
+                    LaneType::Construction => cs.parking_lane,
                     LaneType::LightRail => unreachable!(),
                 },
-                self.polygon.clone(),
+                polygon.clone(),
             );
         }
         match lane.lane_type {
             LaneType::Sidewalk => {
-                draw.extend(app.cs.sidewalk_lines, calculate_sidewalk_lines(lane));
+                draw.extend(cs.sidewalk_lines, calculate_sidewalk_lines(lane));
             }
             LaneType::Parking => {
-                draw.extend(
-                    app.cs.general_road_marking,
-                    calculate_parking_lines(map, lane),
-                );
+                draw.extend(cs.general_road_marking, calculate_parking_lines(map, lane));
             }
             LaneType::Driving | LaneType::Bus => {
                 draw.extend(
-                    app.cs.general_road_marking,
+                    cs.general_road_marking,
                     calculate_driving_lines(map, lane, road),
                 );
+                draw.extend(cs.general_road_marking, calculate_turn_markings(map, lane));
                 draw.extend(
-                    app.cs.general_road_marking,
-                    calculate_turn_markings(map, lane),
-                );
-                draw.extend(
-                    app.cs.general_road_marking,
+                    cs.general_road_marking,
                     calculate_one_way_markings(lane, road),
                 );
             }
             LaneType::Biking => {}
             LaneType::SharedLeftTurn => {
                 draw.push(
-                    app.cs.road_center_line,
+                    cs.road_center_line,
                     lane.lane_center_pts
                         .must_shift_right(lane.width / 2.0)
                         .make_polygons(Distance::meters(0.25)),
                 );
                 draw.push(
-                    app.cs.road_center_line,
+                    cs.road_center_line,
                     lane.lane_center_pts
                         .must_shift_left(lane.width / 2.0)
                         .make_polygons(Distance::meters(0.25)),
@@ -92,13 +101,13 @@ impl DrawLane {
             LaneType::LightRail => {
                 let track_width = lane.width / 4.0;
                 draw.push(
-                    app.cs.light_rail_track,
+                    cs.light_rail_track,
                     lane.lane_center_pts
                         .must_shift_right((lane.width - track_width) / 2.5)
                         .make_polygons(track_width),
                 );
                 draw.push(
-                    app.cs.light_rail_track,
+                    cs.light_rail_track,
                     lane.lane_center_pts
                         .must_shift_left((lane.width - track_width) / 2.5)
                         .make_polygons(track_width),
@@ -112,7 +121,7 @@ impl DrawLane {
                     // Reuse perp_line. Project away an arbitrary amount
                     let pt2 = pt.project_away(Distance::meters(1.0), angle);
                     draw.push(
-                        app.cs.light_rail_track,
+                        cs.light_rail_track,
                         perp_line(Line::must_new(pt, pt2), lane.width).make_polygons(track_width),
                     );
                     dist_along += tile_every;
@@ -120,6 +129,19 @@ impl DrawLane {
             }
         }
 
+        if road.is_private() {
+            draw.push(cs.private_road.alpha(0.5), polygon);
+        }
+
+        if road.zorder < 0 {
+            draw = draw.color(RewriteColor::ChangeAlpha(0.5));
+        }
+
+        draw
+    }
+
+    fn render_icons(lane: &Lane, prerender: &Prerender) -> GeomBatch {
+        let mut draw = GeomBatch::new();
         if lane.is_bus() || lane.is_biking() || lane.lane_type == LaneType::Construction {
             let buffer = Distance::meters(2.0);
             let btwn = Distance::meters(30.0);
@@ -130,14 +152,14 @@ impl DrawLane {
                 let (pt, angle) = lane.lane_center_pts.must_dist_along(dist);
                 if lane.is_bus() {
                     draw.append(
-                        GeomBatch::mapspace_svg(g.prerender, "system/assets/map/bus_only.svg")
+                        GeomBatch::mapspace_svg(prerender, "system/assets/map/bus_only.svg")
                             .scale(0.06)
                             .centered_on(pt)
                             .rotate(angle.shortest_rotation_towards(Angle::new_degs(-90.0))),
                     );
                 } else if lane.is_biking() {
                     draw.append(
-                        GeomBatch::mapspace_svg(g.prerender, "system/assets/meters/bike.svg")
+                        GeomBatch::mapspace_svg(prerender, "system/assets/meters/bike.svg")
                             .scale(0.06)
                             .centered_on(pt)
                             .rotate(angle.shortest_rotation_towards(Angle::new_degs(-90.0))),
@@ -146,7 +168,7 @@ impl DrawLane {
                     // TODO Still not quite centered right, but close enough
                     draw.append(
                         GeomBatch::mapspace_svg(
-                            g.prerender,
+                            prerender,
                             "system/assets/map/under_construction.svg",
                         )
                         .scale(0.05)
@@ -160,16 +182,7 @@ impl DrawLane {
                 dist += btwn;
             }
         }
-
-        if road.is_private() {
-            draw.push(app.cs.private_road.alpha(0.5), self.polygon.clone());
-        }
-
-        if self.zorder < 0 {
-            draw = draw.color(RewriteColor::ChangeAlpha(0.5));
-        }
-
-        g.upload(draw)
+        draw
     }
 }
 
@@ -183,7 +196,8 @@ impl Renderable for DrawLane {
         // exhaustively see every lane during a single session
         let mut draw = self.draw_default.borrow_mut();
         if draw.is_none() {
-            *draw = Some(self.render(g, app));
+            let batch = self.render(g.prerender, app);
+            *draw = Some(g.upload(batch));
         }
         g.redraw(draw.as_ref().unwrap());
     }
@@ -1,25 +1,30 @@
 use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
+use crate::render::area::{RIPPLE_PHASES, RIPPLE_PHASE_DURATION};
 use crate::render::building::DrawBuilding;
 use crate::render::bus_stop::DrawBusStop;
 use crate::render::intersection::DrawIntersection;
 use crate::render::lane::DrawLane;
 use crate::render::parking_lot::DrawParkingLot;
 use crate::render::road::DrawRoad;
-use crate::render::{draw_vehicle, DrawArea, DrawPedCrowd, DrawPedestrian, Renderable};
-use aabb_quadtree::QuadTree;
+use crate::render::{
+    draw_vehicle, BackgroundTiles, DrawArea, DrawBusRoute, DrawPedCrowd, DrawPedestrian,
+    HeatmapOverlay, Renderable,
+};
+use aabb_quadtree::{ItemId, QuadTree};
 use abstutil::Timer;
-use ezgui::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Prerender};
-use geom::{Bounds, Circle, Distance, Polygon, Pt2D, Time};
+use ezgui::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Instance, Prerender, UpdateType};
+use geom::{Angle, Bounds, Circle, Distance, GPSBounds, PolyLine, Polygon, Pt2D, Time};
+use instant::Instant;
 use map_model::{
-    AreaID, BuildingID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, RoadID, Traversable,
-    NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
+    AreaID, AreaType, Building, BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map,
+    ParkingLotID, RoadID, Traversable, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
 };
-use sim::{GetDrawAgents, UnzoomedAgent, VehicleType};
+use sim::{AgentID, DrawAgentsOn, GetDrawAgents, UnzoomedAgent, VehicleType};
 use std::borrow::Borrow;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 pub struct DrawMap {
     pub roads: Vec<DrawRoad>,
@@ -28,29 +33,76 @@ pub struct DrawMap {
     pub buildings: Vec<DrawBuilding>,
     pub parking_lots: Vec<DrawParkingLot>,
     pub bus_stops: HashMap<BusStopID, DrawBusStop>,
+    // Empty until render_bus_routes is called
+    pub bus_routes: HashMap<BusRouteID, DrawBusRoute>,
     pub areas: Vec<DrawArea>,
 
     // TODO Move?
     pub agents: RefCell<AgentCache>,
+    pub heatmap: Option<HeatmapOverlay>,
 
     pub boundary_polygon: Drawable,
     pub draw_all_unzoomed_roads_and_intersections: Drawable,
+    // Used instead of the above when zoomed very far out
+    pub draw_all_road_centerlines: Drawable,
     pub draw_all_buildings: Drawable,
     pub draw_all_building_paths: Drawable,
     pub draw_all_building_outlines: Drawable,
+    pub draw_all_extruded_buildings: Drawable,
     pub draw_all_unzoomed_parking_lots: Drawable,
     pub draw_all_areas: Drawable,
+    draw_grass_texture: Drawable,
+    // One per phase of the animation
+    draw_water_ripples: Vec<Drawable>,
+    water_bounds: Vec<Bounds>,
+    created: Instant,
+    pub background: BackgroundTiles,
 
     quadtree: QuadTree<ID>,
+    // Roads, lanes, and intersections, to move them in the quadtree when edits change their shape
+    quadtree_ids: HashMap<ID, ItemId>,
+}
+
+// The slow part of building a DrawMap. It doesn't need the GPU, so it can happen off the UI thread.
+pub struct MapGeometry {
+    roads: Vec<GeomBatch>,
+    intersections: Vec<GeomBatch>,
 }
 
 impl DrawMap {
     pub fn new(map: &Map, cs: &ColorScheme, ctx: &EventCtx, timer: &mut Timer) -> DrawMap {
+        let geometry = DrawMap::calculate_geometry(map, cs, timer);
+        DrawMap::from_geometry(map, cs, geometry, ctx, timer)
+    }
+
+    // Calculating the geometry for huge maps is slow, but each road and intersection is
+    // independent, so do that on worker threads.
+    pub fn calculate_geometry(map: &Map, cs: &ColorScheme, timer: &mut Timer) -> MapGeometry {
+        MapGeometry {
+            roads: timer.parallelize("render roads", map.all_roads().iter().collect(), |r| {
+                DrawRoad::render(r, map, cs)
+            }),
+            intersections: timer.parallelize(
+                "render intersections",
+                map.all_intersections().iter().collect(),
+                |i| DrawIntersection::render_geometry(i, map, cs),
+            ),
+        }
+    }
+
+    // Uploads everything. This has to happen on the UI thread.
+    pub fn from_geometry(
+        map: &Map,
+        cs: &ColorScheme,
+        geometry: MapGeometry,
+        ctx: &EventCtx,
+        timer: &mut Timer,
+    ) -> DrawMap {
         let mut roads: Vec<DrawRoad> = Vec::new();
         timer.start_iter("make DrawRoads", map.all_roads().len());
-        for r in map.all_roads() {
+        for (r, batch) in map.all_roads().iter().zip(geometry.roads) {
             timer.next();
-            roads.push(DrawRoad::new(r, map, cs, ctx.prerender));
+            roads.push(DrawRoad::from_batch(r, batch, ctx.prerender));
         }
 
         let mut lanes: Vec<DrawLane> = Vec::new();
@@ -62,13 +114,14 @@ impl DrawMap {
 
         let mut intersections: Vec<DrawIntersection> = Vec::new();
         timer.start_iter("make DrawIntersections", map.all_intersections().len());
-        for i in map.all_intersections() {
+        for (i, batch) in map.all_intersections().iter().zip(geometry.intersections) {
             timer.next();
-            intersections.push(DrawIntersection::new(i, map, cs, ctx.prerender));
+            intersections.push(DrawIntersection::from_batch(i, map, batch, ctx.prerender));
         }
 
         let draw_all_unzoomed_roads_and_intersections =
             DrawMap::regenerate_unzoomed_layer(map, cs, ctx, timer);
+        let draw_all_road_centerlines = DrawMap::regenerate_road_centerlines(map, cs, ctx, timer);
 
         let mut buildings: Vec<DrawBuilding> = Vec::new();
         let mut all_buildings = GeomBatch::new();
@@ -92,6 +145,22 @@ impl DrawMap {
         let draw_all_building_outlines = all_building_outlines.upload(ctx);
         timer.stop("upload all buildings");
 
+        timer.start("extrude buildings");
+        let mut north_to_south: Vec<&Building> = map.all_buildings().iter().collect();
+        north_to_south.sort_by(|b1, b2| {
+            b1.polygon
+                .get_bounds()
+                .max_y
+                .partial_cmp(&b2.polygon.get_bounds().max_y)
+                .unwrap()
+        });
+        let mut extruded_buildings = GeomBatch::new();
+        for b in north_to_south {
+            DrawBuilding::extrude(b, cs, &mut extruded_buildings);
+        }
+        let draw_all_extruded_buildings = extruded_buildings.upload(ctx);
+        timer.stop("extrude buildings");
+
         timer.start("make DrawParkingLot");
         let mut parking_lots: Vec<DrawParkingLot> = Vec::new();
         let mut all_unzoomed_parking_lots = GeomBatch::new();
@@ -124,6 +193,23 @@ impl DrawMap {
         let draw_all_areas = all_areas.upload(ctx);
         timer.stop("upload all areas");
 
+        timer.start("texture areas");
+        let mut grass = GeomBatch::new();
+        for a in map.all_areas() {
+            DrawArea::grass_texture(a, cs, &mut grass);
+        }
+        let draw_grass_texture = grass.upload(ctx);
+        let draw_water_ripples = (0..RIPPLE_PHASES)
+            .map(|phase| {
+                let mut batch = GeomBatch::new();
+                for a in map.all_areas() {
+                    DrawArea::water_ripples(a, cs, phase, &mut batch);
+                }
+                batch.upload(ctx)
+            })
+            .collect();
+        timer.stop("texture areas");
+
         let boundary_polygon = ctx.prerender.upload(GeomBatch::from(vec![(
             cs.map_background,
             map.get_boundary_polygon().clone(),
@@ -131,25 +217,46 @@ impl DrawMap {
 
         timer.start("create quadtree");
         let mut quadtree = QuadTree::default(map.get_bounds().as_bbox());
+        let mut quadtree_ids = HashMap::new();
+        // Everything that gets merged into BackgroundTiles
+        let mut static_objects: Vec<(ID, Bounds)> = Vec::new();
         // TODO use iter chain if everything was boxed as a renderable...
         for obj in &roads {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            let bounds = obj.get_outline(map).get_bounds();
+            if let Some(item) = quadtree.insert_with_box(obj.get_id(), bounds.as_bbox()) {
+                quadtree_ids.insert(obj.get_id(), item);
+            }
+            static_objects.push((obj.get_id(), bounds));
         }
         for obj in &lanes {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            let bounds = obj.get_outline(map).get_bounds();
+            if let Some(item) = quadtree.insert_with_box(obj.get_id(), bounds.as_bbox()) {
+                quadtree_ids.insert(obj.get_id(), item);
+            }
+            static_objects.push((obj.get_id(), bounds));
         }
         for obj in &intersections {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            let bounds = obj.get_outline(map).get_bounds();
+            if let Some(item) = quadtree.insert_with_box(obj.get_id(), bounds.as_bbox()) {
+                quadtree_ids.insert(obj.get_id(), item);
+            }
+            static_objects.push((obj.get_id(), bounds));
         }
         for obj in &buildings {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            let bounds = obj.get_outline(map).get_bounds();
+            quadtree.insert_with_box(obj.get_id(), bounds.as_bbox());
+            static_objects.push((obj.get_id(), bounds));
         }
         for obj in &parking_lots {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            let bounds = obj.get_outline(map).get_bounds();
+            quadtree.insert_with_box(obj.get_id(), bounds.as_bbox());
+            static_objects.push((obj.get_id(), bounds));
         }
-        // Don't put BusStops in the quadtree
+        // Don't put BusStops in the quadtree. BusRoutes are added once they're rendered.
         for obj in &areas {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            let bounds = obj.get_outline(map).get_bounds();
+            quadtree.insert_with_box(obj.get_id(), bounds.as_bbox());
+            static_objects.push((obj.get_id(), bounds));
         }
         timer.stop("create quadtree");
 
@@ -165,22 +272,39 @@ impl DrawMap {
             buildings,
             parking_lots,
             bus_stops,
+            bus_routes: HashMap::new(),
             areas,
             boundary_polygon,
             draw_all_unzoomed_roads_and_intersections,
+            draw_all_road_centerlines,
             draw_all_buildings,
             draw_all_building_paths,
             draw_all_building_outlines,
+            draw_all_extruded_buildings,
             draw_all_unzoomed_parking_lots,
             draw_all_areas,
+            draw_grass_texture,
+            draw_water_ripples,
+            water_bounds: map
+                .all_areas()
+                .iter()
+                .filter(|a| a.area_type == AreaType::Water)
+                .map(|a| a.polygon.get_bounds())
+                .collect(),
+            created: Instant::now(),
+            background: BackgroundTiles::new(static_objects),
 
             agents: RefCell::new(AgentCache {
                 time: None,
                 agents_per_on: HashMap::new(),
                 unzoomed: None,
+                density: None,
+                trails: None,
             }),
+            heatmap: None,
 
             quadtree,
+            quadtree_ids,
         }
     }
 
@@ -232,6 +356,69 @@ impl DrawMap {
         draw_all_unzoomed_roads_and_intersections
     }
 
+    // Each road is one thick line between the centers of its intersections, with most of its
+    // points dropped. Much cheaper to draw than the full unzoomed layer, and when zoomed out far
+    // enough to use it, nobody can see the difference.
+    pub fn regenerate_road_centerlines(
+        map: &Map,
+        cs: &ColorScheme,
+        ctx: &EventCtx,
+        timer: &mut Timer,
+    ) -> Drawable {
+        timer.start("generate simplified road centerlines");
+        let mut pieces: Vec<(isize, Polygon, Color)> = Vec::new();
+        for r in map.all_roads() {
+            let simple = r.center_pts.simplify(Distance::meters(5.0));
+            let mut pts = vec![map.get_i(r.src_i).polygon.center()];
+            pts.extend(simple.points().clone());
+            pts.push(map.get_i(r.dst_i).polygon.center());
+            let pl = PolyLine::deduping_new(pts).unwrap_or(simple);
+            pieces.push((
+                r.zorder,
+                pl.make_polygons(r.get_half_width(map) * 2.0),
+                if r.is_light_rail() {
+                    cs.light_rail_track
+                } else if r.is_private() {
+                    cs.private_road
+                } else {
+                    osm_rank_to_color(cs, r.get_rank())
+                },
+            ));
+        }
+        pieces.sort_by_key(|(z, _, _)| *z);
+        let mut batch = GeomBatch::new();
+        for (_, poly, color) in pieces {
+            batch.push(color, poly);
+        }
+        let draw = batch.upload(ctx);
+        timer.stop("generate simplified road centerlines");
+        draw
+    }
+
+    // Call right after drawing areas.
+    pub fn draw_area_textures(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw_grass_texture);
+        let phase = if g.is_screencap() {
+            0
+        } else {
+            (abstutil::elapsed_seconds(self.created) / RIPPLE_PHASE_DURATION.as_secs_f64()) as usize
+                % RIPPLE_PHASES
+        };
+        g.redraw(&self.draw_water_ripples[phase]);
+    }
+
+    // While any water is on screen, keep redrawing so the ripples move, even if nothing else is
+    // happening.
+    pub fn animate_water(&self, ctx: &mut EventCtx, min_zoom_for_detail: f64) {
+        if ctx.canvas.cam_zoom < min_zoom_for_detail {
+            return;
+        }
+        let screen = ctx.canvas.get_screen_bounds();
+        if self.water_bounds.iter().any(|b| b.overlaps(&screen)) {
+            ctx.request_update(UpdateType::Poll(RIPPLE_PHASE_DURATION));
+        }
+    }
+
     // The alt to these is implementing std::ops::Index, but that's way more verbose!
     pub fn get_r(&self, id: RoadID) -> &DrawRoad {
         &self.roads[id.0]
@@ -257,6 +444,46 @@ impl DrawMap {
         &self.bus_stops[&id]
     }
 
+    // Pathfinding every route is slow and only the debug layer shows them, so they're not rendered
+    // until that's turned on. Once rendered, they go in the quadtree like everything else.
+    pub fn render_bus_routes(&mut self, ctx: &mut EventCtx, map: &Map, cs: &ColorScheme) {
+        if !self.bus_routes.is_empty() {
+            return;
+        }
+        let route_polygons = ctx.loading_screen("render bus routes", |_, timer| {
+            timer.parallelize(
+                "render bus routes",
+                map.all_bus_routes().iter().collect(),
+                |r| DrawBusRoute::render(r, map),
+            )
+        });
+        for (r, polygon) in map.all_bus_routes().iter().zip(route_polygons) {
+            if let Some(polygon) = polygon {
+                let draw = DrawBusRoute::new(r.id, polygon, cs, ctx.prerender);
+                if let Some(item) = self
+                    .quadtree
+                    .insert_with_box(draw.get_id(), draw.bounds.as_bbox())
+                {
+                    self.quadtree_ids.insert(draw.get_id(), item);
+                }
+                self.bus_routes.insert(r.id, draw);
+            }
+        }
+    }
+
+    // Edits can change the path a route takes. If the routes were rendered, redo them.
+    pub fn rerender_bus_routes(&mut self, ctx: &mut EventCtx, map: &Map, cs: &ColorScheme) {
+        if self.bus_routes.is_empty() {
+            return;
+        }
+        for r in self.bus_routes.drain().map(|(r, _)| r).collect::<Vec<_>>() {
+            if let Some(item) = self.quadtree_ids.remove(&ID::BusRoute(r)) {
+                self.quadtree.remove(item);
+            }
+        }
+        self.render_bus_routes(ctx, map, cs);
+    }
+
     pub fn get_a(&self, id: AreaID) -> &DrawArea {
         &self.areas[id.0]
     }
@@ -305,18 +532,47 @@ impl DrawMap {
             ID::BusStop(id) => {
                 return Some(self.get_bs(id));
             }
+            ID::BusRoute(id) => {
+                return self.bus_routes.get(&id).map(|r| r as &dyn Renderable);
+            }
             ID::Area(id) => {
                 return Some(self.get_a(id));
             }
         };
 
-        agents.populate_if_needed(on, &app.primary.map, &app.primary.sim, &app.cs, prerender);
+        agents.populate_if_needed(
+            on,
+            &app.primary.map,
+            &app.primary.sim,
+            &app.cs,
+            prerender,
+            app.opts.agent_trails,
+        );
 
         // Why might this fail? Pedestrians merge into crowds, and crowds dissipate into
         // individuals
         agents.get(on).into_iter().find(|r| r.get_id() == id)
     }
 
+    // Call after replacing the drawn road, lane, or intersection, so the quadtree matches its
+    // outline and the background tiles containing it get re-rendered. Edits can't change the shape
+    // of anything yet, so this doesn't handle objects being added or removed.
+    pub fn update_quadtree(&mut self, id: ID, map: &Map) {
+        let bounds = match id {
+            ID::Road(r) => self.get_r(r).get_outline(map).get_bounds(),
+            ID::Lane(l) => self.get_l(l).get_outline(map).get_bounds(),
+            ID::Intersection(i) => self.get_i(i).get_outline(map).get_bounds(),
+            _ => unreachable!(),
+        };
+        if let Some(item) = self.quadtree_ids.remove(&id) {
+            self.quadtree.remove(item);
+        }
+        if let Some(item) = self.quadtree.insert_with_box(id.clone(), bounds.as_bbox()) {
+            self.quadtree_ids.insert(id.clone(), item);
+        }
+        self.background.invalidate(vec![(id, bounds)]);
+    }
+
     // Unsorted, unexpanded, raw result.
     pub fn get_matching_objects(&self, bounds: Bounds) -> Vec<ID> {
         let mut results: Vec<ID> = Vec::new();
@@ -325,14 +581,109 @@ impl DrawMap {
         }
         results
     }
+
+    // Lanes whose middle is inside the area
+    pub fn get_matching_lanes(&self, map: &Map, area: &Polygon) -> Vec<LaneID> {
+        let mut results = Vec::new();
+        for &(id, _, _) in &self.quadtree.query(area.get_bounds().as_bbox()) {
+            if let ID::Lane(l) = id {
+                if area.contains_pt(map.get_l(*l).lane_center_pts.middle()) {
+                    results.push(*l);
+                }
+            }
+        }
+        results
+    }
+
+    // For inspecting geometry problems in something like QGIS. Every polygon is written as its
+    // triangles, exactly as it's rendered. All coordinates, including the bounding boxes, are
+    // converted to GPS.
+    pub fn export_geojson(&self, map: &Map, path: String) -> Result<(), std::io::Error> {
+        let gps_bounds = map.get_gps_bounds();
+        let mut features = Vec::new();
+        for l in &self.lanes {
+            let lane = map.get_l(l.id);
+            features.push(geojson_feature(
+                gps_bounds,
+                "lane",
+                l.id.0,
+                &l.polygon,
+                l.get_zorder(),
+                format!("{:?}", lane.lane_type),
+            ));
+        }
+        for i in &self.intersections {
+            let intersection = map.get_i(i.id);
+            features.push(geojson_feature(
+                gps_bounds,
+                "intersection",
+                i.id.0,
+                &intersection.polygon,
+                i.get_zorder(),
+                format!("{:?}", intersection.intersection_type),
+            ));
+        }
+        for b in &self.buildings {
+            let bldg = map.get_b(b.id);
+            features.push(geojson_feature(
+                gps_bounds,
+                "building",
+                b.id.0,
+                &bldg.polygon,
+                b.get_zorder(),
+                format!("{:?}", bldg.bldg_type),
+            ));
+        }
+        for a in &self.areas {
+            let area = map.get_a(a.id);
+            features.push(geojson_feature(
+                gps_bounds,
+                "area",
+                a.id.0,
+                &area.polygon,
+                a.get_zorder(),
+                format!("{:?}", area.area_type),
+            ));
+        }
+        let geojson = geojson::GeoJson::from(geojson::FeatureCollection {
+            bbox: Some(gps_bbox(map.get_bounds(), gps_bounds)),
+            features,
+            foreign_members: None,
+        });
+        // abstutil::write_json insists on .json
+        std::fs::write(path, serde_json::to_string_pretty(&geojson)?)
+    }
+
+    // Only for debugging the spatial index. Returns every node of the quadtree, with its depth and
+    // the number of objects overlapping it.
+    pub fn quadtree_cells(&self) -> Vec<(Bounds, usize, usize)> {
+        let mut cells = Vec::new();
+        self.quadtree.inspect(|rect, depth, _| {
+            cells.push((Bounds::from_bbox(rect), depth));
+        });
+        cells
+            .into_iter()
+            .map(|(bounds, depth)| {
+                let count = self.quadtree.query(bounds.as_bbox()).len();
+                (bounds, depth, count)
+            })
+            .collect()
+    }
 }
 
+// How many recent positions to remember per agent
+const TRAIL_LENGTH: usize = 20;
+
 pub struct AgentCache {
     // This time applies to agents_per_on. unzoomed has its own possibly separate Time!
     time: Option<Time>,
     agents_per_on: HashMap<Traversable, Vec<Box<dyn Renderable>>>,
     // agent radius also matters
     unzoomed: Option<(Time, Option<Distance>, UnzoomedAgents, Drawable)>,
+    density: Option<(Time, UnzoomedAgents, Drawable)>,
+    // The recent positions of every agent drawn, oldest first, and the last time each was seen.
+    // None when trails are off.
+    trails: Option<HashMap<AgentID, (Time, VecDeque<Pt2D>)>>,
 }
 
 impl AgentCache {
@@ -350,33 +701,91 @@ impl AgentCache {
         source: &dyn GetDrawAgents,
         cs: &ColorScheme,
         prerender: &Prerender,
+        trails: bool,
     ) {
         let now = source.time();
         if Some(now) == self.time && self.agents_per_on.contains_key(&on) {
             return;
         }
-        let step_count = source.step_count();
+        self.set_time(now, trails);
+        let (peds, crowds) = source.get_draw_peds(on, map);
+        let agents = DrawAgentsOn {
+            cars: source.get_draw_cars(on, map),
+            peds,
+            crowds,
+        };
+        let list = render_agents(
+            agents,
+            source.step_count(),
+            now,
+            &mut self.trails,
+            map,
+            cs,
+            prerender,
+        );
+        self.agents_per_on.insert(on, list);
+    }
 
-        let mut list: Vec<Box<dyn Renderable>> = Vec::new();
-        for c in source.get_draw_cars(on, map).into_iter() {
-            list.push(draw_vehicle(c, map, prerender, cs));
+    // Fill in everything on screen at once, instead of asking about each Traversable separately.
+    pub fn populate_in(
+        &mut self,
+        bounds: &Bounds,
+        agents_on: &Vec<Traversable>,
+        map: &Map,
+        source: &dyn GetDrawAgents,
+        cs: &ColorScheme,
+        prerender: &Prerender,
+        trails: bool,
+    ) {
+        let now = source.time();
+        self.set_time(now, trails);
+        if agents_on
+            .iter()
+            .all(|on| self.agents_per_on.contains_key(on))
+        {
+            return;
         }
-        let (loners, crowds) = source.get_draw_peds(on, map);
-        for p in loners {
-            list.push(Box::new(DrawPedestrian::new(
-                p, step_count, map, prerender, cs,
-            )));
+
+        let step_count = source.step_count();
+        for (on, agents) in source.get_draw_agents_in(bounds, map) {
+            // Something already drawn this time step doesn't need to be redone.
+            if self.agents_per_on.contains_key(&on) {
+                continue;
+            }
+            let list = render_agents(
+                agents,
+                step_count,
+                now,
+                &mut self.trails,
+                map,
+                cs,
+                prerender,
+            );
+            self.agents_per_on.insert(on, list);
         }
-        for c in crowds {
-            list.push(Box::new(DrawPedCrowd::new(c, map, prerender, cs)));
+        // Anything on screen that the query didn't return is empty.
+        for on in agents_on {
+            self.agents_per_on.entry(*on).or_insert_with(Vec::new);
         }
+    }
 
-        if Some(now) != self.time {
-            self.agents_per_on.clear();
-            self.time = Some(now);
+    fn set_time(&mut self, now: Time, trails: bool) {
+        if !trails {
+            self.trails = None;
+        } else if self.trails.is_none() {
+            self.trails = Some(HashMap::new());
         }
-
-        self.agents_per_on.insert(on, list);
+        if Some(now) == self.time {
+            return;
+        }
+        // Forget about agents that weren't drawn last time, so trails don't jump across the map
+        // or pile up for agents that've finished.
+        if let Some(ref mut trails) = self.trails {
+            let prev = self.time;
+            trails.retain(|_, (t, _)| Some(*t) == prev);
+        }
+        self.agents_per_on.clear();
+        self.time = Some(now);
     }
 
     // TODO GetDrawAgents indirection added for time traveling, but that's been removed. Maybe
@@ -399,34 +808,33 @@ impl AgentCache {
             }
         }
 
-        let mut batch = GeomBatch::new();
-        // It's quite silly to produce triangles for the same circle over and over again. ;)
-        if let Some(r) = maybe_radius {
-            let circle = Circle::new(Pt2D::new(0.0, 0.0), r).to_polygon();
-            for agent in source.get_unzoomed_agents(map) {
-                if let Some(color) = color_agents.color(&agent) {
-                    batch.push(color, circle.translate(agent.pos.x(), agent.pos.y()));
-                }
-            }
-        } else {
-            // Lane thickness is a little hard to see, so double it. Most of the time, the circles
-            // don't leak out of the road too much.
-            let car_circle =
-                Circle::new(Pt2D::new(0.0, 0.0), 4.0 * NORMAL_LANE_THICKNESS).to_polygon();
-            let ped_circle =
-                Circle::new(Pt2D::new(0.0, 0.0), 4.0 * SIDEWALK_THICKNESS).to_polygon();
-            for agent in source.get_unzoomed_agents(map) {
-                if let Some(color) = color_agents.color(&agent) {
-                    if agent.vehicle_type.is_some() {
-                        batch.push(color, car_circle.translate(agent.pos.x(), agent.pos.y()));
-                    } else {
-                        batch.push(color, ped_circle.translate(agent.pos.x(), agent.pos.y()));
-                    }
-                }
+        // Every agent is the same circle, so just upload that once and move it around.
+        let circle = GeomBatch::from(vec![(
+            Color::WHITE,
+            Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(1.0)).to_polygon(),
+        )]);
+        let mut instances = Vec::new();
+        for agent in source.get_unzoomed_agents(map) {
+            if let Some(color) = color_agents.color(&agent) {
+                let radius = if let Some(r) = maybe_radius {
+                    r
+                } else if agent.vehicle_type.is_some() {
+                    // Lane thickness is a little hard to see, so double it. Most of the time, the
+                    // circles don't leak out of the road too much.
+                    4.0 * NORMAL_LANE_THICKNESS
+                } else {
+                    4.0 * SIDEWALK_THICKNESS
+                };
+                instances.push(Instance {
+                    pos: agent.pos,
+                    angle: Angle::ZERO,
+                    scale: radius.inner_meters(),
+                    color,
+                });
             }
         }
 
-        let draw = g.upload(batch);
+        let draw = g.upload_instanced(circle, instances);
         g.redraw(&draw);
         self.unzoomed = Some((now, maybe_radius, color_agents.clone(), draw));
 
@@ -434,7 +842,7 @@ impl AgentCache {
             let mut cnt = 0;
             for input in source.get_all_draw_cars(map) {
                 cnt += 1;
-                draw_vehicle(input, map, g.prerender, cs);
+                draw_vehicle(input, &[], map, g.prerender, cs);
             }
             println!(
                 "At {}, debugged {} cars",
@@ -444,6 +852,113 @@ impl AgentCache {
             // Pedestrians aren't the ones crashing
         }
     }
+
+    // When zoomed out even further, individual agents are just noise. Group them into a grid and
+    // draw one blob per cell, sized and colored by how many agents are there.
+    pub fn draw_agent_density(
+        &mut self,
+        source: &dyn GetDrawAgents,
+        map: &Map,
+        color_agents: &UnzoomedAgents,
+        g: &mut GfxCtx,
+        cs: &ColorScheme,
+    ) {
+        let now = source.time();
+        if let Some((time, ref orig_agents, ref draw)) = self.density {
+            if now == time && color_agents == orig_agents {
+                g.redraw(draw);
+                return;
+            }
+        }
+
+        let cell_size = Distance::meters(100.0);
+        // (sum of x, sum of y, count) per cell
+        let mut cells: HashMap<(i64, i64), (f64, f64, usize)> = HashMap::new();
+        for agent in source.get_unzoomed_agents(map) {
+            if color_agents.color(&agent).is_none() {
+                continue;
+            }
+            let key = (
+                (agent.pos.x() / cell_size.inner_meters()) as i64,
+                (agent.pos.y() / cell_size.inner_meters()) as i64,
+            );
+            let cell = cells.entry(key).or_insert((0.0, 0.0, 0));
+            cell.0 += agent.pos.x();
+            cell.1 += agent.pos.y();
+            cell.2 += 1;
+        }
+        let max = cells.values().map(|(_, _, cnt)| *cnt).max().unwrap_or(0);
+
+        let mut batch = GeomBatch::new();
+        for (_, (x, y, cnt)) in cells {
+            let pct = (cnt as f64) / (max as f64);
+            // Blobs are centered on the agents, not the cell, so the grid doesn't show.
+            batch.push(
+                cs.good_to_bad_red.eval(pct).alpha(0.8),
+                Circle::new(
+                    Pt2D::new(x / (cnt as f64), y / (cnt as f64)),
+                    cell_size * (0.2 + 0.5 * pct.sqrt()),
+                )
+                .to_polygon(),
+            );
+        }
+
+        let draw = g.upload(batch);
+        g.redraw(&draw);
+        self.density = Some((now, color_agents.clone(), draw));
+    }
+}
+
+fn render_agents(
+    agents: DrawAgentsOn,
+    step_count: usize,
+    now: Time,
+    trails: &mut Option<HashMap<AgentID, (Time, VecDeque<Pt2D>)>>,
+    map: &Map,
+    cs: &ColorScheme,
+    prerender: &Prerender,
+) -> Vec<Box<dyn Renderable>> {
+    let mut list: Vec<Box<dyn Renderable>> = Vec::new();
+    for c in agents.cars {
+        let trail = update_trail(trails, AgentID::Car(c.id), c.body.last_pt(), now);
+        list.push(draw_vehicle(c, &trail, map, prerender, cs));
+    }
+    for p in agents.peds {
+        let trail = update_trail(trails, AgentID::Pedestrian(p.id), p.pos, now);
+        list.push(Box::new(DrawPedestrian::new(
+            p, &trail, step_count, map, prerender, cs,
+        )));
+    }
+    for c in agents.crowds {
+        list.push(Box::new(DrawPedCrowd::new(c, map, prerender, cs)));
+    }
+    list
+}
+
+// Remember where an agent is now, and return everywhere it's recently been, oldest first.
+fn update_trail(
+    trails: &mut Option<HashMap<AgentID, (Time, VecDeque<Pt2D>)>>,
+    id: AgentID,
+    pt: Pt2D,
+    now: Time,
+) -> Vec<Pt2D> {
+    let trails = match trails {
+        Some(t) => t,
+        None => {
+            return Vec::new();
+        }
+    };
+    let (time, pts) = trails
+        .entry(id)
+        .or_insert_with(|| (now, VecDeque::with_capacity(TRAIL_LENGTH + 1)));
+    *time = now;
+    if pts.back() != Some(&pt) {
+        pts.push_back(pt);
+        if pts.len() > TRAIL_LENGTH {
+            pts.pop_front();
+        }
+    }
+    pts.iter().cloned().collect()
 }
 
 #[derive(PartialEq, Clone)]
@@ -482,7 +997,7 @@ impl UnzoomedAgents {
 
     fn color(&self, agent: &UnzoomedAgent) -> Option<Color> {
         match agent.vehicle_type {
-            Some(VehicleType::Car) => {
+            Some(VehicleType::Car) | Some(VehicleType::Truck) => {
                 if self.cars {
                     if agent.parking {
                         Some(self.parking_color)
@@ -527,3 +1042,53 @@ fn osm_rank_to_color(cs: &ColorScheme, rank: usize) -> Color {
         cs.unzoomed_residential
     }
 }
+
+fn geojson_feature(
+    gps_bounds: &GPSBounds,
+    obj_type: &str,
+    id: usize,
+    polygon: &Polygon,
+    zorder: isize,
+    subtype: String,
+) -> geojson::Feature {
+    let mut properties = serde_json::Map::new();
+    properties.insert("type".to_string(), obj_type.into());
+    properties.insert("id".to_string(), id.into());
+    properties.insert("zorder".to_string(), zorder.into());
+    properties.insert("subtype".to_string(), subtype.into());
+
+    let triangles = polygon
+        .triangles()
+        .into_iter()
+        .map(|tri| {
+            vec![gps_bounds
+                .convert_back(&vec![tri.pt1, tri.pt2, tri.pt3, tri.pt1])
+                .into_iter()
+                .map(|gps| vec![gps.x(), gps.y()])
+                .collect()]
+        })
+        .collect();
+    geojson::Feature {
+        bbox: Some(gps_bbox(&polygon.get_bounds(), gps_bounds)),
+        geometry: Some(geojson::Geometry::new(geojson::Value::MultiPolygon(
+            triangles,
+        ))),
+        id: None,
+        properties: Some(properties),
+        foreign_members: None,
+    }
+}
+
+// [west, south, east, north]. The world's y axis points south.
+fn gps_bbox(bounds: &Bounds, gps_bounds: &GPSBounds) -> Vec<f64> {
+    let corners = gps_bounds.convert_back(&vec![
+        Pt2D::new(bounds.min_x, bounds.max_y),
+        Pt2D::new(bounds.max_x, bounds.min_y),
+    ]);
+    vec![
+        corners[0].x(),
+        corners[0].y(),
+        corners[1].x(),
+        corners[1].y(),
+    ]
+}
@@ -1,31 +1,46 @@
 mod area;
+mod background;
 mod bike;
 mod building;
+mod bus_route;
 mod bus_stop;
 mod car;
+mod headless;
+mod heatmap_overlay;
 mod intersection;
 mod lane;
 mod map;
+mod neighborhood;
 mod parking_lot;
 mod pedestrian;
 mod road;
+mod route;
 mod traffic_signal;
 mod turn;
+mod tween;
 
 use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
 pub use crate::render::area::DrawArea;
+pub use crate::render::background::BackgroundTiles;
 use crate::render::bike::DrawBike;
+pub use crate::render::building::MIN_ZOOM_FOR_EXTRUSION;
+pub use crate::render::bus_route::DrawBusRoute;
 use crate::render::car::DrawCar;
+pub use crate::render::headless::HeadlessMap;
+pub use crate::render::heatmap_overlay::{all_heatmap_sources, HeatmapOverlay};
 pub use crate::render::intersection::{calculate_corners, DrawIntersection};
-pub use crate::render::map::{AgentCache, DrawMap, UnzoomedAgents};
+pub use crate::render::map::{AgentCache, DrawMap, MapGeometry, UnzoomedAgents};
+pub use crate::render::neighborhood::DrawNeighborhood;
 pub use crate::render::pedestrian::{DrawPedCrowd, DrawPedestrian};
 pub use crate::render::road::DrawRoad;
-pub use crate::render::traffic_signal::{draw_signal_phase, make_signal_diagram};
+pub use crate::render::route::DrawRoute;
+pub use crate::render::traffic_signal::{banned_by_edits, draw_signal_phase, make_signal_diagram};
 pub use crate::render::turn::{DrawTurnGroup, DrawUberTurnGroup};
-use ezgui::{GfxCtx, Prerender};
-use geom::{Distance, Polygon, Pt2D};
+pub use crate::render::tween::draw_trip_markers;
+use ezgui::{Color, GeomBatch, GfxCtx, Prerender};
+use geom::{Distance, Line, Polygon, Pt2D};
 use map_model::{IntersectionID, Map};
 use sim::{DrawCarInput, VehicleType};
 
@@ -35,6 +50,10 @@ pub const CROSSWALK_LINE_THICKNESS: Distance = Distance::const_meters(0.15);
 
 pub const OUTLINE_THICKNESS: Distance = Distance::const_meters(0.5);
 
+// Bus stops and traffic signal icons are too small to make out until zoomed in this far, even
+// though the rest of the detailed view appears before.
+pub const MIN_ZOOM_FOR_SMALL_DETAILS: f64 = 6.0;
+
 // Does something belong here or as a method on ID? If it ONLY applies to renderable things, then
 // here. For example, trips aren't drawn, so it's meaningless to ask what their bounding box is.
 pub trait Renderable {
@@ -43,6 +62,9 @@ pub trait Renderable {
     fn get_id(&self) -> ID;
     // Only traffic signals need UI. :\
     fn draw(&self, g: &mut GfxCtx, app: &App, opts: &DrawOptions);
+    // When everything static about the object has already been drawn by BackgroundTiles, just
+    // draw whatever's left.
+    fn draw_dynamic(&self, _: &mut GfxCtx, _: &App, _: &DrawOptions) {}
     // Higher z-ordered objects are drawn later. Default to low so roads at -1 don't vanish.
     fn get_zorder(&self) -> isize {
         -5
@@ -57,15 +79,31 @@ pub trait Renderable {
 
 fn draw_vehicle(
     input: DrawCarInput,
+    trail: &[Pt2D],
     map: &Map,
     prerender: &Prerender,
     cs: &ColorScheme,
 ) -> Box<dyn Renderable> {
     if input.id.1 == VehicleType::Bike {
-        Box::new(DrawBike::new(input, map, prerender, cs))
+        Box::new(DrawBike::new(input, trail, map, prerender, cs))
     } else {
-        Box::new(DrawCar::new(input, map, prerender, cs))
+        Box::new(DrawCar::new(input, trail, map, prerender, cs))
+    }
+}
+
+// Connect an agent's recent positions, oldest first. The oldest parts fade out.
+fn draw_trail(trail: &[Pt2D], color: Color, thickness: Distance) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let n = trail.len() as f32;
+    for (idx, pair) in trail.windows(2).enumerate() {
+        if let Some(line) = Line::new(pair[0], pair[1]) {
+            batch.push(
+                color.alpha(0.6 * (idx + 1) as f32 / n),
+                line.make_polygons(thickness),
+            );
+        }
     }
+    batch
 }
 
 // TODO Borrow, don't clone, and fix up lots of places storing indirect things to populate
@@ -0,0 +1,47 @@
+use crate::app::App;
+use ezgui::{Color, EventCtx, GeomBatch, Line, Text};
+use geom::{Distance, Polygon};
+use map_model::Neighborhood;
+
+// A neighborhood on top of the map: a translucent area with a solid outline and its name in the
+// middle.
+pub struct DrawNeighborhood {
+    pub neighborhood: Neighborhood,
+    pub color: Color,
+    pub polygon: Polygon,
+}
+
+impl DrawNeighborhood {
+    // Colored by the order they're loaded in, so every view of the same neighborhoods agrees.
+    // Neighborhoods completely off the map are skipped.
+    pub fn load_all(app: &App) -> Vec<DrawNeighborhood> {
+        Neighborhood::load_all(app.primary.map.get_name())
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, n)| {
+                let polygon = n.polygon(&app.primary.map)?;
+                Some(DrawNeighborhood {
+                    neighborhood: n,
+                    color: app.cs.rotating_color_plot(idx),
+                    polygon,
+                })
+            })
+            .collect()
+    }
+
+    pub fn render(&self, ctx: &EventCtx) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        batch.push(self.color.alpha(0.3), self.polygon.clone());
+        if let Ok(o) = self.polygon.to_outline(Distance::meters(5.0)) {
+            batch.push(self.color, o);
+        }
+        batch.append(
+            Text::from(Line(&self.neighborhood.name))
+                .with_bg()
+                .render_to_batch(ctx.prerender)
+                .scale(2.0)
+                .centered_on(self.polygon.polylabel()),
+        );
+        batch
+    }
+}
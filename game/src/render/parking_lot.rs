@@ -35,6 +35,14 @@ impl DrawParkingLot {
                 .centered_on(lot.polygon.polylabel()),
         );
 
+        DrawParkingLot {
+            id: lot.id,
+            draw: prerender.upload(DrawParkingLot::render(lot, cs)),
+        }
+    }
+
+    // When zoomed in
+    pub fn render(lot: &ParkingLot, cs: &ColorScheme) -> GeomBatch {
         // Trim the front path line away from the sidewalk's center line, so that it doesn't
         // overlap. For now, this cleanup is visual; it doesn't belong in the map_model layer.
         let orig_line = &lot.sidewalk_line;
@@ -77,11 +85,7 @@ impl DrawParkingLot {
                 .make_polygons(Distance::meters(0.25)),
             );
         }
-
-        DrawParkingLot {
-            id: lot.id,
-            draw: prerender.upload(batch),
-        }
+        batch
     }
 }
 
@@ -1,9 +1,10 @@
 use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
-use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
+use crate::render::tween::spawn_in;
+use crate::render::{draw_trail, DrawOptions, Renderable, OUTLINE_THICKNESS};
 use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Line, Prerender, Text};
-use geom::{ArrowCap, Circle, Distance, PolyLine, Polygon};
+use geom::{ArrowCap, Circle, Distance, PolyLine, Polygon, Pt2D};
 use map_model::{Map, SIDEWALK_THICKNESS};
 use sim::{DrawPedCrowdInput, DrawPedestrianInput, PedCrowdLocation, PedestrianID};
 
@@ -18,6 +19,7 @@ pub struct DrawPedestrian {
 impl DrawPedestrian {
     pub fn new(
         input: DrawPedestrianInput,
+        trail: &[Pt2D],
         step_count: usize,
         map: &Map,
         prerender: &Prerender,
@@ -42,11 +44,13 @@ impl DrawPedestrian {
             );
         }
 
+        let mut batch = draw_trail(trail, cs.rotating_color_agents(input.id.0), radius);
+        batch.append(spawn_in(draw_default, input.pos, input.age));
         DrawPedestrian {
             id: input.id,
             body_circle,
             zorder: input.on.get_zorder(map),
-            draw_default: prerender.upload(draw_default),
+            draw_default: prerender.upload(batch),
         }
     }
 
@@ -17,6 +17,21 @@ pub struct DrawRoad {
 
 impl DrawRoad {
     pub fn new(r: &Road, map: &Map, cs: &ColorScheme, prerender: &Prerender) -> DrawRoad {
+        DrawRoad::from_batch(r, DrawRoad::render(r, map, cs), prerender)
+    }
+
+    // When render() already happened elsewhere, like on a worker thread
+    pub fn from_batch(r: &Road, batch: GeomBatch, prerender: &Prerender) -> DrawRoad {
+        DrawRoad {
+            id: r.id,
+            zorder: r.zorder,
+            draw_center_line: prerender.upload(batch),
+            label: RefCell::new(None),
+        }
+    }
+
+    // Just the center line; the lanes are drawn separately
+    pub fn render(r: &Road, map: &Map, cs: &ColorScheme) -> GeomBatch {
         let mut draw = GeomBatch::new();
         let center = r.get_current_center(map);
         let width = Distance::meters(0.25);
@@ -41,13 +56,7 @@ impl DrawRoad {
                 center.dashed_lines(width, Distance::meters(2.0), Distance::meters(1.0)),
             );
         }
-
-        DrawRoad {
-            id: r.id,
-            zorder: r.zorder,
-            draw_center_line: prerender.upload(draw),
-            label: RefCell::new(None),
-        }
+        draw
     }
 }
 
@@ -56,9 +65,12 @@ impl Renderable for DrawRoad {
         ID::Road(self.id)
     }
 
-    fn draw(&self, g: &mut GfxCtx, app: &App, _: &DrawOptions) {
+    fn draw(&self, g: &mut GfxCtx, app: &App, opts: &DrawOptions) {
         g.redraw(&self.draw_center_line);
+        self.draw_dynamic(g, app, opts);
+    }
 
+    fn draw_dynamic(&self, g: &mut GfxCtx, app: &App, _: &DrawOptions) {
         if app.opts.label_roads {
             // Lazily calculate
             let mut label = self.label.borrow_mut();
@@ -0,0 +1,77 @@
+use crate::colors::ColorScheme;
+use ezgui::{Drawable, EventCtx, GeomBatch, GfxCtx};
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{Map, Path, PathConstraints, PathRequest};
+
+const WIDTH: Distance = Distance::const_meters(3.0);
+// Roughly the fastest bikes in the simulation, 10mph
+const MAX_BIKE_SPEED: Speed = Speed::const_meters_per_second(4.4704);
+
+// A route someone could take, possibly with a few legs using different modes. Each leg is drawn
+// thick over the lanes and turns it uses, colored by how it's traveled.
+pub struct DrawRoute {
+    pub distance: Distance,
+    // Ignores delays at intersections and waiting for a bus, so it's optimistic
+    pub estimated_time: Duration,
+
+    draw: Drawable,
+}
+
+impl DrawRoute {
+    // None if none of the legs could be traced.
+    pub fn new(
+        ctx: &EventCtx,
+        legs: Vec<(PathRequest, Path)>,
+        now: Time,
+        map: &Map,
+        cs: &ColorScheme,
+    ) -> Option<DrawRoute> {
+        let mut batch = GeomBatch::new();
+        let mut distance = Distance::ZERO;
+        let mut estimated_time = Duration::ZERO;
+        let mut traced_any = false;
+        for (req, path) in legs {
+            let pl = match path.trace(map, req.start.dist_along(), None) {
+                Some(pl) => pl,
+                None => continue,
+            };
+            let color = match req.constraints {
+                PathConstraints::Pedestrian => cs.unzoomed_pedestrian,
+                PathConstraints::Bike => cs.unzoomed_bike,
+                PathConstraints::Car => cs.unzoomed_car,
+                PathConstraints::Bus | PathConstraints::Train => cs.unzoomed_bus,
+            };
+            batch.push(color.alpha(0.8), pl.make_polygons(WIDTH));
+
+            let mut full_time = Duration::ZERO;
+            for step in path.get_steps() {
+                let t = step.as_traversable();
+                let speed = match req.constraints {
+                    // Same as what pathfinding assumes for walking
+                    PathConstraints::Pedestrian => Speed::meters_per_second(1.34),
+                    PathConstraints::Bike => t.speed_limit(now, map).min(MAX_BIKE_SPEED),
+                    _ => t.speed_limit(now, map),
+                };
+                full_time += t.length(map) / speed;
+            }
+            // The path covers the first and last lanes entirely; the trace doesn't.
+            if path.total_length() > Distance::ZERO {
+                estimated_time += full_time * (pl.length() / path.total_length());
+            }
+            distance += pl.length();
+            traced_any = true;
+        }
+        if !traced_any {
+            return None;
+        }
+        Some(DrawRoute {
+            distance,
+            estimated_time,
+            draw: ctx.upload(batch),
+        })
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
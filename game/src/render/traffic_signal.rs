@@ -7,7 +7,10 @@ use ezgui::{
     RewriteColor, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Angle, ArrowCap, Circle, Distance, Duration, Line, PolyLine, Polygon, Pt2D};
-use map_model::{IntersectionID, Phase, PhaseType, TurnPriority, SIDEWALK_THICKNESS};
+use map_model::{
+    ControlTrafficSignal, EditIntersection, IntersectionID, Map, Phase, PhaseType, TurnGroupID,
+    TurnPriority, SIDEWALK_THICKNESS,
+};
 use std::collections::BTreeSet;
 
 // Only draws a box when time_left is present
@@ -210,14 +213,20 @@ pub fn draw_signal_phase(
             }
         }
         TrafficSignalStyle::Icons => {
+            let banned = banned_by_edits(&app.primary.map, signal, signal);
             for g in DrawTurnGroup::for_i(i, &app.primary.map) {
                 batch.push(app.cs.signal_turn_block_bg, g.block.clone());
-                let arrow_color = match phase.get_priority_of_group(g.id) {
+                batch.push(g.kind.color(&app.cs), g.outline.clone());
+                let priority = phase.get_priority_of_group(g.id);
+                let arrow_color = match priority {
                     TurnPriority::Protected => app.cs.signal_protected_turn,
                     TurnPriority::Yield => app.cs.signal_permitted_turn.alpha(1.0),
                     TurnPriority::Banned => app.cs.signal_banned_turn,
                 };
                 batch.push(arrow_color, g.arrow.clone());
+                if banned.contains(&g.id) {
+                    batch.push(app.cs.turn_icon_banned_strike, g.strike.clone());
+                }
             }
         }
         TrafficSignalStyle::IndividualTurnArrows => {
@@ -279,6 +288,82 @@ fn crosswalk_icon(geom: &PolyLine) -> (Pt2D, Angle) {
     )
 }
 
+// Turn groups that edits have banned in every phase, even though the signal allowed them at some
+// point before. Groups that just wait out some phases aren't restricted. Without any saved edits
+// to the intersection, compare against the fallback instead, to catch unsaved changes.
+pub fn banned_by_edits(
+    map: &Map,
+    signal: &ControlTrafficSignal,
+    fallback: &ControlTrafficSignal,
+) -> BTreeSet<TurnGroupID> {
+    let orig = match map.get_edits().original_intersections.get(&signal.id) {
+        Some(EditIntersection::TrafficSignal(raw)) => {
+            match ControlTrafficSignal::import(raw.clone(), signal.id, map) {
+                Ok(ts) => Some(ts),
+                Err(_) => {
+                    return BTreeSet::new();
+                }
+            }
+        }
+        // Stop signs don't ban any turns
+        Some(EditIntersection::StopSign(_)) => None,
+        Some(EditIntersection::Closed) => {
+            return BTreeSet::new();
+        }
+        None => Some(fallback.clone()),
+    };
+    signal
+        .turn_groups
+        .keys()
+        .filter(|g| {
+            signal
+                .phases
+                .iter()
+                .all(|p| p.get_priority_of_group(**g) == TurnPriority::Banned)
+                && orig
+                    .as_ref()
+                    .map(|ts| {
+                        ts.phases
+                            .iter()
+                            .any(|p| p.get_priority_of_group(**g) != TurnPriority::Banned)
+                    })
+                    .unwrap_or(true)
+        })
+        .cloned()
+        .collect()
+}
+
+fn pedestrian_intervals(ctx: &EventCtx, phase: &Phase, signal: &ControlTrafficSignal) -> Widget {
+    let intervals = match phase.pedestrian_intervals(signal) {
+        Some(x) => x,
+        None => {
+            return Widget::nothing();
+        }
+    };
+    let mut txt = Text::new();
+    if intervals.walk == Duration::ZERO {
+        txt.add(Line("Too short to cross; crosswalks stay closed").fg(Color::RED));
+    } else if intervals.leading == Duration::ZERO {
+        txt.add(Line(format!("Walk: {}", intervals.walk)).secondary());
+    } else {
+        txt.add(
+            Line(format!(
+                "Walk: {} ({} head start)",
+                intervals.walk, intervals.leading
+            ))
+            .secondary(),
+        );
+    }
+    txt.add(
+        Line(format!(
+            "Flashing don't walk: {}",
+            intervals.flashing_dont_walk
+        ))
+        .secondary(),
+    );
+    txt.draw(ctx)
+}
+
 pub fn make_signal_diagram(
     ctx: &mut EventCtx,
     app: &App,
@@ -309,6 +394,18 @@ pub fn make_signal_diagram(
         txt.add(Line(""));
         txt.add(Line(format!("{} phases", signal.phases.len())).small_heading());
         txt.add(Line(format!("Signal offset: {}", signal.offset)));
+        txt.add(Line(format!(
+            "Pedestrian head start: {}",
+            signal.leading_pedestrian_interval
+        )));
+        txt.add(Line(if signal.right_turn_on_red {
+            "Turns on red allowed"
+        } else {
+            "No turns on red"
+        }));
+        if signal.transit_signal_priority {
+            txt.add(Line("Transit signal priority"));
+        }
         {
             let mut total = Duration::ZERO;
             for p in &signal.phases {
@@ -426,6 +523,7 @@ pub fn make_signal_diagram(
                     .centered_vert()
                     .align_right(),
                 ]),
+                pedestrian_intervals(ctx, phase, signal),
             ])
         } else {
             Widget::col(vec![
@@ -436,6 +534,7 @@ pub fn make_signal_diagram(
                     }
                 },
                 phase_btn,
+                pedestrian_intervals(ctx, phase, signal),
             ])
         }
         .padding(10);
@@ -1,13 +1,64 @@
-use geom::{Angle, ArrowCap, Distance, PolyLine, Polygon};
-use map_model::{IntersectionCluster, IntersectionID, LaneID, Map, TurnGroupID, UberTurnGroup};
+use crate::colors::ColorScheme;
+use ezgui::Color;
+use geom::{Angle, ArrowCap, Distance, PolyLine, Polygon, Pt2D};
+use map_model::{
+    IntersectionCluster, IntersectionID, LaneID, Map, TurnGroupID, TurnType, UberTurnGroup,
+};
 use std::collections::{HashMap, HashSet};
 
 const TURN_ICON_ARROW_LENGTH: Distance = Distance::const_meters(1.5);
+const GLYPH_THICKNESS: Distance = Distance::const_meters(0.5);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TurnIconKind {
+    Straight,
+    Left,
+    Right,
+    UTurn,
+    Crosswalk,
+}
+
+impl TurnIconKind {
+    // TurnType doesn't distinguish u-turns, so just look at the angles. Lane-changing turns count
+    // as straight.
+    fn new(turn_type: TurnType, entry: Angle, exit: Angle) -> TurnIconKind {
+        if turn_type == TurnType::Crosswalk {
+            return TurnIconKind::Crosswalk;
+        }
+        let diff = entry.shortest_rotation_towards(exit).normalized_degrees();
+        if diff < 30.0 || diff > 330.0 {
+            TurnIconKind::Straight
+        } else if diff > 150.0 && diff < 210.0 {
+            TurnIconKind::UTurn
+        } else if diff > 180.0 {
+            // Clockwise, just like TurnType::from_angles
+            TurnIconKind::Right
+        } else {
+            TurnIconKind::Left
+        }
+    }
+
+    pub fn color(self, cs: &ColorScheme) -> Color {
+        match self {
+            TurnIconKind::Straight => cs.turn_icon_straight,
+            TurnIconKind::Left => cs.turn_icon_left,
+            TurnIconKind::Right => cs.turn_icon_right,
+            TurnIconKind::UTurn => cs.turn_icon_u_turn,
+            TurnIconKind::Crosswalk => cs.turn_icon_crosswalk,
+        }
+    }
+}
 
 pub struct DrawTurnGroup {
     pub id: TurnGroupID,
+    pub kind: TurnIconKind,
     pub block: Polygon,
+    // The glyph depends on the kind of turn
     pub arrow: Polygon,
+    // Drawn around the block, in the kind's color
+    pub outline: Polygon,
+    // Drawn over the glyph when the turn isn't allowed
+    pub strike: Polygon,
 }
 
 impl DrawTurnGroup {
@@ -23,7 +74,19 @@ impl DrawTurnGroup {
                 .max()
                 .unwrap();
             let (pl, width) = group.src_center_and_width(map);
-            let (block, arrow) = make_geom(offset as f64, pl, width, group.angle);
+            let kind = TurnIconKind::new(
+                group.turn_type,
+                pl.first_line().angle().opposite(),
+                group.angle,
+            );
+            let (block, arrow, outline, strike) = make_geom(
+                offset as f64,
+                pl,
+                width,
+                group.angle,
+                kind,
+                group.geom.last_pt(),
+            );
             let mut seen_lanes = HashSet::new();
             for t in &group.members {
                 if !seen_lanes.contains(&t.src) {
@@ -34,8 +97,11 @@ impl DrawTurnGroup {
 
             draw.push(DrawTurnGroup {
                 id: group.id,
+                kind,
                 block,
                 arrow,
+                outline,
+                strike,
             });
         }
         draw
@@ -44,8 +110,11 @@ impl DrawTurnGroup {
 
 pub struct DrawUberTurnGroup {
     pub group: UberTurnGroup,
+    pub kind: TurnIconKind,
     pub block: Polygon,
     pub arrow: Polygon,
+    pub outline: Polygon,
+    pub strike: Polygon,
 }
 
 impl DrawUberTurnGroup {
@@ -60,7 +129,19 @@ impl DrawUberTurnGroup {
                 .max()
                 .unwrap();
             let (pl, width) = group.src_center_and_width(map);
-            let (block, arrow) = make_geom(offset as f64, pl, width, group.angle());
+            let kind = TurnIconKind::new(
+                TurnType::Straight,
+                pl.first_line().angle().opposite(),
+                group.angle(),
+            );
+            let (block, arrow, outline, strike) = make_geom(
+                offset as f64,
+                pl,
+                width,
+                group.angle(),
+                kind,
+                group.geom.last_pt(),
+            );
             let mut seen_lanes = HashSet::new();
             for ut in &group.members {
                 if !seen_lanes.contains(&ut.entry()) {
@@ -71,16 +152,27 @@ impl DrawUberTurnGroup {
 
             draw.push(DrawUberTurnGroup {
                 group,
+                kind,
                 block,
                 arrow,
+                outline,
+                strike,
             });
         }
         draw
     }
 }
 
-// Produces (block, arrow)
-fn make_geom(offset: f64, pl: PolyLine, width: Distance, angle: Angle) -> (Polygon, Polygon) {
+// Produces (block, glyph, outline, strike). The polyline points away from the intersection, and
+// the angle is the overall direction of the turn.
+fn make_geom(
+    offset: f64,
+    pl: PolyLine,
+    width: Distance,
+    angle: Angle,
+    kind: TurnIconKind,
+    exit_pt: Pt2D,
+) -> (Polygon, Polygon, Polygon, Polygon) {
     let height = TURN_ICON_ARROW_LENGTH;
     // Always extend the pl first to handle short entry lanes
     let extension = PolyLine::must_new(vec![
@@ -91,15 +183,78 @@ fn make_geom(offset: f64, pl: PolyLine, width: Distance, angle: Angle) -> (Polyg
     let pl = pl.must_extend(extension);
     let slice = pl.exact_slice(offset * height, (offset + 1.0) * height);
     let block = slice.make_polygons(width);
+    let outline = block
+        .to_outline(Distance::meters(0.1))
+        .unwrap_or_else(|_| block.clone());
 
-    let arrow = {
-        let center = slice.middle();
-        PolyLine::must_new(vec![
-            center.project_away(TURN_ICON_ARROW_LENGTH / 2.0, angle.opposite()),
-            center.project_away(TURN_ICON_ARROW_LENGTH / 2.0, angle),
+    let center = slice.middle();
+    let half = TURN_ICON_ARROW_LENGTH / 2.0;
+    // The direction traffic enters the intersection
+    let entry = slice.first_line().angle().opposite();
+    let glyph = match kind {
+        TurnIconKind::Straight => PolyLine::must_new(vec![
+            center.project_away(half, angle.opposite()),
+            center.project_away(half, angle),
+        ])
+        .make_arrow(GLYPH_THICKNESS, ArrowCap::Triangle),
+        TurnIconKind::Left | TurnIconKind::Right => PolyLine::must_new(vec![
+            center.project_away(half, entry.opposite()),
+            center,
+            center.project_away(half, angle),
         ])
-        .make_arrow(Distance::meters(0.5), ArrowCap::Triangle)
+        .make_arrow(GLYPH_THICKNESS, ArrowCap::Triangle),
+        TurnIconKind::UTurn => {
+            // Hook towards whichever side the turn exits
+            let side1 = entry.rotate_degs(90.0);
+            let side2 = entry.rotate_degs(-90.0);
+            let side = if center.project_away(half, side1).dist_to(exit_pt)
+                < center.project_away(half, side2).dist_to(exit_pt)
+            {
+                side1
+            } else {
+                side2
+            };
+            let start = center
+                .project_away(half, entry.opposite())
+                .project_away(half / 2.0, side.opposite());
+            let top = start.project_away(TURN_ICON_ARROW_LENGTH, entry);
+            PolyLine::must_new(vec![
+                start,
+                top,
+                top.project_away(half, side),
+                top.project_away(half, side)
+                    .project_away(half, entry.opposite()),
+            ])
+            .make_arrow(GLYPH_THICKNESS * 0.6, ArrowCap::Triangle)
+        }
+        TurnIconKind::Crosswalk => {
+            // A few stripes across the walking direction, like a little zebra crossing
+            let stripe_len = width.min(TURN_ICON_ARROW_LENGTH) * 0.8;
+            Polygon::union_all(
+                vec![-1.0, 0.0, 1.0]
+                    .into_iter()
+                    .map(|x| {
+                        let pt = center.project_away(half * 0.6 * x, angle);
+                        PolyLine::must_new(vec![
+                            pt.project_away(stripe_len / 2.0, angle.rotate_degs(90.0)),
+                            pt.project_away(stripe_len / 2.0, angle.rotate_degs(-90.0)),
+                        ])
+                        .make_polygons(GLYPH_THICKNESS / 2.0)
+                    })
+                    .collect(),
+            )
+        }
     };
 
-    (block, arrow)
+    let strike = PolyLine::must_new(vec![
+        center
+            .project_away(half, entry.opposite())
+            .project_away(half, entry.rotate_degs(90.0)),
+        center
+            .project_away(half, entry)
+            .project_away(half, entry.rotate_degs(-90.0)),
+    ])
+    .make_polygons(GLYPH_THICKNESS / 2.0);
+
+    (block, glyph, outline, strike)
 }
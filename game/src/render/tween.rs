@@ -0,0 +1,86 @@
+use crate::app::App;
+use ezgui::{GeomBatch, GfxCtx, RewriteColor};
+use geom::{Circle, Distance, Duration, Pt2D, Time};
+use map_model::SIDEWALK_THICKNESS;
+
+// Agents used to pop into and out of existence at buildings. These animations are driven by sim
+// time, not real time, so they freeze when the sim is paused and keep up with agents when it's
+// sped up.
+const SPAWN_ANIMATION: Duration = Duration::const_seconds(2.0);
+const MARKER_DURATION: Duration = Duration::const_seconds(10.0);
+const MARKER_RADIUS: Distance = Distance::const_meters(8.0);
+
+// Smoothly goes from 0 to 1 as age goes from 0 to length.
+fn ease(age: Duration, length: Duration) -> f64 {
+    let t = (age / length).max(0.0).min(1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Grows and fades in an agent right after it appears.
+pub fn spawn_in(batch: GeomBatch, center: Pt2D, age: Option<Duration>) -> GeomBatch {
+    let pct = match age {
+        Some(age) if age < SPAWN_ANIMATION => ease(age, SPAWN_ANIMATION),
+        _ => {
+            return batch;
+        }
+    };
+    // Don't start from nothing, or a paused sim would hide the agent
+    let scale = 0.3 + 0.7 * pct;
+    batch
+        .translate(-center.x(), -center.y())
+        .scale(scale)
+        .translate(center.x(), center.y())
+        .color(RewriteColor::ChangeAlpha((0.3 + 0.7 * pct) as f32))
+}
+
+// Buildings where trips just started or ended get a ring that spreads out and fades. When somebody
+// finishes a trip, they also shrink away at the end of the building's front path.
+pub fn draw_trip_markers(g: &mut GfxCtx, app: &App, now: Time) {
+    let map = &app.primary.map;
+    let screen = g.get_screen_bounds();
+    let mut batch = GeomBatch::new();
+    for (time, b, leaving) in app
+        .primary
+        .sim
+        .get_analytics()
+        .building_transitions
+        .iter()
+        .rev()
+        .skip_while(|(t, _, _)| *t > now)
+        .take_while(|(t, _, _)| now - *t < MARKER_DURATION)
+    {
+        let bldg = map.get_b(*b);
+        let center = bldg.label_center;
+        if !screen.contains(center) {
+            continue;
+        }
+        let age = now - *time;
+        let pct = ease(age, MARKER_DURATION);
+        let color = if *leaving {
+            app.cs.trip_started
+        } else {
+            app.cs.trip_ended
+        };
+
+        let radius = MARKER_RADIUS * (0.2 + 0.8 * pct);
+        if let Ok(ring) = Circle::new(center, radius)
+            .to_polygon()
+            .to_outline(Distance::meters(1.0))
+        {
+            batch.push(color.alpha((1.0 - pct) as f32), ring);
+        }
+
+        if !*leaving && age < SPAWN_ANIMATION {
+            let pct = ease(age, SPAWN_ANIMATION);
+            batch.push(
+                color.alpha((1.0 - pct) as f32),
+                Circle::new(
+                    bldg.front_path.line.pt2(),
+                    (SIDEWALK_THICKNESS / 4.0) * (1.0 - pct),
+                )
+                .to_polygon(),
+            );
+        }
+    }
+    batch.draw(g);
+}
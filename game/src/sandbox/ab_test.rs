@@ -0,0 +1,386 @@
+use crate::app::{App, PerMap};
+use crate::game::{DrawBaselayer, State, Transition};
+use crate::sandbox::{GameplayMode, SpeedSetting};
+use abstutil::prettyprint_usize;
+use ezgui::{
+    hotkey, Btn, Choice, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, ScreenPt, ScreenRectangle, Text, TextExt, UpdateType, VerticalAlignment, Widget,
+    UPDATE_FREQUENCY,
+};
+use geom::{Distance, Duration, Pt2D, Time};
+
+// Runs the same scenario on the original map and with the current edits. Both sims advance in
+// lockstep. They're shown side by side, with the original map on the left; both halves follow the
+// same camera. Or one can fill the screen, with all of the usual detail. In that case, the other
+// lives in app.secondary, and toggling just swaps them, so everything that draws app.primary
+// (including the agent cache) works unchanged.
+pub struct ABTestMode {
+    top_center: Composite,
+    summary: Composite,
+    // Labels for the halves of the screen
+    split_labels: Vec<Composite>,
+    paused: bool,
+    split_screen: bool,
+    // True when the original map is swapped into app.primary
+    showing_baseline: bool,
+    // When the summary was last calculated
+    summary_time: Time,
+}
+
+impl ABTestMode {
+    // Returns None if this gameplay mode has no scenario to run.
+    pub fn new(ctx: &mut EventCtx, app: &mut App, mode: &GameplayMode) -> Option<Box<dyn State>> {
+        let ok = ctx.loading_screen("set up A/B test", |ctx, mut timer| {
+            let scenario = mode.scenario(
+                &app.primary.map,
+                app.primary.current_flags.num_agents,
+                app.primary.current_flags.sim_flags.make_rng(),
+                &mut timer,
+            )?;
+            app.primary
+                .map
+                .recalculate_pathfinding_after_edits(&mut timer);
+
+            let mut flags = app.primary.current_flags.clone();
+            flags.sim_flags.load = abstutil::path_map(app.primary.map.get_name());
+            let mut baseline = PerMap::new(flags, &app.cs, ctx, &mut timer);
+
+            app.primary.clear_sim();
+            // Same seed for both, so the trips match up
+            for per_map in vec![&mut app.primary, &mut baseline] {
+                scenario.instantiate(
+                    &mut per_map.sim,
+                    &per_map.map,
+                    &mut per_map.current_flags.sim_flags.make_rng(),
+                    &mut timer,
+                );
+                per_map.sim.tiny_step(&per_map.map, &mut None);
+            }
+            app.secondary = Some(baseline);
+            Some(())
+        });
+        ok?;
+
+        let mut state = ABTestMode {
+            top_center: Composite::new(Widget::nothing()).build(ctx),
+            summary: Composite::new(Widget::nothing()).build(ctx),
+            split_labels: make_split_labels(ctx, app, false),
+            paused: true,
+            split_screen: true,
+            showing_baseline: false,
+            summary_time: app.primary.sim.time(),
+        };
+        state.top_center = state.make_top_center(ctx, app, app.opts.default_speed);
+        state.summary = make_summary(ctx, app, state.showing_baseline);
+        Some(Box::new(state))
+    }
+
+    fn make_top_center(&self, ctx: &mut EventCtx, app: &App, speed: SpeedSetting) -> Composite {
+        Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("A/B test").small_heading().draw(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            if self.split_screen {
+                Widget::row(vec![
+                    "Showing both, side by side".draw_text(ctx),
+                    Btn::text_fg("show one in detail")
+                        .build_def(ctx, hotkey(Key::S))
+                        .align_right(),
+                ])
+            } else {
+                Widget::col(vec![
+                    Widget::row(vec![
+                        if self.showing_baseline {
+                            "Showing the original map".draw_text(ctx)
+                        } else {
+                            format!("Showing {}", app.primary.map.get_edits().edits_name)
+                                .draw_text(ctx)
+                        },
+                        Btn::text_fg("show the other")
+                            .build_def(ctx, hotkey(Key::Tab))
+                            .align_right(),
+                    ]),
+                    Btn::text_fg("show both side by side")
+                        .build_def(ctx, hotkey(Key::S))
+                        .align_right(),
+                ])
+            },
+            Widget::row(vec![
+                app.primary
+                    .sim
+                    .time()
+                    .ampm_tostring()
+                    .draw_text(ctx)
+                    .named("time"),
+                if self.paused {
+                    Btn::text_bg2("play").build_def(ctx, hotkey(Key::Space))
+                } else {
+                    Btn::text_bg2("pause").build_def(ctx, hotkey(Key::Space))
+                },
+                Widget::dropdown(
+                    ctx,
+                    "speed",
+                    speed,
+                    vec![
+                        Choice::new("real-time speed", SpeedSetting::Realtime),
+                        Choice::new("10x speed", SpeedSetting::Fast),
+                        Choice::new("60x speed", SpeedSetting::Faster),
+                        Choice::new("3600x speed", SpeedSetting::Fastest),
+                    ],
+                ),
+            ])
+            .evenly_spaced(),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx)
+    }
+
+    fn swap(&mut self, app: &mut App) {
+        std::mem::swap(&mut app.primary, app.secondary.as_mut().unwrap());
+        app.primary.current_selection = None;
+        self.showing_baseline = !self.showing_baseline;
+    }
+}
+
+impl State for ABTestMode {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() && !self.split_screen {
+            app.recalculate_current_selection(ctx);
+        }
+
+        let speed: SpeedSetting = self.top_center.dropdown_value("speed");
+        match self.top_center.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "show the other" => {
+                    self.swap(app);
+                    self.top_center = self.make_top_center(ctx, app, speed);
+                }
+                "show one in detail" | "show both side by side" => {
+                    self.split_screen = !self.split_screen;
+                    app.primary.current_selection = None;
+                    self.top_center = self.make_top_center(ctx, app, speed);
+                }
+                "play" | "pause" => {
+                    self.paused = !self.paused;
+                    self.top_center = self.make_top_center(ctx, app, speed);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        if ctx.input.is_window_resized() {
+            self.split_labels = make_split_labels(ctx, app, self.showing_baseline);
+        }
+
+        if !self.paused {
+            if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+                ctx.input.use_update_event();
+                // Both sims aim for the same time and split the time budget, so the UI stays
+                // responsive at any speed. Whichever one falls behind catches up over the next
+                // few updates, before the other goes any further.
+                let behind = app
+                    .primary
+                    .sim
+                    .time()
+                    .min(app.secondary.as_ref().unwrap().sim.time());
+                let target = behind + speed.multiplier() * real_dt;
+                let budget = Duration::seconds(UPDATE_FREQUENCY.as_secs_f64() / 2.0);
+                for per_map in vec![&mut app.primary, app.secondary.as_mut().unwrap()] {
+                    if per_map.sim.time() < target {
+                        let dt = target - per_map.sim.time();
+                        per_map
+                            .sim
+                            .time_limited_step(&per_map.map, dt, budget, &mut None);
+                    }
+                }
+                let now = app
+                    .primary
+                    .sim
+                    .time()
+                    .min(app.secondary.as_ref().unwrap().sim.time());
+                if !self.split_screen {
+                    app.recalculate_current_selection(ctx);
+                }
+
+                let time = now.ampm_tostring().draw_text(ctx);
+                self.top_center.replace(ctx, "time", time.named("time"));
+                if now - self.summary_time >= Duration::minutes(1) {
+                    self.summary_time = now;
+                    self.summary = make_summary(ctx, app, self.showing_baseline);
+                }
+            }
+            ctx.request_update(UpdateType::Game);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        if self.split_screen {
+            DrawBaselayer::Custom
+        } else {
+            DrawBaselayer::DefaultMap
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if self.split_screen {
+            g.clear(app.cs.void_background);
+            let (baseline, edited) = baseline_and_edited(app, self.showing_baseline);
+            draw_half(g, app, baseline, true);
+            draw_half(g, app, edited, false);
+            for label in &self.split_labels {
+                label.draw(g);
+            }
+        }
+        self.top_center.draw(g);
+        self.summary.draw(g);
+    }
+
+    fn on_destroy(&mut self, _: &mut EventCtx, app: &mut App) {
+        if self.showing_baseline {
+            self.swap(app);
+        }
+        app.secondary = None;
+        // Whoever spawned this mode expects the edited map with nothing running yet
+        app.primary.clear_sim();
+    }
+}
+
+// They're placed relative to the window, so redo them when it's resized
+fn make_split_labels(ctx: &mut EventCtx, app: &App, showing_baseline: bool) -> Vec<Composite> {
+    let (_, edited) = baseline_and_edited(app, showing_baseline);
+    vec![
+        ("Original map".to_string(), 0.25),
+        (edited.map.get_edits().edits_name.clone(), 0.75),
+    ]
+    .into_iter()
+    .map(|(label, pct)| {
+        Composite::new(Line(label).small_heading().draw(ctx))
+            .aligned(
+                HorizontalAlignment::Centered(pct * ctx.canvas.window_width),
+                VerticalAlignment::Top,
+            )
+            .build(ctx)
+    })
+    .collect()
+}
+
+fn baseline_and_edited(app: &App, showing_baseline: bool) -> (&PerMap, &PerMap) {
+    if showing_baseline {
+        (&app.primary, app.secondary.as_ref().unwrap())
+    } else {
+        (app.secondary.as_ref().unwrap(), &app.primary)
+    }
+}
+
+// Draws one map in half of the screen, the way the minimap does. The center of the canvas winds
+// up in the middle of the half, so panning and zooming move both halves together. There's no
+// detailed view of lanes and buildings here, no matter the zoom.
+fn draw_half(g: &mut GfxCtx, app: &App, per_map: &PerMap, left: bool) {
+    let half_width = g.canvas.window_width / 2.0;
+    let x1 = if left { 0.0 } else { half_width };
+    let zoom = g.canvas.cam_zoom;
+    let center = g.canvas.center_to_map_pt();
+    g.fork(
+        Pt2D::new(
+            center.x() - half_width / 2.0 / zoom,
+            center.y() - g.canvas.window_height / 2.0 / zoom,
+        ),
+        ScreenPt::new(x1, 0.0),
+        zoom,
+        None,
+    );
+    g.enable_clipping(ScreenRectangle {
+        x1,
+        y1: 0.0,
+        x2: x1 + half_width,
+        y2: g.canvas.window_height,
+    });
+    g.redraw(&per_map.draw_map.boundary_polygon);
+    g.redraw(&per_map.draw_map.draw_all_areas);
+    g.redraw(&per_map.draw_map.draw_all_unzoomed_parking_lots);
+    g.redraw(&per_map.draw_map.draw_all_unzoomed_roads_and_intersections);
+    g.redraw(&per_map.draw_map.draw_all_buildings);
+
+    let mut cache = per_map.draw_map.agents.borrow_mut();
+    cache.draw_unzoomed_agents(
+        &per_map.sim,
+        &per_map.map,
+        &app.unzoomed_agents,
+        g,
+        if app.opts.large_unzoomed_agents {
+            Some(Distance::meters(10.0) / zoom)
+        } else {
+            None
+        },
+        app.opts.debug_all_agents,
+        &app.cs,
+    );
+
+    g.disable_clipping();
+    g.unfork();
+}
+
+fn make_summary(ctx: &mut EventCtx, app: &App, showing_baseline: bool) -> Composite {
+    let (baseline, edited) = baseline_and_edited(app, showing_baseline);
+
+    let mut num_same = 0;
+    let mut num_faster = 0;
+    let mut num_slower = 0;
+    let mut sum_faster = Duration::ZERO;
+    let mut sum_slower = Duration::ZERO;
+    for (_, b, a, _) in edited
+        .sim
+        .get_analytics()
+        .both_finished_trips(edited.sim.time(), baseline.sim.get_analytics())
+    {
+        if a == b {
+            num_same += 1;
+        } else if a < b {
+            num_faster += 1;
+            sum_faster += b - a;
+        } else {
+            num_slower += 1;
+            sum_slower += a - b;
+        }
+    }
+    let (baseline_finished, _) = baseline.sim.num_trips();
+    let (edited_finished, _) = edited.sim.num_trips();
+
+    let mut txt = Text::from(Line("Trip times with the edits").small_heading());
+    txt.add(Line(format!(
+        "{} trips finished on the original map, {} with the edits",
+        prettyprint_usize(baseline_finished),
+        prettyprint_usize(edited_finished)
+    )));
+    txt.add(
+        Line(format!(
+            "{} trips faster, saving {}",
+            prettyprint_usize(num_faster),
+            sum_faster
+        ))
+        .fg(Color::GREEN),
+    );
+    txt.add(
+        Line(format!(
+            "{} trips slower, losing {}",
+            prettyprint_usize(num_slower),
+            sum_slower
+        ))
+        .fg(Color::RED),
+    );
+    txt.add(Line(format!("{} trips unchanged", prettyprint_usize(num_same))).secondary());
+
+    Composite::new(txt.draw(ctx))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Bottom)
+        .build(ctx)
+}
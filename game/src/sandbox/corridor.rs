@@ -0,0 +1,139 @@
+use crate::app::App;
+use crate::common::CommonState;
+use crate::game::{State, Transition};
+use crate::helpers::ID;
+use crate::layer::corridor::CorridorTravelTimes;
+use ezgui::{
+    hotkey, Btn, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use map_model::RoadID;
+
+// Pick a sequence of connected roads, then start measuring travel times along them.
+pub struct CorridorPicker {
+    roads: Vec<RoadID>,
+    composite: Composite,
+    draw: Drawable,
+}
+
+impl CorridorPicker {
+    pub fn new(ctx: &mut EventCtx, app: &App, start: RoadID) -> Box<dyn State> {
+        Box::new(CorridorPicker::with_roads(ctx, app, vec![start]))
+    }
+
+    fn with_roads(ctx: &mut EventCtx, app: &App, roads: Vec<RoadID>) -> CorridorPicker {
+        let map = &app.primary.map;
+        let mut batch = GeomBatch::new();
+        for (idx, r) in roads.iter().enumerate() {
+            let road = map.get_r(*r);
+            batch.push(app.cs.after_changes.alpha(0.5), road.get_thick_polygon(map));
+            batch.append(
+                Text::from(Line((idx + 1).to_string()))
+                    .with_bg()
+                    .render_to_batch(ctx.prerender)
+                    .scale(0.5)
+                    .centered_on(road.center_pts.middle()),
+            );
+        }
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("Track a corridor").small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(
+                Line("Click roads connected to the end, in the direction vehicles travel")
+                    .secondary(),
+            )
+            .wrap_to_pct(ctx, 20)
+            .draw(ctx),
+            format!("{} roads", roads.len()).draw_text(ctx),
+            Widget::row(vec![
+                if roads.len() > 1 {
+                    Btn::text_fg("remove last road").build_def(ctx, hotkey(Key::Backspace))
+                } else {
+                    Btn::text_fg("remove last road").inactive(ctx)
+                },
+                Btn::text_fg("start tracking").build_def(ctx, hotkey(Key::Enter)),
+            ]),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+
+        CorridorPicker {
+            roads,
+            composite,
+            draw: ctx.upload(batch),
+        }
+    }
+
+    // The road has to continue from the last one
+    fn can_add(&self, app: &App, r: RoadID) -> bool {
+        if self.roads.contains(&r) {
+            return false;
+        }
+        let map = &app.primary.map;
+        let last = map.get_r(*self.roads.last().unwrap());
+        let road = map.get_r(r);
+        let end = if self.roads.len() == 1 {
+            // Either direction works for the first road
+            vec![last.src_i, last.dst_i]
+        } else {
+            let prev = map.get_r(self.roads[self.roads.len() - 2]);
+            // Whichever end of the last road isn't shared with the one before it
+            if last.src_i == prev.src_i || last.src_i == prev.dst_i {
+                vec![last.dst_i]
+            } else {
+                vec![last.src_i]
+            }
+        };
+        end.contains(&road.src_i) || end.contains(&road.dst_i)
+    }
+}
+
+impl State for CorridorPicker {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+        }
+        if let Some(ID::Lane(l)) = app.primary.current_selection {
+            let r = app.primary.map.get_l(l).parent;
+            if self.can_add(app, r) && app.per_obj.left_click(ctx, "add this road") {
+                let mut roads = self.roads.clone();
+                roads.push(r);
+                *self = CorridorPicker::with_roads(ctx, app, roads);
+            }
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "remove last road" => {
+                    let mut roads = self.roads.clone();
+                    roads.pop();
+                    *self = CorridorPicker::with_roads(ctx, app, roads);
+                }
+                "start tracking" => {
+                    let idx = app.primary.sim.track_corridor(self.roads.clone());
+                    app.layer = Some(Box::new(CorridorTravelTimes::new(ctx, app, idx)));
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
@@ -7,7 +7,10 @@ use ezgui::{
     Autocomplete, Btn, Composite, EventCtx, GfxCtx, Line, LinePlot, Outcome, PlotOptions, Series,
     Text, Widget,
 };
+use geom::Statistic;
+use map_model::IntersectionID;
 use sim::{CarID, VehicleType};
+use std::collections::BTreeSet;
 
 pub struct ActiveTraffic {
     composite: Composite,
@@ -108,6 +111,8 @@ impl TransitRoutes {
                     .collect(),
             )
             .flex_wrap(ctx, 80),
+            time_between_stops(ctx, app),
+            transit_signal_priority(ctx, app),
             Line("Currently inactive routes").draw(ctx),
             Text::from_multiline(
                 inactive_routes
@@ -126,6 +131,112 @@ impl TransitRoutes {
     }
 }
 
+// Compared to the baseline, where nobody drives in the bus lanes, this shows what violations cost
+// and what enforcement saves.
+fn time_between_stops(ctx: &EventCtx, app: &App) -> Widget {
+    let now = app.primary.sim.time();
+    let times = app.primary.sim.get_analytics().bus_time_between_stops(now);
+    if times.is_empty() {
+        return Widget::nothing();
+    }
+    let baseline = app
+        .has_prebaked()
+        .map(|_| app.prebaked().bus_time_between_stops(now));
+
+    let mut txt = Text::from(Line("Average time between stops").small_heading());
+    let violations = app.primary.sim.get_analytics().bus_lane_violations.len();
+    if violations > 0 {
+        txt.add(
+            Line(format!(
+                "Drivers entered bus lanes {} times so far",
+                abstutil::prettyprint_usize(violations)
+            ))
+            .secondary(),
+        );
+    }
+    let mut rows: Vec<(String, String)> = Vec::new();
+    for (r, hgram) in times {
+        let mean = hgram.select(Statistic::Mean).unwrap();
+        let line = match baseline
+            .as_ref()
+            .and_then(|b| b.get(&r))
+            .and_then(|h| h.select(Statistic::Mean))
+        {
+            Some(before) => format!("{} ({} in the baseline)", mean, before),
+            None => mean.to_string(),
+        };
+        rows.push((app.primary.map.get_br(r).full_name.clone(), line));
+    }
+    rows.sort();
+    for (name, line) in rows {
+        txt.add(Line(format!("{}: {}", name, line)));
+    }
+    txt.draw(ctx)
+}
+
+// How much buses save waiting at signals with transit signal priority, and what it costs everybody
+// else there
+fn transit_signal_priority(ctx: &EventCtx, app: &App) -> Widget {
+    let equipped: BTreeSet<IntersectionID> = app
+        .primary
+        .map
+        .all_intersections()
+        .iter()
+        .filter(|i| {
+            app.primary
+                .map
+                .maybe_get_traffic_signal(i.id)
+                .map(|ts| ts.transit_signal_priority)
+                .unwrap_or(false)
+        })
+        .map(|i| i.id)
+        .collect();
+    if equipped.is_empty() {
+        return Widget::nothing();
+    }
+    let now = app.primary.sim.time();
+    let analytics = app.primary.sim.get_analytics();
+
+    let mut txt = Text::from(Line("Transit signal priority").small_heading());
+    let extended = analytics
+        .transit_signal_priority
+        .iter()
+        .filter(|(_, _, _, extended)| *extended)
+        .count();
+    txt.add(
+        Line(format!(
+            "{} signals, {} green extensions, {} early greens",
+            equipped.len(),
+            abstutil::prettyprint_usize(extended),
+            abstutil::prettyprint_usize(analytics.transit_signal_priority.len() - extended)
+        ))
+        .secondary(),
+    );
+    let (bus, other) = analytics.bus_and_other_delays(&equipped, now);
+    let baseline = app
+        .has_prebaked()
+        .map(|_| app.prebaked().bus_and_other_delays(&equipped, now));
+    for (name, hgram, before) in vec![
+        ("Buses", bus, baseline.as_ref().map(|(b, _)| b)),
+        ("Everybody else", other, baseline.as_ref().map(|(_, o)| o)),
+    ] {
+        let mean = match hgram.select(Statistic::Mean) {
+            Some(mean) => mean,
+            None => {
+                continue;
+            }
+        };
+        txt.add(Line(match before.and_then(|h| h.select(Statistic::Mean)) {
+            Some(before) => format!(
+                "{}: {} average delay ({} in the baseline)",
+                name, mean, before
+            ),
+            None => format!("{}: {} average delay", name, mean),
+        }));
+    }
+    txt.draw(ctx)
+}
+
 impl State for TransitRoutes {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         let bus = match self.composite.event(ctx) {
@@ -5,11 +5,12 @@ use crate::sandbox::dashboards::DashTab;
 use abstutil::prettyprint_usize;
 use ezgui::{
     Choice, Color, CompareTimes, Composite, DrawWithTooltips, EventCtx, GeomBatch, GfxCtx, Line,
-    Outcome, Text, Widget,
+    Outcome, Text, TextExt, Widget,
 };
 use geom::{Distance, Duration, Polygon, Pt2D};
-use sim::TripMode;
-use std::collections::BTreeSet;
+use map_model::Neighborhood;
+use sim::{TripID, TripMode};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub struct TripSummaries {
     composite: Composite,
@@ -18,7 +19,20 @@ pub struct TripSummaries {
 
 impl TripSummaries {
     pub fn new(ctx: &mut EventCtx, app: &App, filter: Filter) -> Box<dyn State> {
-        let filters = vec![
+        let neighborhoods: BTreeMap<String, Polygon> =
+            Neighborhood::load_all(app.primary.map.get_name())
+                .into_iter()
+                .filter_map(|n| {
+                    let poly = n.polygon(&app.primary.map)?;
+                    Some((n.name, poly))
+                })
+                .collect();
+        let starting_in = filter
+            .starting_in
+            .as_ref()
+            .and_then(|name| neighborhoods.get(name));
+
+        let mut filters = vec![
             Widget::dropdown(
                 ctx,
                 "filter",
@@ -32,15 +46,25 @@ impl TripSummaries {
             ),
             checkbox_per_mode(ctx, app, &filter.modes),
         ];
+        if !neighborhoods.is_empty() {
+            let mut choices = vec![Choice::new("anywhere", None)];
+            for name in neighborhoods.keys() {
+                choices.push(Choice::new(name, Some(name.clone())));
+            }
+            filters.push(Widget::row(vec![
+                "Starting in:".draw_text(ctx),
+                Widget::dropdown(ctx, "starting in", filter.starting_in.clone(), choices),
+            ]));
+        }
 
         Box::new(TripSummaries {
             composite: Composite::new(Widget::col(vec![
                 DashTab::TripSummaries.picker(ctx, app),
                 Widget::row(filters).centered_horiz(),
-                summary(ctx, app, &filter),
+                summary(ctx, app, &filter, starting_in),
                 Widget::row(vec![
-                    contingency_table(ctx, app, &filter).centered_vert(),
-                    scatter_plot(ctx, app, &filter),
+                    contingency_table(ctx, app, &filter, starting_in).centered_vert(),
+                    scatter_plot(ctx, app, &filter, starting_in),
                 ])
                 .evenly_spaced(),
             ]))
@@ -59,6 +83,11 @@ impl State for TripSummaries {
                 let mut filter = Filter {
                     changes_pct: self.composite.dropdown_value("filter"),
                     modes: BTreeSet::new(),
+                    starting_in: if self.composite.has_widget("starting in") {
+                        self.composite.dropdown_value("starting in")
+                    } else {
+                        None
+                    },
                 };
                 for m in TripMode::all() {
                     if self.composite.is_checked(m.ongoing_verb()) {
@@ -84,7 +113,12 @@ impl State for TripSummaries {
     }
 }
 
-fn summary(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
+fn summary(
+    ctx: &mut EventCtx,
+    app: &App,
+    filter: &Filter,
+    starting_in: Option<&Polygon>,
+) -> Widget {
     if app.has_prebaked().is_none() {
         return Widget::nothing();
     }
@@ -94,13 +128,13 @@ fn summary(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     let mut num_slower = 0;
     let mut sum_faster = Duration::ZERO;
     let mut sum_slower = Duration::ZERO;
-    for (b, a, mode) in app
+    for (id, b, a, mode) in app
         .primary
         .sim
         .get_analytics()
         .both_finished_trips(app.primary.sim.time(), app.prebaked())
     {
-        if !filter.modes.contains(&mode) {
+        if !filter.modes.contains(&mode) || !starts_in(app, id, starting_in) {
             continue;
         }
         let same = if let Some(pct) = filter.changes_pct {
@@ -160,12 +194,17 @@ fn summary(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     .evenly_spaced()])
 }
 
-fn scatter_plot(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
+fn scatter_plot(
+    ctx: &mut EventCtx,
+    app: &App,
+    filter: &Filter,
+    starting_in: Option<&Polygon>,
+) -> Widget {
     if app.has_prebaked().is_none() {
         return Widget::nothing();
     }
 
-    let points = filter.get_trips(app);
+    let points = filter.get_trips(app, starting_in);
     if points.is_empty() {
         return Widget::nothing();
     }
@@ -186,7 +225,12 @@ fn scatter_plot(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     .padding(10)
 }
 
-fn contingency_table(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
+fn contingency_table(
+    ctx: &mut EventCtx,
+    app: &App,
+    filter: &Filter,
+    starting_in: Option<&Polygon>,
+) -> Widget {
     if app.has_prebaked().is_none() {
         return Widget::nothing();
     }
@@ -194,7 +238,7 @@ fn contingency_table(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     let total_width = 500.0;
     let total_height = 300.0;
 
-    let points = filter.get_trips(app);
+    let points = filter.get_trips(app, starting_in);
     if points.is_empty() {
         return Widget::nothing();
     }
@@ -319,6 +363,8 @@ fn contingency_table(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
 pub struct Filter {
     changes_pct: Option<f64>,
     modes: BTreeSet<TripMode>,
+    // The name of a neighborhood
+    starting_in: Option<String>,
 }
 
 impl Filter {
@@ -326,18 +372,20 @@ impl Filter {
         Filter {
             changes_pct: None,
             modes: TripMode::all().into_iter().collect(),
+            starting_in: None,
         }
     }
 
-    fn get_trips(&self, app: &App) -> Vec<(Duration, Duration)> {
+    fn get_trips(&self, app: &App, starting_in: Option<&Polygon>) -> Vec<(Duration, Duration)> {
         let mut points = Vec::new();
-        for (b, a, mode) in app
+        for (id, b, a, mode) in app
             .primary
             .sim
             .get_analytics()
             .both_finished_trips(app.primary.sim.time(), app.prebaked())
         {
             if self.modes.contains(&mode)
+                && starts_in(app, id, starting_in)
                 && self
                     .changes_pct
                     .map(|pct| pct_diff(a, b) > pct)
@@ -350,6 +398,12 @@ impl Filter {
     }
 }
 
+fn starts_in(app: &App, id: TripID, starting_in: Option<&Polygon>) -> bool {
+    starting_in
+        .map(|poly| poly.contains_pt(app.primary.sim.trip_info(id).start.pt(&app.primary.map)))
+        .unwrap_or(true)
+}
+
 fn pct_diff(a: Duration, b: Duration) -> f64 {
     if a >= b {
         (a / b) - 1.0
@@ -1,25 +1,30 @@
 use crate::app::App;
-use crate::game::{DrawBaselayer, State, Transition};
+use crate::game::{msg, DrawBaselayer, State, Transition};
 use crate::helpers::{
     checkbox_per_mode, cmp_duration_shorter, color_for_mode, color_for_trip_phase,
+    copy_to_clipboard,
 };
 use crate::info::{OpenTrip, Tab};
+use crate::layer::trip_query::TripQueryResults;
 use crate::sandbox::dashboards::DashTab;
 use crate::sandbox::SandboxMode;
 use abstutil::prettyprint_usize;
 use ezgui::{
-    Btn, Checkbox, Color, Composite, EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome,
+    Btn, Checkbox, Choice, Color, Composite, EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome,
     RewriteColor, ScreenDims, ScreenPt, Text, TextExt, Widget,
 };
 use geom::{Distance, Duration, Polygon, Pt2D, Time};
-use sim::{TripEndpoint, TripID, TripMode};
-use std::collections::{BTreeSet, HashMap};
+use map_model::Neighborhood;
+use sim::{TripEndpoint, TripID, TripMode, TripQuery};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 const ROWS: usize = 8;
 
 pub struct TripTable {
     composite: Composite,
     opts: Options,
+    // Every row matching the filters, not just the current page, as tab-separated values
+    tsv: String,
 }
 
 struct Options {
@@ -30,7 +35,13 @@ struct Options {
     off_map_ends: bool,
     unmodified_trips: bool,
     modified_trips: bool,
+    starting_in: Option<String>,
+    // The last query applied, parsed, and the neighborhoods it needs
+    query: Option<(String, TripQuery, BTreeMap<String, Polygon>)>,
     skip: usize,
+
+    // Loaded once, only the ones overlapping this map
+    neighborhoods: BTreeMap<String, Polygon>,
 }
 
 impl Options {
@@ -66,18 +77,30 @@ impl TripTable {
             off_map_ends: true,
             unmodified_trips: true,
             modified_trips: true,
+            starting_in: None,
+            query: None,
             skip: 0,
+            neighborhoods: Neighborhood::load_all(app.primary.map.get_name())
+                .into_iter()
+                .filter_map(|n| {
+                    let poly = n.polygon(&app.primary.map)?;
+                    Some((n.name, poly))
+                })
+                .collect(),
         };
+        let (composite, tsv) = make(ctx, app, &opts);
         Box::new(TripTable {
-            composite: make(ctx, app, &opts),
+            composite,
             opts,
+            tsv,
         })
     }
 
     fn recalc(&mut self, ctx: &mut EventCtx, app: &App) {
-        let mut new = make(ctx, app, &self.opts);
+        let (mut new, tsv) = make(ctx, app, &self.opts);
         new.restore(ctx, &self.composite);
         self.composite = new;
+        self.tsv = tsv;
     }
 }
 
@@ -117,6 +140,34 @@ impl State for TripTable {
                     self.opts.skip += ROWS;
                     self.recalc(ctx, app);
                 }
+                "copy to clipboard" => {
+                    copy_to_clipboard(ctx, app, self.tsv.clone());
+                }
+                "apply query" => {
+                    let query = self.composite.text_box("query").trim().to_string();
+                    if query.is_empty() {
+                        self.opts.query = None;
+                    } else {
+                        match TripQuery::parse(&query).and_then(|q| {
+                            let neighborhoods = q.load_neighborhoods(&app.primary.map)?;
+                            Ok((q, neighborhoods))
+                        }) {
+                            Ok((q, neighborhoods)) => {
+                                self.opts.query = Some((query, q, neighborhoods));
+                            }
+                            Err(err) => {
+                                return Transition::Push(msg("Bad query", vec![err]));
+                            }
+                        }
+                    }
+                    self.opts.skip = 0;
+                    self.recalc(ctx, app);
+                }
+                "show matching trips on the map" => {
+                    let query = self.opts.query.as_ref().unwrap().0.clone();
+                    app.layer = Some(Box::new(TripQueryResults::new(ctx, app, query)));
+                    return Transition::Pop;
+                }
                 x => {
                     if let Ok(idx) = x.parse::<usize>() {
                         let trip = TripID(idx);
@@ -169,6 +220,14 @@ impl State for TripTable {
                     self.opts.skip = 0;
                     self.recalc(ctx, app);
                 }
+                if !self.opts.neighborhoods.is_empty() {
+                    let starting_in: Option<String> = self.composite.dropdown_value("starting in");
+                    if self.opts.starting_in != starting_in {
+                        self.opts.starting_in = starting_in;
+                        self.opts.skip = 0;
+                        self.recalc(ctx, app);
+                    }
+                }
             }
         };
 
@@ -197,7 +256,7 @@ struct Entry {
     percent_waiting: usize,
 }
 
-fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
+fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> (Composite, String) {
     // Only make one pass through prebaked data
     let trip_times_before = if app.has_prebaked().is_some() {
         let mut times = HashMap::new();
@@ -214,6 +273,12 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
     // Gather raw data
     let mut data = Vec::new();
     let sim = &app.primary.sim;
+    // Matched every time, so trips that finished since the query was applied show up
+    let matching: Option<BTreeSet<TripID>> = opts.query.as_ref().map(|(_, q, neighborhoods)| {
+        q.find_since(sim, &app.primary.map, neighborhoods, 0)
+            .into_iter()
+            .collect()
+    });
     let mut aborted = 0;
     for (_, id, maybe_mode, duration_after) in &sim.get_analytics().finished_trips {
         let mode = if let Some(m) = maybe_mode {
@@ -242,6 +307,16 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
         if !opts.modified_trips && trip.modified {
             continue;
         }
+        if let Some(ref name) = opts.starting_in {
+            if !opts.neighborhoods[name].contains_pt(trip.start.pt(&app.primary.map)) {
+                continue;
+            }
+        }
+        if let Some(ref matching) = matching {
+            if !matching.contains(id) {
+                continue;
+            }
+        }
 
         let (_, waiting) = sim.finished_trip_time(*id).unwrap();
         let duration_before = if let Some(ref times) = trip_times_before {
@@ -283,6 +358,39 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
     }
     let total_rows = data.len();
 
+    // Pastes into a spreadsheet as cells
+    let mut tsv = vec![{
+        let mut header = vec!["Trip ID"];
+        if app.primary.has_modified_trips {
+            header.push("Modified");
+        }
+        header.extend(vec!["Type", "Departure", "Duration"]);
+        if app.has_prebaked().is_some() {
+            header.push("Duration before");
+        }
+        header.extend(vec!["Time spent waiting", "Percent waiting"]);
+        header.join("\t")
+    }];
+    for x in &data {
+        let mut row = vec![x.trip.0.to_string()];
+        if app.primary.has_modified_trips {
+            row.push(if x.modified { "Yes" } else { "No" }.to_string());
+        }
+        row.extend(vec![
+            x.mode.ongoing_verb().to_string(),
+            x.departure.ampm_tostring(),
+            x.duration_after.to_string(),
+        ]);
+        if app.has_prebaked().is_some() {
+            row.push(x.duration_before.to_string());
+        }
+        row.extend(vec![
+            x.waiting.to_string(),
+            format!("{}%", x.percent_waiting),
+        ]);
+        tsv.push(row.join("\t"));
+    }
+
     // Render data
     let mut rows = Vec::new();
     for x in data.into_iter().skip(opts.skip).take(ROWS) {
@@ -380,6 +488,41 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
             Widget::nothing()
         },
     ]));
+    if !opts.neighborhoods.is_empty() {
+        let mut choices = vec![Choice::new("anywhere", None)];
+        for name in opts.neighborhoods.keys() {
+            choices.push(Choice::new(name, Some(name.clone())));
+        }
+        col.push(Widget::row(vec![
+            "Starting in:".draw_text(ctx),
+            Widget::dropdown(ctx, "starting in", opts.starting_in.clone(), choices),
+        ]));
+    }
+    col.push(Widget::row(vec![
+        "Query:".draw_text(ctx).centered_vert(),
+        Widget::text_entry(
+            ctx,
+            opts.query
+                .as_ref()
+                .map(|(q, _, _)| q.clone())
+                .unwrap_or_else(String::new),
+            false,
+        )
+        .named("query"),
+        Btn::text_bg2("apply query").build_def(ctx, None),
+        if opts.query.is_some() {
+            Btn::text_bg2("show matching trips on the map").build_def(ctx, None)
+        } else {
+            Btn::text_bg2("show matching trips on the map").inactive(ctx)
+        },
+    ]));
+    col.push(
+        Text::from(
+            Line("Like: mode=drive AND duration>30min AND destination in some neighborhood")
+                .secondary(),
+        )
+        .draw(ctx),
+    );
     let (_, unfinished) = app.primary.sim.num_trips();
     col.push(
         Text::from_multiline(vec![
@@ -417,6 +560,9 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
         } else {
             Btn::text_fg(">").inactive(ctx)
         },
+        Btn::text_fg("copy to clipboard")
+            .build_def(ctx, None)
+            .margin_left(32),
     ]));
 
     col.push(make_table(
@@ -435,9 +581,12 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
         .centered_horiz(),
     );
 
-    Composite::new(Widget::col(col))
-        .exact_size_percent(90, 90)
-        .build(ctx)
+    (
+        Composite::new(Widget::col(col))
+            .exact_size_percent(90, 90)
+            .build(ctx),
+        tsv.join("\n"),
+    )
 }
 
 // TODO Figure out a nicer API to construct generic sortable tables.
@@ -561,10 +710,7 @@ fn preview_route(g: &mut GfxCtx, app: &App, id: TripID) -> GeomBatch {
                 Color::hex("#5B5B5B"),
                 Color::hex("#CC4121"),
             ))
-            .centered_on(match trip.start {
-                TripEndpoint::Bldg(b) => app.primary.map.get_b(b).label_center,
-                TripEndpoint::Border(i, _) => app.primary.map.get_i(i).polygon.center(),
-            }),
+            .centered_on(trip.start.pt(&app.primary.map)),
     );
     batch.append(
         GeomBatch::mapspace_svg(g.prerender, "system/assets/timeline/goal_pos.svg")
@@ -574,10 +720,7 @@ fn preview_route(g: &mut GfxCtx, app: &App, id: TripID) -> GeomBatch {
                 Color::hex("#5B5B5B"),
                 Color::hex("#CC4121"),
             ))
-            .centered_on(match trip.end {
-                TripEndpoint::Bldg(b) => app.primary.map.get_b(b).label_center,
-                TripEndpoint::Border(i, _) => app.primary.map.get_i(i).polygon.center(),
-            }),
+            .centered_on(trip.end.pt(&app.primary.map)),
     );
 
     batch
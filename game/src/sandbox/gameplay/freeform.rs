@@ -146,6 +146,12 @@ pub fn make_change_traffic(btn: ScreenRectangle, current: String) -> Box<dyn Sta
                          to 5pm.",
                     ),
                 );
+                list.push(
+                    Choice::new("trips to and from school", "school_trips".to_string()).tooltip(
+                        "Students leave home in the morning for the closest school, then return \
+                         in the afternoon. Maps without schools in OpenStreetMap will be empty.",
+                    ),
+                );
                 list.push(
                     Choice::new("random unrealistic trips", "random".to_string()).tooltip(
                         "Lots of trips will start at midnight, but not constantly appear through \
@@ -427,7 +433,7 @@ impl State for AgentSpawner {
 }
 
 // TODO This exists in a few other places, in less clear forms...
-fn path_request(
+pub fn path_request(
     from: TripEndpoint,
     to: TripEndpoint,
     mode: TripMode,
@@ -5,7 +5,7 @@ mod freeform;
 mod play_scenario;
 mod tutorial;
 
-pub use self::freeform::spawn_agents_around;
+pub use self::freeform::{path_request, spawn_agents_around};
 pub use self::tutorial::{Tutorial, TutorialPointer, TutorialState};
 use crate::app::App;
 use crate::challenges::{challenges_picker, Challenge};
@@ -22,7 +22,10 @@ use ezgui::{
 use geom::{Duration, Polygon};
 use map_model::{EditCmd, EditIntersection, Map, MapEdits};
 use rand_xorshift::XorShiftRng;
-use sim::{Analytics, OrigPersonID, Scenario, ScenarioGenerator, ScenarioModifier};
+use sim::{
+    Analytics, CapturedRoutes, CarOwnership, OrigPersonID, Population, Scenario, ScenarioGenerator,
+    ScenarioModifier,
+};
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum GameplayMode {
@@ -113,8 +116,27 @@ impl GameplayMode {
                 ScenarioGenerator::small_run(map)
             })
             .generate(map, &mut rng, timer)
+        } else if name == "school_trips" {
+            ScenarioGenerator::school_trips(map, &mut rng, timer)
         } else if name == "home_to_work" {
-            ScenarioGenerator::proletariat_robot(map, &mut rng, timer)
+            // If the importer synthesized people from census data, use that instead of spreading
+            // people around uniformly.
+            match Population::load(map, timer) {
+                Ok(population) => {
+                    let ownership = CarOwnership::load(map, timer).unwrap_or_else(|err| {
+                        timer.error(format!("{}; not limiting cars per household", err));
+                        None
+                    });
+                    ScenarioGenerator::from_population(
+                        map,
+                        &population,
+                        ownership.as_ref(),
+                        &mut rng,
+                        timer,
+                    )
+                }
+                Err(_) => ScenarioGenerator::proletariat_robot(map, &mut rng, timer),
+            }
         } else {
             let path = abstutil::path_scenario(map.get_name(), &name);
             let mut scenario = match abstutil::maybe_read_binary(path.clone(), timer) {
@@ -160,7 +182,11 @@ impl GameplayMode {
                 EditCmd::ChangeLaneType { .. }
                 | EditCmd::ReverseLane { .. }
                 | EditCmd::ChangeSpeedLimit { .. }
-                | EditCmd::ChangeAccessRestrictions { .. } => {
+                | EditCmd::ChangeSpeedLimitSchedule { .. }
+                | EditCmd::ChangeAccessRestrictions { .. }
+                | EditCmd::ChangeParkingPolicy { .. }
+                | EditCmd::ChangeTruckAccess { .. }
+                | EditCmd::ChangeBusLaneCamera { .. } => {
                     if !self.can_edit_lanes() {
                         return false;
                     }
@@ -174,6 +200,10 @@ impl GameplayMode {
                     }
                     _ => {}
                 },
+                // Part of editing traffic signals
+                EditCmd::ChangeLeadingPedestrianInterval { .. }
+                | EditCmd::ChangeRightTurnOnRed { .. }
+                | EditCmd::ChangeTransitSignalPriority { .. } => {}
             }
         }
         true
@@ -191,6 +221,22 @@ impl GameplayMode {
                 app.primary.current_flags.sim_flags.make_rng(),
                 timer,
             ) {
+                if app.primary.current_flags.sim_flags.opts.fixed_routes {
+                    match abstutil::maybe_read_binary::<CapturedRoutes>(
+                        abstutil::path_prebaked_routes(&scenario.map_name, &scenario.scenario_name),
+                        timer,
+                    ) {
+                        Ok(routes) => {
+                            app.primary.sim.use_fixed_routes(routes);
+                        }
+                        Err(err) => {
+                            println!(
+                                "--fixed_routes, but no prebaked routes for {}: {}",
+                                scenario.scenario_name, err
+                            );
+                        }
+                    }
+                }
                 scenario.instantiate(
                     &mut app.primary.sim,
                     &app.primary.map,
@@ -1,24 +1,32 @@
 use crate::app::App;
-use crate::common::CityPicker;
-use crate::edit::EditMode;
+use crate::common::{CityPicker, CommonState};
+use crate::edit::{apply_map_edits, EditMode};
 use crate::game::{msg, State, Transition, WizardState};
-use crate::helpers::{checkbox_per_mode, nice_map_name};
+use crate::helpers::{checkbox_per_mode, nice_map_name, ID};
 use crate::sandbox::gameplay::freeform::make_change_traffic;
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
 use crate::sandbox::{SandboxControls, SandboxMode};
+use abstutil::prettyprint_usize;
 use ezgui::{
-    hotkey, lctrl, AreaSlider, Btn, Choice, Color, Composite, EventCtx, GeomBatch, GfxCtx,
-    HorizontalAlignment, Key, Line, Outcome, Spinner, Text, TextExt, VerticalAlignment, Widget,
+    hotkey, lctrl, AreaSlider, Btn, Checkbox, Choice, Color, Composite, Drawable, EventCtx,
+    GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Spinner, Text, TextExt,
+    VerticalAlignment, Widget,
+};
+use geom::{Distance, Duration, Polygon, Time};
+use map_model::{
+    EditCmd, IntersectionID, LaneID, LaneType, Map, Neighborhood, PathConstraints, PathRequest,
+    PathStep, Position, RoadID,
 };
-use geom::Polygon;
 use maplit::btreeset;
-use sim::{ScenarioModifier, TripMode};
+use sim::{ScenarioModifier, TripEndpoint, TripID, TripMode};
 use std::collections::BTreeSet;
 
 pub struct PlayScenario {
     top_center: Composite,
     scenario_name: String,
     modifiers: Vec<ScenarioModifier>,
+    // Only used to keep evacuation progress up to date
+    time: Time,
 }
 
 impl PlayScenario {
@@ -32,6 +40,7 @@ impl PlayScenario {
             top_center: make_top_center(ctx, app, name, &modifiers),
             scenario_name: name.to_string(),
             modifiers,
+            time: app.primary.sim.time(),
         })
     }
 }
@@ -47,6 +56,17 @@ impl GameplayState for PlayScenario {
         // on_destroy can wipe this out.
         app.primary.has_modified_trips = !self.modifiers.is_empty();
 
+        if app.primary.sim.time() != self.time {
+            self.time = app.primary.sim.time();
+            if self
+                .modifiers
+                .iter()
+                .any(|m| matches!(m, ScenarioModifier::Evacuate { .. }))
+            {
+                self.top_center = make_top_center(ctx, app, &self.scenario_name, &self.modifiers);
+            }
+        }
+
         match self.top_center.event(ctx) {
             Some(Outcome::Clicked(x)) => match x.as_ref() {
                 "change map" => {
@@ -109,7 +129,7 @@ fn make_top_center(
     scenario_name: &str,
     modifiers: &Vec<ScenarioModifier>,
 ) -> Composite {
-    let rows = vec![
+    let mut rows = vec![
         Widget::row(vec![
             Line("Sandbox").small_heading().draw(ctx),
             Widget::draw_batch(
@@ -145,6 +165,25 @@ fn make_top_center(
             Widget::nothing()
         },
     ];
+    for m in modifiers {
+        if let ScenarioModifier::Evacuate {
+            region,
+            exits,
+            start,
+            window,
+            ..
+        } = m
+        {
+            rows.push(evacuation_status(
+                ctx,
+                app,
+                &region.name,
+                exits,
+                *start,
+                *window,
+            ));
+        }
+    }
 
     Composite::new(Widget::col(rows))
         .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
@@ -192,6 +231,7 @@ impl EditScenarioModifiers {
         rows.push(Widget::row(vec![
             Btn::text_bg2("New modification").build_def(ctx, None),
             Btn::text_bg2("Change trip mode").build_def(ctx, None),
+            Btn::text_bg2("Plan an evacuation").build_def(ctx, None),
         ]));
         rows.push(
             Widget::row(vec![
@@ -243,9 +283,47 @@ impl State for EditScenarioModifiers {
                         self.modifiers.clone(),
                     ));
                 }
+                "Plan an evacuation" => {
+                    let map_name = app.primary.map.get_name().to_string();
+                    if abstutil::list_all_objects(abstutil::path_all_neighborhoods(&map_name))
+                        .is_empty()
+                    {
+                        return Transition::Push(msg(
+                            "Error",
+                            vec!["Draw the region to evacuate as a neighborhood first"],
+                        ));
+                    }
+                    let scenario_name = self.scenario_name.clone();
+                    let modifiers = self.modifiers.clone();
+                    return Transition::Push(WizardState::new(Box::new(move |wiz, ctx, app| {
+                        let mut wizard = wiz.wrap(ctx);
+                        let name = wizard.choose_string("Which neighborhood?", || {
+                            abstutil::list_all_objects(abstutil::path_all_neighborhoods(&map_name))
+                        })?;
+                        Some(Transition::Replace(EvacuationPlanner::new(
+                            ctx,
+                            app,
+                            scenario_name.clone(),
+                            modifiers.clone(),
+                            Neighborhood::load(&map_name, &name).unwrap(),
+                        )))
+                    })));
+                }
                 x => {
                     if let Some(x) = x.strip_prefix("delete modifier ") {
-                        self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
+                        let removed = self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
+                        if let ScenarioModifier::Evacuate { contraflow, .. } = removed {
+                            if !contraflow.is_empty() {
+                                let mut edits = app.primary.map.get_edits().clone();
+                                edits.commands.retain(|cmd| match cmd {
+                                    EditCmd::ReverseLane { l, dst_i } => {
+                                        !contraflow.contains(&(*l, *dst_i))
+                                    }
+                                    _ => true,
+                                });
+                                apply_map_edits(ctx, app, edits);
+                            }
+                        }
                         return Transition::Replace(EditScenarioModifiers::new(
                             ctx,
                             self.scenario_name.clone(),
@@ -270,11 +348,15 @@ impl State for EditScenarioModifiers {
 
 // TODO Wizard isn't the right UI for this
 fn new_modifier(scenario_name: String, modifiers: Vec<ScenarioModifier>) -> Box<dyn State> {
-    WizardState::new(Box::new(move |wiz, ctx, _| {
+    WizardState::new(Box::new(move |wiz, ctx, app| {
         let mut wizard = wiz.wrap(ctx);
         let new_mod = match wizard
             .choose_string("", || {
-                vec!["repeat days", "cancel all trips for some people"]
+                vec![
+                    "repeat days",
+                    "cancel all trips for some people",
+                    "only people starting in a neighborhood",
+                ]
             })?
             .as_str()
         {
@@ -284,6 +366,15 @@ fn new_modifier(scenario_name: String, modifiers: Vec<ScenarioModifier>) -> Box<
             x if x == "cancel all trips for some people" => ScenarioModifier::CancelPeople(
                 wizard.input_percent("What percent of people should cancel trips? (0 to 100)")?,
             ),
+            x if x == "only people starting in a neighborhood" => {
+                let map_name = app.primary.map.get_name().to_string();
+                let name = wizard.choose_string("Which neighborhood?", || {
+                    abstutil::list_all_objects(abstutil::path_all_neighborhoods(&map_name))
+                })?;
+                ScenarioModifier::StartingInNeighborhood(
+                    Neighborhood::load(&map_name, &name).unwrap(),
+                )
+            }
             _ => unreachable!(),
         };
         let mut mods = modifiers.clone();
@@ -413,3 +504,296 @@ impl State for ChangeMode {
         self.composite.draw(g);
     }
 }
+
+struct EvacuationPlanner {
+    composite: Composite,
+    scenario_name: String,
+    modifiers: Vec<ScenarioModifier>,
+    region: Neighborhood,
+    polygon: Option<Polygon>,
+    // Starts as all of the borders people can leave through
+    candidates: BTreeSet<IntersectionID>,
+    exits: BTreeSet<IntersectionID>,
+    draw: Drawable,
+}
+
+impl EvacuationPlanner {
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        scenario_name: String,
+        modifiers: Vec<ScenarioModifier>,
+        region: Neighborhood,
+    ) -> Box<dyn State> {
+        let map = &app.primary.map;
+        let candidates: BTreeSet<IntersectionID> = map
+            .all_outgoing_borders()
+            .into_iter()
+            .map(|i| i.id)
+            .collect();
+        let mut planner = EvacuationPlanner {
+            composite: Composite::new(Widget::col(vec![
+                Line(format!("Evacuate {}", region.name))
+                    .small_heading()
+                    .draw(ctx),
+                Text::from(
+                    Line(
+                        "Everybody in the region when the evacuation starts drives to the closest \
+                         exit. Click borders to choose the exits.",
+                    )
+                    .secondary(),
+                )
+                .wrap_to_pct(ctx, 30)
+                .draw(ctx),
+                Widget::row(vec![
+                    "Starting at hour:".draw_text(ctx).centered_vert(),
+                    Spinner::new(ctx, (0, 23), 12).named("start hour"),
+                ]),
+                Widget::row(vec![
+                    "Minutes for everybody to leave:"
+                        .draw_text(ctx)
+                        .centered_vert(),
+                    Spinner::new(ctx, (1, 240), 30).named("window"),
+                ]),
+                Checkbox::text(ctx, "contraflow on exit roads", None, false),
+                Widget::row(vec![
+                    Btn::text_bg2("Apply").build_def(ctx, hotkey(Key::Enter)),
+                    Btn::text_bg2("Discard changes").build_def(ctx, hotkey(Key::Escape)),
+                ])
+                .centered(),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx),
+            scenario_name,
+            modifiers,
+            polygon: region.polygon(map),
+            region,
+            exits: candidates.clone(),
+            candidates,
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        planner.redraw(ctx, app);
+        Box::new(planner)
+    }
+
+    fn redraw(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut batch = GeomBatch::new();
+        if let Some(ref p) = self.polygon {
+            batch.push(Color::RED.alpha(0.3), p.clone());
+        }
+        for i in &self.candidates {
+            batch.push(
+                if self.exits.contains(i) {
+                    Color::GREEN
+                } else {
+                    Color::grey(0.5)
+                },
+                app.primary.map.get_i(*i).polygon.clone(),
+            );
+        }
+        self.draw = ctx.upload(batch);
+    }
+}
+
+impl State for EvacuationPlanner {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+        }
+        if let Some(ID::Intersection(i)) = app.primary.current_selection {
+            if self.candidates.contains(&i) {
+                if self.exits.contains(&i) {
+                    if app.per_obj.left_click(ctx, "don't use this exit") {
+                        self.exits.remove(&i);
+                        self.redraw(ctx, app);
+                    }
+                } else if app.per_obj.left_click(ctx, "use this exit") {
+                    self.exits.insert(i);
+                    self.redraw(ctx, app);
+                }
+            }
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "Discard changes" => Transition::Pop,
+                "Apply" => {
+                    if self.exits.is_empty() {
+                        return Transition::Push(msg("Error", vec!["Pick at least one exit"]));
+                    }
+                    let mut contraflow = Vec::new();
+                    if let Some(ref polygon) = self.polygon {
+                        if self.composite.is_checked("contraflow on exit roads") {
+                            contraflow = contraflow_toward(polygon, &self.exits, &app.primary.map);
+                            let mut edits = app.primary.map.get_edits().clone();
+                            for (l, dst_i) in &contraflow {
+                                edits.commands.push(EditCmd::ReverseLane {
+                                    l: *l,
+                                    dst_i: *dst_i,
+                                });
+                            }
+                            apply_map_edits(ctx, app, edits);
+                        }
+                    }
+
+                    let mut mods = self.modifiers.clone();
+                    mods.push(ScenarioModifier::Evacuate {
+                        region: self.region.clone(),
+                        exits: self.exits.clone(),
+                        start: Time::START_OF_DAY
+                            + Duration::hours(self.composite.spinner("start hour") as usize),
+                        window: Duration::minutes(self.composite.spinner("window") as usize),
+                        contraflow,
+                    });
+                    Transition::PopThenReplace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        mods,
+                    ))
+                }
+                _ => unreachable!(),
+            },
+            None => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+// Find the route from the middle of the region to each exit, and along all of it, take one lane
+// heading back toward the region and point it toward the exit instead. Roads with only one lane
+// heading back keep it, so emergency vehicles can still get in.
+fn contraflow_toward(
+    region: &Polygon,
+    exits: &BTreeSet<IntersectionID>,
+    map: &Map,
+) -> Vec<(LaneID, IntersectionID)> {
+    let center = region.center();
+    let start = match map
+        .all_lanes()
+        .iter()
+        .filter(|l| l.is_driving() && region.contains_pt(l.lane_center_pts.middle()))
+        .min_by(|a, b| {
+            a.lane_center_pts
+                .middle()
+                .dist_to(center)
+                .partial_cmp(&b.lane_center_pts.middle().dist_to(center))
+                .unwrap()
+        }) {
+        Some(l) => l.id,
+        None => {
+            return Vec::new();
+        }
+    };
+
+    // Exits can share parts of their routes
+    let mut outbound: BTreeSet<(RoadID, IntersectionID)> = BTreeSet::new();
+    for i in exits {
+        let end = match map
+            .get_i(*i)
+            .incoming_lanes
+            .iter()
+            .find(|l| map.get_l(**l).is_driving())
+        {
+            Some(l) => *l,
+            None => {
+                continue;
+            }
+        };
+        let req = PathRequest {
+            start: Position::new(start, Distance::ZERO),
+            end: Position::new(end, map.get_l(end).length()),
+            constraints: PathConstraints::Car,
+        };
+        if let Some(path) = map.pathfind(req) {
+            for step in path.get_steps() {
+                if let PathStep::Lane(l) = step {
+                    let l = map.get_l(*l);
+                    outbound.insert((l.parent, l.dst_i));
+                }
+            }
+        }
+    }
+
+    let mut contraflow = Vec::new();
+    for (r, toward) in outbound {
+        let r = map.get_r(r);
+        let inbound = r.children(r.dst_i != toward);
+        if inbound
+            .iter()
+            .filter(|(_, lt)| *lt == LaneType::Driving)
+            .count()
+            > 1
+            && inbound[0].1 == LaneType::Driving
+        {
+            contraflow.push((inbound[0].0, toward));
+        }
+    }
+    contraflow
+}
+
+// How many people have made it out so far, and once everybody's done, the clearance time
+fn evacuation_status(
+    ctx: &mut EventCtx,
+    app: &App,
+    name: &str,
+    exits: &BTreeSet<IntersectionID>,
+    start: Time,
+    window: Duration,
+) -> Widget {
+    let sim = &app.primary.sim;
+    let evacuees: BTreeSet<TripID> = sim
+        .all_trip_info()
+        .into_iter()
+        .filter(|(_, info)| {
+            info.modified
+                && info.departure >= start
+                && info.departure <= start + window
+                && match info.end {
+                    TripEndpoint::Border(i, _) => exits.contains(&i),
+                    TripEndpoint::Bldg(_) => false,
+                }
+        })
+        .map(|(id, _)| id)
+        .collect();
+    let mut out = 0;
+    let mut stuck = 0;
+    let mut last_out = start;
+    for (t, id, mode, _) in &sim.get_analytics().finished_trips {
+        if evacuees.contains(id) {
+            if mode.is_some() {
+                out += 1;
+                last_out = *t;
+            } else {
+                stuck += 1;
+            }
+        }
+    }
+
+    let mut txt = Text::from(Line(format!(
+        "Evacuating {}: {} of {} people out",
+        name,
+        prettyprint_usize(out),
+        prettyprint_usize(evacuees.len())
+    )));
+    if stuck > 0 {
+        txt.add(Line(format!("{} couldn't get out", prettyprint_usize(stuck))).secondary());
+    }
+    if !evacuees.is_empty() && out + stuck == evacuees.len() {
+        txt.add(Line(format!("Cleared after {}", last_out - start)).secondary());
+    } else if sim.time() > start {
+        txt.add(
+            Line(format!(
+                "{} since the evacuation started",
+                sim.time() - start
+            ))
+            .secondary(),
+        );
+    }
+    txt.draw(ctx).centered_horiz()
+}
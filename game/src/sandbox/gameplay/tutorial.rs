@@ -253,7 +253,7 @@ impl Tutorial {
             if app.primary.sim.is_done() {
                 let mut before = Duration::ZERO;
                 let mut after = Duration::ZERO;
-                for (b, a, _) in app
+                for (_, b, a, _) in app
                     .primary
                     .sim
                     .get_analytics()
@@ -638,6 +638,7 @@ fn make_bike_lane_scenario(map: &Map) -> ScenarioGenerator {
         num_peds: 0,
         num_cars: 10,
         num_bikes: 10,
+        num_trucks: 0,
         percent_use_transit: 0.0,
         start_time: Time::START_OF_DAY,
         stop_time: Time::START_OF_DAY + Duration::seconds(10.0),
@@ -988,7 +989,7 @@ impl TutorialState {
                         "",
                         "(The keyboard shortcuts are very helpful here!)",
                     ],
-                    arrow(speed.composite.center_of("30x speed")),
+                    arrow(speed.composite.center_of("60x speed")),
                 )
                 .msg(
                     vec!["Advance time by certain amounts"],
@@ -1174,6 +1175,7 @@ impl TutorialState {
                             percent_use_transit: 0.0,
                         }],
                         border_spawn_over_time: Vec::new(),
+                        neighborhood_spawn_over_time: Vec::new(),
                     }
                     .generate(
                         &app.primary.map,
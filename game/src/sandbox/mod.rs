@@ -1,10 +1,14 @@
+mod ab_test;
+mod corridor;
 mod dashboards;
 pub mod gameplay;
 mod misc_tools;
 mod speed;
+mod tour;
 mod uber_turns;
 
 use self::misc_tools::{RoutePreview, ShowTrafficSignal, TurnExplorer};
+use self::tour::TourPlayer;
 use crate::app::App;
 use crate::common::{tool_panel, CommonState, ContextualActions, IsochroneViewer, Minimap};
 use crate::debug::DebugMode;
@@ -18,16 +22,19 @@ use crate::layer::PickLayer;
 use crate::managed::{WrappedComposite, WrappedOutcome};
 use crate::pregame::MainMenu;
 use crate::render::UnzoomedAgents;
+pub use ab_test::ABTestMode;
 use ezgui::{
     hotkey, lctrl, Btn, Choice, Color, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment,
     Key, Line, Outcome, Text, TextExt, UpdateType, VerticalAlignment, Widget, Wizard,
 };
-pub use gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
+pub use gameplay::{
+    path_request, spawn_agents_around, GameplayMode, TutorialPointer, TutorialState,
+};
 use geom::{Polygon, Time};
 use map_model::MapEdits;
 use sim::{AgentType, VehicleType};
 pub use speed::TimeWarpScreen;
-pub use speed::{SpeedControls, TimePanel};
+pub use speed::{SpeedControls, SpeedSetting, TimePanel};
 
 pub struct SandboxMode {
     gameplay: Box<dyn gameplay::GameplayState>,
@@ -44,6 +51,7 @@ pub struct SandboxControls {
     speed: Option<SpeedControls>,
     pub agent_meter: Option<AgentMeter>,
     minimap: Option<Minimap>,
+    tour: TourPlayer,
 }
 
 impl SandboxMode {
@@ -88,6 +96,7 @@ impl SandboxMode {
                 } else {
                     None
                 },
+                tour: TourPlayer::new(),
             },
             gameplay,
             gameplay_mode: mode,
@@ -138,6 +147,10 @@ impl State for SandboxMode {
             }
         }
 
+        if let Some(t) = self.controls.tour.event(ctx, app, &mut self.controls.speed) {
+            return t;
+        }
+
         if let Some(ref mut s) = self.controls.speed {
             if let Some(t) = s.event(ctx, app, Some(&self.gameplay_mode)) {
                 return t;
@@ -301,7 +314,7 @@ impl AgentMeter {
         let (finished, unfinished) = app.primary.sim.num_trips();
         let by_type = app.primary.sim.num_agents();
 
-        let rows = vec![
+        let mut rows = vec![
             "Active trips".draw_text(ctx),
             Widget::custom_row(vec![
                 Widget::custom_row(vec![
@@ -356,6 +369,20 @@ impl AgentMeter {
                     .align_right(),
             ]),
         ];
+        if let Some(routes) = app.primary.sim.get_fixed_routes() {
+            let (kept, unusable) = routes.stats();
+            rows.push(
+                Text::from(
+                    Line(format!(
+                        "Baseline routes: {} re-used, {} didn't work",
+                        prettyprint_usize(kept),
+                        prettyprint_usize(unusable)
+                    ))
+                    .secondary(),
+                )
+                .draw(ctx),
+            );
+        }
 
         let composite = Composite::new(Widget::col(rows))
             .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
@@ -413,6 +440,10 @@ impl ContextualActions for Actions {
                     }
                     if app.opts.dev {
                         actions.push((Key::U, "explore uber-turns".to_string()));
+                    }
+                }
+                ID::Building(_) => {
+                    if app.opts.dev {
                         actions.push((Key::I, "explore isochrone from here".to_string()));
                     }
                 }
@@ -423,6 +454,7 @@ impl ContextualActions for Actions {
                     if can_edit_lane(&self.gameplay, l, app) {
                         actions.push((Key::E, "edit lane".to_string()));
                     }
+                    actions.push((Key::C, "track travel times along a corridor".to_string()));
                 }
                 ID::Car(c) => {
                     if c.1 == VehicleType::Bus || c.1 == VehicleType::Train {
@@ -459,12 +491,15 @@ impl ContextualActions for Actions {
             (ID::Intersection(i), "explore uber-turns") => {
                 Transition::Push(uber_turns::UberTurnPicker::new(ctx, app, i))
             }
-            (ID::Intersection(i), "explore isochrone from here") => {
-                Transition::Push(IsochroneViewer::new(ctx, app, i))
+            (ID::Building(b), "explore isochrone from here") => {
+                Transition::Push(IsochroneViewer::new(ctx, app, b))
             }
             (ID::Lane(l), "explore turns from this lane") => {
                 Transition::Push(TurnExplorer::new(ctx, app, l))
             }
+            (ID::Lane(l), "track travel times along a corridor") => Transition::Push(
+                corridor::CorridorPicker::new(ctx, app, app.primary.map.get_l(l).parent),
+            ),
             (ID::Lane(l), "edit lane") => Transition::PushTwice(
                 Box::new(EditMode::new(ctx, app, self.gameplay.clone())),
                 Box::new(LaneEditor::new(ctx, app, l, self.gameplay.clone())),
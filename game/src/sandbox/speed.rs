@@ -2,16 +2,18 @@ use crate::app::{App, FindDelayedIntersections, ShowEverything};
 use crate::common::Warping;
 use crate::game::{msg, DrawBaselayer, State, Transition};
 use crate::helpers::ID;
+use crate::layer::gridlock::GridlockAgents;
 use crate::render::DrawOptions;
 use crate::sandbox::{GameplayMode, SandboxMode};
 use abstutil::prettyprint_usize;
 use ezgui::{
     hotkey, AreaSlider, Btn, Checkbox, Choice, Color, Composite, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, PersistentSplit, RewriteColor, Text, UpdateType,
-    VerticalAlignment, Widget,
+    VerticalAlignment, Widget, UPDATE_FREQUENCY,
 };
 use geom::{Duration, Polygon, Pt2D, Time};
 use instant::Instant;
+use serde::{Deserialize, Serialize};
 use sim::AlertLocation;
 
 pub struct SpeedControls {
@@ -21,18 +23,29 @@ pub struct SpeedControls {
     setting: SpeedSetting,
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
-enum SpeedSetting {
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
+pub enum SpeedSetting {
     // 1 sim second per real second
     Realtime,
-    // 5 sim seconds per real second
+    // 10 sim seconds per real second
     Fast,
-    // 30 sim seconds per real second
+    // 1 sim minute per real second
     Faster,
     // 1 sim hour per real second
     Fastest,
 }
 
+impl SpeedSetting {
+    pub fn multiplier(self) -> f64 {
+        match self {
+            SpeedSetting::Realtime => 1.0,
+            SpeedSetting::Fast => 10.0,
+            SpeedSetting::Faster => 60.0,
+            SpeedSetting::Fastest => 3600.0,
+        }
+    }
+}
+
 impl SpeedControls {
     fn make_panel(ctx: &mut EventCtx, app: &App, paused: bool, setting: SpeedSetting) -> Composite {
         let mut row = Vec::new();
@@ -56,8 +69,8 @@ impl SpeedControls {
             Widget::custom_row(
                 vec![
                     (SpeedSetting::Realtime, "real-time speed"),
-                    (SpeedSetting::Fast, "5x speed"),
-                    (SpeedSetting::Faster, "30x speed"),
+                    (SpeedSetting::Fast, "10x speed"),
+                    (SpeedSetting::Faster, "60x speed"),
                     (SpeedSetting::Fastest, "3600x speed"),
                 ]
                 .into_iter()
@@ -85,18 +98,23 @@ impl SpeedControls {
         );
 
         row.push(
-            PersistentSplit::new(
-                ctx,
-                "step forwards",
-                app.opts.time_increment,
-                hotkey(Key::M),
-                vec![
-                    Choice::new("+1h", Duration::hours(1)),
-                    Choice::new("+30m", Duration::minutes(30)),
-                    Choice::new("+10m", Duration::minutes(10)),
-                    Choice::new("+0.1s", Duration::seconds(0.1)),
-                ],
-            )
+            Widget::custom_row(vec![
+                Btn::svg_def("system/assets/speed/step_backwards.svg")
+                    .pad(9)
+                    .build(ctx, "step backwards", hotkey(Key::N)),
+                PersistentSplit::new(
+                    ctx,
+                    "step forwards",
+                    app.opts.time_increment,
+                    hotkey(Key::M),
+                    vec![
+                        Choice::new("+1h", Duration::hours(1)),
+                        Choice::new("+30m", Duration::minutes(30)),
+                        Choice::new("+10m", Duration::minutes(10)),
+                        Choice::new("+0.1s", Duration::seconds(0.1)),
+                    ],
+                ),
+            ])
             .bg(app.cs.section_bg)
             .margin_right(16),
         );
@@ -124,11 +142,12 @@ impl SpeedControls {
     }
 
     pub fn new(ctx: &mut EventCtx, app: &App) -> SpeedControls {
-        let composite = SpeedControls::make_panel(ctx, app, false, SpeedSetting::Realtime);
+        let setting = app.opts.default_speed;
+        let composite = SpeedControls::make_panel(ctx, app, false, setting);
         SpeedControls {
             composite,
             paused: false,
-            setting: SpeedSetting::Realtime,
+            setting,
         }
     }
 
@@ -145,12 +164,12 @@ impl SpeedControls {
                     self.composite = SpeedControls::make_panel(ctx, app, self.paused, self.setting);
                     return None;
                 }
-                "5x speed" => {
+                "10x speed" => {
                     self.setting = SpeedSetting::Fast;
                     self.composite = SpeedControls::make_panel(ctx, app, self.paused, self.setting);
                     return None;
                 }
-                "30x speed" => {
+                "60x speed" => {
                     self.setting = SpeedSetting::Faster;
                     self.composite = SpeedControls::make_panel(ctx, app, self.paused, self.setting);
                     return None;
@@ -189,9 +208,20 @@ impl SpeedControls {
                         maybe_mode.cloned(),
                     ))));
                 }
+                "step backwards" => {
+                    let dt = self.composite.persistent_split_value("step forwards");
+                    let now = app.primary.sim.time();
+                    let target = if now - Time::START_OF_DAY > dt {
+                        now - dt
+                    } else {
+                        Time::START_OF_DAY
+                    };
+                    return Some(rewind(ctx, app, target, maybe_mode));
+                }
                 "step forwards" => {
                     let dt = self.composite.persistent_split_value("step forwards");
                     if dt == Duration::seconds(0.1) {
+                        app.primary.maybe_checkpoint();
                         app.primary
                             .sim
                             .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
@@ -257,19 +287,14 @@ impl SpeedControls {
         if !self.paused {
             if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
                 ctx.input.use_update_event();
-                let multiplier = match self.setting {
-                    SpeedSetting::Realtime => 1.0,
-                    SpeedSetting::Fast => 5.0,
-                    SpeedSetting::Faster => 30.0,
-                    SpeedSetting::Fastest => 3600.0,
-                };
-                let dt = multiplier * real_dt;
-                // TODO This should match the update frequency in ezgui. Plumb along the deadline
-                // or frequency to here.
+                let dt = self.setting.multiplier() * real_dt;
+                app.primary.maybe_checkpoint();
+                // Don't hold up the next frame. If the sim can't keep up with the speed, it just
+                // runs slower than asked.
                 app.primary.sim.time_limited_step(
                     &app.primary.map,
                     dt,
-                    Duration::seconds(0.033),
+                    Duration::seconds(UPDATE_FREQUENCY.as_secs_f64()),
                     &mut app.primary.sim_cb,
                 );
                 app.recalculate_current_selection(ctx);
@@ -279,7 +304,11 @@ impl SpeedControls {
         // TODO Need to do this anywhere that steps the sim, like TimeWarpScreen.
         let alerts = app.primary.sim.clear_alerts();
         if !alerts.is_empty() {
-            let popup = msg("Alerts", alerts.iter().map(|(_, _, msg)| msg).collect());
+            // Don't stop the sim; just mention what happened and offer to go look
+            for (t, _, msg) in &alerts {
+                app.notifications
+                    .toast(ctx, Text::from(Line(format!("At {}: {}", t, msg))));
+            }
             let maybe_id = match alerts[0].1 {
                 AlertLocation::Nil => None,
                 AlertLocation::Intersection(i) => Some(ID::Intersection(i)),
@@ -294,22 +323,58 @@ impl SpeedControls {
             /*if maybe_id != Some(ID::Building(map_model::BuildingID(91))) {
                 return None;
             }*/
-            self.pause(ctx, app);
             if let Some(id) = maybe_id {
-                // Just go to the first one, but print all messages
-                return Some(Transition::PushTwice(
-                    popup,
-                    Warping::new(
+                // Just offer the first one, but show all messages
+                app.notifications.confirm(
+                    ctx,
+                    Text::from(Line(format!("Go to the alert at {}?", alerts[0].0))),
+                    Box::new(move |ctx, app| {
+                        // By the time the player answers, the thing might be gone
+                        match id.canonical_point(&app.primary) {
+                            Some(pt) => Transition::Push(Warping::new(
+                                ctx,
+                                pt,
+                                Some(10.0),
+                                None,
+                                &mut app.primary,
+                            )),
+                            None => {
+                                app.notifications.toast(
+                                    ctx,
+                                    Text::from(Line(format!("{:?} isn't around anymore", id))),
+                                );
+                                Transition::Keep
+                            }
+                        }
+                    }),
+                );
+            }
+        }
+
+        let gridlock = app.primary.sim.take_new_gridlock();
+        for g in &gridlock {
+            app.notifications
+                .toast(ctx, Text::from(Line(g.describe()).fg(Color::RED)));
+        }
+        if let Some(g) = gridlock.into_iter().next() {
+            // Like alerts, just offer to look at the first one
+            app.notifications.confirm(
+                ctx,
+                Text::from(Line(format!(
+                    "Go to the gridlock at {}?",
+                    g.since.ampm_tostring()
+                ))),
+                Box::new(move |ctx, app| {
+                    app.layer = Some(Box::new(GridlockAgents::new(ctx, app, g.intersection)));
+                    Transition::Push(Warping::new(
                         ctx,
-                        id.canonical_point(&app.primary).unwrap(),
+                        app.primary.map.get_i(g.intersection).polygon.center(),
                         Some(10.0),
                         None,
                         &mut app.primary,
-                    ),
-                ));
-            } else {
-                return Some(Transition::Push(popup));
-            }
+                    ))
+                }),
+            );
         }
 
         None
@@ -337,6 +402,36 @@ impl SpeedControls {
     pub fn is_paused(&self) -> bool {
         self.paused
     }
+
+    // Simulated seconds per real second, or 0 when paused.
+    pub fn multiplier(&self) -> f64 {
+        if self.paused {
+            0.0
+        } else {
+            self.setting.multiplier()
+        }
+    }
+
+    // Rounds down to the nearest setting, but never slower than realtime. 0 pauses.
+    pub fn set_speed(&mut self, ctx: &mut EventCtx, app: &App, multiplier: f64) {
+        if multiplier <= 0.0 {
+            self.pause(ctx, app);
+            return;
+        }
+        let setting = vec![
+            SpeedSetting::Fastest,
+            SpeedSetting::Faster,
+            SpeedSetting::Fast,
+        ]
+        .into_iter()
+        .find(|s| s.multiplier() <= multiplier)
+        .unwrap_or(SpeedSetting::Realtime);
+        if self.paused || self.setting != setting {
+            self.paused = false;
+            self.setting = setting;
+            self.composite = SpeedControls::make_panel(ctx, app, self.paused, self.setting);
+        }
+    }
 }
 
 // TODO Text entry would be great
@@ -344,15 +439,31 @@ struct JumpToTime {
     composite: Composite,
     target: Time,
     maybe_mode: Option<GameplayMode>,
+    // The last few minutes can be scrubbed through more finely, to rewind to just before
+    // something happened. The range covered by the recent checkpoints, and the chosen time.
+    recent: Option<(Time, Time)>,
+    rewind_target: Time,
 }
 
 impl JumpToTime {
     fn new(ctx: &mut EventCtx, app: &App, maybe_mode: Option<GameplayMode>) -> JumpToTime {
         let target = app.primary.sim.time();
         let end_of_day = app.primary.sim.get_end_of_day();
+        let recent = app
+            .primary
+            .checkpoints
+            .earliest_recent()
+            .filter(|t| *t < target)
+            .map(|t| (t, target));
+        let rewind_target = match recent {
+            Some((start, end)) => (start + (end - start) * 0.5).round_seconds(1.0).max(start),
+            None => target,
+        };
         JumpToTime {
             target,
             maybe_mode,
+            recent,
+            rewind_target,
             composite: Composite::new(Widget::col(vec![
                 Widget::row(vec![
                     Line("Jump to what time?").small_heading().draw(ctx),
@@ -398,6 +509,7 @@ impl JumpToTime {
                 Btn::text_bg2("Jump to the next delay over 5 minutes")
                     .build_def(ctx, None)
                     .centered_horiz(),
+                rewind_scrubber(ctx, recent, rewind_target),
                 Checkbox::text(
                     ctx,
                     "don't draw (for faster simulations)",
@@ -412,6 +524,49 @@ impl JumpToTime {
     }
 }
 
+// Scrubs through the last few minutes, covered by the recent checkpoints
+fn rewind_scrubber(ctx: &mut EventCtx, recent: Option<(Time, Time)>, target: Time) -> Widget {
+    let (start, end) = match recent {
+        Some(x) => x,
+        None => {
+            return Widget::nothing();
+        }
+    };
+    Widget::col(vec![
+        Widget::draw_batch(
+            ctx,
+            GeomBatch::from(vec![(
+                Color::WHITE,
+                Polygon::rectangle(0.25 * ctx.canvas.window_width, 2.0),
+            )]),
+        )
+        .margin_above(10),
+        Text::from(
+            Line(format!(
+                "Or rewind to shortly before now, back to {}, to watch something happen again",
+                start.ampm_tostring()
+            ))
+            .secondary(),
+        )
+        .wrap_to_pct(ctx, 25)
+        .draw(ctx),
+        AreaSlider::new(
+            ctx,
+            0.25 * ctx.canvas.window_width,
+            (target - start) / (end - start),
+        )
+        .named("rewind slider"),
+        rewind_btn(ctx, target),
+    ])
+}
+
+fn rewind_btn(ctx: &mut EventCtx, target: Time) -> Widget {
+    Btn::text_bg2(format!("Rewind to {}", target.ampm_tostring()))
+        .build(ctx, "rewind", None)
+        .centered_horiz()
+        .named("rewind")
+}
+
 impl State for JumpToTime {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         match self.composite.event(ctx) {
@@ -421,6 +576,15 @@ impl State for JumpToTime {
                 }
                 "jump to time" => {
                     if self.target < app.primary.sim.time() {
+                        if app.primary.rewind_to_checkpoint(self.target) {
+                            app.recalculate_current_selection(ctx);
+                            return Transition::Replace(TimeWarpScreen::new(
+                                ctx,
+                                app,
+                                self.target,
+                                false,
+                            ));
+                        }
                         if let Some(mode) = self.maybe_mode.take() {
                             return Transition::ReplaceThenPush(
                                 Box::new(SandboxMode::new(ctx, app, mode)),
@@ -435,6 +599,20 @@ impl State for JumpToTime {
                     }
                     return Transition::Replace(TimeWarpScreen::new(ctx, app, self.target, false));
                 }
+                "rewind" => {
+                    // There's always a recent checkpoint at or before the target
+                    assert!(app.primary.rewind_to_checkpoint(self.rewind_target));
+                    app.recalculate_current_selection(ctx);
+                    if app.primary.sim.time() == self.rewind_target {
+                        return Transition::Pop;
+                    }
+                    return Transition::Replace(TimeWarpScreen::new(
+                        ctx,
+                        app,
+                        self.rewind_target,
+                        false,
+                    ));
+                }
                 "Jump to the next delay over 5 minutes" => {
                     return Transition::Replace(TimeWarpScreen::new(
                         ctx,
@@ -465,6 +643,18 @@ impl State for JumpToTime {
                     .named("jump to time"),
             );
         }
+        if let Some((start, end)) = self.recent {
+            let pct = self.composite.area_slider("rewind slider").get_percent();
+            let target = (start + (end - start) * pct)
+                .round_seconds(1.0)
+                .max(start)
+                .min(end);
+            if target != self.rewind_target {
+                self.rewind_target = target;
+                self.composite
+                    .replace(ctx, "rewind", rewind_btn(ctx, target));
+            }
+        }
         if self.composite.clicked_outside(ctx) {
             return Transition::Pop;
         }
@@ -526,17 +716,24 @@ impl State for TimeWarpScreen {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         if ctx.input.nonblocking_is_update_event().is_some() {
             ctx.input.use_update_event();
+            app.primary.maybe_checkpoint();
             app.primary.sim.time_limited_step(
                 &app.primary.map,
                 self.target - app.primary.sim.time(),
                 Duration::seconds(0.033),
                 &mut app.primary.sim_cb,
             );
-            for (t, maybe_i, alert) in app.primary.sim.clear_alerts() {
-                // TODO Just the first :(
+            let alerts = app.primary.sim.clear_alerts();
+            if !alerts.is_empty() {
+                // Stop warping, so the player doesn't miss what happened
                 return Transition::Replace(msg(
-                    "Alert",
-                    vec![format!("At {}, near {:?}, {}", t, maybe_i, alert)],
+                    "Alerts",
+                    alerts
+                        .into_iter()
+                        .map(|(t, maybe_i, alert)| {
+                            format!("At {}, near {:?}, {}", t, maybe_i, alert)
+                        })
+                        .collect(),
                 ));
             }
             if let Some(ref mut cb) = app.primary.sim_cb {
@@ -652,6 +849,34 @@ impl State for TimeWarpScreen {
     }
 }
 
+// Actually step the sim backwards: restore the latest checkpoint before the target, then replay
+// forwards from there. Without a checkpoint, fall back to starting over from midnight.
+fn rewind(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    target: Time,
+    maybe_mode: Option<&GameplayMode>,
+) -> Transition {
+    if app.primary.rewind_to_checkpoint(target) {
+        app.recalculate_current_selection(ctx);
+        if app.primary.sim.time() == target {
+            return Transition::KeepWithMouseover;
+        }
+        return Transition::Push(TimeWarpScreen::new(ctx, app, target, false));
+    }
+    if let Some(mode) = maybe_mode {
+        Transition::ReplaceThenPush(
+            Box::new(SandboxMode::new(ctx, app, mode.clone())),
+            TimeWarpScreen::new(ctx, app, target, false),
+        )
+    } else {
+        Transition::Push(msg(
+            "Error",
+            vec!["Sorry, you can't go rewind time from this mode."],
+        ))
+    }
+}
+
 pub struct TimePanel {
     time: Time,
     pub composite: Composite,
@@ -0,0 +1,220 @@
+use crate::app::App;
+use crate::game::{State, Transition, WizardState};
+use crate::layer::layer_by_name;
+use crate::sandbox::{SandboxMode, SpeedControls};
+use abstutil::Timer;
+use ezgui::{EventCtx, Key, UpdateType, Warper};
+use geom::{Duration, LonLat};
+use instant::Instant;
+use serde::{Deserialize, Serialize};
+
+// Real time spent at a view recorded with the hotkey. Edit the file to change it.
+const DEFAULT_DWELL: Duration = Duration::const_seconds(5.0);
+const RECORDED_TOUR: &str = "recorded";
+
+// A scripted path through the map for live demos and recorded videos, so they go the same way
+// every time. Stored by name alongside the map.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Tour {
+    pub map_name: String,
+    pub name: String,
+    pub stops: Vec<TourStop>,
+}
+
+// Missing settings are left alone when the tour reaches a stop.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TourStop {
+    pub center: LonLat,
+    pub zoom: f64,
+    // How long to stay here once the camera arrives, in real time
+    pub dwell: Duration,
+    // Simulated seconds per real second; 0 pauses
+    pub speed: Option<f64>,
+    // The name of a layer, as listed in the layer picker, or "None"
+    pub layer: Option<String>,
+}
+
+impl Tour {
+    pub fn load(map_name: &str, name: &str) -> Result<Tour, std::io::Error> {
+        abstutil::maybe_read_json(abstutil::path_tour(map_name, name), &mut Timer::throwaway())
+    }
+
+    pub fn save(&self) {
+        abstutil::write_json(abstutil::path_tour(&self.map_name, &self.name), self);
+    }
+}
+
+// Press F5 in the sandbox to play a tour, and Escape to stop early. F6 adds the current view,
+// layer, and speed to the "recorded" tour, as a starting point for writing one.
+pub struct TourPlayer {
+    playing: Option<Playing>,
+}
+
+struct Playing {
+    tour: Tour,
+    idx: usize,
+    warper: Option<Warper>,
+    // Once the camera gets to the current stop
+    arrived: Option<Instant>,
+}
+
+impl TourPlayer {
+    pub fn new() -> TourPlayer {
+        TourPlayer { playing: None }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.is_some()
+    }
+
+    pub fn play(&mut self, ctx: &EventCtx, app: &App, tour: Tour) {
+        if tour.stops.is_empty() {
+            return;
+        }
+        let mut playing = Playing {
+            tour,
+            idx: 0,
+            warper: None,
+            arrived: None,
+        };
+        playing.warp(ctx, app);
+        self.playing = Some(playing);
+    }
+
+    pub fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        speed: &mut Option<SpeedControls>,
+    ) -> Option<Transition> {
+        let playing = match self.playing {
+            Some(ref mut p) => p,
+            None => {
+                if ctx.input.new_was_pressed(&Key::F6.into()) {
+                    record_view(ctx, app, speed);
+                } else if ctx.input.new_was_pressed(&Key::F5.into()) {
+                    return Some(Transition::Push(choose_tour(app)));
+                }
+                return None;
+            }
+        };
+        if ctx.input.new_was_pressed(&Key::Escape.into()) {
+            self.playing = None;
+            return None;
+        }
+
+        if let Some(ref warper) = playing.warper {
+            if warper.event(ctx) {
+                return None;
+            }
+            playing.warper = None;
+            playing.arrive(ctx, app, speed);
+        }
+        if abstutil::elapsed_seconds(playing.arrived.unwrap())
+            < playing.tour.stops[playing.idx].dwell.inner_seconds()
+        {
+            ctx.request_update(UpdateType::Game);
+            return None;
+        }
+        playing.idx += 1;
+        if playing.idx == playing.tour.stops.len() {
+            self.playing = None;
+        } else {
+            playing.warp(ctx, app);
+        }
+        None
+    }
+}
+
+impl Playing {
+    fn warp(&mut self, ctx: &EventCtx, app: &App) {
+        let stop = &self.tour.stops[self.idx];
+        let pt = match app
+            .primary
+            .map
+            .get_gps_bounds()
+            .try_convert(&vec![stop.center])
+        {
+            Some(pts) => pts[0],
+            None => {
+                println!("Tour stop {} isn't on this map", self.idx + 1);
+                ctx.canvas.center_to_map_pt()
+            }
+        };
+        self.warper = Some(Warper::new(ctx, pt, Some(stop.zoom)));
+        self.arrived = None;
+    }
+
+    fn arrive(&mut self, ctx: &mut EventCtx, app: &mut App, speed: &mut Option<SpeedControls>) {
+        let stop = &self.tour.stops[self.idx];
+        if let Some(ref name) = stop.layer {
+            if name == "None" {
+                app.layer = None;
+            } else {
+                match layer_by_name(ctx, app, name) {
+                    Some(layer) => {
+                        app.layer = Some(layer);
+                    }
+                    None => {
+                        println!("Tour stop {} uses an unknown layer {}", self.idx + 1, name);
+                    }
+                }
+            }
+        }
+        if let (Some(s), Some(multiplier)) = (speed.as_mut(), stop.speed) {
+            s.set_speed(ctx, app, multiplier);
+        }
+        self.arrived = Some(Instant::now());
+    }
+}
+
+fn choose_tour(app: &App) -> Box<dyn State> {
+    let map_name = app.primary.map.get_name().to_string();
+    WizardState::new(Box::new(move |wiz, ctx, _| {
+        let name = wiz.wrap(ctx).choose_string("Play which tour?", || {
+            abstutil::list_all_objects(abstutil::path_all_tours(&map_name))
+        })?;
+        let tour = match Tour::load(&map_name, &name) {
+            Ok(t) => t,
+            Err(err) => {
+                println!("Couldn't load tour {}: {}", name, err);
+                return Some(Transition::Pop);
+            }
+        };
+        Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+            let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+            sandbox.controls.tour.play(ctx, app, tour);
+        })))
+    }))
+}
+
+fn record_view(ctx: &EventCtx, app: &App, speed: &Option<SpeedControls>) {
+    let map_name = app.primary.map.get_name();
+    let mut tour = Tour::load(map_name, RECORDED_TOUR).unwrap_or_else(|_| Tour {
+        map_name: map_name.to_string(),
+        name: RECORDED_TOUR.to_string(),
+        stops: Vec::new(),
+    });
+    tour.stops.push(TourStop {
+        center: ctx
+            .canvas
+            .center_to_map_pt()
+            .to_gps(app.primary.map.get_gps_bounds()),
+        zoom: ctx.canvas.cam_zoom,
+        dwell: DEFAULT_DWELL,
+        speed: speed.as_ref().map(|s| s.multiplier()),
+        layer: Some(
+            app.layer
+                .as_ref()
+                .and_then(|l| l.name())
+                .unwrap_or("None")
+                .to_string(),
+        ),
+    });
+    tour.save();
+    println!(
+        "Added stop {} to {}",
+        tour.stops.len(),
+        abstutil::path_tour(map_name, RECORDED_TOUR)
+    );
+}
@@ -0,0 +1,346 @@
+use crate::app::App;
+use crate::common::{CommonState, ContextualActions, Minimap};
+use crate::game::{State, Transition};
+use crate::helpers::ID;
+use crate::layer::PickLayer;
+use crate::sandbox::TimePanel;
+use abstutil::prettyprint_usize;
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, LinePlot, Outcome,
+    PlotOptions, Series, Text, TextExt, UpdateType, VerticalAlignment, Widget,
+};
+use sim::TelemetryUpdate;
+use std::collections::BTreeMap;
+use std::sync::mpsc::Receiver;
+
+// Just navigation, layers, and the info panel. Nothing here can edit the map or advance the
+// simulation, so it's safe to hand to somebody who just wants to look at results.
+pub struct Viewer {
+    top_center: Composite,
+    common: CommonState,
+    minimap: Minimap,
+    time_panel: TimePanel,
+    telemetry: Option<Telemetry>,
+}
+
+impl Viewer {
+    // If telemetry is an address like 0.0.0.0:9100, also listen there for headless runs to report
+    // their progress. Anybody who can reach that address can report; there's no authentication,
+    // so only use 0.0.0.0 on a trusted network.
+    pub fn new(ctx: &mut EventCtx, app: &mut App, telemetry: Option<String>) -> Viewer {
+        let edits_name = &app.primary.map.get_edits().edits_name;
+        let mut txt = Text::from(Line(app.primary.map.get_name()).small_heading());
+        if edits_name != "untitled edits" {
+            txt.add(Line(format!("with edits \"{}\"", edits_name)));
+        }
+        Viewer {
+            top_center: Composite::new(Widget::row(vec![
+                txt.draw(ctx),
+                Btn::text_fg("quit").build_def(ctx, hotkey(Key::Escape)),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            common: CommonState::new(),
+            minimap: Minimap::new(ctx, app),
+            time_panel: TimePanel::new(ctx, app),
+            telemetry: telemetry.map(|address| match listen(&address) {
+                Ok(updates) => Telemetry::new(ctx, app, address, updates),
+                Err(err) => panic!("Can't listen for telemetry on {}: {}", address, err),
+            }),
+        }
+    }
+}
+
+impl State for Viewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+        }
+
+        if let Some(t) = self.minimap.event(ctx, app) {
+            return t;
+        }
+        if let Some(t) = PickLayer::update(ctx, app, &self.minimap.composite) {
+            return t;
+        }
+
+        // Before the top panel, so Key::Escape closes the info panel first.
+        if let Some(t) = self.common.event(ctx, app, &mut Actions) {
+            return t;
+        }
+
+        self.time_panel.event(ctx, app);
+        if let Some(ref mut telemetry) = self.telemetry {
+            telemetry.event(ctx, app);
+        }
+
+        match self.top_center.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "quit" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some(ref l) = app.layer {
+            l.draw(g, app);
+        }
+        self.common.draw(g, app);
+        self.top_center.draw(g);
+        self.time_panel.draw(g);
+        self.minimap.draw(g, app);
+        if let Some(ref telemetry) = self.telemetry {
+            telemetry.composite.draw(g);
+        }
+    }
+}
+
+const POLL_TELEMETRY: std::time::Duration = std::time::Duration::from_secs(1);
+// Anybody can connect, so limit how much memory and how many threads they can use up.
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+const MAX_CONNECTIONS: usize = 16;
+const MAX_RUNS: usize = 20;
+// Once a run has this many updates, every other one is dropped, so the plot still covers the whole
+// run.
+const MAX_UPDATES_PER_RUN: usize = 2000;
+
+// Plots the progress of headless runs happening somewhere else, like `sim_metrics --stream`. Each
+// run is identified by its map, scenario, and edits.
+struct Telemetry {
+    address: String,
+    updates: Receiver<Result<TelemetryUpdate, String>>,
+    runs: BTreeMap<String, Vec<TelemetryUpdate>>,
+    // How many lines didn't parse, and the last problem
+    bad_lines: usize,
+    last_error: Option<String>,
+    composite: Composite,
+}
+
+impl Telemetry {
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        address: String,
+        updates: Receiver<Result<TelemetryUpdate, String>>,
+    ) -> Telemetry {
+        let mut t = Telemetry {
+            address,
+            updates,
+            runs: BTreeMap::new(),
+            bad_lines: 0,
+            last_error: None,
+            composite: Composite::new(Widget::nothing()).build(ctx),
+        };
+        t.recalc(ctx, app);
+        t
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, app: &App) {
+        // Updates arrive in the background, so keep checking even without input. Runs report once
+        // a sim minute at most, so there's no need to check every frame.
+        ctx.request_update(UpdateType::Poll(POLL_TELEMETRY));
+        let mut changed = false;
+        while let Ok(result) = self.updates.try_recv() {
+            changed = true;
+            match result {
+                Ok(update) => {
+                    let run = format!(
+                        "{} on {} ({})",
+                        update.scenario_name, update.map_name, update.edits_name
+                    );
+                    if !self.runs.contains_key(&run) && self.runs.len() >= MAX_RUNS {
+                        self.bad_lines += 1;
+                        self.last_error = Some(format!(
+                            "Already following {} runs, ignoring {}",
+                            MAX_RUNS, run
+                        ));
+                        continue;
+                    }
+                    let updates = self.runs.entry(run).or_insert_with(Vec::new);
+                    if updates.len() >= MAX_UPDATES_PER_RUN {
+                        let mut keep = false;
+                        updates.retain(|_| {
+                            keep = !keep;
+                            keep
+                        });
+                    }
+                    updates.push(update);
+                }
+                Err(err) => {
+                    self.bad_lines += 1;
+                    self.last_error = Some(err);
+                }
+            }
+        }
+        if changed {
+            self.recalc(ctx, app);
+        }
+    }
+
+    fn recalc(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut col = vec![Line("Remote runs").small_heading().draw(ctx)];
+        if self.runs.is_empty() {
+            col.push(
+                Text::from(
+                    Line(format!(
+                        "Waiting for runs to report to {}. Try sim_metrics --stream.",
+                        self.address
+                    ))
+                    .secondary(),
+                )
+                .wrap_to_pct(ctx, 20)
+                .draw(ctx),
+            );
+        } else {
+            let mut txt = Text::new();
+            let mut series = Vec::new();
+            for (idx, (run, updates)) in self.runs.iter().enumerate() {
+                let latest = updates.last().unwrap();
+                txt.add(Line(run));
+                txt.add(
+                    Line(format!(
+                        "{}{}: {} active agents, {} trips finished, {} to go",
+                        latest.sample.time.ampm_tostring(),
+                        if latest.done { ", done" } else { "" },
+                        prettyprint_usize(latest.sample.active_agents),
+                        prettyprint_usize(latest.sample.finished_trips),
+                        prettyprint_usize(latest.unfinished_trips)
+                    ))
+                    .secondary(),
+                );
+                series.push(Series {
+                    label: run.clone(),
+                    color: app.cs.rotating_color_plot(idx),
+                    pts: updates
+                        .iter()
+                        .map(|u| (u.sample.time, u.sample.active_agents))
+                        .collect(),
+                });
+            }
+            col.push(txt.draw(ctx));
+            col.push("Active agents".draw_text(ctx));
+            col.push(LinePlot::new(ctx, series, PlotOptions::fixed()));
+        }
+        if let Some(ref err) = self.last_error {
+            col.push(
+                Text::from(
+                    Line(format!(
+                        "Ignored {} bad lines. The last: {}",
+                        prettyprint_usize(self.bad_lines),
+                        err
+                    ))
+                    .secondary(),
+                )
+                .wrap_to_pct(ctx, 20)
+                .draw(ctx),
+            );
+        }
+
+        self.composite = Composite::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx);
+    }
+}
+
+// Every connection sends lines of JSON. Several runs can report at once, up to MAX_CONNECTIONS.
+// Lines that don't parse are passed along as errors, and a line longer than MAX_LINE_BYTES closes
+// the connection.
+#[cfg(not(target_arch = "wasm32"))]
+fn listen(address: &str) -> Result<Receiver<Result<TelemetryUpdate, String>>, String> {
+    use std::io::{BufRead, Read};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let listener = std::net::TcpListener::bind(address).map_err(|err| err.to_string())?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let connections = Arc::new(AtomicUsize::new(0));
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            if connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                // Dropping the stream closes it
+                if tx
+                    .send(Err(format!(
+                        "Refused a connection; already have {}",
+                        MAX_CONNECTIONS
+                    )))
+                    .is_err()
+                {
+                    break;
+                }
+                continue;
+            }
+            connections.fetch_add(1, Ordering::SeqCst);
+            let connections = connections.clone();
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let mut reader = std::io::BufReader::new(stream);
+                loop {
+                    let mut buf = Vec::new();
+                    match (&mut reader)
+                        .take(MAX_LINE_BYTES)
+                        .read_until(b'\n', &mut buf)
+                    {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+                    if buf.last() != Some(&b'\n') && buf.len() as u64 == MAX_LINE_BYTES {
+                        let _ = tx.send(Err(format!(
+                            "Closed a connection that sent a line over {} bytes",
+                            MAX_LINE_BYTES
+                        )));
+                        break;
+                    }
+                    let update = std::str::from_utf8(&buf)
+                        .map_err(|err| err.to_string())
+                        .and_then(|line| {
+                            serde_json::from_str(line.trim_end()).map_err(|err| err.to_string())
+                        });
+                    if tx.send(update).is_err() {
+                        break;
+                    }
+                }
+                connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+    Ok(rx)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn listen(_: &str) -> Result<Receiver<Result<TelemetryUpdate, String>>, String> {
+    Err("Not supported on the web".to_string())
+}
+
+// No actions on anything; the info panel is purely for looking.
+struct Actions;
+impl ContextualActions for Actions {
+    fn actions(&self, _: &App, _: ID) -> Vec<(Key, String)> {
+        Vec::new()
+    }
+
+    fn execute(
+        &mut self,
+        _: &mut EventCtx,
+        _: &mut App,
+        _: ID,
+        _: String,
+        _: &mut bool,
+    ) -> Transition {
+        unreachable!()
+    }
+
+    fn is_paused(&self) -> bool {
+        true
+    }
+}
@@ -30,11 +30,14 @@ impl Angle {
         Angle::new_rads(self.0 + degrees.to_radians())
     }
 
+    // In [0, 2pi), even after lots of rotations
     pub fn normalized_radians(self) -> f64 {
-        if self.0 < 0.0 {
-            self.0 + (2.0 * std::f64::consts::PI)
+        let rads = self.0.rem_euclid(2.0 * std::f64::consts::PI);
+        // rem_euclid can round up to exactly 2pi for tiny negative angles
+        if rads >= 2.0 * std::f64::consts::PI {
+            0.0
         } else {
-            self.0
+            rads
         }
     }
 
@@ -106,3 +109,45 @@ impl std::ops::Div<f64> for Angle {
         Angle::new_rads(self.0 / scalar)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn normalized_in_range(degs in -2000.0..2000.0f64) {
+            let theta = Angle::new_degs(degs).normalized_degrees();
+            prop_assert!(theta >= 0.0 && theta < 360.0, "{} normalized to {}", degs, theta);
+        }
+
+        #[test]
+        fn full_rotations_are_equal(degs in -720.0..720.0f64, turns in -3i32..3) {
+            let a = Angle::new_degs(degs);
+            prop_assert!(a.approx_eq(a.rotate_degs(360.0 * f64::from(turns)), 0.001));
+        }
+
+        #[test]
+        fn opposite_twice(degs in -720.0..720.0f64) {
+            let a = Angle::new_degs(degs);
+            prop_assert!(a.opposite().opposite().approx_eq(a, 0.001));
+            prop_assert!(!a.opposite().approx_eq(a, 179.0));
+        }
+
+        #[test]
+        fn shortest_rotation(degs1 in -720.0..720.0f64, degs2 in -720.0..720.0f64) {
+            let (a, b) = (Angle::new_degs(degs1), Angle::new_degs(degs2));
+            let rot = a.shortest_rotation_towards(b);
+            prop_assert!(b.rotate_degs(rot.normalized_degrees()).approx_eq(a, 0.001));
+        }
+
+        #[test]
+        fn reorient_is_never_upside_down(degs in -720.0..720.0f64) {
+            let a = Angle::new_degs(degs);
+            let result = a.reorient();
+            prop_assert!(result.approx_eq(Angle::ZERO, 90.001), "{} reoriented to {}", a, result);
+            prop_assert!(result.approx_eq(a, 0.001) || result.approx_eq(a.opposite(), 0.001));
+        }
+    }
+}
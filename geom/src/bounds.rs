@@ -44,6 +44,13 @@ impl Bounds {
         pt.x() >= self.min_x && pt.x() <= self.max_x && pt.y() >= self.min_y && pt.y() <= self.max_y
     }
 
+    pub fn overlaps(&self, other: &Bounds) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+
     pub fn as_bbox(&self) -> Rect {
         Rect {
             top_left: Point {
@@ -57,6 +64,15 @@ impl Bounds {
         }
     }
 
+    pub fn from_bbox(rect: &Rect) -> Bounds {
+        Bounds {
+            min_x: f64::from(rect.top_left.x),
+            min_y: f64::from(rect.top_left.y),
+            max_x: f64::from(rect.bottom_right.x),
+            max_y: f64::from(rect.bottom_right.y),
+        }
+    }
+
     pub fn get_rectangle(&self) -> Polygon {
         Polygon::new(&vec![
             Pt2D::new(self.min_x, self.min_y),
@@ -165,3 +181,60 @@ impl GPSBounds {
                 .approx_eq(LonLat::new(other.max_lon, other.max_lat))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn pts() -> impl Strategy<Value = Vec<Pt2D>> {
+        prop::collection::vec((-1000.0..1000.0f64, -1000.0..1000.0f64), 1..20)
+            .prop_map(|pts| pts.into_iter().map(|(x, y)| Pt2D::new(x, y)).collect())
+    }
+
+    proptest! {
+        #[test]
+        fn contains_all_points(pts in pts()) {
+            let b = Bounds::from(&pts);
+            prop_assert!(b.width() >= 0.0 && b.height() >= 0.0);
+            for pt in &pts {
+                prop_assert!(b.contains(*pt));
+            }
+            prop_assert!(b.contains(b.center()));
+        }
+
+        #[test]
+        fn union_contains_both(pts1 in pts(), pts2 in pts()) {
+            let b1 = Bounds::from(&pts1);
+            let b2 = Bounds::from(&pts2);
+            let mut union = b1.clone();
+            union.union(b2.clone());
+            for pt in pts1.iter().chain(pts2.iter()) {
+                prop_assert!(union.contains(*pt));
+            }
+            prop_assert!(union.width() >= b1.width().max(b2.width()));
+            prop_assert!(union.height() >= b1.height().max(b2.height()));
+            prop_assert!(union.overlaps(&b1) && union.overlaps(&b2));
+            prop_assert_eq!(b1.overlaps(&b2), b2.overlaps(&b1));
+
+            // Same thing as one big bounds
+            let all = Bounds::from(&pts1.into_iter().chain(pts2.into_iter()).collect());
+            prop_assert_eq!(
+                (union.min_x, union.min_y, union.max_x, union.max_y),
+                (all.min_x, all.min_y, all.max_x, all.max_y)
+            );
+        }
+
+        #[test]
+        fn rectangle_matches(pts in pts()) {
+            let b = Bounds::from(&pts);
+            prop_assume!(b.width() > 1.0 && b.height() > 1.0);
+            let rect = b.get_rectangle().get_bounds();
+            prop_assert_eq!(
+                (rect.min_x, rect.min_y, rect.max_x, rect.max_y),
+                (b.min_x, b.min_y, b.max_x, b.max_y)
+            );
+            prop_assert!(b.get_rectangle().contains_pt(b.center()));
+        }
+    }
+}
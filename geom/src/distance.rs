@@ -1,4 +1,4 @@
-use crate::{trim_f64, Duration, Speed};
+use crate::{trim_f64, Duration, Speed, UnitFmt};
 use serde::{Deserialize, Serialize};
 use std::{cmp, f64, fmt, ops};
 
@@ -59,8 +59,14 @@ impl Distance {
         self.0
     }
 
-    // TODO Store a bit in Maps to mark if they're in the US or not, plumb here to use meters
-    pub fn describe_rounded(self) -> String {
+    pub fn describe_rounded(self, fmt: &UnitFmt) -> String {
+        if fmt.metric {
+            return if self.0 >= 1000.0 {
+                format!("{} km", (self.0 / 100.0).round() / 10.0)
+            } else {
+                format!("{} m", self.0.round())
+            };
+        }
         let feet = self.0 * 3.28084;
         let miles = feet / 5280.0;
         if miles >= 0.1 {
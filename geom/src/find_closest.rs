@@ -1,32 +1,59 @@
 use crate::{Bounds, Distance, Pt2D};
-use aabb_quadtree::geom::{Point, Rect};
-use aabb_quadtree::QuadTree;
 use geo;
 use geo::algorithm::contains::Contains;
 use geo::prelude::{ClosestPoint, EuclideanDistance};
+use rstar::{RTree, RTreeObject, AABB};
 use std::collections::HashMap;
 
+// An R-tree doesn't need to know the bounds upfront, so geometry can be added and removed at any
+// time. This lets long-lived indices (like the ones the map keeps) stay in sync with edits.
 pub struct FindClosest<K> {
     // TODO maybe any type of geo:: thing
-    geometries: HashMap<K, geo::LineString<f64>>,
-    quadtree: QuadTree<K>,
+    geometries: HashMap<K, (geo::LineString<f64>, AABB<[f64; 2]>)>,
+    rtree: RTree<Entry<K>>,
+}
+
+#[derive(PartialEq)]
+struct Entry<K> {
+    key: K,
+    envelope: AABB<[f64; 2]>,
+}
+
+impl<K> RTreeObject for Entry<K> {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
 }
 
 impl<K> FindClosest<K>
 where
     K: Clone + std::cmp::Eq + std::hash::Hash + std::fmt::Debug,
 {
-    pub fn new(bounds: &Bounds) -> FindClosest<K> {
+    pub fn new() -> FindClosest<K> {
         FindClosest {
             geometries: HashMap::new(),
-            quadtree: QuadTree::default(bounds.as_bbox()),
+            rtree: RTree::new(),
         }
     }
 
+    // If the key is already present, its old geometry is replaced.
     pub fn add(&mut self, key: K, pts: &Vec<Pt2D>) {
-        self.geometries.insert(key.clone(), pts_to_line_string(pts));
-        self.quadtree
-            .insert_with_box(key, Bounds::from(pts).as_bbox());
+        self.remove(&key);
+        let envelope = to_envelope(&Bounds::from(pts));
+        self.geometries
+            .insert(key.clone(), (pts_to_line_string(pts), envelope));
+        self.rtree.insert(Entry { key, envelope });
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        if let Some((_, envelope)) = self.geometries.remove(key) {
+            self.rtree.remove(&Entry {
+                key: key.clone(),
+                envelope,
+            });
+        }
     }
 
     pub fn all_close_pts(
@@ -35,33 +62,31 @@ where
         max_dist_away: Distance,
     ) -> Vec<(K, Pt2D, Distance)> {
         let query_geom = geo::Point::new(query_pt.x(), query_pt.y());
-        let query_bbox = Rect {
-            top_left: Point {
-                x: (query_pt.x() - max_dist_away.inner_meters()) as f32,
-                y: (query_pt.y() - max_dist_away.inner_meters()) as f32,
-            },
-            bottom_right: Point {
-                x: (query_pt.x() + max_dist_away.inner_meters()) as f32,
-                y: (query_pt.y() + max_dist_away.inner_meters()) as f32,
-            },
-        };
-
-        self.quadtree
-            .query(query_bbox)
-            .into_iter()
-            .filter_map(|(key, _, _)| {
-                if let geo::Closest::SinglePoint(pt) =
-                    self.geometries[&key].closest_point(&query_geom)
-                {
+        let query_bbox = AABB::from_corners(
+            [
+                query_pt.x() - max_dist_away.inner_meters(),
+                query_pt.y() - max_dist_away.inner_meters(),
+            ],
+            [
+                query_pt.x() + max_dist_away.inner_meters(),
+                query_pt.y() + max_dist_away.inner_meters(),
+            ],
+        );
+
+        self.rtree
+            .locate_in_envelope_intersecting(&query_bbox)
+            .filter_map(|entry| {
+                let geometry = &self.geometries[&entry.key].0;
+                if let geo::Closest::SinglePoint(pt) = geometry.closest_point(&query_geom) {
                     let dist = Distance::meters(pt.euclidean_distance(&query_geom));
                     if dist <= max_dist_away {
-                        Some((key.clone(), Pt2D::new(pt.x(), pt.y()), dist))
+                        Some((entry.key.clone(), Pt2D::new(pt.x(), pt.y()), dist))
                     } else {
                         None
                     }
-                } else if self.geometries[&key].contains(&query_geom) {
+                } else if geometry.contains(&query_geom) {
                     // TODO Yay, FindClosest has a bug. :P
-                    Some((key.clone(), query_pt, Distance::ZERO))
+                    Some((entry.key.clone(), query_pt, Distance::ZERO))
                 } else {
                     None
                 }
@@ -69,6 +94,14 @@ where
             .collect()
     }
 
+    // Everything whose bounding box overlaps the query.
+    pub fn all_in_bounds(&self, bounds: &Bounds) -> Vec<K> {
+        self.rtree
+            .locate_in_envelope_intersecting(&to_envelope(bounds))
+            .map(|entry| entry.key.clone())
+            .collect()
+    }
+
     // Finds the closest point on the existing geometry to the query pt.
     pub fn closest_pt(&self, query_pt: Pt2D, max_dist_away: Distance) -> Option<(K, Pt2D)> {
         self.all_close_pts(query_pt, max_dist_away)
@@ -78,6 +111,19 @@ where
     }
 }
 
+impl<K> Default for FindClosest<K>
+where
+    K: Clone + std::cmp::Eq + std::hash::Hash + std::fmt::Debug,
+{
+    fn default() -> FindClosest<K> {
+        FindClosest::new()
+    }
+}
+
+fn to_envelope(b: &Bounds) -> AABB<[f64; 2]> {
+    AABB::from_corners([b.min_x, b.min_y], [b.max_x, b.max_y])
+}
+
 fn pts_to_line_string(raw_pts: &Vec<Pt2D>) -> geo::LineString<f64> {
     let pts: Vec<geo::Point<f64>> = raw_pts
         .iter()
@@ -85,3 +131,106 @@ fn pts_to_line_string(raw_pts: &Vec<Pt2D>) -> geo::LineString<f64> {
         .collect();
     pts.into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn pt() -> impl Strategy<Value = Pt2D> {
+        (0.0..500.0f64, 0.0..500.0f64).prop_map(|(x, y)| Pt2D::new(x, y))
+    }
+
+    fn polylines() -> impl Strategy<Value = Vec<Vec<Pt2D>>> {
+        prop::collection::vec(prop::collection::vec(pt(), 2..6), 1..40)
+    }
+
+    // Check every segment
+    fn brute_force_dist(pts: &Vec<Pt2D>, query: Pt2D) -> f64 {
+        pts.windows(2)
+            .map(|pair| {
+                let (a, b) = (pair[0], pair[1]);
+                let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+                let len_squared = dx * dx + dy * dy;
+                let t = if len_squared == 0.0 {
+                    0.0
+                } else {
+                    (((query.x() - a.x()) * dx + (query.y() - a.y()) * dy) / len_squared)
+                        .max(0.0)
+                        .min(1.0)
+                };
+                let (x, y) = (a.x() + t * dx, a.y() + t * dy);
+                ((query.x() - x).powi(2) + (query.y() - y).powi(2)).sqrt()
+            })
+            .fold(std::f64::MAX, f64::min)
+    }
+
+    // Distances are rounded, so don't be picky about anything right at the edge
+    const EPSILON: f64 = 0.01;
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(
+            polylines in polylines(),
+            query in pt(),
+            radius in 1.0..200.0f64,
+            corner in pt(),
+        ) {
+            let mut closest = FindClosest::new();
+            // Replace some old geometry far away, and remove some, to make sure the R-tree stays in
+            // sync.
+            for (idx, pts) in polylines.iter().enumerate() {
+                closest.add(idx, &pts.iter().map(|pt| pt.offset(1000.0, 1000.0)).collect());
+                closest.add(idx, pts);
+            }
+            let removed = |idx: usize| idx % 5 == 0;
+            for idx in 0..polylines.len() {
+                if removed(idx) {
+                    closest.remove(&idx);
+                }
+            }
+            let dists: Vec<(usize, f64)> = polylines
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !removed(*idx))
+                .map(|(idx, pts)| (idx, brute_force_dist(pts, query)))
+                .collect();
+
+            let found = closest.all_close_pts(query, Distance::meters(radius));
+            for (idx, dist) in &dists {
+                let result = found.iter().find(|(k, _, _)| k == idx);
+                if let Some((_, _, d)) = result {
+                    prop_assert!((d.inner_meters() - dist).abs() < EPSILON);
+                } else {
+                    prop_assert!(*dist > radius - EPSILON);
+                }
+            }
+            prop_assert!(found.iter().all(|(idx, _, _)| !removed(*idx)));
+
+            let best = dists
+                .iter()
+                .map(|(_, d)| *d)
+                .fold(std::f64::MAX, f64::min);
+            match closest.closest_pt(query, Distance::meters(radius)) {
+                Some((idx, _)) => {
+                    let dist = dists.iter().find(|(k, _)| *k == idx).unwrap().1;
+                    prop_assert!(dist < best + EPSILON);
+                }
+                None => {
+                    prop_assert!(best > radius - EPSILON);
+                }
+            }
+
+            let bounds = Bounds::from(&vec![query, corner]);
+            let mut in_bounds = closest.all_in_bounds(&bounds);
+            in_bounds.sort();
+            let expected: Vec<usize> = polylines
+                .iter()
+                .enumerate()
+                .filter(|(idx, pts)| !removed(*idx) && Bounds::from(pts).overlaps(&bounds))
+                .map(|(idx, _)| idx)
+                .collect();
+            prop_assert_eq!(in_bounds, expected);
+        }
+    }
+}
@@ -29,10 +29,18 @@ pub use crate::ring::Ring;
 pub use crate::speed::Speed;
 pub use crate::stats::{HgramValue, Histogram, Statistic};
 pub use crate::time::Time;
+use serde::{Deserialize, Serialize};
 
 // About 0.4 inches... which is quite tiny on the scale of things. :)
 pub const EPSILON_DIST: Distance = Distance::const_meters(0.01);
 
+// How to describe distances and speeds to people
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnitFmt {
+    // Meters and km/h, instead of feet, miles, and mph
+    pub metric: bool,
+}
+
 pub fn trim_f64(x: f64) -> f64 {
     (x * 10_000.0).round() / 10_000.0
 }
@@ -259,6 +259,31 @@ impl Polygon {
         result
     }
 
+    // Like union_all, but also keeps the rings of every piece (including holes), so the outline
+    // goes around all of them. If any piece isn't made of rings, they're all dropped.
+    pub fn union_all_rings(list: Vec<Polygon>) -> Polygon {
+        let mut rings = Some(Vec::new());
+        for p in &list {
+            match (rings.as_mut(), p.rings.as_ref()) {
+                (Some(all), Some(these)) => {
+                    all.extend(these.iter().cloned());
+                }
+                (Some(all), None) => match Ring::new(p.points.clone()) {
+                    Ok(ring) => {
+                        all.push(ring);
+                    }
+                    Err(_) => {
+                        rings = None;
+                    }
+                },
+                (None, _) => {}
+            }
+        }
+        let mut result = Polygon::union_all(list);
+        result.rings = rings;
+        result
+    }
+
     pub fn intersection(&self, other: &Polygon) -> Vec<Polygon> {
         from_multi(to_geo(self.points()).intersection(&to_geo(other.points())))
     }
@@ -420,3 +445,165 @@ fn from_geo(p: geo::Polygon<f64>) -> Polygon {
 fn from_multi(multi: geo::MultiPolygon<f64>) -> Vec<Polygon> {
     multi.into_iter().map(from_geo).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<Pt2D> {
+        vec![
+            Pt2D::new(x, y),
+            Pt2D::new(x + size, y),
+            Pt2D::new(x + size, y + size),
+            Pt2D::new(x, y + size),
+            Pt2D::new(x, y),
+        ]
+    }
+
+    #[test]
+    fn union_all_rings_outlines_every_piece() {
+        let union = Polygon::union_all_rings(vec![
+            Polygon::with_holes(square(0.0, 0.0, 100.0), vec![square(40.0, 40.0, 20.0)]),
+            Polygon::new(&square(200.0, 0.0, 50.0)),
+        ]);
+        let outline = union.to_outline(Distance::meters(1.0)).unwrap();
+        // The edge of the first outer ring, the hole, and the other square
+        for pt in vec![
+            Pt2D::new(50.0, 0.0),
+            Pt2D::new(50.0, 40.0),
+            Pt2D::new(225.0, 50.0),
+        ] {
+            assert!(outline.contains_pt(pt), "outline misses {}", pt);
+        }
+        // Inside the hole
+        assert!(!union.contains_pt(Pt2D::new(50.0, 50.0)));
+        assert!(union.contains_pt(Pt2D::new(225.0, 25.0)));
+    }
+
+    // Star-shaped around a center, so the polygon is always simple, but often concave.
+    fn polygon_pts() -> impl Strategy<Value = Vec<Pt2D>> {
+        (
+            (-500.0..500.0f64, -500.0..500.0f64),
+            prop::collection::vec((10.0..100.0f64, 0.0..0.5f64), 3..12),
+        )
+            .prop_map(|((x, y), spokes)| {
+                let center = Pt2D::new(x, y);
+                let n = spokes.len() as f64;
+                spokes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, (radius, jitter))| {
+                        center.project_away(
+                            Distance::meters(radius),
+                            Angle::new_degs((idx as f64 + jitter) * 360.0 / n),
+                        )
+                    })
+                    .collect()
+            })
+    }
+
+    fn shoelace_area(pts: &Vec<Pt2D>) -> f64 {
+        let mut sum = 0.0;
+        for (idx, pt1) in pts.iter().enumerate() {
+            let pt2 = pts[(idx + 1) % pts.len()];
+            sum += pt1.x() * pt2.y() - pt2.x() * pt1.y();
+        }
+        (sum / 2.0).abs()
+    }
+
+    // Ray casting, independent of the triangulation
+    fn ring_contains(pts: &Vec<Pt2D>, pt: Pt2D) -> bool {
+        let mut inside = false;
+        for (idx, pt1) in pts.iter().enumerate() {
+            let pt2 = pts[(idx + 1) % pts.len()];
+            if (pt1.y() > pt.y()) != (pt2.y() > pt.y())
+                && pt.x() < (pt2.x() - pt1.x()) * (pt.y() - pt1.y()) / (pt2.y() - pt1.y()) + pt1.x()
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    fn dist_to_segment(pt: Pt2D, pt1: Pt2D, pt2: Pt2D) -> f64 {
+        let (dx, dy) = (pt2.x() - pt1.x(), pt2.y() - pt1.y());
+        let len_squared = dx * dx + dy * dy;
+        if len_squared == 0.0 {
+            return pt.raw_dist_to(pt1);
+        }
+        let t = (((pt.x() - pt1.x()) * dx + (pt.y() - pt1.y()) * dy) / len_squared)
+            .max(0.0)
+            .min(1.0);
+        pt.raw_dist_to(Pt2D::new(pt1.x() + t * dx, pt1.y() + t * dy))
+    }
+
+    // Points right on an edge could reasonably go either way
+    fn near_any_edge(poly: &Polygon, pt: Pt2D) -> bool {
+        poly.triangles().into_iter().any(|tri| {
+            dist_to_segment(pt, tri.pt1, tri.pt2) < 0.05
+                || dist_to_segment(pt, tri.pt2, tri.pt3) < 0.05
+                || dist_to_segment(pt, tri.pt3, tri.pt1) < 0.05
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn triangles_cover_area(pts in polygon_pts()) {
+            let poly = Polygon::new(&pts);
+            let sum: f64 = poly
+                .triangles()
+                .into_iter()
+                .map(|tri| shoelace_area(&vec![tri.pt1, tri.pt2, tri.pt3]))
+                .sum();
+            let expected = shoelace_area(&pts);
+            prop_assert!(
+                (sum - expected).abs() <= 1e-6 * expected + 0.01,
+                "{} vs {}",
+                sum,
+                expected
+            );
+        }
+
+        #[test]
+        fn triangle_centers_inside(pts in polygon_pts()) {
+            let poly = Polygon::new(&pts);
+            for tri in poly.triangles() {
+                let center = Pt2D::new(
+                    (tri.pt1.x() + tri.pt2.x() + tri.pt3.x()) / 3.0,
+                    (tri.pt1.y() + tri.pt2.y() + tri.pt3.y()) / 3.0,
+                );
+                if near_any_edge(&poly, center) {
+                    continue;
+                }
+                prop_assert!(poly.contains_pt(center));
+                prop_assert!(ring_contains(&pts, center));
+            }
+        }
+
+        #[test]
+        fn containment_matches_ring(
+            pts in polygon_pts(),
+            samples in prop::collection::vec((0.0..1.0f64, 0.0..1.0f64), 20),
+            (dx, dy) in (-100.0..100.0f64, -100.0..100.0f64),
+        ) {
+            let poly = Polygon::new(&pts);
+            let moved = poly.translate(dx, dy);
+            let b = poly.get_bounds();
+            for (x, y) in samples {
+                let pt = Pt2D::new(b.min_x + x * b.width(), b.min_y + y * b.height());
+                if near_any_edge(&poly, pt) || near_any_edge(&moved, pt.offset(dx, dy)) {
+                    continue;
+                }
+                prop_assert_eq!(
+                    poly.contains_pt(pt),
+                    ring_contains(&pts, pt),
+                    "{} in {:?}",
+                    pt,
+                    pts
+                );
+                prop_assert_eq!(moved.contains_pt(pt.offset(dx, dy)), poly.contains_pt(pt));
+            }
+        }
+    }
+}
@@ -700,6 +700,44 @@ impl PolyLine {
     pub fn get_bounds(&self) -> Bounds {
         Bounds::from(&self.pts)
     }
+
+    // Ramer-Douglas-Peucker: drops points within epsilon of the line between the points kept
+    // around them. The endpoints always stay.
+    pub fn simplify(&self, epsilon: Distance) -> PolyLine {
+        let mut keep = vec![false; self.pts.len()];
+        keep[0] = true;
+        keep[self.pts.len() - 1] = true;
+        let mut stack = vec![(0, self.pts.len() - 1)];
+        while let Some((i1, i2)) = stack.pop() {
+            let line = Line::new(self.pts[i1], self.pts[i2]);
+            let mut farthest: Option<(usize, Distance)> = None;
+            for idx in (i1 + 1)..i2 {
+                let pt = self.pts[idx];
+                let dist = match line {
+                    Some(ref l) => pt.dist_to(l.project_pt(pt)),
+                    // A loop
+                    None => pt.dist_to(self.pts[i1]),
+                };
+                if farthest.map(|(_, d)| dist > d).unwrap_or(true) {
+                    farthest = Some((idx, dist));
+                }
+            }
+            if let Some((idx, dist)) = farthest {
+                if dist > epsilon {
+                    keep[idx] = true;
+                    stack.push((i1, idx));
+                    stack.push((idx, i2));
+                }
+            }
+        }
+        PolyLine::unchecked_new(
+            self.pts
+                .iter()
+                .zip(keep)
+                .filter_map(|(pt, keep)| if keep { Some(*pt) } else { None })
+                .collect(),
+        )
+    }
 }
 
 impl fmt::Display for PolyLine {
@@ -763,3 +801,96 @@ fn to_set(pts: &[Pt2D]) -> (HashSet<HashablePt2D>, HashSet<HashablePt2D>) {
     }
     (deduped, dupes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const MAX_TURN_DEGS: f64 = 45.0;
+
+    // Wander around without any sharp turns or tiny segments, so shifting never flips anything
+    // around.
+    fn polyline() -> impl Strategy<Value = PolyLine> {
+        (
+            (-500.0..500.0f64, -500.0..500.0f64),
+            0.0..360.0f64,
+            prop::collection::vec((5.0..50.0f64, -MAX_TURN_DEGS..MAX_TURN_DEGS), 1..8),
+        )
+            .prop_filter_map("invalid polyline", |((x, y), heading, segments)| {
+                let mut pts = vec![Pt2D::new(x, y)];
+                let mut angle = Angle::new_degs(heading);
+                for (length, turn) in segments {
+                    angle = angle.rotate_degs(turn);
+                    let last = *pts.last().unwrap();
+                    pts.push(last.project_away(Distance::meters(length), angle));
+                }
+                PolyLine::new(pts).ok()
+            })
+    }
+
+    fn approx_eq(d1: Distance, d2: Distance, within: f64) -> bool {
+        (d1 - d2).inner_meters().abs() <= within
+    }
+
+    proptest! {
+        #[test]
+        fn length(pl in polyline()) {
+            let sum = pl.lines().iter().fold(Distance::ZERO, |so_far, l| so_far + l.length());
+            prop_assert!(approx_eq(pl.length(), sum, 0.01));
+            prop_assert!(approx_eq(pl.reversed().length(), pl.length(), 0.01));
+            prop_assert!(approx_eq(
+                pl.first_half().length() + pl.second_half().length(),
+                pl.length(),
+                0.01 * (pl.points().len() as f64 + 2.0)
+            ));
+        }
+
+        #[test]
+        fn simplify(pl in polyline(), epsilon in 0.0..10.0f64) {
+            let simple = pl.simplify(Distance::meters(epsilon));
+            prop_assert_eq!(simple.first_pt(), pl.first_pt());
+            prop_assert_eq!(simple.last_pt(), pl.last_pt());
+            prop_assert!(simple.points().len() <= pl.points().len());
+            prop_assert!(simple.length() <= pl.length() + Distance::meters(0.01));
+        }
+
+        #[test]
+        fn slice(pl in polyline(), start_pct in 0.0..0.9f64, len_pct in 0.0..1.0f64) {
+            let start = pl.length() * start_pct;
+            let end = start + (pl.length() - start) * len_pct;
+            prop_assume!(end - start >= Distance::meters(1.0));
+
+            let (slice, leftover) = pl.slice(start, end).unwrap();
+            let tolerance = 0.01 * (pl.points().len() as f64 + 2.0);
+            prop_assert_eq!(leftover, Distance::ZERO);
+            prop_assert!(approx_eq(slice.length(), end - start, tolerance));
+            prop_assert!(slice.first_pt().approx_eq(pl.must_dist_along(start).0, EPSILON_DIST));
+            prop_assert!(slice.last_pt().approx_eq(pl.must_dist_along(end).0, EPSILON_DIST));
+        }
+
+        #[test]
+        fn shift(pl in polyline(), width in 0.1..3.0f64) {
+            let width = Distance::meters(width);
+            let right = pl.shift_right(width).unwrap();
+            let left = pl.shift_left(width).unwrap();
+            prop_assert_eq!(right.points().len(), pl.points().len());
+            prop_assert_eq!(left.points().len(), pl.points().len());
+
+            // The endpoints move perpendicularly
+            prop_assert!(approx_eq(right.first_pt().dist_to(pl.first_pt()), width, 0.01));
+            prop_assert!(approx_eq(right.last_pt().dist_to(pl.last_pt()), width, 0.01));
+            prop_assert!(approx_eq(left.first_pt().dist_to(pl.first_pt()), width, 0.01));
+            prop_assert!(approx_eq(right.first_pt().dist_to(left.first_pt()), width * 2.0, 0.01));
+
+            // Every bend grows or shrinks the length by at most 2 * width * tan(turn / 2)
+            let max_change = 2.0
+                * width.inner_meters()
+                * (MAX_TURN_DEGS / 2.0).to_radians().tan()
+                * (pl.points().len() - 2) as f64
+                + 0.01 * pl.points().len() as f64;
+            prop_assert!(approx_eq(right.length(), pl.length(), max_change));
+            prop_assert!(approx_eq(left.length(), pl.length(), max_change));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{trim_f64, Distance, Duration, EPSILON_DIST};
+use crate::{trim_f64, Distance, Duration, UnitFmt, EPSILON_DIST};
 use serde::{Deserialize, Serialize};
 use std::{fmt, ops};
 
@@ -31,6 +31,10 @@ impl Speed {
         Speed::meters_per_second(0.44704 * value)
     }
 
+    pub fn km_per_hour(value: f64) -> Speed {
+        Speed::meters_per_second(value / 3.6)
+    }
+
     pub fn from_dist_time(d: Distance, t: Duration) -> Speed {
         Speed::meters_per_second(d.inner_meters() / t.inner_seconds())
     }
@@ -55,6 +59,14 @@ impl Speed {
             other
         }
     }
+
+    pub fn describe(self, fmt: &UnitFmt) -> String {
+        if fmt.metric {
+            format!("{} km/h", (self.0 * 3.6).round())
+        } else {
+            self.to_string()
+        }
+    }
 }
 
 impl ops::Add for Speed {
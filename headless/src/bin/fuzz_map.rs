@@ -0,0 +1,362 @@
+use abstutil::{CmdArgs, Timer};
+use geom::{Distance, Duration, GPSBounds, LonLat, Polygon, Pt2D};
+use map_model::raw::{
+    OriginalBuilding, OriginalIntersection, OriginalRoad, RawBuilding, RawIntersection, RawMap,
+    RawRoad,
+};
+use map_model::{osm, IntersectionType, Map};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use sim::{ScenarioGenerator, Sim, SimOptions};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Throw random small road networks at map construction, then run a short simulation on the result.
+// Real OSM data has all sorts of weird topology that breaks the importer in surprising ways; this
+// tries to find those problems first. Any input that panics or produces bad geometry is saved as a
+// synthetic map, so it can be opened in the map_editor and reproduced with --only.
+//
+// cargo run --release --bin fuzz_map -- --iterations=100 --seed=42
+// cargo run --release --bin fuzz_map -- --mutate=data/input/raw_maps/montlake.bin
+
+const WORLD_SIZE: f64 = 500.0;
+const HIGHWAY_TYPES: [&str; 7] = [
+    "primary",
+    "secondary",
+    "tertiary",
+    "residential",
+    "service",
+    "primary_link",
+    "footway",
+];
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let seed = args
+        .optional_parse("--seed", |s| s.parse::<u64>())
+        .unwrap_or(42);
+    let iterations = args
+        .optional_parse("--iterations", |s| s.parse::<usize>())
+        .unwrap_or(100);
+    let num_intersections = args
+        .optional_parse("--size", |s| s.parse::<usize>())
+        .unwrap_or(15);
+    let only = args.optional_parse("--only", |s| s.parse::<usize>());
+    let mutate = args.optional("--mutate");
+    args.done();
+
+    let base = mutate.map(|path| abstutil::read_binary::<RawMap>(path, &mut Timer::throwaway()));
+
+    let mut failures = Vec::new();
+    for iteration in 0..iterations {
+        // Every iteration gets its own RNG, so a single failure can be reproduced directly.
+        let mut rng = XorShiftRng::seed_from_u64(seed.wrapping_mul(1_000_003) + iteration as u64);
+        if only.map(|x| x != iteration).unwrap_or(false) {
+            continue;
+        }
+
+        let raw = match base {
+            Some(ref base) => mutate_map(base.clone(), &mut rng),
+            None => random_map(num_intersections, &mut rng),
+        };
+        let name = format!("fuzz_{}_{}", seed, iteration);
+
+        println!("Iteration {}: {} roads", iteration, raw.roads.len());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            check(raw.clone(), &mut rng)
+        }));
+        let problems = match result {
+            Ok(problems) => problems,
+            Err(err) => vec![format!("panicked: {}", panic_message(err))],
+        };
+        if !problems.is_empty() {
+            let mut raw = raw;
+            raw.name = name.clone();
+            abstutil::write_json(abstutil::path_synthetic_map(&name), &raw);
+            failures.push((iteration, problems));
+        }
+    }
+
+    println!("\n{} of {} inputs failed", failures.len(), iterations);
+    for (iteration, problems) in &failures {
+        println!("- Iteration {}:", iteration);
+        for p in problems {
+            println!("  - {}", p);
+        }
+    }
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+// Build the map, validate its geometry, and simulate a few minutes with invariant checking.
+fn check(raw: RawMap, rng: &mut XorShiftRng) -> Vec<String> {
+    let mut timer = Timer::throwaway();
+    let map = Map::create_from_raw(raw, true, &mut timer);
+    let mut problems = validate_geometry(&map);
+    if !problems.is_empty() {
+        return problems;
+    }
+
+    let mut opts = SimOptions::new("fuzz");
+    opts.alerts = sim::AlertHandler::Silence;
+    opts.check_invariants = true;
+    let mut sim = Sim::new(&map, opts, &mut timer);
+    ScenarioGenerator::small_run(&map)
+        .generate(&map, rng, &mut timer)
+        .instantiate(&mut sim, &map, rng, &mut timer);
+    sim.timed_step(&map, Duration::minutes(5), &mut None, &mut timer);
+    problems.extend(sim.invariant_violations().clone());
+    problems
+}
+
+fn validate_geometry(map: &Map) -> Vec<String> {
+    let mut problems = Vec::new();
+    for l in map.all_lanes() {
+        if l.lane_center_pts.length() <= Distance::ZERO {
+            problems.push(format!("{} has no length", l.id));
+        }
+        if !all_finite(l.lane_center_pts.points()) {
+            problems.push(format!("{} has non-finite points", l.id));
+        }
+    }
+    for i in map.all_intersections() {
+        if i.polygon.points().len() < 3 {
+            problems.push(format!("{} has a degenerate polygon", i.id));
+        }
+        if !all_finite(i.polygon.points()) {
+            problems.push(format!("{} has non-finite points", i.id));
+        }
+    }
+    for t in map.all_turns().values() {
+        if !all_finite(t.geom.points()) {
+            problems.push(format!("{} has non-finite points", t.id));
+        }
+    }
+    problems
+}
+
+fn all_finite(pts: &Vec<Pt2D>) -> bool {
+    pts.iter()
+        .all(|pt| pt.x().is_finite() && pt.y().is_finite())
+}
+
+fn random_map(num_intersections: usize, rng: &mut XorShiftRng) -> RawMap {
+    let mut raw = RawMap::blank("fuzz", "fuzz");
+
+    let mut pts = Vec::new();
+    for osm_node_id in 0..num_intersections as i64 {
+        let pt = Pt2D::new(
+            rng.gen_range(0.0, WORLD_SIZE),
+            rng.gen_range(0.0, WORLD_SIZE),
+        );
+        pts.push((OriginalIntersection { osm_node_id }, pt));
+    }
+    // Real data has intersections almost on top of each other
+    for _ in 0..num_intersections / 5 {
+        let (_, pt) = *pts.choose(rng).unwrap();
+        let osm_node_id = pts.len() as i64;
+        pts.push((
+            OriginalIntersection { osm_node_id },
+            pt.offset(rng.gen_range(-2.0, 2.0), rng.gen_range(-2.0, 2.0)),
+        ));
+    }
+
+    // Connect each intersection to a few of its nearest neighbors. Sometimes there are duplicate
+    // roads between the same pair.
+    let mut osm_way_id = 0;
+    for (i1, pt1) in &pts {
+        let mut by_dist: Vec<&(OriginalIntersection, Pt2D)> =
+            pts.iter().filter(|(i2, _)| i1 != i2).collect();
+        by_dist.sort_by_key(|(_, pt2)| pt1.dist_to(*pt2));
+        for (i2, pt2) in by_dist.into_iter().take(rng.gen_range(1, 4)) {
+            let copies = if rng.gen_bool(0.05) { 2 } else { 1 };
+            for _ in 0..copies {
+                if raw.roads.keys().any(|r| r.i1 == *i2 && r.i2 == *i1) && rng.gen_bool(0.7) {
+                    continue;
+                }
+                let mut center_points = vec![*pt1];
+                // A bit of curvature
+                if rng.gen_bool(0.3) {
+                    let mid = Pt2D::new((pt1.x() + pt2.x()) / 2.0, (pt1.y() + pt2.y()) / 2.0);
+                    center_points
+                        .push(mid.offset(rng.gen_range(-20.0, 20.0), rng.gen_range(-20.0, 20.0)));
+                }
+                center_points.push(*pt2);
+                raw.roads.insert(
+                    OriginalRoad {
+                        osm_way_id,
+                        i1: *i1,
+                        i2: *i2,
+                    },
+                    RawRoad {
+                        center_points,
+                        osm_tags: random_tags(rng),
+                        turn_restrictions: Vec::new(),
+                        complicated_turn_restrictions: Vec::new(),
+                    },
+                );
+                osm_way_id += 1;
+            }
+        }
+    }
+
+    let mut degree: BTreeMap<OriginalIntersection, usize> = BTreeMap::new();
+    for r in raw.roads.keys() {
+        *degree.entry(r.i1).or_insert(0) += 1;
+        *degree.entry(r.i2).or_insert(0) += 1;
+    }
+    for (id, point) in pts {
+        let intersection_type = match degree.get(&id) {
+            None => {
+                continue;
+            }
+            Some(1) => {
+                if rng.gen_bool(0.5) {
+                    IntersectionType::Border
+                } else {
+                    IntersectionType::StopSign
+                }
+            }
+            Some(_) => {
+                if rng.gen_bool(0.3) {
+                    IntersectionType::TrafficSignal
+                } else {
+                    IntersectionType::StopSign
+                }
+            }
+        };
+        raw.intersections.insert(
+            id,
+            RawIntersection {
+                point,
+                intersection_type,
+                elevation: Distance::ZERO,
+            },
+        );
+    }
+
+    // A few buildings, so trips have somewhere to go
+    for osm_way_id in 0..num_intersections as i64 {
+        let center = Pt2D::new(
+            rng.gen_range(0.0, WORLD_SIZE),
+            rng.gen_range(0.0, WORLD_SIZE),
+        );
+        raw.buildings.insert(
+            OriginalBuilding { osm_way_id },
+            RawBuilding {
+                polygon: Polygon::rectangle(10.0, 10.0).translate(center.x(), center.y()),
+                osm_tags: BTreeMap::new(),
+                public_garage_name: None,
+                num_parking_spots: 0,
+                amenities: BTreeSet::new(),
+            },
+        );
+    }
+
+    fix_bounds(&mut raw);
+    raw
+}
+
+fn random_tags(rng: &mut XorShiftRng) -> BTreeMap<String, String> {
+    let mut tags = BTreeMap::new();
+    tags.insert(
+        osm::HIGHWAY.to_string(),
+        HIGHWAY_TYPES.choose(rng).unwrap().to_string(),
+    );
+    if rng.gen_bool(0.2) {
+        tags.insert("oneway".to_string(), "yes".to_string());
+    }
+    if rng.gen_bool(0.3) {
+        tags.insert("lanes".to_string(), rng.gen_range(1, 7).to_string());
+    }
+    if rng.gen_bool(0.5) {
+        tags.insert(
+            osm::SIDEWALK.to_string(),
+            ["both", "left", "right", "none"]
+                .choose(rng)
+                .unwrap()
+                .to_string(),
+        );
+    }
+    if rng.gen_bool(0.2) {
+        tags.insert("cycleway".to_string(), "lane".to_string());
+    }
+    if rng.gen_bool(0.2) {
+        tags.insert(osm::PARKING_BOTH.to_string(), "parallel".to_string());
+    }
+    tags
+}
+
+// Take a real map and break it in small ways: delete roads, jitter points, drop tags, and squash
+// roads to almost nothing.
+fn mutate_map(mut raw: RawMap, rng: &mut XorShiftRng) -> RawMap {
+    let ids: Vec<OriginalRoad> = raw.roads.keys().cloned().collect();
+    let num_mutations = rng.gen_range(1, 1 + ids.len() / 10 + 1);
+    for _ in 0..num_mutations {
+        let id = *ids.choose(rng).unwrap();
+        if !raw.roads.contains_key(&id) {
+            continue;
+        }
+        match rng.gen_range(0, 4) {
+            0 => {
+                raw.roads.remove(&id);
+            }
+            1 => {
+                for pt in &mut raw.roads.get_mut(&id).unwrap().center_points {
+                    *pt = pt.offset(rng.gen_range(-5.0, 5.0), rng.gen_range(-5.0, 5.0));
+                }
+            }
+            2 => {
+                let tags = &mut raw.roads.get_mut(&id).unwrap().osm_tags;
+                let keys: Vec<String> = tags.keys().cloned().collect();
+                if let Some(k) = keys.choose(rng) {
+                    if k != osm::HIGHWAY {
+                        tags.remove(k);
+                    }
+                }
+            }
+            _ => {
+                let road = raw.roads.get_mut(&id).unwrap();
+                let first = road.center_points[0];
+                road.center_points = vec![first, first.offset(0.1, 0.1)];
+                if let Some(i) = raw.intersections.get_mut(&id.i2) {
+                    i.point = first.offset(0.1, 0.1);
+                }
+            }
+        }
+    }
+
+    // Don't leave intersections without roads
+    let mut used = BTreeSet::new();
+    for r in raw.roads.keys() {
+        used.insert(r.i1);
+        used.insert(r.i2);
+    }
+    raw.intersections.retain(|i, _| used.contains(i));
+    raw
+}
+
+// Same as the map_editor does for synthetic maps: pretend the world is somewhere in Seattle.
+fn fix_bounds(raw: &mut RawMap) {
+    let mut seattle_bounds = GPSBounds::new();
+    seattle_bounds.update(LonLat::new(-122.453224, 47.723277));
+    seattle_bounds.update(LonLat::new(-122.240505, 47.495342));
+
+    raw.boundary_polygon = Polygon::rectangle(WORLD_SIZE, WORLD_SIZE);
+    raw.gps_bounds = GPSBounds::new();
+    raw.gps_bounds
+        .update(Pt2D::new(0.0, 0.0).to_gps(&seattle_bounds));
+    raw.gps_bounds
+        .update(Pt2D::new(WORLD_SIZE, WORLD_SIZE).to_gps(&seattle_bounds));
+}
+
+fn panic_message(err: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = err.downcast_ref::<String>() {
+        s.clone()
+    } else if let Some(s) = err.downcast_ref::<&str>() {
+        s.to_string()
+    } else {
+        "unknown panic".to_string()
+    }
+}
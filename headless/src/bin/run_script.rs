@@ -0,0 +1,339 @@
+use abstutil::{CmdArgs, Timer};
+use geom::{Duration, Time};
+use map_model::{BuildingID, ControlTrafficSignal, IntersectionID, Map, PhaseType};
+use rand_xorshift::XorShiftRng;
+use rhai::{Dynamic, Engine, EvalAltResult, FuncArgs, ImmutableString, RegisterFn, Scope, AST};
+use sim::{
+    IndividTrip, PersonID, PersonSpec, Scenario, Sim, SimFlags, SpawnTrip, TripEndpoint, TripID,
+    TripMode,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+// Run a simulation with hooks into a Rhai script, so researchers can prototype control policies
+// without recompiling. The top level of the script runs once before the simulation starts. Then
+// these functions are called, if the script defines them:
+//
+// - on_tick(), every --tick seconds of sim time
+// - on_trip_start(trip, mode)
+// - on_trip_end(trip, mode, seconds); mode is "aborted" if the trip didn't finish
+// - on_phase_change(intersection, phase)
+//
+// Scripts can call:
+//
+// - time(), in seconds since midnight
+// - num_active_trips()
+// - num_phases(intersection), current_phase(intersection), phase_duration(intersection, phase)
+//   (all -1 if the intersection isn't a traffic signal)
+// - set_phase_duration(intersection, phase, seconds), which takes effect the next time the phase
+//   starts
+// - spawn_trip(from_building, to_building, mode), where mode is walk, bike, transit, or drive
+// - set_var(name, value) and get_var(name), since Rhai functions can't see global variables
+//
+// The functions that change something return false if they couldn't.
+//
+// cargo run --release --bin run_script -- --script=headless/scripts/example.rhai \
+//   data/system/scenarios/montlake/weekday.bin
+
+// How often to check for new events. Signal phases are at least this long.
+const STEP: Duration = Duration::const_seconds(1.0);
+
+struct World {
+    map: Map,
+    sim: Sim,
+    rng: XorShiftRng,
+    vars: BTreeMap<String, f64>,
+}
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let script = args.required("--script");
+    let tick = Duration::seconds(
+        args.optional_parse("--tick", |s| s.parse::<f64>())
+            .unwrap_or(60.0),
+    );
+    let mut sim_flags = SimFlags::from_args(&mut args);
+    // Off by default here; see SimOptions
+    sim_flags.opts.detect_gridlock = args.enabled("--detect_gridlock");
+    args.done();
+
+    let mut timer = Timer::new("setup scripted sim");
+    let (map, sim, rng) = sim_flags.load(&mut timer);
+    timer.done();
+    let world = Rc::new(RefCell::new(World {
+        map,
+        sim,
+        rng,
+        vars: BTreeMap::new(),
+    }));
+
+    let mut engine = Engine::new();
+    register_api(&mut engine, &world);
+    let ast = match engine.compile_file(script.clone().into()) {
+        Ok(ast) => ast,
+        Err(err) => panic!("Couldn't load {}: {}", script, err),
+    };
+    let mut hooks = Hooks {
+        engine,
+        ast,
+        scope: Scope::new(),
+        missing: BTreeSet::new(),
+    };
+    if let Err(err) = hooks
+        .engine
+        .consume_ast_with_scope(&mut hooks.scope, &hooks.ast)
+    {
+        panic!("{} failed: {}", script, err);
+    }
+
+    run(&world, &mut hooks, tick);
+
+    let w = world.borrow();
+    let (finished, unfinished) = w.sim.num_trips();
+    println!(
+        "Done at {}. {} trips finished, {} unfinished",
+        w.sim.time(),
+        abstutil::prettyprint_usize(finished),
+        abstutil::prettyprint_usize(unfinished)
+    );
+}
+
+struct Hooks {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    // Hooks the script doesn't define
+    missing: BTreeSet<&'static str>,
+}
+
+impl Hooks {
+    fn call<A: FuncArgs>(&mut self, name: &'static str, args: A) {
+        if self.missing.contains(name) {
+            return;
+        }
+        if let Err(err) = self
+            .engine
+            .call_fn::<A, Dynamic>(&mut self.scope, &self.ast, name, args)
+        {
+            match *err {
+                // The error names the function with its argument types
+                EvalAltResult::ErrorFunctionNotFound(ref f, _) if f.starts_with(name) => {
+                    self.missing.insert(name);
+                }
+                _ => panic!("{} failed: {}", name, err),
+            }
+        }
+    }
+}
+
+enum HookCall {
+    TripStart(TripID, TripMode),
+    TripEnd(TripID, Option<TripMode>, Duration),
+    PhaseChange(IntersectionID, usize),
+}
+
+fn run(world: &Rc<RefCell<World>>, hooks: &mut Hooks, tick: Duration) {
+    let mut trip_log_idx = 0;
+    let mut finished_idx = 0;
+    let mut started: BTreeSet<TripID> = BTreeSet::new();
+    let mut phases: BTreeMap<IntersectionID, usize> = BTreeMap::new();
+    {
+        let w = world.borrow();
+        for i in w.map.all_intersections() {
+            if i.is_traffic_signal() {
+                phases.insert(i.id, w.sim.current_phase_and_remaining_time(i.id).0);
+            }
+        }
+    }
+    let mut next_tick = world.borrow().sim.time() + tick;
+
+    loop {
+        // Don't hold onto the world while calling hooks; they need it too.
+        let (now, calls) = {
+            let mut w = world.borrow_mut();
+            let w = &mut *w;
+            if w.sim.is_done() || w.sim.time() >= w.sim.get_end_of_day() {
+                break;
+            }
+            w.sim
+                .timed_step(&w.map, STEP, &mut None, &mut Timer::throwaway());
+
+            let mut calls = Vec::new();
+            let analytics = w.sim.get_analytics();
+            for (_, trip, _, _) in &analytics.trip_log[trip_log_idx..] {
+                if started.insert(*trip) {
+                    calls.push(HookCall::TripStart(*trip, w.sim.trip_info(*trip).mode));
+                }
+            }
+            trip_log_idx = analytics.trip_log.len();
+            for (_, trip, mode, dt) in &analytics.finished_trips[finished_idx..] {
+                calls.push(HookCall::TripEnd(*trip, *mode, *dt));
+            }
+            finished_idx = analytics.finished_trips.len();
+            for (i, phase) in phases.iter_mut() {
+                let current = w.sim.current_phase_and_remaining_time(*i).0;
+                if current != *phase {
+                    *phase = current;
+                    calls.push(HookCall::PhaseChange(*i, current));
+                }
+            }
+            (w.sim.time(), calls)
+        };
+
+        for call in calls {
+            match call {
+                HookCall::TripStart(trip, mode) => {
+                    hooks.call("on_trip_start", (trip.0 as i64, mode.verb().to_string()));
+                }
+                HookCall::TripEnd(trip, mode, dt) => {
+                    hooks.call(
+                        "on_trip_end",
+                        (
+                            trip.0 as i64,
+                            mode.map(|m| m.verb()).unwrap_or("aborted").to_string(),
+                            dt.inner_seconds(),
+                        ),
+                    );
+                }
+                HookCall::PhaseChange(i, phase) => {
+                    hooks.call("on_phase_change", (i.0 as i64, phase as i64));
+                }
+            }
+        }
+        if now >= next_tick {
+            hooks.call("on_tick", ());
+            next_tick = next_tick + tick;
+        }
+    }
+}
+
+fn signal(map: &Map, i: i64) -> Option<&ControlTrafficSignal> {
+    if i < 0 {
+        return None;
+    }
+    map.maybe_get_traffic_signal(IntersectionID(i as usize))
+}
+
+fn register_api(engine: &mut Engine, world: &Rc<RefCell<World>>) {
+    let w = world.clone();
+    engine.register_fn("time", move || {
+        (w.borrow().sim.time() - Time::START_OF_DAY).inner_seconds()
+    });
+
+    let w = world.clone();
+    engine.register_fn("num_active_trips", move || {
+        w.borrow().sim.num_trips().1 as i64
+    });
+
+    let w = world.clone();
+    engine.register_fn("num_phases", move |i: i64| {
+        signal(&w.borrow().map, i)
+            .map(|ts| ts.phases.len() as i64)
+            .unwrap_or(-1)
+    });
+
+    let w = world.clone();
+    engine.register_fn("current_phase", move |i: i64| {
+        let w = w.borrow();
+        match signal(&w.map, i) {
+            Some(ts) => w.sim.current_phase_and_remaining_time(ts.id).0 as i64,
+            None => -1,
+        }
+    });
+
+    let w = world.clone();
+    engine.register_fn("phase_duration", move |i: i64, phase: i64| {
+        signal(&w.borrow().map, i)
+            .and_then(|ts| ts.phases.get(phase as usize))
+            .map(|p| p.phase_type.simple_duration().inner_seconds())
+            .unwrap_or(-1.0)
+    });
+
+    let w = world.clone();
+    engine.register_fn(
+        "set_phase_duration",
+        move |i: i64, phase: i64, seconds: f64| {
+            let mut w = w.borrow_mut();
+            let w = &mut *w;
+            if seconds < STEP.inner_seconds() {
+                return false;
+            }
+            let mut ts = match signal(&w.map, i) {
+                Some(ts) => ts.clone(),
+                None => {
+                    return false;
+                }
+            };
+            let dt = Duration::seconds(seconds);
+            match ts.phases.get_mut(phase as usize) {
+                Some(p) => {
+                    p.phase_type = match p.phase_type {
+                        PhaseType::Fixed(_) => PhaseType::Fixed(dt),
+                        PhaseType::Adaptive(_) => PhaseType::Adaptive(dt),
+                    };
+                }
+                None => {
+                    return false;
+                }
+            }
+            w.map.incremental_edit_traffic_signal(ts);
+            w.sim.handle_live_edited_traffic_signals(&w.map);
+            true
+        },
+    );
+
+    let w = world.clone();
+    engine.register_fn(
+        "spawn_trip",
+        move |from: i64, to: i64, mode: ImmutableString| {
+            let mut w = w.borrow_mut();
+            let w = &mut *w;
+            if from < 0 || to < 0 || from == to {
+                return false;
+            }
+            let (from, to) = (BuildingID(from as usize), BuildingID(to as usize));
+            if w.map.maybe_get_b(from).is_none() || w.map.maybe_get_b(to).is_none() {
+                return false;
+            }
+            let mode = match TripMode::all()
+                .into_iter()
+                .find(|m| m.verb() == mode.as_str())
+            {
+                Some(m) => m,
+                None => {
+                    return false;
+                }
+            };
+            let trip = match SpawnTrip::new(
+                TripEndpoint::Bldg(from),
+                TripEndpoint::Bldg(to),
+                mode,
+                &w.map,
+            ) {
+                Some(t) => t,
+                None => {
+                    return false;
+                }
+            };
+            let mut scenario = Scenario::empty(&w.map, "scripted trips");
+            scenario.people.push(PersonSpec {
+                id: PersonID(w.sim.get_all_people().len()),
+                orig_id: None,
+                trips: vec![IndividTrip::new(w.sim.time(), trip)],
+            });
+            scenario.instantiate(&mut w.sim, &w.map, &mut w.rng, &mut Timer::throwaway());
+            true
+        },
+    );
+
+    let w = world.clone();
+    engine.register_fn("set_var", move |name: ImmutableString, value: f64| {
+        w.borrow_mut().vars.insert(name.to_string(), value);
+    });
+
+    let w = world.clone();
+    engine.register_fn("get_var", move |name: ImmutableString| {
+        w.borrow().vars.get(name.as_str()).cloned().unwrap_or(0.0)
+    });
+}
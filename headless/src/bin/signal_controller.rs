@@ -0,0 +1,109 @@
+use abstutil::{CmdArgs, Timer};
+use geom::{Duration, Time};
+use map_model::IntersectionID;
+use sim::{DetectorState, PhaseCommand, SignalController, SimFlags};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+// Run a simulation where some traffic signals are driven by a controller in another process. This
+// connects over TCP to --controller. Every --tick seconds of sim time, it sends one line of JSON
+// with detector readings:
+//
+// {"time": 28800.0, "detectors": [{"intersection": 5, "current_phase": 0, "phase_ends_in": 3.0,
+//   "lanes": [{"lane": 12, "waiting": 2, "vehicles": 4}]}]}
+//
+// and waits for one line back with phase commands, maybe empty:
+//
+// [{"intersection": 5, "phase": 1, "duration": 10.0}]
+//
+// Times and durations are in seconds. A signal stays in its phase until it's told otherwise, but
+// agents only start turns they can finish within the last duration given.
+//
+// cargo run --release --bin signal_controller -- --controller=127.0.0.1:9000 --signals=5,12 \
+//   data/system/scenarios/montlake/weekday.bin
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let address = args.required("--controller");
+    // By default, all of them
+    let signals = args.optional_parse("--signals", |s| {
+        s.split(',')
+            .map(|x| x.parse::<usize>().map(IntersectionID))
+            .collect::<Result<Vec<_>, _>>()
+    });
+    let tick = Duration::seconds(
+        args.optional_parse("--tick", |s| s.parse::<f64>())
+            .unwrap_or(1.0),
+    );
+    let mut sim_flags = SimFlags::from_args(&mut args);
+    // Off by default here; see SimOptions
+    sim_flags.opts.detect_gridlock = args.enabled("--detect_gridlock");
+    args.done();
+
+    let mut timer = Timer::new("setup externally controlled sim");
+    let (map, mut sim, _) = sim_flags.load(&mut timer);
+    timer.done();
+
+    let signals = signals.unwrap_or_else(|| {
+        map.all_intersections()
+            .iter()
+            .filter(|i| i.is_traffic_signal())
+            .map(|i| i.id)
+            .collect()
+    });
+    println!("Handing {} signals over to {}", signals.len(), address);
+    sim.control_signals_externally(signals, &map);
+
+    let mut controller = match SocketController::connect(&address) {
+        Ok(c) => c,
+        Err(err) => panic!("Couldn't connect to {}: {}", address, err),
+    };
+    while !sim.is_done() && sim.time() < sim.get_end_of_day() {
+        sim.timed_step(&map, tick, &mut None, &mut Timer::throwaway());
+        if let Err(err) = sim.run_signal_controller(&map, &mut controller) {
+            println!("At {}, skipped bad commands: {}", sim.time(), err);
+        }
+    }
+    println!("Done at {}", sim.time());
+}
+
+struct SocketController {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl SocketController {
+    fn connect(address: &str) -> std::io::Result<SocketController> {
+        let writer = TcpStream::connect(address)?;
+        // One tiny message per tick; don't wait around to batch them up.
+        writer.set_nodelay(true)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(SocketController { reader, writer })
+    }
+}
+
+impl SignalController for SocketController {
+    fn control(&mut self, now: Time, detectors: Vec<DetectorState>) -> Vec<PhaseCommand> {
+        let msg = serde_json::json!({
+            "time": (now - Time::START_OF_DAY).inner_seconds(),
+            "detectors": detectors,
+        });
+        if let Err(err) = writeln!(self.writer, "{}", msg) {
+            panic!("Lost the controller: {}", err);
+        }
+
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => panic!("The controller hung up"),
+            Ok(_) => {}
+            Err(err) => panic!("Lost the controller: {}", err),
+        }
+        match serde_json::from_str(&line) {
+            Ok(cmds) => cmds,
+            Err(err) => {
+                println!("At {}, couldn't understand the controller: {}", now, err);
+                Vec::new()
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use abstutil::{CmdArgs, Timer};
+use geom::{Duration, Histogram, Statistic, Time};
+use map_model::{Map, MapEdits, MapProvenance};
+use serde_json::{json, Value};
+use sim::{Scenario, Sim, SimFlags, TelemetryUpdate, TripMode, TripQuery};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::TcpStream;
+
+// Run a scenario without the UI, optionally with some map edits, then write aggregate metrics as
+// JSON. Meant for batch experiments and catching regressions in CI.
+//
+// cargo run --release --bin sim_metrics -- --edits="bus lanes on 24th" --hours=12 \
+//   --output=metrics.json data/system/scenarios/montlake/weekday.bin
+//
+// The output has trip times per mode, the most vehicles stopped on each lane at once, and delays
+// per intersection, plus anywhere still gridlocked at the end if --detect_gridlock is passed.
+// Times and durations are in seconds. It also records where the map came from, so results can be
+// matched to the exact map they were produced with.
+//
+// Pass --query="mode=drive AND duration>30min" to also summarize just the trips matching a query;
+// see sim::TripQuery for the syntax.
+//
+// To watch a long run from another machine, start `viewer --telemetry=0.0.0.0:9100` there, then
+// pass --stream=that_machine:9100. Every sample gets sent as one line of JSON, unencrypted. The
+// viewer accepts updates from anybody, so keep both on a trusted network.
+
+// How often to check on the run and report progress
+const SAMPLE_INTERVAL: Duration = Duration::const_seconds(60.0);
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let edits_name = args.optional("--edits");
+    let query = args
+        .optional("--query")
+        .map(|q| match TripQuery::parse(&q) {
+            Ok(parsed) => (q, parsed),
+            Err(err) => panic!("Bad --query: {}", err),
+        });
+    // By default, run until everybody's done or the day ends
+    let hours = args.optional_parse("--hours", |s| s.parse::<f64>());
+    let output = args
+        .optional("--output")
+        .unwrap_or_else(|| "metrics.json".to_string());
+    let mut stream = args
+        .optional("--stream")
+        .map(|address| match TcpStream::connect(&address) {
+            Ok(s) => s,
+            Err(err) => panic!("Couldn't connect to the viewer at {}: {}", address, err),
+        });
+    let mut sim_flags = SimFlags::from_args(&mut args);
+    // Off by default here; see SimOptions
+    sim_flags.opts.detect_gridlock = args.enabled("--detect_gridlock");
+    args.done();
+
+    let mut timer = Timer::new("setup headless sim");
+    let scenario: Scenario = abstutil::read_binary(sim_flags.load.clone(), &mut timer);
+    let mut map = Map::new(abstutil::path_map(&scenario.map_name), &mut timer);
+    if let Some(ref name) = edits_name {
+        let edits = match MapEdits::load(&map, name, &mut timer) {
+            Ok(e) => e,
+            Err(err) => panic!("Couldn't load {}: {}", name, err),
+        };
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+    let mut opts = sim_flags.opts.clone();
+    if opts.run_name == "unnamed" {
+        opts.run_name = scenario.scenario_name.clone();
+    }
+    let mut sim = Sim::new(&map, opts, &mut timer);
+    scenario.instantiate(&mut sim, &map, &mut sim_flags.make_rng(), &mut timer);
+    timer.done();
+
+    let end = match hours {
+        Some(h) => Time::START_OF_DAY + Duration::seconds(h * 3600.0),
+        None => sim.get_end_of_day(),
+    };
+    let timer = Timer::new("run sim");
+    while !sim.is_done() && sim.time() < end {
+        let dt = SAMPLE_INTERVAL.min(end - sim.time());
+        sim.timed_step(&map, dt, &mut None, &mut Timer::throwaway());
+        if let Some(mut s) = stream.take() {
+            if send_telemetry(&mut s, &sim, &map, &scenario.scenario_name, false) {
+                stream = Some(s);
+            }
+        }
+    }
+    timer.done();
+    println!("Done at {}", sim.time());
+    if let Some(mut s) = stream {
+        send_telemetry(&mut s, &sim, &map, &scenario.scenario_name, true);
+    }
+
+    let mut metrics = json!({
+        "map": map.get_name(),
+        "map_provenance": MapProvenance::load(map.get_name()),
+        "scenario": scenario.scenario_name,
+        "edits": map.get_edits().edits_name,
+        "end_time": (sim.time() - Time::START_OF_DAY).inner_seconds(),
+        "trips": trip_times(&sim),
+        "max_queued_per_lane": sim
+            .max_queued_per_lane()
+            .iter()
+            .map(|(l, max)| json!({"lane": l, "max_queued": max}))
+            .collect::<Vec<_>>(),
+        "intersection_delays": intersection_delays(&sim),
+        "gridlock": sim.current_gridlock(),
+    });
+    if let Some((q, parsed)) = query {
+        let trips = match parsed.find(&sim, &map) {
+            Ok(trips) => trips,
+            Err(err) => panic!("Bad --query: {}", err),
+        };
+        let mut hgram = Histogram::new();
+        for id in &trips {
+            hgram.add(sim.finished_trip_time(*id).unwrap().0);
+        }
+        let mut summary = summarize(&hgram);
+        summary["query"] = json!(q);
+        summary["trips"] = json!(trips.into_iter().map(|t| t.0).collect::<Vec<_>>());
+        metrics["query"] = summary;
+    }
+    abstutil::write_json(output, &metrics);
+}
+
+// Returns false if the viewer went away. The run keeps going without it.
+fn send_telemetry(
+    stream: &mut TcpStream,
+    sim: &Sim,
+    map: &Map,
+    scenario_name: &str,
+    done: bool,
+) -> bool {
+    let sample = match sim.get_analytics().metrics.last() {
+        Some(s) => s.clone(),
+        None => {
+            return true;
+        }
+    };
+    let update = TelemetryUpdate {
+        map_name: map.get_name().to_string(),
+        scenario_name: scenario_name.to_string(),
+        edits_name: map.get_edits().edits_name.clone(),
+        sample,
+        unfinished_trips: sim.num_trips().1,
+        done,
+    };
+    if let Err(err) = writeln!(stream, "{}", serde_json::to_string(&update).unwrap()) {
+        println!("Lost the viewer, continuing without it: {}", err);
+        return false;
+    }
+    true
+}
+
+// Per mode, how many trips finished and the distribution of how long they took
+fn trip_times(sim: &Sim) -> Value {
+    let mut per_mode: BTreeMap<TripMode, Histogram<Duration>> = TripMode::all()
+        .into_iter()
+        .map(|m| (m, Histogram::new()))
+        .collect();
+    let mut aborted = 0;
+    for (_, _, mode, dt) in &sim.get_analytics().finished_trips {
+        match mode {
+            Some(m) => per_mode.get_mut(m).unwrap().add(*dt),
+            None => {
+                aborted += 1;
+            }
+        }
+    }
+
+    let mut result = serde_json::Map::new();
+    for (mode, hgram) in per_mode {
+        result.insert(format!("{:?}", mode).to_lowercase(), summarize(&hgram));
+    }
+    result.insert("aborted".to_string(), json!(aborted));
+    Value::Object(result)
+}
+
+// Every intersection where somebody waited, with the distribution of delays there
+fn intersection_delays(sim: &Sim) -> Vec<Value> {
+    sim.get_analytics()
+        .intersection_delays
+        .iter()
+        .filter(|(_, delays)| !delays.is_empty())
+        .map(|(i, delays)| {
+            let mut hgram = Histogram::new();
+            for (_, dt, _) in delays {
+                hgram.add(*dt);
+            }
+            let mut summary = summarize(&hgram);
+            summary["intersection"] = json!(i);
+            summary
+        })
+        .collect()
+}
+
+fn summarize(hgram: &Histogram<Duration>) -> Value {
+    let mut result = serde_json::Map::new();
+    result.insert("count".to_string(), json!(hgram.count()));
+    for (key, stat) in vec![
+        ("min", Statistic::Min),
+        ("mean", Statistic::Mean),
+        ("p50", Statistic::P50),
+        ("p90", Statistic::P90),
+        ("p99", Statistic::P99),
+        ("max", Statistic::Max),
+    ] {
+        if let Some(dt) = hgram.select(stat) {
+            result.insert(key.to_string(), json!(dt.inner_seconds()));
+        }
+    }
+    Value::Object(result)
+}
@@ -0,0 +1,155 @@
+use abstutil::{CmdArgs, Timer};
+use geom::{Bounds, Polygon};
+use map_model::{EditedObject, Map, MapEdits};
+use std::fs::File;
+use std::io::Write;
+
+// Describe a proposal's edits in plain language, so it can be reviewed without opening the game.
+// Prints one line per change. With --html, also writes a page with a thumbnail of each change.
+//
+// cargo run --release --bin summarize_edits -- --map=data/system/maps/montlake.bin \
+//   --edits="bus lanes on 24th" --html=summary.html
+
+const THUMBNAIL_SIZE: f64 = 200.0;
+// Meters of context around the changed objects
+const THUMBNAIL_PADDING: f64 = 50.0;
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let map_path = args.required("--map");
+    let edits_name = args.required("--edits");
+    let html_path = args.optional("--html");
+    args.done();
+
+    let mut timer = Timer::new("summarize edits");
+    let map = Map::new(map_path, &mut timer);
+    let edits = match MapEdits::load(&map, &edits_name, &mut timer) {
+        Ok(e) => e,
+        Err(err) => panic!("Couldn't load {}: {}", edits_name, err),
+    };
+    timer.done();
+
+    let summary = edits.summarize(&map);
+    println!("{}", edits.edits_name);
+    for line in &edits.proposal_description {
+        println!("  {}", line);
+    }
+    for item in &summary {
+        println!("- {}", item.description);
+    }
+
+    if let Some(path) = html_path {
+        let mut f = File::create(&path).unwrap();
+        writeln!(
+            f,
+            "<html><head><title>{}</title></head><body>",
+            escape(&edits.edits_name)
+        )
+        .unwrap();
+        writeln!(f, "<h1>{}</h1>", escape(&edits.edits_name)).unwrap();
+        for line in &edits.proposal_description {
+            writeln!(f, "<p>{}</p>", escape(line)).unwrap();
+        }
+        if let Some(ref link) = edits.proposal_link {
+            writeln!(
+                f,
+                "<p><a href=\"{}\">{}</a></p>",
+                escape(link),
+                escape(link)
+            )
+            .unwrap();
+        }
+        writeln!(f, "<table>").unwrap();
+        for item in &summary {
+            writeln!(
+                f,
+                "<tr><td>{}</td><td>{}</td></tr>",
+                thumbnail(&map, &item.objects),
+                escape(&item.description)
+            )
+            .unwrap();
+        }
+        writeln!(f, "</table></body></html>").unwrap();
+        println!("Wrote {}", path);
+    }
+}
+
+// An SVG of the roads and intersections around some objects, with the objects highlighted
+fn thumbnail(map: &Map, objects: &Vec<EditedObject>) -> String {
+    let highlight: Vec<Polygon> = objects.iter().map(|obj| polygon(map, *obj)).collect();
+    let mut bounds = Bounds::new();
+    for p in &highlight {
+        bounds.union(p.get_bounds());
+    }
+    // Square, so every thumbnail is drawn at the same size
+    let center = bounds.center();
+    let radius = (bounds.width().max(bounds.height()) / 2.0) + THUMBNAIL_PADDING;
+    let mut view = Bounds::new();
+    view.min_x = center.x() - radius;
+    view.min_y = center.y() - radius;
+    view.max_x = center.x() + radius;
+    view.max_y = center.y() + radius;
+
+    let mut context = Vec::new();
+    for r in map.all_roads() {
+        let p = r.get_thick_polygon(map);
+        if p.get_bounds().overlaps(&view) {
+            context.push(p);
+        }
+    }
+    for i in map.all_intersections() {
+        if i.polygon.get_bounds().overlaps(&view) {
+            context.push(i.polygon.clone());
+        }
+    }
+
+    format!(
+        "<svg width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\" style=\"background: \
+         #C4C4C4\"><path fill=\"#555555\" d=\"{}\"/><path fill=\"#FF2A6D\" d=\"{}\"/></svg>",
+        THUMBNAIL_SIZE,
+        THUMBNAIL_SIZE,
+        view.min_x,
+        view.min_y,
+        2.0 * radius,
+        2.0 * radius,
+        svg_path(&context),
+        svg_path(&highlight)
+    )
+}
+
+fn polygon(map: &Map, obj: EditedObject) -> Polygon {
+    match obj {
+        EditedObject::Lane(l) => {
+            let lane = map.get_l(l);
+            lane.lane_center_pts.make_polygons(lane.width)
+        }
+        EditedObject::Road(r) => map.get_r(r).get_thick_polygon(map),
+        EditedObject::Intersection(i) => map.get_i(i).polygon.clone(),
+    }
+}
+
+// Polygons aren't always stored as one ring, so just draw every triangle.
+fn svg_path(polygons: &Vec<Polygon>) -> String {
+    let mut d = String::new();
+    for p in polygons {
+        for tri in p.triangles() {
+            d.push_str(&format!(
+                "M{:.1} {:.1}L{:.1} {:.1}L{:.1} {:.1}Z",
+                tri.pt1.x(),
+                tri.pt1.y(),
+                tri.pt2.x(),
+                tri.pt2.y(),
+                tri.pt3.x(),
+                tri.pt3.y()
+            ));
+        }
+    }
+    d
+}
+
+fn escape(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
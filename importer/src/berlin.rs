@@ -1,4 +1,4 @@
-use crate::utils::{download, download_kml, osmconvert};
+use crate::utils::{download, download_kml};
 use abstutil::Timer;
 use kml::ExtraShapes;
 use serde::Deserialize;
@@ -41,16 +41,13 @@ fn input() {
 
 pub fn osm_to_raw(name: &str) {
     input();
-    osmconvert(
-        "input/berlin/osm/berlin-latest.osm.pbf",
-        format!("input/berlin/polygons/{}.poly", name),
-        format!("input/berlin/osm/{}.osm", name),
-    );
+    // convert_osm only keeps what's inside the clipping polygon while reading the PBF
+    let osm_input = abstutil::path("input/berlin/osm/berlin-latest.osm.pbf");
 
     println!("- Running convert_osm");
     let map = convert_osm::convert(
         convert_osm::Options {
-            osm_input: abstutil::path(format!("input/berlin/osm/{}.osm", name)),
+            osm_input,
             city_name: "berlin".to_string(),
             name: name.to_string(),
 
@@ -61,11 +58,14 @@ pub fn osm_to_raw(name: &str) {
             map_config: map_model::MapConfig {
                 driving_side: map_model::raw::DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                right_turn_on_red: false,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::JustOSM,
+            sidewalks: convert_osm::Sidewalks::Infer,
             public_offstreet_parking: convert_osm::PublicOffstreetParking::None,
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(3),
+            traffic_signals: convert_osm::TrafficSignals::JustOSM,
             elevation: None,
         },
         &mut abstutil::Timer::throwaway(),
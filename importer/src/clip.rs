@@ -0,0 +1,266 @@
+use abstutil::{FileWithProgress, Timer};
+use geom::LonLat;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+
+// Clips an .osm file (optionally compressed as .osm.bz2) to an Osmosis polygon, like `osmconvert
+// -B=clipping.poly --complete-ways`. Every way with at least one node inside is kept whole, along
+// with all of its nodes. convert_osm later trims roads crossing the boundary and turns the
+// crossing points into border intersections. Skips if the output is newer than the input and the
+// polygon, so changing either one clips again.
+//
+// The input is streamed twice, so huge extracts never have to fit in memory. Only XML needs this;
+// convert_osm clips .osm.pbf files itself while reading them.
+pub fn clip_osm(input: &str, clipping_polygon: &str, output: &str) {
+    if newer_than(output, &[input, clipping_polygon]) {
+        println!("- {} already exists", output);
+        return;
+    }
+    println!("- Clipping {} to {}", input, clipping_polygon);
+    let mut timer = Timer::new(format!("clip {}", input));
+    let boundary = LonLat::read_osmosis_polygon(clipping_polygon.to_string()).unwrap();
+
+    // First figure out what to keep. OSM files list all nodes, then all ways, then all relations,
+    // so by the time a way or relation shows up, everything it refers to has been decided.
+    let mut inside_nodes: HashSet<i64> = HashSet::new();
+    let mut keep_nodes: HashSet<i64> = HashSet::new();
+    let mut keep_ways: HashSet<i64> = HashSet::new();
+    let mut keep_relations: HashSet<i64> = HashSet::new();
+    // The way or relation being read, and the nodes or members seen so far
+    let mut current_id: i64 = 0;
+    let mut way_nodes: Vec<i64> = Vec::new();
+    let mut relation_matches = false;
+
+    timer.start("find everything inside the boundary");
+    read_osm(input, &mut timer, |event| match event {
+        XmlEvent::StartElement {
+            name, attributes, ..
+        } => match name.local_name.as_ref() {
+            "node" => {
+                let lon: f64 = attr(&attributes, "lon").parse().unwrap();
+                let lat: f64 = attr(&attributes, "lat").parse().unwrap();
+                if contains(&boundary, LonLat::new(lon, lat)) {
+                    inside_nodes.insert(attr(&attributes, "id").parse().unwrap());
+                }
+            }
+            "way" | "relation" => {
+                current_id = attr(&attributes, "id").parse().unwrap();
+                way_nodes.clear();
+                relation_matches = false;
+            }
+            "nd" => {
+                way_nodes.push(attr(&attributes, "ref").parse().unwrap());
+            }
+            "member" => {
+                let id: i64 = attr(&attributes, "ref").parse().unwrap();
+                relation_matches |= match attr(&attributes, "type").as_ref() {
+                    "node" => inside_nodes.contains(&id),
+                    "way" => keep_ways.contains(&id),
+                    "relation" => keep_relations.contains(&id),
+                    _ => false,
+                };
+            }
+            _ => {}
+        },
+        XmlEvent::EndElement { name } => match name.local_name.as_ref() {
+            "way" => {
+                if way_nodes.iter().any(|n| inside_nodes.contains(n)) {
+                    keep_ways.insert(current_id);
+                    keep_nodes.extend(way_nodes.drain(..));
+                }
+            }
+            "relation" => {
+                if relation_matches {
+                    keep_relations.insert(current_id);
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    });
+    keep_nodes.extend(inside_nodes);
+    timer.stop("find everything inside the boundary");
+    timer.note(format!(
+        "Keeping {} nodes, {} ways, {} relations",
+        abstutil::prettyprint_usize(keep_nodes.len()),
+        abstutil::prettyprint_usize(keep_ways.len()),
+        abstutil::prettyprint_usize(keep_relations.len())
+    ));
+
+    // Then copy those objects over
+    timer.start(format!("write {}", output));
+    let mut out = BufWriter::new(File::create(output).unwrap());
+    writeln!(out, "<?xml version='1.0' encoding='UTF-8'?>").unwrap();
+    writeln!(out, "<osm version=\"0.6\" generator=\"abstreet importer\">").unwrap();
+    // How deep into an object being copied
+    let mut depth = 0;
+    read_osm(input, &mut timer, |event| match event {
+        XmlEvent::StartElement {
+            name, attributes, ..
+        } => {
+            if depth == 0 {
+                let keep = match name.local_name.as_ref() {
+                    "node" => &keep_nodes,
+                    "way" => &keep_ways,
+                    "relation" => &keep_relations,
+                    _ => {
+                        return;
+                    }
+                };
+                if !keep.contains(&attr(&attributes, "id").parse().unwrap()) {
+                    return;
+                }
+            }
+            depth += 1;
+            write!(out, "{}<{}", "  ".repeat(depth), name.local_name).unwrap();
+            for a in attributes {
+                write!(out, " {}=\"{}\"", a.name.local_name, escape(&a.value)).unwrap();
+            }
+            writeln!(out, ">").unwrap();
+        }
+        XmlEvent::EndElement { name } => {
+            if depth > 0 {
+                writeln!(out, "{}</{}>", "  ".repeat(depth), name.local_name).unwrap();
+                depth -= 1;
+            }
+        }
+        _ => {}
+    });
+    writeln!(out, "</osm>").unwrap();
+    timer.stop(format!("write {}", output));
+}
+
+fn read_osm<F: FnMut(XmlEvent)>(path: &str, timer: &mut Timer, mut handle: F) {
+    let (reader, done) = FileWithProgress::new(path).unwrap();
+    let reader: Box<dyn Read> = if path.ends_with(".bz2") {
+        Box::new(bzip2::read::MultiBzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+    for event in EventReader::new(reader) {
+        handle(event.unwrap_or_else(|err| panic!("Can't parse {}: {}", path, err)));
+    }
+    done(timer);
+}
+
+// False if the output is missing, or if any modification time can't be read
+fn newer_than(output: &str, inputs: &[&str]) -> bool {
+    let modified = |path: &str| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    match modified(output) {
+        Some(out) => inputs
+            .iter()
+            .all(|path| modified(path).map(|t| t <= out).unwrap_or(false)),
+        None => false,
+    }
+}
+
+fn attr(attributes: &[OwnedAttribute], key: &str) -> String {
+    attributes
+        .iter()
+        .find(|a| a.name.local_name == key)
+        .unwrap_or_else(|| panic!("OSM element missing {}", key))
+        .value
+        .clone()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Even-odd rule, treating coordinates as planar. Fine at the scale of a city.
+fn contains(boundary: &[LonLat], pt: LonLat) -> bool {
+    let mut inside = false;
+    let mut j = boundary.len() - 1;
+    for i in 0..boundary.len() {
+        let (a, b) = (boundary[i], boundary[j]);
+        if (a.y() > pt.y()) != (b.y() > pt.y())
+            && pt.x() < (b.x() - a.x()) * (pt.y() - a.y()) / (b.y() - a.y()) + a.x()
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_whole_ways_touching_the_boundary() {
+        let dir = std::env::temp_dir().join(format!("abst_clip_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.osm").display().to_string();
+        let poly = dir.join("boundary.poly").display().to_string();
+        let output = dir.join("output.osm").display().to_string();
+
+        // A square from (0, 0) to (1, 1)
+        std::fs::write(
+            &poly,
+            vec![
+                "boundary",
+                "1",
+                "    0.0    0.0",
+                "    1.0    0.0",
+                "    1.0    1.0",
+                "    0.0    1.0",
+                "END",
+                "END",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        // Node 1 is inside, 2 and 3 are outside. Way 10 crosses the boundary, way 11 is entirely
+        // outside. Relation 20 refers to way 10, relation 21 only to way 11.
+        std::fs::write(
+            &input,
+            r#"<?xml version='1.0' encoding='UTF-8'?>
+<osm version="0.6">
+  <node id="1" lat="0.5" lon="0.5"/>
+  <node id="2" lat="2.0" lon="2.0"/>
+  <node id="3" lat="3.0" lon="3.0"/>
+  <way id="10"><nd ref="1"/><nd ref="2"/><tag k="highway" v="residential"/></way>
+  <way id="11"><nd ref="2"/><nd ref="3"/></way>
+  <relation id="20"><member type="way" ref="10" role=""/></relation>
+  <relation id="21"><member type="way" ref="11" role=""/></relation>
+</osm>"#,
+        )
+        .unwrap();
+
+        clip_osm(&input, &poly, &output);
+        let mut ids: Vec<(String, String)> = Vec::new();
+        read_osm(&output, &mut Timer::throwaway(), |event| {
+            if let XmlEvent::StartElement {
+                name, attributes, ..
+            } = event
+            {
+                if name.local_name == "node"
+                    || name.local_name == "way"
+                    || name.local_name == "relation"
+                {
+                    ids.push((name.local_name, attr(&attributes, "id")));
+                }
+            }
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let expected: Vec<(String, String)> = vec![
+            ("node", "1"),
+            ("node", "2"),
+            ("way", "10"),
+            ("relation", "20"),
+        ]
+        .into_iter()
+        .map(|(t, id)| (t.to_string(), id.to_string()))
+        .collect();
+        assert_eq!(ids, expected);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::utils::{download, osmconvert};
+use crate::utils::download;
 
 fn input() {
     download(
@@ -9,16 +9,13 @@ fn input() {
 
 pub fn osm_to_raw(name: &str) {
     input();
-    osmconvert(
-        "input/krakow/osm/malopolskie-latest.osm.pbf",
-        format!("input/krakow/polygons/{}.poly", name),
-        format!("input/krakow/osm/{}.osm", name),
-    );
+    // convert_osm only keeps what's inside the clipping polygon while reading the PBF
+    let osm_input = abstutil::path("input/krakow/osm/malopolskie-latest.osm.pbf");
 
     println!("- Running convert_osm");
     let map = convert_osm::convert(
         convert_osm::Options {
-            osm_input: abstutil::path(format!("input/krakow/osm/{}.osm", name)),
+            osm_input,
             city_name: "krakow".to_string(),
             name: name.to_string(),
 
@@ -29,11 +26,14 @@ pub fn osm_to_raw(name: &str) {
             map_config: map_model::MapConfig {
                 driving_side: map_model::raw::DrivingSide::Right,
                 bikes_can_use_bus_lanes: false,
+                right_turn_on_red: false,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::SomeAdditionalWhereNoData { pct: 90 },
+            sidewalks: convert_osm::Sidewalks::Infer,
             public_offstreet_parking: convert_osm::PublicOffstreetParking::None,
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(3), /* TODO: support amenity=parking_entrance */
+            traffic_signals: convert_osm::TrafficSignals::JustOSM,
             // TODO: investigate why some many buildings drop their private parkings
             elevation: None,
         },
@@ -1,5 +1,9 @@
 mod berlin;
+mod clip;
 mod krakow;
+mod od_matrix;
+mod pipeline;
+mod population;
 mod seattle;
 #[cfg(feature = "scenarios")]
 mod soundcast;
@@ -13,19 +17,30 @@ struct Job {
     raw_to_map: bool,
     scenario: bool,
     scenario_everyone: bool,
+    neighborhoods: bool,
+    population: Option<String>,
+    od_matrix: Option<String>,
 
     skip_ch: bool,
 
+    pipeline: bool,
+    bbox: Option<String>,
+    drive_on_left: bool,
+
     only_map: Option<String>,
 
     oneshot: Option<String>,
     oneshot_clip: Option<String>,
     oneshot_drive_on_left: bool,
+    oneshot_no_inferred_sidewalks: bool,
+    oneshot_signals: Option<String>,
+    oneshot_blockface: Option<String>,
+    oneshot_garages: Option<String>,
 }
 
 fn main() {
     let mut args = abstutil::CmdArgs::new();
-    let job = Job {
+    let mut job = Job {
         city: args.optional("--city").unwrap_or("seattle".to_string()),
         // Download all raw input files, then convert OSM to the intermediate RawMap.
         osm_to_raw: args.enabled("--raw"),
@@ -35,10 +50,26 @@ fn main() {
         scenario: args.enabled("--scenario"),
         // Produce a variation of the weekday scenario including off-map trips.
         scenario_everyone: args.enabled("--scenario_everyone"),
+        // Clip neighborhood boundaries from data/input/$city/neighborhoods/ to each map.
+        neighborhoods: args.enabled("--neighborhoods"),
+        // Synthesize the people living in each map from a CSV of block-level population and
+        // employment counts.
+        population: args.optional("--population"),
+        // Turn a CSV of origin-destination pairs into a scenario.
+        od_matrix: args.optional("--od_matrix"),
         // Skip the most expensive step of --map, building contraction hierarchies. The resulting
         // map won't be usable for simulation; as soon as you try to pathfind, it'll crash.
         skip_ch: args.enabled("--skip_ch"),
 
+        // Do everything needed to play the maps: --raw, --map, then record the results in
+        // data/player/local_builds.txt, so the updater leaves them alone.
+        pipeline: args.enabled("--pipeline"),
+        // Instead of a city, download OSM data for min_lon,min_lat,max_lon,max_lat and run the
+        // whole pipeline on it. The map needs a name.
+        bbox: args.optional("--bbox"),
+        // Only for --bbox
+        drive_on_left: args.enabled("--drive_on_left"),
+
         // Only process one map. If not specified, process all maps defined by clipping polygons in
         // data/input/$city/polygons/.
         only_map: args.optional_free(),
@@ -47,26 +78,65 @@ fn main() {
         oneshot: args.optional("--oneshot"),
         oneshot_clip: args.optional("--oneshot_clip"),
         oneshot_drive_on_left: args.enabled("--oneshot_drive_on_left"),
+        // Only create sidewalks where OSM has them tagged.
+        oneshot_no_inferred_sidewalks: args.enabled("--oneshot_no_inferred_sidewalks"),
+        // A city's inventory of traffic signals, as KML or CSV, to use in addition to OSM.
+        oneshot_signals: args.optional("--oneshot_signals"),
+        // Where the city says there is and isn't on-street parking, as lines along the curb in
+        // KML or GeoJSON. Used where OSM doesn't say. A CSV only has points, not lines, so it
+        // won't match any curbs.
+        oneshot_blockface: args.optional("--oneshot_blockface"),
+        // Public parking garages and lots, with their number of spots, in KML, GeoJSON, or CSV.
+        oneshot_garages: args.optional("--oneshot_garages"),
     };
     args.done();
     if !job.osm_to_raw
         && !job.raw_to_map
         && !job.scenario
         && !job.scenario_everyone
+        && !job.neighborhoods
+        && job.population.is_none()
+        && job.od_matrix.is_none()
+        && !job.pipeline
+        && job.bbox.is_none()
         && job.oneshot.is_none()
     {
         println!(
             "Nothing to do! Pass some combination of --raw, --map, --scenario, \
-             --scenario_everyone or --oneshot"
+             --scenario_everyone, --neighborhoods, --population, --od_matrix, --pipeline, --bbox \
+             or --oneshot"
         );
         std::process::exit(1);
     }
 
     if let Some(path) = job.oneshot {
-        oneshot(path, job.oneshot_clip, !job.oneshot_drive_on_left);
+        oneshot(
+            path,
+            job.oneshot_clip,
+            !job.oneshot_drive_on_left,
+            !job.oneshot_no_inferred_sidewalks,
+            job.oneshot_signals,
+            job.oneshot_blockface,
+            job.oneshot_garages,
+        );
         return;
     }
 
+    if let Some(bbox) = job.bbox {
+        let name = job.only_map.unwrap_or_else(|| {
+            println!("--bbox needs a name for the new map");
+            std::process::exit(1);
+        });
+        let mut timer = abstutil::Timer::new(format!("import {}", name));
+        pipeline::import_bbox(&name, &bbox, !job.drive_on_left, &mut timer);
+        return;
+    }
+
+    if job.pipeline {
+        job.osm_to_raw = true;
+        job.raw_to_map = true;
+    }
+
     let names = if let Some(n) = job.only_map {
         println!("- Just working on {}", n);
         vec![n]
@@ -111,7 +181,12 @@ fn main() {
 
         let mut maybe_map = if job.raw_to_map {
             Some(utils::raw_to_map(&name, !job.skip_ch, &mut timer))
-        } else if job.scenario || job.scenario_everyone {
+        } else if job.scenario
+            || job.scenario_everyone
+            || job.neighborhoods
+            || job.population.is_some()
+            || job.od_matrix.is_some()
+        {
             Some(map_model::Map::new(abstutil::path_map(&name), &mut timer))
         } else {
             None
@@ -148,13 +223,58 @@ fn main() {
             .save();
             timer.stop(format!("scenario_everyone for {}", name));
         }
+
+        if job.neighborhoods {
+            utils::import_neighborhoods(maybe_map.as_ref().unwrap(), &mut timer);
+        }
+
+        if let Some(ref path) = job.population {
+            timer.start(format!("synthesize population for {}", name));
+            population::synthesize(maybe_map.as_ref().unwrap(), path, &mut timer).save();
+            timer.stop(format!("synthesize population for {}", name));
+        }
+
+        if let Some(ref path) = job.od_matrix {
+            timer.start(format!("import OD matrix for {}", name));
+            od_matrix::import(maybe_map.as_ref().unwrap(), path, &mut timer).save();
+            timer.stop(format!("import OD matrix for {}", name));
+        }
+
+        if job.pipeline {
+            if let Err(err) = pipeline::register(pipeline::map_files(&name)) {
+                panic!("Couldn't record the local build of {}: {}", name, err);
+            }
+        }
     }
 }
 
-fn oneshot(osm_path: String, clip: Option<String>, drive_on_right: bool) {
+fn oneshot(
+    osm_path: String,
+    clip: Option<String>,
+    drive_on_right: bool,
+    infer_sidewalks: bool,
+    signals: Option<String>,
+    blockface: Option<String>,
+    garages: Option<String>,
+) {
     let mut timer = abstutil::Timer::new("oneshot");
+    let name = abstutil::basename(
+        osm_path
+            .trim_end_matches(".bz2")
+            .trim_end_matches(".pbf")
+            .trim_end_matches(".osm"),
+    );
+    // convert_osm clips too, but for XML, only after reading everything, so shrink big XML inputs
+    // first. PBF inputs are clipped while reading.
+    let osm_path = match clip {
+        Some(ref poly) if !osm_path.ends_with(".pbf") => {
+            let clipped = format!("{}_clipped.osm", name);
+            clip::clip_osm(&osm_path, poly, &clipped);
+            clipped
+        }
+        _ => osm_path,
+    };
     println!("- Running convert_osm on {}", osm_path);
-    let name = abstutil::basename(&osm_path);
     let raw = convert_osm::convert(
         convert_osm::Options {
             osm_input: osm_path,
@@ -169,11 +289,36 @@ fn oneshot(osm_path: String, clip: Option<String>, drive_on_right: bool) {
                     map_model::raw::DrivingSide::Left
                 },
                 bikes_can_use_bus_lanes: true,
+                right_turn_on_red: false,
             },
 
-            onstreet_parking: convert_osm::OnstreetParking::JustOSM,
-            public_offstreet_parking: convert_osm::PublicOffstreetParking::None,
+            onstreet_parking: match blockface {
+                Some(path) => {
+                    let shapes = utils::import_extra_shapes(&path, "blockface", &mut timer);
+                    convert_osm::OnstreetParking::Blockface(shapes)
+                }
+                None => convert_osm::OnstreetParking::JustOSM,
+            },
+            sidewalks: if infer_sidewalks {
+                convert_osm::Sidewalks::Infer
+            } else {
+                convert_osm::Sidewalks::JustOSM
+            },
+            public_offstreet_parking: match garages {
+                Some(path) => {
+                    let shapes = utils::import_extra_shapes(&path, "garages", &mut timer);
+                    convert_osm::PublicOffstreetParking::GIS(shapes)
+                }
+                None => convert_osm::PublicOffstreetParking::None,
+            },
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(1),
+            traffic_signals: match signals {
+                Some(path) => {
+                    let shapes = utils::import_extra_shapes(&path, "signals", &mut timer);
+                    convert_osm::TrafficSignals::Inventory(shapes)
+                }
+                None => convert_osm::TrafficSignals::JustOSM,
+            },
             elevation: None,
         },
         &mut timer,
@@ -181,6 +326,8 @@ fn oneshot(osm_path: String, clip: Option<String>, drive_on_right: bool) {
     let map = map_model::Map::create_from_raw(raw, true, &mut timer);
     timer.start("save map");
     map.save();
+    utils::record_content_hash(&name);
+    map_model::MapSummary::new(&map).save(&name);
     timer.stop("save map");
     println!("{} has been created", abstutil::path_map(&name));
 }
@@ -0,0 +1,200 @@
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, LonLat, Pt2D, Time};
+use map_model::{BuildingID, Map};
+use serde::Deserialize;
+use sim::{IndividTrip, PersonID, PersonSpec, Scenario, SpawnTrip, TripEndpoint, TripMode};
+use std::collections::HashMap;
+use std::fs::File;
+
+// Turns an origin-destination matrix into a scenario. The CSV has one row per group of identical
+// trips. Each end is either a building's OSM way ID or a point, which is matched to the closest
+// building:
+//
+// from_bldg,from_lon,from_lat,to_bldg,to_lon,to_lat,departure,mode,count
+// 123456,,,,-122.3010,47.6380,7:30:00,drive,12
+//
+// The departure is the time of day, and the mode is walk, bike, transit, or drive. Every trip
+// becomes its own person.
+pub fn import(map: &Map, csv_path: &str, timer: &mut Timer) -> Scenario {
+    let bldgs_by_osm_id: HashMap<i64, BuildingID> = map
+        .all_buildings()
+        .iter()
+        .map(|b| (b.osm_way_id, b.id))
+        .collect();
+    let find_bldg = |endpoint: &Option<Endpoint>| match endpoint.as_ref()? {
+        Endpoint::Building(id) => bldgs_by_osm_id.get(id).cloned(),
+        Endpoint::Point(gps) => {
+            let pt = Pt2D::from_gps(*gps, map.get_gps_bounds());
+            // Points off the map shouldn't snap to buildings along the edge.
+            if !map.get_boundary_polygon().contains_pt(pt) {
+                return None;
+            }
+            map.closest_building(pt, Distance::meters(100.0))
+                .map(|(b, _)| b)
+        }
+    };
+
+    let mut scenario = Scenario::empty(map, &abstutil::basename(csv_path));
+    scenario.only_seed_buses = None;
+    let mut total_trips = 0;
+    let mut skipped = 0;
+    let rows = parse(File::open(csv_path).unwrap())
+        .unwrap_or_else(|err| panic!("Couldn't read {}: {}", csv_path, err));
+    for row in rows {
+        total_trips += row.count;
+        let trip = match (find_bldg(&row.from), find_bldg(&row.to)) {
+            (Some(from), Some(to)) if from != to => SpawnTrip::new(
+                TripEndpoint::Bldg(from),
+                TripEndpoint::Bldg(to),
+                row.mode,
+                map,
+            ),
+            _ => None,
+        };
+        if let Some(trip) = trip {
+            for _ in 0..row.count {
+                scenario.people.push(PersonSpec {
+                    id: PersonID(scenario.people.len()),
+                    orig_id: None,
+                    trips: vec![IndividTrip::new(row.depart, trip.clone())],
+                });
+            }
+        } else {
+            skipped += row.count;
+        }
+    }
+    timer.note(format!(
+        "{} of {} trips from {} are in {}",
+        prettyprint_usize(scenario.people.len()),
+        prettyprint_usize(total_trips),
+        csv_path,
+        map.get_name()
+    ));
+    if skipped > 0 {
+        timer.warn(format!(
+            "Skipped {} trips that start or end off the map, or go nowhere",
+            prettyprint_usize(skipped)
+        ));
+    }
+    scenario
+}
+
+#[derive(Debug, PartialEq)]
+enum Endpoint {
+    // An OSM way ID
+    Building(i64),
+    Point(LonLat),
+}
+
+impl Endpoint {
+    // The building wins if both are given. None if neither is.
+    fn new(osm_id: Option<i64>, lon: Option<f64>, lat: Option<f64>) -> Option<Endpoint> {
+        if let Some(id) = osm_id {
+            return Some(Endpoint::Building(id));
+        }
+        Some(Endpoint::Point(LonLat::new(lon?, lat?)))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Row {
+    from: Option<Endpoint>,
+    to: Option<Endpoint>,
+    depart: Time,
+    mode: TripMode,
+    count: usize,
+}
+
+fn parse<R: std::io::Read>(reader: R) -> Result<Vec<Row>, String> {
+    let mut rows = Vec::new();
+    for rec in csv::Reader::from_reader(reader).deserialize() {
+        let rec: Record = rec.map_err(|err| err.to_string())?;
+        let depart = Time::parse(&rec.departure)
+            .map_err(|err| format!("Bad departure {}: {}", rec.departure, err))?;
+        let mode = match rec.mode.as_ref() {
+            "walk" => TripMode::Walk,
+            "bike" => TripMode::Bike,
+            "transit" => TripMode::Transit,
+            "drive" => TripMode::Drive,
+            x => {
+                return Err(format!("Unknown mode {}", x));
+            }
+        };
+        rows.push(Row {
+            from: Endpoint::new(rec.from_bldg, rec.from_lon, rec.from_lat),
+            to: Endpoint::new(rec.to_bldg, rec.to_lon, rec.to_lat),
+            depart,
+            mode,
+            count: rec.count,
+        });
+    }
+    Ok(rows)
+}
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    from_bldg: Option<i64>,
+    from_lon: Option<f64>,
+    from_lat: Option<f64>,
+    to_bldg: Option<i64>,
+    to_lon: Option<f64>,
+    to_lat: Option<f64>,
+    departure: String,
+    mode: String,
+    count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::Duration;
+
+    const HEADER: &str = "from_bldg,from_lon,from_lat,to_bldg,to_lon,to_lat,departure,mode,count";
+
+    #[test]
+    fn parse_rows() {
+        let csv = vec![
+            HEADER,
+            "123456,,,,-122.3010,47.6380,7:30:00,drive,12",
+            // The building wins over the point
+            "42,-122.3,47.6,43,,,17:00:00,bike,1",
+            // Half a point is nothing
+            ",-122.3,,,,,8:00:00,walk,3",
+        ]
+        .join("\n");
+        assert_eq!(
+            parse(csv.as_bytes()).unwrap(),
+            vec![
+                Row {
+                    from: Some(Endpoint::Building(123456)),
+                    to: Some(Endpoint::Point(LonLat::new(-122.3010, 47.6380))),
+                    depart: Time::START_OF_DAY + Duration::minutes(7 * 60 + 30),
+                    mode: TripMode::Drive,
+                    count: 12,
+                },
+                Row {
+                    from: Some(Endpoint::Building(42)),
+                    to: Some(Endpoint::Building(43)),
+                    depart: Time::START_OF_DAY + Duration::hours(17),
+                    mode: TripMode::Bike,
+                    count: 1,
+                },
+                Row {
+                    from: None,
+                    to: None,
+                    depart: Time::START_OF_DAY + Duration::hours(8),
+                    mode: TripMode::Walk,
+                    count: 3,
+                },
+            ]
+        );
+
+        for bad in vec![
+            "1,,,2,,,7:30:00,fly,1",
+            "1,,,2,,,noon,walk,1",
+            "1,,,2,,,7:30:00,walk,many",
+        ] {
+            assert!(parse(vec![HEADER, bad].join("\n").as_bytes()).is_err());
+        }
+    }
+}
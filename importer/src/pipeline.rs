@@ -0,0 +1,145 @@
+use crate::utils::{download, raw_to_map};
+use abstutil::Timer;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+
+// From nothing to a playable map for anywhere: grab OSM data for a bounding box from Overpass,
+// convert it, build the map and its summary, and record everything as a local build.
+pub fn import_bbox(name: &str, bbox: &str, drive_on_right: bool, timer: &mut Timer) {
+    let coords = bbox
+        .split(",")
+        .map(|x| x.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .filter(|coords| coords.len() == 4)
+        .unwrap_or_else(|| panic!("--bbox={} isn't min_lon,min_lat,max_lon,max_lat", bbox));
+    let (min_lon, min_lat, max_lon, max_lat) = (coords[0], coords[1], coords[2], coords[3]);
+
+    // Overpass returns complete ways, so they still need clipping to the box.
+    let clip = abstutil::path(format!("input/{}/polygons/{}.poly", name, name));
+    std::fs::create_dir_all(std::path::Path::new(&clip).parent().unwrap())
+        .expect("Creating parent dir failed");
+    let mut f = File::create(&clip).unwrap();
+    writeln!(f, "{}", name).unwrap();
+    writeln!(f, "1").unwrap();
+    for (lon, lat) in vec![
+        (min_lon, max_lat),
+        (max_lon, max_lat),
+        (max_lon, min_lat),
+        (min_lon, min_lat),
+        (min_lon, max_lat),
+    ] {
+        writeln!(f, "     {}    {}", lon, lat).unwrap();
+    }
+    writeln!(f, "END").unwrap();
+    writeln!(f, "END").unwrap();
+
+    let osm = format!("input/{}/osm/{}.osm", name, name);
+    download(
+        &osm,
+        &format!(
+            "https://overpass-api.de/api/map?bbox={},{},{},{}",
+            min_lon, min_lat, max_lon, max_lat
+        ),
+    );
+
+    println!("- Running convert_osm");
+    let raw = convert_osm::convert(
+        convert_osm::Options {
+            osm_input: abstutil::path(&osm),
+            city_name: name.to_string(),
+            name: name.to_string(),
+
+            clip: Some(clip),
+            map_config: map_model::MapConfig {
+                driving_side: if drive_on_right {
+                    map_model::raw::DrivingSide::Right
+                } else {
+                    map_model::raw::DrivingSide::Left
+                },
+                bikes_can_use_bus_lanes: true,
+                right_turn_on_red: false,
+            },
+
+            onstreet_parking: convert_osm::OnstreetParking::JustOSM,
+            sidewalks: convert_osm::Sidewalks::Infer,
+            public_offstreet_parking: convert_osm::PublicOffstreetParking::None,
+            private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(1),
+            traffic_signals: convert_osm::TrafficSignals::JustOSM,
+            elevation: None,
+        },
+        timer,
+    );
+    let output = abstutil::path_raw_map(name);
+    println!("- Saving {}", output);
+    abstutil::write_binary(output, &raw);
+
+    raw_to_map(name, true, timer);
+
+    let mut files = map_files(name);
+    files.push(osm);
+    if let Err(err) = register(files) {
+        panic!("Couldn't record the local build of {}: {}", name, err);
+    }
+    println!(
+        "{} is ready. Open it from the map chooser in the game.",
+        abstutil::path_map(name)
+    );
+}
+
+// Everything built for one map that the game or the importer needs later, relative to data/.
+pub fn map_files(name: &str) -> Vec<String> {
+    vec![
+        format!("input/raw_maps/{}.bin", name),
+        format!("system/maps/{}.bin", name),
+        format!("system/map_summaries/{}.bin", name),
+        format!("system/map_provenance/{}.json", name),
+    ]
+}
+
+// Records the checksums of files built locally in data/player/local_builds.txt, so the updater
+// doesn't delete them as unknown files or replace them with the last uploaded version. The real
+// data/MANIFEST.txt is only changed by `updater --upload`.
+pub fn register(files: Vec<String>) -> Result<(), std::io::Error> {
+    let path = abstutil::path_local_builds();
+    // Path to checksum
+    let mut builds: BTreeMap<String, String> = BTreeMap::new();
+    if let Ok(f) = File::open(&path) {
+        for line in BufReader::new(f).lines() {
+            let line = line?;
+            let parts = line.split(",").collect::<Vec<_>>();
+            assert_eq!(parts.len(), 2);
+            builds.insert(parts[0].to_string(), parts[1].to_string());
+        }
+    }
+
+    for file in files {
+        let checksum = md5sum(&abstutil::path(&file))?;
+        println!("- Recording local build of data/{}", file);
+        builds.insert(format!("data/{}", file), checksum);
+    }
+
+    std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap())?;
+    let mut f = File::create(&path)?;
+    for (file, checksum) in builds {
+        writeln!(f, "{},{}", file, checksum)?;
+    }
+    println!("- Wrote {}", path);
+    Ok(())
+}
+
+// Matches how the updater checksums files
+pub fn md5sum(path: &str) -> Result<String, std::io::Error> {
+    let mut file = File::open(path)?;
+    let mut buffer = [0 as u8; 4096];
+    let mut context = md5::Context::new();
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        context.consume(&buffer[..n]);
+    }
+    Ok(format!("{:x}", context.compute()))
+}
@@ -0,0 +1,137 @@
+use abstutil::Timer;
+use geom::{LonLat, Pt2D};
+use map_model::{BuildingID, BuildingType, Map};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
+use sim::{Population, Resident};
+use std::fs::File;
+
+// Synthesizes everybody living in a map from block-level counts. The CSV has one row per census
+// block (or any other small area), located by its centroid:
+//
+// longitude,latitude,population,employment
+// -122.3010,47.6380,120,15
+//
+// Each building belongs to the block with the closest centroid. A block's residents are spread
+// over its residential buildings, weighted by their estimated number of residents, and its jobs
+// over its commercial buildings, weighted by area. Then residents are matched up to jobs.
+pub fn synthesize(map: &Map, csv_path: &str, timer: &mut Timer) -> Population {
+    // Keep the result stable when re-importing
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+
+    let mut blocks: Vec<(Pt2D, Record)> = Vec::new();
+    for rec in csv::Reader::from_reader(File::open(csv_path).unwrap()).deserialize() {
+        let rec: Record = rec.unwrap();
+        let pt = Pt2D::from_gps(
+            LonLat::new(rec.longitude, rec.latitude),
+            map.get_gps_bounds(),
+        );
+        // Blocks just past the edge of the map would otherwise get all of the buildings along
+        // the edge, and cram their whole population into them.
+        if map.get_boundary_polygon().contains_pt(pt) {
+            blocks.push((pt, rec));
+        }
+    }
+    timer.note(format!(
+        "{} blocks from {} are inside {}",
+        blocks.len(),
+        csv_path,
+        map.get_name()
+    ));
+    let mut population = Population {
+        map_name: map.get_name().to_string(),
+        people: Vec::new(),
+    };
+    if blocks.is_empty() {
+        return population;
+    }
+
+    let mut homes: Vec<Vec<(BuildingID, usize)>> = vec![Vec::new(); blocks.len()];
+    let mut workplaces: Vec<Vec<(BuildingID, f64)>> = vec![Vec::new(); blocks.len()];
+    timer.start_iter("match buildings to blocks", map.all_buildings().len());
+    for b in map.all_buildings() {
+        timer.next();
+        let center = b.polygon.center();
+        let idx = blocks
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (pt, _))| pt.dist_to(center))
+            .unwrap()
+            .0;
+        // Zero weights aren't allowed
+        let area = b.polygon.area().max(1.0);
+        match b.bldg_type {
+            BuildingType::Residential(num_ppl) => {
+                homes[idx].push((b.id, num_ppl.max(1)));
+            }
+            BuildingType::ResidentialCommercial(num_ppl) => {
+                homes[idx].push((b.id, num_ppl.max(1)));
+                workplaces[idx].push((b.id, area));
+            }
+            BuildingType::Commercial => {
+                workplaces[idx].push((b.id, area));
+            }
+            BuildingType::Empty => {}
+        }
+    }
+
+    let mut residents = Vec::new();
+    let mut jobs = Vec::new();
+    let mut no_homes = 0;
+    let mut no_jobs = 0;
+    for (idx, (_, rec)) in blocks.iter().enumerate() {
+        if homes[idx].is_empty() {
+            no_homes += rec.population;
+        } else {
+            for _ in 0..rec.population {
+                residents.push(homes[idx].choose_weighted(&mut rng, |(_, n)| *n).unwrap().0);
+            }
+        }
+        if workplaces[idx].is_empty() {
+            no_jobs += rec.employment;
+        } else {
+            for _ in 0..rec.employment {
+                jobs.push(
+                    workplaces[idx]
+                        .choose_weighted(&mut rng, |(_, area)| *area)
+                        .unwrap()
+                        .0,
+                );
+            }
+        }
+    }
+    if no_homes > 0 || no_jobs > 0 {
+        timer.warn(format!(
+            "Skipped {} residents and {} jobs in blocks without any matching buildings",
+            abstutil::prettyprint_usize(no_homes),
+            abstutil::prettyprint_usize(no_jobs)
+        ));
+    }
+
+    // There might be more jobs than residents, because people commute in from off the map, or
+    // the other way around. Just match up as many as possible.
+    residents.shuffle(&mut rng);
+    jobs.shuffle(&mut rng);
+    for (idx, home) in residents.into_iter().enumerate() {
+        population.people.push(Resident {
+            home,
+            work: jobs.get(idx).cloned(),
+        });
+    }
+    timer.note(format!(
+        "Synthesized {} people for {}",
+        abstutil::prettyprint_usize(population.people.len()),
+        map.get_name()
+    ));
+    population
+}
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    longitude: f64,
+    latitude: f64,
+    population: usize,
+    employment: usize,
+}
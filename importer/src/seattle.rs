@@ -1,4 +1,4 @@
-use crate::utils::{download, download_kml, osmconvert};
+use crate::utils::{download, download_kml};
 use map_model::Map;
 use sim::Scenario;
 
@@ -41,16 +41,13 @@ fn input() {
 
 pub fn osm_to_raw(name: &str) {
     input();
-    osmconvert(
-        "input/seattle/osm/washington-latest.osm.pbf",
-        format!("input/seattle/polygons/{}.poly", name),
-        format!("input/seattle/osm/{}.osm", name),
-    );
+    // convert_osm only keeps what's inside the clipping polygon while reading the PBF
+    let osm_input = abstutil::path("input/seattle/osm/washington-latest.osm.pbf");
 
     println!("- Running convert_osm");
     let map = convert_osm::convert(
         convert_osm::Options {
-            osm_input: abstutil::path(format!("input/seattle/osm/{}.osm", name)),
+            osm_input,
             city_name: "seattle".to_string(),
             name: name.to_string(),
 
@@ -61,11 +58,13 @@ pub fn osm_to_raw(name: &str) {
             map_config: map_model::MapConfig {
                 driving_side: map_model::raw::DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                right_turn_on_red: true,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::Blockface(abstutil::path(
                 "input/seattle/blockface.bin",
             )),
+            sidewalks: convert_osm::Sidewalks::Infer,
             public_offstreet_parking: convert_osm::PublicOffstreetParking::GIS(abstutil::path(
                 "input/seattle/offstreet_parking.bin",
             )),
@@ -79,6 +78,7 @@ pub fn osm_to_raw(name: &str) {
                     _ => 1,
                 },
             ),
+            traffic_signals: convert_osm::TrafficSignals::JustOSM,
             elevation: Some(abstutil::path("input/seattle/N47W122.hgt")),
         },
         &mut abstutil::Timer::throwaway(),
@@ -118,4 +118,6 @@ pub fn adjust_private_parking(map: &mut Map, scenario: &Scenario) {
         map.hack_override_offstreet_spots_individ(b, count);
     }
     map.save();
+    crate::utils::record_content_hash(map.get_name());
+    map_model::MapSummary::new(map).save(map.get_name());
 }
@@ -1,5 +1,5 @@
 use abstutil::{prettyprint_usize, Counter, FileWithProgress, Timer};
-use geom::{Distance, Duration, FindClosest, LonLat, Pt2D, Time};
+use geom::{Distance, Duration, LonLat, Pt2D, Time};
 use kml::{ExtraShape, ExtraShapes};
 use map_model::Map;
 use serde::{Deserialize, Serialize};
@@ -115,13 +115,6 @@ fn import_parcels(
     huge_map: &Map,
     timer: &mut Timer,
 ) -> (HashMap<usize, Endpoint>, HashMap<usize, ExtraShape>) {
-    // TODO I really just want to do polygon containment with a quadtree. FindClosest only does
-    // line-string stuff right now, which'll be weird for the last->first pt line and stuff.
-    let mut closest_bldg: FindClosest<i64> = FindClosest::new(huge_map.get_bounds());
-    for b in huge_map.all_buildings() {
-        closest_bldg.add(b.osm_way_id, b.polygon.points());
-    }
-
     let mut x_coords: Vec<f64> = Vec::new();
     let mut y_coords: Vec<f64> = Vec::new();
     // Dummy values
@@ -177,9 +170,12 @@ fn import_parcels(
         let gps = LonLat::new(x, y);
         let pt = Pt2D::from_gps(gps, bounds);
         let osm_building = if bounds.contains(gps) {
-            closest_bldg
-                .closest_pt(pt, Distance::meters(30.0))
-                .map(|(b, _)| b)
+            // TODO I really just want to do polygon containment with a quadtree. FindClosest only
+            // does line-string stuff right now, which'll be weird for the last->first pt line and
+            // stuff.
+            huge_map
+                .closest_building(pt, Distance::meters(30.0))
+                .map(|(b, _)| huge_map.get_b(b).osm_way_id)
         } else {
             None
         };
@@ -1,3 +1,4 @@
+use crate::pipeline::md5sum;
 use abstutil::Timer;
 use std::path::Path;
 use std::process::Command;
@@ -92,24 +93,18 @@ pub fn download_kml(
         .arg(output.replace(".bin", ".kml")));
 }
 
-// Uses osmconvert to clip the input .osm (or .pbf) against a polygon and produce some output.
-// Skips if the output exists.
-pub fn osmconvert(input: &str, clipping_polygon: String, output: String) {
-    let input = abstutil::path(input);
-    let clipping_polygon = abstutil::path(clipping_polygon);
-    let output = abstutil::path(output);
-
-    if Path::new(&output).exists() {
-        println!("- {} already exists", output);
+// Call after saving a map, so its provenance has the right checksum.
+pub fn record_content_hash(name: &str) {
+    let mut provenance = map_model::MapProvenance::load(name);
+    // The raw map came from somewhere else, so there's nothing to add to
+    if provenance.osm_source.is_empty() {
         return;
     }
-    println!("- Clipping {} to {}", input, clipping_polygon);
-
-    run(Command::new("osmconvert")
-        .arg(input)
-        .arg(format!("-B={}", clipping_polygon))
-        .arg("--complete-ways")
-        .arg(format!("-o={}", output)));
+    provenance.content_hash = match md5sum(&abstutil::path_map(name)) {
+        Ok(checksum) => checksum,
+        Err(err) => panic!("Couldn't checksum {}: {}", abstutil::path_map(name), err),
+    };
+    provenance.save(name);
 }
 
 // Removes files. Be careful!
@@ -141,6 +136,8 @@ pub fn raw_to_map(name: &str, build_ch: bool, timer: &mut Timer) -> map_model::M
     let map = map_model::Map::create_from_raw(raw, build_ch, timer);
     timer.start("save map");
     map.save();
+    record_content_hash(name);
+    map_model::MapSummary::new(&map).save(name);
     timer.stop("save map");
     timer.stop(format!("Raw->Map for {}", name));
 
@@ -156,3 +153,58 @@ pub fn raw_to_map(name: &str, build_ch: bool, timer: &mut Timer) -> map_model::M
 
     map
 }
+
+// Clips all neighborhood boundaries defined for a city to one map. The input is a directory of
+// Osmosis polygons, named by the neighborhood.
+pub fn import_neighborhoods(map: &map_model::Map, timer: &mut Timer) {
+    let dir = abstutil::path(format!("input/{}/neighborhoods", map.get_city_name()));
+    let names = abstutil::list_all_objects(dir.clone());
+    timer.start_iter("import neighborhoods", names.len());
+    for name in names {
+        timer.next();
+        let pts = match geom::LonLat::read_osmosis_polygon(format!("{}/{}.poly", dir, name)) {
+            Ok(pts) => pts,
+            Err(err) => {
+                timer.warn(format!("Skipping neighborhood {}: {}", name, err));
+                continue;
+            }
+        };
+        let n = map_model::Neighborhood::new(name, map.get_name().to_string(), pts);
+        if n.polygon(map).is_some() {
+            n.save();
+        }
+    }
+}
+
+// Converts a city's dataset (signal locations, blockfaces, garages, etc) into the ExtraShapes that
+// convert_osm expects, returning the path to them in data/input/oneshot/. The suffix distinguishes
+// the output from other datasets converted from a file with the same name. The input is KML,
+// GeoJSON, or a CSV of points with at least these columns:
+//
+// longitude,latitude
+// -122.3010,47.6380
+//
+// Every other column becomes an attribute of the point.
+pub fn import_extra_shapes(path: &str, suffix: &str, timer: &mut Timer) -> String {
+    let everywhere = geom::GPSBounds::from(vec![
+        geom::LonLat::new(-180.0, -90.0),
+        geom::LonLat::new(180.0, 90.0),
+    ]);
+    // convert_osm only keeps shapes inside the map, so don't filter here.
+    let shapes = if path.ends_with(".csv") {
+        kml::load_csv(path).unwrap()
+    } else if path.ends_with(".geojson") || path.ends_with(".json") {
+        kml::load_geojson(path).unwrap()
+    } else {
+        kml::load(path, &everywhere, false, timer).unwrap()
+    };
+    timer.note(format!("{} shapes in {}", shapes.shapes.len(), path));
+
+    let output = abstutil::path(format!(
+        "input/oneshot/{}_{}.bin",
+        abstutil::basename(path),
+        suffix
+    ));
+    abstutil::write_binary(output.clone(), &shapes);
+    output
+}
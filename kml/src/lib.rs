@@ -1,4 +1,5 @@
 use abstutil::{prettyprint_usize, FileWithProgress, Timer};
+use geojson::{Feature, GeoJson, Value};
 use geom::{GPSBounds, LonLat};
 use quick_xml::events::Event;
 use quick_xml::Reader;
@@ -110,6 +111,146 @@ pub fn load(
     Ok(ExtraShapes { shapes })
 }
 
+// A CSV of points, with at least longitude and latitude columns. Every other column becomes an
+// attribute of the point.
+pub fn load_csv(path: &str) -> Result<ExtraShapes, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_reader(std::fs::File::open(path)?);
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| -> Result<usize, Box<dyn Error>> {
+        headers
+            .iter()
+            .position(|x| x == name)
+            .ok_or_else(|| format!("{} has no {} column", path, name).into())
+    };
+    let (lon_col, lat_col) = (column("longitude")?, column("latitude")?);
+
+    let mut shapes = Vec::new();
+    for rec in reader.records() {
+        let rec = rec?;
+        let mut attributes = BTreeMap::new();
+        for (idx, (k, v)) in headers.iter().zip(rec.iter()).enumerate() {
+            if idx != lon_col && idx != lat_col && !v.is_empty() {
+                attributes.insert(k.to_string(), v.to_string());
+            }
+        }
+        shapes.push(ExtraShape {
+            points: vec![LonLat::new(
+                rec[lon_col].parse::<f64>()?,
+                rec[lat_col].parse::<f64>()?,
+            )],
+            attributes,
+        });
+    }
+    Ok(ExtraShapes { shapes })
+}
+
+pub fn load_geojson(path: &str) -> Result<ExtraShapes, Box<dyn Error>> {
+    let raw = abstutil::slurp_file(path)?;
+    let geojson = String::from_utf8_lossy(&raw).parse::<GeoJson>()?;
+    Ok(geojson_to_shapes(geojson))
+}
+
+// Every feature becomes one shape per piece of its geometry, all with the feature's properties.
+// Only the outer ring of polygons is kept.
+fn geojson_to_shapes(geojson: GeoJson) -> ExtraShapes {
+    let features = match geojson {
+        GeoJson::FeatureCollection(collection) => collection.features,
+        GeoJson::Feature(feature) => vec![feature],
+        GeoJson::Geometry(geometry) => vec![Feature {
+            bbox: None,
+            geometry: Some(geometry),
+            id: None,
+            properties: None,
+            foreign_members: None,
+        }],
+    };
+
+    let mut shapes = Vec::new();
+    for feature in features {
+        let mut attributes = BTreeMap::new();
+        for (k, v) in feature.properties.unwrap_or_default() {
+            // Don't wind up with quotes around strings
+            let v = match v {
+                serde_json::Value::String(x) => x,
+                x => x.to_string(),
+            };
+            attributes.insert(k, v);
+        }
+        if let Some(geometry) = feature.geometry {
+            add_geometry(geometry.value, &attributes, &mut shapes);
+        }
+    }
+    ExtraShapes { shapes }
+}
+
+fn add_geometry(value: Value, attributes: &BTreeMap<String, String>, shapes: &mut Vec<ExtraShape>) {
+    let pts = |positions: Vec<Vec<f64>>| -> Vec<LonLat> {
+        positions
+            .into_iter()
+            .filter(|pos| pos.len() >= 2)
+            .map(|pos| LonLat::new(pos[0], pos[1]))
+            .collect()
+    };
+    let mut polygon_attributes = attributes.clone();
+    polygon_attributes
+        .entry("spatial_type".to_string())
+        .or_insert_with(|| "Polygon".to_string());
+
+    match value {
+        Value::Point(pos) => {
+            shapes.push(ExtraShape {
+                points: pts(vec![pos]),
+                attributes: attributes.clone(),
+            });
+        }
+        Value::MultiPoint(positions) => {
+            for pos in positions {
+                shapes.push(ExtraShape {
+                    points: pts(vec![pos]),
+                    attributes: attributes.clone(),
+                });
+            }
+        }
+        Value::LineString(positions) => {
+            shapes.push(ExtraShape {
+                points: pts(positions),
+                attributes: attributes.clone(),
+            });
+        }
+        Value::MultiLineString(lines) => {
+            for positions in lines {
+                shapes.push(ExtraShape {
+                    points: pts(positions),
+                    attributes: attributes.clone(),
+                });
+            }
+        }
+        Value::Polygon(rings) => {
+            if let Some(outer) = rings.into_iter().next() {
+                shapes.push(ExtraShape {
+                    points: pts(outer),
+                    attributes: polygon_attributes,
+                });
+            }
+        }
+        Value::MultiPolygon(polygons) => {
+            for rings in polygons {
+                if let Some(outer) = rings.into_iter().next() {
+                    shapes.push(ExtraShape {
+                        points: pts(outer),
+                        attributes: polygon_attributes.clone(),
+                    });
+                }
+            }
+        }
+        Value::GeometryCollection(geometries) => {
+            for geometry in geometries {
+                add_geometry(geometry.value, attributes, shapes);
+            }
+        }
+    }
+}
+
 fn parse_pt(input: &str) -> Option<LonLat> {
     let coords: Vec<&str> = input.split(',').collect();
     if coords.len() != 2 {
@@ -678,7 +678,7 @@ impl Model {
         self.world.delete(ID::Intersection(id.i1));
         self.world.delete(ID::Intersection(id.i2));
 
-        let mut closest = FindClosest::new(&self.compute_bounds());
+        let mut closest = FindClosest::new();
         let pts = &mut self.map.roads.get_mut(&id).unwrap().center_points;
         for (idx, pair) in pts.windows(2).enumerate() {
             closest.add(idx + 1, &vec![pair[0], pair[1]]);
@@ -1,8 +1,9 @@
-use crate::{IntersectionID, LaneID, Map, PathConstraints};
+use crate::{BuildingID, LaneID, LaneType, Map, PathConstraints, RoadID};
 use abstutil::Timer;
-use geom::Duration;
+use geom::{Duration, Speed};
 use petgraph::graphmap::DiGraphMap;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
 // SCC = strongly connected component
 
@@ -95,16 +96,69 @@ fn bidi_flood(map: &Map, start: LaneID, largest_group: &HashSet<LaneID>) -> Opti
     None
 }
 
-// TODO Different cost function
-// TODO Cost per building
-pub fn all_costs_from(map: &Map, start: IntersectionID) -> HashMap<IntersectionID, Duration> {
-    let mut graph = DiGraphMap::new();
-    for r in map.all_roads() {
-        graph.add_edge(r.src_i, r.dst_i, r.id);
+// Starting from a building, how long it takes to reach every other building within time_limit,
+// using some mode. This ignores waiting at intersections, parking, and anything else the
+// simulation would add, so it's optimistic.
+pub fn all_costs_from(
+    map: &Map,
+    start: BuildingID,
+    time_limit: Duration,
+    constraints: PathConstraints,
+) -> HashMap<BuildingID, Duration> {
+    let start_lane = match constraints {
+        PathConstraints::Pedestrian => map.get_b(start).sidewalk(),
+        PathConstraints::Bike => map
+            .find_closest_lane(
+                map.get_b(start).sidewalk(),
+                vec![LaneType::Biking, LaneType::Driving],
+            )
+            .unwrap_or_else(|_| map.find_driving_lane_near_building(start)),
+        PathConstraints::Car | PathConstraints::Bus | PathConstraints::Train => {
+            map.find_driving_lane_near_building(start)
+        }
+    };
+    let speed = |l: LaneID| {
+        let limit = map.get_parent(l).speed_limit;
+        match constraints {
+            // Same as what pathfinding assumes
+            PathConstraints::Pedestrian => Speed::meters_per_second(1.34),
+            PathConstraints::Bike => limit.min(Speed::miles_per_hour(10.0)),
+            _ => limit,
+        }
+    };
+
+    // Bounded Dijkstra over lanes. Buildings are reachable once any lane of their road is.
+    let mut lane_costs: HashMap<LaneID, Duration> = HashMap::new();
+    let mut queue: BinaryHeap<(Reverse<Duration>, LaneID)> = BinaryHeap::new();
+    queue.push((Reverse(Duration::ZERO), start_lane));
+    while let Some((Reverse(cost), l)) = queue.pop() {
+        if lane_costs.contains_key(&l) {
+            continue;
+        }
+        lane_costs.insert(l, cost);
+
+        let cost = cost + map.get_l(l).length() / speed(l);
+        for turn in map.get_turns_for(l, constraints) {
+            let next_cost = cost + turn.geom.length() / speed(turn.id.dst);
+            if next_cost <= time_limit && !lane_costs.contains_key(&turn.id.dst) {
+                queue.push((Reverse(next_cost), turn.id.dst));
+            }
+        }
     }
 
-    petgraph::algo::dijkstra(&graph, start, None, |(_, _, r)| {
-        let r = map.get_r(*r);
-        r.center_pts.length() / r.speed_limit
-    })
+    let mut road_costs: HashMap<RoadID, Duration> = HashMap::new();
+    for (l, cost) in lane_costs {
+        let r = map.get_l(l).parent;
+        if road_costs.get(&r).map(|c| cost < *c).unwrap_or(true) {
+            road_costs.insert(r, cost);
+        }
+    }
+    map.all_buildings()
+        .iter()
+        .filter_map(|b| {
+            road_costs
+                .get(&map.get_l(b.sidewalk()).parent)
+                .map(|cost| (b.id, *cost))
+        })
+        .collect()
 }
@@ -0,0 +1,12 @@
+use geom::LonLat;
+use serde::{Deserialize, Serialize};
+
+// Markup explaining a proposal. It doesn't affect the map at all, but travels with the edits so
+// it's there when they're shared. Stored in GPS to survive basemap updates.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Annotation {
+    Arrow { from: LonLat, to: LonLat },
+    Callout { pt: LonLat, text: String },
+    // The first point isn't repeated at the end.
+    Region { points: Vec<LonLat> },
+}
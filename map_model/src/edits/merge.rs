@@ -0,0 +1,453 @@
+use crate::{Annotation, EditCmd, IntersectionID, LaneID, MapEdits, RoadID};
+use abstutil::retain_btreemap;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Two people working from the same starting edits (maybe no edits at all) can diverge. Merging
+// looks at the net effect each side has on every lane, road, and intersection. Something changed
+// by only one side, or changed identically by both, merges cleanly; anything else is a conflict
+// for a human to resolve.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EditedObject {
+    Lane(LaneID),
+    Road(RoadID),
+    Intersection(IntersectionID),
+}
+
+#[derive(Clone, Debug)]
+pub struct EditConflict {
+    pub obj: EditedObject,
+    // The net changes from each side. Either might be empty, if that side reverted something the
+    // base had changed.
+    pub ours: Vec<EditCmd>,
+    pub theirs: Vec<EditCmd>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    KeepOurs,
+    KeepTheirs,
+}
+
+pub struct MergedEdits {
+    pub clean: Vec<EditCmd>,
+    pub conflicts: Vec<EditConflict>,
+    // Annotations never conflict. Anything added by either side is kept, and anything deleted by
+    // either side is gone.
+    pub annotations: Vec<Annotation>,
+}
+
+impl MergedEdits {
+    // Three-way merge. All three edits must be relative to the same map.
+    pub fn new(base: &MapEdits, ours: &MapEdits, theirs: &MapEdits) -> MergedEdits {
+        let mut annotations: Vec<Annotation> = ours
+            .annotations
+            .iter()
+            .filter(|a| !base.annotations.contains(a) || theirs.annotations.contains(a))
+            .cloned()
+            .collect();
+        for a in &theirs.annotations {
+            if !base.annotations.contains(a) && !annotations.contains(a) {
+                annotations.push(a.clone());
+            }
+        }
+
+        let base = net_changes(base);
+        let ours = net_changes(ours);
+        let theirs = net_changes(theirs);
+
+        let mut objects = BTreeSet::new();
+        for changes in vec![&base, &ours, &theirs] {
+            objects.extend(changes.keys().cloned());
+        }
+
+        let mut clean = Vec::new();
+        let mut conflicts = Vec::new();
+        let none = Vec::new();
+        for obj in objects {
+            let b = base.get(&obj).unwrap_or(&none);
+            let o = ours.get(&obj).unwrap_or(&none);
+            let t = theirs.get(&obj).unwrap_or(&none);
+            if same_changes(o, b) {
+                clean.extend(t.clone());
+            } else if same_changes(t, b) || same_changes(o, t) {
+                clean.extend(o.clone());
+            } else {
+                conflicts.push(EditConflict {
+                    obj,
+                    ours: o.clone(),
+                    theirs: t.clone(),
+                });
+            }
+        }
+
+        MergedEdits {
+            clean,
+            conflicts,
+            annotations,
+        }
+    }
+
+    // One resolution per conflict, in order.
+    pub fn resolve(&self, edits_name: String, resolutions: &Vec<Resolution>) -> MapEdits {
+        assert_eq!(self.conflicts.len(), resolutions.len());
+        let mut edits = MapEdits::new();
+        edits.edits_name = edits_name;
+        edits.commands = self.clean.clone();
+        edits.annotations = self.annotations.clone();
+        for (conflict, resolution) in self.conflicts.iter().zip(resolutions.iter()) {
+            match resolution {
+                Resolution::KeepOurs => edits.commands.extend(conflict.ours.clone()),
+                Resolution::KeepTheirs => edits.commands.extend(conflict.theirs.clone()),
+            }
+        }
+        edits
+    }
+}
+
+impl MapEdits {
+    // Every object that winds up different between two sets of edits (relative to the same map),
+    // with the net changes each side makes to it. One side is empty if only the other touched the
+    // object.
+    pub fn diff(&self, other: &MapEdits) -> Vec<EditConflict> {
+        let ours = net_changes(self);
+        let theirs = net_changes(other);
+        let objects: BTreeSet<EditedObject> = ours.keys().chain(theirs.keys()).cloned().collect();
+
+        let none = Vec::new();
+        let mut diffs = Vec::new();
+        for obj in objects {
+            let o = ours.get(&obj).unwrap_or(&none);
+            let t = theirs.get(&obj).unwrap_or(&none);
+            if !same_changes(o, t) {
+                diffs.push(EditConflict {
+                    obj,
+                    ours: o.clone(),
+                    theirs: t.clone(),
+                });
+            }
+        }
+        diffs
+    }
+}
+
+impl EditCmd {
+    pub fn edited_object(&self) -> EditedObject {
+        match self {
+            EditCmd::ChangeLaneType { id, .. } => EditedObject::Lane(*id),
+            EditCmd::ReverseLane { l, .. } => EditedObject::Lane(*l),
+            EditCmd::ChangeSpeedLimit { id, .. } => EditedObject::Road(*id),
+            EditCmd::ChangeSpeedLimitSchedule { id, .. } => EditedObject::Road(*id),
+            EditCmd::ChangeIntersection { i, .. } => EditedObject::Intersection(*i),
+            EditCmd::ChangeAccessRestrictions { id, .. } => EditedObject::Road(*id),
+            EditCmd::ChangeParkingPolicy { id, .. } => EditedObject::Lane(*id),
+            EditCmd::ChangeTruckAccess { id, .. } => EditedObject::Road(*id),
+            EditCmd::ChangeBusLaneCamera { id, .. } => EditedObject::Road(*id),
+            EditCmd::ChangeLeadingPedestrianInterval { i, .. } => EditedObject::Intersection(*i),
+            EditCmd::ChangeRightTurnOnRed { i, .. } => EditedObject::Intersection(*i),
+            EditCmd::ChangeTransitSignalPriority { i, .. } => EditedObject::Intersection(*i),
+        }
+    }
+}
+
+// Collapse the command history into at most one command per kind of change per object. Changes
+// that wind up undoing themselves disappear.
+pub(crate) fn net_changes(edits: &MapEdits) -> BTreeMap<EditedObject, Vec<EditCmd>> {
+    let mut changes: BTreeMap<EditedObject, Vec<EditCmd>> = BTreeMap::new();
+    for cmd in &edits.commands {
+        let list = changes.entry(cmd.edited_object()).or_insert_with(Vec::new);
+        if let Some(idx) = list
+            .iter()
+            .position(|x| std::mem::discriminant(x) == std::mem::discriminant(cmd))
+        {
+            match squash(&list[idx], cmd) {
+                Some(squashed) => {
+                    list[idx] = squashed;
+                }
+                None => {
+                    list.remove(idx);
+                }
+            }
+        } else if !is_noop(cmd) {
+            list.push(cmd.clone());
+        }
+    }
+    retain_btreemap(&mut changes, |_, list| !list.is_empty());
+    changes
+}
+
+// Both commands are the same kind, on the same object. Keep the original state from the first and
+// the new state from the second.
+fn squash(first: &EditCmd, second: &EditCmd) -> Option<EditCmd> {
+    let cmd = match (first, second) {
+        (EditCmd::ChangeLaneType { id, orig_lt, .. }, EditCmd::ChangeLaneType { lt, .. }) => {
+            EditCmd::ChangeLaneType {
+                id: *id,
+                lt: *lt,
+                orig_lt: *orig_lt,
+            }
+        }
+        (EditCmd::ReverseLane { dst_i: dst1, .. }, EditCmd::ReverseLane { dst_i: dst2, .. }) => {
+            // Reversing twice cancels out
+            if dst1 != dst2 {
+                return None;
+            }
+            first.clone()
+        }
+        (EditCmd::ChangeSpeedLimit { id, old, .. }, EditCmd::ChangeSpeedLimit { new, .. }) => {
+            EditCmd::ChangeSpeedLimit {
+                id: *id,
+                new: *new,
+                old: *old,
+            }
+        }
+        (
+            EditCmd::ChangeSpeedLimitSchedule { id, old, .. },
+            EditCmd::ChangeSpeedLimitSchedule { new, .. },
+        ) => EditCmd::ChangeSpeedLimitSchedule {
+            id: *id,
+            new: new.clone(),
+            old: old.clone(),
+        },
+        (EditCmd::ChangeIntersection { i, old, .. }, EditCmd::ChangeIntersection { new, .. }) => {
+            EditCmd::ChangeIntersection {
+                i: *i,
+                new: new.clone(),
+                old: old.clone(),
+            }
+        }
+        (
+            EditCmd::ChangeAccessRestrictions {
+                id,
+                old_allow_through_traffic,
+                ..
+            },
+            EditCmd::ChangeAccessRestrictions {
+                new_allow_through_traffic,
+                ..
+            },
+        ) => EditCmd::ChangeAccessRestrictions {
+            id: *id,
+            new_allow_through_traffic: *new_allow_through_traffic,
+            old_allow_through_traffic: *old_allow_through_traffic,
+        },
+        (
+            EditCmd::ChangeParkingPolicy { id, old, .. },
+            EditCmd::ChangeParkingPolicy { new, .. },
+        ) => EditCmd::ChangeParkingPolicy {
+            id: *id,
+            new: *new,
+            old: *old,
+        },
+        (EditCmd::ChangeTruckAccess { id, old, .. }, EditCmd::ChangeTruckAccess { new, .. }) => {
+            EditCmd::ChangeTruckAccess {
+                id: *id,
+                new: *new,
+                old: *old,
+            }
+        }
+        (
+            EditCmd::ChangeBusLaneCamera { id, old, .. },
+            EditCmd::ChangeBusLaneCamera { new, .. },
+        ) => EditCmd::ChangeBusLaneCamera {
+            id: *id,
+            new: *new,
+            old: *old,
+        },
+        (
+            EditCmd::ChangeLeadingPedestrianInterval { i, old, .. },
+            EditCmd::ChangeLeadingPedestrianInterval { new, .. },
+        ) => EditCmd::ChangeLeadingPedestrianInterval {
+            i: *i,
+            new: *new,
+            old: *old,
+        },
+        (
+            EditCmd::ChangeRightTurnOnRed { i, old, .. },
+            EditCmd::ChangeRightTurnOnRed { new, .. },
+        ) => EditCmd::ChangeRightTurnOnRed {
+            i: *i,
+            new: *new,
+            old: *old,
+        },
+        (
+            EditCmd::ChangeTransitSignalPriority { i, old, .. },
+            EditCmd::ChangeTransitSignalPriority { new, .. },
+        ) => EditCmd::ChangeTransitSignalPriority {
+            i: *i,
+            new: *new,
+            old: *old,
+        },
+        _ => unreachable!(),
+    };
+    if is_noop(&cmd) {
+        None
+    } else {
+        Some(cmd)
+    }
+}
+
+fn is_noop(cmd: &EditCmd) -> bool {
+    match cmd {
+        EditCmd::ChangeLaneType { lt, orig_lt, .. } => lt == orig_lt,
+        EditCmd::ReverseLane { .. } => false,
+        EditCmd::ChangeSpeedLimit { new, old, .. } => new == old,
+        EditCmd::ChangeSpeedLimitSchedule { new, old, .. } => new == old,
+        EditCmd::ChangeIntersection { new, old, .. } => new == old,
+        EditCmd::ChangeAccessRestrictions {
+            new_allow_through_traffic,
+            old_allow_through_traffic,
+            ..
+        } => new_allow_through_traffic == old_allow_through_traffic,
+        EditCmd::ChangeParkingPolicy { new, old, .. } => new == old,
+        EditCmd::ChangeTruckAccess { new, old, .. } => new == old,
+        EditCmd::ChangeBusLaneCamera { new, old, .. } => new == old,
+        EditCmd::ChangeLeadingPedestrianInterval { new, old, .. } => new == old,
+        EditCmd::ChangeRightTurnOnRed { new, old, .. } => new == old,
+        EditCmd::ChangeTransitSignalPriority { new, old, .. } => new == old,
+    }
+}
+
+// The order of commands for different kinds of changes doesn't matter.
+fn same_changes(list1: &Vec<EditCmd>, list2: &Vec<EditCmd>) -> bool {
+    list1.len() == list2.len() && list1.iter().all(|cmd| list2.contains(cmd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LaneType;
+    use geom::Speed;
+
+    fn edits(commands: Vec<EditCmd>) -> MapEdits {
+        let mut edits = MapEdits::new();
+        edits.commands = commands;
+        edits
+    }
+
+    fn lane(id: usize, orig_lt: LaneType, lt: LaneType) -> EditCmd {
+        EditCmd::ChangeLaneType {
+            id: LaneID(id),
+            lt,
+            orig_lt,
+        }
+    }
+
+    fn speed(id: usize, old: f64, new: f64) -> EditCmd {
+        EditCmd::ChangeSpeedLimit {
+            id: RoadID(id),
+            new: Speed::miles_per_hour(new),
+            old: Speed::miles_per_hour(old),
+        }
+    }
+
+    #[test]
+    fn merges_cleanly() {
+        let base = edits(vec![lane(0, LaneType::Parking, LaneType::Driving)]);
+        let mut ours = base.clone();
+        ours.commands
+            .push(lane(1, LaneType::Driving, LaneType::Bus));
+        ours.commands.push(speed(2, 25.0, 20.0));
+        let mut theirs = base.clone();
+        // The same change as ours
+        theirs.commands.push(speed(2, 25.0, 20.0));
+        theirs
+            .commands
+            .push(lane(3, LaneType::Driving, LaneType::Biking));
+
+        let merged = MergedEdits::new(&base, &ours, &theirs);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(
+            merged.clean,
+            vec![
+                lane(0, LaneType::Parking, LaneType::Driving),
+                lane(1, LaneType::Driving, LaneType::Bus),
+                lane(3, LaneType::Driving, LaneType::Biking),
+                speed(2, 25.0, 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_net_changes_matter() {
+        let base = edits(vec![lane(0, LaneType::Parking, LaneType::Driving)]);
+        // Taking a detour to the same place doesn't conflict
+        let ours = edits(vec![
+            lane(0, LaneType::Parking, LaneType::Driving),
+            lane(1, LaneType::Driving, LaneType::Bus),
+            lane(1, LaneType::Bus, LaneType::Biking),
+        ]);
+        let theirs = edits(vec![
+            lane(0, LaneType::Parking, LaneType::Driving),
+            lane(1, LaneType::Driving, LaneType::Biking),
+            // Changed and then put back
+            lane(2, LaneType::Driving, LaneType::Bus),
+            lane(2, LaneType::Bus, LaneType::Driving),
+        ]);
+        let merged = MergedEdits::new(&base, &ours, &theirs);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(
+            merged.clean,
+            vec![
+                lane(0, LaneType::Parking, LaneType::Driving),
+                lane(1, LaneType::Driving, LaneType::Biking),
+            ]
+        );
+
+        // Reverting something from the base, when the other side left it alone, wins
+        let ours = edits(vec![
+            lane(0, LaneType::Parking, LaneType::Driving),
+            lane(0, LaneType::Driving, LaneType::Parking),
+        ]);
+        let merged = MergedEdits::new(&base, &ours, &base);
+        assert!(merged.conflicts.is_empty());
+        assert!(merged.clean.is_empty());
+    }
+
+    #[test]
+    fn conflicts_resolve_in_order() {
+        let base = edits(Vec::new());
+        let ours = edits(vec![
+            speed(5, 25.0, 20.0),
+            lane(1, LaneType::Driving, LaneType::Bus),
+            lane(2, LaneType::Parking, LaneType::Driving),
+        ]);
+        let theirs = edits(vec![
+            lane(1, LaneType::Driving, LaneType::Biking),
+            speed(5, 25.0, 30.0),
+            lane(2, LaneType::Parking, LaneType::Driving),
+        ]);
+        let merged = MergedEdits::new(&base, &ours, &theirs);
+        assert_eq!(
+            merged.clean,
+            vec![lane(2, LaneType::Parking, LaneType::Driving)]
+        );
+        // Conflicts are ordered by object, not by when they happened
+        assert_eq!(
+            merged.conflicts.iter().map(|c| c.obj).collect::<Vec<_>>(),
+            vec![EditedObject::Lane(LaneID(1)), EditedObject::Road(RoadID(5))]
+        );
+        assert_eq!(
+            merged.conflicts[0].ours,
+            vec![lane(1, LaneType::Driving, LaneType::Bus)]
+        );
+        assert_eq!(
+            merged.conflicts[0].theirs,
+            vec![lane(1, LaneType::Driving, LaneType::Biking)]
+        );
+
+        let resolved = merged.resolve(
+            "merged".to_string(),
+            &vec![Resolution::KeepOurs, Resolution::KeepTheirs],
+        );
+        assert_eq!(resolved.edits_name, "merged");
+        assert_eq!(
+            resolved.commands,
+            vec![
+                lane(2, LaneType::Parking, LaneType::Driving),
+                lane(1, LaneType::Driving, LaneType::Bus),
+                speed(5, 25.0, 30.0),
+            ]
+        );
+    }
+}
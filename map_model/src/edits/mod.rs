@@ -1,11 +1,18 @@
+mod annotations;
+mod merge;
+mod summary;
+
+pub use self::annotations::Annotation;
+pub use self::merge::{EditConflict, EditedObject, MergedEdits, Resolution};
+pub use self::summary::EditSummary;
 use crate::raw::{OriginalIntersection, OriginalRoad};
 use crate::{
     connectivity, ControlStopSign, ControlTrafficSignal, IntersectionID, IntersectionType, LaneID,
-    LaneType, Map, PathConstraints, RoadID, TurnID, Zone,
+    LaneType, Map, ParkingPolicy, PathConstraints, RoadID, SpeedLimitPeriod, TurnID, Zone,
 };
 use abstutil::{deserialize_btreemap, retain_btreemap, retain_btreeset, serialize_btreemap, Timer};
 use enumset::EnumSet;
-use geom::{Distance, Speed};
+use geom::{Distance, Duration, Speed};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -13,18 +20,29 @@ use std::collections::{BTreeMap, BTreeSet};
 pub struct MapEdits {
     pub edits_name: String,
     pub commands: Vec<EditCmd>,
+    // Runs of commands that undo together, as [start, end) indices into commands. Only kept for
+    // the session; saved edits are compressed anyway.
+    pub grouped_commands: Vec<(usize, usize)>,
 
     // Derived from commands, kept up to date by update_derived
     pub original_lts: BTreeMap<LaneID, LaneType>,
     pub reversed_lanes: BTreeSet<LaneID>,
     pub original_intersections: BTreeMap<IntersectionID, EditIntersection>,
     pub changed_speed_limits: BTreeSet<RoadID>,
+    pub changed_speed_limit_schedules: BTreeSet<RoadID>,
     pub changed_access_restrictions: BTreeSet<RoadID>,
+    pub changed_parking_policies: BTreeSet<LaneID>,
+    pub changed_truck_access: BTreeSet<RoadID>,
+    pub changed_leading_pedestrian_intervals: BTreeSet<IntersectionID>,
+    pub changed_right_turn_on_red: BTreeSet<IntersectionID>,
+    pub changed_transit_signal_priority: BTreeSet<IntersectionID>,
+    pub changed_bus_lane_cameras: BTreeSet<RoadID>,
 
     // Edits without these are player generated.
     pub proposal_description: Vec<String>,
     // The link is optional even for proposals
     pub proposal_link: Option<String>,
+    pub annotations: Vec<Annotation>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -53,6 +71,11 @@ pub enum EditCmd {
         new: Speed,
         old: Speed,
     },
+    ChangeSpeedLimitSchedule {
+        id: RoadID,
+        new: Vec<SpeedLimitPeriod>,
+        old: Vec<SpeedLimitPeriod>,
+    },
     ChangeIntersection {
         i: IntersectionID,
         new: EditIntersection,
@@ -64,6 +87,40 @@ pub enum EditCmd {
         new_allow_through_traffic: EnumSet<PathConstraints>,
         old_allow_through_traffic: EnumSet<PathConstraints>,
     },
+    // Prices and time limits for one parking lane
+    ChangeParkingPolicy {
+        id: LaneID,
+        new: ParkingPolicy,
+        old: ParkingPolicy,
+    },
+    ChangeTruckAccess {
+        id: RoadID,
+        new: bool,
+        old: bool,
+    },
+    ChangeBusLaneCamera {
+        id: RoadID,
+        new: bool,
+        old: bool,
+    },
+    // Only for traffic signals. Not part of EditIntersection, since the signal format shared with
+    // the timing data has nowhere to put it.
+    ChangeLeadingPedestrianInterval {
+        i: IntersectionID,
+        new: Duration,
+        old: Duration,
+    },
+    // Also only for traffic signals
+    ChangeRightTurnOnRed {
+        i: IntersectionID,
+        new: bool,
+        old: bool,
+    },
+    ChangeTransitSignalPriority {
+        i: IntersectionID,
+        new: bool,
+        old: bool,
+    },
 }
 
 pub struct EditEffects {
@@ -80,13 +137,22 @@ impl MapEdits {
             edits_name: "untitled edits".to_string(),
             proposal_description: Vec::new(),
             proposal_link: None,
+            annotations: Vec::new(),
             commands: Vec::new(),
+            grouped_commands: Vec::new(),
 
             original_lts: BTreeMap::new(),
             reversed_lanes: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            changed_speed_limit_schedules: BTreeSet::new(),
             changed_access_restrictions: BTreeSet::new(),
+            changed_parking_policies: BTreeSet::new(),
+            changed_truck_access: BTreeSet::new(),
+            changed_leading_pedestrian_intervals: BTreeSet::new(),
+            changed_right_turn_on_red: BTreeSet::new(),
+            changed_transit_signal_priority: BTreeSet::new(),
+            changed_bus_lane_cameras: BTreeSet::new(),
         }
     }
 
@@ -100,6 +166,30 @@ impl MapEdits {
         )
     }
 
+    // Like pushing each command, except undo_last removes all of them at once.
+    pub fn push_group(&mut self, cmds: Vec<EditCmd>) {
+        let start = self.commands.len();
+        self.commands.extend(cmds);
+        if self.commands.len() - start > 1 {
+            self.grouped_commands.push((start, self.commands.len()));
+        }
+    }
+
+    // Removes the most recent command, or the whole group it belongs to. Returns them in the
+    // order they were pushed.
+    pub fn undo_last(&mut self) -> Vec<EditCmd> {
+        let len = self.commands.len();
+        self.grouped_commands.retain(|(_, end)| *end <= len);
+        match self.grouped_commands.last() {
+            Some((start, end)) if *end == len => {
+                let start = *start;
+                self.grouped_commands.pop();
+                self.commands.split_off(start)
+            }
+            _ => self.commands.pop().into_iter().collect(),
+        }
+    }
+
     // TODO Version these? Or it's unnecessary, since we have a command stack.
     fn save(&self, map: &Map) {
         assert_ne!(self.edits_name, "untitled edits");
@@ -115,7 +205,14 @@ impl MapEdits {
         let mut reversed_lanes = BTreeSet::new();
         let mut orig_intersections: BTreeMap<IntersectionID, EditIntersection> = BTreeMap::new();
         let mut changed_speed_limits = BTreeSet::new();
+        let mut changed_speed_limit_schedules = BTreeSet::new();
         let mut changed_access_restrictions = BTreeSet::new();
+        let mut changed_parking_policies = BTreeSet::new();
+        let mut changed_truck_access = BTreeSet::new();
+        let mut changed_leading_pedestrian_intervals = BTreeSet::new();
+        let mut changed_right_turn_on_red = BTreeSet::new();
+        let mut changed_transit_signal_priority = BTreeSet::new();
+        let mut changed_bus_lane_cameras = BTreeSet::new();
 
         for cmd in &self.commands {
             match cmd {
@@ -134,6 +231,9 @@ impl MapEdits {
                 EditCmd::ChangeSpeedLimit { id, .. } => {
                     changed_speed_limits.insert(*id);
                 }
+                EditCmd::ChangeSpeedLimitSchedule { id, .. } => {
+                    changed_speed_limit_schedules.insert(*id);
+                }
                 EditCmd::ChangeIntersection { i, ref old, .. } => {
                     if !orig_intersections.contains_key(i) {
                         orig_intersections.insert(*i, old.clone());
@@ -142,6 +242,24 @@ impl MapEdits {
                 EditCmd::ChangeAccessRestrictions { id, .. } => {
                     changed_access_restrictions.insert(*id);
                 }
+                EditCmd::ChangeParkingPolicy { id, .. } => {
+                    changed_parking_policies.insert(*id);
+                }
+                EditCmd::ChangeTruckAccess { id, .. } => {
+                    changed_truck_access.insert(*id);
+                }
+                EditCmd::ChangeBusLaneCamera { id, .. } => {
+                    changed_bus_lane_cameras.insert(*id);
+                }
+                EditCmd::ChangeLeadingPedestrianInterval { i, .. } => {
+                    changed_leading_pedestrian_intervals.insert(*i);
+                }
+                EditCmd::ChangeRightTurnOnRed { i, .. } => {
+                    changed_right_turn_on_red.insert(*i);
+                }
+                EditCmd::ChangeTransitSignalPriority { i, .. } => {
+                    changed_transit_signal_priority.insert(*i);
+                }
             }
         }
 
@@ -152,16 +270,53 @@ impl MapEdits {
         retain_btreeset(&mut changed_speed_limits, |r| {
             map.get_r(*r).speed_limit != map.get_r(*r).speed_limit_from_osm()
         });
+        // OSM has maxspeed:conditional, but it isn't imported yet
+        retain_btreeset(&mut changed_speed_limit_schedules, |r| {
+            !map.get_r(*r).speed_limit_schedule.is_empty()
+        });
         retain_btreeset(&mut changed_access_restrictions, |r| {
             let r = map.get_r(*r);
             r.access_restrictions_from_osm() != r.allow_through_traffic
         });
+        retain_btreeset(&mut changed_parking_policies, |l| {
+            let l = map.get_l(*l);
+            l.parking_policy_from_osm(map) != l.parking_policy
+        });
+        retain_btreeset(&mut changed_truck_access, |r| {
+            let r = map.get_r(*r);
+            r.trucks_allowed_from_osm() != r.allow_trucks
+        });
+        retain_btreeset(&mut changed_bus_lane_cameras, |r| {
+            map.get_r(*r).bus_lane_camera
+        });
+        retain_btreeset(&mut changed_leading_pedestrian_intervals, |i| {
+            map.maybe_get_traffic_signal(*i)
+                .map(|ts| ts.leading_pedestrian_interval != Duration::ZERO)
+                .unwrap_or(false)
+        });
+        retain_btreeset(&mut changed_right_turn_on_red, |i| {
+            map.maybe_get_traffic_signal(*i)
+                .map(|ts| ts.right_turn_on_red != map.config.right_turn_on_red)
+                .unwrap_or(false)
+        });
+        retain_btreeset(&mut changed_transit_signal_priority, |i| {
+            map.maybe_get_traffic_signal(*i)
+                .map(|ts| ts.transit_signal_priority)
+                .unwrap_or(false)
+        });
 
         self.original_lts = orig_lts;
         self.reversed_lanes = reversed_lanes;
         self.original_intersections = orig_intersections;
         self.changed_speed_limits = changed_speed_limits;
+        self.changed_speed_limit_schedules = changed_speed_limit_schedules;
         self.changed_access_restrictions = changed_access_restrictions;
+        self.changed_parking_policies = changed_parking_policies;
+        self.changed_truck_access = changed_truck_access;
+        self.changed_bus_lane_cameras = changed_bus_lane_cameras;
+        self.changed_leading_pedestrian_intervals = changed_leading_pedestrian_intervals;
+        self.changed_right_turn_on_red = changed_right_turn_on_red;
+        self.changed_transit_signal_priority = changed_transit_signal_priority;
     }
 
     // Assumes update_derived has been called.
@@ -193,6 +348,13 @@ impl MapEdits {
                 old: map.get_r(*r).speed_limit_from_osm(),
             });
         }
+        for r in &self.changed_speed_limit_schedules {
+            self.commands.push(EditCmd::ChangeSpeedLimitSchedule {
+                id: *r,
+                new: map.get_r(*r).speed_limit_schedule.clone(),
+                old: Vec::new(),
+            });
+        }
         for r in &self.changed_access_restrictions {
             self.commands.push(EditCmd::ChangeAccessRestrictions {
                 id: *r,
@@ -200,6 +362,49 @@ impl MapEdits {
                 old_allow_through_traffic: map.get_r(*r).access_restrictions_from_osm(),
             });
         }
+        for l in &self.changed_parking_policies {
+            self.commands.push(EditCmd::ChangeParkingPolicy {
+                id: *l,
+                new: map.get_l(*l).parking_policy,
+                old: map.get_l(*l).parking_policy_from_osm(map),
+            });
+        }
+        for r in &self.changed_truck_access {
+            self.commands.push(EditCmd::ChangeTruckAccess {
+                id: *r,
+                new: map.get_r(*r).allow_trucks,
+                old: map.get_r(*r).trucks_allowed_from_osm(),
+            });
+        }
+        for r in &self.changed_bus_lane_cameras {
+            self.commands.push(EditCmd::ChangeBusLaneCamera {
+                id: *r,
+                new: true,
+                old: false,
+            });
+        }
+        for i in &self.changed_leading_pedestrian_intervals {
+            self.commands
+                .push(EditCmd::ChangeLeadingPedestrianInterval {
+                    i: *i,
+                    new: map.get_traffic_signal(*i).leading_pedestrian_interval,
+                    old: Duration::ZERO,
+                });
+        }
+        for i in &self.changed_right_turn_on_red {
+            self.commands.push(EditCmd::ChangeRightTurnOnRed {
+                i: *i,
+                new: map.get_traffic_signal(*i).right_turn_on_red,
+                old: map.config.right_turn_on_red,
+            });
+        }
+        for i in &self.changed_transit_signal_priority {
+            self.commands.push(EditCmd::ChangeTransitSignalPriority {
+                i: *i,
+                new: true,
+                old: false,
+            });
+        }
     }
 }
 
@@ -233,6 +438,9 @@ pub struct PermanentMapEdits {
     pub proposal_description: Vec<String>,
     // The link is optional even for proposals
     pub proposal_link: Option<String>,
+    // Older edits don't have these
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -275,6 +483,11 @@ enum PermanentEditCmd {
         new: Speed,
         old: Speed,
     },
+    ChangeSpeedLimitSchedule {
+        id: OriginalRoad,
+        new: Vec<SpeedLimitPeriod>,
+        old: Vec<SpeedLimitPeriod>,
+    },
     ChangeIntersection {
         i: OriginalIntersection,
         new: PermanentEditIntersection,
@@ -285,6 +498,36 @@ enum PermanentEditCmd {
         new_allow_through_traffic: EnumSet<PathConstraints>,
         old_allow_through_traffic: EnumSet<PathConstraints>,
     },
+    ChangeParkingPolicy {
+        id: OriginalLane,
+        new: ParkingPolicy,
+        old: ParkingPolicy,
+    },
+    ChangeTruckAccess {
+        id: OriginalRoad,
+        new: bool,
+        old: bool,
+    },
+    ChangeBusLaneCamera {
+        id: OriginalRoad,
+        new: bool,
+        old: bool,
+    },
+    ChangeLeadingPedestrianInterval {
+        i: OriginalIntersection,
+        new: Duration,
+        old: Duration,
+    },
+    ChangeRightTurnOnRed {
+        i: OriginalIntersection,
+        new: bool,
+        old: bool,
+    },
+    ChangeTransitSignalPriority {
+        i: OriginalIntersection,
+        new: bool,
+        old: bool,
+    },
 }
 
 impl PermanentMapEdits {
@@ -294,6 +537,7 @@ impl PermanentMapEdits {
             edits_name: edits.edits_name.clone(),
             proposal_description: edits.proposal_description.clone(),
             proposal_link: edits.proposal_link.clone(),
+            annotations: edits.annotations.clone(),
             commands: edits
                 .commands
                 .iter()
@@ -316,6 +560,13 @@ impl PermanentMapEdits {
                             old: *old,
                         }
                     }
+                    EditCmd::ChangeSpeedLimitSchedule { id, new, old } => {
+                        PermanentEditCmd::ChangeSpeedLimitSchedule {
+                            id: map.get_r(*id).orig_id,
+                            new: new.clone(),
+                            old: old.clone(),
+                        }
+                    }
                     EditCmd::ChangeIntersection { i, new, old } => {
                         PermanentEditCmd::ChangeIntersection {
                             i: map.get_i(*i).orig_id,
@@ -332,6 +583,48 @@ impl PermanentMapEdits {
                         new_allow_through_traffic: *new_allow_through_traffic,
                         old_allow_through_traffic: *old_allow_through_traffic,
                     },
+                    EditCmd::ChangeParkingPolicy { id, new, old } => {
+                        PermanentEditCmd::ChangeParkingPolicy {
+                            id: OriginalLane::to_permanent(*id, map),
+                            new: *new,
+                            old: *old,
+                        }
+                    }
+                    EditCmd::ChangeTruckAccess { id, new, old } => {
+                        PermanentEditCmd::ChangeTruckAccess {
+                            id: map.get_r(*id).orig_id,
+                            new: *new,
+                            old: *old,
+                        }
+                    }
+                    EditCmd::ChangeBusLaneCamera { id, new, old } => {
+                        PermanentEditCmd::ChangeBusLaneCamera {
+                            id: map.get_r(*id).orig_id,
+                            new: *new,
+                            old: *old,
+                        }
+                    }
+                    EditCmd::ChangeLeadingPedestrianInterval { i, new, old } => {
+                        PermanentEditCmd::ChangeLeadingPedestrianInterval {
+                            i: map.get_i(*i).orig_id,
+                            new: *new,
+                            old: *old,
+                        }
+                    }
+                    EditCmd::ChangeRightTurnOnRed { i, new, old } => {
+                        PermanentEditCmd::ChangeRightTurnOnRed {
+                            i: map.get_i(*i).orig_id,
+                            new: *new,
+                            old: *old,
+                        }
+                    }
+                    EditCmd::ChangeTransitSignalPriority { i, new, old } => {
+                        PermanentEditCmd::ChangeTransitSignalPriority {
+                            i: map.get_i(*i).orig_id,
+                            new: *new,
+                            old: *old,
+                        }
+                    }
                 })
                 .collect(),
         }
@@ -340,8 +633,10 @@ impl PermanentMapEdits {
     pub fn from_permanent(perma: PermanentMapEdits, map: &Map) -> Result<MapEdits, String> {
         let mut edits = MapEdits {
             edits_name: perma.edits_name,
+            grouped_commands: Vec::new(),
             proposal_description: perma.proposal_description,
             proposal_link: perma.proposal_link,
+            annotations: perma.annotations,
             commands: perma
                 .commands
                 .into_iter()
@@ -371,6 +666,16 @@ impl PermanentMapEdits {
                         )?;
                         Ok(EditCmd::ChangeSpeedLimit { id, new, old })
                     }
+                    PermanentEditCmd::ChangeSpeedLimitSchedule { id, new, old } => {
+                        let id = map.find_r_by_osm_id(
+                            id.osm_way_id,
+                            (id.i1.osm_node_id, id.i2.osm_node_id),
+                        )?;
+                        for period in &new {
+                            period.validate()?;
+                        }
+                        Ok(EditCmd::ChangeSpeedLimitSchedule { id, new, old })
+                    }
                     PermanentEditCmd::ChangeIntersection { i, new, old } => {
                         let id = map.find_i_by_osm_id(i.osm_node_id)?;
                         Ok(EditCmd::ChangeIntersection {
@@ -398,6 +703,36 @@ impl PermanentMapEdits {
                             old_allow_through_traffic,
                         })
                     }
+                    PermanentEditCmd::ChangeParkingPolicy { id, new, old } => {
+                        let id = id.from_permanent(map)?;
+                        Ok(EditCmd::ChangeParkingPolicy { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeTruckAccess { id, new, old } => {
+                        let id = map.find_r_by_osm_id(
+                            id.osm_way_id,
+                            (id.i1.osm_node_id, id.i2.osm_node_id),
+                        )?;
+                        Ok(EditCmd::ChangeTruckAccess { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeBusLaneCamera { id, new, old } => {
+                        let id = map.find_r_by_osm_id(
+                            id.osm_way_id,
+                            (id.i1.osm_node_id, id.i2.osm_node_id),
+                        )?;
+                        Ok(EditCmd::ChangeBusLaneCamera { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeLeadingPedestrianInterval { i, new, old } => {
+                        let i = map.find_i_by_osm_id(i.osm_node_id)?;
+                        Ok(EditCmd::ChangeLeadingPedestrianInterval { i, new, old })
+                    }
+                    PermanentEditCmd::ChangeRightTurnOnRed { i, new, old } => {
+                        let i = map.find_i_by_osm_id(i.osm_node_id)?;
+                        Ok(EditCmd::ChangeRightTurnOnRed { i, new, old })
+                    }
+                    PermanentEditCmd::ChangeTransitSignalPriority { i, new, old } => {
+                        let i = map.find_i_by_osm_id(i.osm_node_id)?;
+                        Ok(EditCmd::ChangeTransitSignalPriority { i, new, old })
+                    }
                 })
                 .collect::<Result<Vec<EditCmd>, String>>()?,
 
@@ -405,7 +740,14 @@ impl PermanentMapEdits {
             reversed_lanes: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            changed_speed_limit_schedules: BTreeSet::new(),
             changed_access_restrictions: BTreeSet::new(),
+            changed_parking_policies: BTreeSet::new(),
+            changed_truck_access: BTreeSet::new(),
+            changed_leading_pedestrian_intervals: BTreeSet::new(),
+            changed_right_turn_on_red: BTreeSet::new(),
+            changed_transit_signal_priority: BTreeSet::new(),
+            changed_bus_lane_cameras: BTreeSet::new(),
         };
         edits.update_derived(map);
         Ok(edits)
@@ -499,6 +841,9 @@ impl EditCmd {
             EditCmd::ChangeLaneType { lt, id, .. } => format!("{} on #{}", lt.short_name(), id.0),
             EditCmd::ReverseLane { l, .. } => format!("reverse {}", l),
             EditCmd::ChangeSpeedLimit { id, new, .. } => format!("limit {} for {}", new, id),
+            EditCmd::ChangeSpeedLimitSchedule { id, new, .. } => {
+                format!("{} time-based limits for {}", new.len(), id)
+            }
             EditCmd::ChangeIntersection { i, new, .. } => match new {
                 EditIntersection::StopSign(_) => format!("stop sign #{}", i.0),
                 EditIntersection::TrafficSignal(_) => format!("traffic signal #{}", i.0),
@@ -508,6 +853,40 @@ impl EditCmd {
             EditCmd::ChangeAccessRestrictions { id, .. } => {
                 format!("access restrictions for {}", id)
             }
+            EditCmd::ChangeParkingPolicy { id, new, .. } => {
+                format!("parking {} on #{}", new.describe(), id.0)
+            }
+            EditCmd::ChangeTruckAccess { id, new, .. } => {
+                if *new {
+                    format!("allow trucks on {}", id)
+                } else {
+                    format!("ban trucks on {}", id)
+                }
+            }
+            EditCmd::ChangeBusLaneCamera { id, new, .. } => {
+                if *new {
+                    format!("bus lane camera on {}", id)
+                } else {
+                    format!("remove bus lane camera on {}", id)
+                }
+            }
+            EditCmd::ChangeLeadingPedestrianInterval { i, new, .. } => {
+                format!("{} pedestrian head start at #{}", new, i.0)
+            }
+            EditCmd::ChangeRightTurnOnRed { i, new, .. } => {
+                if *new {
+                    format!("allow turns on red at #{}", i.0)
+                } else {
+                    format!("ban turns on red at #{}", i.0)
+                }
+            }
+            EditCmd::ChangeTransitSignalPriority { i, new, .. } => {
+                if *new {
+                    format!("transit signal priority at #{}", i.0)
+                } else {
+                    format!("remove transit signal priority at #{}", i.0)
+                }
+            }
         }
     }
 
@@ -580,6 +959,15 @@ impl EditCmd {
                     false
                 }
             }
+            EditCmd::ChangeSpeedLimitSchedule { id, new, .. } => {
+                if &map.roads[id.0].speed_limit_schedule != new {
+                    map.roads[id.0].speed_limit_schedule = new.clone();
+                    effects.changed_roads.insert(*id);
+                    true
+                } else {
+                    false
+                }
+            }
             EditCmd::ChangeIntersection {
                 i,
                 ref new,
@@ -590,7 +978,8 @@ impl EditCmd {
                 }
 
                 map.stop_signs.remove(i);
-                map.traffic_signals.remove(i);
+                // Timing data doesn't include these, so keep them across changes to the phases
+                let old_signal = map.traffic_signals.remove(i);
                 effects.changed_intersections.insert(*i);
                 match new {
                     EditIntersection::StopSign(ref ss) => {
@@ -602,10 +991,13 @@ impl EditCmd {
                         if old == &EditIntersection::Closed {
                             recalculate_turns(*i, map, effects, timer);
                         }
-                        map.traffic_signals.insert(
-                            *i,
-                            ControlTrafficSignal::import(raw_ts.clone(), *i, map).unwrap(),
-                        );
+                        let mut ts = ControlTrafficSignal::import(raw_ts.clone(), *i, map).unwrap();
+                        if let Some(old_ts) = old_signal {
+                            ts.leading_pedestrian_interval = old_ts.leading_pedestrian_interval;
+                            ts.right_turn_on_red = old_ts.right_turn_on_red;
+                            ts.transit_signal_priority = old_ts.transit_signal_priority;
+                        }
+                        map.traffic_signals.insert(*i, ts);
                     }
                     EditIntersection::Closed => {
                         map.intersections[i.0].intersection_type = IntersectionType::Construction;
@@ -632,6 +1024,61 @@ impl EditCmd {
                 effects.changed_intersections.insert(r.dst_i);
                 true
             }
+            EditCmd::ChangeParkingPolicy { id, new, .. } => {
+                if map.lanes[id.0].parking_policy != *new {
+                    map.lanes[id.0].parking_policy = *new;
+                    effects.changed_roads.insert(map.lanes[id.0].parent);
+                    true
+                } else {
+                    false
+                }
+            }
+            EditCmd::ChangeTruckAccess { id, new, .. } => {
+                if map.roads[id.0].allow_trucks != *new {
+                    map.roads[id.0].allow_trucks = *new;
+                    effects.changed_roads.insert(*id);
+                    true
+                } else {
+                    false
+                }
+            }
+            EditCmd::ChangeBusLaneCamera { id, new, .. } => {
+                if map.roads[id.0].bus_lane_camera != *new {
+                    map.roads[id.0].bus_lane_camera = *new;
+                    effects.changed_roads.insert(*id);
+                    true
+                } else {
+                    false
+                }
+            }
+            EditCmd::ChangeLeadingPedestrianInterval { i, new, .. } => {
+                match map.traffic_signals.get_mut(i) {
+                    Some(ts) if ts.leading_pedestrian_interval != *new => {
+                        ts.leading_pedestrian_interval = *new;
+                        effects.changed_intersections.insert(*i);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            EditCmd::ChangeRightTurnOnRed { i, new, .. } => match map.traffic_signals.get_mut(i) {
+                Some(ts) if ts.right_turn_on_red != *new => {
+                    ts.right_turn_on_red = *new;
+                    effects.changed_intersections.insert(*i);
+                    true
+                }
+                _ => false,
+            },
+            EditCmd::ChangeTransitSignalPriority { i, new, .. } => {
+                match map.traffic_signals.get_mut(i) {
+                    Some(ts) if ts.transit_signal_priority != *new => {
+                        ts.transit_signal_priority = *new;
+                        effects.changed_intersections.insert(*i);
+                        true
+                    }
+                    _ => false,
+                }
+            }
         }
     }
 
@@ -666,6 +1113,14 @@ impl EditCmd {
                     false
                 }
             }
+            EditCmd::ChangeSpeedLimitSchedule { id, new, old } => {
+                EditCmd::ChangeSpeedLimitSchedule {
+                    id: *id,
+                    new: old.clone(),
+                    old: new.clone(),
+                }
+                .apply(effects, map, timer)
+            }
             EditCmd::ChangeIntersection {
                 i,
                 ref old,
@@ -686,6 +1141,46 @@ impl EditCmd {
                 new_allow_through_traffic: *old_allow_through_traffic,
             }
             .apply(effects, map, timer),
+            EditCmd::ChangeParkingPolicy { id, new, old } => EditCmd::ChangeParkingPolicy {
+                id: *id,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeTruckAccess { id, new, old } => EditCmd::ChangeTruckAccess {
+                id: *id,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeBusLaneCamera { id, new, old } => EditCmd::ChangeBusLaneCamera {
+                id: *id,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeLeadingPedestrianInterval { i, new, old } => {
+                EditCmd::ChangeLeadingPedestrianInterval {
+                    i: *i,
+                    new: *old,
+                    old: *new,
+                }
+                .apply(effects, map, timer)
+            }
+            EditCmd::ChangeRightTurnOnRed { i, new, old } => EditCmd::ChangeRightTurnOnRed {
+                i: *i,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeTransitSignalPriority { i, new, old } => {
+                EditCmd::ChangeTransitSignalPriority {
+                    i: *i,
+                    new: *old,
+                    old: *new,
+                }
+                .apply(effects, map, timer)
+            }
         }
     }
 }
@@ -765,6 +1260,11 @@ impl Map {
         }
     }
 
+    // Annotations don't change the map, so there's no need to go through apply_edits.
+    pub fn set_edit_annotations(&mut self, annotations: Vec<Annotation>) {
+        self.edits.annotations = annotations;
+    }
+
     pub fn save_edits(&self) {
         // Don't overwrite the current edits with the compressed first. Otherwise, undo/redo order
         // in the UI gets messed up.
@@ -856,6 +1356,9 @@ impl Map {
         if !effects.changed_roads.is_empty() {
             self.zones = Zone::make_all(self);
         }
+        for r in &effects.changed_roads {
+            self.reindex_road(*r);
+        }
 
         new_edits.update_derived(self);
         self.edits = new_edits;
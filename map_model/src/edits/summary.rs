@@ -0,0 +1,161 @@
+use crate::edits::merge::net_changes;
+use crate::{EditCmd, EditIntersection, EditedObject, Map, MapEdits};
+use enumset::EnumSet;
+use geom::Duration;
+
+// One line describing some edits in plain language, like "3 lanes converted from driving lane to
+// bus lane on NE 45th St", and everything it covers.
+#[derive(Clone, Debug)]
+pub struct EditSummary {
+    pub description: String,
+    pub objects: Vec<EditedObject>,
+}
+
+#[derive(PartialEq)]
+enum Phrase {
+    // Finishes "N lanes ..."
+    Lanes(String),
+    Other(String),
+}
+
+impl MapEdits {
+    // Only the net changes, so something changed and then undone doesn't show up. Similar changes
+    // at the same place are grouped together.
+    pub fn summarize(&self, map: &Map) -> Vec<EditSummary> {
+        // Keep the order things were first changed in
+        let mut groups: Vec<(Phrase, Vec<EditedObject>)> = Vec::new();
+        for (obj, cmds) in net_changes(self) {
+            for cmd in cmds {
+                let phrase = describe(&cmd, map);
+                if let Some(idx) = groups.iter().position(|(p, _)| *p == phrase) {
+                    if !groups[idx].1.contains(&obj) {
+                        groups[idx].1.push(obj);
+                    }
+                } else {
+                    groups.push((phrase, vec![obj]));
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(phrase, objects)| EditSummary {
+                description: match phrase {
+                    Phrase::Lanes(rest) => {
+                        if objects.len() == 1 {
+                            format!("1 lane {}", rest)
+                        } else {
+                            format!("{} lanes {}", objects.len(), rest)
+                        }
+                    }
+                    Phrase::Other(x) => x,
+                },
+                objects,
+            })
+            .collect()
+    }
+}
+
+fn describe(cmd: &EditCmd, map: &Map) -> Phrase {
+    match cmd {
+        EditCmd::ChangeLaneType { id, lt, orig_lt } => Phrase::Lanes(format!(
+            "converted from {} to {} on {}",
+            orig_lt.short_name(),
+            lt.short_name(),
+            map.get_parent(*id).get_name()
+        )),
+        EditCmd::ReverseLane { l, .. } => {
+            Phrase::Lanes(format!("reversed on {}", map.get_parent(*l).get_name()))
+        }
+        EditCmd::ChangeSpeedLimit { id, new, old } => Phrase::Other(format!(
+            "speed limit on {} changed from {} to {}",
+            map.get_r(*id).get_name(),
+            old,
+            new
+        )),
+        EditCmd::ChangeSpeedLimitSchedule { id, new, .. } => {
+            let name = map.get_r(*id).get_name();
+            Phrase::Other(if new.is_empty() {
+                format!("time-based speed limits removed from {}", name)
+            } else {
+                format!("{} time-based speed limits on {}", new.len(), name)
+            })
+        }
+        EditCmd::ChangeIntersection { i, new, old } => {
+            let name = map.get_i(*i).name(map);
+            Phrase::Other(match (old, new) {
+                (_, EditIntersection::Closed) => format!("{} closed", name),
+                (EditIntersection::Closed, _) => format!("{} reopened", name),
+                (EditIntersection::TrafficSignal(_), EditIntersection::TrafficSignal(_)) => {
+                    format!("signal at {} retimed", name)
+                }
+                (EditIntersection::StopSign(_), EditIntersection::TrafficSignal(_)) => {
+                    format!("signal added at {}", name)
+                }
+                (EditIntersection::TrafficSignal(_), EditIntersection::StopSign(_)) => {
+                    format!("signal at {} replaced with stop signs", name)
+                }
+                (EditIntersection::StopSign(_), EditIntersection::StopSign(_)) => {
+                    format!("stop signs at {} changed", name)
+                }
+            })
+        }
+        EditCmd::ChangeAccessRestrictions {
+            id,
+            new_allow_through_traffic,
+            ..
+        } => {
+            let name = map.get_r(*id).get_name();
+            Phrase::Other(if *new_allow_through_traffic == EnumSet::all() {
+                format!("through-traffic allowed again on {}", name)
+            } else {
+                format!("through-traffic restricted on {}", name)
+            })
+        }
+        EditCmd::ChangeParkingPolicy { id, new, .. } => Phrase::Other(format!(
+            "parking on {} changed to {}",
+            map.get_parent(*id).get_name(),
+            new.describe()
+        )),
+        EditCmd::ChangeTruckAccess { id, new, .. } => {
+            let name = map.get_r(*id).get_name();
+            Phrase::Other(if *new {
+                format!("trucks allowed on {}", name)
+            } else {
+                format!("trucks banned from {}", name)
+            })
+        }
+        EditCmd::ChangeBusLaneCamera { id, new, .. } => {
+            let name = map.get_r(*id).get_name();
+            Phrase::Other(if *new {
+                format!("bus lane camera added on {}", name)
+            } else {
+                format!("bus lane camera removed from {}", name)
+            })
+        }
+        EditCmd::ChangeLeadingPedestrianInterval { i, new, .. } => {
+            let name = map.get_i(*i).name(map);
+            Phrase::Other(if *new == Duration::ZERO {
+                format!("pedestrian head start removed at {}", name)
+            } else {
+                format!("{} pedestrian head start at {}", new, name)
+            })
+        }
+        EditCmd::ChangeRightTurnOnRed { i, new, .. } => {
+            let name = map.get_i(*i).name(map);
+            Phrase::Other(if *new {
+                format!("turns on red allowed at {}", name)
+            } else {
+                format!("turns on red banned at {}", name)
+            })
+        }
+        EditCmd::ChangeTransitSignalPriority { i, new, .. } => {
+            let name = map.get_i(*i).name(map);
+            Phrase::Other(if *new {
+                format!("transit signal priority added at {}", name)
+            } else {
+                format!("transit signal priority removed at {}", name)
+            })
+        }
+    }
+}
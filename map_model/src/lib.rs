@@ -3,40 +3,49 @@ pub mod connectivity;
 mod edits;
 mod make;
 mod map;
+mod neighborhood;
 mod objects;
 pub mod osm;
 mod pathfind;
+mod provenance;
 pub mod raw;
+mod summary;
 mod traversable;
 
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, MapEdits, OriginalLane, PermanentMapEdits,
+    Annotation, EditCmd, EditConflict, EditEffects, EditIntersection, EditSummary, EditedObject,
+    MapEdits, MergedEdits, OriginalLane, PermanentMapEdits, Resolution,
 };
 pub use crate::make::initial::lane_specs::RoadSpec;
 pub use crate::map::MapConfig;
+pub use crate::neighborhood::Neighborhood;
 pub use crate::objects::area::{Area, AreaID, AreaType};
 pub use crate::objects::building::{
-    Building, BuildingID, BuildingType, FrontPath, OffstreetParking,
+    AmenityType, Building, BuildingID, BuildingType, FrontPath, OffstreetParking,
 };
 pub use crate::objects::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
 pub use crate::objects::intersection::{Intersection, IntersectionID, IntersectionType};
 pub use crate::objects::lane::{
-    Lane, LaneID, LaneType, PARKING_LOT_SPOT_LENGTH, PARKING_SPOT_LENGTH,
+    Lane, LaneID, LaneType, ParkingPolicy, PARKING_LOT_SPOT_LENGTH, PARKING_SPOT_LENGTH,
 };
 pub use crate::objects::parking_lot::{ParkingLot, ParkingLotID};
-pub use crate::objects::road::{DirectedRoadID, Road, RoadID};
+pub use crate::objects::road::{DirectedRoadID, Road, RoadID, SpeedLimitPeriod};
 pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign};
-pub use crate::objects::traffic_signals::{ControlTrafficSignal, Phase, PhaseType};
+pub use crate::objects::traffic_signals::{
+    ControlTrafficSignal, PedestrianIntervals, Phase, PhaseType,
+};
 pub use crate::objects::turn::{Turn, TurnGroup, TurnGroupID, TurnID, TurnPriority, TurnType};
 pub use crate::objects::zone::Zone;
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGroup};
 use crate::pathfind::Pathfinder;
 pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep};
+pub use crate::provenance::MapProvenance;
+pub use crate::summary::MapSummary;
 pub use crate::traversable::{Position, Traversable};
 use abstutil::Cloneable;
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::{Bounds, Distance, GPSBounds, Polygon};
+use geom::{Bounds, Distance, FindClosest, GPSBounds, Polygon};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -85,6 +94,11 @@ pub struct Map {
     pathfinder_dirty: bool,
     // Not the source of truth, just cached.
     zones: Vec<Zone>,
+    // Also derived. Rebuilt after loading, then kept in sync with edits.
+    #[serde(skip_serializing, skip_deserializing)]
+    closest_road: FindClosest<RoadID>,
+    #[serde(skip_serializing, skip_deserializing)]
+    closest_bldg: FindClosest<BuildingID>,
 
     city_name: String,
     name: String,
@@ -1,9 +1,9 @@
 use crate::{Road, RoadID};
 use abstutil::Timer;
-use geom::{Bounds, Distance, FindClosest};
+use geom::{Distance, FindClosest};
 
-pub fn find_bridges(roads: &mut Vec<Road>, bounds: &Bounds, timer: &mut Timer) {
-    let mut closest: FindClosest<RoadID> = FindClosest::new(bounds);
+pub fn find_bridges(roads: &mut Vec<Road>, timer: &mut Timer) {
+    let mut closest: FindClosest<RoadID> = FindClosest::new();
     let mut bridges = Vec::new();
     for r in roads.iter() {
         closest.add(r.id, r.center_pts.points());
@@ -2,7 +2,8 @@ use crate::make::match_points_to_lanes;
 use crate::raw::{OriginalBuilding, RawBuilding, RawParkingLot};
 use crate::{
     osm, Building, BuildingID, BuildingType, FrontPath, LaneID, LaneType, Map, OffstreetParking,
-    ParkingLot, ParkingLotID, Position, NORMAL_LANE_THICKNESS, PARKING_LOT_SPOT_LENGTH,
+    ParkingLot, ParkingLotID, ParkingPolicy, Position, NORMAL_LANE_THICKNESS,
+    PARKING_LOT_SPOT_LENGTH,
 };
 use abstutil::{Tags, Timer};
 use geom::{Angle, Distance, FindClosest, HashablePt2D, Line, PolyLine, Polygon, Pt2D, Ring};
@@ -30,7 +31,6 @@ pub fn make_all_buildings(
     let sidewalk_buffer = Distance::meters(7.5);
     let driveway_buffer = Distance::meters(7.0);
     let sidewalk_pts = match_points_to_lanes(
-        map.get_bounds(),
         query,
         map.all_lanes(),
         |l| l.is_sidewalk(),
@@ -41,81 +41,48 @@ pub fn make_all_buildings(
         timer,
     );
 
-    let mut results = Vec::new();
-    timer.start_iter("create building front paths", center_per_bldg.len());
-    for (orig_id, bldg_center) in center_per_bldg {
-        timer.next();
-        if let Some(sidewalk_pos) = sidewalk_pts.get(&bldg_center) {
-            let b = &input[&orig_id];
-            let sidewalk_line = match Line::new(bldg_center.to_pt2d(), sidewalk_pos.pt(map)) {
-                Some(l) => trim_path(&b.polygon, l),
-                None => {
-                    timer.warn(format!(
-                        "Skipping building {} because front path has 0 length",
-                        orig_id
-                    ));
-                    continue;
-                }
-            };
-
-            let id = BuildingID(results.len());
-            let mut rng = XorShiftRng::seed_from_u64(orig_id.osm_way_id as u64);
-            let mut bldg = Building {
-                id,
-                polygon: b.polygon.clone(),
-                address: get_address(&b.osm_tags, sidewalk_pos.lane(), map),
-                name: b.osm_tags.get(osm::NAME).cloned(),
-                osm_way_id: orig_id.osm_way_id,
-                front_path: FrontPath {
-                    sidewalk: *sidewalk_pos,
-                    line: sidewalk_line.clone(),
-                },
-                amenities: b.amenities.clone(),
-                parking: None,
-                label_center: b.polygon.polylabel(),
-                bldg_type: classify_bldg(
-                    Tags::new(b.osm_tags.clone()),
-                    &b.amenities,
-                    b.polygon.area(),
-                    &mut rng,
-                ),
-            };
-
-            // Can this building have a driveway? If it's not next to a driving lane, then no.
-            let sidewalk_lane = sidewalk_pos.lane();
-            if let Ok(driving_lane) = map
-                .get_parent(sidewalk_lane)
-                .find_closest_lane(sidewalk_lane, vec![LaneType::Driving])
-            {
-                let driving_pos = sidewalk_pos.equiv_pos(driving_lane, Distance::ZERO, map);
+    // Buildings are independent of each other, but the IDs depend on which ones get skipped, so
+    // assign those afterwards.
+    let requests: Vec<(OriginalBuilding, HashablePt2D, Position)> = center_per_bldg
+        .into_iter()
+        .filter_map(|(id, center)| sidewalk_pts.get(&center).map(|pos| (id, center, *pos)))
+        .collect();
+    let orig_ids: Vec<OriginalBuilding> = requests.iter().map(|(id, _, _)| *id).collect();
+    let bldgs = timer.parallelize(
+        "create building front paths",
+        requests,
+        |(orig_id, bldg_center, sidewalk_pos)| {
+            make_bldg(
+                orig_id,
+                &input[&orig_id],
+                bldg_center.to_pt2d(),
+                sidewalk_pos,
+                driveway_buffer,
+                map,
+            )
+        },
+    );
 
-                // This shouldn't fail much anymore, unless equiv_pos winds up being pretty
-                // different
-                if driving_pos.dist_along() > driveway_buffer
-                    && map.get_l(driving_lane).length() - driving_pos.dist_along() > driveway_buffer
-                {
-                    let driveway_line = PolyLine::must_new(vec![
-                        sidewalk_line.pt1(),
-                        sidewalk_line.pt2(),
-                        driving_pos.pt(map),
-                    ]);
-                    bldg.parking = Some(OffstreetParking {
-                        public_garage_name: b.public_garage_name.clone(),
-                        num_spots: b.num_parking_spots,
-                        driveway_line,
-                        driving_pos,
-                    });
-                }
-            }
-            if bldg.parking.is_none() {
+    let mut results = Vec::new();
+    for (orig_id, maybe_bldg) in orig_ids.into_iter().zip(bldgs) {
+        let mut bldg = match maybe_bldg {
+            Some(b) => b,
+            None => {
                 timer.warn(format!(
-                    "{} can't have a driveway. Forfeiting {} parking spots",
-                    bldg.id, b.num_parking_spots
+                    "Skipping building {} because front path has 0 length",
+                    orig_id
                 ));
+                continue;
             }
-
-            results.push(bldg);
+        };
+        bldg.id = BuildingID(results.len());
+        if bldg.parking.is_none() {
+            timer.warn(format!(
+                "{} can't have a driveway. Forfeiting {} parking spots",
+                bldg.id, input[&orig_id].num_parking_spots
+            ));
         }
+        results.push(bldg);
     }
 
     timer.note(format!(
@@ -127,6 +94,74 @@ pub fn make_all_buildings(
     results
 }
 
+// The ID is filled out by the caller. None if the front path would have 0 length.
+fn make_bldg(
+    orig_id: OriginalBuilding,
+    b: &RawBuilding,
+    bldg_center: Pt2D,
+    sidewalk_pos: Position,
+    driveway_buffer: Distance,
+    map: &Map,
+) -> Option<Building> {
+    let sidewalk_line = trim_path(&b.polygon, Line::new(bldg_center, sidewalk_pos.pt(map))?);
+
+    let mut rng = XorShiftRng::seed_from_u64(orig_id.osm_way_id as u64);
+    let mut bldg = Building {
+        id: BuildingID(0),
+        polygon: b.polygon.clone(),
+        address: get_address(&b.osm_tags, sidewalk_pos.lane(), map),
+        name: b.osm_tags.get(osm::NAME).cloned(),
+        osm_way_id: orig_id.osm_way_id,
+        front_path: FrontPath {
+            sidewalk: sidewalk_pos,
+            line: sidewalk_line.clone(),
+        },
+        amenities: b.amenities.clone(),
+        parking: None,
+        label_center: b.polygon.polylabel(),
+        bldg_type: classify_bldg(
+            Tags::new(b.osm_tags.clone()),
+            &b.amenities,
+            b.polygon.area(),
+            &mut rng,
+        ),
+        levels: b
+            .osm_tags
+            .get("building:levels")
+            .and_then(|x| x.parse::<f64>().ok())
+            .filter(|x| *x > 0.0)
+            .unwrap_or(1.0),
+    };
+
+    // Can this building have a driveway? If it's not next to a driving lane, then no.
+    let sidewalk_lane = sidewalk_pos.lane();
+    if let Ok(driving_lane) = map
+        .get_parent(sidewalk_lane)
+        .find_closest_lane(sidewalk_lane, vec![LaneType::Driving])
+    {
+        let driving_pos = sidewalk_pos.equiv_pos(driving_lane, Distance::ZERO, map);
+
+        // This shouldn't fail much anymore, unless equiv_pos winds up being pretty different
+        if driving_pos.dist_along() > driveway_buffer
+            && map.get_l(driving_lane).length() - driving_pos.dist_along() > driveway_buffer
+        {
+            let driveway_line = PolyLine::must_new(vec![
+                sidewalk_line.pt1(),
+                sidewalk_line.pt2(),
+                driving_pos.pt(map),
+            ]);
+            bldg.parking = Some(OffstreetParking {
+                public_garage_name: b.public_garage_name.clone(),
+                num_spots: b.num_parking_spots,
+                driveway_line,
+                driving_pos,
+                policy: ParkingPolicy::from_area_tags(&b.osm_tags),
+            });
+        }
+    }
+    Some(bldg)
+}
+
 pub fn make_all_parking_lots(
     input: &Vec<RawParkingLot>,
     aisles: &Vec<Vec<Pt2D>>,
@@ -145,7 +180,6 @@ pub fn make_all_parking_lots(
     let sidewalk_buffer = Distance::meters(7.5);
     let driveway_buffer = Distance::meters(7.0);
     let sidewalk_pts = match_points_to_lanes(
-        map.get_bounds(),
         query,
         map.all_lanes(),
         |l| l.is_sidewalk(),
@@ -206,6 +240,8 @@ pub fn make_all_parking_lots(
                     driving_pos,
                     sidewalk_line,
                     sidewalk_pos: *sidewalk_pos,
+                    // TODO Keep the OSM tags for lots too
+                    policy: ParkingPolicy::free(),
                 });
             } else {
                 timer.warn(format!(
@@ -220,7 +256,7 @@ pub fn make_all_parking_lots(
         input.len() - results.len()
     ));
 
-    let mut closest: FindClosest<ParkingLotID> = FindClosest::new(map.get_bounds());
+    let mut closest: FindClosest<ParkingLotID> = FindClosest::new();
     for lot in &results {
         closest.add(lot.id, lot.polygon.points());
     }
@@ -282,7 +318,9 @@ fn trim_path(poly: &Polygon, path: Line) -> Line {
 }
 
 fn get_address(tags: &BTreeMap<String, String>, sidewalk: LaneID, map: &Map) -> String {
-    match (tags.get("addr:housenumber"), tags.get("addr:street")) {
+    // Some places, like squares or small villages, are addressed without a street
+    let street = tags.get("addr:street").or_else(|| tags.get("addr:place"));
+    match (tags.get("addr:housenumber"), street) {
         (Some(num), Some(st)) => format!("{} {}", num, st),
         (None, Some(st)) => format!("??? {}", st),
         _ => format!("??? {}", map.get_parent(sidewalk).get_name()),
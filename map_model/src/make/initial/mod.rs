@@ -94,6 +94,7 @@ impl InitialMap {
             );
         }
 
+        let mut roads = Vec::new();
         for (id, r) in &raw.roads {
             if id.i1 == id.i2 {
                 timer.warn(format!("Skipping loop {}", id));
@@ -101,9 +102,13 @@ impl InitialMap {
             }
             m.intersections.get_mut(&id.i1).unwrap().roads.insert(*id);
             m.intersections.get_mut(&id.i2).unwrap().roads.insert(*id);
-
-            m.roads
-                .insert(*id, Road::new(*id, r, raw.config.driving_side));
+            roads.push((*id, r));
+        }
+        let driving_side = raw.config.driving_side;
+        for road in timer.parallelize("initial road geometry", roads, |(id, r)| {
+            Road::new(id, r, driving_side)
+        }) {
+            m.roads.insert(road.id, road);
         }
 
         timer.start_iter("find each intersection polygon", m.intersections.len());
@@ -2,6 +2,7 @@ mod bridges;
 mod buildings;
 pub mod initial;
 mod remove_disconnected;
+mod slip_lanes;
 pub mod traffic_signals;
 mod transit;
 pub mod turns;
@@ -10,18 +11,19 @@ use crate::pathfind::Pathfinder;
 use crate::raw::{OriginalIntersection, OriginalRoad, RawMap};
 use crate::{
     connectivity, osm, Area, AreaID, ControlStopSign, ControlTrafficSignal, Intersection,
-    IntersectionID, IntersectionType, Lane, LaneID, Map, MapEdits, PathConstraints, Position, Road,
-    RoadID, Zone,
+    IntersectionID, IntersectionType, Lane, LaneID, Map, MapEdits, ParkingPolicy, PathConstraints,
+    Position, Road, RoadID, Zone,
 };
 use abstutil::Timer;
 use enumset::EnumSet;
-use geom::{Bounds, Distance, FindClosest, HashablePt2D, Polygon, Speed, EPSILON_DIST};
+use geom::{Distance, FindClosest, HashablePt2D, Polygon, Speed, EPSILON_DIST};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 impl Map {
     pub fn create_from_raw(mut raw: RawMap, build_ch: bool, timer: &mut Timer) -> Map {
         // Better to defer this and see RawMaps with more debug info in map_editor
         remove_disconnected::remove_disconnected_roads(&mut raw, timer);
+        slip_lanes::find_slip_lanes(&mut raw, timer);
 
         timer.start("raw_map to InitialMap");
         let gps_bounds = raw.gps_bounds.clone();
@@ -40,6 +42,8 @@ impl Map {
             areas: Vec::new(),
             parking_lots: Vec::new(),
             zones: Vec::new(),
+            closest_road: FindClosest::new(),
+            closest_bldg: FindClosest::new(),
             boundary_polygon: raw.boundary_polygon.clone(),
             stop_signs: BTreeMap::new(),
             traffic_signals: BTreeMap::new(),
@@ -131,15 +135,19 @@ impl Map {
                 src_i: i1,
                 dst_i: i2,
                 speed_limit: Speed::ZERO,
+                speed_limit_schedule: Vec::new(),
                 zorder: if let Some(layer) = raw.roads[&r.id].osm_tags.get("layer") {
                     layer.parse::<isize>().unwrap()
                 } else {
                     0
                 },
                 allow_through_traffic: EnumSet::new(),
+                allow_trucks: true,
+                bus_lane_camera: false,
             };
             road.speed_limit = road.speed_limit_from_osm();
             road.allow_through_traffic = road.access_restrictions_from_osm();
+            road.allow_trucks = road.trucks_allowed_from_osm();
 
             let mut total_back_width = Distance::ZERO;
             for lane in &r.lane_specs {
@@ -192,6 +200,10 @@ impl Map {
                     building_paths: Vec::new(),
                     bus_stops: BTreeSet::new(),
                     parking_blackhole: None,
+                    parking_policy: ParkingPolicy::from_road_tags(
+                        &road.osm_tags,
+                        !lane.reverse_pts,
+                    ),
                 });
             }
             if road.get_name() == "???" {
@@ -292,7 +304,8 @@ impl Map {
             });
         }
 
-        bridges::find_bridges(&mut map.roads, &map.bounds, timer);
+        bridges::find_bridges(&mut map.roads, timer);
+        map.index_geometry();
 
         let mut stop_signs: BTreeMap<IntersectionID, ControlStopSign> = BTreeMap::new();
         let mut traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal> = BTreeMap::new();
@@ -375,7 +388,6 @@ fn is_border(intersection: &Intersection, lanes: &Vec<Lane>) -> bool {
 // If the result doesn't contain a requested point, then there was no matching lane close
 // enough.
 fn match_points_to_lanes<F: Fn(&Lane) -> bool>(
-    bounds: &Bounds,
     pts: HashSet<HashablePt2D>,
     lanes: &Vec<Lane>,
     filter: F,
@@ -387,7 +399,7 @@ fn match_points_to_lanes<F: Fn(&Lane) -> bool>(
         return HashMap::new();
     }
 
-    let mut closest: FindClosest<LaneID> = FindClosest::new(bounds);
+    let mut closest: FindClosest<LaneID> = FindClosest::new();
     timer.start_iter("index lanes", lanes.len());
     for l in lanes {
         timer.next();
@@ -0,0 +1,243 @@
+use crate::osm;
+use crate::raw::{OriginalIntersection, OriginalRoad, RawMap};
+use abstutil::Timer;
+use geom::Distance;
+use std::collections::HashMap;
+
+// A slip lane (or channelized turn) lets vehicles turn between two roads without entering the
+// main junction. In OSM, it's a short one-way road cutting the corner, forming a triangle with
+// the two roads it connects. Mark these, so that turn generation and stop sign placement can
+// treat them specially.
+pub fn find_slip_lanes(raw: &mut RawMap, timer: &mut Timer) {
+    let mut roads_per_intersection: HashMap<OriginalIntersection, Vec<OriginalRoad>> =
+        HashMap::new();
+    for id in raw.roads.keys() {
+        roads_per_intersection
+            .entry(id.i1)
+            .or_insert_with(Vec::new)
+            .push(*id);
+        roads_per_intersection
+            .entry(id.i2)
+            .or_insert_with(Vec::new)
+            .push(*id);
+    }
+
+    let mut slip_lanes = Vec::new();
+    timer.start_iter("find slip lanes", raw.roads.len());
+    for id in raw.roads.keys() {
+        timer.next();
+        if is_candidate(raw, *id) && forms_triangle(*id, &roads_per_intersection) {
+            slip_lanes.push(*id);
+        }
+    }
+
+    timer.note(format!("Found {} slip lanes", slip_lanes.len()));
+    for id in slip_lanes {
+        raw.roads
+            .get_mut(&id)
+            .unwrap()
+            .osm_tags
+            .insert(osm::SLIP_LANE.to_string(), "yes".to_string());
+    }
+}
+
+fn is_candidate(raw: &RawMap, id: OriginalRoad) -> bool {
+    let road = &raw.roads[&id];
+    let spec = road.get_spec();
+    let fwd = spec.fwd.iter().any(|lt| lt.is_for_moving_vehicles());
+    let back = spec.back.iter().any(|lt| lt.is_for_moving_vehicles());
+    // Both directions, or no vehicles at all
+    if fwd == back {
+        return false;
+    }
+
+    // Links are explicitly tagged as connectors, so allow them to be a bit longer.
+    let max_length = if road
+        .osm_tags
+        .get(osm::HIGHWAY)
+        .map(|x| x.ends_with("_link"))
+        .unwrap_or(false)
+    {
+        Distance::meters(150.0)
+    } else {
+        Distance::meters(60.0)
+    };
+    let length: Distance = road
+        .center_points
+        .windows(2)
+        .map(|pair| pair[0].dist_to(pair[1]))
+        .sum();
+    length <= max_length
+}
+
+// Both endpoints of the slip lane must split from or merge into exactly one other road, and those
+// two roads must meet at a third, real junction.
+fn forms_triangle(
+    id: OriginalRoad,
+    roads_per_intersection: &HashMap<OriginalIntersection, Vec<OriginalRoad>>,
+) -> bool {
+    let at_i1 = &roads_per_intersection[&id.i1];
+    let at_i2 = &roads_per_intersection[&id.i2];
+    if at_i1.len() != 3 || at_i2.len() != 3 {
+        return false;
+    }
+    for leg1 in at_i1 {
+        if *leg1 == id {
+            continue;
+        }
+        let k = other_endpt(*leg1, id.i1);
+        if k == id.i2 || roads_per_intersection[&k].len() < 3 {
+            continue;
+        }
+        for leg2 in at_i2 {
+            if *leg2 != id && *leg2 != *leg1 && other_endpt(*leg2, id.i2) == k {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn other_endpt(id: OriginalRoad, i: OriginalIntersection) -> OriginalIntersection {
+    if id.i1 == i {
+        id.i2
+    } else {
+        id.i1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::RawIntersection;
+    use crate::IntersectionType;
+    use geom::Pt2D;
+    use std::collections::BTreeMap;
+
+    // A four-way junction at the origin, with a channelized right turn from the south arm onto the
+    // east arm. The south and east arms are each split where the slip lane leaves and rejoins, this
+    // far from the junction. The slip lane curves around the corner like a real one, so it's a bit
+    // more than 1.5 times longer than that.
+    fn channelized_junction(
+        slip_lane_tags: Vec<(&str, &str)>,
+        corner: f64,
+    ) -> (RawMap, OriginalRoad) {
+        let mut raw = RawMap::blank("test", "test");
+        let mut pts = BTreeMap::new();
+        for (osm_node_id, x, y) in vec![
+            (1, 0.0, 0.0),
+            (2, 0.0, 300.0),
+            (3, 0.0, corner),
+            (4, 300.0, 0.0),
+            (5, corner, 0.0),
+            (6, 0.0, -300.0),
+            (7, -300.0, 0.0),
+        ] {
+            let i = OriginalIntersection { osm_node_id };
+            pts.insert(i, Pt2D::new(x, y));
+            raw.intersections.insert(
+                i,
+                RawIntersection {
+                    point: Pt2D::new(x, y),
+                    intersection_type: IntersectionType::StopSign,
+                    elevation: Distance::ZERO,
+                },
+            );
+        }
+
+        let mut slip_lane = None;
+        for (osm_way_id, i1, i2) in vec![
+            // South arm, split where the slip lane leaves
+            (100, 2, 3),
+            (101, 3, 1),
+            // East arm, split where the slip lane rejoins
+            (102, 1, 5),
+            (103, 5, 4),
+            // North and west arms
+            (104, 1, 6),
+            (105, 1, 7),
+            // The slip lane
+            (106, 3, 5),
+        ] {
+            let id = OriginalRoad {
+                osm_way_id,
+                i1: OriginalIntersection { osm_node_id: i1 },
+                i2: OriginalIntersection { osm_node_id: i2 },
+            };
+            let mut osm_tags = BTreeMap::new();
+            let mut center_points = vec![pts[&id.i1], pts[&id.i2]];
+            if osm_way_id == 106 {
+                for (k, v) in &slip_lane_tags {
+                    osm_tags.insert(k.to_string(), v.to_string());
+                }
+                slip_lane = Some(id);
+                // A quarter circle centered at (corner, corner)
+                center_points = (0..=8)
+                    .map(|step| {
+                        let angle = (step as f64) * std::f64::consts::PI / 16.0;
+                        Pt2D::new(corner - corner * angle.cos(), corner - corner * angle.sin())
+                    })
+                    .collect();
+            } else {
+                osm_tags.insert(osm::HIGHWAY.to_string(), "primary".to_string());
+            }
+            raw.roads.insert(
+                id,
+                crate::raw::RawRoad {
+                    center_points,
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                },
+            );
+        }
+        (raw, slip_lane.unwrap())
+    }
+
+    #[test]
+    fn test_detect_slip_lane() {
+        let (mut raw, slip_lane) = channelized_junction(
+            vec![(osm::HIGHWAY, "primary_link"), ("oneway", "yes")],
+            30.0,
+        );
+        find_slip_lanes(&mut raw, &mut Timer::throwaway());
+        for (id, road) in &raw.roads {
+            assert_eq!(
+                road.osm_tags.contains_key(osm::SLIP_LANE),
+                *id == slip_lane,
+                "{}",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn test_two_way_connector_isnt_slip_lane() {
+        let (mut raw, slip_lane) = channelized_junction(vec![(osm::HIGHWAY, "residential")], 30.0);
+        find_slip_lanes(&mut raw, &mut Timer::throwaway());
+        assert!(!raw.roads[&slip_lane].osm_tags.contains_key(osm::SLIP_LANE));
+    }
+
+    #[test]
+    fn test_link_tag_allows_longer_slip_lanes() {
+        // (highway tag, where the slip lane splits off, whether it's detected). The slip lane is
+        // about 63m, 94m, and 157m long.
+        for (highway, corner, expected) in vec![
+            ("primary", 40.0, false),
+            ("primary_link", 40.0, true),
+            ("primary_link", 60.0, true),
+            ("primary_link", 100.0, false),
+        ] {
+            let (mut raw, slip_lane) =
+                channelized_junction(vec![(osm::HIGHWAY, highway), ("oneway", "yes")], corner);
+            find_slip_lanes(&mut raw, &mut Timer::throwaway());
+            assert_eq!(
+                raw.roads[&slip_lane].osm_tags.contains_key(osm::SLIP_LANE),
+                expected,
+                "highway={} splitting off {}m from the junction",
+                highway,
+                corner
+            );
+        }
+    }
+}
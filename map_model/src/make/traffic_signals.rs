@@ -97,6 +97,9 @@ fn greedy_assignment(map: &Map, intersection: IntersectionID) -> ControlTrafficS
         id: intersection,
         phases,
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups,
     };
     // This must succeed
@@ -119,6 +122,9 @@ fn degenerate(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
         id: i,
         phases,
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups: TurnGroup::for_i(i, map),
     };
     ts.validate().ok()
@@ -164,6 +170,9 @@ fn three_way(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
         id: i,
         phases,
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups,
     };
     ts.validate().ok()
@@ -205,6 +214,9 @@ fn four_way_four_phase(map: &Map, i: IntersectionID) -> Option<ControlTrafficSig
         id: i,
         phases,
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups: TurnGroup::for_i(i, map),
     };
     ts.validate().ok()
@@ -245,6 +257,9 @@ fn four_way_two_phase(map: &Map, i: IntersectionID) -> Option<ControlTrafficSign
         id: i,
         phases,
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups: TurnGroup::for_i(i, map),
     };
     ts.validate().ok()
@@ -296,6 +311,9 @@ fn four_oneways(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
         id: i,
         phases,
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups: TurnGroup::for_i(i, map),
     };
     ts.validate().ok()
@@ -322,6 +340,9 @@ fn all_walk_all_yield(map: &Map, i: IntersectionID) -> ControlTrafficSignal {
         id: i,
         phases: vec![all_walk, all_yield],
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups,
     };
     // This must succeed
@@ -359,6 +380,9 @@ fn phase_per_road(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal>
         id: i,
         phases,
         offset: Duration::ZERO,
+        leading_pedestrian_interval: Duration::ZERO,
+        right_turn_on_red: map.config.right_turn_on_red,
+        transit_signal_priority: false,
         turn_groups,
     };
     ts.validate().ok()
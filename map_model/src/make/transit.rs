@@ -50,6 +50,10 @@ fn make_route(
             Ok((sidewalk_pos, driving_pos)) => {
                 // Create a new bus stop if needed.
                 let stop_id = if let Some(id) = pt_to_stop.get(&(sidewalk_pos, driving_pos)) {
+                    // Different routes might disagree about the shelter
+                    if stop.shelter {
+                        map.bus_stops.get_mut(id).unwrap().has_shelter = true;
+                    }
                     *id
                 } else {
                     let id = BusStopID {
@@ -66,6 +70,8 @@ fn make_route(
                             driving_pos,
                             sidewalk_pos,
                             is_train_stop: !r.is_bus,
+                            has_shelter: stop.shelter,
+                            is_terminal: false,
                         },
                     );
                     id
@@ -155,6 +161,18 @@ fn make_route(
         }
     }
 
+    // Routes that continue past a border don't lay over at their first or last stop. That's still
+    // true when the border has no lane to start or end on, so check the raw route, not the lanes.
+    if r.border_start.is_none() {
+        map.bus_stops.get_mut(&route.stops[0]).unwrap().is_terminal = true;
+    }
+    if r.border_end.is_none() {
+        map.bus_stops
+            .get_mut(route.stops.last().unwrap())
+            .unwrap()
+            .is_terminal = true;
+    }
+
     map.bus_routes.push(route);
     Ok(())
 }
@@ -185,7 +203,6 @@ impl Matcher {
             }
         }
         let sidewalk_pts = match_points_to_lanes(
-            map.get_bounds(),
             lookup_sidewalk_pts,
             map.all_lanes(),
             |l| l.is_sidewalk(),
@@ -195,7 +212,6 @@ impl Matcher {
             timer,
         );
         let bus_pts = match_points_to_lanes(
-            map.get_bounds(),
             lookup_bus_pts,
             map.all_lanes(),
             |l| l.is_bus() || l.is_driving(),
@@ -205,7 +221,6 @@ impl Matcher {
             timer,
         );
         let light_rail_pts = match_points_to_lanes(
-            map.get_bounds(),
             lookup_light_rail_pts,
             map.all_lanes(),
             |l| l.lane_type == LaneType::LightRail,
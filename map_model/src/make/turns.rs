@@ -26,6 +26,9 @@ pub fn make_all_turns(
     let mut final_turns: Vec<Turn> = Vec::new();
     let mut filtered_turns: HashMap<LaneID, Vec<Turn>> = HashMap::new();
     for turn in unique_turns {
+        if is_slip_lane_u_turn(&turn, roads, lanes) {
+            continue;
+        }
         if !does_turn_pass_restrictions(&turn, &i.roads, roads, lanes) {
             continue;
         }
@@ -751,3 +754,32 @@ fn does_turn_pass_restrictions(
 
     true
 }
+
+// A slip lane cuts the corner between two roads. Going between the slip lane and either of those
+// roads at its ends means doubling back along the triangle; nobody does that.
+fn is_slip_lane_u_turn(turn: &Turn, roads: &Vec<Road>, lanes: &Vec<Lane>) -> bool {
+    if turn.between_sidewalks() {
+        return false;
+    }
+
+    let src = &roads[lanes[turn.id.src.0].parent.0];
+    let dst = &roads[lanes[turn.id.dst.0].parent.0];
+    let (slip_lane, other) = if src.is_slip_lane() {
+        (src, dst)
+    } else if dst.is_slip_lane() {
+        (dst, src)
+    } else {
+        return false;
+    };
+
+    let i = turn.id.parent;
+    let other_endpt = |r: &Road| if r.src_i == i { r.dst_i } else { r.src_i };
+    let j = other_endpt(slip_lane);
+    let k = other_endpt(other);
+    // Slip lanes are rare, so just search every road for the third side of the triangle.
+    roads.iter().any(|r| {
+        r.id != slip_lane.id
+            && r.id != other.id
+            && ((r.src_i == j && r.dst_i == k) || (r.src_i == k && r.dst_i == j))
+    })
+}
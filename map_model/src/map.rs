@@ -1,12 +1,12 @@
 use crate::raw::{DrivingSide, RawMap};
 use crate::{
-    Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop, BusStopID, ControlStopSign,
-    ControlTrafficSignal, Intersection, IntersectionID, Lane, LaneID, LaneType, Map, MapEdits,
-    ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest, Position, Road, RoadID, Turn,
-    TurnGroupID, TurnID, TurnType,
+    osm, Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop, BusStopID,
+    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, Lane, LaneID, LaneType,
+    Map, MapEdits, ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest, Position, Road,
+    RoadID, Turn, TurnGroupID, TurnID, TurnType,
 };
 use abstutil::Timer;
-use geom::{Angle, Bounds, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D};
+use geom::{Angle, Bounds, Distance, FindClosest, GPSBounds, Line, PolyLine, Polygon, Pt2D};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
@@ -16,6 +16,8 @@ pub struct MapConfig {
     // (Australia).
     pub driving_side: DrivingSide,
     pub bikes_can_use_bus_lanes: bool,
+    // The default for traffic signals. When driving on the left, this is about left turns.
+    pub right_turn_on_red: bool,
 }
 
 impl Map {
@@ -23,7 +25,8 @@ impl Map {
         if path.starts_with(&abstutil::path_all_maps()) {
             match abstutil::maybe_read_binary(path.clone(), timer) {
                 Ok(map) => {
-                    let map: Map = map;
+                    let mut map: Map = map;
+                    map.index_geometry();
 
                     if false {
                         use abstutil::{prettyprint_usize, serialized_size_bytes};
@@ -126,6 +129,8 @@ impl Map {
             areas: Vec::new(),
             parking_lots: Vec::new(),
             zones: Vec::new(),
+            closest_road: FindClosest::new(),
+            closest_bldg: FindClosest::new(),
             boundary_polygon: Polygon::new(&vec![
                 Pt2D::new(0.0, 0.0),
                 Pt2D::new(1.0, 0.0),
@@ -138,6 +143,7 @@ impl Map {
             config: MapConfig {
                 driving_side: DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                right_turn_on_red: false,
             },
             pathfinder: None,
             pathfinder_dirty: false,
@@ -207,6 +213,10 @@ impl Map {
         self.bus_stops.get(&id)
     }
 
+    pub fn maybe_get_br(&self, route: BusRouteID) -> Option<&BusRoute> {
+        self.bus_routes.get(route.0)
+    }
+
     pub fn maybe_get_stop_sign(&self, id: IntersectionID) -> Option<&ControlStopSign> {
         self.stop_signs.get(&id)
     }
@@ -564,6 +574,48 @@ impl Map {
         self.pathfinder.as_ref().unwrap().pathfind(req, self)
     }
 
+    // Only for vehicles, and much slower than pathfind. Also ignores private zones.
+    pub fn pathfind_avoiding(&self, req: PathRequest, avoid: &BTreeSet<RoadID>) -> Option<Path> {
+        crate::pathfind::pathfind_avoiding(&req, avoid, self)
+    }
+
+    // Stays off roads that don't allow trucks, except for the first and last roads.
+    pub fn pathfind_for_truck(&self, req: PathRequest) -> Option<Path> {
+        let banned = self.roads_banning_trucks();
+        if banned.is_empty() {
+            return self.pathfind(req);
+        }
+        self.pathfind_avoiding(req, &banned)
+    }
+
+    // Like pathfind_for_truck, but much faster for lots of trucks starting from the same few
+    // places, like borders.
+    pub fn pathfind_many_for_truck(&self, reqs: Vec<PathRequest>) -> Vec<Option<Path>> {
+        let banned = self.roads_banning_trucks();
+        if banned.is_empty() {
+            return reqs.into_iter().map(|req| self.pathfind(req)).collect();
+        }
+        self.pathfind_many_avoiding(reqs, &banned)
+    }
+
+    // Like pathfind_avoiding, but requests starting from the same lane share one search. The
+    // avoided roads are never passed through, but paths can end on one.
+    pub fn pathfind_many_avoiding(
+        &self,
+        reqs: Vec<PathRequest>,
+        avoid: &BTreeSet<RoadID>,
+    ) -> Vec<Option<Path>> {
+        crate::pathfind::pathfind_many_avoiding(reqs, avoid, self)
+    }
+
+    fn roads_banning_trucks(&self) -> BTreeSet<RoadID> {
+        self.roads
+            .iter()
+            .filter(|r| !r.allow_trucks)
+            .map(|r| r.id)
+            .collect()
+    }
+
     pub fn should_use_transit(
         &self,
         start: Position,
@@ -629,6 +681,86 @@ impl Map {
         None
     }
 
+    // Roads and buildings whose OSM name contains the query, ignoring case. Buildings also match
+    // by address and by the name or kind of any amenity inside, like "cafe".
+    pub fn search_by_name(&self, query: &str) -> (Vec<RoadID>, Vec<BuildingID>) {
+        let query = query.to_lowercase();
+        let matches = |name: Option<&String>| {
+            name.map(|n| n.to_lowercase().contains(&query))
+                .unwrap_or(false)
+        };
+        let roads = self
+            .roads
+            .iter()
+            .filter(|r| matches(r.osm_tags.get(osm::NAME)))
+            .map(|r| r.id)
+            .collect();
+        let bldgs = self
+            .buildings
+            .iter()
+            .filter(|b| {
+                matches(b.name.as_ref())
+                    || matches(Some(&b.address))
+                    || b.amenities
+                        .iter()
+                        .any(|(name, amenity)| matches(Some(name)) || matches(Some(amenity)))
+            })
+            .map(|b| b.id)
+            .collect();
+        (roads, bldgs)
+    }
+
+    pub(crate) fn index_geometry(&mut self) {
+        let mut closest_road = FindClosest::new();
+        for r in &self.roads {
+            closest_road.add(r.id, r.center_pts.points());
+        }
+        let mut closest_bldg = FindClosest::new();
+        for b in &self.buildings {
+            closest_bldg.add(b.id, b.polygon.points());
+        }
+        self.closest_road = closest_road;
+        self.closest_bldg = closest_bldg;
+    }
+
+    // Re-index a road whose geometry changed
+    pub(crate) fn reindex_road(&mut self, r: RoadID) {
+        let pts = self.get_r(r).center_pts.points().clone();
+        self.closest_road.add(r, &pts);
+    }
+
+    pub fn closest_road(&self, pt: Pt2D, max_dist_away: Distance) -> Option<(RoadID, Pt2D)> {
+        self.closest_road.closest_pt(pt, max_dist_away)
+    }
+
+    pub fn all_close_roads(
+        &self,
+        pt: Pt2D,
+        max_dist_away: Distance,
+    ) -> Vec<(RoadID, Pt2D, Distance)> {
+        self.closest_road.all_close_pts(pt, max_dist_away)
+    }
+
+    // Roads whose center-line is within the bounds. Since only center-lines are indexed, the query
+    // is padded so that roads just off the edge but with wide lanes poking in are included too.
+    pub fn roads_in_bounds(&self, bounds: &Bounds) -> Vec<RoadID> {
+        let pad = 20.0;
+        self.closest_road.all_in_bounds(&Bounds {
+            min_x: bounds.min_x - pad,
+            min_y: bounds.min_y - pad,
+            max_x: bounds.max_x + pad,
+            max_y: bounds.max_y + pad,
+        })
+    }
+
+    pub fn closest_building(
+        &self,
+        pt: Pt2D,
+        max_dist_away: Distance,
+    ) -> Option<(BuildingID, Pt2D)> {
+        self.closest_bldg.closest_pt(pt, max_dist_away)
+    }
+
     pub fn right_shift(&self, pl: PolyLine, width: Distance) -> PolyLine {
         self.config.driving_side.right_shift(pl, width)
     }
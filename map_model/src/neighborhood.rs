@@ -0,0 +1,84 @@
+use crate::{BuildingID, Map};
+use abstutil::Timer;
+use geom::{LonLat, Polygon};
+use serde::{Deserialize, Serialize};
+
+// A named region of a map, like "Ballard" or "Capitol Hill". These are either imported from
+// Osmosis polygons or drawn by hand, and live alongside the map, not in it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Neighborhood {
+    pub name: String,
+    pub map_name: String,
+    // Stored in GPS, so the definition survives the map being regenerated with slightly different
+    // bounds. The first point isn't repeated at the end.
+    pub points: Vec<LonLat>,
+}
+
+impl Neighborhood {
+    pub fn new(name: String, map_name: String, mut points: Vec<LonLat>) -> Neighborhood {
+        if points.len() > 1 && points[0] == *points.last().unwrap() {
+            points.pop();
+        }
+        Neighborhood {
+            name,
+            map_name,
+            points,
+        }
+    }
+
+    pub fn load(map_name: &str, name: &str) -> Result<Neighborhood, std::io::Error> {
+        abstutil::maybe_read_json(
+            abstutil::path_neighborhood(map_name, name),
+            &mut Timer::throwaway(),
+        )
+    }
+
+    // Sorted by name
+    pub fn load_all(map_name: &str) -> Vec<Neighborhood> {
+        abstutil::load_all_objects(abstutil::path_all_neighborhoods(map_name))
+            .into_iter()
+            .map(|(_, n)| n)
+            .collect()
+    }
+
+    pub fn save(&self) {
+        assert!(self.points.len() >= 3);
+        abstutil::write_json(
+            abstutil::path_neighborhood(&self.map_name, &self.name),
+            self,
+        );
+    }
+
+    pub fn delete(&self) -> Result<(), std::io::Error> {
+        std::fs::remove_file(abstutil::path_neighborhood(&self.map_name, &self.name))
+    }
+
+    // Clipped to the map's boundary. None if the neighborhood is completely off the map. If the
+    // boundary cuts it into pieces, only the biggest is kept.
+    //
+    // This is slow for complicated boundaries, so hang onto the result instead of calling it for
+    // every point.
+    pub fn polygon(&self, map: &Map) -> Option<Polygon> {
+        if self.points.len() < 3 {
+            return None;
+        }
+        Polygon::new(&map.get_gps_bounds().convert(&self.points))
+            .intersection(map.get_boundary_polygon())
+            .into_iter()
+            .max_by(|a, b| a.area().partial_cmp(&b.area()).unwrap())
+    }
+
+    // Every building whose center is inside the neighborhood
+    pub fn buildings(&self, map: &Map) -> Vec<BuildingID> {
+        let poly = if let Some(poly) = self.polygon(map) {
+            poly
+        } else {
+            return Vec::new();
+        };
+        map.all_buildings()
+            .iter()
+            .filter(|b| poly.contains_pt(b.polygon.center()))
+            .map(|b| b.id)
+            .collect()
+    }
+}
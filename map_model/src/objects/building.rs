@@ -1,4 +1,4 @@
-use crate::{LaneID, Position};
+use crate::{LaneID, ParkingPolicy, Position};
 use abstutil::{deserialize_usize, serialize_usize};
 use geom::{Line, PolyLine, Polygon, Pt2D};
 use serde::{Deserialize, Serialize};
@@ -38,6 +38,7 @@ pub struct OffstreetParking {
     // Guaranteed to be at least 7m (MAX_CAR_LENGTH + a little buffer) away from both ends of the
     // lane, to prevent various headaches
     pub driving_pos: Position,
+    pub policy: ParkingPolicy,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -59,6 +60,8 @@ pub struct Building {
     // a parking blackhole) might be far away
     pub parking: Option<OffstreetParking>,
     pub bldg_type: BuildingType,
+    // From building:levels, or 1 if it's not tagged
+    pub levels: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -83,4 +86,65 @@ impl Building {
             None
         }
     }
+
+    pub fn has_amenity(&self, category: AmenityType) -> bool {
+        self.amenities
+            .iter()
+            .any(|(_, a)| AmenityType::categorize(a) == Some(category))
+    }
+}
+
+// Broad groups of the amenity and shop values from OSM
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AmenityType {
+    Groceries,
+    Food,
+    Bar,
+    Medical,
+    Religious,
+    Education,
+    Financial,
+    Culture,
+    Childcare,
+    Shopping,
+}
+
+impl AmenityType {
+    pub fn categorize(a: &str) -> Option<AmenityType> {
+        match a {
+            "supermarket" | "convenience" => Some(AmenityType::Groceries),
+            "restaurant" | "cafe" | "fast_food" | "food_court" | "ice_cream" | "pastry"
+            | "deli" => Some(AmenityType::Food),
+            "pub" | "bar" | "nightclub" | "lounge" => Some(AmenityType::Bar),
+            "doctors" | "dentist" | "clinic" | "hospital" | "pharmacy" | "chiropractor" => {
+                Some(AmenityType::Medical)
+            }
+            "place_of_worship" => Some(AmenityType::Religious),
+            "college" | "school" | "kindergarten" | "university" => Some(AmenityType::Education),
+            "bank" | "post_office" | "atm" => Some(AmenityType::Financial),
+            "theatre" | "arts_centre" | "library" | "cinema" | "art_gallery" => {
+                Some(AmenityType::Culture)
+            }
+            "childcare" => Some(AmenityType::Childcare),
+            "second_hand" | "clothes" | "furniture" | "shoes" | "department_store" => {
+                Some(AmenityType::Shopping)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            AmenityType::Groceries => "groceries",
+            AmenityType::Food => "food",
+            AmenityType::Bar => "bar",
+            AmenityType::Medical => "medical",
+            AmenityType::Religious => "church / temple",
+            AmenityType::Education => "education",
+            AmenityType::Financial => "bank / post office",
+            AmenityType::Culture => "media",
+            AmenityType::Childcare => "childcare",
+            AmenityType::Shopping => "shopping",
+        }
+    }
 }
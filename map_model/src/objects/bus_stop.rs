@@ -41,6 +41,10 @@ pub struct BusStop {
     pub sidewalk_pos: Position,
     // If it's both, train overrides bus
     pub is_train_stop: bool,
+    pub has_shelter: bool,
+    // The first or last stop of some route that doesn't continue past the map boundary. Vehicles
+    // wait here between runs.
+    pub is_terminal: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -4,9 +4,9 @@ use crate::{
     TurnType,
 };
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::{Distance, Line, PolyLine, Pt2D};
+use geom::{Distance, Duration, Line, PolyLine, Pt2D};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 // Bit longer than the longest car.
@@ -115,6 +115,8 @@ pub struct Lane {
     // If set, cars trying to park near here should actually start their search at this other lane.
     // Only populated for driving lanes inevitably leading to borders.
     pub parking_blackhole: Option<LaneID>,
+    // Only meaningful for parking lanes
+    pub parking_policy: ParkingPolicy,
 }
 
 impl Lane {
@@ -181,6 +183,11 @@ impl Lane {
         }
     }
 
+    pub(crate) fn parking_policy_from_osm(&self, map: &Map) -> ParkingPolicy {
+        let r = map.get_r(self.parent);
+        ParkingPolicy::from_road_tags(&r.osm_tags, r.dir_and_offset(self.id).0)
+    }
+
     pub fn is_driving(&self) -> bool {
         self.lane_type == LaneType::Driving
     }
@@ -282,3 +289,81 @@ impl Lane {
             })
     }
 }
+
+// What it costs to park somewhere, and for how long it's allowed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ParkingPolicy {
+    // In cents. 0 means free.
+    pub hourly_price: usize,
+    pub max_stay: Option<Duration>,
+}
+
+impl ParkingPolicy {
+    pub fn free() -> ParkingPolicy {
+        ParkingPolicy {
+            hourly_price: 0,
+            max_stay: None,
+        }
+    }
+
+    // In cents, prorated
+    pub fn cost(&self, stay: Duration) -> usize {
+        ((self.hourly_price as f64) * stay.inner_seconds() / 3600.0).round() as usize
+    }
+
+    pub fn describe(&self) -> String {
+        let price = if self.hourly_price == 0 {
+            "free".to_string()
+        } else {
+            format!("{}/hour", abstutil::prettyprint_cents(self.hourly_price))
+        };
+        if let Some(dt) = self.max_stay {
+            format!("{}, {} max", price, dt)
+        } else {
+            price
+        }
+    }
+
+    // https://wiki.openstreetmap.org/wiki/Key:parking:condition for one side of a road, falling
+    // back to what's tagged for both sides
+    pub(crate) fn from_road_tags(tags: &BTreeMap<String, String>, fwd: bool) -> ParkingPolicy {
+        let side = if fwd { "right" } else { "left" };
+        let get = |key: &str| {
+            tags.get(&format!("parking:condition:{}:{}", side, key))
+                .or_else(|| tags.get(&format!("parking:condition:both:{}", key)))
+        };
+        ParkingPolicy::parse(get("charge"), get("maxstay"))
+    }
+
+    // For garages and lots, tagged like any other amenity
+    pub(crate) fn from_area_tags(tags: &BTreeMap<String, String>) -> ParkingPolicy {
+        ParkingPolicy::parse(tags.get("charge"), tags.get("maxstay"))
+    }
+
+    // charge looks like "2 USD/hour"; other currencies are treated like dollars, and flat fees are
+    // ignored. maxstay looks like "2 hours" or "30 minutes".
+    fn parse(charge: Option<&String>, maxstay: Option<&String>) -> ParkingPolicy {
+        let mut policy = ParkingPolicy::free();
+        if let Some(charge) = charge {
+            let parts: Vec<&str> = charge.split_whitespace().collect();
+            if parts.len() == 2 && (parts[1].ends_with("/hour") || parts[1].ends_with("/h")) {
+                if let Ok(dollars) = parts[0].parse::<f64>() {
+                    policy.hourly_price = (dollars * 100.0).round() as usize;
+                }
+            }
+        }
+        if let Some(maxstay) = maxstay {
+            let parts: Vec<&str> = maxstay.split_whitespace().collect();
+            if parts.len() == 2 {
+                if let Ok(n) = parts[0].parse::<f64>() {
+                    if parts[1].starts_with('h') {
+                        policy.max_stay = Some(Duration::seconds(n * 3600.0));
+                    } else if parts[1].starts_with("min") {
+                        policy.max_stay = Some(Duration::seconds(n * 60.0));
+                    }
+                }
+            }
+        }
+        policy
+    }
+}
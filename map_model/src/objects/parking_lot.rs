@@ -1,4 +1,4 @@
-use crate::Position;
+use crate::{ParkingPolicy, Position};
 use abstutil::{deserialize_usize, serialize_usize};
 use geom::{Angle, Line, PolyLine, Polygon, Pt2D};
 use serde::{Deserialize, Serialize};
@@ -46,4 +46,6 @@ pub struct ParkingLot {
     // Lot to sidewalk
     pub sidewalk_line: Line,
     pub sidewalk_pos: Position,
+
+    pub policy: ParkingPolicy,
 }
@@ -2,11 +2,14 @@ use crate::raw::{OriginalRoad, RestrictionType};
 use crate::{osm, BusStopID, IntersectionID, LaneID, LaneType, Map, PathConstraints, Zone};
 use abstutil::{deserialize_usize, serialize_usize};
 use enumset::EnumSet;
-use geom::{Distance, PolyLine, Polygon, Speed};
+use geom::{Distance, Duration, PolyLine, Polygon, Speed, Time, UnitFmt};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 
+// A maxweight below this keeps trucks off a road
+const MIN_TRUCK_WEIGHT_TONNES: f64 = 10.0;
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct RoadID(
     #[serde(
@@ -99,7 +102,14 @@ pub struct Road {
     pub complicated_turn_restrictions: Vec<(RoadID, RoadID)>,
     pub orig_id: OriginalRoad,
     pub speed_limit: Speed,
+    // Overrides speed_limit during parts of every day. The first period containing the current
+    // time wins.
+    pub speed_limit_schedule: Vec<SpeedLimitPeriod>,
     pub allow_through_traffic: EnumSet<PathConstraints>,
+    // Freight routing avoids roads without this, except to start or finish there.
+    pub allow_trucks: bool,
+    // Drivers who'd otherwise cut through the bus lanes here stay out of them.
+    pub bus_lane_camera: bool,
     pub zorder: isize,
 
     // Invariant: A road must contain at least one child
@@ -117,6 +127,48 @@ pub struct Road {
 
 type HomogenousTuple2<T> = (T, T);
 
+// Like a school zone or a lower limit at night. If end is before start, the period wraps around
+// midnight.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SpeedLimitPeriod {
+    pub start: Time,
+    pub end: Time,
+    pub limit: Speed,
+}
+
+impl SpeedLimitPeriod {
+    // A period that starts and ends at the same time is ambiguous; it could mean never or all day.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start == self.end {
+            return Err(format!(
+                "Speed limit period starts and ends at {}",
+                self.start.ampm_tostring()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, time: Time) -> bool {
+        // Only the time of day matters, even when the simulation runs for several days
+        let t = Time::START_OF_DAY
+            + Duration::seconds(time.inner_seconds() % Duration::hours(24).inner_seconds());
+        if self.start <= self.end {
+            t >= self.start && t < self.end
+        } else {
+            t >= self.start || t < self.end
+        }
+    }
+
+    pub fn describe(&self, units: &UnitFmt) -> String {
+        format!(
+            "{} from {} to {}",
+            self.limit.describe(units),
+            self.start.ampm_tostring(),
+            self.end.ampm_tostring()
+        )
+    }
+}
+
 impl Road {
     pub fn children(&self, fwds: bool) -> &Vec<(LaneID, LaneType)> {
         if fwds {
@@ -194,6 +246,14 @@ impl Road {
             .map(|(id, _)| *id)
     }
 
+    pub fn speed_limit_at(&self, time: Time) -> Speed {
+        self.speed_limit_schedule
+            .iter()
+            .find(|p| p.contains(time))
+            .map(|p| p.limit)
+            .unwrap_or(self.speed_limit)
+    }
+
     pub(crate) fn speed_limit_from_osm(&self) -> Speed {
         if let Some(limit) = self.osm_tags.get(osm::MAXSPEED) {
             // TODO handle other units
@@ -204,12 +264,13 @@ impl Road {
             }
         }
 
-        if self.osm_tags.get(osm::HIGHWAY) == Some(&"primary".to_string())
-            || self.osm_tags.get(osm::HIGHWAY) == Some(&"secondary".to_string())
-        {
-            return Speed::miles_per_hour(40.0);
+        match self.osm_tags.get(osm::HIGHWAY).map(|x| x.as_str()) {
+            Some("motorway") => Speed::miles_per_hour(60.0),
+            Some("trunk") => Speed::miles_per_hour(50.0),
+            Some("motorway_link") | Some("trunk_link") => Speed::miles_per_hour(35.0),
+            Some("primary") | Some("secondary") => Speed::miles_per_hour(40.0),
+            _ => Speed::miles_per_hour(20.0),
         }
-        Speed::miles_per_hour(20.0)
     }
 
     pub fn incoming_lanes(&self, i: IntersectionID) -> &Vec<(LaneID, LaneType)> {
@@ -411,10 +472,36 @@ impl Road {
         }
     }
 
+    pub fn is_slip_lane(&self) -> bool {
+        self.osm_tags.contains_key(osm::SLIP_LANE)
+    }
+
+    // Freeways and their ramps, where only vehicles with an engine are allowed
+    pub fn is_highway(&self) -> bool {
+        match self.osm_tags.get(osm::HIGHWAY).map(|x| x.as_str()) {
+            Some("motorway") | Some("motorway_link") => true,
+            _ => false,
+        }
+    }
+
+    // An on- or off-ramp, connecting a freeway to the rest of the network
+    pub fn is_ramp(&self) -> bool {
+        self.osm_tags.get(osm::HIGHWAY) == Some(&"motorway_link".to_string())
+    }
+
     pub fn is_private(&self) -> bool {
         self.allow_through_traffic != EnumSet::all()
     }
 
+    // What kind of crosswalk crosses this road at one end, if OSM says
+    pub fn crossing_type(&self, i: IntersectionID) -> Option<&String> {
+        if i == self.dst_i {
+            self.osm_tags.get(osm::CROSSING_FWD)
+        } else {
+            self.osm_tags.get(osm::CROSSING_BACK)
+        }
+    }
+
     pub(crate) fn access_restrictions_from_osm(&self) -> EnumSet<PathConstraints> {
         if self.osm_tags.get("access") == Some(&"private".to_string()) {
             EnumSet::new()
@@ -423,6 +510,22 @@ impl Road {
         }
     }
 
+    pub(crate) fn trucks_allowed_from_osm(&self) -> bool {
+        match self.osm_tags.get("hgv").map(|x| x.as_str()) {
+            Some("no") | Some("destination") | Some("delivery") => {
+                return false;
+            }
+            _ => {}
+        }
+        // Something like "7.5" or "3.5 t". Other units are rare, so just ignore them.
+        if let Some(weight) = self.osm_tags.get("maxweight") {
+            if let Some(Ok(tonnes)) = weight.split(' ').next().map(|x| x.parse::<f64>()) {
+                return tonnes >= MIN_TRUCK_WEIGHT_TONNES;
+            }
+        }
+        true
+    }
+
     pub fn get_zone<'a>(&self, map: &'a Map) -> Option<&'a Zone> {
         if !self.is_private() {
             return None;
@@ -73,6 +73,32 @@ impl ControlStopSign {
             }
         }
 
+        // Vehicles coming off a slip lane yield when they merge; nobody else needs to stop.
+        if map
+            .get_i(id)
+            .roads
+            .iter()
+            .any(|r| map.get_r(*r).is_slip_lane())
+        {
+            for (r, cfg) in ss.roads.iter_mut() {
+                cfg.must_stop = map.get_r(*r).is_slip_lane();
+            }
+            return ss;
+        }
+
+        // On-ramps merge into the freeway, so they yield to it.
+        let roads = &map.get_i(id).roads;
+        if ss.roads.keys().any(|r| map.get_r(*r).is_ramp())
+            && roads
+                .iter()
+                .any(|r| map.get_r(*r).is_highway() && !map.get_r(*r).is_ramp())
+        {
+            for (r, cfg) in ss.roads.iter_mut() {
+                cfg.must_stop = map.get_r(*r).is_ramp();
+            }
+            return ss;
+        }
+
         if ss.roads.len() <= 2 {
             // Degenerate roads and deadends don't need any stop signs.
             return ss;
@@ -1,17 +1,29 @@
 use crate::make::traffic_signals::{brute_force, get_possible_policies};
+use crate::raw::DrivingSide;
 use crate::{
     DirectedRoadID, IntersectionID, Map, TurnGroup, TurnGroupID, TurnID, TurnPriority, TurnType,
 };
 use abstutil::{deserialize_btreemap, retain_btreeset, serialize_btreemap, Timer};
-use geom::Duration;
+use geom::{Duration, Speed};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
+// The flashing don't walk interval is timed so that somebody this slow who steps off the curb just
+// before it starts can still make it across.
+const PEDESTRIAN_CLEARANCE_SPEED: Speed = Speed::const_meters_per_second(1.07);
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ControlTrafficSignal {
     pub id: IntersectionID,
     pub phases: Vec<Phase>,
     pub offset: Duration,
+    // At the start of every phase, crosswalks get a head start on the turns yielding to them.
+    pub leading_pedestrian_interval: Duration,
+    // After stopping, vehicles can make the turn toward the curb while it's banned, if there's a
+    // gap in everything with the green.
+    pub right_turn_on_red: bool,
+    // Buses can ask for the green to be held a bit longer or to come early.
+    pub transit_signal_priority: bool,
 
     #[serde(
         serialize_with = "serialize_btreemap",
@@ -36,6 +48,17 @@ pub enum PhaseType {
     Adaptive(Duration),
 }
 
+// How one phase is split up for people crossing the street, measured from the start of the phase.
+// People can only step off the curb during the walk interval. During the flashing don't walk
+// interval, people already in the crosswalk finish crossing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PedestrianIntervals {
+    // Vehicles yielding to crosswalks wait during the first part of the walk interval.
+    pub leading: Duration,
+    pub walk: Duration,
+    pub flashing_dont_walk: Duration,
+}
+
 impl PhaseType {
     // TODO Maybe don't have this; force callers to acknowledge different policies
     pub fn simple_duration(&self) -> Duration {
@@ -112,6 +135,17 @@ impl ControlTrafficSignal {
         Ok(self)
     }
 
+    pub fn can_turn_on_red(&self, t: TurnID, map: &Map) -> bool {
+        if !self.right_turn_on_red {
+            return false;
+        }
+        let turn_type = map.get_t(t).turn_type;
+        match map.get_driving_side() {
+            DrivingSide::Right => turn_type == TurnType::Right,
+            DrivingSide::Left => turn_type == TurnType::Left,
+        }
+    }
+
     // Returns true if this did anything
     pub fn convert_to_ped_scramble(&mut self) -> bool {
         let orig = self.clone();
@@ -181,6 +215,52 @@ impl Phase {
         true
     }
 
+    // None if there aren't any protected crosswalks in this phase. If the phase is too short to
+    // finish the longest crosswalk, there's no walk interval at all.
+    pub fn pedestrian_intervals(
+        &self,
+        parent: &ControlTrafficSignal,
+    ) -> Option<PedestrianIntervals> {
+        let longest = self
+            .protected_groups
+            .iter()
+            .filter(|g| g.crosswalk)
+            .map(|g| parent.turn_groups[g].geom.length())
+            .max()?;
+        let duration = self.phase_type.simple_duration();
+        let flashing_dont_walk = (longest / PEDESTRIAN_CLEARANCE_SPEED).min(duration);
+        let walk = duration - flashing_dont_walk;
+        Some(PedestrianIntervals {
+            leading: parent.leading_pedestrian_interval.min(walk),
+            walk,
+            flashing_dont_walk,
+        })
+    }
+
+    // Turns yielding to a protected crosswalk in this phase have to wait out the leading
+    // pedestrian interval.
+    pub fn held_by_leading_pedestrian_interval(
+        &self,
+        t: TurnID,
+        parent: &ControlTrafficSignal,
+    ) -> bool {
+        if parent.leading_pedestrian_interval == Duration::ZERO {
+            return false;
+        }
+        let group = match parent.turn_groups.values().find(|g| g.members.contains(&t)) {
+            Some(g) => g,
+            None => {
+                return false;
+            }
+        };
+        if !self.yield_groups.contains(&group.id) {
+            return false;
+        }
+        self.protected_groups
+            .iter()
+            .any(|cw| cw.crosswalk && group.conflicts_with(&parent.turn_groups[cw]))
+    }
+
     pub fn get_priority_of_turn(&self, t: TurnID, parent: &ControlTrafficSignal) -> TurnPriority {
         // TODO Cache this?
         if let Some(g) = parent
@@ -301,6 +381,9 @@ impl ControlTrafficSignal {
             id,
             phases,
             offset: Duration::ZERO,
+            leading_pedestrian_interval: Duration::ZERO,
+            right_turn_on_red: map.config.right_turn_on_red,
+            transit_signal_priority: false,
             turn_groups: TurnGroup::for_i(id, map),
         }
         .validate()
@@ -28,3 +28,11 @@ pub const SYNTHETIC_LANES: &str = "abst:synthetic_lanes";
 // Any roads might have these.
 pub const INFERRED_PARKING: &str = "abst:parking_inferred";
 pub const INFERRED_SIDEWALKS: &str = "abst:sidewalks_inferred";
+
+// Short one-way roads cutting the corner of a junction, detected during map construction.
+pub const SLIP_LANE: &str = "abst:slip_lane";
+
+// The kind of pedestrian crossing mapped near each end of a road, like "zebra" or "unmarked".
+// Normally that's a separate node in OSM, just before the intersection.
+pub const CROSSING_FWD: &str = "abst:crossing_fwd";
+pub const CROSSING_BACK: &str = "abst:crossing_back";
@@ -1,10 +1,14 @@
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn};
-use crate::{Lane, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, Turn, TurnID};
+use crate::{
+    Lane, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, RoadID, Turn, TurnID,
+};
 use abstutil::MultiMap;
 use fast_paths::{deserialize_32, serialize_32, FastGraph, InputGraph, PathCalculator};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
 use thread_local::ThreadLocal;
 
 #[derive(Serialize, Deserialize)]
@@ -259,3 +263,123 @@ pub fn cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map: &Map) -
         PathConstraints::Pedestrian => unreachable!(),
     }
 }
+
+// Plain Dijkstra's that never enters the given roads, except for the ones at the start and end of
+// the request. Much slower than the contraction hierarchy, but doesn't need any preparation.
+pub fn pathfind_avoiding(req: &PathRequest, avoid: &BTreeSet<RoadID>, map: &Map) -> Option<Path> {
+    assert_ne!(req.constraints, PathConstraints::Pedestrian);
+    let start = req.start.lane();
+    let end = req.end.lane();
+    let end_road = map.get_l(end).parent;
+
+    let mut backrefs: HashMap<LaneID, TurnID> = HashMap::new();
+    let mut best_cost: HashMap<LaneID, usize> = HashMap::new();
+    let mut queue: BinaryHeap<(Reverse<usize>, LaneID)> = BinaryHeap::new();
+    queue.push((Reverse(0), start));
+    best_cost.insert(start, 0);
+
+    while let Some((Reverse(cost_so_far), current)) = queue.pop() {
+        if current == end && current != start {
+            return Some(trace_back(req, &backrefs, map));
+        }
+        if best_cost
+            .get(&current)
+            .map(|c| cost_so_far > *c)
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        let lane = map.get_l(current);
+        for turn in map.get_turns_for(current, req.constraints) {
+            let next = map.get_l(turn.id.dst);
+            if avoid.contains(&next.parent) && next.parent != end_road {
+                continue;
+            }
+            if !req.constraints.can_use(next, map) {
+                continue;
+            }
+            let cost = cost_so_far + cost(lane, turn, req.constraints, map);
+            if best_cost.get(&next.id).map(|c| cost < *c).unwrap_or(true) {
+                best_cost.insert(next.id, cost);
+                backrefs.insert(next.id, turn.id);
+                queue.push((Reverse(cost), next.id));
+            }
+        }
+    }
+    None
+}
+
+// Like pathfind_avoiding, but requests starting from the same lane share one search. The avoided
+// roads can be the last step of a path, but are never passed through.
+pub fn pathfind_many_avoiding(
+    reqs: Vec<PathRequest>,
+    avoid: &BTreeSet<RoadID>,
+    map: &Map,
+) -> Vec<Option<Path>> {
+    let mut results: Vec<Option<Path>> = reqs.iter().map(|_| None).collect();
+    let mut per_start: BTreeMap<(LaneID, PathConstraints), Vec<usize>> = BTreeMap::new();
+    for (idx, req) in reqs.iter().enumerate() {
+        assert_ne!(req.constraints, PathConstraints::Pedestrian);
+        per_start
+            .entry((req.start.lane(), req.constraints))
+            .or_insert_with(Vec::new)
+            .push(idx);
+    }
+
+    for ((start, constraints), indices) in per_start {
+        let mut backrefs: HashMap<LaneID, TurnID> = HashMap::new();
+        let mut best_cost: HashMap<LaneID, usize> = HashMap::new();
+        let mut queue: BinaryHeap<(Reverse<usize>, LaneID)> = BinaryHeap::new();
+        queue.push((Reverse(0), start));
+        best_cost.insert(start, 0);
+
+        while let Some((Reverse(cost_so_far), current)) = queue.pop() {
+            if best_cost
+                .get(&current)
+                .map(|c| cost_so_far > *c)
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            let lane = map.get_l(current);
+            if current != start && avoid.contains(&lane.parent) {
+                continue;
+            }
+            for turn in map.get_turns_for(current, constraints) {
+                let next = map.get_l(turn.id.dst);
+                if !constraints.can_use(next, map) {
+                    continue;
+                }
+                let cost = cost_so_far + cost(lane, turn, constraints, map);
+                if best_cost.get(&next.id).map(|c| cost < *c).unwrap_or(true) {
+                    best_cost.insert(next.id, cost);
+                    backrefs.insert(next.id, turn.id);
+                    queue.push((Reverse(cost), next.id));
+                }
+            }
+        }
+
+        for idx in indices {
+            let end = reqs[idx].end.lane();
+            if end != start && backrefs.contains_key(&end) {
+                results[idx] = Some(trace_back(&reqs[idx], &backrefs, map));
+            }
+        }
+    }
+    results
+}
+
+fn trace_back(req: &PathRequest, backrefs: &HashMap<LaneID, TurnID>, map: &Map) -> Path {
+    let start = req.start.lane();
+    let end = req.end.lane();
+    let mut steps = vec![PathStep::Lane(end)];
+    let mut current = end;
+    while current != start {
+        let turn = backrefs[&current];
+        steps.push(PathStep::Turn(turn));
+        steps.push(PathStep::Lane(turn.src));
+        current = turn.src;
+    }
+    steps.reverse();
+    Path::new(map, steps, req.end.dist_along(), Vec::new())
+}
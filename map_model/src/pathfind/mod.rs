@@ -6,10 +6,11 @@ mod walking;
 
 pub use self::driving::cost;
 use self::driving::VehiclePathfinder;
+pub(crate) use self::driving::{pathfind_avoiding, pathfind_many_avoiding};
 use self::walking::{one_step_walking_path, walking_path_to_steps, SidewalkPathfinder};
 pub use self::walking::{walking_cost, WalkingNode};
 use crate::{
-    osm, BusRouteID, BusStopID, Intersection, Lane, LaneID, LaneType, Map, Position, Traversable,
+    BusRouteID, BusStopID, Intersection, Lane, LaneID, LaneType, Map, Position, Traversable,
     TurnID, UberTurn, Zone,
 };
 use abstutil::Timer;
@@ -146,6 +147,14 @@ impl Path {
         }
     }
 
+    // The same route, but ending somewhere else along the last lane. Only for paths not started
+    // yet.
+    pub fn with_end_dist(&self, end_dist: Distance) -> Path {
+        let mut path = self.clone();
+        path.end_dist = end_dist;
+        path
+    }
+
     pub fn one_step(l: LaneID, map: &Map) -> Path {
         Path::new(
             map,
@@ -459,9 +468,7 @@ impl PathConstraints {
                     true
                 } else if l.is_driving() || (l.is_bus() && map.config.bikes_can_use_bus_lanes) {
                     let road = map.get_r(l.parent);
-                    road.osm_tags.get("bicycle") != Some(&"no".to_string())
-                        && road.osm_tags.get(osm::HIGHWAY) != Some(&"motorway".to_string())
-                        && road.osm_tags.get(osm::HIGHWAY) != Some(&"motorway_link".to_string())
+                    road.osm_tags.get("bicycle") != Some(&"no".to_string()) && !road.is_highway()
                 } else {
                     false
                 }
@@ -488,7 +495,7 @@ impl PathConstraints {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct PathRequest {
     pub start: Position,
     pub end: Position,
@@ -0,0 +1,57 @@
+use abstutil::Timer;
+use serde::{Deserialize, Serialize};
+
+// Where a map came from, so results can be traced back to the exact inputs that built it. The
+// importer writes this next to every map it builds, instead of inside the map, so adding it didn't
+// change the map format. Maps built some other way just have blanks.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MapProvenance {
+    // The .osm or .osm.pbf file
+    pub osm_source: String,
+    // The last modification time of that file, in seconds since the Unix epoch. Usually that's
+    // when it was downloaded or clipped, but the OSM data itself could be older.
+    pub osm_date: Option<u64>,
+    // The git revision of the importer, or just the crate version if git isn't available
+    pub importer_version: String,
+    // Which city's import settings were used, and the extra data sources that went in
+    pub config: String,
+    // The md5sum of the map file, the same as data/MANIFEST.txt uses, so two copies can be compared
+    // without diffing them
+    pub content_hash: String,
+}
+
+impl MapProvenance {
+    pub fn unknown() -> MapProvenance {
+        MapProvenance {
+            osm_source: String::new(),
+            osm_date: None,
+            importer_version: String::new(),
+            config: String::new(),
+            content_hash: String::new(),
+        }
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        if self.osm_source.is_empty() {
+            return vec!["Unknown origin".to_string()];
+        }
+        vec![
+            format!("Built from {}", self.osm_source),
+            format!("Imported by {} with {}", self.importer_version, self.config),
+            format!("Content hash {}", self.content_hash),
+        ]
+    }
+
+    pub fn save(&self, map_name: &str) {
+        abstutil::write_json(abstutil::path_map_provenance(map_name), self);
+    }
+
+    // Unknown if the importer didn't record anything
+    pub fn load(map_name: &str) -> MapProvenance {
+        abstutil::maybe_read_json(
+            abstutil::path_map_provenance(map_name),
+            &mut Timer::throwaway(),
+        )
+        .unwrap_or_else(|_| MapProvenance::unknown())
+    }
+}
@@ -124,6 +124,7 @@ impl RawMap {
             config: MapConfig {
                 driving_side: DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                right_turn_on_red: false,
             },
         }
     }
@@ -477,4 +478,6 @@ pub struct RawBusStop {
     pub vehicle_pos: Pt2D,
     // If it's not explicitly mapped, we'll do equiv_pos.
     pub ped_pos: Option<Pt2D>,
+    // Tagged on either the stop or the platform
+    pub shelter: bool,
 }
@@ -0,0 +1,69 @@
+use crate::{Map, MapProvenance};
+use abstutil::Timer;
+use geom::{Distance, Pt2D};
+use serde::{Deserialize, Serialize};
+
+// Loading a whole map just to describe it is too slow, so the importer writes this next to every
+// map it builds.
+#[derive(Serialize, Deserialize)]
+pub struct MapSummary {
+    // Each road from one end to the other, already scaled to fit in a THUMBNAIL_SIZE square
+    pub thumbnail: Vec<(Pt2D, Pt2D)>,
+    pub width: Distance,
+    pub height: Distance,
+    pub num_roads: usize,
+    pub num_intersections: usize,
+    pub num_buildings: usize,
+    pub provenance: MapProvenance,
+}
+
+impl MapSummary {
+    // In pixels
+    pub const THUMBNAIL_SIZE: f64 = 300.0;
+
+    pub fn new(map: &Map) -> MapSummary {
+        let bounds = map.get_bounds();
+        let zoom = MapSummary::THUMBNAIL_SIZE / bounds.width().max(bounds.height());
+        let scale = |pt: Pt2D| {
+            Pt2D::new(
+                (pt.x() - bounds.min_x) * zoom,
+                (pt.y() - bounds.min_y) * zoom,
+            )
+        };
+        MapSummary {
+            thumbnail: map
+                .all_roads()
+                .iter()
+                .map(|r| {
+                    (
+                        scale(r.center_pts.first_pt()),
+                        scale(r.center_pts.last_pt()),
+                    )
+                })
+                .collect(),
+            width: Distance::meters(bounds.width()),
+            height: Distance::meters(bounds.height()),
+            num_roads: map.all_roads().len(),
+            num_intersections: map.all_intersections().len(),
+            num_buildings: map.all_buildings().len(),
+            provenance: MapProvenance::load(map.get_name()),
+        }
+    }
+
+    pub fn save(&self, map_name: &str) {
+        abstutil::write_binary(abstutil::path_map_summary(map_name), self);
+    }
+
+    pub fn load(map_name: &str) -> Option<MapSummary> {
+        abstutil::maybe_read_binary(
+            abstutil::path_map_summary(map_name),
+            &mut Timer::throwaway(),
+        )
+        .ok()
+    }
+
+    // It's just a cache, so it doesn't matter if this fails
+    pub fn delete(map_name: &str) {
+        let _ = std::fs::remove_file(abstutil::path_map_summary(map_name));
+    }
+}
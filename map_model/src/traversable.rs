@@ -1,5 +1,5 @@
 use crate::{LaneID, Map, TurnID};
-use geom::{Angle, Distance, PolyLine, Pt2D, Speed};
+use geom::{Angle, Distance, PolyLine, Pt2D, Speed, Time};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
@@ -136,6 +136,13 @@ impl Traversable {
         }
     }
 
+    pub fn exists(&self, map: &Map) -> bool {
+        match *self {
+            Traversable::Lane(id) => map.maybe_get_l(id).is_some(),
+            Traversable::Turn(id) => map.maybe_get_t(id).is_some(),
+        }
+    }
+
     // TODO Just expose the PolyLine instead of all these layers of helpers
     pub fn length(&self, map: &Map) -> Distance {
         match *self {
@@ -170,10 +177,11 @@ impl Traversable {
         }
     }
 
-    pub fn speed_limit(&self, map: &Map) -> Speed {
+    // Some roads have a different limit depending on the time of day
+    pub fn speed_limit(&self, now: Time, map: &Map) -> Speed {
         match *self {
-            Traversable::Lane(id) => map.get_parent(id).speed_limit,
-            Traversable::Turn(id) => map.get_parent(id.dst).speed_limit,
+            Traversable::Lane(id) => map.get_parent(id).speed_limit_at(now),
+            Traversable::Turn(id) => map.get_parent(id.dst).speed_limit_at(now),
         }
     }
 
@@ -1,12 +1,18 @@
-use crate::{AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType};
+use crate::{
+    AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType,
+    VehicleType,
+};
 use abstutil::Counter;
 use geom::{Distance, Duration, Histogram, Time};
 use map_model::{
-    BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathRequest, RoadID,
-    Traversable, TurnGroupID,
+    BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, Path,
+    PathRequest, RoadID, Traversable, TurnGroupID, TurnID, TurnType,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+// Longer than the game takes to fade out markers for people entering or leaving buildings
+const BUILDING_TRANSITIONS_WINDOW: Duration = Duration::const_seconds(60.0);
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Analytics {
@@ -28,6 +34,39 @@ pub struct Analytics {
     // Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
+    // When a car left a spot, how long it was parked there, and what it paid in cents
+    pub parking_payments: Vec<(Time, ParkingSpot, Duration, usize)>,
+    // When somebody leaves (true) or enters (false) a building. Only the last
+    // BUILDING_TRANSITIONS_WINDOW is kept, just for drawing markers that fade out, so this isn't
+    // worth saving.
+    #[serde(skip)]
+    pub building_transitions: VecDeque<(Time, BuildingID, bool)>,
+    // Registered by the user while the sim runs
+    pub corridors: Vec<Corridor>,
+    pub turn_counts: BTreeMap<IntersectionID, TurnCounts>,
+    // Surrogate safety measures. A conflict is when an agent starts a turn shortly after somebody
+    // else cleared a conflicting turn; the gap is the post-encroachment time.
+    pub conflicts: Vec<(Time, IntersectionID, Duration)>,
+    // Where a vehicle had to stop abruptly behind somebody
+    pub hard_braking: Vec<(Time, Traversable, Distance)>,
+    // Trucks entering a road that doesn't allow them
+    pub truck_violations: Vec<(Time, CarID, RoadID)>,
+    // Cars driving in a bus lane
+    pub bus_lane_violations: Vec<(Time, CarID, LaneID)>,
+    // Buses granted transit signal priority. True if the phase was extended, false if it was cut
+    // short.
+    pub transit_signal_priority: Vec<(Time, IntersectionID, CarID, bool)>,
+    // Taken every METRICS_SAMPLE_INTERVAL while the sim runs
+    pub metrics: Vec<MetricsSample>,
+    // Just bookkeeping for conflicts
+    current_turns: BTreeMap<AgentID, TurnID>,
+    recently_cleared: BTreeMap<IntersectionID, Vec<(TurnID, Time)>>,
+    // Just bookkeeping for parking payments
+    parked_since: BTreeMap<CarID, Time>,
+    // Just bookkeeping for metrics. Sum and count of intersection delays since the last sample, and
+    // how many trips have finished (not aborted) so far.
+    delays_since_sample: (Duration, usize),
+    num_finished_trips: usize,
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
 
     // After we restore from a savestate, don't record anything. This is only going to make sense
@@ -50,6 +89,21 @@ impl Analytics {
             intersection_delays: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
+            parking_payments: Vec::new(),
+            building_transitions: VecDeque::new(),
+            corridors: Vec::new(),
+            turn_counts: BTreeMap::new(),
+            conflicts: Vec::new(),
+            hard_braking: Vec::new(),
+            truck_violations: Vec::new(),
+            bus_lane_violations: Vec::new(),
+            transit_signal_priority: Vec::new(),
+            metrics: Vec::new(),
+            current_turns: BTreeMap::new(),
+            recently_cleared: BTreeMap::new(),
+            parked_since: BTreeMap::new(),
+            delays_since_sample: (Duration::ZERO, 0),
+            num_finished_trips: 0,
             alerts: Vec::new(),
             record_anything: true,
         }
@@ -90,6 +144,10 @@ impl Analytics {
                     if let Some(id) = map.get_turn_group(t) {
                         *self.demand.entry(id).or_insert(0) -= 1;
                     }
+
+                    if let Some(counts) = self.turn_counts.get_mut(&t.parent) {
+                        counts.record(a, t, time, map);
+                    }
                 }
             };
         }
@@ -133,6 +191,7 @@ impl Analytics {
         {
             self.finished_trips
                 .push((time, trip, Some(mode), total_time));
+            self.num_finished_trips += 1;
         } else if let Event::TripAborted(id) = ev {
             self.started_trips.entry(id).or_insert(time);
             self.finished_trips.push((time, id, None, Duration::ZERO));
@@ -144,10 +203,17 @@ impl Analytics {
                 .entry(id)
                 .or_insert_with(Vec::new)
                 .push((time, delay, agent.to_type()));
+            self.delays_since_sample.0 += delay;
+            self.delays_since_sample.1 += 1;
+        }
+
+        if let Event::TransitSignalPriority(i, car, extended) = ev {
+            self.transit_signal_priority.push((time, i, car, extended));
         }
 
         // Parking spot changes
-        if let Event::CarReachedParkingSpot(_, spot) = ev {
+        if let Event::CarReachedParkingSpot(car, spot) = ev {
+            self.parked_since.insert(car, time);
             if let ParkingSpot::Onstreet(l, _) = spot {
                 self.parking_lane_changes
                     .entry(l)
@@ -160,7 +226,15 @@ impl Analytics {
                     .push((time, true));
             }
         }
-        if let Event::CarLeftParkingSpot(_, spot) = ev {
+        if let Event::CarLeftParkingSpot(car, spot) = ev {
+            if let Some(since) = self.parked_since.remove(&car) {
+                // Cars seeded before the day starts were parked overnight, when nobody pays.
+                if since != Time::START_OF_DAY {
+                    let dt = time - since;
+                    self.parking_payments
+                        .push((time, spot, dt, spot.policy(map).cost(dt)));
+                }
+            }
             if let ParkingSpot::Onstreet(l, _) = spot {
                 self.parking_lane_changes
                     .entry(l)
@@ -174,6 +248,66 @@ impl Analytics {
             }
         }
 
+        // Corridors
+        if let Event::AgentEntersTraversable(a, on, _) = ev {
+            for c in &mut self.corridors {
+                c.agent_enters(a, on, time, map);
+            }
+        }
+        // A vehicle that parks, finishes on a bike, or vanishes at a border partway through a
+        // corridor never completes it.
+        let stopped = match ev {
+            Event::CarReachedParkingSpot(car, _) | Event::BikeStoppedAtSidewalk(car, _) => {
+                Some(AgentID::Car(car))
+            }
+            Event::PersonLeavesMap(_, Some(a), _, _) => Some(a),
+            _ => None,
+        };
+        if let Some(a) = stopped {
+            for c in &mut self.corridors {
+                c.in_progress.remove(&a);
+            }
+        }
+
+        // Safety
+        if let Event::AgentEntersTraversable(a, on, _) = ev {
+            self.record_conflicts(a, on, time, map);
+        }
+        if let Event::HardBraking(_, on, dist) = ev {
+            self.hard_braking.push((time, on, dist));
+        }
+
+        // Freight only starts and ends at borders, so restricted roads leading to one are fine.
+        if let Event::AgentEntersTraversable(AgentID::Car(c), Traversable::Lane(l), _) = ev {
+            if c.1 == VehicleType::Truck {
+                let r = map.get_parent(l);
+                if !r.allow_trucks
+                    && !map.get_i(r.src_i).is_border()
+                    && !map.get_i(r.dst_i).is_border()
+                {
+                    self.truck_violations.push((time, c, r.id));
+                }
+            }
+            if c.1 == VehicleType::Car && map.get_l(l).is_bus() {
+                self.bus_lane_violations.push((time, c, l));
+            }
+        }
+
+        // Building transitions
+        if let Event::PersonLeavesBuilding(_, b) = ev {
+            self.building_transitions.push_back((time, b, true));
+        } else if let Event::PersonEntersBuilding(_, b) = ev {
+            self.building_transitions.push_back((time, b, false));
+        }
+        while self
+            .building_transitions
+            .front()
+            .map(|(t, _, _)| time - *t > BUILDING_TRANSITIONS_WINDOW)
+            .unwrap_or(false)
+        {
+            self.building_transitions.pop_front();
+        }
+
         // TODO Kinda hacky, but these all consume the event, so kinda bundle em.
         match ev {
             Event::TripPhaseStarting(id, _, maybe_req, phase_type) => {
@@ -196,6 +330,36 @@ impl Analytics {
         }
     }
 
+    fn record_conflicts(&mut self, a: AgentID, on: Traversable, time: Time, map: &Map) {
+        match on {
+            Traversable::Turn(t) => {
+                let recent = self
+                    .recently_cleared
+                    .entry(t.parent)
+                    .or_insert_with(Vec::new);
+                recent.retain(|(_, cleared)| time - *cleared <= CONFLICT_THRESHOLD);
+                let turn = map.get_t(t);
+                if let Some(pet) = recent
+                    .iter()
+                    .filter(|(other, _)| turn.conflicts_with(map.get_t(*other)))
+                    .map(|(_, cleared)| time - *cleared)
+                    .min()
+                {
+                    self.conflicts.push((time, t.parent, pet));
+                }
+                self.current_turns.insert(a, t);
+            }
+            Traversable::Lane(_) => {
+                if let Some(t) = self.current_turns.remove(&a) {
+                    self.recently_cleared
+                        .entry(t.parent)
+                        .or_insert_with(Vec::new)
+                        .push((t, time));
+                }
+            }
+        }
+    }
+
     pub fn record_demand(&mut self, path: &Path, map: &Map) {
         for step in path.get_steps() {
             if let Traversable::Turn(t) = step.as_traversable() {
@@ -224,12 +388,12 @@ impl Analytics {
         None
     }
 
-    // Returns pairs of trip times for finished trips in both worlds. (before, after, mode)
+    // Returns pairs of trip times for finished trips in both worlds. (ID, before, after, mode)
     pub fn both_finished_trips(
         &self,
         now: Time,
         before: &Analytics,
-    ) -> Vec<(Duration, Duration, TripMode)> {
+    ) -> Vec<(TripID, Duration, Duration, TripMode)> {
         let mut a = BTreeMap::new();
         for (t, id, maybe_mode, dt) in &self.finished_trips {
             if *t > now {
@@ -247,7 +411,7 @@ impl Analytics {
             }
             if let Some(mode) = maybe_mode {
                 if let Some(dt1) = a.remove(id) {
-                    results.push((*dt, dt1, *mode));
+                    results.push((*id, *dt, dt1, *mode));
                 }
             }
         }
@@ -312,6 +476,47 @@ impl Analytics {
         delay_to_stop
     }
 
+    // Across all stops and buses, how long a route takes to get from one stop to the next
+    pub fn bus_time_between_stops(&self, now: Time) -> BTreeMap<BusRouteID, Histogram<Duration>> {
+        let mut per_bus: BTreeMap<(CarID, BusRouteID), Time> = BTreeMap::new();
+        let mut results: BTreeMap<BusRouteID, Histogram<Duration>> = BTreeMap::new();
+        for (t, car, route, _) in &self.bus_arrivals {
+            if *t > now {
+                break;
+            }
+            if let Some(prev) = per_bus.insert((*car, *route), *t) {
+                results
+                    .entry(*route)
+                    .or_insert_with(Histogram::new)
+                    .add(*t - prev);
+            }
+        }
+        results
+    }
+
+    // Delays at some intersections so far, for buses and for everybody else
+    pub fn bus_and_other_delays(
+        &self,
+        intersections: &BTreeSet<IntersectionID>,
+        now: Time,
+    ) -> (Histogram<Duration>, Histogram<Duration>) {
+        let mut bus = Histogram::new();
+        let mut other = Histogram::new();
+        for i in intersections {
+            for (t, dt, agent_type) in self.intersection_delays.get(i).into_iter().flatten() {
+                if *t > now {
+                    break;
+                }
+                if *agent_type == AgentType::Bus {
+                    bus.add(*dt);
+                } else {
+                    other.add(*dt);
+                }
+            }
+        }
+        (bus, other)
+    }
+
     // TODO Refactor!
     // For each stop, a list of (time, delay)
     pub fn bus_arrivals_over_time(
@@ -544,6 +749,33 @@ impl Analytics {
     }
 }
 
+impl Analytics {
+    // Checked every step, so it's cheap. Only then is it worth gathering the sample.
+    pub(crate) fn wants_metrics_sample(&self, time: Time) -> bool {
+        self.record_anything
+            && self
+                .metrics
+                .last()
+                .map(|last| time - last.time >= METRICS_SAMPLE_INTERVAL)
+                .unwrap_or(true)
+    }
+
+    pub(crate) fn sample_metrics(&mut self, time: Time, active_agents: usize) {
+        let (sum, cnt) = self.delays_since_sample;
+        self.metrics.push(MetricsSample {
+            time,
+            active_agents,
+            finished_trips: self.num_finished_trips,
+            avg_delay: if cnt == 0 {
+                Duration::ZERO
+            } else {
+                sum / (cnt as f64)
+            },
+        });
+        self.delays_since_sample = (Duration::ZERO, 0);
+    }
+}
+
 impl Default for Analytics {
     fn default() -> Analytics {
         let mut a = Analytics::new();
@@ -552,6 +784,172 @@ impl Default for Analytics {
     }
 }
 
+// A sequence of roads, like an arterial. Vehicles that drive along the entire thing, without
+// turning off partway, have their end-to-end travel time recorded. Vehicles that start or end
+// their trip in the middle of the corridor aren't counted. This only works while the sim is
+// running; it can't be applied to prebaked results afterwards.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Corridor {
+    pub roads: Vec<RoadID>,
+    // When each vehicle entered the first road, and the index of the road it's on now
+    in_progress: BTreeMap<AgentID, (Time, usize)>,
+    // When a vehicle left the last road and how long it took since entering the first
+    pub travel_times: Vec<(Time, Duration)>,
+}
+
+impl Corridor {
+    pub fn new(roads: Vec<RoadID>) -> Corridor {
+        assert!(!roads.is_empty());
+        Corridor {
+            roads,
+            in_progress: BTreeMap::new(),
+            travel_times: Vec::new(),
+        }
+    }
+
+    fn agent_enters(&mut self, a: AgentID, on: Traversable, time: Time, map: &Map) {
+        if let AgentID::Pedestrian(_) = a {
+            return;
+        }
+        match on {
+            Traversable::Lane(l) => {
+                let r = map.get_l(l).parent;
+                if let Some((_, idx)) = self.in_progress.get_mut(&a) {
+                    if *idx + 1 < self.roads.len() && self.roads[*idx + 1] == r {
+                        *idx += 1;
+                        return;
+                    }
+                    if self.roads[*idx] != r {
+                        self.in_progress.remove(&a);
+                    }
+                }
+                if r == self.roads[0] && !self.in_progress.contains_key(&a) {
+                    self.in_progress.insert(a, (time, 0));
+                }
+            }
+            Traversable::Turn(t) => {
+                if let Some((start, idx)) = self.in_progress.get(&a).cloned() {
+                    let last = self.roads.len() - 1;
+                    if idx == last && map.get_l(t.src).parent == self.roads[last] {
+                        // Leaving the last road. If the next road is part of the corridor (because
+                        // it loops), the lane handling will figure it out.
+                        self.travel_times.push((time, time - start));
+                        self.in_progress.remove(&a);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Post-encroachment times at or below this count as a conflict
+pub const CONFLICT_THRESHOLD: Duration = Duration::const_seconds(1.5);
+
+// Counted in blocks of this length, like the manual counts traffic engineers do.
+pub const TURN_COUNT_INTERVAL: Duration = Duration::const_seconds(15.0 * 60.0);
+
+pub const METRICS_SAMPLE_INTERVAL: Duration = Duration::const_seconds(60.0);
+
+// A snapshot of the whole simulation, for plotting over time
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MetricsSample {
+    pub time: Time,
+    pub active_agents: usize,
+    // Cumulative since midnight, not counting aborted trips
+    pub finished_trips: usize,
+    // Of all the delays measured at intersections since the previous sample
+    pub avg_delay: Duration,
+}
+
+// What a headless run streams to a viewer watching it, as one line of JSON per sample
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TelemetryUpdate {
+    pub map_name: String,
+    pub scenario_name: String,
+    pub edits_name: String,
+    pub sample: MetricsSample,
+    pub unfinished_trips: usize,
+    // The last update from a run has this set
+    pub done: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TurnMovement {
+    Left,
+    Through,
+    Right,
+    UTurn,
+}
+
+impl TurnMovement {
+    pub fn all() -> Vec<TurnMovement> {
+        vec![
+            TurnMovement::Left,
+            TurnMovement::Through,
+            TurnMovement::Right,
+            TurnMovement::UTurn,
+        ]
+    }
+
+    pub fn classify(t: TurnID, map: &Map) -> TurnMovement {
+        if map.get_l(t.src).parent == map.get_l(t.dst).parent {
+            return TurnMovement::UTurn;
+        }
+        match map.get_t(t).turn_type {
+            TurnType::Left => TurnMovement::Left,
+            TurnType::Right => TurnMovement::Right,
+            // Lane-changing turns still go straight through
+            _ => TurnMovement::Through,
+        }
+    }
+}
+
+// Vehicles counted per approach and movement at one intersection.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TurnCounts {
+    pub started: Time,
+    // (Interval index, incoming road, movement) -> count
+    pub counts: BTreeMap<(usize, RoadID, TurnMovement), usize>,
+}
+
+impl TurnCounts {
+    pub fn new(started: Time) -> TurnCounts {
+        TurnCounts {
+            started,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    fn record(&mut self, a: AgentID, t: TurnID, time: Time, map: &Map) {
+        if let AgentID::Pedestrian(_) = a {
+            return;
+        }
+        let interval = (time.inner_seconds() / TURN_COUNT_INTERVAL.inner_seconds()) as usize;
+        *self
+            .counts
+            .entry((
+                interval,
+                map.get_l(t.src).parent,
+                TurnMovement::classify(t, map),
+            ))
+            .or_insert(0) += 1;
+    }
+
+    // Summed over all intervals
+    pub fn totals(&self) -> BTreeMap<(RoadID, TurnMovement), usize> {
+        let mut totals = BTreeMap::new();
+        for ((_, r, m), cnt) in &self.counts {
+            *totals.entry((*r, *m)).or_insert(0) += cnt;
+        }
+        totals
+    }
+
+    // When the interval starts
+    pub fn interval_start(interval: usize) -> Time {
+        Time::START_OF_DAY + (interval as f64) * TURN_COUNT_INTERVAL
+    }
+}
+
 #[derive(Debug)]
 pub struct TripPhase {
     pub start_time: Time,
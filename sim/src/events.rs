@@ -1,7 +1,7 @@
 use crate::{
     AgentID, CarID, OffMapLocation, ParkingSpot, PedestrianID, PersonID, TripID, TripMode,
 };
-use geom::Duration;
+use geom::{Distance, Duration};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, Path, PathRequest, Traversable,
 };
@@ -39,6 +39,10 @@ pub enum Event {
     // board.
     AgentEntersTraversable(AgentID, Traversable, Option<usize>),
     IntersectionDelayMeasured(IntersectionID, Duration, AgentID),
+    // A bus got the current phase extended (true) or cut short (false)
+    TransitSignalPriority(IntersectionID, CarID, bool),
+    // Where the car stopped
+    HardBraking(CarID, Traversable, Distance),
 
     TripFinished {
         trip: TripID,
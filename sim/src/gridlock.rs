@@ -0,0 +1,295 @@
+use crate::{AgentID, DrivingSimState, IntersectionSimState};
+use geom::{Duration, Time};
+use map_model::{IntersectionID, Map, Traversable};
+use petgraph::graphmap::DiGraphMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// How often to look for gridlock while the sim runs
+const CHECK_INTERVAL: Duration = Duration::const_seconds(60.0);
+// Vehicles waiting on each other in a cycle might untangle quickly, so don't report it right away
+const CYCLE_THRESHOLD: Duration = Duration::const_seconds(30.0);
+// Anybody waiting to turn this long is stuck, even without a cycle
+const STUCK_THRESHOLD: Duration = Duration::const_seconds(5.0 * 60.0);
+
+// Somewhere traffic has locked up
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Gridlock {
+    // Where the agent waiting longest is trying to turn
+    pub intersection: IntersectionID,
+    // When they started waiting
+    pub since: Time,
+    // True if vehicles are waiting on each other in a cycle, so nobody can ever move. Otherwise,
+    // somebody's just been waiting longer than STUCK_THRESHOLD.
+    pub cycle: bool,
+    // For a cycle, every vehicle in it. Otherwise, whoever's in the way of the stuck agents, or
+    // the stuck agents themselves if nobody obvious is.
+    pub blocking: BTreeSet<AgentID>,
+}
+
+impl Gridlock {
+    pub fn describe(&self) -> String {
+        if self.cycle {
+            format!(
+                "Gridlock at {}: {} vehicles waiting on each other since {}",
+                self.intersection,
+                self.blocking.len(),
+                self.since.ampm_tostring()
+            )
+        } else {
+            format!(
+                "Stuck at {} since {}, waiting on {} agents",
+                self.intersection,
+                self.since.ampm_tostring(),
+                self.blocking.len()
+            )
+        }
+    }
+
+    // A gridlock found again in a later check is the same one, even if more vehicles joined it
+    fn same_as(&self, other: &Gridlock) -> bool {
+        self.intersection == other.intersection && self.cycle == other.cycle
+    }
+}
+
+// Periodically looks for gridlock, remembering which ones haven't been shown to the player yet.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct GridlockDetector {
+    last_check: Option<Time>,
+    // Everything found by the last check
+    pub current: Vec<Gridlock>,
+    // Found, still there, and not picked up by the UI yet
+    pub unannounced: Vec<Gridlock>,
+}
+
+impl GridlockDetector {
+    pub fn new() -> GridlockDetector {
+        GridlockDetector {
+            last_check: None,
+            current: Vec::new(),
+            unannounced: Vec::new(),
+        }
+    }
+
+    pub fn wants_check(&self, now: Time) -> bool {
+        self.last_check
+            .map(|last| now - last >= CHECK_INTERVAL)
+            .unwrap_or(true)
+    }
+
+    pub fn check(
+        &mut self,
+        now: Time,
+        intersections: &IntersectionSimState,
+        driving: &DrivingSimState,
+        map: &Map,
+    ) {
+        self.last_check = Some(now);
+        let found = find(now, intersections, driving, map);
+        self.unannounced
+            .retain(|old| found.iter().any(|g| g.same_as(old)));
+        for g in &found {
+            if !self.current.iter().any(|old| old.same_as(g)) {
+                self.unannounced.push(g.clone());
+            }
+        }
+        self.current = found;
+    }
+}
+
+fn find(
+    now: Time,
+    intersections: &IntersectionSimState,
+    driving: &DrivingSimState,
+    map: &Map,
+) -> Vec<Gridlock> {
+    // Cars wait on the one in front of them. At the front of a queue, they wait on anybody doing a
+    // conflicting turn, and on the last car in the lane they want to enter, if there's no room.
+    let queues = driving
+        .cars_waiting_in_queues()
+        .into_iter()
+        .map(|(car, leader)| (AgentID::Car(car), AgentID::Car(leader)))
+        .collect();
+    let mut waiting: BTreeMap<AgentID, (IntersectionID, Time, BTreeSet<AgentID>)> = BTreeMap::new();
+    for (agent, turn, since, blockers) in intersections.waiting_agents(map) {
+        let mut blockers: BTreeSet<AgentID> = blockers.into_iter().collect();
+        if let AgentID::Car(car) = agent {
+            if let Some(last) = driving.blocking_entry(car, Traversable::Lane(turn.dst)) {
+                blockers.insert(AgentID::Car(last));
+            }
+        }
+        waiting.insert(agent, (turn.parent, since, blockers));
+    }
+    find_in_graph(now, queues, waiting)
+}
+
+// Separate from the sim state, so it's easy to test. queues has (follower, leader) pairs. waiting
+// has everybody at the front of a queue trying to turn: where, since when, and who's in the way.
+fn find_in_graph(
+    now: Time,
+    queues: Vec<(AgentID, AgentID)>,
+    waiting: BTreeMap<AgentID, (IntersectionID, Time, BTreeSet<AgentID>)>,
+) -> Vec<Gridlock> {
+    // An edge from x to y means x is waiting on y.
+    let mut graph: DiGraphMap<AgentID, ()> = DiGraphMap::new();
+    for (follower, leader) in queues {
+        graph.add_edge(follower, leader, ());
+    }
+    for (agent, (_, _, blockers)) in &waiting {
+        for b in blockers {
+            graph.add_edge(*agent, *b, ());
+        }
+    }
+
+    let mut results = Vec::new();
+    let mut in_cycle: BTreeSet<AgentID> = BTreeSet::new();
+    for agents in petgraph::algo::kosaraju_scc(&graph) {
+        if agents.len() < 2 {
+            continue;
+        }
+        // Everybody at the front of a queue in the cycle has to have been waiting a while
+        let fronts: Vec<(IntersectionID, Time)> = agents
+            .iter()
+            .filter_map(|a| waiting.get(a).map(|(i, t, _)| (*i, *t)))
+            .collect();
+        if fronts.iter().any(|(_, t)| now - *t < CYCLE_THRESHOLD) {
+            continue;
+        }
+        let (intersection, since) = match fronts.into_iter().min_by_key(|(_, t)| *t) {
+            Some(x) => x,
+            None => continue,
+        };
+        in_cycle.extend(agents.iter().cloned());
+        results.push(Gridlock {
+            intersection,
+            since,
+            cycle: true,
+            blocking: agents.into_iter().collect(),
+        });
+    }
+
+    // Anybody else stuck for too long. Skip people only stuck because of a cycle; that's already
+    // been reported.
+    let mut stuck: BTreeMap<IntersectionID, Gridlock> = BTreeMap::new();
+    for (agent, (i, since, blockers)) in waiting {
+        if now - since < STUCK_THRESHOLD
+            || in_cycle.contains(&agent)
+            || blockers.iter().any(|b| in_cycle.contains(b))
+        {
+            continue;
+        }
+        let g = stuck.entry(i).or_insert_with(|| Gridlock {
+            intersection: i,
+            since,
+            cycle: false,
+            blocking: BTreeSet::new(),
+        });
+        g.since = g.since.min(since);
+        if blockers.is_empty() {
+            g.blocking.insert(agent);
+        } else {
+            g.blocking.extend(blockers);
+        }
+    }
+    results.extend(stuck.into_iter().map(|(_, g)| g));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CarID, PedestrianID, VehicleType};
+
+    fn car(id: usize) -> AgentID {
+        AgentID::Car(CarID(id, VehicleType::Car))
+    }
+
+    fn at(seconds: f64) -> Time {
+        Time::START_OF_DAY + Duration::seconds(seconds)
+    }
+
+    // Four cars at the front of their queues around a block, each waiting on the next
+    fn square(since: Time) -> BTreeMap<AgentID, (IntersectionID, Time, BTreeSet<AgentID>)> {
+        let mut waiting = BTreeMap::new();
+        for idx in 0..4 {
+            waiting.insert(
+                car(idx),
+                (
+                    IntersectionID(idx),
+                    since + Duration::seconds(idx as f64),
+                    vec![car((idx + 1) % 4)].into_iter().collect(),
+                ),
+            );
+        }
+        waiting
+    }
+
+    #[test]
+    fn finds_cycle() {
+        // Car 4 is just queued behind the cycle, so it isn't part of it
+        let found = find_in_graph(at(100.0), vec![(car(4), car(0))], square(at(10.0)));
+        assert_eq!(
+            found,
+            vec![Gridlock {
+                intersection: IntersectionID(0),
+                since: at(10.0),
+                cycle: true,
+                blocking: (0..4).map(car).collect(),
+            }]
+        );
+    }
+
+    #[test]
+    fn ignores_new_cycle() {
+        // Car 3 only started waiting 20s ago
+        assert!(find_in_graph(at(33.0), Vec::new(), square(at(10.0))).is_empty());
+        // One car leaving breaks the cycle, no matter how long the others waited
+        let mut waiting = square(at(10.0));
+        waiting.remove(&car(2));
+        assert!(find_in_graph(at(100.0), Vec::new(), waiting).is_empty());
+    }
+
+    #[test]
+    fn finds_stuck_agents() {
+        let ped = AgentID::Pedestrian(PedestrianID(0));
+        let mut waiting = BTreeMap::new();
+        // Waiting on a car that isn't waiting on anybody
+        waiting.insert(
+            car(0),
+            (
+                IntersectionID(0),
+                at(10.0),
+                vec![car(1)].into_iter().collect(),
+            ),
+        );
+        // Nobody obvious in the way
+        waiting.insert(ped, (IntersectionID(0), at(20.0), BTreeSet::new()));
+        // Not waiting long enough yet
+        waiting.insert(car(2), (IntersectionID(1), at(200.0), BTreeSet::new()));
+
+        let found = find_in_graph(at(400.0), Vec::new(), waiting);
+        assert_eq!(
+            found,
+            vec![Gridlock {
+                intersection: IntersectionID(0),
+                since: at(10.0),
+                cycle: false,
+                blocking: vec![car(1), ped].into_iter().collect(),
+            }]
+        );
+
+        // Agents only stuck behind a cycle aren't reported separately
+        let mut waiting = square(at(10.0));
+        waiting.insert(
+            car(5),
+            (
+                IntersectionID(5),
+                at(10.0),
+                vec![car(0)].into_iter().collect(),
+            ),
+        );
+        let found = find_in_graph(at(400.0), Vec::new(), waiting);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].cycle);
+    }
+}
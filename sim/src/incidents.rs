@@ -0,0 +1,136 @@
+use crate::{AgentID, CarID, Event};
+use abstutil::Timer;
+use geom::Time;
+use map_model::raw::OriginalRoad;
+use map_model::{Map, RoadID, Traversable};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Incidents closing roads for a while, and variable message signs telling drivers to avoid them.
+// Stored by name alongside the map, like toll schedules.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IncidentPlan {
+    pub map_name: String,
+    pub name: String,
+    pub incidents: Vec<Incident>,
+    pub signs: Vec<MessageSign>,
+}
+
+// While active, no vehicles can enter the road. Anybody already on it can leave, and people
+// walking aren't affected.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Incident {
+    pub road: OriginalRoad,
+    pub start: Time,
+    pub end: Time,
+}
+
+// While any incident is active, drivers entering the road see the sign. This fraction of them
+// detours around every closed road, if their route used one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MessageSign {
+    pub road: OriginalRoad,
+    pub compliance: f64,
+}
+
+impl IncidentPlan {
+    pub fn load(map_name: &str, name: &str) -> Result<IncidentPlan, std::io::Error> {
+        abstutil::maybe_read_json(
+            abstutil::path_incidents(map_name, name),
+            &mut Timer::throwaway(),
+        )
+    }
+
+    pub fn save(&self) {
+        abstutil::write_json(abstutil::path_incidents(&self.map_name, &self.name), self);
+    }
+}
+
+// An IncidentPlan matched up against the current map, plus what the signs have done so far.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Incidents {
+    pub name: String,
+    // (road, start, end)
+    pub incidents: Vec<(RoadID, Time, Time)>,
+    // Compliance per road
+    pub signs: BTreeMap<RoadID, f64>,
+
+    // How many times drivers passed a sign while it was on
+    pub advised: usize,
+    // (when, who, the sign). Only drivers who actually changed their route.
+    pub detours: Vec<(Time, CarID, RoadID)>,
+}
+
+impl Incidents {
+    pub(crate) fn new(plan: IncidentPlan, map: &Map, timer: &mut Timer) -> Incidents {
+        let find = |r: &OriginalRoad| {
+            map.find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id))
+        };
+        let mut incidents = Vec::new();
+        for i in plan.incidents {
+            match find(&i.road) {
+                Ok(r) => {
+                    incidents.push((r, i.start, i.end));
+                }
+                Err(err) => {
+                    timer.warn(format!("Skipping incident: {}", err));
+                }
+            }
+        }
+        let mut signs = BTreeMap::new();
+        for s in plan.signs {
+            match find(&s.road) {
+                Ok(r) => {
+                    signs.insert(r, s.compliance);
+                }
+                Err(err) => {
+                    timer.warn(format!("Skipping message sign: {}", err));
+                }
+            }
+        }
+
+        Incidents {
+            name: plan.name,
+            incidents,
+            signs,
+            advised: 0,
+            detours: Vec::new(),
+        }
+    }
+
+    pub fn closed_roads(&self, now: Time) -> BTreeSet<RoadID> {
+        self.incidents
+            .iter()
+            .filter(|(_, start, end)| now >= *start && now < *end)
+            .map(|(r, _, _)| *r)
+            .collect()
+    }
+
+    // Is some other incident besides this one keeping the road closed?
+    pub(crate) fn still_closed(&self, idx: usize, now: Time) -> bool {
+        let r = self.incidents[idx].0;
+        self.incidents
+            .iter()
+            .enumerate()
+            .any(|(other, (r2, start, end))| {
+                other != idx && *r2 == r && now >= *start && now < *end
+            })
+    }
+
+    // If a driver just passed a sign and heeds it, returns them and the sign.
+    pub(crate) fn event(&mut self, ev: &Event, now: Time, map: &Map) -> Option<(CarID, RoadID)> {
+        if let Event::AgentEntersTraversable(AgentID::Car(car), Traversable::Lane(l), _) = ev {
+            let r = map.get_l(*l).parent;
+            let compliance = *self.signs.get(&r)?;
+            if self.closed_roads(now).is_empty() {
+                return None;
+            }
+            self.advised += 1;
+            // Deterministic, so the same people heed the signs every run
+            if ((car.0 + r.0) * 7919) % 1000 < (compliance * 1000.0) as usize {
+                return Some((*car, r));
+            }
+        }
+        None
+    }
+}
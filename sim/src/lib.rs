@@ -1,42 +1,59 @@
 mod analytics;
 mod events;
+mod gridlock;
+mod incidents;
 mod make;
 mod mechanics;
 mod pandemic;
+mod query;
 mod render;
 mod router;
+mod routes;
 mod scheduler;
+mod signal_controller;
 mod sim;
+mod tolls;
 mod transit;
 mod trips;
 
-pub use self::analytics::{Analytics, TripPhase};
+pub use self::analytics::{
+    Analytics, Corridor, MetricsSample, TelemetryUpdate, TripPhase, TurnCounts, TurnMovement,
+    CONFLICT_THRESHOLD, METRICS_SAMPLE_INTERVAL, TURN_COUNT_INTERVAL,
+};
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::gridlock::Gridlock;
+pub(crate) use self::gridlock::GridlockDetector;
+pub use self::incidents::{Incident, IncidentPlan, Incidents, MessageSign};
 pub use self::make::{
-    BorderSpawnOverTime, IndividTrip, OffMapLocation, OriginDestination, PersonSpec, Scenario,
+    BorderSpawnOverTime, CarOwnership, DepartureTimes, IndividTrip, NeighborhoodSpawnOverTime,
+    OffMapLocation, OriginDestination, PersonSpec, Population, Resident, Scenario,
     ScenarioGenerator, ScenarioModifier, SimFlags, SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
 };
 pub(crate) use self::mechanics::{
     DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
 };
 pub(crate) use self::pandemic::PandemicModel;
+pub use self::query::TripQuery;
 pub(crate) use self::router::{ActionAtEnd, Router};
+pub use self::routes::CapturedRoutes;
 pub(crate) use self::scheduler::{Command, Scheduler};
+pub use self::signal_controller::{DetectorState, LaneDetector, PhaseCommand, SignalController};
 pub use self::sim::{AgentProperties, AlertHandler, Sim, SimCallback, SimOptions};
+pub use self::tolls::{Cordon, CordonArea, RoadToll, TollPeriod, TollSchedule, Tolls};
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{Person, PersonState, TripInfo, TripResult};
 pub use self::trips::{TripEndpoint, TripMode};
 pub(crate) use self::trips::{TripLeg, TripManager};
 pub use crate::render::{
-    CarStatus, DontDrawAgents, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, GetDrawAgents,
-    PedCrowdLocation, UnzoomedAgent,
+    CarStatus, DontDrawAgents, DrawAgentsOn, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput,
+    GetDrawAgents, PedCrowdLocation, UnzoomedAgent,
 };
 use abstutil::{deserialize_usize, serialize_usize, Cloneable};
 use geom::{Distance, Pt2D, Speed, Time};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, DirectedRoadID, IntersectionID, LaneID, Map, ParkingLotID,
-    Path, PathConstraints, PathRequest, Position,
+    ParkingPolicy, Path, PathConstraints, PathRequest, Position,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -49,6 +66,8 @@ pub const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
 // Note this is more than MAX_CAR_LENGTH
 pub const BUS_LENGTH: Distance = Distance::const_meters(12.5);
 pub const LIGHT_RAIL_LENGTH: Distance = Distance::const_meters(60.0);
+// Also more than MAX_CAR_LENGTH, but trucks never park
+pub const TRUCK_LENGTH: Distance = Distance::const_meters(10.0);
 
 // At all speeds (including at rest), cars must be at least this far apart, measured from front of
 // one car to the back of the other.
@@ -74,6 +93,7 @@ impl fmt::Display for CarID {
             VehicleType::Bus => write!(f, "Bus #{}", self.0),
             VehicleType::Train => write!(f, "Train #{}", self.0),
             VehicleType::Bike => write!(f, "Bike #{}", self.0),
+            VehicleType::Truck => write!(f, "Truck #{}", self.0),
         }
     }
 }
@@ -116,6 +136,8 @@ impl AgentID {
                 VehicleType::Bike => AgentType::Bike,
                 VehicleType::Bus => AgentType::Bus,
                 VehicleType::Train => AgentType::Train,
+                // Trucks count as cars for throughput and the like
+                VehicleType::Truck => AgentType::Car,
             },
             AgentID::Pedestrian(_) => AgentType::Pedestrian,
             AgentID::BusPassenger(_, _) => AgentType::TransitRider,
@@ -227,6 +249,7 @@ pub enum VehicleType {
     Bus,
     Train,
     Bike,
+    Truck,
 }
 
 impl fmt::Display for VehicleType {
@@ -236,6 +259,7 @@ impl fmt::Display for VehicleType {
             VehicleType::Bus => write!(f, "bus"),
             VehicleType::Train => write!(f, "train"),
             VehicleType::Bike => write!(f, "bike"),
+            VehicleType::Truck => write!(f, "truck"),
         }
     }
 }
@@ -247,6 +271,7 @@ impl VehicleType {
             VehicleType::Bus => PathConstraints::Bus,
             VehicleType::Train => PathConstraints::Train,
             VehicleType::Bike => PathConstraints::Bike,
+            VehicleType::Truck => PathConstraints::Car,
         }
     }
 
@@ -256,6 +281,7 @@ impl VehicleType {
             VehicleType::Bus => true,
             VehicleType::Train => true,
             VehicleType::Bike => false,
+            VehicleType::Truck => false,
         }
     }
 }
@@ -298,6 +324,16 @@ pub enum ParkingSpot {
     Lot(ParkingLotID, usize),
 }
 
+impl ParkingSpot {
+    pub fn policy(&self, map: &Map) -> ParkingPolicy {
+        match self {
+            ParkingSpot::Onstreet(l, _) => map.get_l(*l).parking_policy,
+            ParkingSpot::Offstreet(b, _) => map.get_b(*b).parking.as_ref().unwrap().policy,
+            ParkingSpot::Lot(pl, _) => map.get_pl(*pl).policy,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ParkedCar {
     pub vehicle: Vehicle,
@@ -1,15 +1,27 @@
 use crate::{
-    DrivingGoal, IndividTrip, PersonID, PersonSpec, Scenario, SidewalkSpot, SpawnTrip,
-    TripEndpoint, TripMode,
+    CarOwnership, DrivingGoal, IndividTrip, PersonID, PersonSpec, Population, Scenario,
+    SidewalkSpot, SpawnTrip, TripEndpoint, TripMode,
 };
 use abstutil::Timer;
-use geom::{Distance, Duration, Time};
-use map_model::{BuildingID, BuildingType, DirectedRoadID, Map, PathConstraints, PathRequest};
+use geom::{Distance, Duration, Polygon, Time};
+use map_model::{
+    AmenityType, Building, BuildingID, BuildingType, DirectedRoadID, Map, Neighborhood,
+    PathConstraints, PathRequest,
+};
 use rand::seq::SliceRandom;
 use rand::Rng;
+use rand_distr::{Distribution, Normal};
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+
+// How many people stop somewhere between work and home
+const ERRAND_PROBABILITY: f64 = 0.3;
+// How many residents go to school
+const STUDENT_PROBABILITY: f64 = 0.2;
+// Students living farther than this from every school, as the crow flies, must go to one off the
+// map
+const MAX_SCHOOL_DIST: Distance = Distance::const_meters(5000.0);
 
 // A way to generate Scenarios
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -19,6 +31,7 @@ pub struct ScenarioGenerator {
     pub only_seed_buses: Option<BTreeSet<String>>,
     pub spawn_over_time: Vec<SpawnOverTime>,
     pub border_spawn_over_time: Vec<BorderSpawnOverTime>,
+    pub neighborhood_spawn_over_time: Vec<NeighborhoodSpawnOverTime>,
 }
 
 // SpawnOverTime and BorderSpawnOverTime should be kept separate. Agents in SpawnOverTime pick
@@ -41,6 +54,8 @@ pub struct BorderSpawnOverTime {
     pub num_peds: usize,
     pub num_cars: usize,
     pub num_bikes: usize,
+    #[serde(default)]
+    pub num_trucks: usize,
     pub percent_use_transit: f64,
     // TODO use https://docs.rs/rand/0.5.5/rand/distributions/struct.Normal.html
     pub start_time: Time,
@@ -49,6 +64,25 @@ pub struct BorderSpawnOverTime {
     pub goal: OriginDestination,
 }
 
+// People going between two neighborhoods, like commuters from a suburb to downtown. Unlike
+// SpawnOverTime, the mode is fixed up-front. Transit trips just walk when no bus helps.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct NeighborhoodSpawnOverTime {
+    pub from: Neighborhood,
+    pub to: Neighborhood,
+    pub mode: TripMode,
+    pub num_agents: usize,
+    pub departures: DepartureTimes,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum DepartureTimes {
+    // Spread evenly between these two times
+    Uniform(Time, Time),
+    // Clustered around a peak. Most people leave within this much time of it.
+    Peak(Time, Duration),
+}
+
 impl ScenarioGenerator {
     // TODO may need to fork the RNG a bit more
     pub fn generate(&self, map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) -> Scenario {
@@ -85,6 +119,19 @@ impl ScenarioGenerator {
                 map,
                 timer,
             );
+            if s.num_trucks > 0 {
+                // Separate, so adding trucks doesn't change everybody else
+                s.spawn_trucks(&mut abstutil::fork_rng(rng), &mut scenario, map, timer);
+            }
+        }
+
+        timer.start_iter(
+            "NeighborhoodSpawnOverTime",
+            self.neighborhood_spawn_over_time.len(),
+        );
+        for s in &self.neighborhood_spawn_over_time {
+            timer.next();
+            s.spawn_agents(rng, &mut scenario, map, timer);
         }
 
         timer.stop(format!("Generating scenario {}", self.scenario_name));
@@ -113,6 +160,7 @@ impl ScenarioGenerator {
                     num_peds: 10,
                     num_cars: 10,
                     num_bikes: 10,
+                    num_trucks: 0,
                     start_time: Time::START_OF_DAY,
                     stop_time: Time::START_OF_DAY + Duration::seconds(5.0),
                     start_from_border: i.some_outgoing_road(map).unwrap(),
@@ -120,6 +168,7 @@ impl ScenarioGenerator {
                     percent_use_transit: 0.5,
                 })
                 .collect(),
+            neighborhood_spawn_over_time: Vec::new(),
         };
         for i in map.all_outgoing_borders() {
             s.spawn_over_time.push(SpawnOverTime {
@@ -141,6 +190,7 @@ impl ScenarioGenerator {
             only_seed_buses: Some(BTreeSet::new()),
             spawn_over_time: Vec::new(),
             border_spawn_over_time: Vec::new(),
+            neighborhood_spawn_over_time: Vec::new(),
         }
     }
 
@@ -159,6 +209,7 @@ impl ScenarioGenerator {
                 percent_use_transit: 0.5,
             }],
             border_spawn_over_time: Vec::new(),
+            neighborhood_spawn_over_time: Vec::new(),
         }
     }
 }
@@ -338,6 +389,113 @@ impl BorderSpawnOverTime {
             }
         }
     }
+
+    // Trucks don't park, so they always leave through some border.
+    fn spawn_trucks(
+        &self,
+        rng: &mut XorShiftRng,
+        scenario: &mut Scenario,
+        map: &Map,
+        timer: &mut Timer,
+    ) {
+        for _ in 0..self.num_trucks {
+            let depart = rand_time(rng, self.start_time, self.stop_time);
+            let goal = match self.goal {
+                OriginDestination::EndOfRoad(_) => {
+                    self.goal
+                        .pick_driving_goal(PathConstraints::Car, map, rng, timer)
+                }
+                _ => map
+                    .all_outgoing_borders()
+                    .choose(rng)
+                    .and_then(|i| i.some_incoming_road(map))
+                    .and_then(|dr| DrivingGoal::end_at_border(dr, PathConstraints::Car, None, map)),
+            };
+            if let Some(goal) = goal {
+                let id = PersonID(scenario.people.len());
+                scenario.people.push(PersonSpec {
+                    id,
+                    orig_id: None,
+                    trips: vec![IndividTrip::new(
+                        depart,
+                        SpawnTrip::Freight {
+                            dr: self.start_from_border,
+                            goal,
+                        },
+                    )],
+                });
+            }
+        }
+    }
+}
+
+impl NeighborhoodSpawnOverTime {
+    fn spawn_agents(
+        &self,
+        rng: &mut XorShiftRng,
+        scenario: &mut Scenario,
+        map: &Map,
+        timer: &mut Timer,
+    ) {
+        let from_bldgs = self.from.buildings(map);
+        let to_bldgs = self.to.buildings(map);
+        if from_bldgs.is_empty() || to_bldgs.is_empty() {
+            timer.warn(format!(
+                "No buildings in {} or {}, so skipping {} trips between them",
+                self.from.name, self.to.name, self.num_agents
+            ));
+            return;
+        }
+
+        for _ in 0..self.num_agents {
+            let depart = self.departures.sample(rng);
+            let from_bldg = *from_bldgs.choose(rng).unwrap();
+            let to_bldg = *to_bldgs.choose(rng).unwrap();
+            let trip = match self.mode {
+                TripMode::Drive => {
+                    SpawnTrip::UsingParkedCar(from_bldg, DrivingGoal::ParkNear(to_bldg))
+                }
+                TripMode::Bike => SpawnTrip::UsingBike(from_bldg, DrivingGoal::ParkNear(to_bldg)),
+                TripMode::Walk | TripMode::Transit => {
+                    if from_bldg == to_bldg {
+                        timer.warn("Skipping walking trip between same two buildings".to_string());
+                        continue;
+                    }
+                    let start = SidewalkSpot::building(from_bldg, map);
+                    let goal = SidewalkSpot::building(to_bldg, map);
+                    let transit = if self.mode == TripMode::Transit {
+                        map.should_use_transit(start.sidewalk_pos, goal.sidewalk_pos)
+                    } else {
+                        None
+                    };
+                    if let Some((stop1, stop2, route)) = transit {
+                        SpawnTrip::UsingTransit(start, goal, route, stop1, stop2)
+                    } else {
+                        SpawnTrip::JustWalking(start, goal)
+                    }
+                }
+            };
+            scenario.people.push(PersonSpec {
+                id: PersonID(scenario.people.len()),
+                orig_id: None,
+                trips: vec![IndividTrip::new(depart, trip)],
+            });
+        }
+    }
+}
+
+impl DepartureTimes {
+    fn sample(self, rng: &mut XorShiftRng) -> Time {
+        match self {
+            DepartureTimes::Uniform(low, high) => rand_time(rng, low, high),
+            DepartureTimes::Peak(peak, spread) => {
+                // About 95% of people leave within two standard deviations of the peak
+                let normal =
+                    Normal::new(peak.inner_seconds(), spread.inner_seconds() / 2.0).unwrap();
+                Time::START_OF_DAY + Duration::seconds(normal.sample(rng).max(0.0))
+            }
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -401,7 +559,8 @@ fn rand_time(rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
     Time::START_OF_DAY + Duration::seconds(rng.gen_range(low.inner_seconds(), high.inner_seconds()))
 }
 
-fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
+// has_car is false when nobody in the household has a car free.
+fn select_trip_mode(distance: Distance, has_car: bool, rng: &mut XorShiftRng) -> TripMode {
     // TODO Make this probabilistic
     // for example probability of walking currently has massive differences
     // at thresholds, it would be nicer to change this graduall
@@ -412,8 +571,8 @@ fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     // in some cities there may case where driving is only possible method
     // to get somewhere, even at a short distance
     if distance < Distance::miles(0.5) {
-        return TripMode::Walk
-    } 
+        return TripMode::Walk;
+    }
     if rng.gen_bool(0.005) {
         // low chance for really, really dedicated cyclists
         return TripMode::Bike;
@@ -425,12 +584,16 @@ fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     }
     if distance < Distance::miles(3.0) {
         if rng.gen_bool(0.15) {
-            return TripMode::Bike
+            return TripMode::Bike;
         }
         if rng.gen_bool(0.05) {
-            return TripMode::Walk
+            return TripMode::Walk;
         }
     }
+    if !has_car {
+        // Like above, transit degrades into walking
+        return TripMode::Transit;
+    }
     return TripMode::Drive;
 }
 
@@ -477,7 +640,7 @@ impl ScenarioGenerator {
             // having random trip distance happening offscreen will allow things
             // like very short car trips, representing larger car trip happening mostly offscreen
             let distance_outside_map = Distance::meters(rng.gen_range(0.0, 20_000.0));
-            let mode = select_trip_mode(distance_on_map + distance_outside_map, rng);
+            let mode = select_trip_mode(distance_on_map + distance_outside_map, true, rng);
             let (goto_work, return_home) = match (
                 SpawnTrip::new(
                     TripEndpoint::Border(random_incoming_border.id, None),
@@ -519,84 +682,263 @@ impl ScenarioGenerator {
         for (home, num_ppl) in residences {
             for _ in 0..num_ppl {
                 timer.next();
-                // Make a person going from their home to a random workplace, then back again later.
-
                 let work = *workplaces.choose(rng).unwrap();
-                // Decide mode based on walking distance.
-                let dist = if let Some(path) = map.pathfind(PathRequest {
-                    start: map.get_b(home).front_path.sidewalk,
-                    end: map.get_b(work).front_path.sidewalk,
-                    constraints: PathConstraints::Pedestrian,
-                }) {
-                    path.total_length()
-                } else {
-                    // Woops, the buildings aren't connected. Probably a bug in importing. Just skip
-                    // this person.
-                    continue;
-                };
-                if home == work {
-                    // working and living in the same building
-                    continue;
+                if let Some((trips, _)) = activity_chain(map, home, work, &workplaces, true, rng) {
+                    s.people.push(PersonSpec {
+                        id: PersonID(s.people.len()),
+                        orig_id: None,
+                        trips,
+                    });
                 }
-                let mode = select_trip_mode(dist, rng);
+            }
+        }
+        s
+    }
+}
+
+impl ScenarioGenerator {
+    // Like proletariat_robot, but people live and work where the census says. People without a
+    // job in the map stay home for now. Everybody living in the same building is one household, and
+    // if car ownership is specified, only as many of them as there are cars can drive.
+    pub fn from_population(
+        map: &Map,
+        population: &Population,
+        ownership: Option<&CarOwnership>,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let mut s = Scenario::empty(map, "census people going to/from work");
+        s.only_seed_buses = None;
+        let errands: Vec<BuildingID> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| match b.bldg_type {
+                BuildingType::Commercial | BuildingType::ResidentialCommercial(_) => true,
+                _ => false,
+            })
+            .map(|b| b.id)
+            .collect();
+        let neighborhoods: Vec<(String, Polygon)> = Neighborhood::load_all(map.get_name())
+            .into_iter()
+            .filter_map(|n| n.polygon(map).map(|poly| (n.name, poly)))
+            .collect();
+
+        let mut households: BTreeMap<BuildingID, Vec<BuildingID>> = BTreeMap::new();
+        for resident in &population.people {
+            if let Some(work) = resident.work {
+                households
+                    .entry(resident.home)
+                    .or_insert_with(Vec::new)
+                    .push(work);
+            }
+        }
+
+        timer.start_iter("create households", households.len());
+        for (home, jobs) in households {
+            timer.next();
+            // None means unlimited
+            let mut cars_left = ownership.map(|o| {
+                let center = map.get_b(home).polygon.center();
+                let rate = neighborhoods
+                    .iter()
+                    .find(|(_, poly)| poly.contains_pt(center))
+                    .and_then(|(name, _)| o.per_neighborhood.get(name))
+                    .cloned()
+                    .unwrap_or(o.cars_per_household);
+                // A rate of 1.5 means every household has one car, and half of them have a
+                // second.
+                let mut cars = rate.floor() as usize;
+                if rng.gen_bool(rate.fract()) {
+                    cars += 1;
+                }
+                cars
+            });
+            for work in jobs {
+                // Once somebody takes a car for the day, nobody else can use it.
+                let has_car = cars_left.map(|n| n > 0).unwrap_or(true);
+                if let Some((trips, mode)) = activity_chain(map, home, work, &errands, has_car, rng)
+                {
+                    if mode == TripMode::Drive {
+                        if let Some(ref mut n) = cars_left {
+                            *n -= 1;
+                        }
+                    }
+                    s.people.push(PersonSpec {
+                        id: PersonID(s.people.len()),
+                        orig_id: None,
+                        trips,
+                    });
+                }
+            }
+        }
+        s
+    }
+
+    // Some residents of every home are students, going to the closest school, college, or
+    // kindergarten in the morning and coming home in the afternoon. Maps without any schools
+    // tagged in OSM get no trips.
+    pub fn school_trips(map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) -> Scenario {
+        let mut s = Scenario::empty(map, "students going to/from school");
+        s.only_seed_buses = None;
+        let schools: Vec<&Building> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| b.has_amenity(AmenityType::Education))
+            .collect();
+        if schools.is_empty() {
+            timer.warn(format!("{} has no schools", map.get_name()));
+            return s;
+        }
 
-                // TODO This will cause a single morning and afternoon rush. Outside of these times,
-                // it'll be really quiet. Probably want a normal distribution centered around these
-                // peak times, but with a long tail.
-                let mut depart_am = rand_time(
+        timer.start_iter("create students", map.all_buildings().len());
+        for home in map.all_buildings() {
+            timer.next();
+            let num_ppl = match home.bldg_type {
+                BuildingType::Residential(n) | BuildingType::ResidentialCommercial(n) => n,
+                BuildingType::Commercial | BuildingType::Empty => 0,
+            };
+            if num_ppl == 0 {
+                continue;
+            }
+            // Only pathfind to the school that's closest in a straight line, and only if it's
+            // near enough
+            let center = home.polygon.center();
+            let school = schools
+                .iter()
+                .min_by_key(|b| b.polygon.center().dist_to(center))
+                .unwrap();
+            if school.id == home.id || school.polygon.center().dist_to(center) > MAX_SCHOOL_DIST {
+                continue;
+            }
+            let dist = match map.pathfind(PathRequest {
+                start: home.front_path.sidewalk,
+                end: school.front_path.sidewalk,
+                constraints: PathConstraints::Pedestrian,
+            }) {
+                Some(path) => path.total_length(),
+                None => continue,
+            };
+
+            for _ in 0..num_ppl {
+                if !rng.gen_bool(STUDENT_PROBABILITY) {
+                    continue;
+                }
+                // Younger students get driven by someone, so the household's car is fine to use.
+                let mode = select_trip_mode(dist, true, rng);
+                let depart_am = rand_time(
                     rng,
                     Time::START_OF_DAY + Duration::hours(7),
-                    Time::START_OF_DAY + Duration::hours(10),
+                    Time::START_OF_DAY + Duration::minutes(8 * 60 + 15),
                 );
-                let mut depart_pm = rand_time(
+                let depart_pm = rand_time(
                     rng,
-                    Time::START_OF_DAY + Duration::hours(17),
-                    Time::START_OF_DAY + Duration::hours(19),
+                    Time::START_OF_DAY + Duration::minutes(14 * 60 + 30),
+                    Time::START_OF_DAY + Duration::hours(16),
                 );
-
-                if rng.gen_bool(0.1) {
-                    // hacky hack to get some background traffic
-                    depart_am = rand_time(
-                        rng,
-                        Time::START_OF_DAY + Duration::hours(0),
-                        Time::START_OF_DAY + Duration::hours(12),
-                    );
-                    depart_pm = rand_time(
-                        rng,
-                        Time::START_OF_DAY + Duration::hours(12),
-                        Time::START_OF_DAY + Duration::hours(24),
-                    );
-                }
-
-                let (goto_work, return_home) = match (
+                if let (Some(goto_school), Some(return_home)) = (
                     SpawnTrip::new(
-                        TripEndpoint::Bldg(home),
-                        TripEndpoint::Bldg(work),
+                        TripEndpoint::Bldg(home.id),
+                        TripEndpoint::Bldg(school.id),
                         mode,
                         map,
                     ),
                     SpawnTrip::new(
-                        TripEndpoint::Bldg(work),
-                        TripEndpoint::Bldg(home),
+                        TripEndpoint::Bldg(school.id),
+                        TripEndpoint::Bldg(home.id),
                         mode,
                         map,
                     ),
                 ) {
-                    (Some(t1), Some(t2)) => (t1, t2),
-                    // Skip the person if either trip can't be created.
-                    _ => continue,
-                };
-
-                s.people.push(PersonSpec {
-                    id: PersonID(s.people.len()),
-                    orig_id: None,
-                    trips: vec![
-                        IndividTrip::new(depart_am, goto_work),
-                        IndividTrip::new(depart_pm, return_home),
-                    ],
-                });
+                    s.people.push(PersonSpec {
+                        id: PersonID(s.people.len()),
+                        orig_id: None,
+                        trips: vec![
+                            IndividTrip::new(depart_am, goto_school),
+                            IndividTrip::new(depart_pm, return_home),
+                        ],
+                    });
+                }
             }
         }
         s
     }
 }
+
+// Going from home to work, then back again later, sometimes running an errand on the way home.
+// The whole chain uses one mode, so the same car or bike goes everywhere. None if any trip is
+// impossible.
+fn activity_chain(
+    map: &Map,
+    home: BuildingID,
+    work: BuildingID,
+    errands: &Vec<BuildingID>,
+    has_car: bool,
+    rng: &mut XorShiftRng,
+) -> Option<(Vec<IndividTrip>, TripMode)> {
+    // Decide mode based on walking distance.
+    let dist = if let Some(path) = map.pathfind(PathRequest {
+        start: map.get_b(home).front_path.sidewalk,
+        end: map.get_b(work).front_path.sidewalk,
+        constraints: PathConstraints::Pedestrian,
+    }) {
+        path.total_length()
+    } else {
+        // Woops, the buildings aren't connected. Probably a bug in importing. Just skip this
+        // person.
+        return None;
+    };
+    if home == work {
+        // working and living in the same building
+        return None;
+    }
+    let mode = select_trip_mode(dist, has_car, rng);
+
+    // TODO This will cause a single morning and afternoon rush. Outside of these times, it'll be
+    // really quiet. Probably want a normal distribution centered around these peak times, but
+    // with a long tail.
+    let mut depart_am = rand_time(
+        rng,
+        Time::START_OF_DAY + Duration::hours(7),
+        Time::START_OF_DAY + Duration::hours(10),
+    );
+    let mut depart_pm = rand_time(
+        rng,
+        Time::START_OF_DAY + Duration::hours(17),
+        Time::START_OF_DAY + Duration::hours(19),
+    );
+
+    if rng.gen_bool(0.1) {
+        // hacky hack to get some background traffic
+        depart_am = rand_time(
+            rng,
+            Time::START_OF_DAY + Duration::hours(0),
+            Time::START_OF_DAY + Duration::hours(12),
+        );
+        depart_pm = rand_time(
+            rng,
+            Time::START_OF_DAY + Duration::hours(12),
+            Time::START_OF_DAY + Duration::hours(24),
+        );
+    }
+
+    let mut stops = vec![(depart_am, home), (depart_pm, work)];
+    if rng.gen_bool(ERRAND_PROBABILITY) {
+        if let Some(errand) = errands.choose(rng) {
+            if *errand != home && *errand != work {
+                // Includes getting there. If it takes longer, the trip home just starts late.
+                let depart = depart_pm + Duration::minutes(rng.gen_range(30, 120));
+                stops.push((depart, *errand));
+            }
+        }
+    }
+
+    let mut trips = Vec::new();
+    for (idx, (depart, from)) in stops.iter().enumerate() {
+        let to = stops.get(idx + 1).map(|(_, b)| *b).unwrap_or(home);
+        trips.push(IndividTrip::new(
+            *depart,
+            SpawnTrip::new(TripEndpoint::Bldg(*from), TripEndpoint::Bldg(to), mode, map)?,
+        ));
+    }
+    Some((trips, mode))
+}
@@ -47,6 +47,14 @@ impl SimFlags {
                     })
                     .unwrap_or(AlertHandler::Print),
                 pathfinding_upfront: args.enabled("--pathfinding_upfront"),
+                check_invariants: args.enabled("--check_invariants"),
+                fixed_routes: args.enabled("--fixed_routes"),
+                tolls: args.optional("--tolls"),
+                incidents: args.optional("--incidents"),
+                bus_lane_violators: args
+                    .optional_parse("--bus_lane_violators", |s| s.parse::<f64>())
+                    .unwrap_or(0.0),
+                detect_gridlock: !args.enabled("--disable_gridlock_detection"),
             },
         }
     }
@@ -78,6 +86,8 @@ impl SimFlags {
             timer.note(format!("Resuming from {}", self.load));
 
             let mut sim: Sim = abstutil::read_binary(self.load.clone(), timer);
+            // Useful for reproducing a violation from the state saved when it was found
+            sim.check_invariants = opts.check_invariants;
 
             let mut map = Map::new(abstutil::path_map(&sim.map_name), timer);
             if sim.edits_name != "untitled edits" {
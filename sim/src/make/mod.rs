@@ -1,13 +1,16 @@
 mod generator;
 mod load;
 mod modifier;
+mod population;
 mod scenario;
 mod spawner;
 
 pub use self::generator::{
-    BorderSpawnOverTime, OriginDestination, ScenarioGenerator, SpawnOverTime,
+    BorderSpawnOverTime, DepartureTimes, NeighborhoodSpawnOverTime, OriginDestination,
+    ScenarioGenerator, SpawnOverTime,
 };
 pub use self::load::SimFlags;
 pub use self::modifier::ScenarioModifier;
+pub use self::population::{CarOwnership, Population, Resident};
 pub use self::scenario::{IndividTrip, OffMapLocation, PersonSpec, Scenario, SpawnTrip};
 pub use self::spawner::{TripSpawner, TripSpec};
@@ -1,6 +1,6 @@
-use crate::{IndividTrip, Scenario, SpawnTrip, TripMode};
+use crate::{IndividTrip, Scenario, SpawnTrip, TripEndpoint, TripMode};
 use geom::{Duration, Time};
-use map_model::Map;
+use map_model::{IntersectionID, LaneID, Map, Neighborhood};
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
 use std::collections::BTreeSet;
@@ -15,6 +15,20 @@ pub enum ScenarioModifier {
         departure_filter: (Time, Time),
         from_modes: BTreeSet<TripMode>,
     },
+    // Only keep people with at least one trip starting in the neighborhood
+    StartingInNeighborhood(Neighborhood),
+    // Everybody inside the region when the evacuation starts heads for the closest exit, leaving
+    // sometime during the window. Whatever else they had planned that day is dropped.
+    Evacuate {
+        region: Neighborhood,
+        // Borders
+        exits: BTreeSet<IntersectionID>,
+        start: Time,
+        window: Duration,
+        // Lanes reversed to head toward the exits, with the intersection each one now leads to.
+        // These are map edits, made when the evacuation is planned and undone if it's removed.
+        contraflow: Vec<(LaneID, IntersectionID)>,
+    },
 }
 
 impl ScenarioModifier {
@@ -39,7 +53,7 @@ impl ScenarioModifier {
                         if trip.depart < departure_filter.0 || trip.depart > departure_filter.1 {
                             continue;
                         }
-                        if !from_modes.contains(&trip.trip.mode()) {
+                        if !from_modes.contains(&trip.trip.mode()) || trip.trip.is_freight() {
                             continue;
                         }
                         if let Some(new) =
@@ -52,6 +66,14 @@ impl ScenarioModifier {
                 }
                 s
             }
+            ScenarioModifier::StartingInNeighborhood(n) => starting_in_neighborhood(s, n, map),
+            ScenarioModifier::Evacuate {
+                region,
+                exits,
+                start,
+                window,
+                ..
+            } => evacuate(s, region, exits, *start, *window, map, rng),
         }
     }
 
@@ -74,6 +96,22 @@ impl ScenarioModifier {
                 departure_filter.1.ampm_tostring(),
                 to_mode.verb()
             ),
+            ScenarioModifier::StartingInNeighborhood(n) => {
+                format!("only keep people with a trip starting in {}", n.name)
+            }
+            ScenarioModifier::Evacuate {
+                region,
+                exits,
+                start,
+                window,
+                ..
+            } => format!(
+                "evacuate {} through {} exits, leaving between {} and {}",
+                region.name,
+                exits.len(),
+                start.ampm_tostring(),
+                (*start + *window).ampm_tostring()
+            ),
         }
     }
 }
@@ -118,3 +156,86 @@ fn cancel_people(mut s: Scenario, pct: usize, rng: &mut XorShiftRng) -> Scenario
     }
     s
 }
+
+fn starting_in_neighborhood(mut s: Scenario, n: &Neighborhood, map: &Map) -> Scenario {
+    // If the neighborhood is off the map, nobody starts there
+    let poly = n.polygon(map);
+    for person in &mut s.people {
+        if let Some(ref poly) = poly {
+            if person
+                .trips
+                .iter()
+                .any(|trip| poly.contains_pt(trip.trip.start(map).pt(map)))
+            {
+                continue;
+            }
+        }
+        // Same problem as cancel_people; a person's trips are all or nothing.
+        for trip in &mut person.trips {
+            trip.modified = true;
+            trip.cancelled = true;
+        }
+    }
+    s
+}
+
+fn evacuate(
+    mut s: Scenario,
+    region: &Neighborhood,
+    exits: &BTreeSet<IntersectionID>,
+    start: Time,
+    window: Duration,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> Scenario {
+    let poly = if let Some(poly) = region.polygon(map) {
+        poly
+    } else {
+        // Nobody to evacuate
+        return s;
+    };
+    // Nothing lines up with the normal day anymore
+    s.scenario_name = format!("{} (evacuating {})", s.scenario_name, region.name);
+    for person in &mut s.people {
+        if person.trips.is_empty() {
+            continue;
+        }
+        // Where is everybody when the evacuation starts? Somebody still in the middle of a trip
+        // finishes it first.
+        let keep = person.trips.iter().take_while(|t| t.depart < start).count();
+        let from = if keep == 0 {
+            person.trips[0].trip.start(map)
+        } else {
+            person.trips[keep - 1].trip.end(map)
+        };
+        if let TripEndpoint::Border(_, _) = from {
+            continue;
+        }
+        let pt = from.pt(map);
+        if !poly.contains_pt(pt) {
+            continue;
+        }
+        let exit = match exits
+            .iter()
+            .min_by_key(|i| map.get_i(**i).polygon.center().dist_to(pt))
+        {
+            Some(i) => *i,
+            None => {
+                continue;
+            }
+        };
+
+        // Drive out if possible
+        let to = TripEndpoint::Border(exit, None);
+        if let Some(trip) = SpawnTrip::new(from.clone(), to.clone(), TripMode::Drive, map)
+            .or_else(|| SpawnTrip::new(from, to, TripMode::Walk, map))
+        {
+            let depart = start + Duration::seconds(rng.gen_range(0.0, window.inner_seconds()));
+            person.trips.truncate(keep);
+            let mut evacuation = IndividTrip::new(depart, trip);
+            evacuation.modified = true;
+            person.trips.push(evacuation);
+        }
+    }
+    s
+}
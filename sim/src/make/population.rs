@@ -0,0 +1,60 @@
+use abstutil::Timer;
+use map_model::{BuildingID, Map};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Error;
+
+// Everybody living in a map, synthesized by the importer from block-level population and
+// employment counts. ScenarioGenerator::from_population turns this into trips.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Population {
+    pub map_name: String,
+    pub people: Vec<Resident>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Resident {
+    pub home: BuildingID,
+    // None if they don't work inside the map
+    pub work: Option<BuildingID>,
+}
+
+impl Population {
+    pub fn load(map: &Map, timer: &mut Timer) -> Result<Population, Error> {
+        abstutil::maybe_read_binary(abstutil::path_population(map.get_name()), timer)
+    }
+
+    pub fn save(&self) {
+        abstutil::write_binary(abstutil::path_population(&self.map_name), self);
+    }
+}
+
+// How many cars households have, on average. They're shared, so once somebody takes the car for the
+// day, the rest of the household has to get around another way.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CarOwnership {
+    pub cars_per_household: f64,
+    // Overrides the default in some neighborhoods, keyed by name
+    pub per_neighborhood: BTreeMap<String, f64>,
+}
+
+impl CarOwnership {
+    // None if this map doesn't have any, meaning there's no limit. Fails if the file is malformed
+    // or has a negative rate.
+    pub fn load(map: &Map, timer: &mut Timer) -> Result<Option<CarOwnership>, String> {
+        let path = abstutil::path_car_ownership(map.get_name());
+        if !abstutil::file_exists(path.clone()) {
+            return Ok(None);
+        }
+        let ownership: CarOwnership = abstutil::maybe_read_json(path.clone(), timer)
+            .map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+        for rate in std::iter::once(&ownership.cars_per_household)
+            .chain(ownership.per_neighborhood.values())
+        {
+            if !rate.is_finite() || *rate < 0.0 {
+                return Err(format!("{} has a bad rate {}", path, rate));
+            }
+        }
+        Ok(Some(ownership))
+    }
+}
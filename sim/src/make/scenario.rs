@@ -1,7 +1,7 @@
 use crate::{
     CarID, DrivingGoal, OrigPersonID, ParkingSpot, PersonID, SidewalkPOI, SidewalkSpot, Sim,
     TripEndpoint, TripMode, TripSpec, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH,
-    MAX_CAR_LENGTH, MIN_CAR_LENGTH,
+    MAX_CAR_LENGTH, MIN_CAR_LENGTH, TRUCK_LENGTH,
 };
 use abstutil::{prettyprint_usize, Counter, Timer};
 use geom::{Distance, Duration, LonLat, Speed, Time};
@@ -79,6 +79,12 @@ pub enum SpawnTrip {
         trip_time: Duration,
         mode: TripMode,
     },
+    // A truck passing through or making a delivery. It appears at one border and leaves through
+    // another, staying on roads that allow trucks.
+    Freight {
+        dr: DirectedRoadID,
+        goal: DrivingGoal,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -110,9 +116,15 @@ impl Scenario {
         timer.start_iter("trips for People", self.people.len());
         let mut spawner = sim.make_spawner();
         let mut parked_cars: Vec<(Vehicle, BuildingID)> = Vec::new();
-        for p in &self.people {
+        // Separate, so tolls don't change anything else about the scenario
+        let mut toll_rng = sim.get_tolls().map(|_| abstutil::fork_rng(rng));
+        for orig in &self.people {
             timer.next();
 
+            let switched = sim
+                .get_tolls()
+                .and_then(|tolls| tolls.maybe_switch_modes(orig, map, toll_rng.as_mut().unwrap()));
+            let p = switched.as_ref().unwrap_or(orig);
             if let Err(err) = p.check_schedule(map) {
                 panic!("{}", err);
             }
@@ -183,6 +195,19 @@ impl Scenario {
         }
     }
 
+    pub fn rand_truck(rng: &mut XorShiftRng) -> VehicleSpec {
+        let max_speed = Some(Scenario::rand_speed(
+            rng,
+            Speed::miles_per_hour(45.0),
+            Speed::miles_per_hour(55.0),
+        ));
+        VehicleSpec {
+            vehicle_type: VehicleType::Truck,
+            length: TRUCK_LENGTH,
+            max_speed,
+        }
+    }
+
     pub fn rand_bike(rng: &mut XorShiftRng) -> VehicleSpec {
         let max_speed = Some(Scenario::rand_speed(
             rng,
@@ -376,40 +401,10 @@ impl SpawnTrip {
                 origin: None,
             },
             SpawnTrip::FromBorder {
-                dr,
-                goal,
-                is_bike,
-                origin,
-            } => {
-                let constraints = if is_bike {
-                    PathConstraints::Bike
-                } else {
-                    PathConstraints::Car
-                };
-                match dr
-                    .lanes(constraints, map)
-                    .choose(rng)
-                    .ok_or_else(|| {
-                        format!("{} has no lanes to spawn a {:?}", dr.id, constraints).into()
-                    })
-                    // TODO We could be more precise and say exactly what vehicle will be used here
-                    .and_then(|l| TripSpec::spawn_vehicle_at(Position::start(*l), is_bike, map))
-                {
-                    Ok(start_pos) => TripSpec::VehicleAppearing {
-                        start_pos,
-                        goal,
-                        use_vehicle: use_vehicle.unwrap(),
-                        retry_if_no_room: true,
-                        origin,
-                    },
-                    Err(err) => TripSpec::NoRoomToSpawn {
-                        i: dr.src_i(map),
-                        goal,
-                        use_vehicle: use_vehicle.unwrap(),
-                        origin,
-                        error: err.to_string(),
-                    },
-                }
+                dr, goal, origin, ..
+            } => appear_at_border(dr, goal, use_vehicle.unwrap(), origin, rng, map),
+            SpawnTrip::Freight { dr, goal } => {
+                appear_at_border(dr, goal, use_vehicle.unwrap(), None, rng, map)
             }
             SpawnTrip::UsingParkedCar(start_bldg, goal) => TripSpec::UsingParkedCar {
                 start_bldg,
@@ -466,6 +461,7 @@ impl SpawnTrip {
             SpawnTrip::UsingTransit(_, _, _, _, _) => TripMode::Transit,
             // TODO Uh...
             SpawnTrip::Remote { .. } => TripMode::Drive,
+            SpawnTrip::Freight { .. } => TripMode::Drive,
         }
     }
 
@@ -477,6 +473,7 @@ impl SpawnTrip {
             SpawnTrip::FromBorder { dr, ref origin, .. } => {
                 TripEndpoint::Border(dr.src_i(map), origin.clone())
             }
+            SpawnTrip::Freight { dr, .. } => TripEndpoint::Border(dr.src_i(map), None),
             SpawnTrip::UsingParkedCar(b, _) => TripEndpoint::Bldg(*b),
             SpawnTrip::UsingBike(b, _) => TripEndpoint::Bldg(*b),
             SpawnTrip::JustWalking(ref spot, _) | SpawnTrip::UsingTransit(ref spot, _, _, _, _) => {
@@ -500,6 +497,7 @@ impl SpawnTrip {
         match self {
             SpawnTrip::VehicleAppearing { ref goal, .. }
             | SpawnTrip::FromBorder { ref goal, .. }
+            | SpawnTrip::Freight { ref goal, .. }
             | SpawnTrip::UsingParkedCar(_, ref goal)
             | SpawnTrip::UsingBike(_, ref goal) => match goal {
                 DrivingGoal::ParkNear(b) => TripEndpoint::Bldg(*b),
@@ -519,6 +517,11 @@ impl SpawnTrip {
         }
     }
 
+    // Freight keeps its mode, no matter what policies people react to
+    pub fn is_freight(&self) -> bool {
+        matches!(self, SpawnTrip::Freight { .. })
+    }
+
     pub fn new(
         from: TripEndpoint,
         to: TripEndpoint,
@@ -568,6 +571,38 @@ impl SpawnTrip {
     }
 }
 
+fn appear_at_border(
+    dr: DirectedRoadID,
+    goal: DrivingGoal,
+    use_vehicle: CarID,
+    origin: Option<OffMapLocation>,
+    rng: &mut XorShiftRng,
+    map: &Map,
+) -> TripSpec {
+    let constraints = use_vehicle.1.to_constraints();
+    match dr
+        .lanes(constraints, map)
+        .choose(rng)
+        .ok_or_else(|| format!("{} has no lanes to spawn a {:?}", dr.id, constraints).into())
+        .and_then(|l| TripSpec::spawn_vehicle_at(Position::start(*l), use_vehicle.1, map))
+    {
+        Ok(start_pos) => TripSpec::VehicleAppearing {
+            start_pos,
+            goal,
+            use_vehicle,
+            retry_if_no_room: true,
+            origin,
+        },
+        Err(err) => TripSpec::NoRoomToSpawn {
+            i: dr.src_i(map),
+            goal,
+            use_vehicle,
+            origin,
+            error: err.to_string(),
+        },
+    }
+}
+
 impl PersonSpec {
     // Verify that the trip start/endpoints of the person match up
     fn check_schedule(&self, map: &Map) -> Result<(), String> {
@@ -709,6 +744,11 @@ impl PersonSpec {
                 }
                 SpawnTrip::JustWalking(_, _) | SpawnTrip::UsingTransit(_, _, _, _, _) => None,
                 SpawnTrip::Remote { .. } => None,
+                // Every delivery gets its own truck
+                SpawnTrip::Freight { .. } => {
+                    vehicle_specs.push(Scenario::rand_truck(rng));
+                    Some(vehicle_specs.len() - 1)
+                }
             };
             vehicle_foreach_trip.push(use_for_trip);
         }
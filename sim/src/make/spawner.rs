@@ -1,11 +1,13 @@
 use crate::{
     CarID, Command, DrivingGoal, OffMapLocation, Person, PersonID, Scheduler, SidewalkSpot,
     TripEndpoint, TripLeg, TripManager, TripMode, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH,
+    TRUCK_LENGTH,
 };
 use abstutil::Timer;
 use geom::{Duration, Time, EPSILON_DIST};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathConstraints, PathRequest, Position,
+    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathConstraints, PathRequest,
+    Position,
 };
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -205,7 +207,7 @@ impl TripSpawner {
         if profile {
             abstutil::start_profiler();
         }
-        let paths = timer.parallelize(
+        let mut paths = timer.parallelize(
             "calculate paths",
             std::mem::replace(&mut self.trips, Vec::new()),
             |tuple| {
@@ -213,7 +215,7 @@ impl TripSpawner {
                 (
                     tuple,
                     req.clone(),
-                    if pathfinding_upfront {
+                    if pathfinding_upfront && !tuple.2.is_truck() {
                         req.and_then(|r| map.pathfind(r))
                     } else {
                         None
@@ -221,6 +223,28 @@ impl TripSpawner {
                 )
             },
         );
+        // Trucks can't use the contraction hierarchy, so route all of them at once. Trucks from the
+        // same border share one search.
+        if pathfinding_upfront {
+            let mut truck_trips = Vec::new();
+            let mut truck_reqs = Vec::new();
+            for (idx, (tuple, maybe_req, _)) in paths.iter().enumerate() {
+                if let Some(req) = maybe_req {
+                    if tuple.2.is_truck() {
+                        truck_trips.push(idx);
+                        truck_reqs.push(req.clone());
+                    }
+                }
+            }
+            timer.start("calculate paths for trucks");
+            for (idx, path) in truck_trips
+                .into_iter()
+                .zip(map.pathfind_many_for_truck(truck_reqs))
+            {
+                paths[idx].2 = path;
+            }
+            timer.stop("calculate paths for trucks");
+        }
         if profile {
             abstutil::stop_profiler();
         }
@@ -381,11 +405,15 @@ impl TripSpec {
     // If possible, fixes problems that schedule_trip would hit.
     pub fn spawn_vehicle_at(
         pos: Position,
-        is_bike: bool,
+        vehicle_type: VehicleType,
         map: &Map,
     ) -> Result<Position, Box<dyn Error>> {
         let lane_len = map.get_l(pos.lane()).length();
-        let vehicle_len = if is_bike { BIKE_LENGTH } else { MAX_CAR_LENGTH };
+        let vehicle_len = match vehicle_type {
+            VehicleType::Bike => BIKE_LENGTH,
+            VehicleType::Truck => TRUCK_LENGTH,
+            _ => MAX_CAR_LENGTH,
+        };
         // There's no hope.
         if lane_len <= vehicle_len {
             return Err(format!(
@@ -447,4 +475,19 @@ impl TripSpec {
             TripSpec::Remote { .. } => None,
         }
     }
+
+    pub(crate) fn pathfind(&self, req: PathRequest, map: &Map) -> Option<Path> {
+        if self.is_truck() {
+            map.pathfind_for_truck(req)
+        } else {
+            map.pathfind(req)
+        }
+    }
+
+    fn is_truck(&self) -> bool {
+        match self {
+            TripSpec::VehicleAppearing { use_vehicle, .. } => use_vehicle.1 == VehicleType::Truck,
+            _ => false,
+        }
+    }
 }
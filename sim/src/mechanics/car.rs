@@ -2,7 +2,7 @@ use crate::{
     CarStatus, DistanceInterval, DrawCarInput, ParkingSpot, PersonID, Router, TimeInterval,
     TransitSimState, TripID, Vehicle, VehicleType,
 };
-use geom::{Distance, Duration, PolyLine, Time};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
 use map_model::{Map, Traversable};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -44,7 +44,7 @@ impl Car {
         map: &Map,
     ) -> CarState {
         let on = self.router.head();
-        let mut speed = on.speed_limit(map);
+        let mut speed = on.speed_limit(start_time, map);
         if let Some(s) = self.vehicle.max_speed {
             speed = speed.min(s);
         }
@@ -229,6 +229,11 @@ impl Car {
             } else {
                 None
             },
+            // Cars leaving a parking spot already slide out of it
+            age: match self.state {
+                CarState::Unparking(_, _, _) => None,
+                _ => Some(now - self.started_at),
+            },
             body,
         }
     }
@@ -263,4 +268,19 @@ impl CarState {
             CarState::IdlingAtStop(_, ref time_int) => time_int.end,
         }
     }
+
+    // Averaged over the current interval. Zero unless crossing.
+    pub fn get_speed(&self) -> Speed {
+        match self {
+            CarState::Crossing(ref time_int, ref dist_int) => {
+                let dt = time_int.end - time_int.start;
+                if dt > Duration::ZERO {
+                    Speed::from_dist_time(dist_int.length(), dt)
+                } else {
+                    Speed::ZERO
+                }
+            }
+            _ => Speed::ZERO,
+        }
+    }
 }
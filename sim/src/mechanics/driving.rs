@@ -7,10 +7,10 @@ use crate::{
     FOLLOWING_DISTANCE,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::{Distance, Duration, PolyLine, Time};
-use map_model::{LaneID, Map, Path, PathStep, Traversable};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
+use map_model::{LaneID, Map, Path, PathStep, RoadID, Traversable};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
 const TIME_TO_UNPARK_ONSTRET: Duration = Duration::const_seconds(10.0);
 const TIME_TO_PARK_ONSTREET: Duration = Duration::const_seconds(15.0);
@@ -22,6 +22,11 @@ const TIME_TO_WAIT_AT_STOP: Duration = Duration::const_seconds(10.0);
 pub(crate) const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
 pub(crate) const BLIND_RETRY_TO_REACH_END_DIST: Duration = Duration::const_seconds(5.0);
 
+// Cars stop instantly in this model, so "hard braking" means coming upon a vehicle that only just
+// stopped, while moving at least this fast.
+const HARD_BRAKING_WINDOW: Duration = Duration::const_seconds(3.0);
+const HARD_BRAKING_MIN_SPEED: Speed = Speed::const_meters_per_second(8.0);
+
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct DrivingSimState {
     #[serde(
@@ -35,6 +40,9 @@ pub struct DrivingSimState {
     )]
     queues: BTreeMap<Traversable, Queue>,
     events: Vec<Event>,
+    // The most cars ever stopped on each lane at once. Lanes where nobody's stopped yet are
+    // missing.
+    max_queued: BTreeMap<LaneID, usize>,
 
     recalc_lanechanging: bool,
 }
@@ -45,6 +53,7 @@ impl DrivingSimState {
             cars: BTreeMap::new(),
             queues: BTreeMap::new(),
             events: Vec::new(),
+            max_queued: BTreeMap::new(),
             recalc_lanechanging,
         };
 
@@ -200,6 +209,12 @@ impl DrivingSimState {
             // We need to mutate two different cars in one case. To avoid fighting the borrow
             // checker, temporarily move one of them out of the BTreeMap.
             let mut car = self.cars.remove(&id).unwrap();
+            let was_crossing = match car.state {
+                CarState::Crossing(ref time_int, ref dist_int) => {
+                    Some((time_int.clone(), dist_int.clone()))
+                }
+                _ => None,
+            };
             // Responsibility of update_car to manage scheduling stuff!
             need_distances = self.update_car_without_distances(
                 &mut car,
@@ -211,6 +226,10 @@ impl DrivingSimState {
                 scheduler,
             );
             self.cars.insert(id, car);
+            if let Some((time_int, dist_int)) = was_crossing {
+                self.check_hard_braking(id, time_int, dist_int, now);
+                self.record_queue_length(id);
+            }
         }
 
         if need_distances {
@@ -337,7 +356,7 @@ impl DrivingSimState {
                 assert!(from != goto);
 
                 if let Traversable::Turn(t) = goto {
-                    let mut speed = goto.speed_limit(map);
+                    let mut speed = goto.speed_limit(now, map);
                     if let Some(s) = car.vehicle.max_speed {
                         speed = speed.min(s);
                     }
@@ -859,6 +878,68 @@ impl DrivingSimState {
         result
     }
 
+    // Every car is in exactly the queue for the head of its path, that queue exists in the map,
+    // no two cars in a queue overlap, and cars with a trip are the active agent for it.
+    pub fn find_invariant_violations(
+        &self,
+        now: Time,
+        map: &Map,
+        trips: &TripManager,
+    ) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut seen = BTreeMap::new();
+        for (on, queue) in &self.queues {
+            if !queue.cars.is_empty() && !on.exists(map) {
+                problems.push(format!("{} has cars, but isn't part of the map", on));
+            }
+            for id in &queue.cars {
+                if let Some(other) = seen.insert(*id, *on) {
+                    problems.push(format!("{} is in the queues for {} and {}", id, other, on));
+                }
+                match self.cars.get(id) {
+                    Some(car) => {
+                        if car.router.head() != *on {
+                            problems.push(format!(
+                                "{} is queued on {}, but its path is at {}",
+                                id,
+                                on,
+                                car.router.head()
+                            ));
+                        }
+                    }
+                    None => {
+                        problems.push(format!("{} is queued on {}, but doesn't exist", id, on));
+                    }
+                }
+            }
+            if let Some(id) = queue.laggy_head {
+                if !self.cars.contains_key(&id) {
+                    problems.push(format!("Laggy head {} of {} doesn't exist", id, on));
+                }
+            }
+
+            // Positioning problems are found with a panic deep inside, so turn that into a report.
+            if std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                queue.get_car_positions(now, &self.cars, &self.queues)
+            }))
+            .is_err()
+            {
+                problems.push(format!("Cars on {} overlap", on));
+            }
+        }
+        for (id, car) in &self.cars {
+            if !seen.contains_key(id) {
+                problems.push(format!("{} isn't in any queue", id));
+            }
+            if let Some((trip, _)) = car.trip_and_person {
+                if trips.agent_to_trip(AgentID::Car(*id)) != Some(trip) {
+                    problems.push(format!("{} exists, but isn't active for {}", id, trip));
+                }
+            }
+        }
+        problems
+    }
+
     pub fn does_car_exist(&self, id: CarID) -> bool {
         self.cars.contains_key(&id)
     }
@@ -912,6 +993,24 @@ impl DrivingSimState {
         }
     }
 
+    pub fn get_draw_cars_on_all(
+        &self,
+        now: Time,
+        on: &Vec<Traversable>,
+        map: &Map,
+        transit: &TransitSimState,
+    ) -> Vec<(Traversable, Vec<DrawCarInput>)> {
+        on.iter()
+            .filter(|on| {
+                self.queues
+                    .get(on)
+                    .map(|q| !q.cars.is_empty())
+                    .unwrap_or(false)
+            })
+            .map(|on| (*on, self.get_draw_cars_on(now, *on, map, transit)))
+            .collect()
+    }
+
     pub fn debug_car(&self, id: CarID) {
         if let Some(ref car) = self.cars.get(&id) {
             println!("{}", abstutil::to_json(car));
@@ -937,6 +1036,13 @@ impl DrivingSimState {
             }
             _ => Duration::ZERO,
         };
+        let on = car.router.head();
+        let queue_position = self.queues.get(&on).and_then(|q| {
+            q.cars
+                .iter()
+                .position(|c| *c == id)
+                .map(|idx| (idx, q.cars.len()))
+        });
 
         AgentProperties {
             total_time: now - car.started_at,
@@ -946,6 +1052,81 @@ impl DrivingSimState {
             total_dist: path.total_length(),
             lanes_crossed: path.lanes_crossed_so_far(),
             total_lanes: path.total_lanes(),
+            speed: car.state.get_speed(),
+            on: Some(on),
+            queue_position,
+        }
+    }
+
+    pub fn num_cars_on(&self, on: Traversable) -> usize {
+        self.queues.get(&on).map(|q| q.cars.len()).unwrap_or(0)
+    }
+
+    // For every lane with cars on it, how much of its length they fill (including following
+    // distance, so 1.0 means bumper-to-bumper) and their average speed. Anybody not crossing right
+    // now counts as stopped.
+    pub fn lane_congestion(&self) -> Vec<(LaneID, f64, Speed)> {
+        let mut results = Vec::new();
+        for queue in self.queues.values() {
+            let l = match queue.id {
+                Traversable::Lane(l) => l,
+                Traversable::Turn(_) => continue,
+            };
+            if queue.cars.is_empty() || queue.geom_len == Distance::ZERO {
+                continue;
+            }
+            let mut filled = Distance::ZERO;
+            let mut total_speed = 0.0;
+            for id in &queue.cars {
+                let car = &self.cars[id];
+                filled += car.vehicle.length + FOLLOWING_DISTANCE;
+                total_speed += car.state.get_speed().inner_meters_per_second();
+            }
+            results.push((
+                l,
+                (filled / queue.geom_len).min(1.0),
+                Speed::meters_per_second(total_speed / (queue.cars.len() as f64)),
+            ));
+        }
+        results
+    }
+
+    pub fn max_queued(&self) -> &BTreeMap<LaneID, usize> {
+        &self.max_queued
+    }
+
+    // (car, the car right in front of it) for everybody who isn't the head of their queue. The
+    // head waits on a laggy head still partly on the queue, if there is one.
+    pub fn cars_waiting_in_queues(&self) -> Vec<(CarID, CarID)> {
+        let mut results = Vec::new();
+        for queue in self.queues.values() {
+            let mut ahead = queue.laggy_head;
+            for id in &queue.cars {
+                if let Some(leader) = ahead {
+                    if leader != *id {
+                        results.push((*id, leader));
+                    }
+                }
+                ahead = Some(*id);
+            }
+        }
+        results
+    }
+
+    // If the car doesn't fit on the next lane or turn, whoever's last there is in the way.
+    pub fn blocking_entry(&self, id: CarID, next: Traversable) -> Option<CarID> {
+        let car = self.cars.get(&id)?;
+        let queue = self.queues.get(&next)?;
+        if queue.room_for_car(car) {
+            return None;
+        }
+        queue.cars.back().cloned()
+    }
+
+    pub fn detour_car(&mut self, id: CarID, avoid: &BTreeSet<RoadID>, map: &Map) -> bool {
+        match self.cars.get_mut(&id) {
+            Some(car) => car.router.detour_around(avoid, &car.vehicle, map),
+            None => false,
         }
     }
 
@@ -1048,6 +1229,82 @@ impl DrivingSimState {
         }
     }
 
+    // Called when a car that was Crossing stops. Queues only get longer when this happens, so it's
+    // the only time to count them.
+    fn record_queue_length(&mut self, id: CarID) {
+        let l = match self.cars[&id].router.head() {
+            Traversable::Lane(l) => l,
+            Traversable::Turn(_) => {
+                return;
+            }
+        };
+        let cars = &self.cars;
+        let stopped = self.queues[&Traversable::Lane(l)]
+            .cars
+            .iter()
+            .filter(|c| match cars[c].state {
+                CarState::Queued { .. } | CarState::WaitingToAdvance { .. } => true,
+                _ => false,
+            })
+            .count();
+        let max = self.max_queued.entry(l).or_insert(0);
+        *max = (*max).max(stopped);
+    }
+
+    // Called when a car that was Crossing becomes Queued. If it got stuck behind a leader that had
+    // only just stopped, record it.
+    fn check_hard_braking(
+        &mut self,
+        id: CarID,
+        time_int: TimeInterval,
+        dist_int: DistanceInterval,
+        now: Time,
+    ) {
+        let car = &self.cars[&id];
+        match car.state {
+            CarState::Queued { .. } => {}
+            _ => {
+                return;
+            }
+        }
+        let on = car.router.head();
+        let queue = &self.queues[&on];
+        let idx = queue.cars.iter().position(|c| *c == id).unwrap();
+        if idx == 0 {
+            return;
+        }
+        let leader_stopped = match self.cars[&queue.cars[idx - 1]].state {
+            CarState::Queued { blocked_since } | CarState::WaitingToAdvance { blocked_since } => {
+                blocked_since
+            }
+            _ => {
+                return;
+            }
+        };
+        let dt = time_int.end - time_int.start;
+        if dt == Duration::ZERO {
+            return;
+        }
+        let speed = Speed::meters_per_second(
+            (dist_int.end - dist_int.start).inner_meters() / dt.inner_seconds(),
+        );
+        if speed < HARD_BRAKING_MIN_SPEED {
+            return;
+        }
+
+        let our_dist = queue
+            .get_car_positions(now, &self.cars, &self.queues)
+            .into_iter()
+            .find(|(c, _)| *c == id)
+            .unwrap()
+            .1;
+        // If nothing had been in the way, when would the car have reached where it's stuck?
+        let stopped_at = time_int.start + (our_dist - dist_int.start).max(Distance::ZERO) / speed;
+        if stopped_at - leader_stopped < HARD_BRAKING_WINDOW {
+            self.events.push(Event::HardBraking(id, on, our_dist));
+        }
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::replace(&mut self.events, Vec::new())
     }
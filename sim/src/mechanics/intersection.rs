@@ -1,6 +1,6 @@
 use crate::mechanics::car::Car;
 use crate::mechanics::Queue;
-use crate::{AgentID, AlertLocation, CarID, Command, Event, Scheduler, Speed};
+use crate::{AgentID, AlertLocation, CarID, Command, Event, Scheduler, Speed, VehicleType};
 use abstutil::{deserialize_btreemap, retain_btreeset, serialize_btreemap};
 use geom::{Duration, Time};
 use map_model::{
@@ -11,7 +11,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
+const MAX_WAIT_TO_MERGE: Duration = Duration::const_seconds(10.0);
 const WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL: Duration = Duration::const_seconds(0.2);
+// Limits on how much transit signal priority can disrupt the normal timing
+const MAX_GREEN_EXTENSION: Duration = Duration::const_seconds(10.0);
+const MIN_GREEN_BEFORE_EARLY_END: Duration = Duration::const_seconds(5.0);
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct IntersectionSimState {
@@ -23,6 +27,10 @@ pub struct IntersectionSimState {
     // structure.
     blocked_by: BTreeSet<(CarID, CarID)>,
     events: Vec<Event>,
+    // Traffic signals whose phases are chosen by a SignalController, not their timing plan
+    external_signals: BTreeSet<IntersectionID>,
+    // Incidents keep vehicles from entering these
+    closed_roads: BTreeSet<RoadID>,
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
@@ -42,7 +50,10 @@ struct State {
 
     // Only relevant for traffic signals
     current_phase: usize,
+    phase_started_at: Time,
     phase_ends_at: Time,
+    // Transit signal priority only adjusts each phase once.
+    transit_priority_granted: bool,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Debug)]
@@ -66,6 +77,8 @@ impl IntersectionSimState {
             break_turn_conflict_cycles,
             blocked_by: BTreeSet::new(),
             events: Vec::new(),
+            external_signals: BTreeSet::new(),
+            closed_roads: BTreeSet::new(),
         };
         for i in map.all_intersections() {
             sim.state.insert(
@@ -76,7 +89,9 @@ impl IntersectionSimState {
                     waiting: BTreeMap::new(),
                     reserved: BTreeSet::new(),
                     current_phase: 0,
+                    phase_started_at: Time::START_OF_DAY,
                     phase_ends_at: Time::START_OF_DAY,
+                    transit_priority_granted: false,
                 },
             );
             if i.is_traffic_signal() && !use_freeform_policy_everywhere {
@@ -175,8 +190,12 @@ impl IntersectionSimState {
                     TurnPriority::Yield => {
                         yielding.push(req);
                     }
-                    // No need to wake up
-                    TurnPriority::Banned => {}
+                    TurnPriority::Banned => {
+                        // Otherwise, no need to wake up
+                        if signal.can_turn_on_red(req.turn, map) {
+                            yielding.push(req);
+                        }
+                    }
                 }
             }
         } else if let Some(ref sign) = map.maybe_get_stop_sign(i) {
@@ -249,14 +268,54 @@ impl IntersectionSimState {
             }
         }
 
+        state.phase_started_at = now;
         state.phase_ends_at = now
             + signal.phases[state.current_phase]
                 .phase_type
                 .simple_duration();
+        state.transit_priority_granted = false;
         scheduler.push(state.phase_ends_at, Command::UpdateIntersection(id));
         self.wakeup_waiting(now, id, scheduler, map);
     }
 
+    // The signal stays in its current phase until set_signal_phase is called.
+    pub fn control_signal_externally(&mut self, id: IntersectionID, scheduler: &mut Scheduler) {
+        self.external_signals.insert(id);
+        scheduler.cancel(Command::UpdateIntersection(id));
+    }
+
+    pub fn is_controlled_externally(&self, id: IntersectionID) -> bool {
+        self.external_signals.contains(&id)
+    }
+
+    // Agents won't start turns they can't finish within the duration. Setting the current phase
+    // again extends it.
+    pub fn set_signal_phase(
+        &mut self,
+        now: Time,
+        id: IntersectionID,
+        phase: usize,
+        duration: Duration,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) {
+        assert!(self.external_signals.contains(&id));
+        let state = self.state.get_mut(&id).unwrap();
+        state.current_phase = phase;
+        state.phase_started_at = now;
+        state.phase_ends_at = now + duration;
+        self.wakeup_waiting(now, id, scheduler, map);
+    }
+
+    // How many agents are waiting at the end of each lane to start a turn here
+    pub fn waiting_per_lane(&self, id: IntersectionID) -> BTreeMap<LaneID, usize> {
+        let mut counts = BTreeMap::new();
+        for req in self.state[&id].waiting.keys() {
+            *counts.entry(req.turn.src).or_insert(0) += 1;
+        }
+        counts
+    }
+
     // For cars: The head car calls this when they're at the end of the lane WaitingToAdvance. If
     // this returns true, then the head car MUST actually start this turn.
     // For peds: Likewise -- only called when the ped is at the start of the turn. They must
@@ -310,10 +369,16 @@ impl IntersectionSimState {
             }
 
             true
+        } else if matches!(agent, AgentID::Car(_))
+            && self.closed_roads.contains(&map.get_l(turn.dst).parent)
+        {
+            // Wait for the incident to clear. reopen_road wakes everybody up.
+            false
         } else if self.use_freeform_policy_everywhere {
             // If we made it this far, we don't conflict with an accepted turn
             true
         } else if let Some(ref signal) = map.maybe_get_traffic_signal(turn.parent) {
+            self.maybe_grant_transit_priority(&req, map, signal, speed, now, scheduler);
             self.traffic_signal_policy(&req, map, signal, speed, now, Some(scheduler))
         } else if let Some(ref sign) = map.maybe_get_stop_sign(turn.parent) {
             self.stop_sign_policy(&req, map, sign, now, scheduler)
@@ -436,6 +501,42 @@ impl IntersectionSimState {
         blocked_by
     }
 
+    // Everybody waiting to start a turn, when they started waiting, and whoever's already doing
+    // or has reserved a conflicting turn.
+    pub fn waiting_agents(&self, map: &Map) -> Vec<(AgentID, TurnID, Time, Vec<AgentID>)> {
+        let mut results = Vec::new();
+        for state in self.state.values() {
+            for (req, t) in &state.waiting {
+                let turn = map.get_t(req.turn);
+                let blockers = state
+                    .accepted
+                    .iter()
+                    .chain(state.reserved.iter())
+                    .filter(|other| {
+                        other.agent != req.agent && map.get_t(other.turn).conflicts_with(turn)
+                    })
+                    .map(|other| other.agent)
+                    .collect();
+                results.push((req.agent, req.turn, *t, blockers));
+            }
+        }
+        results
+    }
+
+    pub fn close_road(&mut self, r: RoadID) {
+        self.closed_roads.insert(r);
+    }
+
+    // Vehicles waiting to enter the road get another chance.
+    pub fn reopen_road(&mut self, now: Time, r: RoadID, scheduler: &mut Scheduler, map: &Map) {
+        if self.closed_roads.remove(&r) {
+            let road = map.get_r(r);
+            for i in vec![road.src_i, road.dst_i] {
+                self.wakeup_waiting(now, i, scheduler, map);
+            }
+        }
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::replace(&mut self.events, Vec::new())
     }
@@ -498,6 +599,13 @@ impl IntersectionSimState {
         i: IntersectionID,
     ) -> (usize, Duration) {
         let state = &self.state[&i];
+        // An external controller may just not have extended the phase yet
+        if self.external_signals.contains(&i) {
+            return (
+                state.current_phase,
+                (state.phase_ends_at - now).max(Duration::ZERO),
+            );
+        }
         if now > state.phase_ends_at {
             panic!(
                 "At {}, but {} should have advanced its phase at {}",
@@ -537,6 +645,30 @@ impl IntersectionSimState {
         assert!(our_priority != TurnPriority::Banned);
         let our_time = self.state[&req.turn.parent].waiting[req];
 
+        // Merging from an on-ramp doesn't mean stopping first; the end of the ramp acts like an
+        // acceleration lane. Just wait for a gap in the freeway traffic. If the freeway is stuck,
+        // squeeze in eventually anyway.
+        if our_priority == TurnPriority::Yield
+            && map.get_r(map.get_l(req.turn.src).parent).is_ramp()
+            && map.get_r(map.get_l(req.turn.dst).parent).is_highway()
+        {
+            let turn = map.get_t(req.turn);
+            let no_gap = self.state[&req.turn.parent].waiting.keys().any(|other| {
+                other != req
+                    && sign.get_priority(other.turn, map) == TurnPriority::Protected
+                    && map.get_t(other.turn).conflicts_with(turn)
+            });
+            if no_gap && now < our_time + MAX_WAIT_TO_MERGE {
+                // We might get woken up earlier, when the freeway traffic moves.
+                scheduler.update(
+                    our_time + MAX_WAIT_TO_MERGE,
+                    Command::update_agent(req.agent),
+                );
+                return false;
+            }
+            return true;
+        }
+
         if our_priority == TurnPriority::Yield && now < our_time + WAIT_AT_STOP_SIGN {
             // Since we have "ownership" of scheduling for req.agent, don't need to use
             // scheduler.update.
@@ -583,10 +715,29 @@ impl IntersectionSimState {
         let remaining_phase_time = state.phase_ends_at - now;
         let our_time = state.waiting[req];
 
-        // Can't go at all this phase.
+        // Can't go at all this phase, unless turning on red.
         let our_priority = phase.get_priority_of_turn(req.turn, signal);
         if our_priority == TurnPriority::Banned {
-            return false;
+            if !signal.can_turn_on_red(req.turn, map) {
+                return false;
+            }
+            // Come to a full stop first
+            if now < our_time + WAIT_AT_STOP_SIGN {
+                if let Some(s) = scheduler {
+                    s.push(
+                        our_time + WAIT_AT_STOP_SIGN,
+                        Command::update_agent(req.agent),
+                    );
+                }
+                return false;
+            }
+            // Then wait for a gap. Anybody already in the intersection is handled by
+            // handle_accepted_conflicts, but somebody with the green might be about to start.
+            return !state.waiting.keys().any(|other| {
+                other != req
+                    && phase.get_priority_of_turn(other.turn, signal) != TurnPriority::Banned
+                    && map.get_t(other.turn).conflicts_with(turn)
+            });
         }
 
         if our_priority == TurnPriority::Yield
@@ -603,6 +754,29 @@ impl IntersectionSimState {
             return false;
         }
 
+        // The phase start isn't known when something else is picking the phases.
+        if !self.external_signals.contains(&req.turn.parent) {
+            if let Some(intervals) = phase.pedestrian_intervals(signal) {
+                // Nobody starts crossing on flashing don't walk. Transit signal priority can move
+                // the end of the phase, so count back from that. If the phase is too short to
+                // cross at all, there's no walk interval and the crosswalk stays closed.
+                if turn.turn_type == TurnType::Crosswalk
+                    && (intervals.walk == Duration::ZERO
+                        || now >= state.phase_ends_at - intervals.flashing_dont_walk)
+                {
+                    return false;
+                }
+
+                let lpi_ends = state.phase_started_at + intervals.leading;
+                if now < lpi_ends && phase.held_by_leading_pedestrian_interval(req.turn, signal) {
+                    if let Some(s) = scheduler {
+                        s.push(lpi_ends, Command::update_agent(req.agent));
+                    }
+                    return false;
+                }
+            }
+        }
+
         // Previously: A yield loses to a conflicting Priority turn.
         // But similar to the description in stop_sign_policy, this caused unnecessary gridlock.
         // Priority vehicles getting scheduled first just requires a little tweak in
@@ -633,6 +807,69 @@ impl IntersectionSimState {
         true
     }
 
+    // A bus waiting at an equipped signal can hold the green long enough to make it through, or end
+    // a conflicting phase early when the next phase lets it go.
+    fn maybe_grant_transit_priority(
+        &mut self,
+        req: &Request,
+        map: &Map,
+        signal: &ControlTrafficSignal,
+        speed: Speed,
+        now: Time,
+        scheduler: &mut Scheduler,
+    ) {
+        let bus = match req.agent {
+            AgentID::Car(car) if car.1 == VehicleType::Bus => car,
+            _ => {
+                return;
+            }
+        };
+        let id = req.turn.parent;
+        if !signal.transit_signal_priority || self.external_signals.contains(&id) {
+            return;
+        }
+        let state = self.state.get_mut(&id).unwrap();
+        if state.transit_priority_granted {
+            return;
+        }
+
+        let phase = &signal.phases[state.current_phase];
+        let priority = phase.get_priority_of_turn(req.turn, signal);
+        let extend = priority != TurnPriority::Banned;
+        let new_end = if extend {
+            let start = if priority == TurnPriority::Yield {
+                now.max(state.waiting[req] + WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL)
+            } else {
+                now
+            };
+            let end = start + map.get_t(req.turn).geom.length() / speed;
+            if end <= state.phase_ends_at || end - state.phase_ends_at > MAX_GREEN_EXTENSION {
+                return;
+            }
+            end
+        } else {
+            let next = &signal.phases[(state.current_phase + 1) % signal.phases.len()];
+            if next.get_priority_of_turn(req.turn, signal) == TurnPriority::Banned {
+                return;
+            }
+            let mut end = now.max(state.phase_started_at + MIN_GREEN_BEFORE_EARLY_END);
+            // Give anybody who just started crossing time to finish
+            if let Some(intervals) = phase.pedestrian_intervals(signal) {
+                end = end.max(now + intervals.flashing_dont_walk);
+            }
+            if end >= state.phase_ends_at {
+                return;
+            }
+            end
+        };
+
+        state.phase_ends_at = new_end;
+        state.transit_priority_granted = true;
+        scheduler.update(new_end, Command::UpdateIntersection(id));
+        self.events
+            .push(Event::TransitSignalPriority(id, bus, extend));
+    }
+
     // If true, the request can go.
     fn handle_accepted_conflicts(
         &mut self,
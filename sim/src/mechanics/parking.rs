@@ -3,7 +3,7 @@ use abstutil::{
     deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
     Timer,
 };
-use geom::{Distance, PolyLine, Pt2D};
+use geom::{Distance, Duration, PolyLine, Pt2D};
 use map_model::{
     BuildingID, Lane, LaneID, LaneType, Map, ParkingLotID, PathConstraints, PathStep, Position,
     Traversable, TurnID,
@@ -11,6 +11,14 @@ use map_model::{
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
 
+// Drivers don't plan ahead how long they'll stay, so when comparing prices, they assume this.
+const EXPECTED_STAY: Duration = Duration::const_seconds(2.0 * 3600.0);
+// How many cents a driver would pay to walk one less meter to their destination
+const CENTS_PER_METER_WALKED: f64 = 0.5;
+// When looking for parking elsewhere, a driver will keep driving this much farther than the first
+// free spot, hoping for something cheaper.
+const MAX_EXTRA_SEARCH: Distance = Distance::const_meters(500.0);
+
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct ParkingSimState {
     #[serde(
@@ -198,6 +206,25 @@ impl ParkingSimState {
         cars
     }
 
+    // Cars in a parking lot belong to the lot's driving lane, same as get_draw_cars_in_lots.
+    pub fn get_draw_cars_on_all(
+        &self,
+        on: &Vec<Traversable>,
+        map: &Map,
+    ) -> Vec<(Traversable, Vec<DrawCarInput>)> {
+        let mut results = Vec::new();
+        for on in on {
+            if let Traversable::Lane(l) = on {
+                let mut cars = self.get_draw_cars(*l, map);
+                cars.extend(self.get_draw_cars_in_lots(*l, map));
+                if !cars.is_empty() {
+                    results.push((*on, cars));
+                }
+            }
+        }
+        results
+    }
+
     pub fn get_draw_car(&self, id: CarID, map: &Map) -> Option<DrawCarInput> {
         let p = self.parked_cars.get(&id)?;
         match p.spot {
@@ -210,6 +237,7 @@ impl ParkingSimState {
                     on: Traversable::Lane(lane),
                     partly_on: Vec::new(),
                     label: None,
+                    age: None,
 
                     body: map
                         .get_l(lane)
@@ -230,6 +258,7 @@ impl ParkingSimState {
                     on: Traversable::Lane(pl.driving_pos.lane()),
                     partly_on: Vec::new(),
                     label: None,
+                    age: None,
 
                     body: PolyLine::must_new(vec![
                         pt.project_away(buffer, angle),
@@ -358,6 +387,22 @@ impl ParkingSimState {
         }
     }
 
+    // Lower is better. Trades off the price of a spot against walking from it to the target. Spots
+    // with a time limit shorter than EXPECTED_STAY are a last resort.
+    pub fn spot_cost(&self, spot: ParkingSpot, target: BuildingID, map: &Map) -> (bool, usize) {
+        let policy = spot.policy(map);
+        let too_short = policy
+            .max_stay
+            .map(|dt| dt < EXPECTED_STAY)
+            .unwrap_or(false);
+        let walk = self
+            .spot_to_sidewalk_pos(spot, map)
+            .pt(map)
+            .dist_to(map.get_b(target).front_path.sidewalk.pt(map));
+        let walk_cents = (walk.inner_meters() * CENTS_PER_METER_WALKED).round() as usize;
+        (too_short, policy.cost(EXPECTED_STAY) + walk_cents)
+    }
+
     pub fn get_owner_of_car(&self, id: CarID) -> Option<PersonID> {
         self.parked_cars.get(&id).and_then(|p| p.vehicle.owner)
     }
@@ -397,6 +442,8 @@ impl ParkingSimState {
     // Unrealistically assumes the driver has knowledge of currently free parking spots, even if
     // they're far away. Since they don't reserve the spot in advance, somebody else can still beat
     // them there, producing some nice, realistic churn if there's too much contention.
+    // After finding the first free spot, keep looking a little farther for a better one, by
+    // spot_cost.
     // The first PathStep is the turn after start, NOT PathStep::Lane(start).
     pub fn path_to_free_parking_spot(
         &self,
@@ -411,32 +458,29 @@ impl ParkingSimState {
         // deterministic.
         let mut queue: BinaryHeap<(Distance, LaneID)> = BinaryHeap::new();
         queue.push((Distance::ZERO, start));
+        // (cost, lane, spot, position)
+        let mut best: Option<((bool, usize), LaneID, ParkingSpot, Position)> = None;
+        let mut search_until: Option<Distance> = None;
 
         while !queue.is_empty() {
             let (dist_so_far, current) = queue.pop().unwrap();
+            if search_until.map(|d| -dist_so_far > d).unwrap_or(false) {
+                break;
+            }
             // If the current lane has a spot open, we wouldn't be asking. This can happen if a spot
             // opens up on the 'start' lane, but behind the car.
             if current != start {
-                // Pick the closest to the start of the lane, since that's closest to where we came
-                // from
-                if let Some((spot, pos)) = self
+                if let Some((cost, spot, pos)) = self
                     .get_all_free_spots(Position::start(current), vehicle, target, map)
                     .into_iter()
-                    .min_by_key(|(_, pos)| pos.dist_along())
+                    .map(|(spot, pos)| (self.spot_cost(spot, target, map), spot, pos))
+                    .min_by_key(|(cost, _, _)| *cost)
                 {
-                    let mut steps = vec![PathStep::Lane(current)];
-                    let mut current = current;
-                    loop {
-                        if current == start {
-                            // Don't include PathStep::Lane(start)
-                            steps.pop();
-                            steps.reverse();
-                            return Some((steps, spot, pos));
-                        }
-                        let turn = backrefs[&current];
-                        steps.push(PathStep::Turn(turn));
-                        steps.push(PathStep::Lane(turn.src));
-                        current = turn.src;
+                    if search_until.is_none() {
+                        search_until = Some(-dist_so_far + MAX_EXTRA_SEARCH);
+                    }
+                    if best.map(|(c, _, _, _)| cost < c).unwrap_or(true) {
+                        best = Some((cost, current, spot, pos));
                     }
                 }
             }
@@ -450,7 +494,21 @@ impl ParkingSimState {
             }
         }
 
-        None
+        let (_, lane, spot, pos) = best?;
+        let mut steps = vec![PathStep::Lane(lane)];
+        let mut current = lane;
+        loop {
+            if current == start {
+                // Don't include PathStep::Lane(start)
+                steps.pop();
+                steps.reverse();
+                return Some((steps, spot, pos));
+            }
+            let turn = backrefs[&current];
+            steps.push(PathStep::Turn(turn));
+            steps.push(PathStep::Lane(turn.src));
+            current = turn.src;
+        }
     }
 
     pub fn collect_events(&mut self) -> Vec<Event> {
@@ -349,6 +349,12 @@ impl WalkingSimState {
             total_dist: p.path.total_length(),
             lanes_crossed: p.path.lanes_crossed_so_far(),
             total_lanes: p.path.total_lanes(),
+            speed: match p.state {
+                PedState::Crossing(_, _) => p.speed,
+                _ => Speed::ZERO,
+            },
+            on: Some(p.path.current_step().as_traversable()),
+            queue_position: None,
         }
     }
 
@@ -386,10 +392,44 @@ impl WalkingSimState {
         peds
     }
 
+    // Every pedestrian is indexed under the current step of their path, which exists in the map,
+    // and is the active agent for their trip.
+    pub fn find_invariant_violations(&self, map: &Map, trips: &TripManager) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (id, ped) in &self.peds {
+            if trips.agent_to_trip(AgentID::Pedestrian(*id)) != Some(ped.trip) {
+                problems.push(format!("{} exists, but isn't active for {}", id, ped.trip));
+            }
+            let on = ped.path.current_step().as_traversable();
+            if !on.exists(map) {
+                problems.push(format!("{} is on {}, which isn't part of the map", id, on));
+            }
+            if !self.peds_per_traversable.get(on).contains(id) {
+                problems.push(format!("{} is on {}, but isn't indexed there", id, on));
+            }
+        }
+        problems
+    }
+
     pub fn does_ped_exist(&self, id: PedestrianID) -> bool {
         self.peds.contains_key(&id)
     }
 
+    pub fn get_draw_peds_on_all(
+        &self,
+        now: Time,
+        on: &Vec<Traversable>,
+        map: &Map,
+    ) -> Vec<(
+        Traversable,
+        (Vec<DrawPedestrianInput>, Vec<DrawPedCrowdInput>),
+    )> {
+        on.iter()
+            .filter(|on| !self.peds_per_traversable.get(**on).is_empty())
+            .map(|on| (*on, self.get_draw_peds_on(now, *on, map)))
+            .collect()
+    }
+
     pub fn get_draw_peds_on(
         &self,
         now: Time,
@@ -659,9 +699,13 @@ impl Pedestrian {
                 PedState::WaitingToTurn(_, _) => Some(self.path.next_step().as_turn()),
                 _ => None,
             },
-            preparing_bike: matches!(self.state, PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)),
+            preparing_bike: matches!(
+                self.state,
+                PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)
+            ),
             waiting_for_bus: matches!(self.state, PedState::WaitingForBus(_, _)),
             on,
+            age: Some(now - self.started_at),
         }
     }
 
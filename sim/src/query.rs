@@ -0,0 +1,382 @@
+use crate::{Sim, TripEndpoint, TripID, TripMode};
+use geom::{Duration, Polygon, Time};
+use map_model::{Map, Neighborhood};
+use std::collections::BTreeMap;
+
+// A small language for picking out finished trips, so they can be counted, charted, or shown on a
+// map. A query is some conditions joined by AND:
+//
+// mode=drive AND duration>30min AND destination in Montlake
+//
+// - mode = or != walk, bike, transit, or drive
+// - duration and waiting compare (<, <=, >, >=, =, !=) with amounts like 90s, 30min, 1h30min, or
+//   1:30:00
+// - departure compares with times of day like 7am, 5:30pm, or 17:30
+// - origin and destination are either "in" a neighborhood drawn on the map, or = border/building
+// - modified = yes or no, for trips changed by an experiment
+//
+// Field names, modes, and neighborhood names ignore case. Aborted trips never match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TripQuery {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    // Whether the mode should match or not
+    Mode(bool, TripMode),
+    Duration(Cmp, Duration),
+    Waiting(Cmp, Duration),
+    Departure(Cmp, Time),
+    InNeighborhood(End, String),
+    AtBorder(End, bool),
+    Modified(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum End {
+    Origin,
+    Destination,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cmp {
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+}
+
+impl Cmp {
+    // Two-character operators first, so >= isn't mistaken for >
+    const ALL: [(&'static str, Cmp); 6] = [
+        ("<=", Cmp::LessEqual),
+        (">=", Cmp::GreaterEqual),
+        ("!=", Cmp::NotEqual),
+        ("<", Cmp::Less),
+        (">", Cmp::Greater),
+        ("=", Cmp::Equal),
+    ];
+
+    fn eval<T: PartialOrd>(self, x: T, y: T) -> bool {
+        match self {
+            Cmp::Less => x < y,
+            Cmp::LessEqual => x <= y,
+            Cmp::Equal => x == y,
+            Cmp::NotEqual => x != y,
+            Cmp::GreaterEqual => x >= y,
+            Cmp::Greater => x > y,
+        }
+    }
+}
+
+impl TripQuery {
+    pub fn parse(query: &str) -> Result<TripQuery, String> {
+        let mut conditions = Vec::new();
+        for clause in split_ignore_case(query, " and ") {
+            let clause = clause.trim();
+            if clause.is_empty() {
+                return Err(format!("\"{}\" has an empty condition", query));
+            }
+            conditions.push(parse_condition(clause)?);
+        }
+        Ok(TripQuery { conditions })
+    }
+
+    // Every finished trip matching the query, in the order they finished
+    pub fn find(&self, sim: &Sim, map: &Map) -> Result<Vec<TripID>, String> {
+        let neighborhoods = self.load_neighborhoods(map)?;
+        Ok(self.find_since(sim, map, &neighborhoods, 0))
+    }
+
+    // The neighborhoods the query mentions. When the same query runs again as more trips finish,
+    // load these once and pass them to find_since.
+    pub fn load_neighborhoods(&self, map: &Map) -> Result<BTreeMap<String, Polygon>, String> {
+        let mut neighborhoods: BTreeMap<String, Polygon> = BTreeMap::new();
+        let mut all: Option<Vec<Neighborhood>> = None;
+        for c in &self.conditions {
+            if let Condition::InNeighborhood(_, ref name) = c {
+                if neighborhoods.contains_key(name) {
+                    continue;
+                }
+                let polygon = all
+                    .get_or_insert_with(|| Neighborhood::load_all(map.get_name()))
+                    .iter()
+                    .find(|n| n.name.to_ascii_lowercase() == *name)
+                    .and_then(|n| n.polygon(map))
+                    .ok_or_else(|| {
+                        format!("There's no neighborhood called {} on this map", name)
+                    })?;
+                neighborhoods.insert(name.clone(), polygon);
+            }
+        }
+        Ok(neighborhoods)
+    }
+
+    // Like find, but skips the first `start` trips to finish (or abort)
+    pub fn find_since(
+        &self,
+        sim: &Sim,
+        map: &Map,
+        neighborhoods: &BTreeMap<String, Polygon>,
+        start: usize,
+    ) -> Vec<TripID> {
+        let mut results = Vec::new();
+        for (_, id, maybe_mode, duration) in sim.get_analytics().finished_trips.iter().skip(start) {
+            if maybe_mode.is_none() {
+                continue;
+            }
+            let trip = sim.trip_info(*id);
+            let (_, waiting) = sim.finished_trip_time(*id).unwrap();
+            let endpoint = |end: End| match end {
+                End::Origin => &trip.start,
+                End::Destination => &trip.end,
+            };
+            if self.conditions.iter().all(|c| match c {
+                Condition::Mode(equal, mode) => (trip.mode == *mode) == *equal,
+                Condition::Duration(cmp, dt) => cmp.eval(*duration, *dt),
+                Condition::Waiting(cmp, dt) => cmp.eval(waiting, *dt),
+                Condition::Departure(cmp, t) => cmp.eval(trip.departure, *t),
+                Condition::InNeighborhood(end, name) => {
+                    neighborhoods[name].contains_pt(endpoint(*end).pt(map))
+                }
+                Condition::AtBorder(end, border) => match endpoint(*end) {
+                    TripEndpoint::Border(_, _) => *border,
+                    TripEndpoint::Bldg(_) => !*border,
+                },
+                Condition::Modified(modified) => trip.modified == *modified,
+            }) {
+                results.push(*id);
+            }
+        }
+        results
+    }
+}
+
+fn parse_condition(clause: &str) -> Result<Condition, String> {
+    let lower = clause.to_ascii_lowercase();
+    if let Some(idx) = lower.find(" in ") {
+        let end = parse_end(lower[..idx].trim())?;
+        let name = lower[idx + " in ".len()..].trim();
+        if name.is_empty() {
+            return Err(format!("\"{}\" needs a neighborhood", clause));
+        }
+        return Ok(Condition::InNeighborhood(end, name.to_string()));
+    }
+
+    let (idx, op, cmp) = Cmp::ALL
+        .iter()
+        .filter_map(|(op, cmp)| lower.find(op).map(|idx| (idx, *op, *cmp)))
+        // The earliest operator, preferring the longest one at that spot
+        .min_by_key(|(idx, op, _)| (*idx, std::cmp::Reverse(op.len())))
+        .ok_or_else(|| format!("\"{}\" doesn't compare anything", clause))?;
+    let field = lower[..idx].trim();
+    let value = lower[idx + op.len()..].trim();
+    let only_equality = || -> Result<bool, String> {
+        match cmp {
+            Cmp::Equal => Ok(true),
+            Cmp::NotEqual => Ok(false),
+            _ => Err(format!("{} can only be compared with = or !=", field)),
+        }
+    };
+
+    match field {
+        "mode" => {
+            let mode = match value {
+                "walk" => TripMode::Walk,
+                "bike" => TripMode::Bike,
+                "transit" => TripMode::Transit,
+                "drive" => TripMode::Drive,
+                _ => {
+                    return Err(format!(
+                        "Unknown mode {}; use walk, bike, transit, or drive",
+                        value
+                    ));
+                }
+            };
+            Ok(Condition::Mode(only_equality()?, mode))
+        }
+        "duration" => Ok(Condition::Duration(cmp, parse_duration(value)?)),
+        "waiting" => Ok(Condition::Waiting(cmp, parse_duration(value)?)),
+        "departure" => Ok(Condition::Departure(cmp, parse_time(value)?)),
+        "origin" | "destination" => {
+            let border = match value {
+                "border" => true,
+                "building" => false,
+                _ => {
+                    return Err(format!(
+                        "{} can be \"in\" a neighborhood, or = border or building",
+                        field
+                    ));
+                }
+            };
+            let equal = only_equality()?;
+            Ok(Condition::AtBorder(parse_end(field)?, border == equal))
+        }
+        "modified" => {
+            let modified = match value {
+                "yes" | "true" => true,
+                "no" | "false" => false,
+                _ => {
+                    return Err("modified can be yes or no".to_string());
+                }
+            };
+            Ok(Condition::Modified(modified == only_equality()?))
+        }
+        _ => Err(format!(
+            "Unknown field {}; use mode, duration, waiting, departure, origin, destination, or \
+             modified",
+            field
+        )),
+    }
+}
+
+fn parse_end(field: &str) -> Result<End, String> {
+    match field {
+        "origin" => Ok(End::Origin),
+        "destination" => Ok(End::Destination),
+        _ => Err(format!(
+            "Only origin and destination can be in a neighborhood, not {}",
+            field
+        )),
+    }
+}
+
+// Either 1:30:00, or a sum of amounts with units, like 1h30min
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    if value.contains(':') {
+        return Duration::parse(value).map_err(|err| err.to_string());
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return Err("Missing a duration".to_string());
+    }
+    while !rest.is_empty() {
+        let num_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(|| format!("{} needs a unit, like 30min", value))?;
+        let amount = rest[..num_len]
+            .parse::<f64>()
+            .map_err(|_| format!("Can't understand the duration {}", value))?;
+        rest = &rest[num_len..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or_else(|| rest.len());
+        total += match rest[..unit_len].trim() {
+            "h" | "hr" | "hrs" | "hour" | "hours" => Duration::seconds(3600.0 * amount),
+            "m" | "min" | "mins" | "minute" | "minutes" => Duration::seconds(60.0 * amount),
+            "s" | "sec" | "secs" | "second" | "seconds" => Duration::seconds(amount),
+            unit => {
+                return Err(format!("Unknown unit {} in {}", unit, value));
+            }
+        };
+        rest = rest[unit_len..].trim_start();
+    }
+    Ok(total)
+}
+
+// Hours and optionally minutes and seconds, like 7am, 5:30pm, or 17:30:00
+fn parse_time(value: &str) -> Result<Time, String> {
+    // Hours to add for pm, or None for a 24-hour clock
+    let (clock, offset) = if let Some(x) = value.strip_suffix("am") {
+        (x.trim(), Some(0))
+    } else if let Some(x) = value.strip_suffix("pm") {
+        (x.trim(), Some(12))
+    } else {
+        (value, None)
+    };
+    let parts = clock
+        .split(':')
+        .map(|x| x.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("Can't understand the time {}", value))?;
+    if parts.is_empty() || parts.len() > 3 {
+        return Err(format!("Can't understand the time {}", value));
+    }
+    // 12am is midnight and 12pm is noon
+    let hours = match offset {
+        Some(offset) if parts[0] >= 1 && parts[0] <= 12 => parts[0] % 12 + offset,
+        None if parts[0] < 24 => parts[0],
+        _ => {
+            return Err(format!("{} has a bad hour", value));
+        }
+    };
+    let minutes = parts.get(1).cloned().unwrap_or(0);
+    let seconds = parts.get(2).cloned().unwrap_or(0);
+    if minutes >= 60 || seconds >= 60 {
+        return Err(format!("{} has bad minutes or seconds", value));
+    }
+    Ok(Time::START_OF_DAY
+        + Duration::hours(hours)
+        + Duration::minutes(minutes)
+        + Duration::seconds(seconds as f64))
+}
+
+fn split_ignore_case<'a>(input: &'a str, separator: &str) -> Vec<&'a str> {
+    let lower = input.to_ascii_lowercase();
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    while let Some(idx) = lower[start..].find(separator) {
+        pieces.push(&input[start..start + idx]);
+        start += idx + separator.len();
+    }
+    pieces.push(&input[start..]);
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            TripQuery::parse("mode=drive AND duration>30min AND destination in Montlake"),
+            Ok(TripQuery {
+                conditions: vec![
+                    Condition::Mode(true, TripMode::Drive),
+                    Condition::Duration(Cmp::Greater, Duration::minutes(30)),
+                    Condition::InNeighborhood(End::Destination, "montlake".to_string()),
+                ]
+            })
+        );
+        assert_eq!(
+            TripQuery::parse("waiting >= 1h30min and departure < 5:30pm and origin != border"),
+            Ok(TripQuery {
+                conditions: vec![
+                    Condition::Waiting(Cmp::GreaterEqual, Duration::minutes(90)),
+                    Condition::Departure(
+                        Cmp::Less,
+                        Time::START_OF_DAY + Duration::hours(17) + Duration::minutes(30)
+                    ),
+                    Condition::AtBorder(End::Origin, false),
+                ]
+            })
+        );
+        assert!(TripQuery::parse("mode>drive").is_err());
+        assert!(TripQuery::parse("speed>10").is_err());
+        assert!(TripQuery::parse("duration>30 AND").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("12am"), Ok(Time::START_OF_DAY));
+        assert_eq!(
+            parse_time("12:30pm"),
+            Ok(Time::START_OF_DAY + Duration::hours(12) + Duration::minutes(30))
+        );
+        assert_eq!(
+            parse_time("23:59:59"),
+            Ok(Time::START_OF_DAY
+                + Duration::hours(23)
+                + Duration::minutes(59)
+                + Duration::seconds(59.0))
+        );
+        for bad in vec!["13pm", "0am", "25", "24", "7:99", "7:30:60", "7::"] {
+            assert!(parse_time(bad).is_err(), "{} should be an error", bad);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{CarID, PedestrianID, PersonID, VehicleType};
-use geom::{Angle, Distance, PolyLine, Pt2D, Time};
+use geom::{Angle, Bounds, Distance, Duration, PolyLine, Pt2D, Time};
 use map_model::{BuildingID, Map, ParkingLotID, Traversable, TurnID};
+use std::collections::BTreeMap;
 
 // Intermediate structures so that sim and game crates don't have a cyclic dependency.
 #[derive(Clone)]
@@ -12,6 +13,8 @@ pub struct DrawPedestrianInput {
     pub preparing_bike: bool,
     pub waiting_for_bus: bool,
     pub on: Traversable,
+    // How long ago the agent appeared
+    pub age: Option<Duration>,
 }
 
 pub struct DrawPedCrowdInput {
@@ -39,6 +42,8 @@ pub struct DrawCarInput {
     // Possibly the rest
     pub partly_on: Vec<Traversable>,
     pub label: Option<String>,
+    // How long ago the agent appeared. None for parked cars.
+    pub age: Option<Duration>,
 
     // Starts at the BACK of the car.
     pub body: PolyLine,
@@ -50,6 +55,14 @@ pub enum CarStatus {
     Parked,
 }
 
+// Everything on one Traversable
+#[derive(Default)]
+pub struct DrawAgentsOn {
+    pub cars: Vec<DrawCarInput>,
+    pub peds: Vec<DrawPedestrianInput>,
+    pub crowds: Vec<DrawPedCrowdInput>,
+}
+
 pub struct UnzoomedAgent {
     // None means a pedestrian.
     pub vehicle_type: Option<VehicleType>,
@@ -76,6 +89,11 @@ pub trait GetDrawAgents {
         on: Traversable,
         map: &Map,
     ) -> (Vec<DrawPedestrianInput>, Vec<DrawPedCrowdInput>);
+    // Like calling get_draw_cars and get_draw_peds on every Traversable overlapping the bounds
+    // (usually the screen), but in one call. Traversables without any agents are omitted, and a few
+    // just outside the bounds may be included.
+    fn get_draw_agents_in(&self, bounds: &Bounds, map: &Map)
+        -> BTreeMap<Traversable, DrawAgentsOn>;
     fn get_all_draw_cars(&self, map: &Map) -> Vec<DrawCarInput>;
     fn get_all_draw_peds(&self, map: &Map) -> Vec<DrawPedestrianInput>;
     fn get_unzoomed_agents(&self, map: &Map) -> Vec<UnzoomedAgent>;
@@ -106,6 +124,9 @@ impl GetDrawAgents for DontDrawAgents {
     ) -> (Vec<DrawPedestrianInput>, Vec<DrawPedCrowdInput>) {
         (Vec::new(), Vec::new())
     }
+    fn get_draw_agents_in(&self, _: &Bounds, _: &Map) -> BTreeMap<Traversable, DrawAgentsOn> {
+        BTreeMap::new()
+    }
     fn get_all_draw_cars(&self, _: &Map) -> Vec<DrawCarInput> {
         Vec::new()
     }
@@ -5,16 +5,18 @@ use crate::{
 use geom::Distance;
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
-    Position, Traversable, TurnID, TurnType,
+    Position, RoadID, Traversable, TurnID, TurnType,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Router {
     // Front is always the current step
     path: Path,
     goal: Goal,
+    // Some drivers route through bus lanes, so the path was found like a bus's would be
+    ignores_bus_lanes: bool,
 }
 
 #[derive(Debug)]
@@ -30,7 +32,6 @@ pub enum ActionAtEnd {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 enum Goal {
     // Spot and cached distance along the last driving lane
-    // TODO Right now, the building is ignored when choosing the best spot.
     ParkNearBuilding {
         target: BuildingID,
         spot: Option<(ParkingSpot, Distance)>,
@@ -55,6 +56,7 @@ impl Router {
         Router {
             path,
             goal: Goal::EndAtBorder { end_dist, i },
+            ignores_bus_lanes: false,
         }
     }
     pub fn vanish_bus(l: LaneID, map: &Map) -> Router {
@@ -65,6 +67,7 @@ impl Router {
                 end_dist: lane.length(),
                 i: lane.dst_i,
             },
+            ignores_bus_lanes: false,
         }
     }
 
@@ -77,6 +80,7 @@ impl Router {
                 stuck_end_dist: None,
                 started_looking: false,
             },
+            ignores_bus_lanes: false,
         }
     }
 
@@ -90,6 +94,7 @@ impl Router {
             Some(Router {
                 path,
                 goal: Goal::BikeThenStop { end_dist },
+                ignores_bus_lanes: false,
             })
         } else {
             println!("{} is the end of a bike route, with no sidewalk", last_lane);
@@ -101,6 +106,7 @@ impl Router {
         Router {
             path,
             goal: Goal::FollowBusRoute { end_dist },
+            ignores_bus_lanes: false,
         }
     }
 
@@ -168,7 +174,7 @@ impl Router {
         // Sanity check laws haven't been broken
         if let Traversable::Lane(l) = self.head() {
             let lane = map.get_l(l);
-            if !vehicle.vehicle_type.to_constraints().can_use(lane, map) {
+            if !self.constraints(vehicle).can_use(lane, map) {
                 panic!(
                     "{} just wound up on {}, a {:?} (check the OSM tags)",
                     vehicle.id, l, lane.lane_type
@@ -226,25 +232,10 @@ impl Router {
                         target,
                         map,
                     );
-                    let best = if let Some(ref p) = map.get_b(target).parking {
-                        if p.driving_pos.lane() == current_lane {
-                            let target_dist = p.driving_pos.dist_along();
-                            // Closest to the building
-                            candidates
-                                .into_iter()
-                                .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
-                        } else {
-                            // Closest to the road endpoint, I guess
-                            candidates
-                                .into_iter()
-                                .min_by_key(|(_, pos)| pos.dist_along())
-                        }
-                    } else {
-                        // Closest to the road endpoint, I guess
-                        candidates
-                            .into_iter()
-                            .min_by_key(|(_, pos)| pos.dist_along())
-                    };
+                    // Balance price against walking to the building
+                    let best = candidates
+                        .into_iter()
+                        .min_by_key(|(s, _)| parking.spot_cost(*s, target, map));
                     if let Some((new_spot, new_pos)) = best {
                         if let Some((t, p)) = trip_and_person {
                             events.push(Event::TripPhaseStarting(
@@ -401,6 +392,79 @@ impl Router {
         self.path.modify_step(3, PathStep::Turn(turn2), map);
     }
 
+    // Take a different route to the same place, staying off these roads. Only possible while on a
+    // lane and not about to start an uber-turn. Returns true if the path changed.
+    pub fn detour_around(
+        &mut self,
+        avoid: &BTreeSet<RoadID>,
+        vehicle: &Vehicle,
+        map: &Map,
+    ) -> bool {
+        if self.path.is_last_step()
+            || self.path.approaching_uber_turn()
+            || self.path.currently_inside_ut().is_some()
+        {
+            return false;
+        }
+        let current = match self.path.current_step() {
+            PathStep::Lane(l) => l,
+            _ => {
+                return false;
+            }
+        };
+        if !self.path.get_steps().iter().any(|step| match step {
+            PathStep::Lane(l) => avoid.contains(&map.get_l(*l).parent),
+            _ => false,
+        }) {
+            return false;
+        }
+        let end_dist = match self.goal {
+            Goal::EndAtBorder { end_dist, .. } | Goal::BikeThenStop { end_dist } => end_dist,
+            Goal::ParkNearBuilding {
+                spot: Some((_, dist)),
+                stuck_end_dist: None,
+                ..
+            } => dist,
+            // Buses keep to their route, and anybody already circling for parking keeps at it
+            _ => {
+                return false;
+            }
+        };
+        let req = PathRequest {
+            start: Position::new(current, Distance::ZERO),
+            end: Position::new(self.path.last_step().as_lane(), end_dist),
+            constraints: self.constraints(vehicle),
+        };
+        match map.pathfind_avoiding(req, avoid) {
+            Some(path) => {
+                self.path = path;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // For drivers who use bus lanes anyway. The path must've been found with Bus constraints.
+    pub fn ignore_bus_lanes(&mut self, path: Path) {
+        self.path = path;
+        self.ignores_bus_lanes = true;
+    }
+
+    // What the path was found with
+    fn constraints(&self, vehicle: &Vehicle) -> PathConstraints {
+        if self.ignores_bus_lanes {
+            PathConstraints::Bus
+        } else {
+            vehicle.vehicle_type.to_constraints()
+        }
+    }
+
+    // Before the vehicle starts, follow a different path to the same goal, like a route captured
+    // from another run or a detour around tolls.
+    pub fn use_path(&mut self, path: Path) {
+        self.path = path;
+    }
+
     pub fn replace_path_for_serialization(&mut self, path: Path) -> Path {
         std::mem::replace(&mut self.path, path)
     }
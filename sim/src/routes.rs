@@ -0,0 +1,71 @@
+use map_model::{Map, Path, PathRequest, PathStep};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// The exact routes agents took in one run, so another run (usually with edits) can reuse them
+// instead of pathfinding again. Comparing the two runs then shows how an edit affects traffic
+// without also changing where people go.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CapturedRoutes {
+    // Pathfinding doesn't depend on the sim state, so the request is enough to identify a route.
+    routes: BTreeMap<PathRequest, Path>,
+
+    // When re-using routes, how many were used, and how many didn't work with the current map
+    #[serde(skip_serializing, skip_deserializing)]
+    kept: usize,
+    #[serde(skip_serializing, skip_deserializing)]
+    unusable: usize,
+}
+
+impl CapturedRoutes {
+    pub fn new() -> CapturedRoutes {
+        CapturedRoutes {
+            routes: BTreeMap::new(),
+            kept: 0,
+            unusable: 0,
+        }
+    }
+
+    // Only the first route for each request is kept; retrying to spawn shouldn't overwrite it.
+    pub(crate) fn record(&mut self, req: &PathRequest, path: &Path) {
+        if !self.routes.contains_key(req) {
+            self.routes.insert(req.clone(), path.clone());
+        }
+    }
+
+    // Returns the captured route, if there is one, it still works with the current map, and it's
+    // different than the path already found.
+    pub(crate) fn lookup(&mut self, req: &PathRequest, path: &Path, map: &Map) -> Option<Path> {
+        let captured = self.routes.get(req)?;
+        if captured == path {
+            // Still re-used, there's just nothing to replace
+            self.kept += 1;
+            return None;
+        }
+        let usable = captured.get_steps().iter().all(|step| match step {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => map
+                .maybe_get_l(*l)
+                .map(|lane| req.constraints.can_use(lane, map))
+                .unwrap_or(false),
+            PathStep::Turn(t) => map.maybe_get_t(*t).is_some(),
+        });
+        if usable {
+            self.kept += 1;
+            Some(captured.clone())
+        } else {
+            // Don't count the same route again if spawning is retried
+            self.routes.remove(req);
+            self.unusable += 1;
+            None
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    // (Routes re-used, routes that didn't work with the current map)
+    pub fn stats(&self) -> (usize, usize) {
+        (self.kept, self.unusable)
+    }
+}
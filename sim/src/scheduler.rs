@@ -24,6 +24,9 @@ pub enum Command {
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
     SeedBus(BusRouteID),
+    // Indexes into Incidents
+    StartIncident(usize),
+    EndIncident(usize),
 }
 
 impl Command {
@@ -48,6 +51,7 @@ impl Command {
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::FinishRemoteTrip(t) => CommandType::FinishRemoteTrip(*t),
             Command::SeedBus(r) => CommandType::SeedBus(*r),
+            Command::StartIncident(idx) | Command::EndIncident(idx) => CommandType::Incident(*idx),
         }
     }
 }
@@ -65,6 +69,7 @@ pub enum CommandType {
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
     SeedBus(BusRouteID),
+    Incident(usize),
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -0,0 +1,38 @@
+use geom::{Duration, Time};
+use map_model::{IntersectionID, LaneID};
+use serde::{Deserialize, Serialize};
+
+// Something besides the timing plan choosing traffic signal phases: a controller in another
+// process, hardware in the loop, a learned policy... Between steps, the caller hands it the
+// current detector readings with Sim::run_signal_controller.
+pub trait SignalController {
+    fn control(&mut self, now: Time, detectors: Vec<DetectorState>) -> Vec<PhaseCommand>;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DetectorState {
+    pub intersection: IntersectionID,
+    pub current_phase: usize,
+    // Until then, agents may start turns in the current phase
+    pub phase_ends_in: Duration,
+    // One per incoming lane
+    pub lanes: Vec<LaneDetector>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LaneDetector {
+    pub lane: LaneID,
+    // At the stop line, ready to start a turn
+    pub waiting: usize,
+    // Anywhere on the lane. Vehicles only; pedestrians are just counted while waiting.
+    pub vehicles: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PhaseCommand {
+    pub intersection: IntersectionID,
+    pub phase: usize,
+    // Agents won't start a turn they can't finish before this runs out. Send the same phase again
+    // to extend it.
+    pub duration: Duration,
+}
@@ -1,24 +1,25 @@
 use crate::analytics::Window;
 use crate::{
-    AgentID, AgentType, AlertLocation, Analytics, CarID, Command, CreateCar, DrawCarInput,
-    DrawPedCrowdInput, DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents,
-    IntersectionSimState, OrigPersonID, PandemicModel, ParkedCar, ParkingSimState, ParkingSpot,
-    PedestrianID, Person, PersonID, PersonState, Router, Scheduler, SidewalkPOI, SidewalkSpot,
-    TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, TripResult, TripSpawner,
-    UnzoomedAgent, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH,
-    LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
+    AgentID, AgentType, AlertLocation, Analytics, CapturedRoutes, CarID, Command, Corridor,
+    CreateCar, DetectorState, DrawAgentsOn, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput,
+    DrivingSimState, Event, GetDrawAgents, Gridlock, GridlockDetector, IncidentPlan, Incidents,
+    IntersectionSimState, LaneDetector, OrigPersonID, PandemicModel, ParkedCar, ParkingSimState,
+    ParkingSpot, PedestrianID, Person, PersonID, PersonState, Router, Scheduler, SidewalkPOI,
+    SidewalkSpot, SignalController, TollSchedule, Tolls, TransitSimState, TripID, TripInfo,
+    TripManager, TripPhaseType, TripResult, TripSpawner, TurnCounts, UnzoomedAgent, Vehicle,
+    VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
 };
 use abstutil::Timer;
 use derivative::Derivative;
-use geom::{Distance, Duration, PolyLine, Pt2D, Speed, Time};
+use geom::{Bounds, Distance, Duration, PolyLine, Pt2D, Speed, Time};
 use instant::Instant;
 use map_model::{
     BuildingID, BusRoute, BusRouteID, IntersectionID, LaneID, Map, ParkingLotID, Path,
-    PathConstraints, PathRequest, Position, RoadID, Traversable,
+    PathConstraints, PathRequest, PathStep, Position, RoadID, Traversable,
 };
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::panic;
 
 // TODO Do something else.
@@ -58,6 +59,36 @@ pub struct Sim {
     #[derivative(PartialEq = "ignore")]
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
+
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub(crate) check_invariants: bool,
+    // Once something's violated, the sim refuses to advance.
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    invariant_violations: Vec<String>,
+
+    // Record the route of every agent as it spawns
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    capturing_routes: Option<CapturedRoutes>,
+    // Use routes from a previous run instead of the ones just calculated
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    fixed_routes: Option<CapturedRoutes>,
+    // Savestates keep this, so the tolls and what's been paid so far survive loading one
+    #[derivative(PartialEq = "ignore")]
+    tolls: Option<Tolls>,
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    bus_lane_violators: f64,
+    // Savestates keep this along with the roads it's closed, so scheduled reopenings still happen
+    // after loading one
+    #[derivative(PartialEq = "ignore")]
+    incidents: Option<Incidents>,
+    // Savestates keep this, so detection stays on (or off) after loading one
+    #[derivative(PartialEq = "ignore")]
+    gridlock: Option<GridlockDetector>,
 }
 
 #[derive(Clone)]
@@ -70,9 +101,40 @@ pub struct SimOptions {
     pub enable_pandemic_model: Option<XorShiftRng>,
     pub alerts: AlertHandler,
     pub pathfinding_upfront: bool,
+    // After every step, make sure the simulation is in a consistent state. If not, save it and
+    // stop advancing. Slow; only for debugging.
+    pub check_invariants: bool,
+    // Re-use the routes captured in the prebaked baseline run, instead of pathfinding. Agents
+    // still reroute normally if their route doesn't work with the current edits.
+    pub fixed_routes: bool,
+    // The name of a TollSchedule for this map. Drivers pay, some route around the tolls, and some
+    // people switch to transit.
+    pub tolls: Option<String>,
+    // This fraction of drivers use bus lanes whenever that's faster, except past bus lane
+    // cameras.
+    pub bus_lane_violators: f64,
+    // The name of an IncidentPlan for this map. Incidents close roads, and message signs send
+    // some drivers around them.
+    pub incidents: Option<String>,
+    // Periodically look for vehicles waiting on each other in a cycle, or anybody stuck at an
+    // intersection for a long time. Building the graph of who's waiting on whom every minute
+    // slows down long runs, so headless runners leave this off unless asked.
+    pub detect_gridlock: bool,
 }
 
-#[derive(Clone)]
+// Everything that savestates skip, so checkpoints can include it
+#[derive(Serialize, Deserialize)]
+struct CheckpointExtras {
+    analytics: Analytics,
+    alerts: AlertHandler,
+    check_invariants: bool,
+    invariant_violations: Vec<String>,
+    capturing_routes: Option<CapturedRoutes>,
+    fixed_routes: Option<CapturedRoutes>,
+    bus_lane_violators: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AlertHandler {
     // Just print the alert to STDOUT
     Print,
@@ -99,6 +161,12 @@ impl SimOptions {
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             pathfinding_upfront: false,
+            check_invariants: false,
+            fixed_routes: false,
+            tolls: None,
+            bus_lane_violators: 0.0,
+            incidents: None,
+            detect_gridlock: false,
         }
     }
 }
@@ -107,6 +175,17 @@ impl SimOptions {
 impl Sim {
     pub fn new(map: &Map, opts: SimOptions, timer: &mut Timer) -> Sim {
         let mut scheduler = Scheduler::new();
+        let incidents =
+            opts.incidents
+                .map(|name| match IncidentPlan::load(map.get_name(), &name) {
+                    Ok(plan) => Incidents::new(plan, map, timer),
+                    Err(err) => panic!("Couldn't load incidents {}: {}", name, err),
+                });
+        if let Some(ref incidents) = incidents {
+            for (idx, (_, start, _)) in incidents.incidents.iter().enumerate() {
+                scheduler.push(*start, Command::StartIncident(idx));
+            }
+        }
         Sim {
             driving: DrivingSimState::new(map, opts.recalc_lanechanging),
             parking: ParkingSimState::new(map, timer),
@@ -134,6 +213,23 @@ impl Sim {
             run_name: opts.run_name,
             step_count: 0,
             alerts: opts.alerts,
+            check_invariants: opts.check_invariants,
+            invariant_violations: Vec::new(),
+            capturing_routes: None,
+            fixed_routes: None,
+            tolls: opts
+                .tolls
+                .map(|name| match TollSchedule::load(map.get_name(), &name) {
+                    Ok(schedule) => Tolls::new(schedule, map, timer),
+                    Err(err) => panic!("Couldn't load tolls {}: {}", name, err),
+                }),
+            bus_lane_violators: opts.bus_lane_violators,
+            incidents,
+            gridlock: if opts.detect_gridlock {
+                Some(GridlockDetector::new())
+            } else {
+                None
+            },
 
             analytics: Analytics::new(),
         }
@@ -323,6 +419,44 @@ impl GetDrawAgents for Sim {
         self.walking.get_draw_peds_on(self.time, on, map)
     }
 
+    fn get_draw_agents_in(
+        &self,
+        bounds: &Bounds,
+        map: &Map,
+    ) -> BTreeMap<Traversable, DrawAgentsOn> {
+        let mut on: BTreeSet<Traversable> = BTreeSet::new();
+        for r in map.roads_in_bounds(bounds) {
+            let r = map.get_r(r);
+            for l in r.all_lanes() {
+                on.insert(Traversable::Lane(l));
+            }
+            for i in vec![r.src_i, r.dst_i] {
+                for t in &map.get_i(i).turns {
+                    on.insert(Traversable::Turn(*t));
+                }
+            }
+        }
+        let on: Vec<Traversable> = on.into_iter().collect();
+
+        let mut results: BTreeMap<Traversable, DrawAgentsOn> = BTreeMap::new();
+        // Same order as get_draw_cars: parked cars first
+        for (on, cars) in self.parking.get_draw_cars_on_all(&on, map) {
+            results.entry(on).or_default().cars.extend(cars);
+        }
+        for (on, cars) in self
+            .driving
+            .get_draw_cars_on_all(self.time, &on, map, &self.transit)
+        {
+            results.entry(on).or_default().cars.extend(cars);
+        }
+        for (on, (peds, crowds)) in self.walking.get_draw_peds_on_all(self.time, &on, map) {
+            let agents = results.entry(on).or_default();
+            agents.peds = peds;
+            agents.crowds = crowds;
+        }
+        results
+    }
+
     fn get_all_draw_cars(&self, map: &Map) -> Vec<DrawCarInput> {
         let mut result = self
             .driving
@@ -353,6 +487,15 @@ impl Sim {
         maybe_cb: &mut Option<Box<dyn SimCallback>>,
     ) -> bool {
         self.step_count += 1;
+        if self.analytics.wants_metrics_sample(self.time) {
+            let active_agents = self.trips.num_agents(&self.transit).values().sum();
+            self.analytics.sample_metrics(self.time, active_agents);
+        }
+        if let Some(ref mut gridlock) = self.gridlock {
+            if gridlock.wants_check(self.time) {
+                gridlock.check(self.time, &self.intersections, &self.driving, map);
+            }
+        }
 
         let max_time = if let Some(t) = self.scheduler.peek_next_time() {
             if t > self.time + max_dt {
@@ -407,7 +550,33 @@ impl Sim {
                     map,
                 );
             }
-            Command::SpawnCar(create_car, retry_if_no_room) => {
+            Command::SpawnCar(mut create_car, retry_if_no_room) => {
+                // Trucks always follow the current restrictions, and don't pay tolls
+                let is_truck = create_car.vehicle.id.1 == VehicleType::Truck;
+                if let Some(routes) = self.fixed_routes.as_mut().filter(|_| !is_truck) {
+                    if let Some(path) =
+                        routes.lookup(&create_car.req, create_car.router.get_path(), map)
+                    {
+                        create_car.router.use_path(path);
+                    }
+                }
+                if let Some(tolls) = self.tolls.as_mut().filter(|_| !is_truck) {
+                    if let Some(path) = tolls.maybe_avoid(
+                        self.time,
+                        &create_car.req,
+                        create_car.router.get_path(),
+                        map,
+                    ) {
+                        create_car.router.use_path(path);
+                    }
+                }
+                if create_car.vehicle.id.1 == VehicleType::Car
+                    && is_bus_lane_violator(create_car.vehicle.id, self.bus_lane_violators)
+                {
+                    if let Some(path) = path_through_bus_lanes(&create_car.req, map) {
+                        create_car.router.ignore_bus_lanes(path);
+                    }
+                }
                 if self.driving.start_car_on_lane(
                     self.time,
                     create_car.clone(),
@@ -423,10 +592,10 @@ impl Sim {
                             trip,
                             person,
                             Some(create_car.req.clone()),
-                            if create_car.vehicle.id.1 == VehicleType::Car {
-                                TripPhaseType::Driving
-                            } else {
+                            if create_car.vehicle.id.1 == VehicleType::Bike {
                                 TripPhaseType::Biking
+                            } else {
+                                TripPhaseType::Driving
                             },
                         ));
                     }
@@ -445,6 +614,9 @@ impl Sim {
                     }
                     self.analytics
                         .record_demand(create_car.router.get_path(), map);
+                    if let Some(ref mut routes) = self.capturing_routes {
+                        routes.record(&create_car.req, create_car.router.get_path());
+                    }
                 } else if retry_if_no_room {
                     // TODO Record this in the trip log
                     self.scheduler.push(
@@ -468,7 +640,15 @@ impl Sim {
                     );
                 }
             }
-            Command::SpawnPed(create_ped) => {
+            Command::SpawnPed(mut create_ped) => {
+                if let Some(ref mut routes) = self.fixed_routes {
+                    if let Some(path) = routes.lookup(&create_ped.req, &create_ped.path, map) {
+                        create_ped.path = path;
+                    }
+                }
+                if let Some(ref mut routes) = self.capturing_routes {
+                    routes.record(&create_ped.req, &create_ped.path);
+                }
                 // Do the order a bit backwards so we don't have to clone the
                 // CreatePedestrian. spawn_ped can't fail.
                 self.trips
@@ -570,6 +750,26 @@ impl Sim {
             Command::SeedBus(r) => {
                 self.seed_bus_route(map.get_br(r), map, &mut Timer::throwaway());
             }
+            Command::StartIncident(idx) => {
+                if let Some(ref incidents) = self.incidents {
+                    let (r, _, end) = incidents.incidents[idx];
+                    self.intersections.close_road(r);
+                    self.scheduler
+                        .push(end.max(self.time), Command::EndIncident(idx));
+                }
+            }
+            Command::EndIncident(idx) => {
+                if let Some(ref incidents) = self.incidents {
+                    if !incidents.still_closed(idx, self.time) {
+                        self.intersections.reopen_road(
+                            self.time,
+                            incidents.incidents[idx].0,
+                            &mut self.scheduler,
+                            map,
+                        );
+                    }
+                }
+            }
         }
 
         // Record events at precisely the time they occur.
@@ -589,6 +789,19 @@ impl Sim {
             if let Some(ref mut m) = self.pandemic {
                 m.handle_event(self.time, &ev, &mut self.scheduler);
             }
+            if let Some(ref mut tolls) = self.tolls {
+                tolls.event(&ev, self.time, map);
+            }
+            if let Some(ref mut incidents) = self.incidents {
+                if let Some((car, sign)) = incidents.event(&ev, self.time, map) {
+                    if self
+                        .driving
+                        .detour_car(car, &incidents.closed_roads(self.time), map)
+                    {
+                        incidents.detours.push((self.time, car, sign));
+                    }
+                }
+            }
 
             self.analytics.event(ev, self.time, map);
         }
@@ -606,10 +819,13 @@ impl Sim {
         let mut last_update = Instant::now();
 
         timer.start(format!("Advance sim to {}", end_time));
-        while self.time < end_time {
+        while self.time < end_time && self.invariant_violations.is_empty() {
             if self.minimal_step(map, end_time - self.time, maybe_cb) {
                 break;
             }
+            if self.check_invariants && self.invariants_violated(map) {
+                break;
+            }
             if !self.analytics.alerts.is_empty() {
                 match self.alerts {
                     AlertHandler::Print => {
@@ -659,10 +875,16 @@ impl Sim {
         let started_at = Instant::now();
         let end_time = self.time + dt;
 
-        while self.time < end_time && Duration::realtime_elapsed(started_at) < real_time_limit {
+        while self.time < end_time
+            && Duration::realtime_elapsed(started_at) < real_time_limit
+            && self.invariant_violations.is_empty()
+        {
             if self.minimal_step(map, end_time - self.time, maybe_cb) {
                 break;
             }
+            if self.check_invariants && self.invariants_violated(map) {
+                break;
+            }
             if !self.analytics.alerts.is_empty() {
                 match self.alerts {
                     AlertHandler::Print => {
@@ -692,6 +914,65 @@ impl Sim {
     }
 }
 
+// Invariants
+impl Sim {
+    // Everything that's inconsistent about the current state. Empty means everything's fine.
+    pub fn find_invariant_violations(&self, map: &Map) -> Vec<String> {
+        let mut problems = self
+            .driving
+            .find_invariant_violations(self.time, map, &self.trips);
+        problems.extend(self.walking.find_invariant_violations(map, &self.trips));
+        // Conservation of agents: everybody with an ongoing trip leg is somewhere.
+        for a in self.trips.active_agents() {
+            let exists = match a {
+                AgentID::Car(c) => self.driving.does_car_exist(c),
+                AgentID::Pedestrian(p) => self.walking.does_ped_exist(p),
+                AgentID::BusPassenger(_, bus) => self.driving.does_car_exist(bus),
+            };
+            if !exists {
+                problems.push(format!("{} has an active trip, but doesn't exist", a));
+            }
+        }
+        problems
+    }
+
+    // Once this is non-empty, the sim won't advance.
+    pub fn invariant_violations(&self) -> &Vec<String> {
+        &self.invariant_violations
+    }
+
+    // If anything's wrong, save the state for debugging, report it, and stop.
+    fn invariants_violated(&mut self, map: &Map) -> bool {
+        let problems = self.find_invariant_violations(map);
+        if problems.is_empty() {
+            return false;
+        }
+        let path = self.save();
+        println!(
+            "{} sim invariants violated at {} (step {}). Saved to {}",
+            problems.len(),
+            self.time,
+            self.step_count,
+            path
+        );
+        for p in &problems {
+            println!("- {}", p);
+        }
+        self.analytics.alerts.push((
+            self.time,
+            AlertLocation::Nil,
+            format!(
+                "{} sim invariants violated, starting with: {}. Saved to {}",
+                problems.len(),
+                problems[0],
+                path
+            ),
+        ));
+        self.invariant_violations = problems;
+        true
+    }
+}
+
 // Helpers to run the sim
 // TODO Old and gunky
 impl Sim {
@@ -711,6 +992,9 @@ impl Sim {
 
             match panic::catch_unwind(panic::AssertUnwindSafe(|| {
                 self.timed_step(map, dt, &mut None, &mut Timer::throwaway());
+                if !self.invariant_violations.is_empty() {
+                    panic!("Sim invariants violated at {}", self.time);
+                }
             })) {
                 Ok(()) => {}
                 Err(err) => {
@@ -834,6 +1118,49 @@ impl Sim {
         Ok(sim)
     }
 
+    // Unlike save(), this keeps everything that affects what happens next, including analytics
+    // and the paths of agents not spawned yet, so resuming from the checkpoint runs exactly like
+    // never having stopped. The pandemic model isn't supported yet.
+    pub fn save_checkpoint(&self, path: String) -> Result<(), String> {
+        if self.pandemic.is_some() {
+            return Err("Can't checkpoint the pandemic model yet".to_string());
+        }
+        let extras = CheckpointExtras {
+            analytics: self.analytics.clone(),
+            alerts: self.alerts.clone(),
+            check_invariants: self.check_invariants,
+            invariant_violations: self.invariant_violations.clone(),
+            capturing_routes: self.capturing_routes.clone(),
+            fixed_routes: self.fixed_routes.clone(),
+            bus_lane_violators: self.bus_lane_violators,
+        };
+        abstutil::maybe_write_binary(&path, &(self, extras))
+            .map_err(|err| format!("Couldn't save {}: {}", path, err))
+    }
+
+    // The map must have the same edits as when the checkpoint was saved.
+    pub fn load_checkpoint(path: String, map: &Map, timer: &mut Timer) -> Result<Sim, String> {
+        if !path.ends_with(".bin") {
+            return Err(format!("{} isn't a checkpoint", path));
+        }
+        let (mut sim, extras): (Sim, CheckpointExtras) =
+            abstutil::maybe_read_binary(path.clone(), timer).map_err(|err| err.to_string())?;
+        if &sim.map_name != map.get_name() || sim.edits_name != map.get_edits().edits_name {
+            return Err(format!(
+                "{} is for {} with edits \"{}\"",
+                path, sim.map_name, sim.edits_name
+            ));
+        }
+        sim.analytics = extras.analytics;
+        sim.alerts = extras.alerts;
+        sim.check_invariants = extras.check_invariants;
+        sim.invariant_violations = extras.invariant_violations;
+        sim.capturing_routes = extras.capturing_routes;
+        sim.fixed_routes = extras.fixed_routes;
+        sim.bus_lane_violators = extras.bus_lane_violators;
+        Ok(sim)
+    }
+
     pub fn restore_paths(&mut self, map: &Map, timer: &mut Timer) {
         let paths = timer.parallelize(
             "calculate paths",
@@ -846,6 +1173,81 @@ impl Sim {
     pub fn handle_live_edited_traffic_signals(&mut self, map: &Map) {
         self.intersections.handle_live_edited_traffic_signals(map)
     }
+
+    // From now on, these traffic signals ignore their timing plan. They stay in their current
+    // phase until a SignalController says otherwise.
+    pub fn control_signals_externally(&mut self, signals: Vec<IntersectionID>, map: &Map) {
+        for i in signals {
+            assert!(map.get_i(i).is_traffic_signal());
+            self.intersections
+                .control_signal_externally(i, &mut self.scheduler);
+        }
+    }
+
+    // Send detector readings for all externally controlled signals, then carry out the commands
+    // that come back. Invalid commands are skipped, and described in the error.
+    pub fn run_signal_controller(
+        &mut self,
+        map: &Map,
+        controller: &mut dyn SignalController,
+    ) -> Result<(), String> {
+        let mut detectors = Vec::new();
+        for i in map.all_intersections() {
+            if !self.intersections.is_controlled_externally(i.id) {
+                continue;
+            }
+            let (current_phase, phase_ends_in) = self.current_phase_and_remaining_time(i.id);
+            let waiting = self.intersections.waiting_per_lane(i.id);
+            detectors.push(DetectorState {
+                intersection: i.id,
+                current_phase,
+                phase_ends_in,
+                lanes: i
+                    .incoming_lanes
+                    .iter()
+                    .map(|l| LaneDetector {
+                        lane: *l,
+                        waiting: waiting.get(l).cloned().unwrap_or(0),
+                        vehicles: self.driving.num_cars_on(Traversable::Lane(*l)),
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut problems = Vec::new();
+        for cmd in controller.control(self.time, detectors) {
+            if !self
+                .intersections
+                .is_controlled_externally(cmd.intersection)
+            {
+                problems.push(format!("{} isn't controlled externally", cmd.intersection));
+            } else if cmd.phase >= map.get_traffic_signal(cmd.intersection).phases.len() {
+                problems.push(format!(
+                    "{} doesn't have phase {}",
+                    cmd.intersection, cmd.phase
+                ));
+            } else if cmd.duration <= Duration::ZERO {
+                problems.push(format!(
+                    "Phase {} at {} needs a positive duration",
+                    cmd.phase, cmd.intersection
+                ));
+            } else {
+                self.intersections.set_signal_phase(
+                    self.time,
+                    cmd.intersection,
+                    cmd.phase,
+                    cmd.duration,
+                    map,
+                    &mut self.scheduler,
+                );
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("; "))
+        }
+    }
 }
 
 // Queries of all sorts
@@ -906,6 +1308,9 @@ impl Sim {
                 total_dist: Distance::meters(0.1),
                 lanes_crossed: 0,
                 total_lanes: 0,
+                speed: Speed::ZERO,
+                on: None,
+                queue_position: None,
             },
         }
     }
@@ -994,6 +1399,7 @@ impl Sim {
             VehicleType::Bike,
             VehicleType::Bus,
             VehicleType::Train,
+            VehicleType::Truck,
         ] {
             let id = CarID(idx, *vt);
             if self.driving.does_car_exist(id) {
@@ -1097,6 +1503,48 @@ impl Sim {
         &self.analytics
     }
 
+    // Start measuring travel times along some roads from now on. Returns an index into
+    // get_analytics().corridors.
+    pub fn track_corridor(&mut self, roads: Vec<RoadID>) -> usize {
+        self.analytics.corridors.push(Corridor::new(roads));
+        self.analytics.corridors.len() - 1
+    }
+
+    // Start recording the route of every agent that spawns from now on.
+    pub fn capture_routes(&mut self) {
+        self.capturing_routes = Some(CapturedRoutes::new());
+    }
+
+    pub fn get_captured_routes(&self) -> Option<&CapturedRoutes> {
+        self.capturing_routes.as_ref()
+    }
+
+    // Agents spawning from now on will use these routes, when they still work.
+    pub fn use_fixed_routes(&mut self, routes: CapturedRoutes) {
+        self.fixed_routes = Some(routes);
+    }
+
+    pub fn get_fixed_routes(&self) -> Option<&CapturedRoutes> {
+        self.fixed_routes.as_ref()
+    }
+
+    pub fn get_tolls(&self) -> Option<&Tolls> {
+        self.tolls.as_ref()
+    }
+
+    pub fn get_incidents(&self) -> Option<&Incidents> {
+        self.incidents.as_ref()
+    }
+
+    // Start counting turning movements at an intersection from now on.
+    pub fn count_turning_movements(&mut self, i: IntersectionID) {
+        let now = self.time;
+        self.analytics
+            .turn_counts
+            .entry(i)
+            .or_insert_with(|| TurnCounts::new(now));
+    }
+
     pub fn find_blockage_front(&self, car: CarID, map: &Map) -> String {
         self.driving
             .find_blockage_front(car, map, &self.intersections)
@@ -1109,6 +1557,34 @@ impl Sim {
             .delayed_intersections(self.time, threshold)
     }
 
+    // Where traffic is locked up, as of the last check. Empty if gridlock detection is off.
+    pub fn current_gridlock(&self) -> Vec<Gridlock> {
+        self.gridlock
+            .as_ref()
+            .map(|g| g.current.clone())
+            .unwrap_or_else(Vec::new)
+    }
+
+    // Gridlock found since the last call and still there, so the UI can mention each one once
+    pub fn take_new_gridlock(&mut self) -> Vec<Gridlock> {
+        match self.gridlock {
+            Some(ref mut g) => std::mem::replace(&mut g.unannounced, Vec::new()),
+            None => Vec::new(),
+        }
+    }
+
+    // (lane, percent of its length filled by cars, average speed of those cars). Only lanes with
+    // cars on them right now are included.
+    pub fn lane_congestion(&self) -> Vec<(LaneID, f64, Speed)> {
+        self.driving.lane_congestion()
+    }
+
+    // The most vehicles ever stopped on each lane at once. Lanes where nobody's stopped are
+    // skipped.
+    pub fn max_queued_per_lane(&self) -> &BTreeMap<LaneID, usize> {
+        self.driving.max_queued()
+    }
+
     pub fn bldg_to_people(&self, b: BuildingID) -> Vec<PersonID> {
         self.trips.bldg_to_people(b)
     }
@@ -1240,4 +1716,95 @@ pub struct AgentProperties {
 
     pub lanes_crossed: usize,
     pub total_lanes: usize,
+
+    // Zero while waiting
+    pub speed: Speed,
+    // None while riding a bus
+    pub on: Option<Traversable>,
+    // For vehicles, (how many are ahead, how many total) in the queue for `on`
+    pub queue_position: Option<(usize, usize)>,
+}
+
+// Deterministically picks some fraction of cars, so the same drivers misbehave in every run.
+fn is_bus_lane_violator(car: CarID, fraction: f64) -> bool {
+    fraction > 0.0 && (car.0 * 7919) % 1000 < (fraction * 1000.0) as usize
+}
+
+// None if the fastest route with bus lanes doesn't use any, or would pass a bus lane camera.
+fn path_through_bus_lanes(req: &PathRequest, map: &Map) -> Option<Path> {
+    let path = map.pathfind(PathRequest {
+        start: req.start,
+        end: req.end,
+        constraints: PathConstraints::Bus,
+    })?;
+    let mut uses_bus_lane = false;
+    for step in path.get_steps() {
+        if let PathStep::Lane(l) = step {
+            let lane = map.get_l(*l);
+            if lane.is_bus() {
+                if map.get_r(lane.parent).bus_lane_camera {
+                    return None;
+                }
+                uses_bus_lane = true;
+            }
+        }
+    }
+    if uses_bus_lane {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ScenarioGenerator, SimFlags};
+
+    // Resuming from a checkpoint has to play out exactly like never having stopped. This needs a
+    // real map, so it's skipped by default. After downloading the data, run it with
+    // `cargo test -p sim -- --ignored checkpoint_resumes_exactly`.
+    #[test]
+    #[ignore]
+    fn checkpoint_resumes_exactly() {
+        let map_path = abstutil::path_map("montlake");
+        assert!(
+            abstutil::file_exists(map_path.clone()),
+            "{} is missing; download the data first",
+            map_path
+        );
+        let mut timer = Timer::throwaway();
+        let map = Map::new(map_path, &mut timer);
+        let mut rng = SimFlags::for_test("checkpoint_resumes_exactly").make_rng();
+        let scenario = ScenarioGenerator::small_run(&map).generate(&map, &mut rng, &mut timer);
+        let mut sim = Sim::new(
+            &map,
+            SimOptions::new("checkpoint_resumes_exactly"),
+            &mut timer,
+        );
+        scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
+        sim.timed_step(&map, Duration::minutes(10), &mut None, &mut timer);
+
+        let path = std::env::temp_dir()
+            .join("checkpoint_resumes_exactly.bin")
+            .to_string_lossy()
+            .to_string();
+        sim.save_checkpoint(path.clone()).unwrap();
+        let mut restored = Sim::load_checkpoint(path.clone(), &map, &mut timer).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(
+            sim == restored,
+            "Restoring the checkpoint changed something"
+        );
+
+        for _ in 0..30 {
+            sim.timed_step(&map, Duration::seconds(10.0), &mut None, &mut timer);
+            restored.timed_step(&map, Duration::seconds(10.0), &mut None, &mut timer);
+            assert!(sim == restored, "The sims diverged at {}", sim.time());
+            assert_eq!(
+                sim.analytics.finished_trips,
+                restored.analytics.finished_trips
+            );
+        }
+    }
 }
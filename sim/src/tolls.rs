@@ -0,0 +1,335 @@
+use crate::{AgentID, CarID, Event, PersonSpec, SpawnTrip, TripMode, VehicleType};
+use abstutil::Timer;
+use geom::{Duration, Polygon, Time};
+use map_model::raw::OriginalRoad;
+use map_model::{LaneID, Map, Neighborhood, Path, PathRequest, PathStep, RoadID, Traversable};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// What it's worth to drivers to avoid a toll, in cents per hour spent detouring
+const VALUE_OF_TIME: f64 = 1500.0;
+// A cordon toll this expensive makes everybody driving into the cordon take transit instead. Below
+// this, the chance of switching is proportional.
+const TOLL_FOR_EVERYONE_TO_SWITCH: usize = 2000;
+// Even within one toll period, don't remember more detours than this
+const MAX_CACHED_DETOURS: usize = 10_000;
+
+// Congestion pricing. Stored by name alongside the map, like neighborhoods, since the cordons are
+// defined by them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TollSchedule {
+    pub map_name: String,
+    pub name: String,
+    pub cordons: Vec<Cordon>,
+    pub roads: Vec<RoadToll>,
+}
+
+// Cars are charged once every time they enter the neighborhood from outside. Trips starting inside
+// don't pay until they leave and come back.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cordon {
+    pub neighborhood: String,
+    pub prices: Vec<TollPeriod>,
+}
+
+// Cars are charged every time they start driving along the road.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RoadToll {
+    pub road: OriginalRoad,
+    pub prices: Vec<TollPeriod>,
+}
+
+// Outside of all periods, there's no toll.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TollPeriod {
+    pub start: Time,
+    pub end: Time,
+    pub cents: usize,
+}
+
+impl TollSchedule {
+    pub fn load(map_name: &str, name: &str) -> Result<TollSchedule, std::io::Error> {
+        abstutil::maybe_read_json(
+            abstutil::path_tolls(map_name, name),
+            &mut Timer::throwaway(),
+        )
+    }
+
+    pub fn save(&self) {
+        abstutil::write_json(abstutil::path_tolls(&self.map_name, &self.name), self);
+    }
+}
+
+fn price_at(prices: &Vec<TollPeriod>, time: Time) -> usize {
+    prices
+        .iter()
+        .find(|p| time >= p.start && time < p.end)
+        .map(|p| p.cents)
+        .unwrap_or(0)
+}
+
+// A TollSchedule matched up against the current map, plus everything charged so far.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tolls {
+    pub name: String,
+    pub cordons: Vec<CordonArea>,
+    pub roads: BTreeMap<RoadID, Vec<TollPeriod>>,
+
+    // (when, who, cents)
+    pub payments: Vec<(Time, CarID, usize)>,
+    last_road: BTreeMap<CarID, RoadID>,
+
+    // Finding a detour is slow, but lots of trips start and end on the same lanes. Keyed by the
+    // start lane, end lane, and roads avoided. Only valid while the current prices are, so it's
+    // emptied whenever any toll period starts or ends.
+    #[serde(skip_serializing, skip_deserializing)]
+    detours: BTreeMap<(LaneID, LaneID, BTreeSet<RoadID>), Option<Path>>,
+    #[serde(skip_serializing, skip_deserializing)]
+    detour_prices: Vec<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CordonArea {
+    pub name: String,
+    pub polygon: Polygon,
+    // Every road with its middle inside the polygon
+    pub roads: BTreeSet<RoadID>,
+    pub prices: Vec<TollPeriod>,
+}
+
+impl Tolls {
+    pub(crate) fn new(schedule: TollSchedule, map: &Map, timer: &mut Timer) -> Tolls {
+        let neighborhoods = Neighborhood::load_all(map.get_name());
+        let mut cordons = Vec::new();
+        for c in schedule.cordons {
+            let polygon = match neighborhoods
+                .iter()
+                .find(|n| n.name == c.neighborhood)
+                .and_then(|n| n.polygon(map))
+            {
+                Some(p) => p,
+                None => {
+                    timer.warn(format!(
+                        "Skipping toll cordon {}; the neighborhood isn't on this map",
+                        c.neighborhood
+                    ));
+                    continue;
+                }
+            };
+            let roads = map
+                .all_roads()
+                .iter()
+                .filter(|r| polygon.contains_pt(r.center_pts.middle()))
+                .map(|r| r.id)
+                .collect();
+            cordons.push(CordonArea {
+                name: c.neighborhood,
+                polygon,
+                roads,
+                prices: c.prices,
+            });
+        }
+
+        let mut roads = BTreeMap::new();
+        for t in schedule.roads {
+            match map.find_r_by_osm_id(
+                t.road.osm_way_id,
+                (t.road.i1.osm_node_id, t.road.i2.osm_node_id),
+            ) {
+                Ok(r) => {
+                    roads.insert(r, t.prices);
+                }
+                Err(err) => {
+                    timer.warn(format!("Skipping road toll: {}", err));
+                }
+            }
+        }
+
+        Tolls {
+            name: schedule.name,
+            cordons,
+            roads,
+            payments: Vec::new(),
+            last_road: BTreeMap::new(),
+            detours: BTreeMap::new(),
+            detour_prices: Vec::new(),
+        }
+    }
+
+    pub(crate) fn event(&mut self, ev: &Event, time: Time, map: &Map) {
+        if let Event::AgentEntersTraversable(AgentID::Car(car), Traversable::Lane(l), _) = ev {
+            if car.1 != VehicleType::Car {
+                return;
+            }
+            let r = map.get_l(*l).parent;
+            let prev = self.last_road.insert(*car, r);
+            if prev == Some(r) {
+                return;
+            }
+            let cents = self.toll_for(prev, r, time);
+            if cents > 0 {
+                self.payments.push((time, *car, cents));
+            }
+        }
+    }
+
+    // What it costs to go from one road to the next at some time
+    fn toll_for(&self, prev: Option<RoadID>, r: RoadID, time: Time) -> usize {
+        let mut cents = self
+            .roads
+            .get(&r)
+            .map(|prices| price_at(prices, time))
+            .unwrap_or(0);
+        for c in &self.cordons {
+            if c.roads.contains(&r) && prev.map(|p| !c.roads.contains(&p)).unwrap_or(false) {
+                cents += price_at(&c.prices, time);
+            }
+        }
+        cents
+    }
+
+    // Pretends the whole path is driven at the given time. Also returns the roads charged for,
+    // counting every road in a cordon.
+    fn toll_for_path(&self, path: &Path, time: Time, map: &Map) -> (usize, BTreeSet<RoadID>) {
+        let mut total = 0;
+        let mut charged = BTreeSet::new();
+        let mut prev = None;
+        for step in path.get_steps() {
+            if let PathStep::Lane(l) | PathStep::ContraflowLane(l) = step {
+                let r = map.get_l(*l).parent;
+                if prev == Some(r) {
+                    continue;
+                }
+                let cents = self.toll_for(prev, r, time);
+                if cents > 0 {
+                    total += cents;
+                    charged.insert(r);
+                    for c in &self.cordons {
+                        if c.roads.contains(&r) {
+                            charged.extend(c.roads.iter().cloned());
+                        }
+                    }
+                }
+                prev = Some(r);
+            }
+        }
+        (total, charged)
+    }
+
+    // If the path pays tolls and a detour around them is worth the time, returns the detour.
+    pub(crate) fn maybe_avoid(
+        &mut self,
+        now: Time,
+        req: &PathRequest,
+        path: &Path,
+        map: &Map,
+    ) -> Option<Path> {
+        let (toll, avoid) = self.toll_for_path(path, now, map);
+        if toll == 0 {
+            return None;
+        }
+        let prices = self.all_prices(now);
+        if prices != self.detour_prices || self.detours.len() >= MAX_CACHED_DETOURS {
+            self.detours.clear();
+            self.detour_prices = prices;
+        }
+        let key = (req.start.lane(), req.end.lane(), avoid);
+        if !self.detours.contains_key(&key) {
+            let detour = map.pathfind_avoiding(req.clone(), &key.2);
+            self.detours.insert(key.clone(), detour);
+        }
+        let detour = self.detours[&key]
+            .as_ref()?
+            .with_end_dist(req.end.dist_along());
+        let extra_time = free_flow_time(&detour, map) - free_flow_time(path, map);
+        let detour_cost = self.toll_for_path(&detour, now, map).0
+            + (extra_time.inner_seconds().max(0.0) / 3600.0 * VALUE_OF_TIME) as usize;
+        if detour_cost < toll {
+            Some(detour)
+        } else {
+            None
+        }
+    }
+
+    // The price of every cordon and road at some time
+    fn all_prices(&self, time: Time) -> Vec<usize> {
+        self.cordons
+            .iter()
+            .map(|c| price_at(&c.prices, time))
+            .chain(self.roads.values().map(|prices| price_at(prices, time)))
+            .collect()
+    }
+
+    // Some people driving into a tolled cordon take transit instead. Only the price when each trip
+    // departs matters, and switching is all or nothing for a person, so one car isn't left
+    // stranded somewhere.
+    pub(crate) fn maybe_switch_modes(
+        &self,
+        person: &PersonSpec,
+        map: &Map,
+        rng: &mut XorShiftRng,
+    ) -> Option<PersonSpec> {
+        let mut max_toll = 0;
+        for t in &person.trips {
+            if t.trip.mode() != TripMode::Drive || t.trip.is_freight() {
+                continue;
+            }
+            let from = t.trip.start(map).pt(map);
+            let to = t.trip.end(map).pt(map);
+            for c in &self.cordons {
+                if c.polygon.contains_pt(to) && !c.polygon.contains_pt(from) {
+                    max_toll = max_toll.max(price_at(&c.prices, t.depart));
+                }
+            }
+        }
+        if max_toll == 0 {
+            return None;
+        }
+        let pct = (max_toll as f64 / TOLL_FOR_EVERYONE_TO_SWITCH as f64).min(1.0);
+        if !rng.gen_bool(pct) {
+            return None;
+        }
+
+        let mut switched = person.clone();
+        for t in &mut switched.trips {
+            if t.trip.mode() != TripMode::Drive || t.trip.is_freight() {
+                continue;
+            }
+            t.trip = SpawnTrip::new(t.trip.start(map), t.trip.end(map), TripMode::Transit, map)?;
+            t.modified = true;
+        }
+        Some(switched)
+    }
+
+    pub fn total_revenue(&self) -> usize {
+        self.payments.iter().map(|(_, _, cents)| *cents).sum()
+    }
+
+    // The roads and cordons charging something right now
+    pub fn is_tolled(&self, r: RoadID, now: Time) -> bool {
+        self.roads
+            .get(&r)
+            .map(|prices| price_at(prices, now) > 0)
+            .unwrap_or(false)
+            || self
+                .cordons
+                .iter()
+                .any(|c| c.roads.contains(&r) && price_at(&c.prices, now) > 0)
+    }
+}
+
+// Ignoring traffic
+fn free_flow_time(path: &Path, map: &Map) -> Duration {
+    let mut total = Duration::ZERO;
+    for step in path.get_steps() {
+        total += match step {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                map.get_l(*l).length() / map.get_parent(*l).speed_limit
+            }
+            PathStep::Turn(t) => map.get_t(*t).geom.length() / map.get_parent(t.dst).speed_limit,
+        };
+    }
+    total
+}
@@ -5,7 +5,7 @@ use crate::{
     TripSpec, Vehicle, VehicleSpec, VehicleType, WalkingSimState,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
-use geom::{Duration, Speed, Time};
+use geom::{Duration, Pt2D, Speed, Time};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathConstraints, PathRequest,
     Position,
@@ -938,7 +938,7 @@ impl TripManager {
         assert!(!self.trips[trip.0].cancelled);
         assert!(!self.trips[trip.0].aborted);
         if !self.pathfinding_upfront && maybe_path.is_none() && maybe_req.is_some() {
-            maybe_path = map.pathfind(maybe_req.clone().unwrap());
+            maybe_path = spec.pathfind(maybe_req.clone().unwrap(), map);
         }
 
         let person = &mut self.people[self.trips[trip.0].person.0];
@@ -1494,6 +1494,13 @@ pub enum PersonState {
 }
 
 impl TripEndpoint {
+    pub fn pt(&self, map: &Map) -> Pt2D {
+        match self {
+            TripEndpoint::Bldg(b) => map.get_b(*b).label_center,
+            TripEndpoint::Border(i, _) => map.get_i(*i).polygon.center(),
+        }
+    }
+
     pub(crate) fn start_sidewalk_spot(&self, map: &Map) -> Option<SidewalkSpot> {
         match self {
             TripEndpoint::Bldg(b) => Some(SidewalkSpot::building(*b, map)),
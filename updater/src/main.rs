@@ -38,10 +38,11 @@ async fn download() {
     let truth = Manifest::load("data/MANIFEST.txt".to_string())
         .unwrap()
         .filter(cities);
+    let local_builds = local_builds();
 
     // Anything local need deleting?
     for path in local.0.keys() {
-        if !truth.0.contains_key(path) {
+        if !truth.0.contains_key(path) && !local.built_locally(&local_builds, path) {
             rm(&path);
         }
     }
@@ -50,6 +51,10 @@ async fn download() {
     let mut failed = Vec::new();
     for (path, entry) in truth.0 {
         if local.0.get(&path).map(|x| &x.checksum) != Some(&entry.checksum) {
+            if local.built_locally(&local_builds, &path) {
+                println!("{} was built locally, not replacing it", path);
+                continue;
+            }
             std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap()).unwrap();
             match curl(entry).await {
                 Ok(()) => {
@@ -165,6 +170,20 @@ async fn check_links() {
     }
 }
 
+// Path to checksum, for files the importer built on this machine. See importer/src/pipeline.rs.
+fn local_builds() -> BTreeMap<String, String> {
+    let mut kv = BTreeMap::new();
+    if let Ok(f) = File::open("data/player/local_builds.txt") {
+        for line in BufReader::new(f).lines() {
+            let line = line.unwrap();
+            let parts = line.split(",").collect::<Vec<_>>();
+            assert_eq!(parts.len(), 2);
+            kv.insert(parts[0].to_string(), parts[1].to_string());
+        }
+    }
+    kv
+}
+
 // keyed by path
 struct Manifest(BTreeMap<String, Entry>);
 struct Entry {
@@ -250,6 +269,14 @@ impl Manifest {
         Ok(Manifest(kv))
     }
 
+    // True if the importer built this file and it hasn't changed since
+    fn built_locally(&self, local_builds: &BTreeMap<String, String>, path: &str) -> bool {
+        match (self.0.get(path), local_builds.get(path)) {
+            (Some(entry), Some(checksum)) => &entry.checksum == checksum,
+            _ => false,
+        }
+    }
+
     fn filter(mut self, cities: Cities) -> Manifest {
         // TODO Temporary hack until directories are organized better
         fn map_belongs_to_city(map: &str, city: &str) -> bool {
@@ -306,8 +333,9 @@ impl Manifest {
                     continue;
                 }
             } else if parts[1] == "system" {
-                if parts[2] == "maps" {
-                    let map = parts[3].trim_end_matches(".bin");
+                if parts[2] == "maps" || parts[2] == "map_summaries" || parts[2] == "map_provenance"
+                {
+                    let map = parts[3].trim_end_matches(".bin").trim_end_matches(".json");
                     if cities
                         .runtime
                         .iter()